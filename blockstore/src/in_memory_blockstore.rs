@@ -33,6 +33,10 @@ impl<const MAX_MULTIHASH_SIZE: usize> InMemoryBlockstore<MAX_MULTIHASH_SIZE> {
         Ok(())
     }
 
+    fn remove_cid(&self, cid: &CidGeneric<MAX_MULTIHASH_SIZE>) {
+        self.map.remove(cid);
+    }
+
     fn contains_cid(&self, cid: &CidGeneric<MAX_MULTIHASH_SIZE>) -> bool {
         self.map.contains_key(cid)
     }
@@ -58,6 +62,12 @@ impl<const MAX_MULTIHASH_SIZE: usize> Blockstore for InMemoryBlockstore<MAX_MULT
         self.insert_cid(cid, data)
     }
 
+    async fn remove<const SS: usize>(&self, cid: &CidGeneric<SS>) -> Result<()> {
+        let cid = get_internal_cid(cid)?;
+        self.remove_cid(&cid);
+        Ok(())
+    }
+
     async fn has<const SS: usize>(&self, cid: &CidGeneric<SS>) -> Result<bool> {
         let cid = get_internal_cid(cid)?;
         Ok(self.contains_cid(&cid))
@@ -146,6 +156,31 @@ mod tests {
         assert_eq!(insert_err, BlockstoreError::CidExists);
     }
 
+    #[tokio::test]
+    async fn test_remove() {
+        let store = InMemoryBlockstore::<128>::new();
+        let cid = CidGeneric::<128>::read_bytes(
+            [
+                0x01, // CIDv1
+                0x01, // CID codec = 1
+                0x02, // code = 2
+                0x03, // len = 3
+                1, 2, 3, // hash
+            ]
+            .as_ref(),
+        )
+        .unwrap();
+
+        store.put_keyed(&cid, &[0xCD; 8]).await.unwrap();
+        store.remove(&cid).await.unwrap();
+        assert!(!store.has(&cid).await.unwrap());
+
+        // removing a missing block succeeds
+        store.remove(&cid).await.unwrap();
+        // and the block can be put again
+        store.put_keyed(&cid, &[0xCD; 8]).await.unwrap();
+    }
+
     #[tokio::test]
     async fn different_cid_size() {
         let cid_bytes = [
@@ -25,6 +25,14 @@ pub enum BlockstoreError {
     /// Error occured when trying to compute CID.
     #[error("Error generating CID: {0}")]
     CidError(#[from] CidError),
+
+    /// An error reported by the storage backing the blockstore.
+    #[error("Storage error: {0}")]
+    StorageError(String),
+
+    /// The operation is not supported by the blockstore.
+    #[error("Operation not supported by the store")]
+    UnsupportedOperation,
 }
 
 type Result<T> = std::result::Result<T, BlockstoreError>;
@@ -47,6 +55,15 @@ pub trait Blockstore {
     /// [`put`]: Blockstore::put
     async fn put_keyed<const S: usize>(&self, cid: &CidGeneric<S>, data: &[u8]) -> Result<()>;
 
+    /// Removes the block from the blockstore. Removing a missing block is not an error.
+    ///
+    /// Returns [`UnsupportedOperation`] unless implemented by the blockstore.
+    ///
+    /// [`UnsupportedOperation`]: BlockstoreError::UnsupportedOperation
+    async fn remove<const S: usize>(&self, _cid: &CidGeneric<S>) -> Result<()> {
+        Err(BlockstoreError::UnsupportedOperation)
+    }
+
     /// Checks whether blockstore has block for provided CID
     async fn has<const S: usize>(&self, cid: &CidGeneric<S>) -> Result<bool> {
         Ok(self.get(cid).await?.is_some())
@@ -0,0 +1,201 @@
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use celestia_rpc::prelude::*;
+use celestia_rpc::Client;
+use celestia_types::blob::SubmitOptions;
+use celestia_types::nmt::Namespace;
+#[cfg(feature = "os-keychain")]
+use celestia_types::ExtendedHeader;
+use celestia_types::{Blob, Commitment};
+use clap::{Args, Subcommand};
+#[cfg(feature = "os-keychain")]
+use lumina_node::blob_submitter::{BlobSubmitter, BlobSubmitterArgs, Keyring};
+#[cfg(feature = "os-keychain")]
+use lumina_node::keystore::OsKeychain;
+#[cfg(feature = "os-keychain")]
+use lumina_node::tx_client::{query_account, TxClient, TxConfig};
+use serde::Serialize;
+use tracing::info;
+
+#[cfg(feature = "os-keychain")]
+use crate::common::CELESTIA_LOCAL_CONSENSUS_RPC_ADDR;
+use crate::common::{parse_namespace, RpcArgs};
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum Command {
+    /// Submit a blob with the data from a file or the standard input
+    Submit(SubmitParams),
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct SubmitParams {
+    /// Namespace of the blob, as hex. Version 0 namespaces can be given by their id only.
+    #[arg(long, value_parser = parse_namespace)]
+    pub(crate) namespace: Namespace,
+
+    /// File with the data of the blob. Data is read from the standard input if not provided.
+    #[arg(long)]
+    pub(crate) file: Option<PathBuf>,
+
+    /// Fee for the validator, in utia. Estimated by the node if not provided.
+    #[arg(long)]
+    pub(crate) fee: Option<u64>,
+
+    /// Gas limit of the transaction. Estimated by the node if not provided.
+    #[arg(long)]
+    pub(crate) gas_limit: Option<u64>,
+
+    /// Print the result as JSON.
+    #[arg(long)]
+    pub(crate) json: bool,
+
+    /// Name of the key in the OS keychain, see `lumina keys import`. The transaction is
+    /// signed locally with it, instead of by the node's own account.
+    #[cfg(feature = "os-keychain")]
+    #[arg(long)]
+    pub(crate) key: Option<String>,
+
+    /// Address of the CometBFT RPC of a consensus node, queried for the account of the `--key`.
+    #[cfg(feature = "os-keychain")]
+    #[arg(long, default_value = CELESTIA_LOCAL_CONSENSUS_RPC_ADDR)]
+    pub(crate) consensus_rpc_url: String,
+
+    #[command(flatten)]
+    pub(crate) rpc: RpcArgs,
+}
+
+#[derive(Serialize)]
+struct SubmitOutput {
+    height: u64,
+    namespace: Namespace,
+    commitment: Commitment,
+}
+
+pub(crate) async fn run(cmd: Command) -> Result<()> {
+    match cmd {
+        Command::Submit(params) => submit(params).await,
+    }
+}
+
+async fn submit(params: SubmitParams) -> Result<()> {
+    let data = match &params.file {
+        Some(path) => {
+            fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?
+        }
+        None => {
+            let mut data = Vec::new();
+            io::stdin()
+                .read_to_end(&mut data)
+                .context("Failed to read the standard input")?;
+            data
+        }
+    };
+
+    let client = params.rpc.client().await?;
+    let network_head = client
+        .header_network_head()
+        .await
+        .context("Failed to get the network head")?;
+    let app_version = network_head
+        .app_version()
+        .context("Unsupported app version of the network")?;
+
+    let blob =
+        Blob::new(params.namespace, data, app_version).context("Failed to create the blob")?;
+    let commitment = blob.commitment;
+
+    let options = SubmitOptions {
+        fee: params.fee,
+        gas_limit: params.gas_limit,
+    };
+
+    info!("Submitting blob with commitment {commitment}");
+    #[cfg(feature = "os-keychain")]
+    let height = match &params.key {
+        Some(name) => {
+            submit_signed(
+                client,
+                &network_head,
+                name,
+                &params.consensus_rpc_url,
+                blob,
+                options,
+            )
+            .await?
+        }
+        None => submit_by_node(&client, blob, options).await?,
+    };
+    #[cfg(not(feature = "os-keychain"))]
+    let height = submit_by_node(&client, blob, options).await?;
+
+    let mut stdout = io::stdout().lock();
+
+    if params.json {
+        let output = SubmitOutput {
+            height,
+            namespace: params.namespace,
+            commitment,
+        };
+        serde_json::to_writer(&mut stdout, &output)?;
+        writeln!(stdout)?;
+    } else {
+        writeln!(stdout, "height:     {height}")?;
+        writeln!(stdout, "namespace:  {}", params.namespace)?;
+        writeln!(stdout, "commitment: {commitment}")?;
+    }
+
+    Ok(())
+}
+
+/// Submit the blob signed by the node's own account.
+async fn submit_by_node(client: &Client, blob: Blob, options: SubmitOptions) -> Result<u64> {
+    client
+        .blob_submit(&[blob], options)
+        .await
+        .context("Failed to submit the blob")
+}
+
+/// Sign the `PayForBlobs` with the key from the OS keychain and submit it through the node.
+#[cfg(feature = "os-keychain")]
+async fn submit_signed(
+    client: Client,
+    network_head: &ExtendedHeader,
+    key_name: &str,
+    consensus_rpc_url: &str,
+    blob: Blob,
+    options: SubmitOptions,
+) -> Result<u64> {
+    let keyring = OsKeychain::default()
+        .keyring(key_name)
+        .with_context(|| format!("Failed to load key {key_name}"))?;
+
+    let consensus = Client::new(consensus_rpc_url, None)
+        .await
+        .with_context(|| format!("Failed to connect to {consensus_rpc_url}"))?;
+    let account = query_account(&consensus, &keyring.address())
+        .await
+        .context("Failed to query the account")?;
+
+    let submitter = BlobSubmitter::new(BlobSubmitterArgs {
+        client,
+        keyring,
+        chain_id: network_head.chain_id().to_string(),
+        account_number: account.account_number,
+        sequence: account.sequence,
+    });
+
+    let response = if options.gas_limit.is_some() {
+        submitter.submit_blob(&[blob], options).await
+    } else {
+        TxClient::new(submitter, TxConfig::default())
+            .with_sequence_provider(consensus)
+            .submit_blobs(&[blob])
+            .await
+    }
+    .context("Failed to submit the blob")?;
+
+    Ok(response.height as u64)
+}
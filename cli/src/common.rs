@@ -1,9 +1,19 @@
-use anyhow::Result;
-use clap::{Parser, ValueEnum};
+use std::io::{self, Write};
+
+use anyhow::{Context, Result};
+use celestia_rpc::Client;
+use celestia_types::nmt::Namespace;
+use clap::{Args, Parser, ValueEnum};
 use lumina_node::network::Network;
 use serde_repr::Serialize_repr;
 
-use crate::{native, server};
+#[cfg(feature = "os-keychain")]
+use crate::keys;
+use crate::{blob, namespace, native, server};
+
+pub(crate) const CELESTIA_LOCAL_BRIDGE_RPC_ADDR: &str = "ws://localhost:26658";
+#[cfg(feature = "os-keychain")]
+pub(crate) const CELESTIA_LOCAL_CONSENSUS_RPC_ADDR: &str = "http://localhost:26657";
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize_repr)]
 #[repr(u8)]
@@ -18,25 +28,77 @@ pub(crate) enum ArgNetwork {
 #[derive(Debug, Parser)]
 pub(crate) enum CliArgs {
     /// Run native node locally
-    Node(native::Params),
+    Node(Box<native::Params>),
     /// Serve compiled wasm node to be run in the browser
     Browser(server::Params),
+    /// Query the data of a namespace through a Celestia node RPC
+    #[command(subcommand)]
+    Namespace(namespace::Command),
+    /// Manage blobs through a Celestia node RPC
+    #[command(subcommand)]
+    Blob(blob::Command),
+    /// Manage the keys of the accounts kept in the OS keychain
+    #[cfg(feature = "os-keychain")]
+    #[command(subcommand)]
+    Keys(keys::Command),
+}
+
+/// Connection to the RPC server of a Celestia node.
+#[derive(Debug, Args)]
+pub(crate) struct RpcArgs {
+    /// Address of the node's RPC server.
+    #[arg(long, default_value = CELESTIA_LOCAL_BRIDGE_RPC_ADDR)]
+    pub(crate) rpc_url: String,
+
+    /// Authentication token for the node's RPC server.
+    #[arg(long, env = "CELESTIA_NODE_AUTH_TOKEN", hide_env_values = true)]
+    pub(crate) auth_token: Option<String>,
+}
+
+impl RpcArgs {
+    pub(crate) async fn client(&self) -> Result<Client> {
+        Client::new(&self.rpc_url, self.auth_token.as_deref())
+            .await
+            .with_context(|| format!("Failed to connect to {}", self.rpc_url))
+    }
 }
 
 /// Run the Lumina node.
 pub async fn run() -> Result<()> {
     let _ = dotenvy::dotenv();
     let args = CliArgs::parse();
-    let _guard = init_tracing();
 
     match args {
-        CliArgs::Node(args) => native::run(args).await,
-        CliArgs::Browser(args) => server::run(args).await,
+        CliArgs::Node(args) => {
+            let _guard = init_tracing(io::stdout());
+            native::run(*args).await
+        }
+        CliArgs::Browser(args) => {
+            let _guard = init_tracing(io::stdout());
+            server::run(args).await
+        }
+        // keep stdout clean for the output of the commands
+        CliArgs::Namespace(cmd) => {
+            let _guard = init_tracing(io::stderr());
+            namespace::run(cmd).await
+        }
+        CliArgs::Blob(cmd) => {
+            let _guard = init_tracing(io::stderr());
+            blob::run(cmd).await
+        }
+        #[cfg(feature = "os-keychain")]
+        CliArgs::Keys(cmd) => {
+            let _guard = init_tracing(io::stderr());
+            keys::run(cmd)
+        }
     }
 }
 
-fn init_tracing() -> tracing_appender::non_blocking::WorkerGuard {
-    let (non_blocking, guard) = tracing_appender::non_blocking(std::io::stdout());
+fn init_tracing<W>(writer: W) -> tracing_appender::non_blocking::WorkerGuard
+where
+    W: Write + Send + Sync + 'static,
+{
+    let (non_blocking, guard) = tracing_appender::non_blocking(writer);
 
     let filter = tracing_subscriber::EnvFilter::builder()
         .with_default_directive(tracing_subscriber::filter::LevelFilter::INFO.into())
@@ -50,6 +112,17 @@ fn init_tracing() -> tracing_appender::non_blocking::WorkerGuard {
     guard
 }
 
+/// Parse the namespace either from its full hex representation or, for the
+/// version 0 namespaces, only from the hex of its id.
+pub(crate) fn parse_namespace(s: &str) -> Result<Namespace> {
+    if let Ok(namespace) = s.parse() {
+        return Ok(namespace);
+    }
+
+    let id = hex::decode(s.trim_start_matches("0x")).context("Invalid hex string")?;
+    Namespace::new_v0(&id).context("Invalid namespace")
+}
+
 impl From<ArgNetwork> for Network {
     fn from(network: ArgNetwork) -> Network {
         match network {
@@ -60,14 +133,3 @@ impl From<ArgNetwork> for Network {
         }
     }
 }
-
-impl From<Network> for ArgNetwork {
-    fn from(network: Network) -> ArgNetwork {
-        match network {
-            Network::Mainnet => ArgNetwork::Mainnet,
-            Network::Arabica => ArgNetwork::Arabica,
-            Network::Mocha => ArgNetwork::Mocha,
-            Network::Private => ArgNetwork::Private,
-        }
-    }
-}
@@ -0,0 +1,216 @@
+//! Configuration of the native node, loaded from a TOML file.
+
+use std::fs;
+#[cfg(any(feature = "metrics", feature = "rpc-server"))]
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use celestia_types::hash::Hash;
+use libp2p::Multiaddr;
+use lumina_node::daser::{DEFAULT_CONCURRENCY_LIMIT, DEFAULT_SAMPLES_PER_HEIGHT};
+use serde::{Deserialize, Serialize};
+
+use crate::common::ArgNetwork;
+use crate::native::ArgTransport;
+
+/// Settings of the native node.
+///
+/// Read from the config file and then overridden with the environment variables
+/// and the command line arguments. Missing keys take the same defaults as the
+/// command line arguments.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct NodeConfig {
+    #[serde(with = "network_name")]
+    pub(crate) network: ArgNetwork,
+    pub(crate) network_id: Option<String>,
+    pub(crate) genesis_hash: Option<Hash>,
+    pub(crate) listen_addrs: Vec<Multiaddr>,
+    pub(crate) relay_addrs: Vec<Multiaddr>,
+    pub(crate) transports: Vec<ArgTransport>,
+    pub(crate) bootnodes: Vec<Multiaddr>,
+    pub(crate) store: Option<PathBuf>,
+    pub(crate) pruning_window_secs: Option<u64>,
+    pub(crate) no_pruning: bool,
+    pub(crate) samples_per_height: usize,
+    pub(crate) sampling_concurrency: usize,
+    pub(crate) sample_timeout_secs: Option<u64>,
+    pub(crate) sample_oldest_first: bool,
+    pub(crate) sampling: bool,
+    pub(crate) header_quorum: usize,
+    pub(crate) max_clock_drift_secs: Option<u64>,
+    pub(crate) no_providing: bool,
+    pub(crate) eds_store: Option<PathBuf>,
+    pub(crate) v1_shwap_ids: bool,
+    #[cfg(feature = "metrics")]
+    pub(crate) metrics_listen: Option<SocketAddr>,
+    #[cfg(feature = "rpc-server")]
+    pub(crate) rpc_listen: Option<SocketAddr>,
+}
+
+impl NodeConfig {
+    /// Read the config from the TOML file.
+    pub(crate) fn from_file(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+
+        NodeConfig::from_toml(&content)
+            .with_context(|| format!("Invalid config file {}", path.display()))
+    }
+
+    /// Parse the config from the content of the TOML file.
+    pub(crate) fn from_toml(content: &str) -> Result<Self> {
+        Ok(toml::from_str(content)?)
+    }
+
+    /// Check the settings that can't be used together.
+    ///
+    /// The command line arguments are checked for conflicts already when parsed, this
+    /// catches the ones coming from the config file.
+    pub(crate) fn validate(&self) -> Result<()> {
+        if self.genesis_hash.is_some() && self.network_id.is_none() {
+            bail!("`genesis-hash` requires a custom `network-id`");
+        }
+        if self.no_pruning && self.pruning_window_secs.is_some() {
+            bail!("`pruning-window-secs` can't be set together with `no-pruning`");
+        }
+        if self.no_providing && self.eds_store.is_some() {
+            bail!("`eds-store` can't be set together with `no-providing`");
+        }
+
+        Ok(())
+    }
+
+    /// Render the config as TOML, in the format read by [`NodeConfig::from_file`].
+    pub(crate) fn to_toml(&self) -> Result<String> {
+        toml::to_string(self).context("Failed to serialize config")
+    }
+}
+
+impl Default for NodeConfig {
+    fn default() -> Self {
+        NodeConfig {
+            network: ArgNetwork::default(),
+            network_id: None,
+            genesis_hash: None,
+            listen_addrs: Vec::new(),
+            relay_addrs: Vec::new(),
+            transports: Vec::new(),
+            bootnodes: Vec::new(),
+            store: None,
+            pruning_window_secs: None,
+            no_pruning: false,
+            samples_per_height: DEFAULT_SAMPLES_PER_HEIGHT,
+            sampling_concurrency: DEFAULT_CONCURRENCY_LIMIT,
+            sample_timeout_secs: None,
+            sample_oldest_first: false,
+            sampling: false,
+            header_quorum: 1,
+            max_clock_drift_secs: None,
+            no_providing: false,
+            eds_store: None,
+            v1_shwap_ids: false,
+            #[cfg(feature = "metrics")]
+            metrics_listen: None,
+            #[cfg(feature = "rpc-server")]
+            rpc_listen: None,
+        }
+    }
+}
+
+/// Networks are written with the same names as on the command line, while the
+/// [`ArgNetwork`] itself is serialized as a number for the browser node.
+mod network_name {
+    use clap::ValueEnum;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    use crate::common::ArgNetwork;
+
+    pub(super) fn serialize<S>(network: &ArgNetwork, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let value = network
+            .to_possible_value()
+            .expect("networks are not skipped");
+        serializer.serialize_str(value.get_name())
+    }
+
+    pub(super) fn deserialize<'de, D>(deserializer: D) -> Result<ArgNetwork, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let name = String::deserialize(deserializer)?;
+        ArgNetwork::from_str(&name, true)
+            .map_err(|_| de::Error::custom(format!("unknown network: {name}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_keys_take_defaults() {
+        let config = NodeConfig::from_toml("").unwrap();
+        assert_eq!(config.network, ArgNetwork::default());
+        assert_eq!(config.samples_per_height, DEFAULT_SAMPLES_PER_HEIGHT);
+        assert_eq!(config.header_quorum, 1);
+        assert!(!config.sampling);
+    }
+
+    #[test]
+    fn parses_all_keys() {
+        let config = NodeConfig::from_toml(
+            r#"
+            network = "private"
+            network-id = "private-devnet"
+            listen-addrs = ["/ip4/0.0.0.0/tcp/2121"]
+            transports = ["tcp", "quic"]
+            store = "/tmp/lumina"
+            sampling = true
+            sample-timeout-secs = 5
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.network, ArgNetwork::Private);
+        assert_eq!(config.network_id.as_deref(), Some("private-devnet"));
+        assert_eq!(config.listen_addrs.len(), 1);
+        assert_eq!(
+            config.transports,
+            vec![ArgTransport::Tcp, ArgTransport::Quic]
+        );
+        assert_eq!(config.store, Some(PathBuf::from("/tmp/lumina")));
+        assert!(config.sampling);
+        assert_eq!(config.sample_timeout_secs, Some(5));
+    }
+
+    #[cfg(feature = "rpc-server")]
+    #[test]
+    fn reads_rpc_listen() {
+        let config = NodeConfig::from_toml("rpc-listen = \"127.0.0.1:26658\"").unwrap();
+        assert_eq!(config.rpc_listen, Some("127.0.0.1:26658".parse().unwrap()));
+    }
+
+    #[test]
+    fn rejects_invalid_config() {
+        // unknown key
+        NodeConfig::from_toml("sampling-enabled = true").unwrap_err();
+        // unknown network
+        NodeConfig::from_toml("network = \"testnet\"").unwrap_err();
+
+        let config = NodeConfig::from_toml("no-pruning = true\npruning-window-secs = 60").unwrap();
+        config.validate().unwrap_err();
+    }
+
+    #[test]
+    fn round_trips_through_toml() {
+        let config = NodeConfig::from_toml("network = \"mocha\"\nsampling = true").unwrap();
+        let parsed = NodeConfig::from_toml(&config.to_toml().unwrap()).unwrap();
+
+        assert_eq!(parsed.network, ArgNetwork::Mocha);
+        assert!(parsed.sampling);
+    }
+}
@@ -0,0 +1,88 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use clap::{Args, Subcommand};
+use lumina_node::blob_submitter::Keyring;
+use lumina_node::keystore::{import_armored_key, import_hex_key, Keystore, OsKeychain};
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum Command {
+    /// Import the key exported with `celestia-appd keys export`
+    Import(ImportParams),
+    /// Print the address of the account of the key
+    Show(KeyParams),
+    /// Remove the key from the OS keychain
+    Remove(KeyParams),
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct ImportParams {
+    /// Name under which the key is stored.
+    pub(crate) name: String,
+
+    /// File with the exported key.
+    #[arg(long)]
+    pub(crate) file: PathBuf,
+
+    /// The file holds the export made with `--unarmored-hex --unsafe`.
+    #[arg(long)]
+    pub(crate) unarmored_hex: bool,
+
+    /// Passphrase the armored key was exported with.
+    #[arg(
+        long,
+        env = "LUMINA_KEY_PASSPHRASE",
+        hide_env_values = true,
+        conflicts_with = "unarmored_hex"
+    )]
+    pub(crate) passphrase: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct KeyParams {
+    /// Name of the key.
+    pub(crate) name: String,
+}
+
+pub(crate) fn run(cmd: Command) -> Result<()> {
+    let keychain = OsKeychain::default();
+
+    match cmd {
+        Command::Import(params) => import(&keychain, params),
+        Command::Show(params) => {
+            let keyring = keychain
+                .keyring(&params.name)
+                .with_context(|| format!("Failed to load key {}", params.name))?;
+            writeln!(io::stdout(), "{}", keyring.address())?;
+            Ok(())
+        }
+        Command::Remove(params) => keychain
+            .remove(&params.name)
+            .with_context(|| format!("Failed to remove key {}", params.name)),
+    }
+}
+
+fn import(keychain: &OsKeychain, params: ImportParams) -> Result<()> {
+    let path = &params.file;
+    let export =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let key = if params.unarmored_hex {
+        import_hex_key(&export)
+    } else {
+        let Some(passphrase) = params.passphrase else {
+            bail!("Passphrase of the armored key is required, see `--passphrase`");
+        };
+        import_armored_key(&export, &passphrase)
+    }
+    .context("Failed to import the key")?;
+
+    keychain
+        .insert(&params.name, &key)
+        .with_context(|| format!("Failed to store key {}", params.name))?;
+    writeln!(io::stdout(), "{}", key.address())?;
+
+    Ok(())
+}
@@ -1,7 +1,12 @@
 #![doc = include_str!("../../README.md")]
 #![cfg(not(target_arch = "wasm32"))]
 
+mod blob;
 mod common;
+mod config;
+#[cfg(feature = "os-keychain")]
+mod keys;
+mod namespace;
 mod native;
 mod server;
 
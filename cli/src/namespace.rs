@@ -0,0 +1,157 @@
+use std::io::{self, Write};
+
+use anyhow::{bail, Context, Result};
+use celestia_rpc::prelude::*;
+use celestia_types::nmt::{Namespace, NamespacedSha2Hasher};
+use celestia_types::{ExtendedHeader, NamespacedShares};
+use clap::{Args, Subcommand, ValueEnum};
+use serde::Serialize;
+
+use crate::common::{parse_namespace, RpcArgs};
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum Command {
+    /// Get the shares of a namespace at the given height, verified against the block header
+    Get(GetParams),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub(crate) enum ArgOutput {
+    /// Human readable summary of the shares
+    #[default]
+    Pretty,
+    /// Concatenated bytes of the shares
+    Raw,
+    /// Shares with their proofs as JSON
+    Json,
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct GetParams {
+    /// Namespace to get, as hex. Version 0 namespaces can be given by their id only.
+    #[arg(long, value_parser = parse_namespace)]
+    pub(crate) namespace: Namespace,
+
+    /// Height of the block.
+    #[arg(long)]
+    pub(crate) height: u64,
+
+    /// Format of the output.
+    #[arg(short, long, value_enum, default_value_t)]
+    pub(crate) output: ArgOutput,
+
+    #[command(flatten)]
+    pub(crate) rpc: RpcArgs,
+}
+
+#[derive(Serialize)]
+struct JsonOutput<'a> {
+    height: u64,
+    namespace: Namespace,
+    shares: &'a NamespacedShares,
+}
+
+pub(crate) async fn run(cmd: Command) -> Result<()> {
+    match cmd {
+        Command::Get(params) => get(params).await,
+    }
+}
+
+async fn get(params: GetParams) -> Result<()> {
+    let client = params.rpc.client().await?;
+
+    let header = client
+        .header_get_by_height(params.height)
+        .await
+        .with_context(|| format!("Failed to get header at height {}", params.height))?;
+    let shares = client
+        .share_get_shares_by_namespace(&header, params.namespace)
+        .await
+        .context("Failed to get shares of the namespace")?;
+
+    verify(&header, params.namespace, &shares)?;
+
+    let mut stdout = io::stdout().lock();
+
+    match params.output {
+        ArgOutput::Pretty => print_pretty(&mut stdout, &header, params.namespace, &shares)?,
+        ArgOutput::Raw => {
+            for share in shares.rows.iter().flat_map(|row| &row.shares) {
+                stdout.write_all(share.as_ref())?;
+            }
+        }
+        ArgOutput::Json => {
+            let output = JsonOutput {
+                height: params.height,
+                namespace: params.namespace,
+                shares: &shares,
+            };
+            serde_json::to_writer_pretty(&mut stdout, &output)?;
+            writeln!(stdout)?;
+        }
+    }
+
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Validate the header and verify that the rows are complete for the namespace against
+/// the row roots which may contain it.
+fn verify(header: &ExtendedHeader, namespace: Namespace, shares: &NamespacedShares) -> Result<()> {
+    // the header comes from an untrusted endpoint, its roots must match its data hash
+    header.validate().context("Invalid header")?;
+
+    let row_roots: Vec<_> = (0..header.dah.square_len())
+        .filter_map(|row| header.dah.row_root(row))
+        .filter(|root| root.contains::<NamespacedSha2Hasher>(*namespace))
+        .collect();
+
+    if row_roots.len() != shares.rows.len() {
+        bail!(
+            "Expected {} rows of the namespace, received {}",
+            row_roots.len(),
+            shares.rows.len()
+        );
+    }
+
+    for (root, row) in row_roots.iter().zip(&shares.rows) {
+        row.proof
+            .verify_complete_namespace(root, &row.shares, *namespace)
+            .map_err(|e| anyhow::anyhow!("Invalid proof of the namespace shares: {e:?}"))?;
+    }
+
+    Ok(())
+}
+
+fn print_pretty(
+    out: &mut impl Write,
+    header: &ExtendedHeader,
+    namespace: Namespace,
+    shares: &NamespacedShares,
+) -> Result<()> {
+    let shares_count: usize = shares.rows.iter().map(|row| row.shares.len()).sum();
+
+    writeln!(out, "height:    {}", header.height())?;
+    writeln!(out, "hash:      {}", header.hash())?;
+    writeln!(out, "namespace: {namespace}")?;
+    writeln!(
+        out,
+        "shares:    {shares_count} in {} rows",
+        shares.rows.len()
+    )?;
+
+    for (row_idx, row) in shares.rows.iter().enumerate() {
+        for (share_idx, share) in row.shares.iter().enumerate() {
+            writeln!(out)?;
+            writeln!(
+                out,
+                "row {row_idx} share {share_idx} (version {}, sequence start: {})",
+                share.version(),
+                share.is_sequence_start()
+            )?;
+            writeln!(out, "{}", hex::encode(share.payload()))?;
+        }
+    }
+
+    Ok(())
+}
@@ -1,63 +1,353 @@
 use std::env;
+#[cfg(any(feature = "metrics", feature = "rpc-server"))]
+use std::net::SocketAddr;
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::{bail, Context, Result};
 use celestia_rpc::prelude::*;
 use celestia_rpc::Client;
-use clap::Args;
-use libp2p::{identity, multiaddr::Protocol, Multiaddr};
-use lumina_node::network::{canonical_network_bootnodes, network_genesis, network_id, Network};
-use lumina_node::node::{Node, NodeConfig};
+use celestia_types::hash::Hash;
+use celestia_types::row::IdLayout;
+use clap::builder::BoolishValueParser;
+use clap::{Args, ValueEnum};
+use libp2p::{multiaddr::Protocol, Multiaddr};
+use lumina_node::daser::DaserConfig;
+use lumina_node::eds_store::EdsStore;
+use lumina_node::network::{network_id, CustomNetwork, Network};
+use lumina_node::node::Node;
+use lumina_node::p2p::TransportConfig;
+#[cfg(feature = "rpc-server")]
+use lumina_node::rpc_server::RpcServer;
 use lumina_node::store::{SledStore, Store};
+#[cfg(feature = "metrics")]
+use metrics_exporter_prometheus::PrometheusBuilder;
+use serde::{Deserialize, Serialize};
 use tokio::time::sleep;
 use tracing::info;
 
-use crate::common::ArgNetwork;
+use crate::common::{ArgNetwork, CELESTIA_LOCAL_BRIDGE_RPC_ADDR};
+use crate::config::NodeConfig;
 
-const CELESTIA_LOCAL_BRIDGE_RPC_ADDR: &str = "ws://localhost:26658";
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ArgTransport {
+    Tcp,
+    Quic,
+}
 
+/// Arguments of the native node.
+///
+/// Each of them can also be given with the environment variable or in the config
+/// file, in the order of the increasing precedence: config file, environment variables
+/// and the command line.
 #[derive(Debug, Args)]
 pub(crate) struct Params {
-    /// Network to connect.
-    #[arg(short, long, value_enum, default_value_t)]
-    pub(crate) network: ArgNetwork,
+    /// Path of the TOML config file, e.g. with `network = "mocha"`. Use `--print-config`
+    /// for all the available keys.
+    #[arg(short, long, env = "LUMINA_CONFIG")]
+    pub(crate) config: Option<PathBuf>,
+
+    /// Print the effective configuration in the format of the config file and exit.
+    #[arg(long)]
+    pub(crate) print_config: bool,
+
+    /// Network to connect. Defaults to mainnet.
+    #[arg(short, long, value_enum, env = "LUMINA_NETWORK")]
+    pub(crate) network: Option<ArgNetwork>,
+
+    /// Id of a custom network to connect, instead of one of the presets.
+    /// Bootnodes of the network are then provided with `--bootnode`.
+    #[arg(long, conflicts_with = "network", env = "LUMINA_NETWORK_ID")]
+    pub(crate) network_id: Option<String>,
+
+    /// Hash of the genesis block of the custom network.
+    #[arg(long, requires = "network_id", env = "LUMINA_GENESIS_HASH")]
+    pub(crate) genesis_hash: Option<Hash>,
 
     /// Listening addresses. Can be used multiple times.
-    #[arg(short, long = "listen")]
+    #[arg(short, long = "listen", env = "LUMINA_LISTEN", value_delimiter = ',')]
     pub(crate) listen_addrs: Vec<Multiaddr>,
 
+    /// Circuit relay multiaddr, including peer id, through which the node can be reached
+    /// when behind a NAT. Can be used multiple times.
+    #[arg(long = "relay", env = "LUMINA_RELAY", value_delimiter = ',')]
+    pub(crate) relay_addrs: Vec<Multiaddr>,
+
+    /// Transport to use for connections. Can be used multiple times. Defaults to all.
+    #[arg(
+        long = "transport",
+        value_enum,
+        env = "LUMINA_TRANSPORT",
+        value_delimiter = ','
+    )]
+    pub(crate) transports: Vec<ArgTransport>,
+
     /// Bootnode multiaddr, including peer id. Can be used multiple times.
-    #[arg(short, long = "bootnode")]
+    #[arg(
+        short,
+        long = "bootnode",
+        env = "LUMINA_BOOTNODE",
+        value_delimiter = ','
+    )]
     pub(crate) bootnodes: Vec<Multiaddr>,
 
     /// Persistent header store path.
-    #[arg(short, long = "store")]
+    #[arg(short, long = "store", env = "LUMINA_STORE")]
     pub(crate) store: Option<PathBuf>,
+
+    /// Remove headers older than the given number of seconds. Defaults to 30 days.
+    #[arg(long = "pruning-window", env = "LUMINA_PRUNING_WINDOW")]
+    pub(crate) pruning_window_secs: Option<u64>,
+
+    /// Disable removal of old headers.
+    #[arg(
+        long,
+        conflicts_with = "pruning_window_secs",
+        env = "LUMINA_NO_PRUNING",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_parser = BoolishValueParser::new()
+    )]
+    pub(crate) no_pruning: Option<bool>,
+
+    /// Number of random samples requested for each synchronized block.
+    #[arg(long, env = "LUMINA_SAMPLES_PER_HEIGHT")]
+    pub(crate) samples_per_height: Option<usize>,
+
+    /// Maximum number of blocks sampled at the same time.
+    #[arg(long, env = "LUMINA_SAMPLING_CONCURRENCY")]
+    pub(crate) sampling_concurrency: Option<usize>,
+
+    /// Seconds after which a sample that wasn't retrieved is considered unavailable.
+    #[arg(long = "sample-timeout", env = "LUMINA_SAMPLE_TIMEOUT")]
+    pub(crate) sample_timeout_secs: Option<u64>,
+
+    /// Sample the blocks from the oldest one, instead of the newest heads first.
+    #[arg(
+        long,
+        env = "LUMINA_SAMPLE_OLDEST_FIRST",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_parser = BoolishValueParser::new()
+    )]
+    pub(crate) sample_oldest_first: Option<bool>,
+
+    /// Enable the data availability sampling of the synchronized blocks. Use
+    /// `--sampling=false` to disable it when it's enabled in the config file.
+    #[arg(
+        long,
+        env = "LUMINA_SAMPLING",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_parser = BoolishValueParser::new()
+    )]
+    pub(crate) sampling: Option<bool>,
+
+    /// Number of peers that must agree on each synchronized batch of headers. Defaults to 1.
+    #[arg(long, env = "LUMINA_HEADER_QUORUM")]
+    pub(crate) header_quorum: Option<usize>,
+
+    /// Seconds by which the received headers can be ahead of the local clock. Defaults to 10.
+    #[arg(long = "max-clock-drift", env = "LUMINA_MAX_CLOCK_DRIFT")]
+    pub(crate) max_clock_drift_secs: Option<u64>,
+
+    /// Don't announce the stored shwap containers on the DHT nor serve them to other peers.
+    #[arg(
+        long,
+        env = "LUMINA_NO_PROVIDING",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_parser = BoolishValueParser::new()
+    )]
+    pub(crate) no_providing: Option<bool>,
+
+    /// Directory of the stored squares from which the shwap containers are served to other peers.
+    #[arg(long, conflicts_with = "no_providing", env = "LUMINA_EDS_STORE")]
+    pub(crate) eds_store: Option<PathBuf>,
+
+    /// Use the shwap identifiers of the v1 specification. The containers are still in the
+    /// draft format, so only the peers using the same identifiers understand them.
+    #[arg(
+        long,
+        env = "LUMINA_V1_SHWAP_IDS",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_parser = BoolishValueParser::new()
+    )]
+    pub(crate) v1_shwap_ids: Option<bool>,
+
+    /// Address on which the Prometheus metrics are served, e.g. `127.0.0.1:9000`.
+    #[cfg(feature = "metrics")]
+    #[arg(long, env = "LUMINA_METRICS_LISTEN")]
+    pub(crate) metrics_listen: Option<SocketAddr>,
+
+    /// Serve the celestia-node compatible JSON-RPC API, on `127.0.0.1:26658` if no other
+    /// address is given. The server has no authentication, so it shouldn't be reachable
+    /// from outside of the local machine.
+    #[cfg(feature = "rpc-server")]
+    #[arg(
+        long,
+        env = "LUMINA_RPC_LISTEN",
+        num_args = 0..=1,
+        default_missing_value = "127.0.0.1:26658"
+    )]
+    pub(crate) rpc_listen: Option<SocketAddr>,
 }
 
-pub(crate) async fn run(args: Params) -> Result<()> {
-    let network = args.network.into();
-    let p2p_local_keypair = identity::Keypair::generate_ed25519();
+impl Params {
+    /// Load the config file, if any, and override it with the arguments.
+    fn into_config(self) -> Result<NodeConfig> {
+        let config = match self.config {
+            Some(ref path) => NodeConfig::from_file(path)?,
+            None => NodeConfig::default(),
+        };
+
+        self.override_config(config)
+    }
 
-    let p2p_bootnodes = if args.bootnodes.is_empty() {
-        match network {
-            Network::Private => fetch_bridge_multiaddrs(CELESTIA_LOCAL_BRIDGE_RPC_ADDR).await?,
-            network => canonical_network_bootnodes(network).collect(),
+    /// Override the settings of the config file with the ones given as the arguments.
+    ///
+    /// Flags override the file only if they are given, so `--sampling=false` disables
+    /// the sampling enabled in the file.
+    fn override_config(self, mut config: NodeConfig) -> Result<NodeConfig> {
+        // preset network given explicitly replaces the custom one from the file
+        if let Some(network) = self.network {
+            config.network = network;
+            config.network_id = None;
+            config.genesis_hash = None;
         }
-    } else {
-        args.bootnodes
+        if self.network_id.is_some() {
+            config.network_id = self.network_id;
+            config.genesis_hash = self.genesis_hash;
+        }
+
+        override_vec(&mut config.listen_addrs, self.listen_addrs);
+        override_vec(&mut config.relay_addrs, self.relay_addrs);
+        override_vec(&mut config.transports, self.transports);
+        override_vec(&mut config.bootnodes, self.bootnodes);
+
+        if self.store.is_some() {
+            config.store = self.store;
+        }
+        if self.pruning_window_secs.is_some() {
+            config.pruning_window_secs = self.pruning_window_secs;
+            config.no_pruning = false;
+        }
+        if let Some(no_pruning) = self.no_pruning {
+            config.no_pruning = no_pruning;
+            if no_pruning {
+                config.pruning_window_secs = None;
+            }
+        }
+        if let Some(samples) = self.samples_per_height {
+            config.samples_per_height = samples;
+        }
+        if let Some(concurrency) = self.sampling_concurrency {
+            config.sampling_concurrency = concurrency;
+        }
+        if self.sample_timeout_secs.is_some() {
+            config.sample_timeout_secs = self.sample_timeout_secs;
+        }
+        if let Some(quorum) = self.header_quorum {
+            config.header_quorum = quorum;
+        }
+        if self.max_clock_drift_secs.is_some() {
+            config.max_clock_drift_secs = self.max_clock_drift_secs;
+        }
+        if self.eds_store.is_some() {
+            config.eds_store = self.eds_store;
+            config.no_providing = false;
+        }
+        if let Some(no_providing) = self.no_providing {
+            config.no_providing = no_providing;
+            if no_providing {
+                config.eds_store = None;
+            }
+        }
+        #[cfg(feature = "metrics")]
+        if self.metrics_listen.is_some() {
+            config.metrics_listen = self.metrics_listen;
+        }
+        #[cfg(feature = "rpc-server")]
+        if self.rpc_listen.is_some() {
+            config.rpc_listen = self.rpc_listen;
+        }
+
+        if let Some(sample_oldest_first) = self.sample_oldest_first {
+            config.sample_oldest_first = sample_oldest_first;
+        }
+        if let Some(sampling) = self.sampling {
+            config.sampling = sampling;
+        }
+        if let Some(v1_shwap_ids) = self.v1_shwap_ids {
+            config.v1_shwap_ids = v1_shwap_ids;
+        }
+
+        config.validate()?;
+
+        Ok(config)
+    }
+}
+
+/// Arguments given multiple times replace the whole list from the config file.
+fn override_vec<T>(config: &mut Vec<T>, args: Vec<T>) {
+    if !args.is_empty() {
+        *config = args;
+    }
+}
+
+pub(crate) async fn run(args: Params) -> Result<()> {
+    let print_config = args.print_config;
+    let config = args.into_config()?;
+
+    if print_config {
+        print!("{}", config.to_toml()?);
+        return Ok(());
+    }
+
+    #[cfg(feature = "metrics")]
+    if let Some(addr) = config.metrics_listen {
+        PrometheusBuilder::new()
+            .with_http_listener(addr)
+            .install()
+            .context("Failed to start the metrics exporter")?;
+        lumina_node::metrics::describe();
+        info!("Serving metrics on {addr}");
+    }
+
+    let network = match config.network_id {
+        Some(id) => Network::Custom(CustomNetwork {
+            id,
+            genesis_hash: config.genesis_hash,
+            bootnodes: config.bootnodes.clone(),
+        }),
+        None => config.network.into(),
     };
+    let store_name = network_id(&network).to_owned();
+    let mut builder = Node::builder()
+        .network(network.clone())
+        .listen_addrs(config.listen_addrs)
+        .relay_addrs(config.relay_addrs);
 
-    let network_id = network_id(network).to_owned();
-    let genesis_hash = network_genesis(network);
+    if !config.bootnodes.is_empty() {
+        builder = builder.bootnodes(config.bootnodes);
+    } else if network == Network::Private {
+        builder = builder.bootnodes(fetch_bridge_multiaddrs(CELESTIA_LOCAL_BRIDGE_RPC_ADDR).await?);
+    }
 
     info!("Initializing store");
 
-    let store = if let Some(db_path) = args.store {
+    let store = if let Some(db_path) = config.store {
         SledStore::new_in_path(db_path).await?
     } else {
-        SledStore::new(network_id.clone()).await?
+        SledStore::new(store_name).await?
     };
 
     match store.head_height().await {
@@ -65,16 +355,70 @@ pub(crate) async fn run(args: Params) -> Result<()> {
         Err(_) => info!("Initialised new store"),
     }
 
-    let node = Node::new(NodeConfig {
-        network_id,
-        genesis_hash,
-        p2p_local_keypair,
-        p2p_bootnodes,
-        p2p_listen_on: args.listen_addrs,
-        store,
-    })
-    .await
-    .context("Failed to start node")?;
+    if config.no_pruning {
+        builder = builder.disable_pruning();
+    } else if let Some(secs) = config.pruning_window_secs {
+        builder = builder.pruning_window(Duration::from_secs(secs));
+    }
+
+    if config.sampling {
+        let default = DaserConfig::default();
+        builder = builder.daser_config(DaserConfig {
+            samples_per_height: config.samples_per_height,
+            concurrency_limit: config.sampling_concurrency,
+            sample_timeout: config
+                .sample_timeout_secs
+                .map_or(default.sample_timeout, Duration::from_secs),
+            prioritize_recent: !config.sample_oldest_first,
+            ..default
+        });
+    }
+
+    if let Some(secs) = config.max_clock_drift_secs {
+        builder = builder.max_clock_drift(Duration::from_secs(secs));
+    }
+
+    if config.no_providing {
+        builder = builder.disable_providing();
+    }
+
+    if let Some(path) = config.eds_store {
+        let eds_store = EdsStore::new(&path)
+            .await
+            .with_context(|| format!("Failed to open eds store in {}", path.display()))?;
+        builder = builder.eds_store(eds_store);
+    }
+
+    if config.v1_shwap_ids {
+        builder = builder.shwap_id_layout(IdLayout::V1);
+    }
+
+    if !config.transports.is_empty() {
+        builder = builder.transports(
+            TransportConfig::empty()
+                .tcp(config.transports.contains(&ArgTransport::Tcp))
+                .quic(config.transports.contains(&ArgTransport::Quic)),
+        );
+    }
+
+    let node = builder
+        .header_quorum(config.header_quorum)
+        .store(store)
+        .start()
+        .await
+        .context("Failed to start node")?;
+    let node = Arc::new(node);
+
+    // kept until the end of main, the server stops when dropped
+    #[cfg(feature = "rpc-server")]
+    let _rpc_server = match config.rpc_listen {
+        Some(addr) => Some(
+            RpcServer::start(node.clone(), addr)
+                .await
+                .context("Failed to start the JSON-RPC server")?,
+        ),
+        None => None,
+    };
 
     node.wait_connected_trusted().await?;
 
@@ -112,3 +456,110 @@ async fn fetch_bridge_multiaddrs(ws_url: &str) -> Result<Vec<Multiaddr>> {
 
     Ok(addrs)
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::common::CliArgs;
+
+    fn parse(args: &[&str]) -> Params {
+        let args = ["lumina", "node"].iter().chain(args);
+        match CliArgs::try_parse_from(args).unwrap() {
+            CliArgs::Node(params) => *params,
+            _ => unreachable!("parsed the node command"),
+        }
+    }
+
+    fn config(toml: &str) -> NodeConfig {
+        NodeConfig::from_toml(toml).unwrap()
+    }
+
+    #[test]
+    fn flags_are_unset_unless_given() {
+        let params = parse(&[]);
+        assert_eq!(params.sampling, None);
+        assert_eq!(params.no_pruning, None);
+
+        let config = params
+            .override_config(config("sampling = true\nno-pruning = true"))
+            .unwrap();
+        assert!(config.sampling);
+        assert!(config.no_pruning);
+    }
+
+    #[test]
+    fn flags_override_the_config_file() {
+        let file = "sampling = true\nno-providing = true\nv1-shwap-ids = true";
+
+        let config = parse(&[
+            "--sampling=false",
+            "--no-providing=false",
+            "--v1-shwap-ids=false",
+        ])
+        .override_config(config(file))
+        .unwrap();
+        assert!(!config.sampling);
+        assert!(!config.no_providing);
+        assert!(!config.v1_shwap_ids);
+
+        let config = parse(&["--sample-oldest-first", "--no-pruning"])
+            .override_config(config("pruning-window-secs = 60"))
+            .unwrap();
+        assert!(config.sample_oldest_first);
+        assert!(config.no_pruning);
+        assert_eq!(config.pruning_window_secs, None);
+    }
+
+    #[test]
+    fn arguments_override_the_config_file() {
+        let file = r#"
+            network = "mocha"
+            samples-per-height = 4
+            listen-addrs = ["/ip4/0.0.0.0/tcp/2121"]
+            pruning-window-secs = 60
+        "#;
+
+        let config = parse(&[
+            "--network",
+            "arabica",
+            "--samples-per-height",
+            "8",
+            "--pruning-window",
+            "120",
+        ])
+        .override_config(config(file))
+        .unwrap();
+
+        assert_eq!(config.network, ArgNetwork::Arabica);
+        assert_eq!(config.samples_per_height, 8);
+        assert_eq!(config.pruning_window_secs, Some(120));
+        // not given as an argument, kept from the file
+        assert_eq!(
+            config.listen_addrs,
+            vec!["/ip4/0.0.0.0/tcp/2121".parse::<Multiaddr>().unwrap()]
+        );
+    }
+
+    #[test]
+    fn conflicting_settings_are_rejected() {
+        parse(&[])
+            .override_config(config("eds-store = \"/tmp/eds\"\nno-providing = true"))
+            .unwrap_err();
+        // resolved by the arguments
+        parse(&["--no-providing=false"])
+            .override_config(config("eds-store = \"/tmp/eds\"\nno-providing = true"))
+            .unwrap();
+
+        CliArgs::try_parse_from(["lumina", "node", "--no-pruning", "--pruning-window", "60"])
+            .unwrap_err();
+    }
+
+    #[cfg(feature = "rpc-server")]
+    #[test]
+    fn rpc_listens_on_localhost_by_default() {
+        let params = parse(&["--rpc-listen"]);
+        assert_eq!(params.rpc_listen, Some("127.0.0.1:26658".parse().unwrap()));
+    }
+}
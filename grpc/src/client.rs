@@ -0,0 +1,141 @@
+//! Client of the gRPC endpoints of the Celestia validator nodes.
+
+use celestia_proto::celestia::blob::v1::query_client::QueryClient as BlobQueryClient;
+use celestia_proto::celestia::blob::v1::QueryParamsRequest as QueryBlobParamsRequest;
+use celestia_proto::cosmos::auth::v1beta1::query_client::QueryClient as AuthQueryClient;
+use celestia_proto::cosmos::auth::v1beta1::{ModuleAccount, QueryAccountRequest};
+use celestia_proto::cosmos::base::node::v1beta1::service_client::ServiceClient as NodeServiceClient;
+use celestia_proto::cosmos::base::node::v1beta1::ConfigRequest;
+use celestia_proto::cosmos::tx::v1beta1::service_client::ServiceClient as TxServiceClient;
+use celestia_proto::cosmos::tx::v1beta1::BroadcastTxRequest;
+use celestia_types::state::{AccAddress, RawTx, TxResponse};
+use prost::Message;
+use tonic::transport::{Channel, Endpoint};
+
+use crate::{BaseAccount, BlobParams, BroadcastMode, Error, Result};
+
+const BASE_ACCOUNT_TYPE_URL: &str = "/cosmos.auth.v1beta1.BaseAccount";
+const MODULE_ACCOUNT_TYPE_URL: &str = "/cosmos.auth.v1beta1.ModuleAccount";
+/// Denomination in which the gas prices are expressed.
+const GAS_PRICE_DENOM: &str = "utia";
+
+/// Client of the cosmos-sdk and celestia-app gRPC services.
+#[derive(Debug, Clone)]
+pub struct GrpcClient {
+    channel: Channel,
+}
+
+impl GrpcClient {
+    /// Create a new client using the provided [`Channel`].
+    pub fn new(channel: Channel) -> Self {
+        GrpcClient { channel }
+    }
+
+    /// Connect to the gRPC endpoint of the validator node, e.g. `http://localhost:9090`.
+    pub async fn connect(url: impl Into<String>) -> Result<Self> {
+        let channel = Endpoint::from_shared(url.into())?.connect().await?;
+        Ok(GrpcClient::new(channel))
+    }
+
+    /// Broadcast the signed transaction.
+    ///
+    /// Depending on the [`BroadcastMode`], the response may be returned before the
+    /// transaction is included in a block.
+    pub async fn broadcast_tx(&self, tx: &RawTx, mode: BroadcastMode) -> Result<TxResponse> {
+        let request = BroadcastTxRequest {
+            tx_bytes: tx.as_bytes().to_vec(),
+            mode: mode.into(),
+        };
+
+        TxServiceClient::new(self.channel.clone())
+            .broadcast_tx(request)
+            .await?
+            .into_inner()
+            .tx_response
+            .ok_or(Error::MissingField("tx_response"))
+    }
+
+    /// Get the account with the given address.
+    ///
+    /// Module accounts are returned as their underlying [`BaseAccount`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnsupportedAccountType`] for other kinds of accounts, e.g. the
+    /// vesting ones.
+    pub async fn get_account(&self, address: &AccAddress) -> Result<BaseAccount> {
+        let request = QueryAccountRequest {
+            address: address.to_string(),
+        };
+
+        let account = AuthQueryClient::new(self.channel.clone())
+            .account(request)
+            .await?
+            .into_inner()
+            .account
+            .ok_or(Error::MissingField("account"))?;
+
+        match account.type_url.as_str() {
+            BASE_ACCOUNT_TYPE_URL => Ok(BaseAccount::decode(&account.value[..])?),
+            MODULE_ACCOUNT_TYPE_URL => ModuleAccount::decode(&account.value[..])?
+                .base_account
+                .ok_or(Error::MissingField("base_account")),
+            _ => Err(Error::UnsupportedAccountType(account.type_url)),
+        }
+    }
+
+    /// Get the minimum gas price, in `utia`, accepted by the node.
+    pub async fn get_min_gas_price(&self) -> Result<f64> {
+        let config = NodeServiceClient::new(self.channel.clone())
+            .config(ConfigRequest {})
+            .await?
+            .into_inner();
+
+        parse_min_gas_price(&config.minimum_gas_price)
+    }
+
+    /// Get the parameters of the blob module.
+    pub async fn get_blob_params(&self) -> Result<BlobParams> {
+        BlobQueryClient::new(self.channel.clone())
+            .params(QueryBlobParamsRequest {})
+            .await?
+            .into_inner()
+            .params
+            .ok_or(Error::MissingField("params"))
+    }
+}
+
+/// Parse the price in `utia` from the decimal coins, e.g. `0.002000000000000000utia`.
+fn parse_min_gas_price(prices: &str) -> Result<f64> {
+    // Node without a minimum gas price configured accepts any fee
+    if prices.is_empty() {
+        return Ok(0.0);
+    }
+
+    prices
+        .split(',')
+        .find_map(|price| price.trim().strip_suffix(GAS_PRICE_DENOM))
+        .and_then(|amount| amount.parse().ok())
+        .ok_or_else(|| Error::InvalidGasPrice(prices.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn min_gas_price() {
+        assert_eq!(parse_min_gas_price("").unwrap(), 0.0);
+        assert_eq!(
+            parse_min_gas_price("0.002000000000000000utia").unwrap(),
+            0.002
+        );
+        assert_eq!(
+            parse_min_gas_price("0.1stake,0.004000000000000000utia").unwrap(),
+            0.004
+        );
+
+        parse_min_gas_price("0.1stake").unwrap_err();
+        parse_min_gas_price("utia").unwrap_err();
+    }
+}
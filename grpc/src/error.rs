@@ -0,0 +1,40 @@
+/// Alias for a `Result` with the error type [`celestia_grpc::Error`].
+///
+/// [`celestia_grpc::Error`]: crate::Error
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Representation of all the errors that can occur when interacting with [`celestia_grpc`].
+///
+/// [`celestia_grpc`]: crate
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Connecting to the endpoint failed.
+    #[error(transparent)]
+    Transport(#[from] tonic::transport::Error),
+
+    /// The request failed with the status returned by the server.
+    #[error("Request failed: {0}")]
+    Status(Box<tonic::Status>),
+
+    /// A required field is missing in the response.
+    #[error("Missing field in the response: {0}")]
+    MissingField(&'static str),
+
+    /// The queried account is of a type that is not supported.
+    #[error("Unsupported account type: {0}")]
+    UnsupportedAccountType(String),
+
+    /// Minimum gas price returned by the node couldn't be parsed.
+    #[error("Invalid minimum gas price: {0}")]
+    InvalidGasPrice(String),
+
+    /// Decoding of the protobuf message failed.
+    #[error(transparent)]
+    Decode(#[from] prost::DecodeError),
+}
+
+impl From<tonic::Status> for Error {
+    fn from(status: tonic::Status) -> Self {
+        Error::Status(Box::new(status))
+    }
+}
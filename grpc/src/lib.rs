@@ -0,0 +1,11 @@
+#![doc = include_str!("../README.md")]
+
+mod client;
+mod error;
+
+pub use crate::client::GrpcClient;
+pub use crate::error::{Error, Result};
+
+pub use celestia_proto::celestia::blob::v1::Params as BlobParams;
+pub use celestia_proto::cosmos::auth::v1beta1::BaseAccount;
+pub use celestia_proto::cosmos::tx::v1beta1::BroadcastMode;
@@ -1,17 +1,24 @@
 //! A browser compatible wrappers for the [`lumina-node`].
 
+use std::cell::Cell;
+use std::rc::Rc;
 use std::result::Result as StdResult;
+use std::time::Duration;
 
 use celestia_types::{hash::Hash, ExtendedHeader};
-use js_sys::Array;
-use libp2p::identity::Keypair;
+use js_sys::{Array, Function};
 use libp2p::multiaddr::Protocol;
-use lumina_node::network::{canonical_network_bootnodes, network_genesis, network_id};
-use lumina_node::node::{Node, NodeConfig};
-use lumina_node::store::{IndexedDbStore, Store};
+use lumina_node::clock::DEFAULT_MAX_CLOCK_DRIFT;
+use lumina_node::events::RecvError;
+use lumina_node::network::{
+    self, canonical_network_bootnodes, network_genesis, network_id, CustomNetwork,
+};
+use lumina_node::node::{Node, NodeBuilder};
+use lumina_node::store::{BlockstoreQuota, IndexedDbStore, Store};
 use serde_wasm_bindgen::{from_value, to_value};
-use tracing::info;
+use tracing::{error, info, warn};
 use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::spawn_local;
 
 use crate::utils::js_value_from_display;
 use crate::utils::JsContext;
@@ -23,17 +30,47 @@ use crate::Result;
 #[wasm_bindgen(js_name = Node)]
 struct WasmNode(Node<IndexedDbStore>);
 
+/// Registration of a callback for the node events, returned by `Node.add_event_listener`.
+#[wasm_bindgen]
+pub struct EventListener {
+    active: Rc<Cell<bool>>,
+}
+
+#[wasm_bindgen]
+impl EventListener {
+    /// Stop calling the callback. Takes effect before the next event is delivered.
+    pub fn remove(&self) {
+        self.active.set(false);
+    }
+}
+
 /// Config for the lumina wasm node.
 #[wasm_bindgen(js_name = NodeConfig)]
 pub struct WasmNodeConfig {
     /// A network to connect to.
     pub network: Network,
+    /// Id of a custom network to connect to. If set, it's used instead of the `network`
+    /// together with the `genesis_hash` and `bootnodes`.
+    #[wasm_bindgen(getter_with_clone)]
+    pub network_id: Option<String>,
     /// Hash of the genesis block in the network.
     #[wasm_bindgen(getter_with_clone)]
     pub genesis_hash: Option<String>,
     /// A list of bootstrap peers to connect to.
     #[wasm_bindgen(getter_with_clone)]
     pub bootnodes: Vec<String>,
+    /// A list of circuit relays, including their peer ids, on which to reserve a slot
+    /// so that the other peers can reach the node through them.
+    #[wasm_bindgen(getter_with_clone)]
+    pub relays: Vec<String>,
+    /// Maximum size of the blocks kept in the browser storage, in bytes.
+    pub blockstore_max_size: u64,
+    /// Size of the blocks left in the browser storage after evicting the least recently
+    /// used ones, in bytes.
+    pub blockstore_target_size: u64,
+    /// Maximum number of seconds by which the received headers can be ahead of
+    /// the local clock.
+    pub max_clock_drift_secs: u32,
 }
 
 #[wasm_bindgen(js_class = Node)]
@@ -41,15 +78,10 @@ impl WasmNode {
     /// Create a new Lumina node.
     #[wasm_bindgen(constructor)]
     pub async fn new(config: WasmNodeConfig) -> Result<WasmNode> {
-        let config = config.into_node_config().await?;
-
-        if let Ok(store_height) = config.store.head_height().await {
-            info!("Initialised store with head height: {store_height}");
-        } else {
-            info!("Initialized new empty store");
-        }
+        let builder = config.into_node_builder().await?;
 
-        let node = Node::new(config)
+        let node = builder
+            .start()
             .await
             .js_context("Failed to start the node")?;
 
@@ -77,6 +109,63 @@ impl WasmNode {
         Ok(self.0.wait_connected_trusted().await?)
     }
 
+    /// Wait until the node is connected to at least 1 peer, failing after `timeout_ms`.
+    pub async fn wait_connected_timeout(&self, timeout_ms: u32) -> Result<()> {
+        let timeout = Duration::from_millis(timeout_ms.into());
+        Ok(self.0.wait_connected_timeout(timeout).await?)
+    }
+
+    /// Wait until the node is connected to at least 1 trusted peer, failing after `timeout_ms`.
+    pub async fn wait_connected_trusted_timeout(&self, timeout_ms: u32) -> Result<()> {
+        let timeout = Duration::from_millis(timeout_ms.into());
+        Ok(self.0.wait_connected_trusted_timeout(timeout).await?)
+    }
+
+    /// Call the `callback` with each event generated by the node from now on.
+    ///
+    /// The callback receives an object with the `event` and the `time` it was
+    /// generated at. The `event` is tagged with its `type`, e.g. `peer_connected`,
+    /// `added_header_from_header_sub`, `fetching_headers_finished` or
+    /// `sampling_finished`, and carries the fields specific to it.
+    pub fn add_event_listener(&self, callback: Function) -> EventListener {
+        let mut subscriber = self.0.event_subscriber();
+        let active = Rc::new(Cell::new(true));
+        let listener = EventListener {
+            active: active.clone(),
+        };
+
+        spawn_local(async move {
+            loop {
+                let info = match subscriber.recv().await {
+                    Ok(info) => info,
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("Event listener lagged, {skipped} events skipped");
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+
+                if !active.get() {
+                    break;
+                }
+
+                let event = match to_value(&info) {
+                    Ok(event) => event,
+                    Err(e) => {
+                        error!("Failed to convert the event: {e}");
+                        continue;
+                    }
+                };
+
+                if let Err(e) = callback.call1(&JsValue::NULL, &event) {
+                    error!("Event listener failed: {e:?}");
+                }
+            }
+        });
+
+        listener
+    }
+
     /// Get current network info.
     pub async fn network_info(&self) -> Result<NetworkInfo> {
         Ok(self.0.network_info().await?.into())
@@ -142,18 +231,40 @@ impl WasmNode {
             .collect::<StdResult<_, _>>()?)
     }
 
+    /// Get the number and the total size of the blocks kept in the browser storage.
+    pub fn storage_usage(&self) -> Result<JsValue> {
+        let usage = self.0.store().blockstore_usage();
+        Ok(to_value(&usage)?)
+    }
+
     /// Get current header syncing info.
     pub async fn syncer_info(&self) -> Result<JsValue> {
         let syncer_info = self.0.syncer_info().await?;
         Ok(to_value(&syncer_info)?)
     }
 
+    /// Get the detailed state of the header synchronization.
+    pub async fn syncer_state(&self) -> Result<JsValue> {
+        let syncer_state = self.0.syncer_state().await?;
+        Ok(to_value(&syncer_state)?)
+    }
+
+    /// Wait until the header at the given height is synchronized to the store.
+    pub async fn await_synced(&self, height: u64) -> Result<()> {
+        Ok(self.0.await_synced(height).await?)
+    }
+
     /// Get the latest header announced in the network.
     pub fn get_network_head_header(&self) -> Result<JsValue> {
         let maybe_head_hedaer = self.0.get_network_head_header();
         Ok(to_value(&maybe_head_hedaer)?)
     }
 
+    /// Get the height of the latest header announced in the network.
+    pub fn get_network_head_height(&self) -> Option<u64> {
+        self.0.get_network_head_height()
+    }
+
     /// Get the latest locally synced header.
     pub async fn get_local_head_header(&self) -> Result<JsValue> {
         let local_head = self.0.get_local_head_header().await?;
@@ -202,38 +313,65 @@ impl WasmNode {
 impl WasmNodeConfig {
     /// Get the configuration with default bootnodes and genesis hash for provided network
     pub fn default(network: Network) -> WasmNodeConfig {
+        let blockstore_quota = BlockstoreQuota::default();
+
         WasmNodeConfig {
             network,
-            genesis_hash: network_genesis(network.into()).map(|h| h.to_string()),
-            bootnodes: canonical_network_bootnodes(network.into())
+            network_id: None,
+            genesis_hash: network_genesis(&network.into()).map(|h| h.to_string()),
+            bootnodes: canonical_network_bootnodes(&network.into())
                 .filter(|addr| addr.iter().any(|proto| proto == Protocol::WebTransport))
                 .map(|addr| addr.to_string())
                 .collect::<Vec<_>>(),
+            relays: Vec::new(),
+            blockstore_max_size: blockstore_quota.max_size,
+            blockstore_target_size: blockstore_quota.target_size,
+            max_clock_drift_secs: DEFAULT_MAX_CLOCK_DRIFT.as_secs() as u32,
         }
     }
 
-    async fn into_node_config(self) -> Result<NodeConfig<IndexedDbStore>> {
-        let network_id = network_id(self.network.into());
-        let store = IndexedDbStore::new(network_id)
-            .await
-            .js_context("Failed to open the store")?;
-
-        let p2p_local_keypair = Keypair::generate_ed25519();
-
+    async fn into_node_builder(self) -> Result<NodeBuilder<IndexedDbStore>> {
         let genesis_hash = self.genesis_hash.map(|h| h.parse()).transpose()?;
-        let p2p_bootnodes = self
+        let bootnodes = self
             .bootnodes
             .iter()
             .map(|addr| addr.parse())
-            .collect::<StdResult<_, _>>()?;
-
-        Ok(NodeConfig {
-            network_id: network_id.to_string(),
-            genesis_hash,
-            p2p_bootnodes,
-            p2p_local_keypair,
-            p2p_listen_on: vec![],
-            store,
-        })
+            .collect::<StdResult<Vec<_>, _>>()?;
+        let relay_addrs = self
+            .relays
+            .iter()
+            .map(|addr| addr.parse())
+            .collect::<StdResult<Vec<_>, _>>()?;
+
+        let network = match self.network_id {
+            Some(id) => network::Network::Custom(CustomNetwork {
+                id,
+                genesis_hash,
+                bootnodes: bootnodes.clone(),
+            }),
+            None => self.network.into(),
+        };
+
+        let store = IndexedDbStore::new(network_id(&network))
+            .await
+            .js_context("Failed to open the store")?
+            .with_blockstore_quota(BlockstoreQuota {
+                max_size: self.blockstore_max_size,
+                target_size: self.blockstore_target_size,
+            });
+
+        if let Ok(store_height) = store.head_height().await {
+            info!("Initialised store with head height: {store_height}");
+        } else {
+            info!("Initialized new empty store");
+        }
+
+        Ok(Node::builder()
+            .network(network)
+            .genesis_hash(genesis_hash)
+            .bootnodes(bootnodes)
+            .relay_addrs(relay_addrs)
+            .max_clock_drift(Duration::from_secs(self.max_clock_drift_secs.into()))
+            .store(store))
     }
 }
@@ -44,6 +44,14 @@ pub fn setup_logging() {
         .init();
 }
 
+/// Initialize the pool of the Web Workers in which the headers and the shwap containers
+/// are verified, exported to javascript as `initThreadPool`.
+///
+/// It must be awaited before the node is started. Without the wasm threads support, the
+/// verification runs on the main thread, in between the other tasks.
+#[cfg(all(feature = "rayon", target_feature = "atomics"))]
+pub use wasm_bindgen_rayon::init_thread_pool;
+
 impl From<Network> for network::Network {
     fn from(network: Network) -> network::Network {
         match network {
@@ -55,13 +63,19 @@ impl From<Network> for network::Network {
     }
 }
 
-impl From<network::Network> for Network {
-    fn from(network: network::Network) -> Network {
+impl TryFrom<network::Network> for Network {
+    type Error = JsError;
+
+    fn try_from(network: network::Network) -> Result<Network, Self::Error> {
         match network {
-            network::Network::Mainnet => Network::Mainnet,
-            network::Network::Arabica => Network::Arabica,
-            network::Network::Mocha => Network::Mocha,
-            network::Network::Private => Network::Private,
+            network::Network::Mainnet => Ok(Network::Mainnet),
+            network::Network::Arabica => Ok(Network::Arabica),
+            network::Network::Mocha => Ok(Network::Mocha),
+            network::Network::Private => Ok(Network::Private),
+            network::Network::Custom(custom) => Err(JsError::new(&format!(
+                "Custom network {} has no preset",
+                custom.id
+            ))),
         }
     }
 }
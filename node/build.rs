@@ -0,0 +1,14 @@
+use cfg_aliases::cfg_aliases;
+
+fn main() {
+    cfg_aliases! {
+        // Computations can be moved to the rayon thread pool, which in the browser
+        // is backed by the Web Workers and requires the wasm threads support
+        rayon_pool: {
+            all(
+                feature = "rayon",
+                any(not(target_arch = "wasm32"), target_feature = "atomics")
+            )
+        },
+    }
+}
@@ -0,0 +1,385 @@
+//! Addresses of the known peers, persisted in the [`Store`] to bootstrap the node
+//! after a restart without depending solely on the bootnodes.
+//!
+//! [`Store`]: crate::store::Store
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use celestia_tendermint::Time;
+use libp2p::{multiaddr::Protocol, Multiaddr, PeerId};
+
+use crate::store::PeerAddr;
+
+/// Time after which an address of a peer that wasn't seen again is evicted.
+const ADDR_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+/// Maximum number of the addresses kept for a single peer.
+const MAX_ADDRS_PER_PEER: usize = 8;
+/// Maximum number of the peers kept, the least recently seen ones are evicted first.
+const MAX_PEERS: usize = 128;
+
+/// Book of the addresses on which the known peers of our network were successfully dialed.
+///
+/// Listen addresses that the peers report about themselves over identify are never
+/// recorded, as nothing guarantees that they are reachable or even belong to the peer.
+#[derive(Debug, Default)]
+pub(crate) struct AddressBook {
+    peers: HashMap<PeerId, Vec<SeenAddr>>,
+    // Addresses dialed since the peer was last identified
+    dialed: HashMap<PeerId, Vec<Multiaddr>>,
+    changed: bool,
+}
+
+#[derive(Debug, Clone)]
+struct SeenAddr {
+    // Without the `/p2p/<peer id>` suffix
+    addr: Multiaddr,
+    last_seen: Time,
+}
+
+impl AddressBook {
+    /// Create the book from the addresses persisted in the store, skipping the stale ones.
+    pub(crate) fn from_persisted(addrs: Vec<PeerAddr>, now: Time) -> Self {
+        let mut book = AddressBook::default();
+
+        for PeerAddr {
+            mut addr,
+            last_seen,
+        } in addrs
+        {
+            let Some(Protocol::P2p(peer_id)) = addr.pop() else {
+                continue;
+            };
+
+            let peer_addrs = book.peers.entry(peer_id).or_default();
+            if peer_addrs.len() < MAX_ADDRS_PER_PEER {
+                peer_addrs.push(SeenAddr { addr, last_seen });
+            }
+        }
+
+        book.evict_stale(now);
+        book.changed = false;
+        book
+    }
+
+    /// Remember the address on which the peer was successfully dialed.
+    ///
+    /// It's recorded only once the peer is identified as a peer of our network with
+    /// [`AddressBook::on_identified`]. Addresses that can't be dialed from the other hosts,
+    /// like the loopback ones, are skipped.
+    pub(crate) fn on_dialed(&mut self, peer_id: PeerId, addr: &Multiaddr) {
+        let Some(addr) = without_peer_id(addr, peer_id).filter(is_dialable) else {
+            return;
+        };
+
+        let dialed = self.dialed.entry(peer_id).or_default();
+        if !dialed.contains(&addr) {
+            dialed.push(addr);
+        }
+    }
+
+    /// Record the addresses on which the peer, identified as a peer of our network,
+    /// was dialed, keeping the most recently seen ones.
+    pub(crate) fn on_identified(&mut self, peer_id: PeerId, now: Time) {
+        let Some(dialed) = self.dialed.remove(&peer_id) else {
+            return;
+        };
+
+        let addrs = self.peers.entry(peer_id).or_default();
+
+        for addr in dialed {
+            match addrs.iter_mut().find(|seen| seen.addr == addr) {
+                Some(seen) => seen.last_seen = now,
+                None => addrs.push(SeenAddr {
+                    addr,
+                    last_seen: now,
+                }),
+            }
+        }
+
+        if addrs.len() > MAX_ADDRS_PER_PEER {
+            addrs.sort_by_key(|seen| Reverse(seen.last_seen));
+            addrs.truncate(MAX_ADDRS_PER_PEER);
+        }
+
+        self.changed = true;
+
+        if self.peers.len() > MAX_PEERS {
+            self.evict_least_recently_seen();
+        }
+    }
+
+    /// Forget the addresses dialed since the peer was last identified, e.g. after it
+    /// disconnected before identifying.
+    pub(crate) fn forget_dialed(&mut self, peer_id: PeerId) {
+        self.dialed.remove(&peer_id);
+    }
+
+    /// Remove the address of the peer, e.g. after dialing it failed.
+    pub(crate) fn remove_addr(&mut self, peer_id: PeerId, addr: &Multiaddr) {
+        let Some(addr) = without_peer_id(addr, peer_id) else {
+            return;
+        };
+        let Some(addrs) = self.peers.get_mut(&peer_id) else {
+            return;
+        };
+
+        let len = addrs.len();
+        addrs.retain(|seen| seen.addr != addr);
+
+        if addrs.len() != len {
+            if addrs.is_empty() {
+                self.peers.remove(&peer_id);
+            }
+            self.changed = true;
+        }
+    }
+
+    /// Remove all the addresses of the peer, e.g. after it got banned.
+    pub(crate) fn remove_peer(&mut self, peer_id: PeerId) {
+        self.dialed.remove(&peer_id);
+
+        if self.peers.remove(&peer_id).is_some() {
+            self.changed = true;
+        }
+    }
+
+    /// Evict the addresses that weren't seen within the last [`ADDR_TTL`].
+    pub(crate) fn evict_stale(&mut self, now: Time) {
+        let Some(cutoff) = now.checked_sub(ADDR_TTL) else {
+            return;
+        };
+
+        self.peers.retain(|_, addrs| {
+            let len = addrs.len();
+            addrs.retain(|seen| seen.last_seen >= cutoff);
+
+            if addrs.len() != len {
+                self.changed = true;
+            }
+
+            !addrs.is_empty()
+        });
+    }
+
+    /// Returns the addresses of each known peer, the most recently seen peers first.
+    pub(crate) fn peers(&self) -> Vec<(PeerId, Vec<Multiaddr>)> {
+        let mut peers: Vec<_> = self.peers.iter().collect();
+        peers.sort_by_key(|(_, addrs)| Reverse(last_seen(addrs)));
+
+        peers
+            .into_iter()
+            .map(|(peer_id, addrs)| {
+                let addrs = addrs.iter().map(|seen| seen.addr.clone()).collect();
+                (*peer_id, addrs)
+            })
+            .collect()
+    }
+
+    /// Returns all the addresses to be persisted, if the book changed since the last call.
+    pub(crate) fn take_changes(&mut self) -> Option<Vec<PeerAddr>> {
+        if !self.changed {
+            return None;
+        }
+        self.changed = false;
+
+        let addrs = self
+            .peers
+            .iter()
+            .flat_map(|(peer_id, addrs)| {
+                addrs.iter().map(|seen| PeerAddr {
+                    addr: seen.addr.clone().with(Protocol::P2p(*peer_id)),
+                    last_seen: seen.last_seen,
+                })
+            })
+            .collect();
+
+        Some(addrs)
+    }
+
+    fn evict_least_recently_seen(&mut self) {
+        let oldest = self
+            .peers
+            .iter()
+            .min_by_key(|(_, addrs)| last_seen(addrs))
+            .map(|(peer_id, _)| *peer_id);
+
+        if let Some(peer_id) = oldest {
+            self.remove_peer(peer_id);
+        }
+    }
+}
+
+fn last_seen(addrs: &[SeenAddr]) -> Option<Time> {
+    addrs.iter().map(|seen| seen.last_seen).max()
+}
+
+/// Strip the `/p2p/<peer id>` suffix, returning `None` if it belongs to a different peer.
+fn without_peer_id(addr: &Multiaddr, peer_id: PeerId) -> Option<Multiaddr> {
+    let mut addr = addr.clone();
+
+    match addr.iter().last() {
+        Some(Protocol::P2p(id)) if id == peer_id => {
+            addr.pop();
+            Some(addr)
+        }
+        Some(Protocol::P2p(_)) => None,
+        _ => Some(addr),
+    }
+}
+
+fn is_dialable(addr: &Multiaddr) -> bool {
+    match addr.iter().next() {
+        Some(Protocol::Ip4(ip)) => !ip.is_loopback() && !ip.is_unspecified(),
+        Some(Protocol::Ip6(ip)) => !ip.is_loopback() && !ip.is_unspecified(),
+        Some(_) => true,
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(secs: i64) -> Time {
+        Time::from_unix_timestamp(secs, 0).unwrap()
+    }
+
+    fn addr(s: &str) -> Multiaddr {
+        s.parse().unwrap()
+    }
+
+    fn dial_and_identify(book: &mut AddressBook, peer: PeerId, addrs: &[&str], now: Time) {
+        for a in addrs {
+            book.on_dialed(peer, &addr(a));
+        }
+        book.on_identified(peer, now);
+    }
+
+    #[test]
+    fn persisted_round_trip() {
+        let peer = PeerId::random();
+        let mut book = AddressBook::default();
+
+        dial_and_identify(
+            &mut book,
+            peer,
+            &[
+                "/ip4/1.2.3.4/tcp/2121",
+                "/ip4/127.0.0.1/tcp/2121",
+                "/ip4/0.0.0.0/udp/2121/quic-v1",
+            ],
+            time(1000),
+        );
+
+        let persisted = book.take_changes().unwrap();
+        assert_eq!(
+            persisted,
+            vec![PeerAddr {
+                addr: addr("/ip4/1.2.3.4/tcp/2121").with(Protocol::P2p(peer)),
+                last_seen: time(1000),
+            }]
+        );
+        assert!(book.take_changes().is_none());
+
+        let book = AddressBook::from_persisted(persisted, time(2000));
+        assert_eq!(
+            book.peers(),
+            vec![(peer, vec![addr("/ip4/1.2.3.4/tcp/2121")])]
+        );
+    }
+
+    #[test]
+    fn records_only_identified_dialed_addrs() {
+        let peer = PeerId::random();
+        let mut book = AddressBook::default();
+
+        // not recorded until the peer identifies
+        book.on_dialed(peer, &addr("/ip4/1.2.3.4/tcp/2121"));
+        assert!(book.peers().is_empty());
+        assert!(book.take_changes().is_none());
+
+        // forgotten if it disconnects before that
+        book.forget_dialed(peer);
+        book.on_identified(peer, time(1000));
+        assert!(book.peers().is_empty());
+        assert!(book.take_changes().is_none());
+
+        // addresses of another peer are skipped
+        let other = addr("/ip4/1.2.3.4/tcp/2121").with(Protocol::P2p(PeerId::random()));
+        book.on_dialed(peer, &other);
+        book.on_identified(peer, time(1000));
+        assert!(book.peers().is_empty());
+
+        dial_and_identify(&mut book, peer, &["/ip4/1.2.3.4/tcp/2121"], time(1000));
+        dial_and_identify(&mut book, peer, &["/ip4/5.6.7.8/tcp/2121"], time(2000));
+        assert_eq!(
+            book.peers(),
+            vec![(
+                peer,
+                vec![addr("/ip4/1.2.3.4/tcp/2121"), addr("/ip4/5.6.7.8/tcp/2121")]
+            )]
+        );
+
+        book.remove_addr(peer, &addr("/ip4/5.6.7.8/tcp/2121"));
+        book.remove_addr(peer, &addr("/ip4/1.2.3.4/tcp/2121"));
+        assert!(book.peers().is_empty());
+    }
+
+    #[test]
+    fn keeps_most_recently_seen_addrs() {
+        let peer = PeerId::random();
+        let mut book = AddressBook::default();
+
+        for n in 0..=MAX_ADDRS_PER_PEER {
+            let a = format!("/ip4/1.2.3.4/tcp/{}", 2000 + n);
+            dial_and_identify(&mut book, peer, &[&a], time(n as i64));
+        }
+
+        let (_, addrs) = &book.peers()[0];
+        assert_eq!(addrs.len(), MAX_ADDRS_PER_PEER);
+        assert!(!addrs.contains(&addr("/ip4/1.2.3.4/tcp/2000")));
+    }
+
+    #[test]
+    fn evicts_stale_addrs() {
+        let peer1 = PeerId::random();
+        let peer2 = PeerId::random();
+        let mut book = AddressBook::default();
+
+        dial_and_identify(&mut book, peer1, &["/ip4/1.2.3.4/tcp/2121"], time(1000));
+        dial_and_identify(&mut book, peer2, &["/ip4/5.6.7.8/tcp/2121"], time(2000));
+        assert_eq!(book.peers()[0].0, peer2);
+
+        let persisted = book.take_changes().unwrap();
+        let now = time(1500 + ADDR_TTL.as_secs() as i64);
+
+        let mut book = AddressBook::from_persisted(persisted, now);
+        assert_eq!(
+            book.peers(),
+            vec![(peer2, vec![addr("/ip4/5.6.7.8/tcp/2121")])]
+        );
+        assert!(book.take_changes().is_none());
+
+        book.evict_stale(time(2500 + ADDR_TTL.as_secs() as i64));
+        assert!(book.peers().is_empty());
+        assert_eq!(book.take_changes().unwrap(), Vec::new());
+    }
+
+    #[test]
+    fn evicts_least_recently_seen_peer() {
+        let mut book = AddressBook::default();
+        let first = PeerId::random();
+
+        dial_and_identify(&mut book, first, &["/ip4/1.2.3.4/tcp/2121"], time(0));
+        for n in 1..=MAX_PEERS {
+            let peer = PeerId::random();
+            dial_and_identify(&mut book, peer, &["/ip4/1.2.3.4/tcp/2121"], time(n as i64));
+        }
+
+        let peers = book.peers();
+        assert_eq!(peers.len(), MAX_PEERS);
+        assert!(peers.iter().all(|(peer, _)| *peer != first));
+    }
+}
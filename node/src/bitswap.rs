@@ -0,0 +1,1624 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use blockstore::Blockstore;
+#[cfg(not(target_arch = "wasm32"))]
+use blockstore::BlockstoreError;
+use celestia_proto::bitswap::message::pb::message::{
+    wantlist::{Entry as WantlistEntry, WantType},
+    Block as RawBlock, BlockPresence, BlockPresenceType, Wantlist,
+};
+use celestia_proto::bitswap::message::pb::Message;
+use celestia_proto::share::p2p::shwap::{
+    Data as RawNamespacedData, Row as RawRow, Sample as RawSample,
+};
+use celestia_types::namespaced_data::NAMESPACED_DATA_ID_MULTIHASH_CODE;
+use celestia_types::row::{IdLayout, RowId, ROW_ID_MULTIHASH_CODE};
+use celestia_types::sample::{SampleId, SAMPLE_ID_MULTIHASH_CODE};
+use celestia_types::shwap::{detect_id_layout, ShwapCid};
+use celestia_types::ErrorKind;
+use cid::CidGeneric;
+use futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use instant::{Duration, Instant};
+use libp2p::{
+    request_response::{self, Codec, OutboundFailure, OutboundRequestId, ProtocolSupport},
+    PeerId, StreamProtocol,
+};
+use multihash::Multihash;
+use prost::Message as _;
+use tokio::sync::mpsc;
+use tracing::{debug, instrument, trace, warn};
+
+use crate::executor::timeout;
+use crate::metrics;
+use crate::p2p::P2pError;
+use crate::peer_tracker::{Misbehavior, PeerTracker};
+
+/// Maximum size of the multihash in the [`Cid`]s handled by bitswap.
+pub(crate) const MAX_MH_SIZE: usize = 64;
+/// Size limit of a single bitswap message in bytes
+const MESSAGE_SIZE_LIMIT: usize = 4 * 1024 * 1024;
+/// Time limit on reading/writing a message
+const MESSAGE_TIME_LIMIT: Duration = Duration::from_secs(10);
+/// Maximum number of peers asked for a single block
+pub(crate) const MAX_PEERS: usize = 10;
+/// Maximum number of the session peers asked for a block before asking the others
+const MAX_SESSION_PEERS: usize = 3;
+/// Time after which the session of a height is dropped if none of its blocks is wanted
+const SESSION_TTL: Duration = Duration::from_secs(60);
+/// Time the asked peers have to respond to a want before the fallback peers are asked
+const PEER_RESPONSE_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub(crate) type Cid = CidGeneric<MAX_MH_SIZE>;
+pub(crate) type BitswapBehaviour = request_response::Behaviour<BitswapCodec>;
+pub(crate) type BitswapEvent = request_response::Event<Message, ()>;
+pub(crate) type BitswapMessage = request_response::Message<Message, ()>;
+/// Sender of the blocks delivered for a want, see [`block_channel`].
+pub(crate) type BlockSender = mpsc::Sender<Result<ReceivedBlock, P2pError>>;
+/// Receiver of the blocks delivered for a want, see [`block_channel`].
+pub(crate) type BlockReceiver = mpsc::Receiver<Result<ReceivedBlock, P2pError>>;
+
+/// Block delivered by a peer for one of the wants, not verified yet.
+#[derive(Debug)]
+pub(crate) struct ReceivedBlock {
+    /// Peer which sent the block.
+    pub(crate) peer: PeerId,
+    /// Data of the block, in the [`IdLayout`] of the client.
+    pub(crate) data: Vec<u8>,
+}
+
+/// Create the channel over which the blocks of a want are delivered.
+///
+/// Blocks of all the peers are delivered as they arrive. The want stays open until
+/// one of them is accepted with [`BitswapClient::accept`], or all of them are rejected
+/// with [`BitswapClient::reject`] and there are no other peers to ask, in which case
+/// the error is delivered instead.
+pub(crate) fn block_channel() -> (BlockSender, BlockReceiver) {
+    mpsc::channel(MAX_PEERS)
+}
+
+/// Representation of all the errors that can occur when interacting with the bitswap.
+#[derive(Debug, thiserror::Error)]
+pub enum BitswapError {
+    /// None of the asked peers has the requested block.
+    #[error("Block not found")]
+    BlockNotFound,
+
+    /// CID is longer than supported by bitswap.
+    #[error("CID too long")]
+    CidTooLong,
+}
+
+impl BitswapError {
+    /// Get the [`ErrorKind`] of the error.
+    pub fn kind(&self) -> ErrorKind {
+        self.code_and_kind().1
+    }
+
+    /// Get the stable code of the error, see [`celestia_types::Error::code`].
+    ///
+    /// Errors of the bitswap use the codes from `2201` to `2299`.
+    pub fn code(&self) -> u16 {
+        self.code_and_kind().0
+    }
+
+    fn code_and_kind(&self) -> (u16, ErrorKind) {
+        match self {
+            BitswapError::BlockNotFound => (2201, ErrorKind::P2p),
+            BitswapError::CidTooLong => (2202, ErrorKind::Encoding),
+        }
+    }
+}
+
+/// Create the bitswap behaviour speaking the given protocols, from the preferred one.
+pub(crate) fn new_behaviour<I>(protocols: I) -> BitswapBehaviour
+where
+    I: IntoIterator<Item = StreamProtocol>,
+{
+    let protocols = protocols
+        .into_iter()
+        .map(|protocol| (protocol, ProtocolSupport::Full));
+
+    BitswapBehaviour::new(protocols, request_response::Config::default())
+}
+
+/// Convert a [`CidGeneric`] of any size to the one used by bitswap.
+pub(crate) fn convert_cid<const S: usize>(cid: &CidGeneric<S>) -> Result<Cid, BitswapError> {
+    let hash = Multihash::<MAX_MH_SIZE>::wrap(cid.hash().code(), cid.hash().digest())
+        .map_err(|_| BitswapError::CidTooLong)?;
+    Ok(CidGeneric::new_v1(cid.codec(), hash))
+}
+
+pub(crate) trait MessageSender {
+    fn send_message(&mut self, peer: &PeerId, message: Message) -> OutboundRequestId;
+}
+
+impl MessageSender for BitswapBehaviour {
+    fn send_message(&mut self, peer: &PeerId, message: Message) -> OutboundRequestId {
+        self.send_request(peer, message)
+    }
+}
+
+/// Client side of the bitswap, tracking the blocks we want from the peers.
+///
+/// Wants are tracked with the [`Cid`]s in the `id_layout` of the client. Each peer is
+/// asked with the [`IdLayout`] of the first shwap version it speaks, and the blocks it
+/// sends are re-encoded back to the layout of the client.
+///
+/// Concurrent wants of the same [`Cid`] are merged into one, and the wants requested
+/// together are sent to each peer in a single message. Blocks are handed to the
+/// requesters unverified and the want stays open until they accept one of them, so
+/// a peer sending an invalid block can't fail the want. Peers whose blocks of a height
+/// were accepted form its session: the following wants of that height are sent only
+/// to them, and the other peers are asked only if none of them has the block.
+pub(crate) struct BitswapClient {
+    wants: HashMap<Cid, Want>,
+    reqs: HashMap<OutboundRequestId, (PeerId, Vec<Cid>)>,
+    sessions: HashMap<u64, Session>,
+    peer_tracker: Arc<PeerTracker>,
+    id_layout: IdLayout,
+    versions: Vec<(StreamProtocol, IdLayout)>,
+}
+
+struct Want {
+    /// Peers asked for the block, which didn't respond yet.
+    peers: HashSet<PeerId>,
+    /// Peers asked if none of the `peers` has the block.
+    fallback: Vec<PeerId>,
+    /// When the `peers` were asked.
+    asked_at: Instant,
+    /// Blocks delivered by the peers, waiting for the verdict of the requesters.
+    verifying: HashMap<PeerId, Vec<u8>>,
+    respond_to: Vec<BlockSender>,
+}
+
+impl Want {
+    /// Hand the block over to all the requesters, until they accept or reject it.
+    fn deliver(&mut self, peer: PeerId, data: Vec<u8>) {
+        for respond_to in &self.respond_to {
+            let block = ReceivedBlock {
+                peer,
+                data: data.clone(),
+            };
+            // Channel is big enough for a block of each asked peer
+            let _ = respond_to.try_send(Ok(block));
+        }
+
+        self.verifying.insert(peer, data);
+    }
+
+    /// Let all the requesters know that none of the peers has the block.
+    fn fail(self) {
+        for respond_to in self.respond_to {
+            let _ = respond_to.try_send(Err(BitswapError::BlockNotFound.into()));
+        }
+    }
+}
+
+/// Peers which delivered the blocks of a height, the most recent first.
+struct Session {
+    peers: VecDeque<PeerId>,
+    last_used: Instant,
+}
+
+/// [`Cid`]s to send to each of the peers in a single message.
+type Batch = HashMap<PeerId, Vec<Cid>>;
+
+impl BitswapClient {
+    pub(crate) fn new(
+        peer_tracker: Arc<PeerTracker>,
+        id_layout: IdLayout,
+        versions: Vec<(StreamProtocol, IdLayout)>,
+    ) -> Self {
+        BitswapClient {
+            wants: HashMap::new(),
+            reqs: HashMap::new(),
+            sessions: HashMap::new(),
+            peer_tracker,
+            id_layout,
+            versions,
+        }
+    }
+
+    /// Layout of the shwap identifiers of the blocks kept by the client.
+    pub(crate) fn id_layout(&self) -> IdLayout {
+        self.id_layout
+    }
+
+    /// Layout of the shwap identifiers spoken by the peer.
+    ///
+    /// Peers which don't speak any of the versions, e.g. the ones not identified yet,
+    /// are assumed to use the layout of the client.
+    pub(crate) fn peer_layout(&self, peer: &PeerId) -> IdLayout {
+        self.versions
+            .iter()
+            .find(|(protocol, _)| self.peer_tracker.supports_protocol(*peer, protocol))
+            .map_or(self.id_layout, |(_, layout)| *layout)
+    }
+
+    /// Height of the block the shwap [`Cid`] belongs to.
+    fn block_height(&self, cid: &Cid) -> Option<u64> {
+        ShwapCid::from_cid(cid, self.id_layout)
+            .ok()
+            .map(|id| id.block_height())
+    }
+
+    /// Ask given peers for a block with the given [`Cid`].
+    #[instrument(level = "trace", skip(self, sender, peers, respond_to))]
+    pub(crate) fn get<S>(
+        &mut self,
+        sender: &mut S,
+        cid: Cid,
+        peers: Vec<PeerId>,
+        respond_to: BlockSender,
+    ) where
+        S: MessageSender,
+    {
+        self.get_many(sender, vec![(cid, respond_to)], peers);
+    }
+
+    /// Ask given peers for the blocks with the given [`Cid`]s, in a single message
+    /// per peer.
+    #[instrument(level = "trace", skip_all)]
+    pub(crate) fn get_many<S>(
+        &mut self,
+        sender: &mut S,
+        wants: Vec<(Cid, BlockSender)>,
+        peers: Vec<PeerId>,
+    ) where
+        S: MessageSender,
+    {
+        let mut batch = Batch::new();
+
+        for (cid, respond_to) in wants {
+            if let Some(want) = self.wants.get_mut(&cid) {
+                // Block is already requested, just wait for it, including the blocks
+                // delivered before
+                for (peer, data) in &want.verifying {
+                    let block = ReceivedBlock {
+                        peer: *peer,
+                        data: data.clone(),
+                    };
+                    let _ = respond_to.try_send(Ok(block));
+                }
+                want.respond_to.push(respond_to);
+                continue;
+            }
+
+            if peers.is_empty() {
+                let _ = respond_to.try_send(Err(P2pError::NoConnectedPeers));
+                continue;
+            }
+
+            let (asked, fallback) = self.select_peers(&cid, &peers);
+
+            for peer in &asked {
+                batch.entry(*peer).or_default().push(cid);
+            }
+
+            self.wants.insert(
+                cid,
+                Want {
+                    peers: asked.into_iter().collect(),
+                    fallback,
+                    asked_at: Instant::now(),
+                    verifying: HashMap::new(),
+                    respond_to: vec![respond_to],
+                },
+            );
+        }
+
+        self.send_wants(sender, batch);
+        trace!("Wants sent");
+    }
+
+    /// Pick the peers asked for the block and the ones asked if they don't have it.
+    ///
+    /// Peers of the height's session are asked first, all the others only if there
+    /// are none of them connected.
+    fn select_peers(&mut self, cid: &Cid, peers: &[PeerId]) -> (Vec<PeerId>, Vec<PeerId>) {
+        let Some(height) = self.block_height(cid) else {
+            return (peers.iter().take(MAX_PEERS).copied().collect(), Vec::new());
+        };
+
+        let session = self.sessions.entry(height).or_insert_with(|| Session {
+            peers: VecDeque::new(),
+            last_used: Instant::now(),
+        });
+        session.last_used = Instant::now();
+
+        let asked: Vec<_> = session
+            .peers
+            .iter()
+            .filter(|peer| peers.contains(peer))
+            .take(MAX_SESSION_PEERS)
+            .copied()
+            .collect();
+
+        if asked.is_empty() {
+            return (peers.iter().take(MAX_PEERS).copied().collect(), Vec::new());
+        }
+
+        let fallback = peers
+            .iter()
+            .filter(|peer| !asked.contains(peer))
+            .take(MAX_PEERS - asked.len())
+            .copied()
+            .collect();
+
+        (asked, fallback)
+    }
+
+    /// Accept the block delivered by the peer as the answer to the want, once it was
+    /// verified by the requester, and withdraw the want from the other peers.
+    pub(crate) fn accept<S>(&mut self, sender: &mut S, cid: &Cid, peer: PeerId)
+    where
+        S: MessageSender,
+    {
+        let Some(want) = self.wants.remove(cid) else {
+            return;
+        };
+
+        self.add_session_peer(cid, peer);
+
+        // Let other peers know that we are not interested anymore
+        let mut cancels = Batch::new();
+        for other in want.peers {
+            cancels.entry(other).or_default().push(*cid);
+        }
+        self.send_cancels(sender, cancels);
+    }
+
+    /// Reject the block delivered by the peer, which failed the verification of the
+    /// requester, and ask the remaining peers instead.
+    pub(crate) fn reject<S>(&mut self, sender: &mut S, cid: &Cid, peer: PeerId)
+    where
+        S: MessageSender,
+    {
+        let Some(want) = self.wants.get_mut(cid) else {
+            return;
+        };
+
+        // Each of the requesters reports the same block
+        if want.verifying.remove(&peer).is_none() {
+            return;
+        }
+
+        debug!("Received invalid block {cid} from {peer}");
+        self.peer_tracker.penalize(peer, Misbehavior::InvalidBlock);
+        self.remove_session_peer(cid, &peer);
+
+        let mut wants = Batch::new();
+        self.remove_peer_from_want(cid, &peer, &mut wants);
+        self.send_wants(sender, wants);
+    }
+
+    /// Remember that the peer delivered a valid block of the height.
+    fn add_session_peer(&mut self, cid: &Cid, peer: PeerId) {
+        let Some(height) = self.block_height(cid) else {
+            return;
+        };
+        let Some(session) = self.sessions.get_mut(&height) else {
+            return;
+        };
+
+        session.peers.retain(|p| *p != peer);
+        session.peers.push_front(peer);
+        session.peers.truncate(MAX_PEERS);
+    }
+
+    fn remove_session_peer(&mut self, cid: &Cid, peer: &PeerId) {
+        if let Some(session) = self
+            .block_height(cid)
+            .and_then(|height| self.sessions.get_mut(&height))
+        {
+            session.peers.retain(|p| p != peer);
+        }
+    }
+
+    /// Drop the wants that nobody waits for anymore and the expired sessions.
+    ///
+    /// Peers which didn't respond to a want in time are penalized and demoted from the
+    /// session of its height, and the fallback peers are asked instead.
+    pub(crate) fn remove_abandoned<S>(&mut self, sender: &mut S)
+    where
+        S: MessageSender,
+    {
+        self.remove_unresponsive_peers(sender);
+
+        let abandoned: Vec<_> = self
+            .wants
+            .iter_mut()
+            .filter_map(|(cid, want)| {
+                want.respond_to.retain(|tx| !tx.is_closed());
+                want.respond_to.is_empty().then_some(*cid)
+            })
+            .collect();
+
+        let mut cancels = Batch::new();
+        for cid in abandoned {
+            if let Some(want) = self.wants.remove(&cid) {
+                for peer in want.peers {
+                    cancels.entry(peer).or_default().push(cid);
+                }
+            }
+        }
+        self.send_cancels(sender, cancels);
+
+        self.sessions
+            .retain(|_, session| session.last_used.elapsed() < SESSION_TTL);
+    }
+
+    fn remove_unresponsive_peers<S>(&mut self, sender: &mut S)
+    where
+        S: MessageSender,
+    {
+        let timed_out: Vec<_> = self
+            .wants
+            .iter()
+            .filter(|(_, want)| {
+                !want.peers.is_empty() && want.asked_at.elapsed() >= PEER_RESPONSE_TIMEOUT
+            })
+            .map(|(cid, want)| (*cid, want.peers.iter().copied().collect::<Vec<_>>()))
+            .collect();
+
+        let mut cancels = Batch::new();
+        let mut wants = Batch::new();
+
+        for (cid, peers) in timed_out {
+            for peer in peers {
+                debug!("Peer {peer} didn't respond to the want of {cid}");
+                self.peer_tracker.penalize(peer, Misbehavior::FailedRequest);
+                self.remove_session_peer(&cid, &peer);
+                cancels.entry(peer).or_default().push(cid);
+                self.remove_peer_from_want(&cid, &peer, &mut wants);
+            }
+        }
+
+        self.send_cancels(sender, cancels);
+        self.send_wants(sender, wants);
+    }
+
+    #[instrument(level = "trace", skip_all)]
+    pub(crate) fn on_event<S>(&mut self, sender: &mut S, ev: BitswapEvent)
+    where
+        S: MessageSender,
+    {
+        match ev {
+            BitswapEvent::Message {
+                peer,
+                message: BitswapMessage::Request { request, .. },
+            } => {
+                self.on_message(sender, peer, request);
+            }
+            BitswapEvent::Message {
+                message: BitswapMessage::Response { request_id, .. },
+                ..
+            } => {
+                self.reqs.remove(&request_id);
+            }
+            BitswapEvent::OutboundFailure {
+                peer,
+                request_id,
+                error,
+            } => {
+                self.on_failure(sender, peer, request_id, error);
+            }
+            BitswapEvent::InboundFailure { .. } | BitswapEvent::ResponseSent { .. } => {}
+        }
+    }
+
+    fn on_message<S>(&mut self, sender: &mut S, peer: PeerId, message: Message)
+    where
+        S: MessageSender,
+    {
+        let peer_layout = self.peer_layout(&peer);
+        let mut wants = Batch::new();
+
+        for block in message.payload {
+            let Some(peer_cid) = compute_cid(&block, peer_layout) else {
+                debug!("Received block with unsupported CID from {peer}");
+                self.peer_tracker.penalize(peer, Misbehavior::InvalidBlock);
+                continue;
+            };
+
+            let layout = cid_layout(&peer_cid, peer_layout);
+            let cid = translate_cid(&peer_cid, layout, self.id_layout);
+
+            match self.wants.get(&cid) {
+                Some(want) if !want.verifying.contains_key(&peer) => {}
+                _ => {
+                    trace!("Received unwanted block {cid} from {peer}");
+                    continue;
+                }
+            }
+
+            let data = if layout == self.id_layout {
+                block.data
+            } else {
+                match reencode_container(&block.data, &cid, self.id_layout) {
+                    Some(data) => data,
+                    None => {
+                        debug!("Received block {peer_cid} with invalid container from {peer}");
+                        self.peer_tracker.penalize(peer, Misbehavior::InvalidBlock);
+                        continue;
+                    }
+                }
+            };
+
+            // unwrap is safe, we just checked the entry
+            let want = self.wants.get_mut(&cid).unwrap();
+
+            metrics::increment(metrics::BITSWAP_BLOCKS_RECEIVED, 1);
+            want.peers.remove(&peer);
+            want.deliver(peer, data);
+        }
+
+        for presence in message.block_presences {
+            if presence.r#type != BlockPresenceType::DontHave as i32 {
+                continue;
+            }
+
+            let Ok(peer_cid) = Cid::try_from(&presence.cid[..]) else {
+                continue;
+            };
+
+            let layout = cid_layout(&peer_cid, peer_layout);
+            let cid = translate_cid(&peer_cid, layout, self.id_layout);
+            self.remove_session_peer(&cid, &peer);
+            self.remove_peer_from_want(&cid, &peer, &mut wants);
+        }
+
+        self.send_wants(sender, wants);
+    }
+
+    fn on_failure<S>(
+        &mut self,
+        sender: &mut S,
+        peer: PeerId,
+        request_id: OutboundRequestId,
+        error: OutboundFailure,
+    ) where
+        S: MessageSender,
+    {
+        let Some((_, cids)) = self.reqs.remove(&request_id) else {
+            return;
+        };
+
+        debug!("Sending want to {peer} failed: {error}");
+        self.peer_tracker.penalize(peer, Misbehavior::FailedRequest);
+
+        let mut wants = Batch::new();
+        for cid in cids {
+            self.remove_session_peer(&cid, &peer);
+            self.remove_peer_from_want(&cid, &peer, &mut wants);
+        }
+        self.send_wants(sender, wants);
+    }
+
+    /// Send the wants to the peers, in the layouts they speak.
+    fn send_wants<S>(&mut self, sender: &mut S, wants: Batch)
+    where
+        S: MessageSender,
+    {
+        for (peer, cids) in wants {
+            let layout = self.peer_layout(&peer);
+            let peer_cids: Vec<_> = cids
+                .iter()
+                .map(|cid| translate_cid(cid, self.id_layout, layout))
+                .collect();
+
+            let req_id =
+                sender.send_message(&peer, want_message(&peer_cids, WantType::Block, false));
+            self.reqs.insert(req_id, (peer, cids));
+        }
+    }
+
+    /// Withdraw the wants from the peers, in the layouts they speak.
+    fn send_cancels<S>(&self, sender: &mut S, cancels: Batch)
+    where
+        S: MessageSender,
+    {
+        for (peer, cids) in cancels {
+            let layout = self.peer_layout(&peer);
+            let peer_cids: Vec<_> = cids
+                .iter()
+                .map(|cid| translate_cid(cid, self.id_layout, layout))
+                .collect();
+
+            sender.send_message(&peer, want_message(&peer_cids, WantType::Block, true));
+        }
+    }
+
+    /// Stop waiting for the block from the peer, asking the fallback peers once none
+    /// of the asked ones is left and none of the delivered blocks is being verified.
+    fn remove_peer_from_want(&mut self, cid: &Cid, peer: &PeerId, wants: &mut Batch) {
+        let Some(want) = self.wants.get_mut(cid) else {
+            return;
+        };
+
+        want.peers.remove(peer);
+
+        if !want.peers.is_empty() || !want.verifying.is_empty() {
+            return;
+        }
+
+        if !want.fallback.is_empty() {
+            for peer in want.fallback.drain(..) {
+                want.peers.insert(peer);
+                wants.entry(peer).or_default().push(*cid);
+            }
+            want.asked_at = Instant::now();
+            return;
+        }
+
+        // unwrap is safe, we just checked the entry
+        let want = self.wants.remove(cid).unwrap();
+        want.fail();
+    }
+}
+
+/// Build the response to the wants of a peer using the blocks from the blockstore.
+///
+/// Only the shwap containers ([`Row`], [`Sample`] and [`NamespacedData`]) are served.
+/// Wanted blocks are sent if we have them, for the `Have` wants only their presence is
+/// announced. Blocks not fitting in a single message are skipped, the peer will ask
+/// for them again. Returns `None` if there is nothing to respond with.
+///
+/// Blocks are stored with the ids in the `local_layout`. The layout of each wanted
+/// [`Cid`] is detected from its codec, falling back to the `peer_layout` of the protocol
+/// spoken by the peer, and the served containers are re-encoded to it.
+///
+/// [`Row`]: celestia_types::row::Row
+/// [`Sample`]: celestia_types::sample::Sample
+/// [`NamespacedData`]: celestia_types::namespaced_data::NamespacedData
+pub(crate) async fn respond_to_wants<B>(
+    blockstore: &B,
+    message: &Message,
+    local_layout: IdLayout,
+    peer_layout: IdLayout,
+) -> Option<Message>
+where
+    B: Blockstore,
+{
+    let wantlist = message.wantlist.as_ref()?;
+    let mut response = Message::default();
+    let mut response_size = 0;
+
+    for entry in wantlist.entries.iter().filter(|entry| !entry.cancel) {
+        let Ok(peer_cid) = Cid::try_from(&entry.block[..]) else {
+            continue;
+        };
+
+        let layout = cid_layout(&peer_cid, peer_layout);
+        let cid = translate_cid(&peer_cid, layout, local_layout);
+
+        let data = if !is_shwap_cid(&cid) {
+            None
+        } else {
+            match blockstore.get(&cid).await {
+                Ok(Some(data)) if layout != local_layout => {
+                    reencode_container(&data, &peer_cid, layout)
+                }
+                Ok(data) => data,
+                Err(e) => {
+                    warn!("Failed to get block {cid} from the blockstore: {e}");
+                    None
+                }
+            }
+        };
+
+        match data {
+            Some(data) if entry.want_type == WantType::Block as i32 => {
+                let block = RawBlock {
+                    prefix: cid_prefix(&peer_cid),
+                    data,
+                };
+
+                // leave some space for the presences and the encoding overhead
+                let block_size = block.encoded_len();
+                if response_size + block_size > MESSAGE_SIZE_LIMIT / 2 {
+                    continue;
+                }
+
+                response_size += block_size;
+                response.payload.push(block);
+            }
+            Some(_) => response.block_presences.push(BlockPresence {
+                cid: entry.block.clone(),
+                r#type: BlockPresenceType::Have as i32,
+            }),
+            None if entry.send_dont_have => response.block_presences.push(BlockPresence {
+                cid: entry.block.clone(),
+                r#type: BlockPresenceType::DontHave as i32,
+            }),
+            None => {}
+        }
+    }
+
+    if response.payload.is_empty() && response.block_presences.is_empty() {
+        None
+    } else {
+        metrics::increment(
+            metrics::BITSWAP_BLOCKS_SERVED,
+            response.payload.len() as u64,
+        );
+        Some(response)
+    }
+}
+
+/// [`Blockstore`] serving the blocks from the first store, falling back to the second one.
+///
+/// New blocks are put only to the first store.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) struct ChainedBlockstore<'a, A, B>(pub(crate) &'a A, pub(crate) &'a B);
+
+#[cfg(not(target_arch = "wasm32"))]
+#[async_trait]
+impl<A, B> Blockstore for ChainedBlockstore<'_, A, B>
+where
+    A: Blockstore + Sync,
+    B: Blockstore + Sync,
+{
+    async fn get<const S: usize>(
+        &self,
+        cid: &CidGeneric<S>,
+    ) -> Result<Option<Vec<u8>>, BlockstoreError> {
+        match self.0.get(cid).await? {
+            Some(data) => Ok(Some(data)),
+            None => self.1.get(cid).await,
+        }
+    }
+
+    async fn put_keyed<const S: usize>(
+        &self,
+        cid: &CidGeneric<S>,
+        data: &[u8],
+    ) -> Result<(), BlockstoreError> {
+        self.0.put_keyed(cid, data).await
+    }
+
+    async fn remove<const S: usize>(&self, cid: &CidGeneric<S>) -> Result<(), BlockstoreError> {
+        self.0.remove(cid).await
+    }
+}
+
+fn want_message(cids: &[Cid], want_type: WantType, cancel: bool) -> Message {
+    let entries = cids
+        .iter()
+        .map(|cid| WantlistEntry {
+            block: cid.to_bytes(),
+            priority: 1,
+            cancel,
+            want_type: want_type as i32,
+            send_dont_have: true,
+        })
+        .collect();
+
+    Message {
+        wantlist: Some(Wantlist {
+            entries,
+            full: false,
+        }),
+        ..Message::default()
+    }
+}
+
+/// Translate the shwap [`Cid`] between the [`IdLayout`]s.
+///
+/// Other [`Cid`]s are returned unchanged.
+fn translate_cid(cid: &Cid, from: IdLayout, to: IdLayout) -> Cid {
+    if from == to {
+        return *cid;
+    }
+
+    match ShwapCid::from_cid(cid, from) {
+        Ok(id) => id.to_cid(to),
+        Err(_) => *cid,
+    }
+}
+
+/// Replace the identifier embedded in the shwap container with the one of the [`Cid`]
+/// in the given [`IdLayout`].
+///
+/// The layouts differ only in the encoding of the identifiers, so the rest of the
+/// container is kept as is.
+fn reencode_container(data: &[u8], cid: &Cid, layout: IdLayout) -> Option<Vec<u8>> {
+    let id = cid.hash().digest().to_vec();
+
+    let data = match ShwapCid::from_cid(cid, layout).ok()? {
+        ShwapCid::Row(_) => RawRow {
+            row_id: id,
+            ..RawRow::decode(data).ok()?
+        }
+        .encode_to_vec(),
+        ShwapCid::Sample(_) => RawSample {
+            sample_id: id,
+            ..RawSample::decode(data).ok()?
+        }
+        .encode_to_vec(),
+        ShwapCid::NamespacedData(_) => RawNamespacedData {
+            data_id: id,
+            ..RawNamespacedData::decode(data).ok()?
+        }
+        .encode_to_vec(),
+    };
+
+    Some(data)
+}
+
+/// Compute the [`Cid`] of the received block.
+///
+/// Shwap containers are not hashed, instead their multihash digest is the
+/// identifier embedded in the container itself, encoded with the given [`IdLayout`].
+/// The layout is detected from the codec of the prefix when possible, the given one is
+/// used otherwise.
+fn compute_cid(block: &RawBlock, layout: IdLayout) -> Option<Cid> {
+    let mut prefix = &block.prefix[..];
+
+    let version = prost::encoding::decode_varint(&mut prefix).ok()?;
+    let codec = prost::encoding::decode_varint(&mut prefix).ok()?;
+    let mh_code = prost::encoding::decode_varint(&mut prefix).ok()?;
+    let mh_len = prost::encoding::decode_varint(&mut prefix).ok()?;
+
+    if version != 1 {
+        return None;
+    }
+
+    let layout = detect_id_layout(codec, usize::try_from(mh_len).ok()?).unwrap_or(layout);
+
+    let digest = match mh_code {
+        code if code == SampleId::multihash_code(layout) => {
+            RawSample::decode(&block.data[..]).ok()?.sample_id
+        }
+        code if code == RowId::multihash_code(layout) => {
+            RawRow::decode(&block.data[..]).ok()?.row_id
+        }
+        NAMESPACED_DATA_ID_MULTIHASH_CODE => {
+            RawNamespacedData::decode(&block.data[..]).ok()?.data_id
+        }
+        _ => return None,
+    };
+
+    if digest.len() as u64 != mh_len {
+        return None;
+    }
+
+    let hash = Multihash::wrap(mh_code, &digest).ok()?;
+    Some(CidGeneric::new_v1(codec, hash))
+}
+
+/// Layout of the shwap [`Cid`] sent by a peer, detected from its codec, or the layout
+/// of the protocol spoken by the peer if the [`Cid`] doesn't tell.
+fn cid_layout(cid: &Cid, peer_layout: IdLayout) -> IdLayout {
+    detect_id_layout(cid.codec(), cid.hash().digest().len()).unwrap_or(peer_layout)
+}
+
+/// Whether the [`Cid`] identifies one of the shwap containers.
+///
+/// The multihash codes of the shwap ids are the same in all the [`IdLayout`]s.
+fn is_shwap_cid(cid: &Cid) -> bool {
+    matches!(
+        cid.hash().code(),
+        SAMPLE_ID_MULTIHASH_CODE | ROW_ID_MULTIHASH_CODE | NAMESPACED_DATA_ID_MULTIHASH_CODE
+    )
+}
+
+/// Encode the prefix of the [`Cid`] as used in bitswap blocks.
+fn cid_prefix(cid: &Cid) -> Vec<u8> {
+    let mut prefix = Vec::new();
+    prost::encoding::encode_varint(1, &mut prefix);
+    prost::encoding::encode_varint(cid.codec(), &mut prefix);
+    prost::encoding::encode_varint(cid.hash().code(), &mut prefix);
+    prost::encoding::encode_varint(cid.hash().size().into(), &mut prefix);
+    prefix
+}
+
+/// Bitswap messages are one-way, so the codec sends them as requests and
+/// never waits for any response.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct BitswapCodec;
+
+#[async_trait]
+impl Codec for BitswapCodec {
+    type Protocol = StreamProtocol;
+    type Request = Message;
+    type Response = ();
+
+    async fn read_request<T>(&mut self, _: &Self::Protocol, io: &mut T) -> io::Result<Self::Request>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let data = timeout(MESSAGE_TIME_LIMIT, read_length_delimited(io))
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "reading message timed out"))??;
+
+        Message::decode(&data[..]).map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+    }
+
+    async fn read_response<T>(&mut self, _: &Self::Protocol, _: &mut T) -> io::Result<()>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        Ok(())
+    }
+
+    async fn write_request<T>(
+        &mut self,
+        _: &Self::Protocol,
+        io: &mut T,
+        msg: Self::Request,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        let buf = msg.encode_length_delimited_to_vec();
+
+        timeout(MESSAGE_TIME_LIMIT, io.write_all(&buf))
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "writing message timed out"))??;
+
+        Ok(())
+    }
+
+    async fn write_response<T>(&mut self, _: &Self::Protocol, _: &mut T, _: ()) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        Ok(())
+    }
+}
+
+/// Reads a single length delimited message.
+///
+/// Peers can keep the stream open for sending subsequent messages,
+/// so we can't just read until EOF.
+async fn read_length_delimited<T>(io: &mut T) -> io::Result<Vec<u8>>
+where
+    T: AsyncRead + Unpin + Send,
+{
+    let mut len_buf = Vec::with_capacity(10);
+
+    let len = loop {
+        let mut byte = [0u8];
+        io.read_exact(&mut byte).await?;
+        len_buf.push(byte[0]);
+
+        if byte[0] & 0x80 == 0 {
+            break prost::decode_length_delimiter(&len_buf[..])
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        }
+
+        if len_buf.len() >= 10 {
+            return Err(io::Error::new(io::ErrorKind::Other, "invalid length"));
+        }
+    };
+
+    if len > MESSAGE_SIZE_LIMIT {
+        return Err(io::Error::new(io::ErrorKind::Other, "message too large"));
+    }
+
+    let mut data = vec![0u8; len];
+    io.read_exact(&mut data).await?;
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::InMemoryStore;
+    use celestia_types::sample::{Sample, SampleId};
+    use celestia_types::{AxisType, ExtendedDataSquare};
+    use futures::io::Cursor;
+    use libp2p::{identify, identity::Keypair, Multiaddr};
+
+    #[cfg(not(target_arch = "wasm32"))]
+    use tokio::test as async_test;
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as async_test;
+
+    const LAYOUT: IdLayout = IdLayout::Draft;
+
+    #[derive(Default)]
+    struct MockSender {
+        sent: Vec<(PeerId, Message)>,
+    }
+
+    impl MessageSender for MockSender {
+        fn send_message(&mut self, peer: &PeerId, message: Message) -> OutboundRequestId {
+            self.sent.push((*peer, message));
+            // OutboundRequestId can't be constructed, so reuse one from a real behaviour
+            new_behaviour([protocol()]).send_request(peer, Message::default())
+        }
+    }
+
+    fn protocol() -> StreamProtocol {
+        StreamProtocol::new("/celestia/private/ipfs/bitswap/1.2.0")
+    }
+
+    fn identify_info(protocols: Vec<StreamProtocol>) -> identify::Info {
+        identify::Info {
+            public_key: Keypair::generate_ed25519().public(),
+            protocol_version: String::new(),
+            agent_version: String::new(),
+            listen_addrs: Vec::new(),
+            protocols,
+            observed_addr: Multiaddr::empty(),
+        }
+    }
+
+    fn sample_block() -> (Cid, RawBlock) {
+        sample_block_at(3)
+    }
+
+    fn sample_block_at(index: usize) -> (Cid, RawBlock) {
+        let eds_json = include_str!("../../types/test_data/shwap_samples/eds.json");
+        let eds: ExtendedDataSquare = serde_json::from_str(eds_json).unwrap();
+
+        let sample = Sample::new(AxisType::Row, index, &eds, 1).unwrap();
+        let cid = convert_cid(&CidGeneric::try_from(sample.sample_id).unwrap()).unwrap();
+
+        let block = RawBlock {
+            prefix: cid_prefix(&cid),
+            data: RawSample::from(sample).encode_to_vec(),
+        };
+
+        (cid, block)
+    }
+
+    #[test]
+    fn compute_sample_cid() {
+        let (cid, block) = sample_block();
+        assert_eq!(compute_cid(&block, IdLayout::default()), Some(cid));
+
+        let sample_id = SampleId::try_from(cid).unwrap();
+        assert_eq!(sample_id.row.block_height, 1);
+        assert_eq!(sample_id.index, 3);
+    }
+
+    #[test]
+    fn compute_sample_cid_draft_layout() {
+        let eds_json = include_str!("../../types/test_data/shwap_samples/eds.json");
+        let eds: ExtendedDataSquare = serde_json::from_str(eds_json).unwrap();
+
+        let sample = Sample::new(AxisType::Row, 3, &eds, 1).unwrap();
+        let cid = convert_cid(&sample.sample_id.to_cid(IdLayout::Draft)).unwrap();
+        let block = RawBlock {
+            prefix: cid_prefix(&cid),
+            data: sample.into_raw(IdLayout::Draft).encode_to_vec(),
+        };
+
+        assert_eq!(compute_cid(&block, IdLayout::Draft), Some(cid));
+    }
+
+    #[test]
+    fn compute_cid_unsupported_code() {
+        let (_, mut block) = sample_block();
+        block.prefix = vec![1, 0x55, 0x12, 0x20];
+        assert_eq!(compute_cid(&block, IdLayout::default()), None);
+    }
+
+    #[async_test]
+    async fn codec_round_trip() {
+        let (cid, block) = sample_block();
+        let msg = Message {
+            payload: vec![block],
+            ..want_message(&[cid], WantType::Have, false)
+        };
+
+        let protocol = StreamProtocol::new("/foo/bar/v0.1");
+        let mut codec = BitswapCodec;
+
+        let mut buf = Vec::new();
+        codec
+            .write_request(&protocol, &mut buf, msg.clone())
+            .await
+            .unwrap();
+
+        // Trailing data of the next message must not be consumed
+        buf.extend_from_slice(&[1, 2, 3]);
+        let mut reader = Cursor::new(buf);
+
+        let decoded = codec.read_request(&protocol, &mut reader).await.unwrap();
+        assert_eq!(decoded, msg);
+        assert_eq!(reader.position() as usize, reader.get_ref().len() - 3);
+    }
+
+    #[async_test]
+    async fn codec_message_too_large() {
+        let mut buf = Vec::new();
+        prost::encode_length_delimiter(MESSAGE_SIZE_LIMIT + 1, &mut buf).unwrap();
+        let mut reader = Cursor::new(buf);
+
+        let protocol = StreamProtocol::new("/foo/bar/v0.1");
+        BitswapCodec
+            .read_request(&protocol, &mut reader)
+            .await
+            .unwrap_err();
+    }
+
+    #[async_test]
+    async fn client_receives_block() {
+        let (cid, block) = sample_block();
+        let peers = vec![PeerId::random(), PeerId::random()];
+        let mut sender = MockSender::default();
+        let mut client = BitswapClient::new(
+            Arc::new(PeerTracker::new()),
+            IdLayout::default(),
+            Vec::new(),
+        );
+
+        let (tx, mut rx) = block_channel();
+        client.get(&mut sender, cid, peers.clone(), tx);
+        assert_eq!(sender.sent.len(), 2);
+
+        let msg = Message {
+            payload: vec![block.clone()],
+            ..Message::default()
+        };
+        client.on_message(&mut sender, peers[0], msg);
+
+        let received = rx.recv().await.unwrap().unwrap();
+        assert_eq!(received.peer, peers[0]);
+        assert_eq!(received.data, block.data);
+        // want is open until the block is verified
+        assert!(client.wants.contains_key(&cid));
+
+        client.accept(&mut sender, &cid, peers[0]);
+        assert!(client.wants.is_empty());
+
+        // cancel was sent to the other peer
+        let (peer, cancel) = sender.sent.last().unwrap();
+        assert_eq!(*peer, peers[1]);
+        assert!(cancel.wantlist.as_ref().unwrap().entries[0].cancel);
+    }
+
+    #[async_test]
+    async fn client_block_not_found() {
+        let (cid, _) = sample_block();
+        let peer = PeerId::random();
+        let mut sender = MockSender::default();
+        let mut client = BitswapClient::new(
+            Arc::new(PeerTracker::new()),
+            IdLayout::default(),
+            Vec::new(),
+        );
+
+        let (tx, mut rx) = block_channel();
+        client.get(&mut sender, cid, vec![peer], tx);
+
+        let msg = Message {
+            block_presences: vec![BlockPresence {
+                cid: cid.to_bytes(),
+                r#type: BlockPresenceType::DontHave as i32,
+            }],
+            ..Message::default()
+        };
+        client.on_message(&mut sender, peer, msg);
+
+        let err = rx.recv().await.unwrap().unwrap_err();
+        assert!(matches!(
+            err,
+            P2pError::Bitswap(BitswapError::BlockNotFound)
+        ));
+    }
+
+    #[async_test]
+    async fn client_penalizes_malformed_block() {
+        let (cid, mut block) = sample_block();
+        let peer = PeerId::random();
+        let peer_tracker = Arc::new(PeerTracker::new());
+        let mut sender = MockSender::default();
+        let mut client = BitswapClient::new(peer_tracker.clone(), IdLayout::default(), Vec::new());
+
+        let (tx, _rx) = block_channel();
+        client.get(&mut sender, cid, vec![peer], tx);
+
+        block.data = vec![1, 2, 3];
+        let msg = Message {
+            payload: vec![block],
+            ..Message::default()
+        };
+        client.on_message(&mut sender, peer, msg);
+
+        assert!(peer_tracker.score(peer) < 0.0);
+        // want is still waiting for a valid block
+        assert!(client.wants.contains_key(&cid));
+    }
+
+    #[async_test]
+    async fn client_translates_layout_of_peer() {
+        let eds_json = include_str!("../../types/test_data/shwap_samples/eds.json");
+        let eds: ExtendedDataSquare = serde_json::from_str(eds_json).unwrap();
+        let sample = Sample::new(AxisType::Row, 3, &eds, 1).unwrap();
+        let cid = convert_cid(&sample.sample_id.to_cid(IdLayout::V1)).unwrap();
+        let draft_cid = convert_cid(&sample.sample_id.to_cid(IdLayout::Draft)).unwrap();
+
+        let draft_protocol = StreamProtocol::new("/celestia/private/draft/ipfs/bitswap/1.2.0");
+        let draft_peer = PeerId::random();
+        let peer_tracker = Arc::new(PeerTracker::new());
+        peer_tracker.set_identified(draft_peer, &identify_info(vec![draft_protocol.clone()]));
+
+        let mut sender = MockSender::default();
+        let mut client = BitswapClient::new(
+            peer_tracker,
+            IdLayout::V1,
+            vec![
+                (protocol(), IdLayout::V1),
+                (draft_protocol, IdLayout::Draft),
+            ],
+        );
+
+        let (tx, mut rx) = block_channel();
+        client.get(&mut sender, cid, vec![draft_peer], tx);
+
+        // peer is asked in its layout
+        let (_, want) = sender.sent.last().unwrap();
+        let entry = &want.wantlist.as_ref().unwrap().entries[0];
+        assert_eq!(entry.block, draft_cid.to_bytes());
+
+        let msg = Message {
+            payload: vec![RawBlock {
+                prefix: cid_prefix(&draft_cid),
+                data: sample.clone().into_raw(IdLayout::Draft).encode_to_vec(),
+            }],
+            ..Message::default()
+        };
+        client.on_message(&mut sender, draft_peer, msg);
+
+        // block is returned in the layout of the client
+        let data = rx.recv().await.unwrap().unwrap().data;
+        let received = Sample::from_raw(RawSample::decode(&data[..]).unwrap(), IdLayout::V1);
+        assert_eq!(received.unwrap().sample_id, sample.sample_id);
+
+        client.accept(&mut sender, &cid, draft_peer);
+        assert!(client.wants.is_empty());
+    }
+
+    #[async_test]
+    async fn client_asks_again_after_rejected_block() {
+        let (cid, block) = sample_block();
+        let peers = vec![PeerId::random(), PeerId::random(), PeerId::random()];
+        let peer_tracker = Arc::new(PeerTracker::new());
+        let mut sender = MockSender::default();
+        let mut client = BitswapClient::new(peer_tracker.clone(), IdLayout::default(), Vec::new());
+
+        let (tx, mut rx) = block_channel();
+        client.get(&mut sender, cid, peers.clone(), tx);
+
+        let msg = Message {
+            payload: vec![block.clone()],
+            ..Message::default()
+        };
+        client.on_message(&mut sender, peers[0], msg.clone());
+        assert_eq!(rx.recv().await.unwrap().unwrap().peer, peers[0]);
+
+        // want doesn't fail while the block is verified
+        let dont_have = Message {
+            block_presences: vec![BlockPresence {
+                cid: cid.to_bytes(),
+                r#type: BlockPresenceType::DontHave as i32,
+            }],
+            ..Message::default()
+        };
+        client.on_message(&mut sender, peers[1], dont_have.clone());
+        assert!(rx.try_recv().is_err());
+
+        // invalid block is penalized and the remaining peer is still waited for
+        client.reject(&mut sender, &cid, peers[0]);
+        assert!(peer_tracker.score(peers[0]) < 0.0);
+        assert!(client.wants.contains_key(&cid));
+        assert!(rx.try_recv().is_err());
+
+        client.on_message(&mut sender, peers[2], msg);
+        assert_eq!(rx.recv().await.unwrap().unwrap().peer, peers[2]);
+
+        // once all the blocks are rejected, the want fails
+        client.reject(&mut sender, &cid, peers[2]);
+        let err = rx.recv().await.unwrap().unwrap_err();
+        assert!(matches!(
+            err,
+            P2pError::Bitswap(BitswapError::BlockNotFound)
+        ));
+        assert!(client.wants.is_empty());
+    }
+
+    #[async_test]
+    async fn client_no_peers() {
+        let (cid, _) = sample_block();
+        let mut sender = MockSender::default();
+        let mut client = BitswapClient::new(
+            Arc::new(PeerTracker::new()),
+            IdLayout::default(),
+            Vec::new(),
+        );
+
+        let (tx, mut rx) = block_channel();
+        client.get(&mut sender, cid, vec![], tx);
+
+        let err = rx.recv().await.unwrap().unwrap_err();
+        assert!(matches!(err, P2pError::NoConnectedPeers));
+    }
+
+    #[async_test]
+    async fn client_batches_wants() {
+        let (cid1, _) = sample_block_at(3);
+        let (cid2, _) = sample_block_at(4);
+        let peers = vec![PeerId::random(), PeerId::random()];
+        let mut sender = MockSender::default();
+        let mut client = BitswapClient::new(
+            Arc::new(PeerTracker::new()),
+            IdLayout::default(),
+            Vec::new(),
+        );
+
+        let (tx1, _rx1) = block_channel();
+        let (tx2, _rx2) = block_channel();
+        let (tx3, _rx3) = block_channel();
+        client.get_many(
+            &mut sender,
+            vec![(cid1, tx1), (cid2, tx2), (cid1, tx3)],
+            peers.clone(),
+        );
+
+        // single message with both wants for each of the peers
+        assert_eq!(sender.sent.len(), 2);
+        for (_, message) in &sender.sent {
+            let entries = &message.wantlist.as_ref().unwrap().entries;
+            assert_eq!(entries.len(), 2);
+        }
+
+        // duplicated want is merged
+        assert_eq!(client.wants.len(), 2);
+        assert_eq!(client.wants[&cid1].respond_to.len(), 2);
+    }
+
+    #[async_test]
+    async fn client_reuses_session_of_height() {
+        let (cid1, block1) = sample_block_at(3);
+        let (cid2, _) = sample_block_at(4);
+        let peers = vec![PeerId::random(), PeerId::random(), PeerId::random()];
+        let mut sender = MockSender::default();
+        let mut client = BitswapClient::new(
+            Arc::new(PeerTracker::new()),
+            IdLayout::default(),
+            Vec::new(),
+        );
+
+        let (tx, _rx) = block_channel();
+        client.get(&mut sender, cid1, peers.clone(), tx);
+        let msg = Message {
+            payload: vec![block1],
+            ..Message::default()
+        };
+        client.on_message(&mut sender, peers[1], msg);
+        client.accept(&mut sender, &cid1, peers[1]);
+        sender.sent.clear();
+
+        // next block of the same height is wanted only from the session peer
+        let (tx, mut rx) = block_channel();
+        client.get(&mut sender, cid2, peers.clone(), tx);
+        assert_eq!(sender.sent.len(), 1);
+        assert_eq!(sender.sent[0].0, peers[1]);
+        sender.sent.clear();
+
+        // other peers are asked when the session peer doesn't have it
+        let msg = Message {
+            block_presences: vec![BlockPresence {
+                cid: cid2.to_bytes(),
+                r#type: BlockPresenceType::DontHave as i32,
+            }],
+            ..Message::default()
+        };
+        client.on_message(&mut sender, peers[1], msg.clone());
+
+        let asked: HashSet<_> = sender.sent.iter().map(|(peer, _)| *peer).collect();
+        assert_eq!(asked, HashSet::from([peers[0], peers[2]]));
+
+        client.on_message(&mut sender, peers[0], msg.clone());
+        client.on_message(&mut sender, peers[2], msg);
+
+        let err = rx.recv().await.unwrap().unwrap_err();
+        assert!(matches!(
+            err,
+            P2pError::Bitswap(BitswapError::BlockNotFound)
+        ));
+    }
+
+    #[async_test]
+    async fn client_demotes_unresponsive_session_peer() {
+        let (cid1, block1) = sample_block_at(3);
+        let (cid2, _) = sample_block_at(4);
+        let peers = vec![PeerId::random(), PeerId::random(), PeerId::random()];
+        let peer_tracker = Arc::new(PeerTracker::new());
+        let mut sender = MockSender::default();
+        let mut client = BitswapClient::new(peer_tracker.clone(), IdLayout::default(), Vec::new());
+
+        let (tx, _rx) = block_channel();
+        client.get(&mut sender, cid1, peers.clone(), tx);
+        let msg = Message {
+            payload: vec![block1],
+            ..Message::default()
+        };
+        client.on_message(&mut sender, peers[1], msg);
+
+        // peer joins the session only once its block is accepted
+        let height = client.block_height(&cid1).unwrap();
+        assert!(client.sessions[&height].peers.is_empty());
+        client.accept(&mut sender, &cid1, peers[1]);
+        assert_eq!(client.sessions[&height].peers, [peers[1]]);
+
+        let (tx, _rx) = block_channel();
+        client.get(&mut sender, cid2, peers.clone(), tx);
+        sender.sent.clear();
+
+        // session peer doesn't respond in time
+        client.wants.get_mut(&cid2).unwrap().asked_at =
+            Instant::now().checked_sub(PEER_RESPONSE_TIMEOUT).unwrap();
+        client.remove_abandoned(&mut sender);
+
+        assert!(peer_tracker.score(peers[1]) < 0.0);
+        assert!(client.sessions[&height].peers.is_empty());
+
+        // want is withdrawn from it and the other peers are asked
+        let (peer, cancel) = &sender.sent[0];
+        assert_eq!(*peer, peers[1]);
+        assert!(cancel.wantlist.as_ref().unwrap().entries[0].cancel);
+        let asked: HashSet<_> = sender.sent[1..].iter().map(|(peer, _)| *peer).collect();
+        assert_eq!(asked, HashSet::from([peers[0], peers[2]]));
+    }
+
+    #[async_test]
+    async fn respond_to_wants_from_blockstore() {
+        let (cid, block) = sample_block();
+        let (missing_cid, _) = sample_block_at(4);
+
+        let store = InMemoryStore::new();
+        store.put_keyed(&cid, &block.data).await.unwrap();
+
+        let wants = |cid: &Cid, want_type| want_message(&[*cid, missing_cid], want_type, false);
+
+        let response = respond_to_wants(&store, &wants(&cid, WantType::Block), LAYOUT, LAYOUT)
+            .await
+            .unwrap();
+        assert_eq!(response.payload, vec![block.clone()]);
+        assert_eq!(
+            response.block_presences,
+            vec![BlockPresence {
+                cid: missing_cid.to_bytes(),
+                r#type: BlockPresenceType::DontHave as i32,
+            }]
+        );
+        // served block is accepted by the client
+        assert_eq!(
+            compute_cid(&response.payload[0], IdLayout::default()),
+            Some(cid)
+        );
+
+        let response = respond_to_wants(&store, &wants(&cid, WantType::Have), LAYOUT, LAYOUT)
+            .await
+            .unwrap();
+        assert!(response.payload.is_empty());
+        assert_eq!(response.block_presences[0].cid, cid.to_bytes());
+        assert_eq!(
+            response.block_presences[0].r#type,
+            BlockPresenceType::Have as i32
+        );
+
+        // cancels are not answered
+        let cancel = want_message(&[cid], WantType::Block, true);
+        assert_eq!(
+            respond_to_wants(&store, &cancel, LAYOUT, LAYOUT).await,
+            None
+        );
+    }
+
+    #[async_test]
+    async fn respond_with_blocks_fitting_the_message() {
+        let (big_cid, _) = sample_block_at(1);
+        let (cid, block) = sample_block_at(2);
+
+        let store = InMemoryStore::new();
+        let big_data = vec![0; MESSAGE_SIZE_LIMIT / 2];
+        store.put_keyed(&big_cid, &big_data).await.unwrap();
+        store.put_keyed(&cid, &block.data).await.unwrap();
+
+        let wants = want_message(&[big_cid, cid], WantType::Block, false);
+        let response = respond_to_wants(&store, &wants, LAYOUT, LAYOUT)
+            .await
+            .unwrap();
+
+        // too big block is skipped, but the following ones are still sent
+        assert_eq!(response.payload, vec![block]);
+    }
+
+    #[async_test]
+    async fn respond_in_layout_of_wanted_cid() {
+        let eds_json = include_str!("../../types/test_data/shwap_samples/eds.json");
+        let eds: ExtendedDataSquare = serde_json::from_str(eds_json).unwrap();
+        let sample = Sample::new(AxisType::Row, 3, &eds, 1).unwrap();
+        let draft_cid = convert_cid(&sample.sample_id.to_cid(IdLayout::Draft)).unwrap();
+        let v1_cid = convert_cid(&sample.sample_id.to_cid(IdLayout::V1)).unwrap();
+
+        let store = InMemoryStore::new();
+        let data = sample.clone().into_raw(IdLayout::Draft).encode_to_vec();
+        store.put_keyed(&draft_cid, &data).await.unwrap();
+
+        // layout is detected from the codec, even if the protocol of the peer says otherwise
+        let wants = want_message(&[v1_cid], WantType::Block, false);
+        let response = respond_to_wants(&store, &wants, IdLayout::Draft, IdLayout::Draft)
+            .await
+            .unwrap();
+
+        let block = &response.payload[0];
+        assert_eq!(compute_cid(block, IdLayout::Draft), Some(v1_cid));
+        let raw = RawSample::decode(&block.data[..]).unwrap();
+        let served = Sample::from_raw(raw, IdLayout::V1).unwrap();
+        assert_eq!(served.sample_id, sample.sample_id);
+
+        // and the presences are announced with the cid of the want
+        let wants = want_message(&[v1_cid], WantType::Have, false);
+        let response = respond_to_wants(&store, &wants, IdLayout::Draft, IdLayout::V1)
+            .await
+            .unwrap();
+        assert_eq!(response.block_presences[0].cid, v1_cid.to_bytes());
+    }
+
+    #[async_test]
+    async fn respond_only_with_shwap_containers() {
+        let hash = Multihash::wrap(0x12, &[1; 32]).unwrap();
+        let cid = Cid::new_v1(0x55, hash);
+
+        let store = InMemoryStore::new();
+        store.put_keyed(&cid, b"foo").await.unwrap();
+
+        let response = respond_to_wants(
+            &store,
+            &want_message(&[cid], WantType::Block, false),
+            LAYOUT,
+            LAYOUT,
+        )
+        .await
+        .unwrap();
+        assert!(response.payload.is_empty());
+        assert_eq!(
+            response.block_presences,
+            vec![BlockPresence {
+                cid: cid.to_bytes(),
+                r#type: BlockPresenceType::DontHave as i32,
+            }]
+        );
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[async_test]
+    async fn respond_to_wants_from_chained_blockstore() {
+        let (cid, block) = sample_block();
+        let (other_cid, other_block) = sample_block_at(4);
+
+        let first = InMemoryStore::new();
+        first.put_keyed(&cid, &block.data).await.unwrap();
+        let second = InMemoryStore::new();
+        second
+            .put_keyed(&other_cid, &other_block.data)
+            .await
+            .unwrap();
+
+        let blockstore = ChainedBlockstore(&first, &second);
+
+        for (cid, block) in [(cid, block), (other_cid, other_block)] {
+            let response = respond_to_wants(
+                &blockstore,
+                &want_message(&[cid], WantType::Block, false),
+                LAYOUT,
+                LAYOUT,
+            )
+            .await
+            .unwrap();
+            assert_eq!(response.payload, vec![block]);
+        }
+    }
+}
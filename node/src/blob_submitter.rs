@@ -0,0 +1,422 @@
+//! Submission of blobs through the RPC of a Celestia bridge or full node.
+//!
+//! Light nodes can't broadcast transactions over the p2p network. [`BlobSubmitter`]
+//! builds the [`MsgPayForBlobs`] locally, computing the [`Commitment`]s of the blobs
+//! with `celestia-types`, signs the transaction with the provided [`Keyring`] and
+//! broadcasts it using the `state.SubmitTx` RPC of the configured node.
+//!
+//! [`Commitment`]: celestia_types::Commitment
+
+use celestia_proto::celestia::blob::v1::MsgPayForBlobs as RawMsgPayForBlobs;
+use celestia_proto::cosmos::base::v1beta1::Coin;
+use celestia_proto::cosmos::crypto::secp256k1::PubKey;
+use celestia_proto::cosmos::tx::signing::v1beta1::SignMode;
+use celestia_proto::cosmos::tx::v1beta1::mode_info::{Single, Sum};
+use celestia_proto::cosmos::tx::v1beta1::{
+    AuthInfo, Fee, ModeInfo, SignDoc, SignerInfo, TxBody, TxRaw,
+};
+use celestia_rpc::{HeaderClient, StateClient};
+use celestia_tendermint::account::Id;
+use celestia_tendermint_proto::v0_34::types::{Blob as RawBlob, BlobTx};
+use celestia_types::blob::{MsgPayForBlobs, SubmitOptions, MSG_PAY_FOR_BLOBS_TYPE_URL};
+use celestia_types::consts::appconsts::AppVersion;
+use celestia_types::hash::Hash;
+use celestia_types::state::{AccAddress, RawTx, TxResponse};
+use celestia_types::Blob;
+use k256::ecdsa::signature::Signer;
+use prost::Message;
+use prost_types::Any;
+use sha2::{Digest, Sha256};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+pub use k256::ecdsa::{Signature, SigningKey, VerifyingKey};
+
+/// Denomination of the fees paid for the transactions.
+pub const FEE_DENOM: &str = "utia";
+/// Price of a single unit of gas used when [`SubmitOptions`] have no fee specified.
+pub const DEFAULT_GAS_PRICE: f64 = 0.002;
+
+const BLOB_TX_TYPE_ID: &str = "BLOB";
+const SECP256K1_PUBKEY_TYPE_URL: &str = "/cosmos.crypto.secp256k1.PubKey";
+
+type Result<T, E = BlobSubmitterError> = std::result::Result<T, E>;
+
+/// Representation of all the errors that can occur when interacting with the [`BlobSubmitter`].
+#[derive(Debug, thiserror::Error)]
+pub enum BlobSubmitterError {
+    /// Gas limit was not provided in the [`SubmitOptions`].
+    #[error("Gas limit must be provided in the submit options")]
+    MissingGasLimit,
+
+    /// Signing the transaction failed.
+    #[error("Signing transaction failed: {0}")]
+    Signing(k256::ecdsa::Error),
+
+    /// Transaction was rejected or failed during execution.
+    #[error("Transaction failed with code {code}: {log}")]
+    TxFailed {
+        /// Error code returned by the application.
+        code: u32,
+        /// Log describing the failure.
+        log: String,
+    },
+
+    /// An error propagated from the [`celestia_types`].
+    #[error(transparent)]
+    Types(#[from] celestia_types::Error),
+
+    /// An error propagated from the [`celestia_rpc`].
+    #[error(transparent)]
+    Rpc(#[from] celestia_rpc::Error),
+
+    /// Broadcasting the transaction failed, so it may or may not have been included.
+    #[error("Broadcasting transaction {hash} failed: {error}")]
+    Broadcast {
+        /// Hash of the broadcasted transaction.
+        hash: Hash,
+        /// Error of the RPC call.
+        error: celestia_rpc::Error,
+    },
+
+    /// Account queried from the chain couldn't be decoded.
+    #[error("Invalid account: {0}")]
+    InvalidAccount(String),
+}
+
+/// Provider of the secp256k1 key of the account paying for the blobs.
+///
+/// Implemented for the [`SigningKey`], which can be loaded from a `Keystore` with
+/// the `keystore` feature enabled.
+pub trait Keyring: Send + Sync {
+    /// Public key of the account.
+    fn public_key(&self) -> VerifyingKey;
+
+    /// Address of the account.
+    fn address(&self) -> AccAddress {
+        AccAddress::new(Id::from(self.public_key()))
+    }
+
+    /// Sign the serialized `SignDoc` of the transaction.
+    fn sign(&self, sign_doc: &[u8]) -> Result<Signature, k256::ecdsa::Error>;
+}
+
+impl Keyring for SigningKey {
+    fn public_key(&self) -> VerifyingKey {
+        *self.verifying_key()
+    }
+
+    fn sign(&self, sign_doc: &[u8]) -> Result<Signature, k256::ecdsa::Error> {
+        let signature: Signature = self.try_sign(sign_doc)?;
+        // Cosmos accepts only signatures with the low S
+        Ok(signature.normalize_s().unwrap_or(signature))
+    }
+}
+
+/// Arguments used to configure the [`BlobSubmitter`].
+pub struct BlobSubmitterArgs<C, K> {
+    /// RPC client of the node broadcasting the transactions.
+    pub client: C,
+    /// Keyring of the account paying for the blobs.
+    pub keyring: K,
+    /// Id of the chain, e.g. `celestia` or `mocha-4`.
+    pub chain_id: String,
+    /// Number of the account paying for the blobs.
+    pub account_number: u64,
+    /// Sequence of the next transaction of the account.
+    pub sequence: u64,
+}
+
+/// Submitter of the blobs over the RPC.
+pub struct BlobSubmitter<C, K> {
+    client: C,
+    keyring: K,
+    address: AccAddress,
+    chain_id: String,
+    account_number: u64,
+    sequence: Mutex<u64>,
+}
+
+impl<C, K> BlobSubmitter<C, K>
+where
+    C: StateClient + HeaderClient + Send + Sync,
+    K: Keyring,
+{
+    /// Create a new [`BlobSubmitter`].
+    pub fn new(args: BlobSubmitterArgs<C, K>) -> Self {
+        let address = args.keyring.address();
+
+        BlobSubmitter {
+            client: args.client,
+            keyring: args.keyring,
+            address,
+            chain_id: args.chain_id,
+            account_number: args.account_number,
+            sequence: Mutex::new(args.sequence),
+        }
+    }
+
+    /// Replace the sequence of the next transaction, e.g. after querying it from the chain.
+    pub async fn set_sequence(&self, sequence: u64) {
+        *self.sequence.lock().await = sequence;
+    }
+
+    /// Address of the account paying for the blobs.
+    pub fn address(&self) -> &AccAddress {
+        &self.address
+    }
+
+    /// Build, sign and broadcast a transaction paying for the given [`Blob`]s.
+    ///
+    /// The gas limit must be provided in the [`SubmitOptions`]. If the fee is missing,
+    /// it is computed using the [`DEFAULT_GAS_PRICE`]. Submissions are serialized so
+    /// that each transaction is signed with the next sequence of the account.
+    ///
+    /// The [`AppVersion`] the message is built for is read from the network head before
+    /// each submission, so that the submitter keeps working across the upgrades.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlobSubmitterError::TxFailed`] if the transaction was rejected or
+    /// failed during the execution, and [`BlobSubmitterError::Broadcast`] if the RPC
+    /// call broadcasting it failed.
+    pub async fn submit_blob(&self, blobs: &[Blob], opts: SubmitOptions) -> Result<TxResponse> {
+        let gas_limit = opts.gas_limit.ok_or(BlobSubmitterError::MissingGasLimit)?;
+        let fee = opts
+            .fee
+            .unwrap_or_else(|| (gas_limit as f64 * DEFAULT_GAS_PRICE).ceil() as u64);
+
+        let app_version = self
+            .client
+            .header_network_head()
+            .await
+            .map_err(celestia_rpc::Error::from)?
+            .app_version()?;
+
+        let mut sequence = self.sequence.lock().await;
+
+        let (tx, hash) = build_blob_tx(BlobTxArgs {
+            keyring: &self.keyring,
+            signer: &self.address,
+            chain_id: &self.chain_id,
+            account_number: self.account_number,
+            sequence: *sequence,
+            blobs,
+            app_version,
+            gas_limit,
+            fee,
+        })?;
+
+        let response = self
+            .client
+            .state_submit_tx(&RawTx::from(tx))
+            .await
+            .map_err(|e| BlobSubmitterError::Broadcast {
+                hash,
+                error: e.into(),
+            })?;
+
+        // Sequence is consumed once the transaction lands in a block, even if it failed
+        if response.height != 0 {
+            *sequence += 1;
+        }
+
+        if response.code != 0 {
+            debug!(
+                "PayForBlobs {} failed: {}",
+                response.txhash, response.raw_log
+            );
+            return Err(BlobSubmitterError::TxFailed {
+                code: response.code,
+                log: response.raw_log,
+            });
+        }
+
+        Ok(response)
+    }
+}
+
+/// Arguments of the `BlobTx` built with [`build_blob_tx`].
+struct BlobTxArgs<'a, K> {
+    keyring: &'a K,
+    signer: &'a AccAddress,
+    chain_id: &'a str,
+    account_number: u64,
+    sequence: u64,
+    blobs: &'a [Blob],
+    app_version: AppVersion,
+    gas_limit: u64,
+    fee: u64,
+}
+
+/// Build a signed `BlobTx` paying for the given blobs, along with the hash of its inner
+/// transaction, which identifies it on the chain.
+fn build_blob_tx<K>(args: BlobTxArgs<'_, K>) -> Result<(Vec<u8>, Hash)>
+where
+    K: Keyring,
+{
+    let BlobTxArgs {
+        keyring,
+        signer,
+        chain_id,
+        account_number,
+        sequence,
+        blobs,
+        app_version,
+        gas_limit,
+        fee,
+    } = args;
+
+    let msg = MsgPayForBlobs::new(blobs, signer.clone(), app_version)?;
+
+    let body = TxBody {
+        messages: vec![Any {
+            type_url: MSG_PAY_FOR_BLOBS_TYPE_URL.to_owned(),
+            value: RawMsgPayForBlobs::from(msg).encode_to_vec(),
+        }],
+        ..Default::default()
+    };
+
+    let public_key = PubKey {
+        key: keyring
+            .public_key()
+            .to_encoded_point(true)
+            .as_bytes()
+            .to_vec(),
+    };
+
+    let auth_info = AuthInfo {
+        signer_infos: vec![SignerInfo {
+            public_key: Some(Any {
+                type_url: SECP256K1_PUBKEY_TYPE_URL.to_owned(),
+                value: public_key.encode_to_vec(),
+            }),
+            mode_info: Some(ModeInfo {
+                sum: Some(Sum::Single(Single {
+                    mode: SignMode::Direct.into(),
+                })),
+            }),
+            sequence,
+        }],
+        fee: Some(Fee {
+            amount: vec![Coin {
+                denom: FEE_DENOM.to_owned(),
+                amount: fee.to_string(),
+            }],
+            gas_limit,
+            ..Default::default()
+        }),
+        tip: None,
+    };
+
+    let body_bytes = body.encode_to_vec();
+    let auth_info_bytes = auth_info.encode_to_vec();
+
+    let sign_doc = SignDoc {
+        body_bytes: body_bytes.clone(),
+        auth_info_bytes: auth_info_bytes.clone(),
+        chain_id: chain_id.to_owned(),
+        account_number,
+    };
+
+    let signature = keyring
+        .sign(&sign_doc.encode_to_vec())
+        .map_err(BlobSubmitterError::Signing)?;
+
+    let tx = TxRaw {
+        body_bytes,
+        auth_info_bytes,
+        signatures: vec![signature.to_bytes().to_vec()],
+    };
+
+    let tx = tx.encode_to_vec();
+    let hash = Hash::Sha256(Sha256::digest(&tx).into());
+
+    let blob_tx = BlobTx {
+        tx,
+        blobs: blobs.iter().cloned().map(RawBlob::from).collect(),
+        type_id: BLOB_TX_TYPE_ID.to_owned(),
+    };
+
+    Ok((blob_tx.encode_to_vec(), hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use celestia_tendermint_proto::Protobuf;
+    use celestia_types::nmt::Namespace;
+    use k256::ecdsa::signature::Verifier;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    #[test]
+    fn blob_tx_is_signed() {
+        let key = SigningKey::from_slice(&[7; 32]).unwrap();
+        let signer = AccAddress::new(Id::from(key.public_key()));
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+        let blob = Blob::new(namespace, b"foo".to_vec(), AppVersion::V2).unwrap();
+
+        let (bytes, hash) = build_blob_tx(BlobTxArgs {
+            keyring: &key,
+            signer: &signer,
+            chain_id: "private",
+            account_number: 3,
+            sequence: 5,
+            blobs: &[blob.clone()],
+            app_version: AppVersion::V2,
+            gas_limit: 80_000,
+            fee: 200,
+        })
+        .unwrap();
+
+        let blob_tx = BlobTx::decode(&bytes[..]).unwrap();
+        assert_eq!(blob_tx.type_id, BLOB_TX_TYPE_ID);
+        assert_eq!(blob_tx.blobs, vec![RawBlob::from(blob.clone())]);
+        assert_eq!(hash, Hash::Sha256(Sha256::digest(&blob_tx.tx).into()));
+
+        let tx = TxRaw::decode(&blob_tx.tx[..]).unwrap();
+        let body = TxBody::decode(&tx.body_bytes[..]).unwrap();
+        let msg = MsgPayForBlobs::decode(&body.messages[0].value[..]).unwrap();
+        assert_eq!(msg.signer, signer);
+        assert_eq!(msg.share_commitments, vec![blob.commitment]);
+
+        let auth_info = AuthInfo::decode(&tx.auth_info_bytes[..]).unwrap();
+        assert_eq!(auth_info.signer_infos[0].sequence, 5);
+        let fee = auth_info.fee.unwrap();
+        assert_eq!(fee.gas_limit, 80_000);
+        assert_eq!(fee.amount[0].amount, "200");
+
+        let sign_doc = SignDoc {
+            body_bytes: tx.body_bytes,
+            auth_info_bytes: tx.auth_info_bytes,
+            chain_id: "private".to_owned(),
+            account_number: 3,
+        };
+        let signature = Signature::from_slice(&tx.signatures[0]).unwrap();
+        key.verifying_key()
+            .verify(&sign_doc.encode_to_vec(), &signature)
+            .unwrap();
+    }
+
+    #[test]
+    fn blob_tx_without_blobs() {
+        let key = SigningKey::from_slice(&[7; 32]).unwrap();
+        let signer = AccAddress::new(Id::from(key.public_key()));
+
+        let err = build_blob_tx(BlobTxArgs {
+            keyring: &key,
+            signer: &signer,
+            chain_id: "private",
+            account_number: 0,
+            sequence: 0,
+            blobs: &[],
+            app_version: AppVersion::V2,
+            gas_limit: 80_000,
+            fee: 200,
+        })
+        .unwrap_err();
+        assert!(matches!(err, BlobSubmitterError::Types(_)));
+    }
+}
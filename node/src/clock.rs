@@ -0,0 +1,73 @@
+//! Source of the wall-clock time of the node.
+//!
+//! The [`Clock`] decides whether the headers received from the network are from the
+//! future, whether the persisted network head is still within the trusting period and
+//! which headers fell out of the pruning window. Devices with a skewed clock can tolerate
+//! a larger drift of the headers' time with [`NodeBuilder::max_clock_drift`], and tests
+//! can move the time forward with a custom [`Clock`], e.g. the `MockClock` from the
+//! `test_utils`.
+//!
+//! [`NodeBuilder::max_clock_drift`]: crate::node::NodeBuilder::max_clock_drift
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use celestia_tendermint::Time;
+use celestia_types::ClockCheck;
+
+pub use celestia_types::DEFAULT_MAX_CLOCK_DRIFT;
+
+/// Source of the current time.
+pub trait Clock: Send + Sync + 'static {
+    /// Current time.
+    fn now(&self) -> Time;
+}
+
+/// [`Clock`] reading the system time.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Time {
+        Time::now()
+    }
+}
+
+/// [`Clock`] used to check the time of the received headers.
+#[derive(Clone)]
+pub(crate) struct HeaderClock {
+    clock: Arc<dyn Clock>,
+    max_clock_drift: Duration,
+}
+
+impl HeaderClock {
+    pub(crate) fn new(clock: Arc<dyn Clock>, max_clock_drift: Duration) -> Self {
+        HeaderClock {
+            clock,
+            max_clock_drift,
+        }
+    }
+
+    /// Check rejecting the headers more than the `max_clock_drift` ahead of the clock.
+    pub(crate) fn check(&self) -> ClockCheck {
+        ClockCheck::At {
+            now: self.clock.now(),
+            max_clock_drift: self.max_clock_drift,
+        }
+    }
+}
+
+impl fmt::Debug for HeaderClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HeaderClock")
+            .field("max_clock_drift", &self.max_clock_drift)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for HeaderClock {
+    fn default() -> Self {
+        HeaderClock::new(Arc::new(SystemClock), DEFAULT_MAX_CLOCK_DRIFT)
+    }
+}
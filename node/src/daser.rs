@@ -0,0 +1,937 @@
+//! Component responsible for the data availability sampling of the synchronized headers.
+//!
+//! For every header in the store the [`Daser`] requests a number of [`Sample`]s at
+//! random coordinates of the block's [`ExtendedDataSquare`], wanting all of them from
+//! the peers at once. The block is accepted
+//! as available only if all of them were retrieved and verified in time. The outcome
+//! is persisted as the [`SamplingMetadata`], so that the heights sampled once aren't
+//! sampled again after a restart. If the sampling fails for another reason than
+//! the samples not arriving in time, eg. there are no connected peers, nothing is
+//! persisted and the height is sampled again after a delay.
+//!
+//! All the unsampled heights of the store are sampled, including the ones already
+//! present when the [`Daser`] starts and the ones synchronized later, both on top
+//! of the head and below the tail. How many samples are taken,
+//! how many heights are sampled at once and in which order is controlled with
+//! the [`DaserConfig`].
+//!
+//! Optionally, the [`SamplingEvidence`] of the most recently sampled heights is kept
+//! in memory, so that the verified samples can be exported, e.g. as the light node's
+//! attestation of the data availability.
+//!
+//! [`Sample`]: celestia_types::sample::Sample
+//! [`ExtendedDataSquare`]: celestia_types::ExtendedDataSquare
+//! [`SamplingMetadata`]: crate::store::SamplingMetadata
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Range, RangeInclusive};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use celestia_types::sample::{Sample, SampleId};
+use futures::future::join_all;
+use futures::stream::FuturesUnordered;
+use futures::StreamExt;
+use instant::Instant;
+use tokio::select;
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::bitswap::convert_cid;
+use crate::events::{EventPublisher, NodeEvent};
+use crate::executor::{sleep, spawn, timeout};
+use crate::p2p::{P2p, P2pError};
+use crate::store::{Store, StoreError};
+
+/// Default number of samples requested for each height.
+pub const DEFAULT_SAMPLES_PER_HEIGHT: usize = 16;
+/// Default number of heights sampled at the same time.
+pub const DEFAULT_CONCURRENCY_LIMIT: usize = 3;
+/// Default time after which a single sample is considered unavailable.
+pub const DEFAULT_SAMPLE_TIMEOUT: Duration = Duration::from_secs(60);
+/// Default time after which the sampling that failed is retried.
+pub const DEFAULT_RETRY_DELAY: Duration = Duration::from_secs(10);
+
+type Result<T, E = DaserError> = std::result::Result<T, E>;
+
+/// Representation of the errors that can occur when sampling a block.
+#[derive(Debug, thiserror::Error)]
+enum DaserError {
+    /// An error propagated from the [`P2p`].
+    #[error(transparent)]
+    P2p(#[from] P2pError),
+
+    /// An error propagated from the [`Store`].
+    #[error(transparent)]
+    Store(#[from] StoreError),
+}
+
+/// Strategy of the data availability sampling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DaserConfig {
+    /// Number of random samples requested for each height.
+    ///
+    /// It is capped at the number of shares in the [`ExtendedDataSquare`], in which
+    /// case the whole square is sampled.
+    ///
+    /// [`ExtendedDataSquare`]: celestia_types::ExtendedDataSquare
+    pub samples_per_height: usize,
+    /// Maximum number of heights sampled at the same time.
+    pub concurrency_limit: usize,
+    /// Time after which a sample that wasn't retrieved is considered unavailable.
+    pub sample_timeout: Duration,
+    /// Time after which the sampling of a height is retried, if it failed without
+    /// reaching a verdict, eg. because the node wasn't connected to any peers.
+    pub retry_delay: Duration,
+    /// Whether to sample the newest heights first.
+    ///
+    /// When enabled, the heads arriving from the network are sampled as soon as they
+    /// are synchronized and the older heights are caught up on afterwards. Otherwise
+    /// the heights are sampled from the oldest one.
+    pub prioritize_recent: bool,
+    /// Number of the most recently sampled heights whose [`SamplingEvidence`] is kept
+    /// in memory. The evidence isn't kept if set to `0`.
+    pub retained_evidence: usize,
+}
+
+impl Default for DaserConfig {
+    fn default() -> Self {
+        DaserConfig {
+            samples_per_height: DEFAULT_SAMPLES_PER_HEIGHT,
+            concurrency_limit: DEFAULT_CONCURRENCY_LIMIT,
+            sample_timeout: DEFAULT_SAMPLE_TIMEOUT,
+            retry_delay: DEFAULT_RETRY_DELAY,
+            prioritize_recent: true,
+            retained_evidence: 0,
+        }
+    }
+}
+
+/// Evidence of the data availability sampling of a single block.
+///
+/// It holds the [`Sample`]s retrieved and verified against the header of the block,
+/// each with its share and the proof of its inclusion in the row or column root.
+#[derive(Clone)]
+pub struct SamplingEvidence {
+    /// Height of the sampled block.
+    pub height: u64,
+    /// Whether the block was accepted as available.
+    pub accepted: bool,
+    /// Coordinates `(row, column)` of all the sampled shares, including the ones that
+    /// weren't retrieved in time.
+    pub coordinates: Vec<(u16, u16)>,
+    /// Samples which were retrieved and verified.
+    pub samples: Vec<Sample>,
+}
+
+/// Component responsible for the data availability sampling of the synchronized headers.
+#[derive(Debug)]
+pub struct Daser<S>
+where
+    S: Store + 'static,
+{
+    cancellation_token: CancellationToken,
+    evidence: Arc<EvidenceCache>,
+    _store: PhantomData<S>,
+}
+
+/// Arguments used to configure the [`Daser`].
+pub struct DaserArgs<S>
+where
+    S: Store + 'static,
+{
+    /// Handler for the peer to peer messaging.
+    pub p2p: Arc<P2p<S>>,
+    /// Headers storage.
+    pub store: Arc<S>,
+    /// Publisher of the [`NodeEvent`]s.
+    pub event_pub: EventPublisher,
+    /// Watcher for the ranges of the headers present in the store, eg. from
+    /// [`Syncer::stored_ranges_watcher`].
+    ///
+    /// [`Syncer::stored_ranges_watcher`]: crate::syncer::Syncer::stored_ranges_watcher
+    pub stored_ranges: watch::Receiver<Vec<RangeInclusive<u64>>>,
+    /// Strategy of the sampling.
+    pub config: DaserConfig,
+}
+
+impl<S> Daser<S>
+where
+    S: Store,
+{
+    /// Create and start the [`Daser`].
+    pub fn start(args: DaserArgs<S>) -> Self {
+        let cancellation_token = CancellationToken::new();
+        let evidence = Arc::new(EvidenceCache::new(args.config.retained_evidence));
+        let mut worker = Worker::new(args, evidence.clone(), cancellation_token.child_token());
+
+        spawn(async move {
+            worker.run().await;
+        });
+
+        Daser {
+            cancellation_token,
+            evidence,
+            _store: PhantomData,
+        }
+    }
+
+    /// Get the [`SamplingEvidence`] of the block at the given height.
+    ///
+    /// Returns `None` if the height wasn't sampled yet or its evidence was already
+    /// evicted, see [`DaserConfig::retained_evidence`].
+    pub fn sampling_evidence(&self, height: u64) -> Option<SamplingEvidence> {
+        self.evidence.get(height)
+    }
+
+    /// Stop the [`Daser`].
+    pub fn stop(&self) {
+        // Singal the Worker to stop.
+        self.cancellation_token.cancel();
+    }
+}
+
+impl<S> Drop for Daser<S>
+where
+    S: Store,
+{
+    fn drop(&mut self) {
+        self.cancellation_token.cancel();
+    }
+}
+
+struct Worker<S>
+where
+    S: Store + 'static,
+{
+    cancellation_token: CancellationToken,
+    p2p: Arc<P2p<S>>,
+    store: Arc<S>,
+    event_pub: EventPublisher,
+    stored_ranges: watch::Receiver<Vec<RangeInclusive<u64>>>,
+    config: DaserConfig,
+    pending: PendingHeights,
+    /// Heights being sampled or waiting to be retried.
+    in_flight: HashSet<u64>,
+    evidence: Arc<EvidenceCache>,
+}
+
+impl<S> Worker<S>
+where
+    S: Store,
+{
+    fn new(
+        args: DaserArgs<S>,
+        evidence: Arc<EvidenceCache>,
+        cancellation_token: CancellationToken,
+    ) -> Self {
+        Worker {
+            cancellation_token,
+            p2p: args.p2p,
+            store: args.store,
+            event_pub: args.event_pub,
+            stored_ranges: args.stored_ranges,
+            config: args.config,
+            pending: PendingHeights::default(),
+            in_flight: HashSet::new(),
+            evidence,
+        }
+    }
+
+    async fn run(&mut self) {
+        let concurrency_limit = self.config.concurrency_limit.max(1);
+        let mut in_progress = FuturesUnordered::new();
+        let mut retries = FuturesUnordered::new();
+        let mut stored_ranges_closed = false;
+
+        self.stored_ranges.mark_changed();
+
+        loop {
+            while in_progress.len() < concurrency_limit {
+                let next = if self.config.prioritize_recent {
+                    self.pending.pop_newest()
+                } else {
+                    self.pending.pop_oldest()
+                };
+                let Some(height) = next else {
+                    break;
+                };
+
+                let p2p = self.p2p.clone();
+                let store = self.store.clone();
+                let event_pub = self.event_pub.clone();
+                let evidence = self.evidence.clone();
+                let config = self.config;
+
+                self.in_flight.insert(height);
+                in_progress.push(async move {
+                    let res =
+                        sample_height(&p2p, &*store, &event_pub, &evidence, height, config).await;
+                    (height, res)
+                });
+            }
+
+            select! {
+                _ = self.cancellation_token.cancelled() => {
+                    break;
+                }
+                res = self.stored_ranges.changed(), if !stored_ranges_closed => {
+                    if res.is_err() {
+                        // Syncer stopped, finish sampling what is already known
+                        stored_ranges_closed = true;
+                        continue;
+                    }
+                    self.schedule_unsampled().await;
+                }
+                Some((height, res)) = in_progress.next(), if !in_progress.is_empty() => {
+                    match res {
+                        Ok(Some(true)) => debug!("Block {height} accepted"),
+                        Ok(Some(false)) => info!("Block {height} rejected"),
+                        Ok(None) => debug!("Skipped sampling of block {height}"),
+                        Err(e) => {
+                            warn!("Sampling of block {height} failed: {e}. Retrying.");
+                            let retry_delay = self.config.retry_delay;
+                            retries.push(async move {
+                                sleep(retry_delay).await;
+                                height
+                            });
+                            continue;
+                        }
+                    }
+                    self.in_flight.remove(&height);
+                }
+                Some(height) = retries.next(), if !retries.is_empty() => {
+                    self.in_flight.remove(&height);
+                    self.pending.insert(height..height + 1);
+                }
+            }
+        }
+
+        debug!("Daser stopped");
+    }
+
+    /// Schedule the heights of the store which weren't sampled yet.
+    async fn schedule_unsampled(&mut self) {
+        let unsampled = match self.store.unsampled_ranges().await {
+            Ok(unsampled) => unsampled,
+            Err(e) => {
+                warn!("Couldn't read the unsampled heights: {e}");
+                return;
+            }
+        };
+
+        for range in unsampled {
+            self.pending.insert(*range.start()..*range.end() + 1);
+        }
+
+        for height in &self.in_flight {
+            self.pending.remove(*height);
+        }
+    }
+}
+
+/// Sample the block at the given height and persist the result.
+///
+/// Returns whether the block was accepted, or `None` if it was already sampled
+/// or isn't in the store anymore. Nothing is persisted if any of the samples failed
+/// for another reason than not arriving in time, the error is returned instead.
+async fn sample_height<S>(
+    p2p: &P2p<S>,
+    store: &S,
+    event_pub: &EventPublisher,
+    evidence: &EvidenceCache,
+    height: u64,
+    config: DaserConfig,
+) -> Result<Option<bool>>
+where
+    S: Store,
+{
+    // Read the header together with its sampling metadata from a consistent view
+    let snapshot = match store.snapshot(height..=height).await {
+        Ok(snapshot) => snapshot,
+        Err(StoreError::NotFound) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    // Missing if removed by the pruner in the meantime
+    let Some(entry) = snapshot.headers.into_iter().next() else {
+        return Ok(None);
+    };
+
+    if entry.sampling_metadata.is_some() {
+        return Ok(None);
+    }
+
+    let header = entry.header;
+
+    let square_len = header.dah.square_len();
+    let coordinates = random_coordinates(square_len, config.samples_per_height);
+
+    // Wants of all the samples are sent together
+    let samples = p2p.get_samples(&coordinates, &header).await?;
+
+    let results = join_all(samples.into_iter().map(|sample| async move {
+        let started = Instant::now();
+        let sample = match timeout(config.sample_timeout, sample).await {
+            Ok(Ok(sample)) => Some(sample),
+            // Not arriving in time is the only verdict of unavailability
+            Err(_) | Ok(Err(P2pError::RequestTimedOut)) => None,
+            Ok(Err(e)) => return Err(e),
+        };
+        Ok((sample, started.elapsed()))
+    }))
+    .await
+    .into_iter()
+    .collect::<Result<Vec<_>, _>>()?;
+
+    let accepted = results.iter().all(|(sample, _)| sample.is_some());
+    let cids = coordinates
+        .iter()
+        .filter_map(|&(row, column)| {
+            let index = usize::from(row) * square_len + usize::from(column);
+            let id = SampleId::new(index, square_len, height).ok()?;
+            convert_cid(&id.to_cid(p2p.id_layout())).ok()
+        })
+        .collect();
+
+    store
+        .update_sampling_metadata(height, accepted, cids)
+        .await?;
+
+    // Published after storing, so the result is visible to the receivers of the events
+    for (&(row, column), (sample, took)) in coordinates.iter().zip(&results) {
+        event_pub.send(NodeEvent::SamplingFinished {
+            height,
+            row,
+            column,
+            accepted: sample.is_some(),
+            took: *took,
+        });
+    }
+
+    evidence.insert(SamplingEvidence {
+        height,
+        accepted,
+        coordinates,
+        samples: results
+            .into_iter()
+            .filter_map(|(sample, _)| sample)
+            .collect(),
+    });
+
+    Ok(Some(accepted))
+}
+
+/// Pick up to `amount` distinct random coordinates in a square of the given width.
+fn random_coordinates(square_len: usize, amount: usize) -> Vec<(u16, u16)> {
+    let total = square_len * square_len;
+    let amount = amount.min(total);
+
+    rand::seq::index::sample(&mut rand::thread_rng(), total, amount)
+        .into_iter()
+        .map(|index| {
+            // `square_len` of the valid header always fits in `u16`
+            let row = (index / square_len) as u16;
+            let column = (index % square_len) as u16;
+            (row, column)
+        })
+        .collect()
+}
+
+/// [`SamplingEvidence`] of the most recently sampled heights.
+struct EvidenceCache {
+    capacity: usize,
+    evidence: Mutex<RetainedEvidence>,
+}
+
+#[derive(Default)]
+struct RetainedEvidence {
+    by_height: HashMap<u64, SamplingEvidence>,
+    // Heights in the order they were sampled, from the least recent one
+    order: VecDeque<u64>,
+}
+
+impl EvidenceCache {
+    fn new(capacity: usize) -> Self {
+        EvidenceCache {
+            capacity,
+            evidence: Mutex::new(RetainedEvidence::default()),
+        }
+    }
+
+    fn get(&self, height: u64) -> Option<SamplingEvidence> {
+        let evidence = self.evidence.lock().unwrap_or_else(PoisonError::into_inner);
+        evidence.by_height.get(&height).cloned()
+    }
+
+    /// Keep the evidence, evicting the least recently sampled heights above the capacity.
+    fn insert(&self, sampling_evidence: SamplingEvidence) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut evidence = self.evidence.lock().unwrap_or_else(PoisonError::into_inner);
+        let height = sampling_evidence.height;
+
+        // a height sampled again becomes the most recent one
+        if evidence
+            .by_height
+            .insert(height, sampling_evidence)
+            .is_some()
+        {
+            evidence.order.retain(|h| *h != height);
+        }
+        evidence.order.push_back(height);
+
+        while evidence.order.len() > self.capacity {
+            if let Some(evicted) = evidence.order.pop_front() {
+                evidence.by_height.remove(&evicted);
+            }
+        }
+    }
+}
+
+impl fmt::Debug for EvidenceCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EvidenceCache")
+            .field("capacity", &self.capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Ranges of the heights waiting to be sampled, in ascending order.
+#[derive(Debug, Default)]
+struct PendingHeights {
+    ranges: Vec<Range<u64>>,
+}
+
+impl PendingHeights {
+    /// Schedule all the heights of the range.
+    fn insert(&mut self, heights: Range<u64>) {
+        if heights.is_empty() {
+            return;
+        }
+
+        // Merge with all the overlapping or adjacent ranges
+        let first = self
+            .ranges
+            .partition_point(|range| range.end < heights.start);
+        let last = self
+            .ranges
+            .partition_point(|range| range.start <= heights.end);
+        let mut merged = heights;
+
+        if first < last {
+            merged.start = merged.start.min(self.ranges[first].start);
+            merged.end = merged.end.max(self.ranges[last - 1].end);
+        }
+
+        self.ranges.splice(first..last, [merged]);
+    }
+
+    /// Unschedule the height.
+    fn remove(&mut self, height: u64) {
+        let index = self.ranges.partition_point(|range| range.end <= height);
+
+        let Some(range) = self.ranges.get_mut(index) else {
+            return;
+        };
+        if !range.contains(&height) {
+            return;
+        }
+
+        let above = height + 1..range.end;
+        range.end = height;
+
+        if range.is_empty() {
+            self.ranges.remove(index);
+            self.insert(above);
+        } else if !above.is_empty() {
+            self.ranges.insert(index + 1, above);
+        }
+    }
+
+    fn pop_newest(&mut self) -> Option<u64> {
+        let last = self.ranges.last_mut()?;
+        let height = last.next_back();
+
+        if last.is_empty() {
+            self.ranges.pop();
+        }
+
+        height
+    }
+
+    fn pop_oldest(&mut self) -> Option<u64> {
+        let first = self.ranges.first_mut()?;
+        let height = first.next();
+
+        if first.is_empty() {
+            self.ranges.remove(0);
+        }
+
+        height
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::EventChannel;
+    use crate::p2p::P2pError;
+    use crate::store::InMemoryStore;
+    use crate::test_utils::{deliver_shwap_block, gen_filled_store};
+    use celestia_proto::share::p2p::shwap::Sample as RawSample;
+    use celestia_types::nmt::Namespace;
+    use celestia_types::test_utils::ExtendedHeaderGenerator;
+    use celestia_types::AxisType;
+    use prost::Message;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    use tokio::test as async_test;
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as async_test;
+
+    #[test]
+    fn random_coordinates_are_distinct_and_in_range() {
+        let coordinates = random_coordinates(8, 16);
+        assert_eq!(coordinates.len(), 16);
+
+        for (i, &(row, column)) in coordinates.iter().enumerate() {
+            assert!(row < 8 && column < 8);
+            assert!(!coordinates[..i].contains(&(row, column)));
+        }
+    }
+
+    #[test]
+    fn random_coordinates_capped_at_square_size() {
+        let mut coordinates = random_coordinates(2, 16);
+        coordinates.sort();
+
+        assert_eq!(coordinates, vec![(0, 0), (0, 1), (1, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn pending_heights_order() {
+        let mut pending = PendingHeights::default();
+        pending.insert(3..6);
+
+        assert_eq!(pending.pop_newest(), Some(5));
+
+        pending.insert(6..8);
+        assert_eq!(pending.pop_newest(), Some(7));
+        assert_eq!(pending.pop_oldest(), Some(3));
+        assert_eq!(pending.pop_newest(), Some(6));
+        assert_eq!(pending.pop_newest(), Some(4));
+        assert_eq!(pending.pop_newest(), None);
+
+        // Heights below the already scheduled ones
+        pending.insert(1..3);
+        pending.insert(8..9);
+        assert_eq!(pending.pop_oldest(), Some(1));
+        assert_eq!(pending.pop_oldest(), Some(2));
+        assert_eq!(pending.pop_oldest(), Some(8));
+        assert_eq!(pending.pop_oldest(), None);
+    }
+
+    #[test]
+    fn pending_heights_merge_and_split() {
+        let mut pending = PendingHeights::default();
+        pending.insert(5..8);
+        pending.insert(1..3);
+        pending.insert(2..6);
+        assert_eq!(pending.ranges, vec![1..8]);
+
+        // Scheduling the same heights again doesn't duplicate them
+        pending.insert(3..5);
+        assert_eq!(pending.ranges, vec![1..8]);
+
+        pending.remove(4);
+        pending.remove(1);
+        pending.remove(7);
+        pending.remove(10);
+        assert_eq!(pending.ranges, vec![2..4, 5..7]);
+    }
+
+    #[test]
+    fn evidence_cache_keeps_recent_heights() {
+        let evidence = |height| SamplingEvidence {
+            height,
+            accepted: true,
+            coordinates: vec![],
+            samples: vec![],
+        };
+
+        let cache = EvidenceCache::new(2);
+        for height in [3, 1, 2] {
+            cache.insert(evidence(height));
+        }
+
+        // evicted in the sampling order, not by the height
+        assert!(cache.get(3).is_none());
+        assert_eq!(cache.get(1).unwrap().height, 1);
+        assert_eq!(cache.get(2).unwrap().height, 2);
+
+        // sampling a height again makes it the most recent one
+        cache.insert(evidence(1));
+        cache.insert(evidence(4));
+        assert!(cache.get(2).is_none());
+        assert_eq!(cache.get(1).unwrap().height, 1);
+        assert_eq!(cache.get(4).unwrap().height, 4);
+
+        let cache = EvidenceCache::new(0);
+        cache.insert(evidence(1));
+        assert!(cache.get(1).is_none());
+    }
+
+    #[async_test]
+    async fn samples_not_arriving_in_time_reject_block() {
+        let (store, _) = gen_filled_store(1);
+        let store = Arc::new(store);
+        let (p2p, mut handle) = P2p::<InMemoryStore>::mocked();
+        let (_ranges_tx, ranges_rx) = watch::channel(vec![1..=1]);
+        let events = EventChannel::new();
+        let mut event_sub = events.subscribe();
+
+        let _daser = Daser::start(DaserArgs {
+            p2p: Arc::new(p2p),
+            store: store.clone(),
+            event_pub: events.publisher(),
+            stored_ranges: ranges_rx,
+            config: DaserConfig {
+                samples_per_height: 2,
+                sample_timeout: Duration::from_millis(10),
+                ..DaserConfig::default()
+            },
+        });
+
+        // samples are never delivered
+        let wants = handle.expect_get_shwap_cids().await;
+        assert_eq!(wants.len(), 2);
+
+        for _ in 0..2 {
+            let event = event_sub.recv().await.unwrap().event;
+            assert!(matches!(
+                event,
+                NodeEvent::SamplingFinished {
+                    height: 1,
+                    accepted: false,
+                    ..
+                }
+            ));
+        }
+
+        // The result is stored before the events are published
+        let metadata = store.get_sampling_metadata(1).await.unwrap().unwrap();
+        assert!(!metadata.accepted);
+        assert_eq!(metadata.cids_sampled.len(), 2);
+        handle.expect_no_cmd().await;
+        drop(wants);
+    }
+
+    #[async_test]
+    async fn failed_sampling_is_retried() {
+        let (store, _) = gen_filled_store(1);
+        let store = Arc::new(store);
+        let (p2p, mut handle) = P2p::<InMemoryStore>::mocked();
+        let (_ranges_tx, ranges_rx) = watch::channel(vec![1..=1]);
+        let events = EventChannel::new();
+        let mut event_sub = events.subscribe();
+
+        let _daser = Daser::start(DaserArgs {
+            p2p: Arc::new(p2p),
+            store: store.clone(),
+            event_pub: events.publisher(),
+            stored_ranges: ranges_rx,
+            config: DaserConfig {
+                samples_per_height: 2,
+                retry_delay: Duration::from_millis(100),
+                ..DaserConfig::default()
+            },
+        });
+
+        let wants = handle.expect_get_shwap_cids().await;
+        assert_eq!(wants.len(), 2);
+        for (_, respond_to) in wants {
+            respond_to
+                .try_send(Err(P2pError::NoConnectedPeers))
+                .unwrap();
+        }
+
+        // Give the daser time to handle the failure
+        crate::executor::sleep(Duration::from_millis(50)).await;
+
+        // nothing is known about the availability of the block yet
+        assert!(store.get_sampling_metadata(1).await.unwrap().is_none());
+        assert!(event_sub.try_recv().is_err());
+
+        // it's sampled again after the delay
+        let wants = handle.expect_get_shwap_cids().await;
+        assert_eq!(wants.len(), 2);
+        for (cid, _) in wants {
+            let id = SampleId::try_from(cid).unwrap();
+            assert_eq!(id.row.block_height, 1);
+        }
+    }
+
+    #[async_test]
+    async fn heights_below_the_tail_are_sampled() {
+        let mut gen = ExtendedHeaderGenerator::new();
+        let headers = gen.next_many(3);
+        let store = InMemoryStore::new();
+        store.append_single_unchecked(headers[2].clone()).unwrap();
+        store
+            .update_sampling_metadata(3, true, vec![])
+            .await
+            .unwrap();
+        let store = Arc::new(store);
+        let (p2p, mut handle) = P2p::<InMemoryStore>::mocked();
+        let (ranges_tx, ranges_rx) = watch::channel(vec![3..=3]);
+
+        let _daser = Daser::start(DaserArgs {
+            p2p: Arc::new(p2p),
+            store: store.clone(),
+            event_pub: EventChannel::new().publisher(),
+            stored_ranges: ranges_rx,
+            config: DaserConfig {
+                samples_per_height: 1,
+                ..DaserConfig::default()
+            },
+        });
+
+        handle.expect_no_cmd().await;
+
+        // backward syncing prepends the headers below the tail
+        store.prepend_single_unchecked(headers[1].clone()).unwrap();
+        store.prepend_single_unchecked(headers[0].clone()).unwrap();
+        ranges_tx.send_replace(vec![1..=3]);
+
+        let mut sampled = Vec::new();
+        for _ in 0..2 {
+            let mut wants = handle.expect_get_shwap_cids().await;
+            assert_eq!(wants.len(), 1);
+            let (cid, _respond_to) = wants.remove(0);
+            sampled.push(SampleId::try_from(cid).unwrap().row.block_height);
+        }
+        sampled.sort();
+
+        assert_eq!(sampled, vec![1, 2]);
+    }
+
+    #[async_test]
+    async fn failed_request_is_not_persisted() {
+        let (store, _) = gen_filled_store(1);
+        let store = Arc::new(store);
+        let (p2p, handle) = P2p::<InMemoryStore>::mocked();
+        let (_ranges_tx, ranges_rx) = watch::channel(vec![1..=1]);
+        let events = EventChannel::new();
+        let mut event_sub = events.subscribe();
+
+        // requests fail, as nothing is handling them
+        drop(handle);
+
+        let _daser = Daser::start(DaserArgs {
+            p2p: Arc::new(p2p),
+            store: store.clone(),
+            event_pub: events.publisher(),
+            stored_ranges: ranges_rx,
+            config: DaserConfig {
+                samples_per_height: 2,
+                ..DaserConfig::default()
+            },
+        });
+
+        // Give the daser time to sample the block
+        crate::executor::sleep(Duration::from_millis(50)).await;
+
+        assert!(store.get_sampling_metadata(1).await.unwrap().is_none());
+        assert!(event_sub.try_recv().is_err());
+    }
+
+    #[async_test]
+    async fn sampled_heights_are_not_sampled_again() {
+        let (store, _) = gen_filled_store(2);
+        store
+            .update_sampling_metadata(1, true, vec![])
+            .await
+            .unwrap();
+        let store = Arc::new(store);
+        let (p2p, mut handle) = P2p::<InMemoryStore>::mocked();
+        let (_ranges_tx, ranges_rx) = watch::channel(vec![1..=2]);
+
+        let _daser = Daser::start(DaserArgs {
+            p2p: Arc::new(p2p),
+            store: store.clone(),
+            event_pub: EventChannel::new().publisher(),
+            stored_ranges: ranges_rx,
+            config: DaserConfig {
+                samples_per_height: 1,
+                ..DaserConfig::default()
+            },
+        });
+
+        let mut wants = handle.expect_get_shwap_cids().await;
+        assert_eq!(wants.len(), 1);
+        let (cid, respond_to) = wants.remove(0);
+        let id = SampleId::try_from(cid).unwrap();
+        assert_eq!(id.row.block_height, 2);
+        respond_to
+            .try_send(Err(P2pError::NoConnectedPeers))
+            .unwrap();
+
+        handle.expect_no_cmd().await;
+    }
+
+    #[async_test]
+    async fn evidence_of_sampled_heights_is_retained() {
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+        let mut gen = ExtendedHeaderGenerator::new();
+        let (header, eds) = gen.next_with_eds(2, &[namespace]);
+        let dah = header.dah.clone();
+        let store = Arc::new(InMemoryStore::new());
+        store.append_single_unchecked(header).unwrap();
+        let (p2p, mut handle) = P2p::<InMemoryStore>::mocked();
+        let (_ranges_tx, ranges_rx) = watch::channel(vec![1..=1]);
+
+        let daser = Daser::start(DaserArgs {
+            p2p: Arc::new(p2p),
+            store: store.clone(),
+            event_pub: EventChannel::new().publisher(),
+            stored_ranges: ranges_rx,
+            config: DaserConfig {
+                samples_per_height: 2,
+                retained_evidence: 1,
+                ..DaserConfig::default()
+            },
+        });
+
+        let wants = handle.expect_get_shwap_cids().await;
+        assert_eq!(wants.len(), 2);
+        for (cid, respond_to) in wants {
+            let id = SampleId::try_from(cid).unwrap();
+            let index = usize::from(id.row.index) * eds.square_len() + usize::from(id.index);
+            let sample = Sample::new(AxisType::Row, index, &eds, 1).unwrap();
+            deliver_shwap_block(&respond_to, RawSample::from(sample).encode_to_vec());
+        }
+
+        // verified samples are stored to be provided to other peers
+        handle.expect_verified_shwap_blocks(2).await;
+
+        // Give the daser time to persist the result
+        crate::executor::sleep(Duration::from_millis(50)).await;
+
+        let evidence = daser.sampling_evidence(1).unwrap();
+        assert!(evidence.accepted);
+        assert_eq!(evidence.coordinates.len(), 2);
+        assert_eq!(evidence.samples.len(), 2);
+
+        for sample in &evidence.samples {
+            sample.validate(&dah).unwrap();
+        }
+
+        assert!(daser.sampling_evidence(2).is_none());
+    }
+}
@@ -0,0 +1,638 @@
+//! On-disk storage of the whole [`ExtendedDataSquare`]s.
+//!
+//! Instead of keeping each share as a separate database entry, [`EdsStore`]
+//! persists every square as a single [CARv1] file, keyed by the `DataHash` of
+//! the block, similarly to the `eds.Store` of celestia-node. The roots of the
+//! CAR are the row and column roots of the [`DataAvailabilityHeader`], followed
+//! by the shares of the original data square in row-major order. The parity
+//! data is recomputed on load, and the few most recently loaded squares are kept
+//! in memory.
+//!
+//! [`EdsStore`] implements [`Blockstore`], serving the shwap [`Row`], [`Sample`]
+//! and [`NamespacedData`] containers requested over bitswap directly from the
+//! stored squares.
+//!
+//! [CARv1]: https://ipld.io/specs/transport/car/carv1/
+
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use async_trait::async_trait;
+use blockstore::{Blockstore, BlockstoreError};
+use celestia_types::hash::Hash;
+use celestia_types::namespaced_data::{
+    NamespacedData, NamespacedDataId, NAMESPACED_DATA_ID_MULTIHASH_CODE,
+};
+use celestia_types::nmt::{
+    NamespacedHash, NamespacedHashExt, NMT_CODEC, NMT_ID_SIZE, NMT_MULTIHASH_CODE,
+};
+use celestia_types::row::{IdLayout, Row, RowId};
+use celestia_types::sample::{Sample, SampleId};
+use celestia_types::AxisType;
+use celestia_types::{DataAvailabilityHeader, ExtendedDataSquare};
+use cid::CidGeneric;
+use multihash::Multihash;
+use prost::Message;
+use sha2::{Digest, Sha256};
+use tokio::task::{spawn_blocking, JoinError};
+
+mod car;
+
+/// Codec of the CAR blocks holding the shares.
+const RAW_CODEC: u64 = 0x55;
+/// Multihash code of the sha2-256.
+const SHA256_CODE: u64 = 0x12;
+
+/// Number of the decoded squares kept in memory.
+const SQUARES_CACHE_SIZE: usize = 8;
+
+const BLOCKS_DIR: &str = "blocks";
+const HEIGHTS_DIR: &str = "heights";
+
+/// CIDs of the CAR roots and blocks, big enough to hold the nmt roots.
+type Cid = CidGeneric<NMT_ID_SIZE>;
+
+type Result<T, E = EdsStoreError> = std::result::Result<T, E>;
+
+/// Square decoded from the CAR file, together with its roots.
+type LoadedSquare = Arc<(ExtendedDataSquare, DataAvailabilityHeader)>;
+
+/// Representation of all the errors that can occur when interacting with the [`EdsStore`].
+#[derive(Debug, thiserror::Error)]
+pub enum EdsStoreError {
+    /// Square not found in the store.
+    #[error("EDS not found in store")]
+    NotFound,
+
+    /// Stored file is not a valid CAR of the square.
+    #[error("Invalid CAR file: {0}")]
+    InvalidCar(String),
+
+    /// [`DataAvailabilityHeader`] doesn't match the square.
+    #[error("Data availability header doesn't match the EDS")]
+    DahMismatch,
+
+    /// An error propagated from the IO operation.
+    #[error("Received io error from eds storage: {0}")]
+    IoError(#[from] io::Error),
+
+    /// An error propagated from the async executor.
+    #[error("Received error from executor: {0}")]
+    ExecutorError(String),
+
+    /// An error propagated from the [`celestia_types`].
+    #[error(transparent)]
+    CelestiaTypes(#[from] celestia_types::Error),
+}
+
+impl From<JoinError> for EdsStoreError {
+    fn from(error: JoinError) -> EdsStoreError {
+        EdsStoreError::ExecutorError(error.to_string())
+    }
+}
+
+/// A flatfile storage of the [`ExtendedDataSquare`]s.
+#[derive(Debug, Clone)]
+pub struct EdsStore {
+    base_path: PathBuf,
+    id_layout: IdLayout,
+    cache: Arc<Mutex<VecDeque<(Hash, LoadedSquare)>>>,
+}
+
+impl EdsStore {
+    /// Create or open the store in the given directory.
+    pub async fn new(base_path: impl AsRef<Path>) -> Result<Self> {
+        let base_path = base_path.as_ref().to_owned();
+
+        spawn_blocking(move || {
+            fs::create_dir_all(base_path.join(BLOCKS_DIR))?;
+            fs::create_dir_all(base_path.join(HEIGHTS_DIR))?;
+            Ok(EdsStore {
+                base_path,
+                id_layout: IdLayout::default(),
+                cache: Arc::default(),
+            })
+        })
+        .await?
+    }
+
+    /// Serve the shwap containers with the identifiers in the given [`IdLayout`].
+    pub fn with_id_layout(mut self, id_layout: IdLayout) -> Self {
+        self.id_layout = id_layout;
+        self
+    }
+
+    /// Store the [`ExtendedDataSquare`] of the block at the given height.
+    ///
+    /// Blocks with the same data share a single file.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`EdsStoreError::DahMismatch`] if the roots of the provided square don't
+    /// match the ones in the [`DataAvailabilityHeader`].
+    pub async fn put(
+        &self,
+        height: u64,
+        eds: &ExtendedDataSquare,
+        dah: &DataAvailabilityHeader,
+    ) -> Result<()> {
+        let data_hash = dah.hash();
+        let car_path = self.car_path(&data_hash);
+        let height_path = self.height_path(height);
+        let eds = eds.clone();
+        let dah = dah.clone();
+
+        spawn_blocking(move || {
+            if !car_path.exists() {
+                dah.verify_eds(&eds)
+                    .map_err(|_| EdsStoreError::DahMismatch)?;
+                write_atomically(&car_path, &encode_car(&eds, &dah)?)?;
+            }
+            write_atomically(&height_path, data_hash.as_bytes())
+        })
+        .await?
+    }
+
+    /// Get the [`ExtendedDataSquare`] of the given `DataHash`.
+    pub async fn get(&self, data_hash: &Hash) -> Result<ExtendedDataSquare> {
+        let square = self.load(*data_hash).await?;
+        Ok(square.0.clone())
+    }
+
+    /// Get the [`ExtendedDataSquare`] of the block at the given height.
+    pub async fn get_by_height(&self, height: u64) -> Result<ExtendedDataSquare> {
+        let square = self.load_by_height(height).await?;
+        Ok(square.0.clone())
+    }
+
+    /// Check if the store has a square of the given `DataHash`.
+    pub async fn has(&self, data_hash: &Hash) -> bool {
+        let car_path = self.car_path(data_hash);
+        spawn_blocking(move || car_path.exists())
+            .await
+            .unwrap_or(false)
+    }
+
+    /// Get the [`Row`] of the square.
+    pub async fn get_row(&self, id: RowId) -> Result<Row> {
+        let square = self.load_by_height(id.block_height).await?;
+        let eds = &square.0;
+
+        if usize::from(id.index) >= eds.square_len() {
+            return Err(EdsStoreError::NotFound);
+        }
+
+        Ok(Row::new(id.index, eds, id.block_height)?)
+    }
+
+    /// Get the [`Sample`] of the square, with the proof of inclusion in the row.
+    pub async fn get_sample(&self, id: SampleId) -> Result<Sample> {
+        let square = self.load_by_height(id.row.block_height).await?;
+        let eds = &square.0;
+        let square_len = eds.square_len();
+        let (row, col) = (usize::from(id.row.index), usize::from(id.index));
+
+        if row >= square_len || col >= square_len {
+            return Err(EdsStoreError::NotFound);
+        }
+
+        let index = row * square_len + col;
+        Ok(Sample::new(AxisType::Row, index, eds, id.row.block_height)?)
+    }
+
+    /// Get the [`NamespacedData`] of the row of the square.
+    ///
+    /// Returns [`EdsStoreError::NotFound`] if the row doesn't contain the namespace.
+    pub async fn get_namespaced_data(&self, id: NamespacedDataId) -> Result<NamespacedData> {
+        let height = id.row.block_height;
+        let square = self.load_by_height(height).await?;
+        let (eds, dah) = &*square;
+
+        for data in eds.get_namespaced_data_stream(id.namespace, dah, height) {
+            let data = data?;
+            if data.namespaced_data_id == id {
+                return Ok(data);
+            }
+        }
+
+        Err(EdsStoreError::NotFound)
+    }
+
+    async fn load_by_height(&self, height: u64) -> Result<LoadedSquare> {
+        let height_path = self.height_path(height);
+
+        let data_hash = spawn_blocking(move || match fs::read(height_path) {
+            Ok(bytes) => bytes
+                .try_into()
+                .map(Hash::Sha256)
+                .map_err(|_| EdsStoreError::InvalidCar(format!("corrupted index of {height}"))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Err(EdsStoreError::NotFound),
+            Err(e) => Err(e.into()),
+        })
+        .await??;
+
+        self.load(data_hash).await
+    }
+
+    async fn load(&self, data_hash: Hash) -> Result<LoadedSquare> {
+        if let Some(square) = self.cached(&data_hash) {
+            return Ok(square);
+        }
+
+        let car_path = self.car_path(&data_hash);
+
+        let square = spawn_blocking(move || {
+            let bytes = match fs::read(car_path) {
+                Ok(bytes) => bytes,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    return Err(EdsStoreError::NotFound)
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            let (eds, dah) = decode_car(&bytes)?;
+
+            if dah.hash() != data_hash {
+                return Err(EdsStoreError::InvalidCar(
+                    "roots don't match the data hash".to_owned(),
+                ));
+            }
+
+            Ok(Arc::new((eds, dah)))
+        })
+        .await??;
+
+        let mut cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
+        if !cache.iter().any(|(hash, _)| *hash == data_hash) {
+            if cache.len() >= SQUARES_CACHE_SIZE {
+                cache.pop_front();
+            }
+            cache.push_back((data_hash, square.clone()));
+        }
+
+        Ok(square)
+    }
+
+    fn cached(&self, data_hash: &Hash) -> Option<LoadedSquare> {
+        let mut cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
+        let pos = cache.iter().position(|(hash, _)| hash == data_hash)?;
+        // move to the back, so that it's evicted last
+        let entry = cache.remove(pos)?;
+        let square = entry.1.clone();
+        cache.push_back(entry);
+        Some(square)
+    }
+
+    fn car_path(&self, data_hash: &Hash) -> PathBuf {
+        let name = format!("{}.car", hex::encode_upper(data_hash.as_bytes()));
+        self.base_path.join(BLOCKS_DIR).join(name)
+    }
+
+    fn height_path(&self, height: u64) -> PathBuf {
+        self.base_path.join(HEIGHTS_DIR).join(height.to_string())
+    }
+}
+
+#[async_trait]
+impl Blockstore for EdsStore {
+    async fn get<const S: usize>(
+        &self,
+        cid: &CidGeneric<S>,
+    ) -> Result<Option<Vec<u8>>, BlockstoreError> {
+        let layout = self.id_layout;
+
+        let res = match cid.hash().code() {
+            code if code == RowId::multihash_code(layout) => {
+                let id = RowId::from_cid(cid, layout)?;
+                self.get_row(id)
+                    .await
+                    .map(|row| row.into_raw(layout).encode_to_vec())
+            }
+            code if code == SampleId::multihash_code(layout) => {
+                let id = SampleId::from_cid(cid, layout)?;
+                self.get_sample(id)
+                    .await
+                    .map(|sample| sample.into_raw(layout).encode_to_vec())
+            }
+            NAMESPACED_DATA_ID_MULTIHASH_CODE => {
+                let id = NamespacedDataId::from_cid(cid, layout)?;
+                self.get_namespaced_data(id)
+                    .await
+                    .map(|data| data.into_raw(layout).encode_to_vec())
+            }
+            _ => return Ok(None),
+        };
+
+        match res {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(EdsStoreError::NotFound) => Ok(None),
+            Err(e) => Err(BlockstoreError::StorageError(e.to_string())),
+        }
+    }
+
+    async fn put_keyed<const S: usize>(
+        &self,
+        _cid: &CidGeneric<S>,
+        _data: &[u8],
+    ) -> Result<(), BlockstoreError> {
+        Err(BlockstoreError::StorageError(
+            "EdsStore accepts only whole squares".to_owned(),
+        ))
+    }
+
+    async fn remove<const S: usize>(&self, _cid: &CidGeneric<S>) -> Result<(), BlockstoreError> {
+        Err(BlockstoreError::StorageError(
+            "EdsStore removes only whole squares".to_owned(),
+        ))
+    }
+}
+
+fn encode_car(eds: &ExtendedDataSquare, dah: &DataAvailabilityHeader) -> Result<Vec<u8>> {
+    let ods_len = eds.square_len() / 2;
+
+    let roots: Vec<_> = dah
+        .row_roots
+        .iter()
+        .chain(&dah.column_roots)
+        .map(nmt_root_cid)
+        .collect::<Result<_>>()?;
+
+    let mut shares = Vec::with_capacity(ods_len * ods_len);
+    for row in 0..ods_len {
+        shares.extend(eds.row(row)?.into_iter().take(ods_len));
+    }
+
+    let blocks = shares.iter().map(|share| (share_cid(share), &share[..]));
+
+    Ok(car::encode(&roots, blocks))
+}
+
+fn decode_car(bytes: &[u8]) -> Result<(ExtendedDataSquare, DataAvailabilityHeader)> {
+    let car = car::decode::<NMT_ID_SIZE>(bytes).map_err(EdsStoreError::InvalidCar)?;
+
+    let roots = car
+        .roots
+        .iter()
+        .map(|cid| {
+            if cid.codec() != NMT_CODEC || cid.hash().code() != NMT_MULTIHASH_CODE {
+                return Err(EdsStoreError::InvalidCar(
+                    "root is not an nmt cid".to_owned(),
+                ));
+            }
+            Ok(NamespacedHash::from_raw(cid.hash().digest())?)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let square_len = roots.len() / 2;
+    let ods_len = square_len / 2;
+
+    if roots.len() % 2 != 0 || car.blocks.len() != ods_len * ods_len {
+        return Err(EdsStoreError::InvalidCar(format!(
+            "{} shares don't match {} roots",
+            car.blocks.len(),
+            roots.len()
+        )));
+    }
+
+    let mut row_roots = roots;
+    let column_roots = row_roots.split_off(square_len);
+    let dah = DataAvailabilityHeader {
+        row_roots,
+        column_roots,
+    };
+
+    let shares = car
+        .blocks
+        .into_iter()
+        .enumerate()
+        .map(|(i, (_, share))| (i / ods_len, i % ods_len, share.to_vec()));
+    let eds = ExtendedDataSquare::reconstruct(shares, &dah)?;
+
+    Ok((eds, dah))
+}
+
+fn nmt_root_cid(root: &NamespacedHash) -> Result<Cid> {
+    let mh = Multihash::wrap(NMT_MULTIHASH_CODE, &root.to_array())
+        .map_err(|e| EdsStoreError::InvalidCar(e.to_string()))?;
+    Ok(Cid::new_v1(NMT_CODEC, mh))
+}
+
+fn share_cid(share: &[u8]) -> Cid {
+    let digest = Sha256::digest(share);
+    // sha256 digest always fits the multihash
+    let mh = Multihash::wrap(SHA256_CODE, &digest).expect("digest fits multihash");
+    Cid::new_v1(RAW_CODEC, mh)
+}
+
+/// Write the file through a temporary one, so that readers never see a partial write.
+fn write_atomically(path: &Path, data: &[u8]) -> Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, data)?;
+    fs::rename(tmp_path, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use celestia_proto::share::p2p::shwap::{
+        Data as RawNamespacedData, Row as RawRow, Sample as RawSample,
+    };
+    use celestia_types::nmt::Namespace;
+    use tempdir::TempDir;
+
+    const HEIGHT: u64 = 45577;
+
+    fn eds_and_dah() -> (ExtendedDataSquare, DataAvailabilityHeader) {
+        let eds_json = include_str!("../../types/test_data/shwap_samples/eds.json");
+        let eds: ExtendedDataSquare = serde_json::from_str(eds_json).unwrap();
+        let dah_json = include_str!("../../types/test_data/shwap_samples/dah.json");
+        let dah: DataAvailabilityHeader = serde_json::from_str(dah_json).unwrap();
+        (eds, dah)
+    }
+
+    async fn filled_store(dir: &TempDir) -> (EdsStore, ExtendedDataSquare, DataAvailabilityHeader) {
+        let store = EdsStore::new(dir.path()).await.unwrap();
+        let (eds, dah) = eds_and_dah();
+        store.put(HEIGHT, &eds, &dah).await.unwrap();
+        (store, eds, dah)
+    }
+
+    #[tokio::test]
+    async fn put_and_get() {
+        let dir = TempDir::new("eds-store").unwrap();
+        let (store, eds, dah) = filled_store(&dir).await;
+
+        assert!(store.has(&dah.hash()).await);
+        assert_eq!(store.get(&dah.hash()).await.unwrap(), eds);
+        assert_eq!(store.get_by_height(HEIGHT).await.unwrap(), eds);
+
+        // squares survive reopening the store
+        let store = EdsStore::new(dir.path()).await.unwrap();
+        assert_eq!(store.get_by_height(HEIGHT).await.unwrap(), eds);
+    }
+
+    #[tokio::test]
+    async fn same_square_shares_a_file() {
+        let dir = TempDir::new("eds-store").unwrap();
+        let (store, eds, dah) = filled_store(&dir).await;
+        store.put(HEIGHT + 1, &eds, &dah).await.unwrap();
+
+        let files = fs::read_dir(dir.path().join(BLOCKS_DIR)).unwrap().count();
+        assert_eq!(files, 1);
+        assert_eq!(store.get_by_height(HEIGHT + 1).await.unwrap(), eds);
+    }
+
+    #[tokio::test]
+    async fn not_found() {
+        let dir = TempDir::new("eds-store").unwrap();
+        let (store, _, _) = filled_store(&dir).await;
+
+        assert!(!store.has(&Hash::Sha256([0; 32])).await);
+        assert!(matches!(
+            store.get(&Hash::Sha256([0; 32])).await,
+            Err(EdsStoreError::NotFound)
+        ));
+        assert!(matches!(
+            store.get_by_height(HEIGHT + 1).await,
+            Err(EdsStoreError::NotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn put_with_wrong_dah() {
+        let dir = TempDir::new("eds-store").unwrap();
+        let store = EdsStore::new(dir.path()).await.unwrap();
+        let (eds, dah) = eds_and_dah();
+
+        let mut smaller_dah = dah.clone();
+        smaller_dah.row_roots.pop();
+        smaller_dah.column_roots.pop();
+        let err = store.put(HEIGHT, &eds, &smaller_dah).await.unwrap_err();
+        assert!(matches!(err, EdsStoreError::DahMismatch));
+
+        // same size, but a different root
+        let mut other_dah = dah.clone();
+        other_dah.row_roots.swap(0, 1);
+        let err = store.put(HEIGHT, &eds, &other_dah).await.unwrap_err();
+        assert!(matches!(err, EdsStoreError::DahMismatch));
+
+        assert!(!store.has(&other_dah.hash()).await);
+    }
+
+    #[tokio::test]
+    async fn loaded_squares_are_cached() {
+        let dir = TempDir::new("eds-store").unwrap();
+        let (store, eds, dah) = filled_store(&dir).await;
+
+        assert_eq!(store.get_by_height(HEIGHT).await.unwrap(), eds);
+
+        // served from memory, even after the file is gone
+        fs::remove_file(store.car_path(&dah.hash())).unwrap();
+        assert_eq!(store.get(&dah.hash()).await.unwrap(), eds);
+        store.get_row(RowId::new(0, HEIGHT).unwrap()).await.unwrap();
+
+        // cache is not shared with a reopened store
+        let store = EdsStore::new(dir.path()).await.unwrap();
+        assert!(matches!(
+            store.get(&dah.hash()).await,
+            Err(EdsStoreError::NotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn corrupted_car() {
+        let dir = TempDir::new("eds-store").unwrap();
+        let (store, _, dah) = filled_store(&dir).await;
+
+        let car_path = store.car_path(&dah.hash());
+        let mut bytes = fs::read(&car_path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        fs::write(&car_path, bytes).unwrap();
+
+        store.get_by_height(HEIGHT).await.unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn blockstore_serves_shwap_containers() {
+        let dir = TempDir::new("eds-store").unwrap();
+        let (store, eds, dah) = filled_store(&dir).await;
+
+        let row_id = RowId::new(1, HEIGHT).unwrap();
+        let cid = CidGeneric::try_from(row_id).unwrap();
+        let bytes = Blockstore::get(&store, &cid).await.unwrap().unwrap();
+        let row = Row::try_from(RawRow::decode(&bytes[..]).unwrap()).unwrap();
+        row.validate(&dah).unwrap();
+        assert_eq!(row.shares, eds.row(1).unwrap());
+
+        let sample_id = SampleId::new(5, eds.square_len(), HEIGHT).unwrap();
+        let cid = CidGeneric::try_from(sample_id).unwrap();
+        let bytes = Blockstore::get(&store, &cid).await.unwrap().unwrap();
+        let sample = Sample::try_from(RawSample::decode(&bytes[..]).unwrap()).unwrap();
+        sample.validate(&dah).unwrap();
+
+        let namespace = Namespace::new_v0(&[1, 187]).unwrap();
+        let expected = eds.get_namespaced_data(namespace, &dah, HEIGHT).unwrap();
+        let id = expected[0].namespaced_data_id;
+        let cid = CidGeneric::try_from(id).unwrap();
+        let bytes = Blockstore::get(&store, &cid).await.unwrap().unwrap();
+        let data =
+            NamespacedData::try_from(RawNamespacedData::decode(&bytes[..]).unwrap()).unwrap();
+        data.validate(&dah).unwrap();
+        assert_eq!(data.shares, expected[0].shares);
+    }
+
+    #[tokio::test]
+    async fn blockstore_serves_v1_layout() {
+        let dir = TempDir::new("eds-store").unwrap();
+        let (store, eds, dah) = filled_store(&dir).await;
+        let store = store.with_id_layout(IdLayout::V1);
+
+        let row_id = RowId::new(1, HEIGHT).unwrap();
+        let cid = row_id.to_cid(IdLayout::V1);
+        let bytes = Blockstore::get(&store, &cid).await.unwrap().unwrap();
+        let raw = RawRow::decode(&bytes[..]).unwrap();
+        let row = Row::from_raw(raw, IdLayout::V1).unwrap();
+        row.validate(&dah).unwrap();
+        assert_eq!(row.row_id, row_id);
+
+        let sample_id = SampleId::new(5, eds.square_len(), HEIGHT).unwrap();
+        let cid = sample_id.to_cid(IdLayout::V1);
+        let bytes = Blockstore::get(&store, &cid).await.unwrap().unwrap();
+        let raw = RawSample::decode(&bytes[..]).unwrap();
+        let sample = Sample::from_raw(raw, IdLayout::V1).unwrap();
+        sample.validate(&dah).unwrap();
+        assert_eq!(sample.sample_id, sample_id);
+
+        // draft ids are not understood by the v1 store
+        let cid = CidGeneric::try_from(row_id).unwrap();
+        assert!(Blockstore::get(&store, &cid).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn blockstore_missing_blocks() {
+        let dir = TempDir::new("eds-store").unwrap();
+        let (store, eds, _) = filled_store(&dir).await;
+
+        // unknown height
+        let cid = CidGeneric::try_from(RowId::new(0, HEIGHT + 1).unwrap()).unwrap();
+        assert_eq!(Blockstore::get(&store, &cid).await.unwrap(), None);
+
+        // row out of the square
+        let index = eds.square_len() as u16;
+        let cid = CidGeneric::try_from(RowId::new(index, HEIGHT).unwrap()).unwrap();
+        assert_eq!(Blockstore::get(&store, &cid).await.unwrap(), None);
+
+        // unsupported cid
+        let cid = share_cid(&[0; 512]);
+        assert_eq!(Blockstore::get(&store, &cid).await.unwrap(), None);
+
+        Blockstore::put_keyed(&store, &cid, &[0; 512])
+            .await
+            .unwrap_err();
+    }
+}
@@ -0,0 +1,311 @@
+//! Minimal encoding and decoding of the [CARv1] files.
+//!
+//! Only the subset of dag-cbor needed for the CAR header is supported, that is
+//! a map with the `roots` array of CIDs and the `version` integer.
+//!
+//! [CARv1]: https://ipld.io/specs/transport/car/carv1/
+
+use std::io::Cursor;
+
+use cid::CidGeneric;
+
+const CAR_VERSION: u64 = 1;
+/// CBOR tag used by dag-cbor to mark the CIDs.
+const CID_TAG: u64 = 42;
+
+const MAJOR_UINT: u8 = 0;
+const MAJOR_BYTES: u8 = 2;
+const MAJOR_TEXT: u8 = 3;
+const MAJOR_ARRAY: u8 = 4;
+const MAJOR_MAP: u8 = 5;
+const MAJOR_TAG: u8 = 6;
+
+/// Decoded CAR file with blocks borrowing from the underlying buffer.
+#[derive(Debug)]
+pub(crate) struct Car<'a, const S: usize> {
+    pub(crate) roots: Vec<CidGeneric<S>>,
+    pub(crate) blocks: Vec<(CidGeneric<S>, &'a [u8])>,
+}
+
+/// Encode the roots and blocks as a CAR file.
+pub(crate) fn encode<'a, const S: usize, I>(roots: &[CidGeneric<S>], blocks: I) -> Vec<u8>
+where
+    I: IntoIterator<Item = (CidGeneric<S>, &'a [u8])>,
+{
+    let header = encode_header(roots);
+
+    let mut car = Vec::new();
+    write_uvarint(&mut car, header.len() as u64);
+    car.extend_from_slice(&header);
+
+    for (cid, data) in blocks {
+        let cid = cid.to_bytes();
+        write_uvarint(&mut car, (cid.len() + data.len()) as u64);
+        car.extend_from_slice(&cid);
+        car.extend_from_slice(data);
+    }
+
+    car
+}
+
+/// Decode the CAR file.
+pub(crate) fn decode<const S: usize>(mut bytes: &[u8]) -> Result<Car<'_, S>, String> {
+    let header = read_section(&mut bytes)?;
+    let roots = decode_header(header)?;
+
+    let mut blocks = Vec::new();
+
+    while !bytes.is_empty() {
+        let section = read_section(&mut bytes)?;
+        let mut cursor = Cursor::new(section);
+        let cid =
+            CidGeneric::read_bytes(&mut cursor).map_err(|e| format!("invalid block cid: {e}"))?;
+        let data = &section[cursor.position() as usize..];
+
+        blocks.push((cid, data));
+    }
+
+    Ok(Car { roots, blocks })
+}
+
+fn read_section<'a>(bytes: &mut &'a [u8]) -> Result<&'a [u8], String> {
+    let len = read_uvarint(bytes).ok_or("invalid section length")?;
+    let len = usize::try_from(len).map_err(|_| "section length overflow")?;
+
+    if bytes.len() < len {
+        return Err("section truncated".to_owned());
+    }
+
+    let (section, rest) = bytes.split_at(len);
+    *bytes = rest;
+
+    Ok(section)
+}
+
+fn encode_header<const S: usize>(roots: &[CidGeneric<S>]) -> Vec<u8> {
+    let mut header = Vec::new();
+
+    write_cbor_head(&mut header, MAJOR_MAP, 2);
+
+    write_cbor_text(&mut header, "roots");
+    write_cbor_head(&mut header, MAJOR_ARRAY, roots.len() as u64);
+    for root in roots {
+        let cid = root.to_bytes();
+        write_cbor_head(&mut header, MAJOR_TAG, CID_TAG);
+        // dag-cbor prefixes CIDs with the multibase identity byte
+        write_cbor_head(&mut header, MAJOR_BYTES, cid.len() as u64 + 1);
+        header.push(0);
+        header.extend_from_slice(&cid);
+    }
+
+    write_cbor_text(&mut header, "version");
+    write_cbor_head(&mut header, MAJOR_UINT, CAR_VERSION);
+
+    header
+}
+
+fn decode_header<const S: usize>(mut header: &[u8]) -> Result<Vec<CidGeneric<S>>, String> {
+    let entries = read_cbor_head(&mut header, MAJOR_MAP)?;
+    let mut roots = None;
+    let mut version = None;
+
+    for _ in 0..entries {
+        match read_cbor_text(&mut header)? {
+            "roots" => {
+                let len = read_cbor_head(&mut header, MAJOR_ARRAY)?;
+                let mut cids = Vec::new();
+
+                for _ in 0..len {
+                    if read_cbor_head(&mut header, MAJOR_TAG)? != CID_TAG {
+                        return Err("root is not a cid".to_owned());
+                    }
+
+                    let bytes = read_cbor_bytes(&mut header)?;
+                    let cid = match bytes.split_first() {
+                        Some((0, cid)) => CidGeneric::try_from(cid),
+                        _ => return Err("invalid root cid prefix".to_owned()),
+                    };
+                    cids.push(cid.map_err(|e| format!("invalid root cid: {e}"))?);
+                }
+
+                roots = Some(cids);
+            }
+            "version" => version = Some(read_cbor_head(&mut header, MAJOR_UINT)?),
+            key => return Err(format!("unexpected header key: {key}")),
+        }
+    }
+
+    match version {
+        Some(CAR_VERSION) => roots.ok_or_else(|| "missing roots".to_owned()),
+        Some(version) => Err(format!("unsupported version: {version}")),
+        None => Err("missing version".to_owned()),
+    }
+}
+
+fn write_cbor_head(buf: &mut Vec<u8>, major: u8, value: u64) {
+    let major = major << 5;
+
+    if value < 24 {
+        buf.push(major | value as u8);
+    } else if let Ok(value) = u8::try_from(value) {
+        buf.push(major | 24);
+        buf.push(value);
+    } else if let Ok(value) = u16::try_from(value) {
+        buf.push(major | 25);
+        buf.extend_from_slice(&value.to_be_bytes());
+    } else if let Ok(value) = u32::try_from(value) {
+        buf.push(major | 26);
+        buf.extend_from_slice(&value.to_be_bytes());
+    } else {
+        buf.push(major | 27);
+        buf.extend_from_slice(&value.to_be_bytes());
+    }
+}
+
+fn write_cbor_text(buf: &mut Vec<u8>, text: &str) {
+    write_cbor_head(buf, MAJOR_TEXT, text.len() as u64);
+    buf.extend_from_slice(text.as_bytes());
+}
+
+fn read_cbor_head(buf: &mut &[u8], expected_major: u8) -> Result<u64, String> {
+    let (&initial, rest) = buf.split_first().ok_or("header truncated")?;
+
+    let major = initial >> 5;
+    if major != expected_major {
+        return Err(format!(
+            "unexpected cbor major type {major}, expected {expected_major}"
+        ));
+    }
+
+    let (len, value) = match initial & 0x1f {
+        n @ 0..=23 => (0, n as u64),
+        24 => (1, take_be(rest, 1)?),
+        25 => (2, take_be(rest, 2)?),
+        26 => (4, take_be(rest, 4)?),
+        27 => (8, take_be(rest, 8)?),
+        _ => return Err("unsupported cbor length encoding".to_owned()),
+    };
+
+    *buf = &rest[len..];
+
+    Ok(value)
+}
+
+fn read_cbor_bytes<'a>(buf: &mut &'a [u8]) -> Result<&'a [u8], String> {
+    let len = read_cbor_head(buf, MAJOR_BYTES)?;
+    take_slice(buf, len)
+}
+
+fn read_cbor_text<'a>(buf: &mut &'a [u8]) -> Result<&'a str, String> {
+    let len = read_cbor_head(buf, MAJOR_TEXT)?;
+    let text = take_slice(buf, len)?;
+    std::str::from_utf8(text).map_err(|_| "invalid utf8 in header".to_owned())
+}
+
+fn take_be(buf: &[u8], len: usize) -> Result<u64, String> {
+    let bytes = buf.get(..len).ok_or("header truncated")?;
+    Ok(bytes.iter().fold(0, |acc, b| (acc << 8) | *b as u64))
+}
+
+fn take_slice<'a>(buf: &mut &'a [u8], len: u64) -> Result<&'a [u8], String> {
+    let len = usize::try_from(len).map_err(|_| "length overflow")?;
+
+    if buf.len() < len {
+        return Err("header truncated".to_owned());
+    }
+
+    let (slice, rest) = buf.split_at(len);
+    *buf = rest;
+
+    Ok(slice)
+}
+
+fn write_uvarint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn read_uvarint(buf: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+
+    for (i, byte) in buf.iter().enumerate().take(10) {
+        value |= ((byte & 0x7f) as u64) << (7 * i);
+
+        if byte & 0x80 == 0 {
+            *buf = &buf[i + 1..];
+            return Some(value);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use multihash::Multihash;
+
+    type Cid = CidGeneric<64>;
+
+    fn cid(codec: u64, digest: &[u8]) -> Cid {
+        Cid::new_v1(codec, Multihash::wrap(0x12, digest).unwrap())
+    }
+
+    #[test]
+    fn car_roundtrip() {
+        let roots = vec![cid(0x71, &[1; 32]), cid(0x71, &[2; 32])];
+        let block1 = vec![7; 300];
+        let block2: [u8; 0] = [];
+        let blocks = vec![
+            (cid(0x55, &[3; 32]), &block1[..]),
+            (cid(0x55, &[4; 32]), &block2[..]),
+        ];
+
+        let bytes = encode(&roots, blocks.clone());
+        let car = decode::<64>(&bytes).unwrap();
+
+        assert_eq!(car.roots, roots);
+        assert_eq!(car.blocks, blocks);
+    }
+
+    #[test]
+    fn header_matches_dag_cbor() {
+        let root = cid(0x71, &[1; 32]);
+        let header = encode_header(&[root]);
+
+        let mut expected = vec![0xa2, 0x65];
+        expected.extend_from_slice(b"roots");
+        expected.extend_from_slice(&[0x81, 0xd8, 0x2a, 0x58, 37, 0]);
+        expected.extend_from_slice(&root.to_bytes());
+        expected.push(0x67);
+        expected.extend_from_slice(b"version");
+        expected.push(0x01);
+
+        assert_eq!(header, expected);
+    }
+
+    #[test]
+    fn decode_truncated() {
+        let bytes = encode(
+            &[cid(0x71, &[1; 32])],
+            [(cid(0x55, &[3; 32]), &[1, 2, 3][..])],
+        );
+
+        decode::<64>(&bytes[..bytes.len() - 1]).unwrap_err();
+        decode::<64>(&bytes[..10]).unwrap_err();
+    }
+
+    #[test]
+    fn uvarint_roundtrip() {
+        for value in [0, 1, 127, 128, 300, 16384, u64::MAX] {
+            let mut buf = Vec::new();
+            write_uvarint(&mut buf, value);
+            let mut slice = &buf[..];
+            assert_eq!(read_uvarint(&mut slice), Some(value));
+            assert!(slice.is_empty());
+        }
+    }
+}
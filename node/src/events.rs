@@ -0,0 +1,391 @@
+//! Events generated by the [`Node`].
+//!
+//! Components of the [`Node`] publish the [`NodeEvent`]s describing their progress
+//! to a shared broadcast channel. Any number of [`EventSubscriber`]s can be created
+//! with [`Node::event_subscriber`], each receiving all the events published after
+//! its creation.
+//!
+//! [`Node`]: crate::node::Node
+//! [`Node::event_subscriber`]: crate::node::Node::event_subscriber
+
+use std::fmt;
+use std::time::Duration;
+
+use celestia_tendermint::Time;
+use libp2p::PeerId;
+use serde::{Serialize, Serializer};
+use tokio::sync::broadcast;
+
+/// Number of the events retained for the subscribers which fall behind.
+const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+/// Representation of the errors that can occur when receiving the events.
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum RecvError {
+    /// All the publishers were dropped, no more events will be received.
+    #[error("Event channel closed")]
+    Closed,
+
+    /// The subscriber fell behind and the given number of the oldest events were skipped.
+    #[error("Subscriber lagged, {0} events skipped")]
+    Lagged(u64),
+}
+
+/// Representation of the errors that can occur when trying to receive the events.
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum TryRecvError {
+    /// There are no new events.
+    #[error("No new events")]
+    Empty,
+
+    /// All the publishers were dropped, no more events will be received.
+    #[error("Event channel closed")]
+    Closed,
+
+    /// The subscriber fell behind and the given number of the oldest events were skipped.
+    #[error("Subscriber lagged, {0} events skipped")]
+    Lagged(u64),
+}
+
+/// A channel through which the components of the [`Node`] publish the events.
+///
+/// [`Node`]: crate::node::Node
+#[derive(Debug)]
+pub struct EventChannel {
+    tx: broadcast::Sender<NodeEventInfo>,
+}
+
+impl EventChannel {
+    /// Create a new [`EventChannel`].
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        EventChannel { tx }
+    }
+
+    /// Create a new [`EventPublisher`].
+    pub fn publisher(&self) -> EventPublisher {
+        EventPublisher {
+            tx: self.tx.clone(),
+        }
+    }
+
+    /// Create a new [`EventSubscriber`].
+    pub fn subscribe(&self) -> EventSubscriber {
+        EventSubscriber {
+            rx: self.tx.subscribe(),
+        }
+    }
+}
+
+impl Default for EventChannel {
+    fn default() -> Self {
+        EventChannel::new()
+    }
+}
+
+/// Publisher of the [`NodeEvent`]s.
+#[derive(Debug, Clone)]
+pub struct EventPublisher {
+    tx: broadcast::Sender<NodeEventInfo>,
+}
+
+impl EventPublisher {
+    /// Publish the event, it is dropped if there are no subscribers.
+    pub(crate) fn send(&self, event: NodeEvent) {
+        let _ = self.tx.send(NodeEventInfo {
+            event,
+            time: Time::now(),
+        });
+    }
+}
+
+/// Subscriber of the [`NodeEvent`]s.
+#[derive(Debug)]
+pub struct EventSubscriber {
+    rx: broadcast::Receiver<NodeEventInfo>,
+}
+
+impl EventSubscriber {
+    /// Wait for the next event.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RecvError::Lagged`] if the subscriber fell behind. Subsequent
+    /// calls continue from the oldest event still retained in the channel.
+    pub async fn recv(&mut self) -> Result<NodeEventInfo, RecvError> {
+        self.rx.recv().await.map_err(|e| match e {
+            broadcast::error::RecvError::Closed => RecvError::Closed,
+            broadcast::error::RecvError::Lagged(skipped) => RecvError::Lagged(skipped),
+        })
+    }
+
+    /// Receive the next event if there is one available.
+    pub fn try_recv(&mut self) -> Result<NodeEventInfo, TryRecvError> {
+        self.rx.try_recv().map_err(|e| match e {
+            broadcast::error::TryRecvError::Empty => TryRecvError::Empty,
+            broadcast::error::TryRecvError::Closed => TryRecvError::Closed,
+            broadcast::error::TryRecvError::Lagged(skipped) => TryRecvError::Lagged(skipped),
+        })
+    }
+}
+
+/// The [`NodeEvent`] together with the time it was published.
+///
+/// Serializes to a structure convenient for the consumers outside of Rust, e.g.
+/// javascript, with the event tagged by its `type` in `snake_case`, peer ids as strings
+/// and durations as milliseconds.
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeEventInfo {
+    /// The event.
+    pub event: NodeEvent,
+    /// Time when the event was published.
+    pub time: Time,
+}
+
+/// Events generated by the components of the [`Node`].
+///
+/// [`Node`]: crate::node::Node
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum NodeEvent {
+    /// A peer connected.
+    PeerConnected {
+        /// The id of the peer.
+        #[serde(serialize_with = "serialize_display")]
+        id: PeerId,
+        /// Whether the peer is trusted.
+        trusted: bool,
+    },
+
+    /// A peer disconnected.
+    PeerDisconnected {
+        /// The id of the peer.
+        #[serde(serialize_with = "serialize_display")]
+        id: PeerId,
+        /// Whether the peer is trusted.
+        trusted: bool,
+    },
+
+    /// A header announced on the `header-sub` was added to the store.
+    AddedHeaderFromHeaderSub {
+        /// The height of the header.
+        height: u64,
+    },
+
+    /// Fetching of a range of headers started.
+    FetchingHeadersStarted {
+        /// The first height of the range.
+        from_height: u64,
+        /// The last height of the range.
+        to_height: u64,
+    },
+
+    /// A range of headers was fetched and stored.
+    FetchingHeadersFinished {
+        /// The first height of the range.
+        from_height: u64,
+        /// The last height of the range.
+        to_height: u64,
+        /// How long fetching and storing of the range took.
+        #[serde(rename = "took_ms", serialize_with = "serialize_millis")]
+        took: Duration,
+    },
+
+    /// Fetching or storing of a range of headers failed.
+    FetchingHeadersFailed {
+        /// The first height of the range.
+        from_height: u64,
+        /// The last height of the range.
+        to_height: u64,
+        /// The description of the failure.
+        error: String,
+        /// How long it took until the failure.
+        #[serde(rename = "took_ms", serialize_with = "serialize_millis")]
+        took: Duration,
+    },
+
+    /// Retrieval and verification of a sample finished.
+    SamplingFinished {
+        /// The height of the sampled block.
+        height: u64,
+        /// The row of the sample in the extended data square.
+        row: u16,
+        /// The column of the sample in the extended data square.
+        column: u16,
+        /// Whether the sample was retrieved and verified successfully.
+        accepted: bool,
+        /// How long the sampling took.
+        #[serde(rename = "took_ms", serialize_with = "serialize_millis")]
+        took: Duration,
+    },
+}
+
+impl fmt::Display for NodeEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NodeEvent::PeerConnected { id, trusted } => {
+                if *trusted {
+                    write!(f, "Trusted peer {id} connected")
+                } else {
+                    write!(f, "Peer {id} connected")
+                }
+            }
+            NodeEvent::PeerDisconnected { id, trusted } => {
+                if *trusted {
+                    write!(f, "Trusted peer {id} disconnected")
+                } else {
+                    write!(f, "Peer {id} disconnected")
+                }
+            }
+            NodeEvent::AddedHeaderFromHeaderSub { height } => {
+                write!(f, "Added header {height} from header-sub")
+            }
+            NodeEvent::FetchingHeadersStarted {
+                from_height,
+                to_height,
+            } => write!(f, "Fetching headers {from_height} until {to_height}"),
+            NodeEvent::FetchingHeadersFinished {
+                from_height,
+                to_height,
+                took,
+            } => write!(
+                f,
+                "Fetched headers {from_height} until {to_height} in {took:?}"
+            ),
+            NodeEvent::FetchingHeadersFailed {
+                from_height,
+                to_height,
+                error,
+                took,
+            } => write!(
+                f,
+                "Fetching headers {from_height} until {to_height} failed after {took:?}: {error}"
+            ),
+            NodeEvent::SamplingFinished {
+                height,
+                row,
+                column,
+                accepted,
+                took,
+            } => {
+                let result = if *accepted { "accepted" } else { "rejected" };
+                write!(
+                    f,
+                    "Sample ({row}, {column}) of block {height} {result} after {took:?}"
+                )
+            }
+        }
+    }
+}
+
+fn serialize_display<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: fmt::Display,
+    S: Serializer,
+{
+    serializer.collect_str(value)
+}
+
+fn serialize_millis<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_u64(duration.as_millis() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    use tokio::test as async_test;
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as async_test;
+
+    #[async_test]
+    async fn events_are_broadcasted() {
+        let channel = EventChannel::new();
+        let publisher = channel.publisher();
+
+        // Events published before subscribing are not received
+        publisher.send(NodeEvent::AddedHeaderFromHeaderSub { height: 1 });
+
+        let mut sub1 = channel.subscribe();
+        let mut sub2 = channel.subscribe();
+        assert_eq!(sub1.try_recv().unwrap_err(), TryRecvError::Empty);
+
+        publisher.send(NodeEvent::AddedHeaderFromHeaderSub { height: 2 });
+
+        for sub in [&mut sub1, &mut sub2] {
+            let info = sub.recv().await.unwrap();
+            assert_eq!(
+                info.event,
+                NodeEvent::AddedHeaderFromHeaderSub { height: 2 }
+            );
+        }
+
+        drop(publisher);
+        drop(channel);
+        assert_eq!(sub1.recv().await.unwrap_err(), RecvError::Closed);
+    }
+
+    #[async_test]
+    async fn lagging_subscriber() {
+        let channel = EventChannel::new();
+        let publisher = channel.publisher();
+        let mut sub = channel.subscribe();
+
+        for height in 0..EVENT_CHANNEL_CAPACITY as u64 + 5 {
+            publisher.send(NodeEvent::AddedHeaderFromHeaderSub { height });
+        }
+
+        assert_eq!(sub.recv().await.unwrap_err(), RecvError::Lagged(5));
+        let info = sub.recv().await.unwrap();
+        assert_eq!(
+            info.event,
+            NodeEvent::AddedHeaderFromHeaderSub { height: 5 }
+        );
+    }
+
+    #[test]
+    fn event_serialization() {
+        let id = PeerId::random();
+        let event = NodeEvent::PeerConnected { id, trusted: true };
+        assert_eq!(
+            serde_json::to_value(event).unwrap(),
+            serde_json::json!({
+                "type": "peer_connected",
+                "id": id.to_string(),
+                "trusted": true,
+            })
+        );
+
+        let event = NodeEvent::SamplingFinished {
+            height: 7,
+            row: 1,
+            column: 2,
+            accepted: false,
+            took: Duration::from_millis(1500),
+        };
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({
+                "type": "sampling_finished",
+                "height": 7,
+                "row": 1,
+                "column": 2,
+                "accepted": false,
+                "took_ms": 1500,
+            })
+        );
+
+        let info = NodeEventInfo {
+            event,
+            time: Time::unix_epoch(),
+        };
+        let json = serde_json::to_value(info).unwrap();
+        assert_eq!(json["event"]["type"], "sampling_finished");
+        assert_eq!(json["time"], "1970-01-01T00:00:00Z");
+    }
+}
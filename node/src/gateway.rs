@@ -0,0 +1,150 @@
+//! Retrieval of the shwap containers from an HTTP gateway, as a fallback for the
+//! networks where the bitswap retrieval is blocked or too slow.
+//!
+//! The containers are requested by their CIDs with the `GET /ipfs/{cid}?format=raw`
+//! request of the [trustless gateway] API, which has to answer with the raw container.
+//! Only the request format is shared with IPFS: the shwap containers aren't published to
+//! the IPFS network, so public IPFS gateways don't have them. The expected gateway is
+//! an HTTP server of the Celestia network operator, which stores the extended data
+//! squares and builds the containers from them, like the bridge node serving bitswap.
+//!
+//! The gateway doesn't need to be trusted, the [`Row`]s, [`Sample`]s and
+//! [`NamespacedData`] it returns are verified against the [`DataAvailabilityHeader`]
+//! the same way as the ones retrieved over bitswap.
+//!
+//! [trustless gateway]: https://specs.ipfs.tech/http-gateways/trustless-gateway/
+//! [`Row`]: celestia_types::row::Row
+//! [`Sample`]: celestia_types::sample::Sample
+//! [`NamespacedData`]: celestia_types::namespaced_data::NamespacedData
+//! [`DataAvailabilityHeader`]: celestia_types::DataAvailabilityHeader
+
+use std::time::Duration;
+
+use reqwest::header::ACCEPT;
+use reqwest::StatusCode;
+
+use crate::bitswap::Cid;
+use crate::executor::timeout;
+use crate::p2p::P2pError;
+
+/// Default time given to the bitswap retrieval before falling back to the gateway.
+pub const DEFAULT_P2P_TIMEOUT: Duration = Duration::from_secs(10);
+/// Maximum time we wait for a shwap container to be delivered by the gateway.
+const GATEWAY_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// Maximum size of a shwap container accepted from the gateway.
+const MAX_CONTAINER_SIZE: usize = 16 * 1024 * 1024;
+/// Content type of the raw blocks in the trustless gateway API.
+const RAW_BLOCK_CONTENT_TYPE: &str = "application/vnd.ipld.raw";
+
+type Result<T, E = P2pError> = std::result::Result<T, E>;
+
+/// Configuration of the HTTP gateway the shwap containers are retrieved from when
+/// the bitswap retrieval fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GatewayConfig {
+    /// Base URL of the gateway serving the shwap containers, see the [module
+    /// documentation](self), e.g. `https://gateway.example.com`.
+    pub url: String,
+    /// Time given to the bitswap retrieval, including its retries, before the
+    /// container is requested from the gateway.
+    pub p2p_timeout: Duration,
+}
+
+impl GatewayConfig {
+    /// Create the configuration of the gateway at the given URL, falling back to it
+    /// after the [`DEFAULT_P2P_TIMEOUT`].
+    pub fn new(url: impl Into<String>) -> Self {
+        GatewayConfig {
+            url: url.into(),
+            p2p_timeout: DEFAULT_P2P_TIMEOUT,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Gateway {
+    url: String,
+    p2p_timeout: Duration,
+    client: reqwest::Client,
+}
+
+impl Gateway {
+    pub(crate) fn new(config: GatewayConfig) -> Self {
+        Gateway {
+            url: config.url.trim_end_matches('/').to_owned(),
+            p2p_timeout: config.p2p_timeout,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Time given to the bitswap retrieval before falling back to the gateway.
+    pub(crate) fn p2p_timeout(&self) -> Duration {
+        self.p2p_timeout
+    }
+
+    /// Request the raw shwap container with the given [`Cid`].
+    ///
+    /// Returned data is not verified.
+    pub(crate) async fn get(&self, cid: &Cid) -> Result<Vec<u8>> {
+        timeout(GATEWAY_REQUEST_TIMEOUT, self.request(cid))
+            .await
+            .map_err(|_| P2pError::RequestTimedOut)?
+    }
+
+    async fn request(&self, cid: &Cid) -> Result<Vec<u8>> {
+        let response = self
+            .client
+            .get(self.cid_url(cid))
+            .header(ACCEPT, RAW_BLOCK_CONTENT_TYPE)
+            .send()
+            .await
+            .map_err(gateway_error)?;
+
+        match response.status() {
+            StatusCode::OK => {}
+            status => return Err(P2pError::Gateway(format!("{cid}: status {status}"))),
+        }
+
+        if response
+            .content_length()
+            .is_some_and(|len| len > MAX_CONTAINER_SIZE as u64)
+        {
+            return Err(P2pError::Gateway(format!("{cid}: container too large")));
+        }
+
+        let data = response.bytes().await.map_err(gateway_error)?;
+
+        if data.len() > MAX_CONTAINER_SIZE {
+            return Err(P2pError::Gateway(format!("{cid}: container too large")));
+        }
+
+        Ok(data.to_vec())
+    }
+
+    fn cid_url(&self, cid: &Cid) -> String {
+        format!("{}/ipfs/{cid}?format=raw", self.url)
+    }
+}
+
+fn gateway_error(e: reqwest::Error) -> P2pError {
+    P2pError::Gateway(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use celestia_types::row::{IdLayout, RowId};
+
+    #[test]
+    fn cid_url() {
+        let cid = RowId::new(0, 1).unwrap().to_cid(IdLayout::default());
+        let cid = crate::bitswap::convert_cid(&cid).unwrap();
+
+        let gateway = Gateway::new(GatewayConfig::new("https://gateway.example.com/"));
+        assert_eq!(
+            gateway.cid_url(&cid),
+            format!("https://gateway.example.com/ipfs/{cid}?format=raw")
+        );
+        assert_eq!(gateway.p2p_timeout(), DEFAULT_P2P_TIMEOUT);
+    }
+}
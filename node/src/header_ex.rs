@@ -4,7 +4,7 @@ use std::task::{Context, Poll};
 
 use async_trait::async_trait;
 use celestia_proto::p2p::pb::{HeaderRequest, HeaderResponse};
-use celestia_types::ExtendedHeader;
+use celestia_types::{ErrorKind, ExtendedHeader};
 use futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use instant::{Duration, Instant};
 use libp2p::{
@@ -30,7 +30,7 @@ use crate::header_ex::server::HeaderExServerHandler;
 use crate::p2p::P2pError;
 use crate::peer_tracker::PeerTracker;
 use crate::store::Store;
-use crate::utils::{protocol_id, OneshotResultSender};
+use crate::utils::OneshotResultSender;
 
 /// Size limit of a request in bytes
 const REQUEST_SIZE_LIMIT: usize = 1024;
@@ -59,8 +59,9 @@ where
     server_handler: HeaderExServerHandler<S>,
 }
 
-pub(crate) struct HeaderExConfig<'a, S> {
-    pub network_id: &'a str,
+pub(crate) struct HeaderExConfig<S> {
+    /// Protocol ids of the supported versions, from the preferred one.
+    pub protocols: Vec<StreamProtocol>,
     pub peer_tracker: Arc<PeerTracker>,
     pub header_store: Arc<S>,
 }
@@ -87,21 +88,51 @@ pub enum HeaderExError {
     /// Error when handling connection to the server.
     #[error("Outbound failure: {0}")]
     OutboundFailure(OutboundFailure),
+
+    /// Not enough peers responded with the same headers.
+    #[error("Quorum of {0} peers not reached")]
+    QuorumNotReached(usize),
+}
+
+impl HeaderExError {
+    /// Get the [`ErrorKind`] of the error.
+    pub fn kind(&self) -> ErrorKind {
+        self.code_and_kind().1
+    }
+
+    /// Get the stable code of the error, see [`celestia_types::Error::code`].
+    ///
+    /// Errors of the `header-ex` use the codes from `2101` to `2199`.
+    pub fn code(&self) -> u16 {
+        self.code_and_kind().0
+    }
+
+    fn code_and_kind(&self) -> (u16, ErrorKind) {
+        match self {
+            HeaderExError::HeaderNotFound => (2101, ErrorKind::P2p),
+            HeaderExError::InvalidResponse => (2102, ErrorKind::Validation),
+            HeaderExError::InvalidRequest => (2103, ErrorKind::Validation),
+            HeaderExError::InboundFailure(..) => (2104, ErrorKind::P2p),
+            HeaderExError::OutboundFailure(..) => (2105, ErrorKind::P2p),
+            HeaderExError::QuorumNotReached(..) => (2106, ErrorKind::P2p),
+        }
+    }
 }
 
 impl<S> HeaderExBehaviour<S>
 where
     S: Store + 'static,
 {
-    pub(crate) fn new(config: HeaderExConfig<'_, S>) -> Self {
+    pub(crate) fn new(config: HeaderExConfig<S>) -> Self {
+        // Outbound streams propose the versions in order, so each peer is asked in
+        // the first one it supports
+        let protocols = config
+            .protocols
+            .into_iter()
+            .map(|protocol| (protocol, ProtocolSupport::Full));
+
         HeaderExBehaviour {
-            req_resp: ReqRespBehaviour::new(
-                [(
-                    protocol_id(config.network_id, "/header-ex/v0.0.3"),
-                    ProtocolSupport::Full,
-                )],
-                request_response::Config::default(),
-            ),
+            req_resp: ReqRespBehaviour::new(protocols, request_response::Config::default()),
             client_handler: HeaderExClientHandler::new(config.peer_tracker),
             server_handler: HeaderExServerHandler::new(config.header_store),
         }
@@ -117,6 +148,17 @@ where
             .on_send_request(&mut self.req_resp, request, respond_to);
     }
 
+    #[instrument(level = "trace", skip(self, respond_to))]
+    pub(crate) fn send_quorum_request(
+        &mut self,
+        request: HeaderRequest,
+        quorum: usize,
+        respond_to: OneshotResultSender<Vec<ExtendedHeader>, P2pError>,
+    ) {
+        self.client_handler
+            .on_send_quorum_request(&mut self.req_resp, request, quorum, respond_to);
+    }
+
     fn on_to_swarm(
         &mut self,
         ev: ToSwarm<ReqRespEvent, THandlerInEvent<ReqRespBehaviour>>,
@@ -351,6 +393,7 @@ impl Codec for HeaderCodec {
     where
         T: AsyncRead + Unpin + Send,
     {
+        let started = Instant::now();
         let data = read_up_to(io, REQUEST_SIZE_LIMIT, REQUEST_TIME_LIMIT).await?;
 
         if data.len() >= REQUEST_SIZE_LIMIT {
@@ -360,9 +403,9 @@ impl Codec for HeaderCodec {
         parse_header_request(&data).ok_or_else(|| {
             // There are two cases that can reach here:
             //
-            // 1. The request is invalid
-            // 2. The request is incomplete because of the size limit or time limit
-            io::Error::new(io::ErrorKind::Other, "invalid or incomplete request")
+            // 1. The request is invalid or over the size limit
+            // 2. The request is incomplete because of the time limit
+            incomplete_message_error(started, REQUEST_TIME_LIMIT, "request")
         })
     }
 
@@ -374,6 +417,7 @@ impl Codec for HeaderCodec {
     where
         T: AsyncRead + Unpin + Send,
     {
+        let started = Instant::now();
         let data = read_up_to(io, RESPONSE_SIZE_LIMIT, RESPONSE_TIME_LIMIT).await?;
 
         if data.len() >= RESPONSE_SIZE_LIMIT {
@@ -391,11 +435,12 @@ impl Codec for HeaderCodec {
         if msgs.is_empty() {
             // There are two cases that can reach here:
             //
-            // 1. The response is invalid
-            // 2. The response is incomplete because of the size limit or time limit
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                "invalid or incomplete response",
+            // 1. The response is invalid or over the size limit
+            // 2. The response is incomplete because of the time limit
+            return Err(incomplete_message_error(
+                started,
+                RESPONSE_TIME_LIMIT,
+                "response",
             ));
         }
 
@@ -450,6 +495,18 @@ impl Codec for HeaderCodec {
     }
 }
 
+/// Error of a message which couldn't be parsed.
+///
+/// Messages cut by the `time_limit` are reported as [`io::ErrorKind::TimedOut`], all
+/// the other ones violated the protocol and are reported as [`io::ErrorKind::InvalidData`].
+fn incomplete_message_error(started: Instant, time_limit: Duration, what: &str) -> io::Error {
+    if started.elapsed() >= time_limit {
+        io::Error::new(io::ErrorKind::TimedOut, format!("{what} timed out"))
+    } else {
+        io::Error::new(io::ErrorKind::InvalidData, format!("invalid {what}"))
+    }
+}
+
 /// Reads up to `size_limit` within `time_limit`.
 async fn read_up_to<T>(io: &mut T, size_limit: usize, time_limit: Duration) -> io::Result<Vec<u8>>
 where
@@ -614,7 +671,7 @@ mod tests {
             .await
             .expect_err("expected error for too large request");
 
-        assert_eq!(decoding_error.kind(), ErrorKind::Other);
+        assert_eq!(decoding_error.kind(), ErrorKind::InvalidData);
     }
 
     #[async_test]
@@ -632,7 +689,7 @@ mod tests {
             .await
             .expect_err("expected error for too large request");
 
-        assert_eq!(decoding_error.kind(), ErrorKind::Other);
+        assert_eq!(decoding_error.kind(), ErrorKind::InvalidData);
     }
 
     #[test]
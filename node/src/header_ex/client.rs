@@ -2,6 +2,7 @@ use std::cmp::Reverse;
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::hash::Hash;
+use std::io;
 use std::sync::Arc;
 use std::task::{Context, Poll};
 
@@ -17,12 +18,21 @@ use tracing::{debug, instrument, trace};
 use crate::executor::{spawn, yield_now};
 use crate::header_ex::utils::{HeaderRequestExt, HeaderResponseExt};
 use crate::header_ex::{HeaderExError, ReqRespBehaviour};
+use crate::metrics;
 use crate::p2p::P2pError;
-use crate::peer_tracker::PeerTracker;
-use crate::utils::{OneshotResultSender, OneshotResultSenderExt, VALIDATIONS_PER_YIELD};
+use crate::peer_tracker::{Misbehavior, PeerTracker};
+#[cfg(not(feature = "batch-verify"))]
+use crate::utils::VALIDATIONS_PER_YIELD;
+#[cfg(rayon_pool)]
+use crate::utils::{rayon_pool_available, spawn_rayon};
+use crate::utils::{OneshotResultSender, OneshotResultSenderExt};
 
 const MAX_PEERS: usize = 10;
 
+/// Number of headers which commit signatures are verified in a single batch.
+#[cfg(feature = "batch-verify")]
+const VALIDATION_BATCH_SIZE: usize = 64;
+
 pub(super) struct HeaderExClientHandler<S = ReqRespBehaviour>
 where
     S: RequestSender,
@@ -46,6 +56,7 @@ impl RequestSender for ReqRespBehaviour {
     type RequestId = OutboundRequestId;
 
     fn send_request(&mut self, peer: &PeerId, request: HeaderRequest) -> OutboundRequestId {
+        metrics::increment(metrics::HEADER_EX_REQUESTS, 1);
         self.send_request(peer, request)
     }
 }
@@ -82,6 +93,86 @@ where
         trace!("Request initiated");
     }
 
+    #[instrument(level = "trace", skip(self, sender, respond_to))]
+    pub(super) fn on_send_quorum_request(
+        &mut self,
+        sender: &mut S,
+        request: HeaderRequest,
+        quorum: usize,
+        respond_to: OneshotResultSender<Vec<ExtendedHeader>, P2pError>,
+    ) {
+        // HEAD responses are expected to differ between peers
+        if !request.is_valid() || request.is_head_request() || quorum == 0 {
+            respond_to.maybe_send_err(HeaderExError::InvalidRequest);
+            return;
+        }
+
+        if usize::try_from(request.amount).is_err() {
+            respond_to.maybe_send_err(HeaderExError::InvalidRequest);
+            return;
+        };
+
+        let peers = self.peer_tracker.best_n_peers(quorum);
+
+        if peers.is_empty() {
+            respond_to.maybe_send_err(P2pError::NoConnectedPeers);
+            return;
+        }
+
+        if peers.len() < quorum {
+            respond_to.maybe_send_err(HeaderExError::QuorumNotReached(quorum));
+            return;
+        }
+
+        let mut rxs = Vec::with_capacity(peers.len());
+
+        for peer in peers {
+            let (tx, rx) = oneshot::channel();
+
+            let req_id = sender.send_request(&peer, request.clone());
+            let state = State {
+                request: request.clone(),
+                respond_to: tx,
+            };
+
+            self.reqs.insert(req_id, state);
+            rxs.push(rx);
+        }
+
+        trace!("Quorum request initiated");
+
+        // Accept the response only if all the peers agree on the hashes
+        spawn(async move {
+            let mut resps = Vec::with_capacity(rxs.len());
+
+            for res in join_all(rxs).await {
+                match res {
+                    Ok(Ok(headers)) => resps.push(headers),
+                    _ => {
+                        respond_to.maybe_send_err(HeaderExError::QuorumNotReached(quorum));
+                        return;
+                    }
+                }
+            }
+
+            let first = resps.pop().expect("at least one response");
+            let agreed = resps.iter().all(|headers| {
+                headers.len() == first.len()
+                    && headers
+                        .iter()
+                        .zip(&first)
+                        .all(|(a, b)| a.hash() == b.hash())
+            });
+
+            if agreed {
+                respond_to.maybe_send_ok(first);
+            } else {
+                debug!("Peers responded with different headers");
+                respond_to.maybe_send_err(HeaderExError::QuorumNotReached(quorum));
+            }
+        });
+    }
+
     fn send_request(
         &mut self,
         sender: &mut S,
@@ -199,14 +290,23 @@ where
             state.request.amount
         );
 
+        let peer_tracker = self.peer_tracker.clone();
+
         spawn(async move {
-            match decode_and_verify_responses(&state.request, &responses).await {
+            match decode_and_verify_responses(&state.request, responses).await {
                 Ok(headers) => {
-                    // TODO: Increase peer score
+                    peer_tracker.reward(peer);
                     state.respond_to.maybe_send_ok(headers);
                 }
                 Err(e) => {
-                    // TODO: Decrease peer score
+                    metrics::increment(metrics::HEADER_EX_REQUEST_FAILURES, 1);
+
+                    // Peers may legitimately not have the requested headers yet
+                    if matches!(e, HeaderExError::InvalidResponse)
+                        && peer_tracker.penalize(peer, Misbehavior::InvalidHeaders)
+                    {
+                        debug!("Peer {peer} banned for sending invalid headers");
+                    }
                     state.respond_to.maybe_send_err(e);
                 }
             }
@@ -221,6 +321,17 @@ where
         error: OutboundFailure,
     ) {
         debug!("Outbound failure");
+        metrics::increment(metrics::HEADER_EX_REQUEST_FAILURES, 1);
+
+        // Timeouts, closed connections and the like aren't the peer's fault, only
+        // the responses violating the protocol are penalized
+        if matches!(error, OutboundFailure::Io(ref e) if e.kind() == io::ErrorKind::InvalidData)
+            && self
+                .peer_tracker
+                .penalize(peer, Misbehavior::InvalidHeaders)
+        {
+            debug!("Peer {peer} banned for sending malformed responses");
+        }
 
         if let Some(state) = self.reqs.remove(&request_id) {
             state
@@ -236,7 +347,7 @@ where
 
 async fn decode_and_verify_responses(
     request: &HeaderRequest,
-    responses: &[HeaderResponse],
+    responses: Vec<HeaderResponse>,
 ) -> Result<Vec<ExtendedHeader>, HeaderExError> {
     if responses.is_empty() {
         return Err(HeaderExError::InvalidResponse);
@@ -249,25 +360,7 @@ async fn decode_and_verify_responses(
         return Err(HeaderExError::InvalidResponse);
     }
 
-    let mut headers = Vec::with_capacity(responses.len());
-
-    'outer: for responses in responses.chunks(VALIDATIONS_PER_YIELD) {
-        for response in responses {
-            // Unmarshal and validate. Propagate error only if nothing
-            // was decoded before.
-            let header = match response.to_extended_header() {
-                Ok(header) => header,
-                Err(e) if headers.is_empty() => return Err(e),
-                Err(_) => break 'outer,
-            };
-
-            trace!("Header: {header}");
-            headers.push(header);
-        }
-
-        // Validation is computation heavy so we yield on every chunk
-        yield_now().await;
-    }
+    let mut headers = decode_responses(responses).await?;
 
     headers.sort_unstable_by_key(|header| header.height());
 
@@ -300,10 +393,168 @@ async fn decode_and_verify_responses(
     Ok(headers)
 }
 
+/// Decode and validate the responses in parallel, keeping the ones preceding the first
+/// invalid one.
+///
+/// Validation verifies the signatures of the commit, which is the bottleneck of
+/// syncing, so with the `rayon` feature the responses are validated on the thread pool.
+#[cfg(all(rayon_pool, not(feature = "batch-verify")))]
+async fn decode_responses_in_parallel(
+    responses: Vec<HeaderResponse>,
+) -> Result<Vec<ExtendedHeader>, HeaderExError> {
+    use rayon::prelude::*;
+
+    let results: Vec<_> = spawn_rayon(move || {
+        responses
+            .par_iter()
+            .map(|response| response.to_extended_header())
+            .collect()
+    })
+    .await
+    // a response which makes decoding panic is invalid
+    .map_err(|_| HeaderExError::InvalidResponse)?;
+
+    let mut headers = Vec::with_capacity(results.len());
+
+    for result in results {
+        // Propagate error only if nothing was decoded before.
+        match result {
+            Ok(header) => {
+                trace!("Header: {header}");
+                headers.push(header);
+            }
+            Err(e) if headers.is_empty() => return Err(e),
+            Err(_) => break,
+        }
+    }
+
+    Ok(headers)
+}
+
+/// Decode and validate the responses, keeping the ones preceding the first invalid one.
+#[cfg(not(feature = "batch-verify"))]
+async fn decode_responses(
+    responses: Vec<HeaderResponse>,
+) -> Result<Vec<ExtendedHeader>, HeaderExError> {
+    #[cfg(rayon_pool)]
+    if rayon_pool_available() {
+        return decode_responses_in_parallel(responses).await;
+    }
+
+    let mut headers = Vec::with_capacity(responses.len());
+
+    'outer: for responses in responses.chunks(VALIDATIONS_PER_YIELD) {
+        for response in responses {
+            // Unmarshal and validate. Propagate error only if nothing
+            // was decoded before.
+            let header = match response.to_extended_header() {
+                Ok(header) => header,
+                Err(e) if headers.is_empty() => return Err(e),
+                Err(_) => break 'outer,
+            };
+
+            trace!("Header: {header}");
+            headers.push(header);
+        }
+
+        // Validation is computation heavy so we yield on every chunk
+        yield_now().await;
+    }
+
+    Ok(headers)
+}
+
+/// Decode the responses and validate them in batches, keeping the ones preceding the
+/// first invalid one.
+///
+/// Signatures of the commits of all the headers in a batch are verified at once with
+/// the ed25519 batch verification.
+#[cfg(feature = "batch-verify")]
+async fn decode_responses(
+    responses: Vec<HeaderResponse>,
+) -> Result<Vec<ExtendedHeader>, HeaderExError> {
+    let mut headers = Vec::with_capacity(responses.len());
+
+    for response in &responses {
+        // Propagate error only if nothing was decoded before.
+        match response.to_unvalidated_extended_header() {
+            Ok(header) => headers.push(header),
+            Err(e) if headers.is_empty() => return Err(e),
+            Err(_) => break,
+        }
+    }
+
+    if headers.is_empty() {
+        return Ok(headers);
+    }
+
+    let headers = validate_in_batches(headers).await?;
+
+    if headers.is_empty() {
+        return Err(HeaderExError::InvalidResponse);
+    }
+
+    for header in &headers {
+        trace!("Header: {header}");
+    }
+
+    Ok(headers)
+}
+
+/// Validate the headers in batches, keeping the ones preceding the first invalid one.
+///
+/// With the `rayon` feature the batches are validated in parallel on the thread pool.
+#[cfg(feature = "batch-verify")]
+async fn validate_in_batches(
+    mut headers: Vec<ExtendedHeader>,
+) -> Result<Vec<ExtendedHeader>, HeaderExError> {
+    #[cfg(rayon_pool)]
+    if rayon_pool_available() {
+        use rayon::prelude::*;
+
+        return spawn_rayon(move || {
+            let first_invalid = headers
+                .par_chunks(VALIDATION_BATCH_SIZE)
+                .enumerate()
+                .find_map_first(|(n, batch)| {
+                    let (idx, _) = ExtendedHeader::validate_batch(batch).err()?;
+                    Some(n * VALIDATION_BATCH_SIZE + idx)
+                });
+
+            if let Some(first_invalid) = first_invalid {
+                headers.truncate(first_invalid);
+            }
+
+            headers
+        })
+        .await
+        // a header which makes validation panic is invalid
+        .map_err(|_| HeaderExError::InvalidResponse);
+    }
+
+    let mut valid = 0;
+
+    for batch in headers.chunks(VALIDATION_BATCH_SIZE) {
+        if let Err((idx, _)) = ExtendedHeader::validate_batch(batch) {
+            valid += idx;
+            break;
+        }
+
+        valid += batch.len();
+
+        // Validation is computation heavy so we yield on every batch
+        yield_now().await;
+    }
+
+    headers.truncate(valid);
+    Ok(headers)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::header_ex::utils::ExtendedHeaderExt;
+    use crate::peer_tracker::ConnectionDirection;
     use celestia_proto::p2p::pb::header_request::Data;
     use celestia_proto::p2p::pb::StatusCode;
     use celestia_types::consts::HASH_SIZE;
@@ -1035,6 +1286,98 @@ mod tests {
         assert!(matches!(rx.await, Ok(Err(P2pError::NoConnectedPeers))));
     }
 
+    #[async_test]
+    async fn quorum_request_agreed() {
+        let peer_tracker = peer_tracker_with_n_peers(15);
+        let mut mock_req = MockReq::new();
+        let mut handler = HeaderExClientHandler::<MockReq>::new(peer_tracker);
+
+        let (tx, rx) = oneshot::channel();
+
+        handler.on_send_quorum_request(&mut mock_req, HeaderRequest::with_origin(5, 3), 3, tx);
+        assert_eq!(mock_req.reqs.len(), 3);
+
+        let mut gen = ExtendedHeaderGenerator::new_from_height(5);
+        let expected_headers = gen.next_many(3);
+        let expected = expected_headers
+            .iter()
+            .map(|header| header.to_header_response())
+            .collect::<Vec<_>>();
+
+        mock_req.send_n_responses(&mut handler, 3, expected);
+
+        let result = rx.await.unwrap().unwrap();
+        assert_eq!(result, expected_headers);
+    }
+
+    #[async_test]
+    async fn quorum_request_disagreement() {
+        let peer_tracker = peer_tracker_with_n_peers(15);
+        let mut mock_req = MockReq::new();
+        let mut handler = HeaderExClientHandler::<MockReq>::new(peer_tracker);
+
+        let (tx, rx) = oneshot::channel();
+
+        handler.on_send_quorum_request(&mut mock_req, HeaderRequest::with_origin(5, 1), 3, tx);
+
+        let mut gen = ExtendedHeaderGenerator::new_from_height(5);
+        let header5 = gen.next();
+        let another_header5 = gen.another_of(&header5);
+
+        mock_req.send_n_responses(&mut handler, 2, vec![header5.to_header_response()]);
+        mock_req.send_n_responses(&mut handler, 1, vec![another_header5.to_header_response()]);
+
+        assert!(matches!(
+            rx.await,
+            Ok(Err(P2pError::HeaderEx(HeaderExError::QuorumNotReached(3))))
+        ));
+    }
+
+    #[async_test]
+    async fn quorum_request_with_failure() {
+        let peer_tracker = peer_tracker_with_n_peers(15);
+        let mut mock_req = MockReq::new();
+        let mut handler = HeaderExClientHandler::<MockReq>::new(peer_tracker);
+
+        let (tx, rx) = oneshot::channel();
+
+        handler.on_send_quorum_request(&mut mock_req, HeaderRequest::with_origin(5, 1), 2, tx);
+
+        let mut gen = ExtendedHeaderGenerator::new_from_height(5);
+        let header5 = gen.next();
+
+        mock_req.send_n_responses(&mut handler, 1, vec![header5.to_header_response()]);
+        mock_req.send_n_failures(&mut handler, 1, OutboundFailure::Timeout);
+
+        assert!(matches!(
+            rx.await,
+            Ok(Err(P2pError::HeaderEx(HeaderExError::QuorumNotReached(2))))
+        ));
+    }
+
+    #[async_test]
+    async fn quorum_request_not_enough_peers() {
+        let peer_tracker = peer_tracker_with_n_peers(2);
+        let mut mock_req = MockReq::new();
+        let mut handler = HeaderExClientHandler::<MockReq>::new(peer_tracker);
+
+        let (tx, rx) = oneshot::channel();
+        handler.on_send_quorum_request(&mut mock_req, HeaderRequest::with_origin(5, 1), 3, tx);
+
+        assert!(matches!(
+            rx.await,
+            Ok(Err(P2pError::HeaderEx(HeaderExError::QuorumNotReached(3))))
+        ));
+
+        let (tx, rx) = oneshot::channel();
+        handler.on_send_quorum_request(&mut mock_req, HeaderRequest::with_origin(0, 1), 2, tx);
+
+        assert!(matches!(
+            rx.await,
+            Ok(Err(P2pError::HeaderEx(HeaderExError::InvalidRequest)))
+        ));
+    }
+
     #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
     struct MockReqId(u64);
 
@@ -1109,13 +1452,50 @@ mod tests {
         }
     }
 
+    #[async_test]
+    async fn only_malformed_responses_are_penalized() {
+        let peer_tracker = peer_tracker_with_n_peers(1);
+        let mut mock_req = MockReq::new();
+        let mut handler = HeaderExClientHandler::<MockReq>::new(peer_tracker.clone());
+
+        let failures = [
+            OutboundFailure::Timeout,
+            OutboundFailure::ConnectionClosed,
+            OutboundFailure::DialFailure,
+            OutboundFailure::UnsupportedProtocols,
+            OutboundFailure::Io(io::Error::new(io::ErrorKind::TimedOut, "")),
+        ];
+
+        for failure in failures {
+            let (tx, _rx) = oneshot::channel();
+            handler.on_send_request(&mut mock_req, HeaderRequest::with_origin(5, 1), tx);
+            let peer = mock_req.reqs[0].peer;
+
+            mock_req.send_n_failures(&mut handler, 1, failure);
+            assert_eq!(peer_tracker.score(peer), 0.0);
+        }
+
+        let (tx, _rx) = oneshot::channel();
+        handler.on_send_request(&mut mock_req, HeaderRequest::with_origin(5, 1), tx);
+        let peer = mock_req.reqs[0].peer;
+
+        let malformed = OutboundFailure::Io(io::Error::new(io::ErrorKind::InvalidData, ""));
+        mock_req.send_n_failures(&mut handler, 1, malformed);
+        assert!(peer_tracker.score(peer) < 0.0);
+    }
+
     fn peer_tracker_with_n_peers(amount: usize) -> Arc<PeerTracker> {
         let peers = Arc::new(PeerTracker::new());
 
         for i in 0..amount {
             let peer = PeerId::random();
             peers.set_trusted(peer, true);
-            peers.set_connected(peer, ConnectionId::new_unchecked(i), None);
+            peers.set_connected(
+                peer,
+                ConnectionId::new_unchecked(i),
+                ConnectionDirection::Outbound,
+                None,
+            );
         }
 
         peers
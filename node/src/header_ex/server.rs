@@ -1,22 +1,31 @@
+use std::collections::HashMap;
 use std::fmt::{Debug, Display};
 use std::sync::Arc;
 use std::task::{Context, Poll};
 
 use celestia_proto::p2p::pb::{header_request, HeaderRequest, HeaderResponse};
 use celestia_types::hash::Hash;
+use instant::{Duration, Instant};
 use libp2p::{
     request_response::{InboundFailure, InboundRequestId, ResponseChannel},
     PeerId,
 };
 use tokio::sync::mpsc::{self, error::TrySendError};
-use tracing::{instrument, trace};
+use tracing::{debug, instrument, trace};
 
 use crate::executor::spawn;
 use crate::header_ex::utils::{ExtendedHeaderExt, HeaderRequestExt, HeaderResponseExt};
 use crate::header_ex::{ReqRespBehaviour, ResponseType};
+use crate::metrics;
 use crate::store::Store;
 
 const MAX_HEADERS_AMOUNT_RESPONSE: u64 = 512;
+/// Number of the headers that a single peer can request in a burst.
+const PEER_HEADERS_BURST: u64 = 2 * MAX_HEADERS_AMOUNT_RESPONSE;
+/// Number of the headers per second that a single peer can request once the burst is used.
+const PEER_HEADERS_PER_SECOND: u64 = MAX_HEADERS_AMOUNT_RESPONSE / 4;
+/// How often the rate limits of the peers which stopped requesting are forgotten.
+const RATE_LIMITS_PRUNE_INTERVAL: Duration = Duration::from_secs(60);
 
 pub(super) struct HeaderExServerHandler<S, R = ReqRespBehaviour>
 where
@@ -24,6 +33,7 @@ where
     R: ResponseSender,
 {
     store: Arc<S>,
+    rate_limiter: RateLimiter,
 
     rx: mpsc::Receiver<(R::Channel, ResponseType)>,
     tx: mpsc::Sender<(R::Channel, ResponseType)>,
@@ -52,7 +62,12 @@ where
 {
     pub(super) fn new(store: Arc<S>) -> Self {
         let (tx, rx) = mpsc::channel(32);
-        HeaderExServerHandler { store, rx, tx }
+        HeaderExServerHandler {
+            store,
+            rate_limiter: RateLimiter::new(Instant::now()),
+            rx,
+            tx,
+        }
     }
 
     #[instrument(level = "trace", skip(self, response_channel))]
@@ -65,6 +80,18 @@ where
     ) where
         Id: Display + Debug,
     {
+        metrics::increment(metrics::HEADER_EX_REQUESTS_SERVED, 1);
+
+        if !self
+            .rate_limiter
+            .try_acquire(peer, request_cost(&request), Instant::now())
+        {
+            debug!("Rate limit of {peer} exceeded, dropping request {request_id}");
+            metrics::increment(metrics::HEADER_EX_REQUESTS_RATE_LIMITED, 1);
+            // Dropping the channel closes the stream without a response
+            return;
+        }
+
         let Some((amount, data)) = parse_request(request) else {
             self.handle_invalid_request(response_channel);
             return;
@@ -113,11 +140,13 @@ where
         let tx = self.tx.clone();
 
         spawn(async move {
+            let started = Instant::now();
             let response = store
                 .get_head()
                 .await
                 .map(|head| head.to_header_response())
                 .unwrap_or_else(|_| HeaderResponse::not_found());
+            metrics::record_duration(metrics::STORE_READ_DURATION, started);
 
             let _ = tx.send((channel, vec![response])).await;
         });
@@ -133,11 +162,13 @@ where
         let tx = self.tx.clone();
 
         spawn(async move {
+            let started = Instant::now();
             let response = store
                 .get_by_hash(&hash)
                 .await
                 .map(|head| head.to_header_response())
                 .unwrap_or_else(|_| HeaderResponse::not_found());
+            metrics::record_duration(metrics::STORE_READ_DURATION, started);
 
             let _ = tx.send((channel, vec![response])).await;
         });
@@ -150,6 +181,7 @@ where
         spawn(async move {
             let amount = amount.min(MAX_HEADERS_AMOUNT_RESPONSE);
             let mut responses = vec![];
+            let started = Instant::now();
 
             for i in origin..origin + amount {
                 match store.get_by_height(i).await {
@@ -164,6 +196,8 @@ where
                 }
             }
 
+            metrics::record_duration(metrics::STORE_READ_DURATION, started);
+
             if responses.is_empty() {
                 responses.reserve_exact(1);
                 responses.push(HeaderResponse::not_found());
@@ -202,6 +236,79 @@ fn parse_request(request: HeaderRequest) -> Option<(u64, header_request::Data)>
     Some((amount, data))
 }
 
+/// Number of the headers that the request may be answered with.
+fn request_cost(request: &HeaderRequest) -> u64 {
+    match request.data {
+        Some(header_request::Data::Origin(height)) if height > 0 => {
+            request.amount.clamp(1, MAX_HEADERS_AMOUNT_RESPONSE)
+        }
+        _ => 1,
+    }
+}
+
+/// Limits the number of the headers requested by each peer, with a token bucket per peer.
+#[derive(Debug)]
+struct RateLimiter {
+    buckets: HashMap<PeerId, Bucket>,
+    last_pruned: Instant,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: u64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    fn new(now: Instant) -> Self {
+        RateLimiter {
+            buckets: HashMap::new(),
+            last_pruned: now,
+        }
+    }
+
+    /// Take `cost` tokens from the bucket of the peer, returns `false` if there aren't enough.
+    fn try_acquire(&mut self, peer: PeerId, cost: u64, now: Instant) -> bool {
+        if now.saturating_duration_since(self.last_pruned) >= RATE_LIMITS_PRUNE_INTERVAL {
+            self.prune(now);
+        }
+
+        let bucket = self.buckets.entry(peer).or_insert(Bucket {
+            tokens: PEER_HEADERS_BURST,
+            last_refill: now,
+        });
+        bucket.refill(now);
+
+        if bucket.tokens < cost {
+            return false;
+        }
+
+        bucket.tokens -= cost;
+        true
+    }
+
+    /// Forget the buckets that got fully refilled, there is nothing to limit for them.
+    fn prune(&mut self, now: Instant) {
+        self.buckets.retain(|_, bucket| {
+            bucket.refill(now);
+            bucket.tokens < PEER_HEADERS_BURST
+        });
+        self.last_pruned = now;
+    }
+}
+
+impl Bucket {
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        let refilled = elapsed.as_millis() as u64 * PEER_HEADERS_PER_SECOND / 1000;
+
+        if refilled > 0 {
+            self.tokens = (self.tokens + refilled).min(PEER_HEADERS_BURST);
+            self.last_refill = now;
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -385,6 +492,46 @@ mod tests {
         }
     }
 
+    #[test]
+    fn rate_limit_per_peer() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new(start);
+        let peer1 = PeerId::random();
+        let peer2 = PeerId::random();
+
+        let range_request = HeaderRequest::with_origin(1, 1000);
+        assert_eq!(request_cost(&range_request), MAX_HEADERS_AMOUNT_RESPONSE);
+        assert_eq!(request_cost(&HeaderRequest::head_request()), 1);
+
+        assert!(limiter.try_acquire(peer1, MAX_HEADERS_AMOUNT_RESPONSE, start));
+        assert!(limiter.try_acquire(peer1, MAX_HEADERS_AMOUNT_RESPONSE, start));
+        assert!(!limiter.try_acquire(peer1, 1, start));
+
+        // other peers have their own limits
+        assert!(limiter.try_acquire(peer2, MAX_HEADERS_AMOUNT_RESPONSE, start));
+
+        let later = start + Duration::from_secs(1);
+        assert!(limiter.try_acquire(peer1, PEER_HEADERS_PER_SECOND, later));
+        assert!(!limiter.try_acquire(peer1, 1, later));
+    }
+
+    #[test]
+    fn rate_limits_of_idle_peers_are_pruned() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new(start);
+        let idle_peer = PeerId::random();
+        let busy_peer = PeerId::random();
+
+        assert!(limiter.try_acquire(idle_peer, 1, start));
+        assert_eq!(limiter.buckets.len(), 1);
+
+        let later = start + RATE_LIMITS_PRUNE_INTERVAL;
+        assert!(limiter.try_acquire(busy_peer, 1, later));
+
+        assert_eq!(limiter.buckets.len(), 1);
+        assert!(limiter.buckets.contains_key(&busy_peer));
+    }
+
     #[derive(Debug)]
     struct TestResponseSender(pub Option<oneshot::Sender<ResponseType>>);
 
@@ -50,6 +50,8 @@ impl HeaderRequestExt for HeaderRequest {
 
 pub(super) trait HeaderResponseExt {
     fn to_extended_header(&self) -> Result<ExtendedHeader, HeaderExError>;
+    #[cfg(feature = "batch-verify")]
+    fn to_unvalidated_extended_header(&self) -> Result<ExtendedHeader, HeaderExError>;
     fn not_found() -> HeaderResponse;
     fn invalid() -> HeaderResponse;
 }
@@ -64,6 +66,17 @@ impl HeaderResponseExt for HeaderResponse {
         }
     }
 
+    #[cfg(feature = "batch-verify")]
+    fn to_unvalidated_extended_header(&self) -> Result<ExtendedHeader, HeaderExError> {
+        match self.status_code() {
+            StatusCode::Invalid => Err(HeaderExError::InvalidResponse),
+            StatusCode::NotFound => Err(HeaderExError::HeaderNotFound),
+            StatusCode::Ok => {
+                ExtendedHeader::decode(&self.body[..]).map_err(|_| HeaderExError::InvalidResponse)
+            }
+        }
+    }
+
     fn not_found() -> HeaderResponse {
         HeaderResponse {
             status_code: StatusCode::NotFound.into(),
@@ -0,0 +1,208 @@
+//! Limit of the incoming connections established from a single IP address.
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::IpAddr;
+use std::task::{Context, Poll};
+
+use libp2p::{
+    core::Endpoint,
+    multiaddr::Protocol,
+    swarm::{
+        dummy, ConnectionClosed, ConnectionDenied, ConnectionId, FromSwarm, ListenFailure,
+        NetworkBehaviour, THandler, THandlerInEvent, THandlerOutEvent, ToSwarm,
+    },
+    Multiaddr, PeerId,
+};
+
+/// Denies the incoming connections from the IP addresses which already reached the limit.
+///
+/// Relayed connections are not counted, as their remote address is the one of the relay.
+pub(crate) struct IpLimitsBehaviour {
+    max_per_ip: Option<u32>,
+    per_ip: HashMap<IpAddr, u32>,
+    connections: HashMap<ConnectionId, IpAddr>,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Incoming connections limit of {limit} reached for {ip}")]
+struct IpLimitExceeded {
+    ip: IpAddr,
+    limit: u32,
+}
+
+impl IpLimitsBehaviour {
+    pub(crate) fn new(max_per_ip: Option<u32>) -> Self {
+        IpLimitsBehaviour {
+            max_per_ip,
+            per_ip: HashMap::new(),
+            connections: HashMap::new(),
+        }
+    }
+
+    fn on_inbound(
+        &mut self,
+        connection_id: ConnectionId,
+        remote_addr: &Multiaddr,
+    ) -> Result<(), ConnectionDenied> {
+        let (Some(limit), Some(ip)) = (self.max_per_ip, remote_ip(remote_addr)) else {
+            return Ok(());
+        };
+
+        let count = self.per_ip.get(&ip).copied().unwrap_or(0);
+
+        if count >= limit {
+            return Err(ConnectionDenied::new(IpLimitExceeded { ip, limit }));
+        }
+
+        self.per_ip.insert(ip, count + 1);
+        self.connections.insert(connection_id, ip);
+
+        Ok(())
+    }
+
+    fn on_closed(&mut self, connection_id: ConnectionId) {
+        let Some(ip) = self.connections.remove(&connection_id) else {
+            return;
+        };
+
+        if let Some(count) = self.per_ip.get_mut(&ip) {
+            *count -= 1;
+
+            if *count == 0 {
+                self.per_ip.remove(&ip);
+            }
+        }
+    }
+}
+
+impl NetworkBehaviour for IpLimitsBehaviour {
+    type ConnectionHandler = dummy::ConnectionHandler;
+    type ToSwarm = Infallible;
+
+    fn handle_established_inbound_connection(
+        &mut self,
+        connection_id: ConnectionId,
+        _peer: PeerId,
+        _local_addr: &Multiaddr,
+        remote_addr: &Multiaddr,
+    ) -> Result<THandler<Self>, ConnectionDenied> {
+        self.on_inbound(connection_id, remote_addr)?;
+        Ok(dummy::ConnectionHandler)
+    }
+
+    fn handle_established_outbound_connection(
+        &mut self,
+        _connection_id: ConnectionId,
+        _peer: PeerId,
+        _addr: &Multiaddr,
+        _role_override: Endpoint,
+    ) -> Result<THandler<Self>, ConnectionDenied> {
+        Ok(dummy::ConnectionHandler)
+    }
+
+    fn on_swarm_event(&mut self, event: FromSwarm) {
+        // Listen failure is reported also when the other behaviours denied the connection
+        // after we accepted it
+        match event {
+            FromSwarm::ConnectionClosed(ConnectionClosed { connection_id, .. })
+            | FromSwarm::ListenFailure(ListenFailure { connection_id, .. }) => {
+                self.on_closed(connection_id)
+            }
+            _ => {}
+        }
+    }
+
+    fn on_connection_handler_event(
+        &mut self,
+        _peer_id: PeerId,
+        _connection_id: ConnectionId,
+        event: THandlerOutEvent<Self>,
+    ) {
+        match event {}
+    }
+
+    fn poll(
+        &mut self,
+        _cx: &mut Context<'_>,
+    ) -> Poll<ToSwarm<Self::ToSwarm, THandlerInEvent<Self>>> {
+        Poll::Pending
+    }
+}
+
+fn remote_ip(addr: &Multiaddr) -> Option<IpAddr> {
+    if addr.iter().any(|protocol| protocol == Protocol::P2pCircuit) {
+        return None;
+    }
+
+    match addr.iter().next()? {
+        Protocol::Ip4(ip) => Some(ip.into()),
+        Protocol::Ip6(ip) => Some(ip.into()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(s: &str) -> Multiaddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn limits_connections_per_ip() {
+        let mut limits = IpLimitsBehaviour::new(Some(2));
+        let first = ConnectionId::new_unchecked(1);
+
+        limits
+            .on_inbound(first, &addr("/ip4/1.2.3.4/tcp/1001"))
+            .unwrap();
+        limits
+            .on_inbound(
+                ConnectionId::new_unchecked(2),
+                &addr("/ip4/1.2.3.4/udp/1002/quic-v1"),
+            )
+            .unwrap();
+        limits
+            .on_inbound(
+                ConnectionId::new_unchecked(3),
+                &addr("/ip4/1.2.3.4/tcp/1003"),
+            )
+            .unwrap_err();
+
+        // other IPs and relayed connections are not affected
+        limits
+            .on_inbound(
+                ConnectionId::new_unchecked(4),
+                &addr("/ip4/5.6.7.8/tcp/1001"),
+            )
+            .unwrap();
+        let relayed = addr("/ip4/1.2.3.4/tcp/1001/p2p/12D3KooWSqZaLcn5Guypo2mrHr297YPJnV8KMEMXNjs3qAS8msw8/p2p-circuit");
+        limits
+            .on_inbound(ConnectionId::new_unchecked(5), &relayed)
+            .unwrap();
+
+        limits.on_closed(first);
+        limits
+            .on_inbound(
+                ConnectionId::new_unchecked(6),
+                &addr("/ip4/1.2.3.4/tcp/1004"),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn no_limit() {
+        let mut limits = IpLimitsBehaviour::new(None);
+
+        for id in 0..100 {
+            limits
+                .on_inbound(
+                    ConnectionId::new_unchecked(id),
+                    &addr("/ip4/1.2.3.4/tcp/1001"),
+                )
+                .unwrap();
+        }
+    }
+}
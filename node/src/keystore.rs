@@ -0,0 +1,740 @@
+//! Storage of the keys of the accounts submitting the transactions.
+//!
+//! A [`Keystore`] holds named secp256k1 keys and loads them as the [`SigningKey`]s,
+//! which implement the [`Keyring`] used by the [`BlobSubmitter`] and [`TxClient`].
+//! Two backends are provided:
+//!
+//! - [`FileKeystore`], keeping each key in a separate file, encrypted with the
+//!   [age](https://age-encryption.org) format using a passphrase derived key (scrypt).
+//! - [`OsKeychain`], keeping the keys in the keychain of the operating system, with
+//!   the `os-keychain` feature enabled. Its [`OsKeychainKeyring`] loads the key only
+//!   for the time of signing each transaction.
+//!
+//! Keys of the existing `celestia-appd` accounts can be imported either from the
+//! ASCII armored export (`celestia-appd keys export <name>`) with
+//! [`import_armored_key`] or from the unarmored hex export
+//! (`celestia-appd keys export <name> --unarmored-hex --unsafe`) with [`import_hex_key`].
+//!
+//! [`Keyring`]: crate::blob_submitter::Keyring
+//! [`BlobSubmitter`]: crate::blob_submitter::BlobSubmitter
+//! [`TxClient`]: crate::tx_client::TxClient
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use age::secrecy::Secret;
+use base64::prelude::*;
+use crypto_secretbox::aead::Aead;
+use crypto_secretbox::{KeyInit, Nonce, XSalsa20Poly1305};
+use sha2::{Digest, Sha256};
+#[cfg(feature = "os-keychain")]
+use tracing::warn;
+use zeroize::Zeroizing;
+
+#[cfg(feature = "os-keychain")]
+use crate::blob_submitter::{Keyring, Signature, VerifyingKey};
+
+pub use crate::blob_submitter::SigningKey;
+
+/// Extension of the files of the [`FileKeystore`].
+pub const KEY_FILE_EXTENSION: &str = "age";
+
+const ARMOR_BLOCK_TYPE: &str = "TENDERMINT PRIVATE KEY";
+const ARMOR_KDF: &str = "bcrypt";
+const ARMOR_KEY_TYPE: &str = "secp256k1";
+/// Cost of the bcrypt used by the cosmos-sdk to derive the key of the armored export.
+const BCRYPT_COST: u32 = 12;
+/// Amino prefix of the `tendermint/PrivKeySecp256k1` followed by the length of the key.
+const AMINO_SECP256K1_PREFIX: [u8; 5] = [0xe1, 0xb0, 0xf7, 0x9b, 0x20];
+const XSALSA20_NONCE_LEN: usize = 24;
+
+type Result<T, E = KeystoreError> = std::result::Result<T, E>;
+
+/// Representation of all the errors that can occur when interacting with the [`Keystore`].
+#[derive(Debug, thiserror::Error)]
+pub enum KeystoreError {
+    /// Key with the given name is not in the keystore.
+    #[error("Key not found: {0}")]
+    KeyNotFound(String),
+
+    /// Key with the given name is already in the keystore.
+    #[error("Key already exists: {0}")]
+    KeyExists(String),
+
+    /// Name of the key is empty or contains characters other than alphanumerics,
+    /// `-`, `_` and `.`.
+    #[error("Invalid key name: {0}")]
+    InvalidName(String),
+
+    /// Stored or imported data is not a valid secp256k1 private key.
+    #[error("Invalid private key")]
+    InvalidKey,
+
+    /// Armored key export couldn't be parsed.
+    #[error("Invalid armored key: {0}")]
+    InvalidArmor(String),
+
+    /// Passphrase doesn't decrypt the key.
+    #[error("Invalid passphrase")]
+    InvalidPassphrase,
+
+    /// Encrypting the key failed.
+    #[error("Encrypting key failed: {0}")]
+    Encryption(#[from] age::EncryptError),
+
+    /// Decrypting the key failed.
+    #[error("Decrypting key failed: {0}")]
+    Decryption(#[from] age::DecryptError),
+
+    /// An error propagated from the keychain of the operating system.
+    #[cfg(feature = "os-keychain")]
+    #[cfg_attr(docs_rs, doc(cfg(feature = "os-keychain")))]
+    #[error("OS keychain error: {0}")]
+    OsKeychain(#[from] keyring::Error),
+
+    /// An I/O error.
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Storage of the named keys of the accounts.
+pub trait Keystore {
+    /// Store the key under the given name.
+    ///
+    /// Returns [`KeystoreError::KeyExists`] if there is already a key with that name.
+    fn insert(&self, name: &str, key: &SigningKey) -> Result<()>;
+
+    /// Load the key with the given name.
+    fn get(&self, name: &str) -> Result<SigningKey>;
+
+    /// Remove the key with the given name.
+    fn remove(&self, name: &str) -> Result<()>;
+}
+
+/// [`Keystore`] keeping the keys in the passphrase encrypted files of a directory.
+///
+/// Each key is stored hex encoded in the `<name>.age` file, encrypted with the
+/// scrypt recipient of the [age](https://age-encryption.org) format, so the files
+/// can also be decrypted with the `age` or `rage` CLI. On unix, the files are
+/// readable only by their owner.
+pub struct FileKeystore {
+    dir: PathBuf,
+    passphrase: Secret<String>,
+}
+
+impl FileKeystore {
+    /// Open the keystore in the given directory, creating the directory if it
+    /// doesn't exist.
+    ///
+    /// On unix, the created directory is accessible only by its owner.
+    pub fn open(dir: impl AsRef<Path>, passphrase: impl Into<String>) -> Result<Self> {
+        let dir = dir.as_ref().to_owned();
+
+        let mut builder = fs::DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+        builder.create(&dir)?;
+
+        Ok(FileKeystore {
+            dir,
+            passphrase: Secret::new(passphrase.into()),
+        })
+    }
+
+    /// Directory of the keystore.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Names of the stored keys, sorted.
+    pub fn list(&self) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+
+            if path.extension().and_then(|ext| ext.to_str()) != Some(KEY_FILE_EXTENSION) {
+                continue;
+            }
+
+            if let Some(name) = path.file_stem().and_then(|name| name.to_str()) {
+                names.push(name.to_owned());
+            }
+        }
+
+        names.sort();
+        Ok(names)
+    }
+
+    fn key_path(&self, name: &str) -> Result<PathBuf> {
+        validate_name(name)?;
+        Ok(self.dir.join(format!("{name}.{KEY_FILE_EXTENSION}")))
+    }
+
+    fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let encryptor = age::Encryptor::with_user_passphrase(self.passphrase.clone());
+        let mut encrypted = Vec::new();
+
+        let mut writer = encryptor.wrap_output(&mut encrypted)?;
+        writer.write_all(plaintext)?;
+        writer.finish()?;
+
+        Ok(encrypted)
+    }
+
+    fn decrypt(&self, encrypted: &[u8]) -> Result<Zeroizing<Vec<u8>>> {
+        let age::Decryptor::Passphrase(decryptor) = age::Decryptor::new(encrypted)? else {
+            return Err(KeystoreError::InvalidKey);
+        };
+
+        let mut reader = decryptor
+            .decrypt(&self.passphrase, None)
+            .map_err(|e| match e {
+                age::DecryptError::DecryptionFailed | age::DecryptError::KeyDecryptionFailed => {
+                    KeystoreError::InvalidPassphrase
+                }
+                e => e.into(),
+            })?;
+        // hex encoded key, reserved upfront so that it's not left behind when reallocating
+        let mut decrypted = Zeroizing::new(Vec::with_capacity(64));
+        reader.read_to_end(&mut decrypted)?;
+
+        Ok(decrypted)
+    }
+}
+
+impl Keystore for FileKeystore {
+    fn insert(&self, name: &str, key: &SigningKey) -> Result<()> {
+        let path = self.key_path(name)?;
+        let hex = Zeroizing::new(hex::encode(key.to_bytes()));
+        let encrypted = self.encrypt(hex.as_bytes())?;
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        let mut file = match options.open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                return Err(KeystoreError::KeyExists(name.to_owned()))
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        file.write_all(&encrypted)?;
+        file.sync_all()?;
+
+        Ok(())
+    }
+
+    fn get(&self, name: &str) -> Result<SigningKey> {
+        let path = self.key_path(name)?;
+
+        let encrypted = match fs::read(&path) {
+            Ok(encrypted) => encrypted,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Err(KeystoreError::KeyNotFound(name.to_owned()))
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let decrypted = self.decrypt(&encrypted)?;
+        let hex = std::str::from_utf8(&decrypted).map_err(|_| KeystoreError::InvalidKey)?;
+
+        import_hex_key(hex)
+    }
+
+    fn remove(&self, name: &str) -> Result<()> {
+        let path = self.key_path(name)?;
+
+        match fs::remove_file(path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                Err(KeystoreError::KeyNotFound(name.to_owned()))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// [`Keystore`] keeping the keys in the keychain of the operating system, e.g. the
+/// macOS Keychain, Windows Credential Manager or the Secret Service on Linux.
+///
+/// Keys are stored hex encoded as the passwords of the entries of the configured
+/// service, with the key name used as the user.
+#[cfg(feature = "os-keychain")]
+#[cfg_attr(docs_rs, doc(cfg(feature = "os-keychain")))]
+#[derive(Debug, Clone)]
+pub struct OsKeychain {
+    service: String,
+}
+
+#[cfg(feature = "os-keychain")]
+impl OsKeychain {
+    /// Default name of the service the keys are stored under.
+    pub const DEFAULT_SERVICE: &'static str = "lumina";
+
+    /// Create a new keychain storing the keys under the given service name.
+    pub fn new(service: impl Into<String>) -> Self {
+        OsKeychain {
+            service: service.into(),
+        }
+    }
+
+    /// Create the [`Keyring`] signing with the key of the given name.
+    ///
+    /// The key is loaded once to read its public key, and then again for each signature.
+    pub fn keyring(&self, name: &str) -> Result<OsKeychainKeyring> {
+        let key = self.get(name)?;
+
+        Ok(OsKeychainKeyring {
+            keychain: self.clone(),
+            name: name.to_owned(),
+            public_key: *key.verifying_key(),
+        })
+    }
+
+    fn entry(&self, name: &str) -> Result<keyring::Entry> {
+        validate_name(name)?;
+        Ok(keyring::Entry::new(&self.service, name)?)
+    }
+}
+
+#[cfg(feature = "os-keychain")]
+impl Default for OsKeychain {
+    fn default() -> Self {
+        OsKeychain::new(OsKeychain::DEFAULT_SERVICE)
+    }
+}
+
+#[cfg(feature = "os-keychain")]
+impl Keystore for OsKeychain {
+    fn insert(&self, name: &str, key: &SigningKey) -> Result<()> {
+        let entry = self.entry(name)?;
+
+        match entry.get_password() {
+            Ok(_) => return Err(KeystoreError::KeyExists(name.to_owned())),
+            Err(keyring::Error::NoEntry) => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        let hex = Zeroizing::new(hex::encode(key.to_bytes()));
+        entry.set_password(&hex)?;
+        Ok(())
+    }
+
+    fn get(&self, name: &str) -> Result<SigningKey> {
+        match self.entry(name)?.get_password() {
+            Ok(hex) => import_hex_key(&Zeroizing::new(hex)),
+            Err(keyring::Error::NoEntry) => Err(KeystoreError::KeyNotFound(name.to_owned())),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn remove(&self, name: &str) -> Result<()> {
+        match self.entry(name)?.delete_password() {
+            Ok(()) => Ok(()),
+            Err(keyring::Error::NoEntry) => Err(KeystoreError::KeyNotFound(name.to_owned())),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// [`Keyring`] signing with the key kept in the [`OsKeychain`].
+///
+/// The key is loaded from the keychain for each signature and dropped right after, so
+/// it doesn't stay in the memory of the process between the transactions. Created with
+/// [`OsKeychain::keyring`].
+#[cfg(feature = "os-keychain")]
+#[cfg_attr(docs_rs, doc(cfg(feature = "os-keychain")))]
+#[derive(Debug)]
+pub struct OsKeychainKeyring {
+    keychain: OsKeychain,
+    name: String,
+    public_key: VerifyingKey,
+}
+
+#[cfg(feature = "os-keychain")]
+impl Keyring for OsKeychainKeyring {
+    fn public_key(&self) -> VerifyingKey {
+        self.public_key
+    }
+
+    fn sign(&self, sign_doc: &[u8]) -> Result<Signature, k256::ecdsa::Error> {
+        let key = self.keychain.get(&self.name).map_err(|e| {
+            warn!("Loading key {} from OS keychain failed: {e}", self.name);
+            k256::ecdsa::Error::new()
+        })?;
+
+        // key could be replaced in the keychain since the keyring was created
+        if *key.verifying_key() != self.public_key {
+            warn!("Key {} in OS keychain was replaced", self.name);
+            return Err(k256::ecdsa::Error::new());
+        }
+
+        Keyring::sign(&key, sign_doc)
+    }
+}
+
+/// Import the key from the unarmored hex export of the `celestia-appd`.
+///
+/// Such export is produced by `celestia-appd keys export <name> --unarmored-hex --unsafe`.
+pub fn import_hex_key(hex: &str) -> Result<SigningKey> {
+    let bytes = Zeroizing::new(hex::decode(hex.trim()).map_err(|_| KeystoreError::InvalidKey)?);
+    SigningKey::from_slice(&bytes).map_err(|_| KeystoreError::InvalidKey)
+}
+
+/// Import the key from the ASCII armored export of the `celestia-appd`.
+///
+/// Such export is produced by `celestia-appd keys export <name>` and is encrypted
+/// with the `passphrase` given to the export.
+pub fn import_armored_key(armored: &str, passphrase: &str) -> Result<SigningKey> {
+    let armor = Armor::decode(armored)?;
+
+    if armor.block_type != ARMOR_BLOCK_TYPE {
+        return Err(KeystoreError::InvalidArmor(format!(
+            "unexpected block type: {}",
+            armor.block_type
+        )));
+    }
+
+    match armor.header("kdf") {
+        Some(ARMOR_KDF) => {}
+        Some(kdf) => {
+            return Err(KeystoreError::InvalidArmor(format!(
+                "unsupported kdf: {kdf}"
+            )))
+        }
+        None => return Err(KeystoreError::InvalidArmor("missing kdf".to_owned())),
+    }
+
+    match armor.header("type") {
+        // older exports don't specify the type of the key
+        Some(ARMOR_KEY_TYPE) | None => {}
+        Some(key_type) => {
+            return Err(KeystoreError::InvalidArmor(format!(
+                "unsupported key type: {key_type}"
+            )))
+        }
+    }
+
+    let salt = armor
+        .header("salt")
+        .ok_or_else(|| KeystoreError::InvalidArmor("missing salt".to_owned()))?;
+    let salt: [u8; 16] = hex::decode(salt)
+        .ok()
+        .and_then(|salt| salt.try_into().ok())
+        .ok_or_else(|| KeystoreError::InvalidArmor("invalid salt".to_owned()))?;
+
+    let amino = decrypt_armored(&armor.data, &salt, passphrase)?;
+    let key = amino
+        .strip_prefix(&AMINO_SECP256K1_PREFIX)
+        .ok_or(KeystoreError::InvalidKey)?;
+
+    SigningKey::from_slice(key).map_err(|_| KeystoreError::InvalidKey)
+}
+
+/// Derive the key the same way as the cosmos-sdk, with the sha256 of the bcrypt hash
+/// string, and open the xsalsa20-poly1305 secretbox prefixed with its nonce.
+fn decrypt_armored(data: &[u8], salt: &[u8; 16], passphrase: &str) -> Result<Zeroizing<Vec<u8>>> {
+    let secret = armor_secret(salt, passphrase)?;
+
+    if data.len() < XSALSA20_NONCE_LEN {
+        return Err(KeystoreError::InvalidArmor("data too short".to_owned()));
+    }
+    let (nonce, ciphertext) = data.split_at(XSALSA20_NONCE_LEN);
+
+    XSalsa20Poly1305::new((&*secret).into())
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map(Zeroizing::new)
+        .map_err(|_| KeystoreError::InvalidPassphrase)
+}
+
+fn armor_secret(salt: &[u8; 16], passphrase: &str) -> Result<Zeroizing<[u8; 32]>> {
+    let hash = bcrypt::hash_with_salt(passphrase, BCRYPT_COST, *salt)
+        .map_err(|e| KeystoreError::InvalidArmor(e.to_string()))?
+        .format_for_version(bcrypt::Version::TwoA);
+    let hash = Zeroizing::new(hash);
+
+    Ok(Zeroizing::new(Sha256::digest(hash.as_bytes()).into()))
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+
+    if valid {
+        Ok(())
+    } else {
+        Err(KeystoreError::InvalidName(name.to_owned()))
+    }
+}
+
+/// OpenPGP style ASCII armor, as used by the tendermint and cosmos-sdk.
+struct Armor {
+    block_type: String,
+    headers: Vec<(String, String)>,
+    data: Vec<u8>,
+}
+
+impl Armor {
+    fn decode(armored: &str) -> Result<Self> {
+        let invalid = |msg: &str| KeystoreError::InvalidArmor(msg.to_owned());
+        // anything preceding the armor, like the prompts of the export, is skipped
+        let mut lines = armored
+            .lines()
+            .map(str::trim)
+            .skip_while(|l| !l.starts_with("-----BEGIN "));
+
+        let block_type = lines
+            .next()
+            .and_then(|line| line.strip_prefix("-----BEGIN "))
+            .and_then(|line| line.strip_suffix("-----"))
+            .ok_or_else(|| invalid("missing begin line"))?
+            .to_owned();
+
+        let mut headers = Vec::new();
+        for line in lines.by_ref() {
+            if line.is_empty() {
+                break;
+            }
+            let (key, value) = line
+                .split_once(':')
+                .ok_or_else(|| invalid("invalid header"))?;
+            headers.push((key.trim().to_owned(), value.trim().to_owned()));
+        }
+
+        let mut body = String::new();
+        let mut checksum = None;
+        for line in lines.by_ref() {
+            if let Some(crc) = line.strip_prefix('=') {
+                checksum = Some(crc.to_owned());
+            } else if line.starts_with("-----END ") {
+                if line != format!("-----END {block_type}-----") {
+                    return Err(invalid("mismatched end line"));
+                }
+                let data = BASE64_STANDARD
+                    .decode(&body)
+                    .map_err(|_| invalid("invalid base64 data"))?;
+
+                if let Some(checksum) = checksum {
+                    let expected = BASE64_STANDARD
+                        .decode(checksum)
+                        .map_err(|_| invalid("invalid checksum"))?;
+                    if expected != crc24(&data).to_be_bytes()[1..] {
+                        return Err(invalid("checksum mismatch"));
+                    }
+                }
+
+                return Ok(Armor {
+                    block_type,
+                    headers,
+                    data,
+                });
+            } else {
+                body.push_str(line);
+            }
+        }
+
+        Err(invalid("missing end line"))
+    }
+
+    fn header(&self, key: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// CRC-24 checksum of the armored data, as specified in RFC 4880.
+fn crc24(data: &[u8]) -> u32 {
+    const INIT: u32 = 0xb704ce;
+    const POLY: u32 = 0x1864cfb;
+
+    let mut crc = INIT;
+    for byte in data {
+        crc ^= (*byte as u32) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x1000000 != 0 {
+                crc ^= POLY;
+            }
+        }
+    }
+
+    crc & 0xffffff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blob_submitter::Keyring;
+    use celestia_tendermint::account::Id;
+    use celestia_types::state::AccAddress;
+    use crypto_secretbox::aead::AeadCore;
+    use rand::rngs::OsRng;
+    use tempdir::TempDir;
+
+    #[test]
+    fn file_keystore_roundtrip() {
+        let dir = TempDir::new("lumina-keystore").unwrap();
+        let keystore = FileKeystore::open(dir.path(), "passphrase").unwrap();
+        let key = SigningKey::random(&mut OsRng);
+
+        keystore.insert("alice", &key).unwrap();
+        assert!(matches!(
+            keystore.insert("alice", &key),
+            Err(KeystoreError::KeyExists(_))
+        ));
+        assert_eq!(keystore.list().unwrap(), vec!["alice".to_owned()]);
+
+        let loaded = keystore.get("alice").unwrap();
+        assert_eq!(loaded.public_key(), key.public_key());
+
+        // key files are never stored in plain text
+        let stored = fs::read(dir.path().join("alice.age")).unwrap();
+        let hex = hex::encode(key.to_bytes());
+        assert!(!stored.windows(hex.len()).any(|w| w == hex.as_bytes()));
+
+        let wrong = FileKeystore::open(dir.path(), "wrong").unwrap();
+        assert!(matches!(
+            wrong.get("alice"),
+            Err(KeystoreError::InvalidPassphrase)
+        ));
+
+        keystore.remove("alice").unwrap();
+        assert!(matches!(
+            keystore.get("alice"),
+            Err(KeystoreError::KeyNotFound(_))
+        ));
+        assert!(keystore.list().unwrap().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn file_keystore_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new("lumina-keystore").unwrap();
+        let keystore_dir = dir.path().join("keys");
+        let keystore = FileKeystore::open(&keystore_dir, "passphrase").unwrap();
+        keystore
+            .insert("alice", &SigningKey::random(&mut OsRng))
+            .unwrap();
+
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&keystore_dir), 0o700);
+        assert_eq!(mode(&keystore_dir.join("alice.age")), 0o600);
+    }
+
+    #[test]
+    fn invalid_key_names() {
+        let dir = TempDir::new("lumina-keystore").unwrap();
+        let keystore = FileKeystore::open(dir.path(), "passphrase").unwrap();
+        let key = SigningKey::random(&mut OsRng);
+
+        for name in ["", ".hidden", "../alice", "a/b", "a b"] {
+            assert!(matches!(
+                keystore.insert(name, &key),
+                Err(KeystoreError::InvalidName(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn import_hex() {
+        let key = SigningKey::random(&mut OsRng);
+        let hex = format!("{}\n", hex::encode_upper(key.to_bytes()));
+
+        let imported = import_hex_key(&hex).unwrap();
+        assert_eq!(imported.to_bytes(), key.to_bytes());
+
+        assert!(matches!(
+            import_hex_key("not a key"),
+            Err(KeystoreError::InvalidKey)
+        ));
+    }
+
+    #[test]
+    fn import_armored() {
+        let key = SigningKey::random(&mut OsRng);
+        let armored = armor_key(&key, "passphrase");
+
+        let imported = import_armored_key(&armored, "passphrase").unwrap();
+        assert_eq!(imported.to_bytes(), key.to_bytes());
+
+        assert!(matches!(
+            import_armored_key(&armored, "wrong"),
+            Err(KeystoreError::InvalidPassphrase)
+        ));
+
+        let tampered = armored.replace("type: secp256k1", "type: ed25519");
+        assert!(matches!(
+            import_armored_key(&tampered, "passphrase"),
+            Err(KeystoreError::InvalidArmor(_))
+        ));
+    }
+
+    /// Key exported with `celestia-appd keys export` by the validator of the devnet,
+    /// together with its address, see `ci/run-validator.sh`.
+    #[test]
+    fn import_celestia_appd_export() {
+        let credentials = Path::new(env!("CARGO_MANIFEST_DIR")).join("../ci/credentials");
+        let armored = fs::read_to_string(credentials.join("bridge-0.key")).unwrap();
+        let address = fs::read_to_string(credentials.join("bridge-0.addr")).unwrap();
+
+        let key = import_armored_key(&armored, "password").unwrap();
+        let expected: AccAddress = address.trim().parse().unwrap();
+        assert_eq!(AccAddress::new(Id::from(key.public_key())), expected);
+    }
+
+    #[test]
+    fn crc24_checksum() {
+        // check value of the CRC-24/OPENPGP
+        assert_eq!(crc24(b"123456789"), 0x21cf02);
+    }
+
+    /// Export the key the same way as `celestia-appd keys export`.
+    fn armor_key(key: &SigningKey, passphrase: &str) -> String {
+        let salt: [u8; 16] = rand::random();
+        let secret = armor_secret(&salt, passphrase).unwrap();
+
+        let mut amino = AMINO_SECP256K1_PREFIX.to_vec();
+        amino.extend_from_slice(&key.to_bytes());
+
+        let nonce = XSalsa20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = XSalsa20Poly1305::new((&*secret).into())
+            .encrypt(&nonce, amino.as_slice())
+            .unwrap();
+        let mut data = nonce.to_vec();
+        data.extend_from_slice(&ciphertext);
+
+        let body = BASE64_STANDARD.encode(&data);
+        let checksum = BASE64_STANDARD.encode(&crc24(&data).to_be_bytes()[1..]);
+        let body_lines: Vec<_> = body
+            .as_bytes()
+            .chunks(64)
+            .map(|chunk| std::str::from_utf8(chunk).unwrap())
+            .collect();
+
+        format!(
+            "-----BEGIN {ARMOR_BLOCK_TYPE}-----\n\
+             kdf: bcrypt\n\
+             salt: {}\n\
+             type: secp256k1\n\
+             \n\
+             {}\n\
+             ={checksum}\n\
+             -----END {ARMOR_BLOCK_TYPE}-----\n",
+            hex::encode_upper(salt),
+            body_lines.join("\n"),
+        )
+    }
+}
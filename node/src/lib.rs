@@ -1,12 +1,37 @@
 #![cfg_attr(docs_rs, feature(doc_cfg))]
 #![doc = include_str!("../README.md")]
 
+mod address_book;
+mod bitswap;
+#[cfg(feature = "blob-submitter")]
+#[cfg_attr(docs_rs, doc(cfg(feature = "blob-submitter")))]
+pub mod blob_submitter;
+pub mod clock;
+pub mod daser;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod eds_store;
+pub mod events;
 mod executor;
+#[cfg(feature = "gateway")]
+#[cfg_attr(docs_rs, doc(cfg(feature = "gateway")))]
+pub mod gateway;
 mod header_ex;
+mod ip_limits;
+#[cfg(all(not(target_arch = "wasm32"), feature = "keystore"))]
+#[cfg_attr(docs_rs, doc(cfg(feature = "keystore")))]
+pub mod keystore;
+pub mod metrics;
+pub mod namespace_subscription;
+pub mod namespaced_data_range;
 pub mod network;
 pub mod node;
 pub mod p2p;
 pub mod peer_tracker;
+pub mod pruner;
+pub mod row_subscription;
+#[cfg(all(not(target_arch = "wasm32"), feature = "rpc-server"))]
+#[cfg_attr(docs_rs, doc(cfg(feature = "rpc-server")))]
+pub mod rpc_server;
 mod session;
 pub mod store;
 mod swarm;
@@ -14,4 +39,7 @@ pub mod syncer;
 #[cfg(any(test, feature = "test-utils"))]
 #[cfg_attr(docs_rs, doc(cfg(feature = "test-utils")))]
 pub mod test_utils;
+#[cfg(feature = "blob-submitter")]
+#[cfg_attr(docs_rs, doc(cfg(feature = "blob-submitter")))]
+pub mod tx_client;
 mod utils;
@@ -0,0 +1,169 @@
+//! Metrics of the [`Node`].
+//!
+//! With the `metrics` feature enabled, the components of the [`Node`] record the metrics
+//! listed below using the [`metrics`] facade. They are exported by whichever recorder
+//! is installed in the application, e.g. the `metrics-exporter-prometheus`. Without
+//! the feature, recording of the metrics compiles to nothing.
+//!
+//! Call [`describe`] after installing the recorder to register the units and
+//! descriptions of the metrics.
+//!
+//! [`Node`]: crate::node::Node
+//! [`metrics`]: https://docs.rs/metrics
+
+use instant::Instant;
+
+/// Counter of the headers added to the store by the [`Syncer`].
+///
+/// [`Syncer`]: crate::syncer::Syncer
+pub const HEADERS_SYNCED: &str = "lumina_syncer_headers_synced_total";
+/// Gauge of the height of the local head of the store.
+pub const LOCAL_HEAD_HEIGHT: &str = "lumina_syncer_local_head_height";
+/// Histogram of the time it took to fetch and store a batch of headers, in seconds.
+pub const HEADER_BATCH_DURATION: &str = "lumina_syncer_batch_duration_seconds";
+/// Counter of the batches of headers which failed to be fetched or stored.
+pub const HEADER_BATCH_FAILURES: &str = "lumina_syncer_batch_failures_total";
+/// Histogram of the time it took to retrieve and verify a sample, in seconds.
+pub const SAMPLING_DURATION: &str = "lumina_sampling_duration_seconds";
+/// Counter of the samples which couldn't be retrieved or verified.
+pub const SAMPLING_FAILURES: &str = "lumina_sampling_failures_total";
+/// Counter of the `header-ex` requests sent to the peers.
+pub const HEADER_EX_REQUESTS: &str = "lumina_header_ex_requests_total";
+/// Counter of the `header-ex` requests which failed or got an invalid response.
+pub const HEADER_EX_REQUEST_FAILURES: &str = "lumina_header_ex_request_failures_total";
+/// Counter of the `header-ex` requests received from the peers.
+pub const HEADER_EX_REQUESTS_SERVED: &str = "lumina_header_ex_requests_served_total";
+/// Counter of the `header-ex` requests from the peers rejected by the rate limit.
+pub const HEADER_EX_REQUESTS_RATE_LIMITED: &str = "lumina_header_ex_requests_rate_limited_total";
+/// Counter of the wanted blocks received over bitswap.
+pub const BITSWAP_BLOCKS_RECEIVED: &str = "lumina_bitswap_blocks_received_total";
+/// Counter of the blocks sent to the peers over bitswap.
+pub const BITSWAP_BLOCKS_SERVED: &str = "lumina_bitswap_blocks_served_total";
+/// Histogram of the time it took to read the headers from the store, in seconds.
+pub const STORE_READ_DURATION: &str = "lumina_store_read_duration_seconds";
+/// Histogram of the time it took to write the headers to the store, in seconds.
+pub const STORE_WRITE_DURATION: &str = "lumina_store_write_duration_seconds";
+/// Gauge of the number of the connected peers.
+pub const CONNECTED_PEERS: &str = "lumina_p2p_connected_peers";
+/// Gauge of the number of the connected trusted peers.
+pub const CONNECTED_TRUSTED_PEERS: &str = "lumina_p2p_connected_trusted_peers";
+/// Counter of the identified peers by the version of the protocols spoken with them.
+///
+/// Labeled with the `protocol`, either `header-ex` or `shwap`, and the `version`, the
+/// protocol id of the version or `none` if the peer doesn't speak any of them.
+pub const PEER_PROTOCOL_VERSIONS: &str = "lumina_p2p_peer_protocol_versions_total";
+
+/// Register the units and descriptions of all the metrics with the installed recorder.
+#[cfg(feature = "metrics")]
+#[cfg_attr(docs_rs, doc(cfg(feature = "metrics")))]
+pub fn describe() {
+    use metrics::{describe_counter, describe_gauge, describe_histogram, Unit};
+
+    describe_counter!(HEADERS_SYNCED, Unit::Count, "Headers added to the store");
+    describe_gauge!(LOCAL_HEAD_HEIGHT, Unit::Count, "Height of the local head");
+    describe_histogram!(
+        HEADER_BATCH_DURATION,
+        Unit::Seconds,
+        "Time to fetch and store a batch of headers"
+    );
+    describe_counter!(
+        HEADER_BATCH_FAILURES,
+        Unit::Count,
+        "Batches of headers failed to be synced"
+    );
+    describe_histogram!(
+        SAMPLING_DURATION,
+        Unit::Seconds,
+        "Time to retrieve and verify a sample"
+    );
+    describe_counter!(
+        SAMPLING_FAILURES,
+        Unit::Count,
+        "Samples failed to be retrieved"
+    );
+    describe_counter!(HEADER_EX_REQUESTS, Unit::Count, "Sent header-ex requests");
+    describe_counter!(
+        HEADER_EX_REQUEST_FAILURES,
+        Unit::Count,
+        "Failed header-ex requests"
+    );
+    describe_counter!(
+        HEADER_EX_REQUESTS_SERVED,
+        Unit::Count,
+        "Received header-ex requests"
+    );
+    describe_counter!(
+        HEADER_EX_REQUESTS_RATE_LIMITED,
+        Unit::Count,
+        "Received header-ex requests rejected by the rate limit"
+    );
+    describe_counter!(
+        BITSWAP_BLOCKS_RECEIVED,
+        Unit::Count,
+        "Wanted blocks received over bitswap"
+    );
+    describe_counter!(
+        BITSWAP_BLOCKS_SERVED,
+        Unit::Count,
+        "Blocks sent to the peers over bitswap"
+    );
+    describe_histogram!(
+        STORE_READ_DURATION,
+        Unit::Seconds,
+        "Time to read headers from the store"
+    );
+    describe_histogram!(
+        STORE_WRITE_DURATION,
+        Unit::Seconds,
+        "Time to write headers to the store"
+    );
+    describe_gauge!(CONNECTED_PEERS, Unit::Count, "Connected peers");
+    describe_gauge!(
+        CONNECTED_TRUSTED_PEERS,
+        Unit::Count,
+        "Connected trusted peers"
+    );
+    describe_counter!(
+        PEER_PROTOCOL_VERSIONS,
+        Unit::Count,
+        "Identified peers by the version of the protocols spoken with them"
+    );
+}
+
+/// Increment the counter by the given value.
+pub(crate) fn increment(name: &'static str, value: u64) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!(name, value);
+    #[cfg(not(feature = "metrics"))]
+    let _ = (name, value);
+}
+
+/// Increment the counter with the given labels by the given value.
+pub(crate) fn increment_labeled(name: &'static str, labels: &[(&'static str, &str)], value: u64) {
+    #[cfg(feature = "metrics")]
+    {
+        let labels: Vec<_> = labels
+            .iter()
+            .map(|(key, value)| metrics::Label::new(*key, value.to_string()))
+            .collect();
+        metrics::counter!(name, value, labels);
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = (name, labels, value);
+}
+
+/// Set the value of the gauge.
+pub(crate) fn set(name: &'static str, value: u64) {
+    #[cfg(feature = "metrics")]
+    metrics::gauge!(name, value as f64);
+    #[cfg(not(feature = "metrics"))]
+    let _ = (name, value);
+}
+
+/// Record the time elapsed since `started` in the histogram.
+pub(crate) fn record_duration(name: &'static str, started: Instant) {
+    #[cfg(feature = "metrics")]
+    metrics::histogram!(name, started.elapsed().as_secs_f64());
+    #[cfg(not(feature = "metrics"))]
+    let _ = (name, started);
+}
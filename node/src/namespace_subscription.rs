@@ -0,0 +1,219 @@
+//! Subscription to the data of multiple namespaces in newly synchronized blocks.
+//!
+//! For every header appended to the store, the data of each subscribed
+//! [`Namespace`] is requested from the network, verified against the block's
+//! [`DataAvailabilityHeader`] and emitted as a [`NamespacedDataEvent`].
+//!
+//! [`DataAvailabilityHeader`]: celestia_types::DataAvailabilityHeader
+
+use std::sync::Arc;
+
+use celestia_types::namespaced_data::NamespacedData;
+use celestia_types::nmt::Namespace;
+use tokio::select;
+use tokio::sync::{mpsc, watch};
+use tracing::{debug, warn};
+
+use crate::executor::spawn;
+use crate::p2p::{P2p, P2pError};
+use crate::store::{Store, StoreError};
+
+/// Data of a single namespace in a synchronized block.
+#[derive(Debug)]
+pub struct NamespacedDataEvent {
+    /// Height of the block.
+    pub height: u64,
+    /// Namespace that the data belongs to.
+    pub namespace: Namespace,
+    /// Verified rows of the namespace data, or an error if they could not be retrieved.
+    ///
+    /// An empty list means that the block has no data in the namespace.
+    pub result: Result<Vec<NamespacedData>, P2pError>,
+}
+
+/// Arguments used to configure the namespaces subscription.
+pub(crate) struct SubscriptionArgs<S>
+where
+    S: Store + 'static,
+{
+    /// Handler for the peer to peer messaging.
+    pub(crate) p2p: Arc<P2p<S>>,
+    /// Headers storage.
+    pub(crate) store: Arc<S>,
+    /// Watcher for the height of the latest synchronized header.
+    pub(crate) local_head_watcher: watch::Receiver<u64>,
+    /// Namespaces to retrieve the data of.
+    pub(crate) namespaces: Vec<Namespace>,
+    /// Height of the first block to retrieve the data from.
+    pub(crate) start_height: u64,
+}
+
+/// Start the subscription, returning the receiving side of the events channel.
+///
+/// The subscription stops when the receiver is dropped or the syncer stops.
+pub(crate) fn subscribe<S>(args: SubscriptionArgs<S>) -> mpsc::Receiver<NamespacedDataEvent>
+where
+    S: Store,
+{
+    let (tx, rx) = mpsc::channel(args.namespaces.len().max(1) * 16);
+
+    spawn(async move {
+        run(args, tx).await;
+    });
+
+    rx
+}
+
+async fn run<S>(args: SubscriptionArgs<S>, tx: mpsc::Sender<NamespacedDataEvent>)
+where
+    S: Store,
+{
+    let SubscriptionArgs {
+        p2p,
+        store,
+        mut local_head_watcher,
+        namespaces,
+        start_height,
+    } = args;
+    let mut next_height = start_height.max(1);
+
+    'subscription: loop {
+        select! {
+            _ = tx.closed() => break,
+            res = local_head_watcher.wait_for(|height| *height >= next_height) => {
+                if res.is_err() {
+                    // Syncer stopped
+                    break;
+                }
+            }
+        }
+
+        let header = match store.get_by_height(next_height).await {
+            Ok(header) => header,
+            // Pruned before we got to it
+            Err(StoreError::NotFound) => {
+                next_height += 1;
+                continue;
+            }
+            Err(e) => {
+                warn!("Failed to get header {next_height} for namespace subscription: {e}");
+                break;
+            }
+        };
+
+        for namespace in &namespaces {
+            let result = select! {
+                _ = tx.closed() => break 'subscription,
+                res = p2p.get_namespaced_data(*namespace, &header) => res,
+            };
+
+            let event = NamespacedDataEvent {
+                height: next_height,
+                namespace: *namespace,
+                result,
+            };
+
+            if tx.send(event).await.is_err() {
+                break 'subscription;
+            }
+        }
+
+        next_height += 1;
+    }
+
+    debug!("Namespace subscription stopped");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::InMemoryStore;
+    use crate::test_utils::{deliver_shwap_block, gen_filled_store};
+    use celestia_proto::share::p2p::shwap::Data as RawNamespacedData;
+    use celestia_types::namespaced_data::NamespacedDataId;
+    use celestia_types::test_utils::ExtendedHeaderGenerator;
+    use celestia_types::{DataAvailabilityHeader, ExtendedDataSquare};
+    use prost::Message;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    use tokio::test as async_test;
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as async_test;
+
+    #[async_test]
+    async fn emits_verified_data_for_synced_headers() {
+        let eds_json = include_str!("../../types/test_data/shwap_samples/eds.json");
+        let eds: ExtendedDataSquare = serde_json::from_str(eds_json).unwrap();
+        let dah_json = include_str!("../../types/test_data/shwap_samples/dah.json");
+        let dah: DataAvailabilityHeader = serde_json::from_str(dah_json).unwrap();
+        let namespace = Namespace::new_v0(&[1, 187]).unwrap();
+
+        let store = Arc::new(InMemoryStore::new());
+        let mut gen = ExtendedHeaderGenerator::new();
+        let mut header = gen.next();
+        header.dah = dah.clone();
+        store.append_single_unchecked(header).unwrap();
+
+        let (p2p, mut handle) = P2p::mocked();
+        let (local_head_tx, local_head_watcher) = watch::channel(0);
+
+        let mut rx = subscribe(SubscriptionArgs {
+            p2p: Arc::new(p2p),
+            store,
+            local_head_watcher,
+            namespaces: vec![namespace],
+            start_height: 1,
+        });
+
+        // Nothing is requested until the header is synced
+        handle.expect_no_cmd().await;
+        local_head_tx.send_replace(1);
+
+        let expected = eds.get_namespaced_data(namespace, &dah, 1).unwrap();
+
+        for row in &expected {
+            let (cid, respond_to) = handle.expect_get_shwap_cid().await;
+            let id = NamespacedDataId::try_from(cid).unwrap();
+            assert_eq!(id, row.namespaced_data_id);
+
+            let raw = RawNamespacedData::from(row.clone());
+            deliver_shwap_block(&respond_to, raw.encode_to_vec());
+        }
+
+        let event = rx.recv().await.unwrap();
+        assert_eq!(event.height, 1);
+        assert_eq!(event.namespace, namespace);
+
+        let rows = event.result.unwrap();
+        assert_eq!(rows.len(), expected.len());
+        assert_eq!(rows[0].shares, expected[0].shares);
+        assert_eq!(rows[1].shares, expected[1].shares);
+
+        // verified rows are stored to be provided to other peers
+        for (cid, _) in handle.expect_verified_shwap_blocks(expected.len()).await {
+            let id = NamespacedDataId::try_from(cid).unwrap();
+            assert!(expected.iter().any(|row| row.namespaced_data_id == id));
+        }
+
+        handle.expect_no_cmd().await;
+    }
+
+    #[async_test]
+    async fn stops_when_receiver_dropped() {
+        let (store, _) = gen_filled_store(1);
+        let (p2p, mut handle) = P2p::mocked();
+        let (local_head_tx, local_head_watcher) = watch::channel(0);
+
+        let rx = subscribe(SubscriptionArgs {
+            p2p: Arc::new(p2p),
+            store: Arc::new(store),
+            local_head_watcher,
+            namespaces: vec![Namespace::new_v0(&[1, 187]).unwrap()],
+            start_height: 1,
+        });
+        drop(rx);
+
+        local_head_tx.send_replace(1);
+        handle.expect_no_cmd().await;
+    }
+}
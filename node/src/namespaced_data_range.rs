@@ -0,0 +1,237 @@
+//! Retrieval of the data of a namespace across a range of heights.
+//!
+//! The progress of the retrieval is tracked by a [`NamespacedDataCursor`], which
+//! can be persisted and used to resume it after an interruption with
+//! [`Node::resume_namespaced_data_range`].
+//!
+//! [`Node::resume_namespaced_data_range`]: crate::node::Node::resume_namespaced_data_range
+
+use std::sync::Arc;
+
+use celestia_types::namespaced_data::NamespacedData;
+use celestia_types::nmt::Namespace;
+use serde::{Deserialize, Serialize};
+
+use crate::node::NodeError;
+use crate::p2p::P2p;
+use crate::store::Store;
+
+type Result<T, E = NodeError> = std::result::Result<T, E>;
+
+/// Position of the retrieval of a namespace data in a range of heights.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamespacedDataCursor {
+    namespace: Namespace,
+    next_height: u64,
+    to_height: u64,
+}
+
+impl NamespacedDataCursor {
+    /// Create a new cursor for the inclusive range of heights `from_height..=to_height`.
+    ///
+    /// The range starts at the genesis height if `from_height` is `0`, and is empty if
+    /// `from_height` is greater than `to_height`.
+    pub fn new(namespace: Namespace, from_height: u64, to_height: u64) -> Self {
+        NamespacedDataCursor {
+            namespace,
+            next_height: from_height.max(1),
+            to_height,
+        }
+    }
+
+    /// Namespace of the retrieved data.
+    pub fn namespace(&self) -> Namespace {
+        self.namespace
+    }
+
+    /// Height of the next block to retrieve the data from.
+    pub fn next_height(&self) -> u64 {
+        self.next_height
+    }
+
+    /// Height of the last block in the range, inclusive.
+    pub fn to_height(&self) -> u64 {
+        self.to_height
+    }
+
+    /// Returns `true` if the data of all the heights in the range was retrieved.
+    pub fn is_finished(&self) -> bool {
+        self.next_height > self.to_height
+    }
+}
+
+/// Data of a namespace in a single block.
+#[derive(Debug, Clone)]
+pub struct NamespacedDataAtHeight {
+    /// Height of the block.
+    pub height: u64,
+    /// Verified rows of the namespace data.
+    ///
+    /// An empty list means that, according to the row roots of the block, the block
+    /// has no data in the namespace.
+    pub rows: Vec<NamespacedData>,
+}
+
+/// Iterator over the data of a namespace in a range of heights.
+///
+/// Created with [`Node::get_namespaced_data_range`].
+///
+/// [`Node::get_namespaced_data_range`]: crate::node::Node::get_namespaced_data_range
+pub struct NamespacedDataRange<S>
+where
+    S: Store + 'static,
+{
+    p2p: Arc<P2p<S>>,
+    store: Arc<S>,
+    cursor: NamespacedDataCursor,
+}
+
+impl<S> NamespacedDataRange<S>
+where
+    S: Store,
+{
+    pub(crate) fn new(p2p: Arc<P2p<S>>, store: Arc<S>, cursor: NamespacedDataCursor) -> Self {
+        NamespacedDataRange { p2p, store, cursor }
+    }
+
+    /// Get the current position in the range.
+    ///
+    /// It points to the first height which wasn't yet returned by [`next`].
+    ///
+    /// [`next`]: NamespacedDataRange::next
+    pub fn cursor(&self) -> NamespacedDataCursor {
+        self.cursor
+    }
+
+    /// Retrieve the verified data of the namespace at the next height of the range.
+    ///
+    /// Only the rows whose roots may contain the namespace are requested. Returns
+    /// `None` once the whole range was retrieved.
+    ///
+    /// # Errors
+    ///
+    /// The cursor is advanced only when the data of a height was retrieved, so calling
+    /// this method again after an error retries the same height. The header of the
+    /// height must already be synchronized, otherwise the [`StoreError::NotFound`] is
+    /// returned.
+    ///
+    /// [`StoreError::NotFound`]: crate::store::StoreError::NotFound
+    pub async fn next(&mut self) -> Option<Result<NamespacedDataAtHeight>> {
+        if self.cursor.is_finished() {
+            return None;
+        }
+
+        let height = self.cursor.next_height;
+        let res = self.get_at_height(height).await;
+
+        if res.is_ok() {
+            self.cursor.next_height += 1;
+        }
+
+        Some(res)
+    }
+
+    async fn get_at_height(&self, height: u64) -> Result<NamespacedDataAtHeight> {
+        let header = self.store.get_by_height(height).await?;
+        let rows = self
+            .p2p
+            .get_namespaced_data(self.cursor.namespace, &header)
+            .await?;
+
+        Ok(NamespacedDataAtHeight { height, rows })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::{InMemoryStore, StoreError};
+    use crate::test_utils::deliver_shwap_block;
+    use celestia_proto::share::p2p::shwap::Data as RawNamespacedData;
+    use celestia_types::namespaced_data::NamespacedDataId;
+    use celestia_types::test_utils::ExtendedHeaderGenerator;
+    use prost::Message;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    use tokio::test as async_test;
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as async_test;
+
+    #[test]
+    fn cursor_bounds() {
+        let namespace = Namespace::new_v0(&[1]).unwrap();
+
+        let cursor = NamespacedDataCursor::new(namespace, 0, 3);
+        assert_eq!(cursor.next_height(), 1);
+        assert!(!cursor.is_finished());
+
+        let cursor = NamespacedDataCursor::new(namespace, 3, 3);
+        assert!(!cursor.is_finished());
+
+        let cursor = NamespacedDataCursor::new(namespace, 4, 3);
+        assert!(cursor.is_finished());
+    }
+
+    #[async_test]
+    async fn iterates_and_resumes() {
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+        let other_namespace = Namespace::new_v0(&[4, 5, 6]).unwrap();
+
+        let store = Arc::new(InMemoryStore::new());
+        let mut gen = ExtendedHeaderGenerator::new_with_seed(1);
+        let (header1, eds1) = gen.next_with_eds(2, &[namespace, other_namespace]);
+        let (header2, _) = gen.next_with_eds(2, &[other_namespace]);
+        store.append_single_unchecked(header1.clone()).unwrap();
+        store.append_single_unchecked(header2).unwrap();
+
+        let (p2p, mut handle) = P2p::mocked();
+        let p2p = Arc::new(p2p);
+        let cursor = NamespacedDataCursor::new(namespace, 1, 3);
+        let mut range = NamespacedDataRange::new(p2p.clone(), store.clone(), cursor);
+
+        let expected = eds1
+            .get_namespaced_data(namespace, &header1.dah, 1)
+            .unwrap();
+        // the namespace fills only the first row of the square
+        assert_eq!(expected.len(), 1);
+
+        let (data, _) = tokio::join!(range.next(), async {
+            let (cid, respond_to) = handle.expect_get_shwap_cid().await;
+            let id = NamespacedDataId::try_from(cid).unwrap();
+            assert_eq!(id, expected[0].namespaced_data_id);
+
+            let raw = RawNamespacedData::from(expected[0].clone());
+            deliver_shwap_block(&respond_to, raw.encode_to_vec());
+            handle.expect_verified_shwap_blocks(1).await;
+        });
+
+        let data = data.unwrap().unwrap();
+        assert_eq!(data.height, 1);
+        assert_eq!(data.rows.len(), 1);
+        assert_eq!(data.rows[0].shares, expected[0].shares);
+        assert_eq!(range.cursor().next_height(), 2);
+
+        // rows of the second block exclude the namespace, so nothing is requested
+        let data = range.next().await.unwrap().unwrap();
+        assert_eq!(data.height, 2);
+        assert!(data.rows.is_empty());
+
+        // third header is not synced yet and the cursor stays in place
+        let err = range.next().await.unwrap().unwrap_err();
+        assert!(matches!(err, NodeError::Store(StoreError::NotFound)));
+        let cursor = range.cursor();
+        assert_eq!(cursor.next_height(), 3);
+
+        // resume once it is synced
+        let (header3, _) = gen.next_with_eds(2, &[other_namespace]);
+        store.append_single_unchecked(header3).unwrap();
+
+        let mut range = NamespacedDataRange::new(p2p, store, cursor);
+        let data = range.next().await.unwrap().unwrap();
+        assert_eq!(data.height, 3);
+        assert!(range.cursor().is_finished());
+        assert!(range.next().await.is_none());
+
+        handle.expect_no_cmd().await;
+    }
+}
@@ -8,7 +8,7 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 /// Supported Celestia networks.
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub enum Network {
     /// Celestia mainnet.
     #[default]
@@ -19,6 +19,33 @@ pub enum Network {
     Mocha,
     /// Private local network.
     Private,
+    /// Network other than the built-in ones, eg. a private devnet.
+    Custom(CustomNetwork),
+}
+
+/// Parameters of a network which is not one of the built-in presets.
+///
+/// # Example
+///
+/// ```
+/// use lumina_node::network::{CustomNetwork, Network};
+///
+/// let network = Network::Custom(CustomNetwork {
+///     id: "my-devnet-1".to_string(),
+///     genesis_hash: None,
+///     bootnodes: vec!["/ip4/10.0.0.1/tcp/2121/p2p/12D3KooWSqZaLcn5Guypo2mrHr297YPJnV8KMEMXNjs3qAS8msw8"
+///         .parse()
+///         .unwrap()],
+/// });
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CustomNetwork {
+    /// Id of the network, the same as the chain id used by the other nodes in the network.
+    pub id: String,
+    /// Hash of the genesis block in the network.
+    pub genesis_hash: Option<Hash>,
+    /// Bootnodes of the network.
+    pub bootnodes: Vec<Multiaddr>,
 }
 
 /// Unknown network provided.
@@ -40,22 +67,24 @@ impl FromStr for Network {
 }
 
 /// Get the string id of the given network.
-pub fn network_id(network: Network) -> &'static str {
+pub fn network_id(network: &Network) -> &str {
     match network {
         Network::Arabica => "arabica-10",
         Network::Mocha => "mocha-4",
         Network::Private => "private",
         Network::Mainnet => "celestia",
+        Network::Custom(custom) => &custom.id,
     }
 }
 
 /// Get the hash of a genesis block for the given network.
-pub fn network_genesis(network: Network) -> Option<Hash> {
+pub fn network_genesis(network: &Network) -> Option<Hash> {
     let hex = match network {
         Network::Mainnet => "6BE39EFD10BA412A9DB5288488303F5DD32CF386707A5BEF33617F4C43301872",
         Network::Arabica => "5904E55478BA4B3002EE885621E007A2A6A2399662841912219AECD5D5CBE393",
         Network::Mocha => "B93BBE20A0FBFDF955811B6420F8433904664D45DB4BF51022BE4200C1A1680D",
         Network::Private => return None,
+        Network::Custom(custom) => return custom.genesis_hash,
     };
 
     let bytes = hex::decode(hex).expect("failed decoding genesis hash");
@@ -65,7 +94,9 @@ pub fn network_genesis(network: Network) -> Option<Hash> {
 }
 
 /// Get official Celestia and Lumina bootnodes for the given network.
-pub fn canonical_network_bootnodes(network: Network) -> impl Iterator<Item = Multiaddr> {
+///
+/// For the [`Network::Custom`] those are the bootnodes it was configured with.
+pub fn canonical_network_bootnodes(network: &Network) -> impl Iterator<Item = Multiaddr> {
     let peers: &[_] = match network {
         Network::Mainnet => &[
             "/dns4/lumina.eiger.co/tcp/2121/p2p/12D3KooW9z4jLqwodwNRcSa5qgcSgtJ13kN7CYLcwZQjPRYodqWx",
@@ -93,10 +124,13 @@ pub fn canonical_network_bootnodes(network: Network) -> impl Iterator<Item = Mul
             "/dns4/da-full-2-mocha-4.celestia-mocha.com/tcp/2121/p2p/12D3KooWR6SHsXPkkvhCRn6vp1RqSefgaT1X1nMNvrVjU2o3GoYy",
         ],
         Network::Private => &[],
+        Network::Custom(custom) => return custom.bootnodes.clone().into_iter(),
     };
     peers
         .iter()
         .map(|s| s.parse().expect("Invalid bootstrap address"))
+        .collect::<Vec<_>>()
+        .into_iter()
 }
 
 #[cfg(test)]
@@ -105,32 +139,50 @@ mod tests {
 
     #[test]
     fn test_network_genesis() {
-        let mainnet = network_genesis(Network::Mainnet);
+        let mainnet = network_genesis(&Network::Mainnet);
         assert!(mainnet.is_some());
 
-        let arabica = network_genesis(Network::Arabica);
+        let arabica = network_genesis(&Network::Arabica);
         assert!(arabica.is_some());
 
-        let mocha = network_genesis(Network::Mocha);
+        let mocha = network_genesis(&Network::Mocha);
         assert!(mocha.is_some());
 
-        let private = network_genesis(Network::Private);
+        let private = network_genesis(&Network::Private);
         assert!(private.is_none());
     }
 
     #[test]
     fn test_canonical_network_bootnodes() {
         // canonical_network_bootnodes works on const data, test it doesn't panic and the data is there
-        let mainnet = canonical_network_bootnodes(Network::Mainnet);
+        let mainnet = canonical_network_bootnodes(&Network::Mainnet);
         assert_ne!(mainnet.count(), 0);
 
-        let arabica = canonical_network_bootnodes(Network::Arabica);
+        let arabica = canonical_network_bootnodes(&Network::Arabica);
         assert_ne!(arabica.count(), 0);
 
-        let mocha = canonical_network_bootnodes(Network::Mocha);
+        let mocha = canonical_network_bootnodes(&Network::Mocha);
         assert_ne!(mocha.count(), 0);
 
-        let private = canonical_network_bootnodes(Network::Private);
+        let private = canonical_network_bootnodes(&Network::Private);
         assert_eq!(private.count(), 0);
     }
+
+    #[test]
+    fn test_custom_network() {
+        let genesis_hash = network_genesis(&Network::Mocha);
+        let bootnodes: Vec<_> = canonical_network_bootnodes(&Network::Mocha).collect();
+        let network = Network::Custom(CustomNetwork {
+            id: "devnet-1".to_string(),
+            genesis_hash,
+            bootnodes: bootnodes.clone(),
+        });
+
+        assert_eq!(network_id(&network), "devnet-1");
+        assert_eq!(network_genesis(&network), genesis_hash);
+        assert_eq!(
+            canonical_network_bootnodes(&network).collect::<Vec<_>>(),
+            bootnodes
+        );
+    }
 }
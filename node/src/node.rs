@@ -4,21 +4,55 @@
 //! [`Store`]: crate::store::Store
 //! [`Syncer`]: crate::syncer::Syncer
 
-use std::ops::RangeBounds;
+use std::future::Future;
+use std::ops::{RangeBounds, RangeInclusive};
 use std::sync::Arc;
+use std::time::Duration;
 
+use celestia_tendermint::Time;
+use celestia_types::blob::{decode_pay_for_blobs, MsgPayForBlobs};
+use celestia_types::fraud_proof::BadEncodingFraudProof;
 use celestia_types::hash::Hash;
-use celestia_types::ExtendedHeader;
+use celestia_types::nmt::Namespace;
+use celestia_types::row::{IdLayout, Row};
+use celestia_types::state::{decode_txs, Tx};
+use celestia_types::{
+    ErrorKind, ExtendedDataSquare, ExtendedHeader, NamespacedRow, NamespacedShares, Share,
+};
+use instant::Instant;
 use libp2p::identity::Keypair;
-use libp2p::swarm::NetworkInfo;
+use libp2p::swarm::{NetworkBehaviour, NetworkInfo};
 use libp2p::{Multiaddr, PeerId};
+use tokio::sync::{mpsc, watch};
+use tokio_util::sync::CancellationToken;
 
-use crate::p2p::{P2p, P2pArgs, P2pError};
-use crate::peer_tracker::PeerTrackerInfo;
+use crate::clock::{Clock, HeaderClock, SystemClock, DEFAULT_MAX_CLOCK_DRIFT};
+use crate::daser::{Daser, DaserArgs, DaserConfig, SamplingEvidence};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::eds_store::EdsStore;
+use crate::events::{EventChannel, EventSubscriber, NodeEvent};
+#[cfg(feature = "gateway")]
+use crate::gateway::GatewayConfig;
+use crate::namespace_subscription::{self, NamespacedDataEvent, SubscriptionArgs};
+use crate::namespaced_data_range::{NamespacedDataCursor, NamespacedDataRange};
+use crate::network::{canonical_network_bootnodes, network_genesis, network_id, Network};
+use crate::p2p::{
+    self, CustomBehaviour, HeadValidator, NamespaceFetchMode, P2p, P2pArgs, P2pError,
+    ProtocolVersions, ResourceLimits, RetryConfig, TransportConfig,
+};
+use crate::peer_tracker::{PeerDiagnostics, PeerTrackerInfo};
+use crate::pruner::{Pruner, PrunerArgs, DEFAULT_PRUNING_WINDOW};
+use crate::row_subscription::{self, RowsEvent};
 use crate::store::{Store, StoreError};
-use crate::syncer::{Syncer, SyncerArgs, SyncerError, SyncingInfo};
+use crate::syncer::{SyncMode, SyncState, Syncer, SyncerArgs, SyncerError, SyncingInfo};
 
 type Result<T, E = NodeError> = std::result::Result<T, E>;
+/// Maximum number of the rows requested at the same time by [`Node::request_eds`].
+const EDS_ROWS_CONCURRENCY: usize = 8;
+/// Number of the preceding headers requested at once by [`Node::get_header_by_time`].
+const HEADERS_BY_TIME_BATCH: u64 = 512;
+
+type P2pStarter<S> = Box<dyn FnOnce(P2pArgs<S>) -> Result<P2p<S>, P2pError> + Send>;
 
 /// Representation of all the errors that can occur when interacting with the [`Node`].
 #[derive(Debug, thiserror::Error)]
@@ -34,25 +68,544 @@ pub enum NodeError {
     /// An error propagated from the [`Store`] module.
     #[error(transparent)]
     Store(#[from] StoreError),
+
+    /// The [`Store`] was not provided to the [`NodeBuilder`].
+    #[error("Store was not provided to the node builder")]
+    MissingStore,
+
+    /// An error propagated from the [`celestia_types`].
+    #[error(transparent)]
+    CelestiaTypes(#[from] celestia_types::Error),
+
+    /// The operation requires the [`Syncer`], which was disabled with
+    /// [`NodeBuilder::disable_syncing`].
+    ///
+    /// [`Syncer`]: crate::syncer::Syncer
+    #[error("Syncing is disabled")]
+    SyncingDisabled,
+
+    /// The headers can't be appended manually while the [`Syncer`] is running.
+    ///
+    /// [`Syncer`]: crate::syncer::Syncer
+    #[error("Syncing is enabled, headers can't be appended manually")]
+    SyncingEnabled,
+
+    /// The operation requires the [`Daser`], which wasn't enabled with
+    /// [`NodeBuilder::daser_config`].
+    #[error("Sampling is disabled")]
+    SamplingDisabled,
+}
+
+impl NodeError {
+    /// Returns `true` if the operation which failed with this error may succeed
+    /// when tried again.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            NodeError::P2p(e) => e.is_retryable(),
+            NodeError::Syncer(e) => e.is_retryable(),
+            NodeError::Store(e) => e.is_retryable(),
+            NodeError::CelestiaTypes(e) => e.is_retryable(),
+            _ => false,
+        }
+    }
+
+    /// Get the [`ErrorKind`] of the error.
+    pub fn kind(&self) -> ErrorKind {
+        self.code_and_kind().1
+    }
+
+    /// Get the stable code of the error, see [`celestia_types::Error::code`].
+    ///
+    /// Errors of the [`Node`] itself use the codes from `5001` to `5999`, the ones
+    /// propagated from its components keep their codes.
+    pub fn code(&self) -> u16 {
+        self.code_and_kind().0
+    }
+
+    fn code_and_kind(&self) -> (u16, ErrorKind) {
+        match self {
+            NodeError::P2p(e) => (e.code(), e.kind()),
+            NodeError::Syncer(e) => (e.code(), e.kind()),
+            NodeError::Store(e) => (e.code(), e.kind()),
+            NodeError::MissingStore => (5004, ErrorKind::Config),
+            NodeError::CelestiaTypes(e) => (e.code(), e.kind()),
+            NodeError::SyncingDisabled => (5005, ErrorKind::Config),
+            NodeError::SyncingEnabled => (5006, ErrorKind::Config),
+            NodeError::SamplingDisabled => (5007, ErrorKind::Config),
+        }
+    }
+}
+
+/// Builder of the [`Node`].
+///
+/// Genesis hash and bootnodes default to the presets of the selected [`Network`],
+/// while each of them can be overridden separately. The [`Store`] is the only
+/// required setting.
+///
+/// # Example
+///
+/// ```no_run
+/// use lumina_node::network::Network;
+/// use lumina_node::node::Node;
+/// use lumina_node::store::InMemoryStore;
+///
+/// # async fn start() {
+/// let node = Node::builder()
+///     .network(Network::Mocha)
+///     .listen_addrs(["/ip4/0.0.0.0/tcp/0".parse().unwrap()])
+///     .store(InMemoryStore::new())
+///     .start()
+///     .await
+///     .expect("Failed to start node");
+/// # }
+/// ```
+pub struct NodeBuilder<S>
+where
+    S: Store + 'static,
+{
+    network: Network,
+    // `None` means the preset of the network
+    genesis_hash: Option<Option<Hash>>,
+    keypair: Option<Keypair>,
+    // `None` means the preset of the network
+    bootnodes: Option<Vec<Multiaddr>>,
+    listen_addrs: Vec<Multiaddr>,
+    relay_addrs: Vec<Multiaddr>,
+    transports: TransportConfig,
+    resource_limits: ResourceLimits,
+    store: Option<S>,
+    pruning_window: Option<Duration>,
+    sampling: Option<DaserConfig>,
+    syncing: bool,
+    sync_mode: SyncMode,
+    header_quorum: usize,
+    provide_blocks: bool,
+    #[cfg(not(target_arch = "wasm32"))]
+    eds_store: Option<EdsStore>,
+    retry: RetryConfig,
+    shwap_id_layout: IdLayout,
+    // `None` means the current versions, with the shwap ids in `shwap_id_layout`
+    protocol_versions: Option<ProtocolVersions>,
+    namespace_fetch_mode: NamespaceFetchMode,
+    #[cfg(feature = "gateway")]
+    gateway: Option<GatewayConfig>,
+    head_validators: Vec<Arc<dyn HeadValidator>>,
+    clock: Arc<dyn Clock>,
+    max_clock_drift: Duration,
+    p2p_starter: Option<P2pStarter<S>>,
+}
+
+impl<S> NodeBuilder<S>
+where
+    S: Store,
+{
+    /// Create a new [`NodeBuilder`] for the default [`Network`].
+    pub fn new() -> Self {
+        NodeBuilder {
+            network: Network::default(),
+            genesis_hash: None,
+            keypair: None,
+            bootnodes: None,
+            listen_addrs: Vec::new(),
+            relay_addrs: Vec::new(),
+            transports: TransportConfig::default(),
+            resource_limits: ResourceLimits::default(),
+            store: None,
+            pruning_window: Some(DEFAULT_PRUNING_WINDOW),
+            sampling: None,
+            syncing: true,
+            sync_mode: SyncMode::default(),
+            header_quorum: 1,
+            provide_blocks: true,
+            #[cfg(not(target_arch = "wasm32"))]
+            eds_store: None,
+            retry: RetryConfig::default(),
+            shwap_id_layout: IdLayout::default(),
+            protocol_versions: None,
+            namespace_fetch_mode: NamespaceFetchMode::default(),
+            #[cfg(feature = "gateway")]
+            gateway: None,
+            head_validators: Vec::new(),
+            clock: Arc::new(SystemClock),
+            max_clock_drift: DEFAULT_MAX_CLOCK_DRIFT,
+            p2p_starter: None,
+        }
+    }
+
+    /// Set the [`Network`] to connect to.
+    pub fn network(mut self, network: Network) -> Self {
+        self.network = network;
+        self
+    }
+
+    /// Override the hash of the genesis block of the [`Network`].
+    ///
+    /// `None` disables the verification of the genesis header.
+    pub fn genesis_hash(mut self, genesis_hash: Option<Hash>) -> Self {
+        self.genesis_hash = Some(genesis_hash);
+        self
+    }
+
+    /// Set the keypair used as the [`Node`]'s identity.
+    ///
+    /// A new ed25519 keypair is generated if not provided.
+    pub fn keypair(mut self, keypair: Keypair) -> Self {
+        self.keypair = Some(keypair);
+        self
+    }
+
+    /// Override the bootstrap nodes of the [`Network`] to connect to and trust.
+    pub fn bootnodes<I>(mut self, bootnodes: I) -> Self
+    where
+        I: IntoIterator<Item = Multiaddr>,
+    {
+        self.bootnodes = Some(bootnodes.into_iter().collect());
+        self
+    }
+
+    /// Set the addresses where the [`Node`] will listen for incoming connections.
+    pub fn listen_addrs<I>(mut self, listen_addrs: I) -> Self
+    where
+        I: IntoIterator<Item = Multiaddr>,
+    {
+        self.listen_addrs = listen_addrs.into_iter().collect();
+        self
+    }
+
+    /// Set the circuit relays through which the [`Node`] can be reached when it's behind a NAT.
+    ///
+    /// The node reserves a slot on each of them and upgrades the relayed connections to
+    /// direct ones with hole punching when possible. Each address must include the peer ID
+    /// of the relay.
+    pub fn relay_addrs<I>(mut self, relay_addrs: I) -> Self
+    where
+        I: IntoIterator<Item = Multiaddr>,
+    {
+        self.relay_addrs = relay_addrs.into_iter().collect();
+        self
+    }
+
+    /// Select the transports used to dial the peers and accept their connections.
+    ///
+    /// Defaults to all the transports available on the platform.
+    pub fn transports(mut self, transports: TransportConfig) -> Self {
+        self.transports = transports;
+        self
+    }
+
+    /// Limit the connections and the resources used by the peer to peer networking.
+    ///
+    /// Defaults to [`ResourceLimits::default`], suited for the current platform.
+    pub fn resource_limits(mut self, limits: ResourceLimits) -> Self {
+        self.resource_limits = limits;
+        self
+    }
+
+    /// Set the store for headers.
+    pub fn store(mut self, store: S) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Set the age after which the headers are removed from the store.
+    ///
+    /// Defaults to [`DEFAULT_PRUNING_WINDOW`].
+    pub fn pruning_window(mut self, pruning_window: Duration) -> Self {
+        self.pruning_window = Some(pruning_window);
+        self
+    }
+
+    /// Keep all the headers in the store.
+    pub fn disable_pruning(mut self) -> Self {
+        self.pruning_window = None;
+        self
+    }
+
+    /// Enable the data availability sampling of the synchronized headers, with the given
+    /// strategy.
+    ///
+    /// Sampling is disabled by default.
+    pub fn daser_config(mut self, config: DaserConfig) -> Self {
+        self.sampling = Some(config);
+        self
+    }
+
+    /// Don't sample the synchronized headers.
+    pub fn disable_sampling(mut self) -> Self {
+        self.sampling = None;
+        self
+    }
+
+    /// Don't synchronize the headers from the network.
+    ///
+    /// The [`Node`] then works on demand, only with the headers provided to it with
+    /// [`Node::append_headers`]. They are still sampled, if sampling is enabled.
+    pub fn disable_syncing(mut self) -> Self {
+        self.syncing = false;
+        self
+    }
+
+    /// Set the direction of the headers synchronization.
+    ///
+    /// Pruning is disabled in the [`SyncMode::Backward`] mode, as it would remove
+    /// the historical headers being synchronized.
+    pub fn sync_mode(mut self, sync_mode: SyncMode) -> Self {
+        self.sync_mode = sync_mode;
+        self
+    }
+
+    /// Set the number of peers that must agree on each synchronized batch of headers.
+    ///
+    /// With the quorum higher than `1`, the [`Syncer`] waits until enough peers are
+    /// connected and accepts a batch only if all of the queried peers responded with
+    /// the same hash of its boundary header. This protects against a single malicious
+    /// peer feeding the node a bad chain. Defaults to `1`, which disables the check.
+    ///
+    /// [`Syncer`]: crate::syncer::Syncer
+    pub fn header_quorum(mut self, quorum: usize) -> Self {
+        self.header_quorum = quorum;
+        self
+    }
+
+    /// Stop providing the stored shwap containers to the other peers.
+    ///
+    /// By default the containers retrieved by the [`Node`] are announced on the DHT
+    /// and served to the peers asking for them over bitswap. Disabling it only makes
+    /// the [`Node`] consume the data from the network.
+    pub fn disable_providing(mut self) -> Self {
+        self.provide_blocks = false;
+        self
+    }
+
+    /// Serve the shwap containers over bitswap also from the squares in the [`EdsStore`].
+    ///
+    /// This lets the [`Node`] act as a data-serving peer, eg. for the browser nodes,
+    /// answering the requests for [`Row`]s, [`Sample`]s and [`NamespacedData`] of any
+    /// square in the [`EdsStore`], not only the containers it retrieved itself.
+    /// It has no effect if providing is disabled.
+    ///
+    /// [`NamespacedData`]: celestia_types::namespaced_data::NamespacedData
+    /// [`Sample`]: celestia_types::sample::Sample
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn eds_store(mut self, eds_store: EdsStore) -> Self {
+        self.eds_store = Some(eds_store);
+        self
+    }
+
+    /// Set the policy of retrying the failed `header-ex` requests and shwap fetches.
+    ///
+    /// Defaults to [`RetryConfig::default`].
+    pub fn retry_config(mut self, retry: RetryConfig) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Set the layout of the shwap identifiers used when fetching and serving the containers.
+    ///
+    /// Defaults to [`IdLayout::Draft`], spoken by the celestia-node peers. [`IdLayout::V1`]
+    /// only migrates the identifiers to the finalized shwap specification, so it's
+    /// understood only by the peers using the same layout.
+    pub fn shwap_id_layout(mut self, layout: IdLayout) -> Self {
+        self.shwap_id_layout = layout;
+        self
+    }
+
+    /// Set the versions of the `header-ex` and shwap protocols spoken with the peers.
+    ///
+    /// The version is chosen separately for each peer, so listing the versions of an
+    /// upcoming network upgrade lets the [`Node`] talk to the peers on both sides of it.
+    /// Defaults to [`ProtocolVersions::new`] with the layout set with
+    /// [`NodeBuilder::shwap_id_layout`].
+    pub fn protocol_versions(mut self, versions: ProtocolVersions) -> Self {
+        self.protocol_versions = Some(versions);
+        self
+    }
+
+    /// Set how the data of the namespaces is retrieved from the peers.
+    ///
+    /// Defaults to [`NamespaceFetchMode::NamespaceShares`], requesting only the shares
+    /// of the namespace. [`NamespaceFetchMode::FullRows`] requests the whole rows instead.
+    pub fn namespace_fetch_mode(mut self, mode: NamespaceFetchMode) -> Self {
+        self.namespace_fetch_mode = mode;
+        self
+    }
+
+    /// Retrieve the shwap containers from an HTTP gateway when the bitswap retrieval
+    /// times out.
+    ///
+    /// This lets the [`Node`] work behind the networks which block or throttle the
+    /// peer to peer traffic. The gateway doesn't need to be trusted, as the containers
+    /// it returns are verified against the [`DataAvailabilityHeader`] of their block.
+    /// It has to serve the shwap containers themselves, which public IPFS gateways
+    /// don't have, see the [`gateway`] module.
+    ///
+    /// [`DataAvailabilityHeader`]: celestia_types::DataAvailabilityHeader
+    /// [`gateway`]: crate::gateway
+    #[cfg(feature = "gateway")]
+    #[cfg_attr(docs_rs, doc(cfg(feature = "gateway")))]
+    pub fn shwap_gateway(mut self, config: GatewayConfig) -> Self {
+        self.gateway = Some(config);
+        self
+    }
+
+    /// Add a validator of the heads received over the header-sub.
+    ///
+    /// Heads are propagated to the other peers and synchronized only if they pass the
+    /// built-in checks and all the added validators accept them. See [`HeadValidator`]
+    /// for details.
+    pub fn head_validator<V>(mut self, validator: V) -> Self
+    where
+        V: HeadValidator + 'static,
+    {
+        self.head_validators.push(Arc::new(validator));
+        self
+    }
+
+    /// Set the [`Clock`] used to check the time of the headers.
+    ///
+    /// It decides which received headers are from the future, whether the persisted
+    /// network head is still trusted and which headers fell out of the pruning window.
+    /// Defaults to the [`SystemClock`].
+    pub fn clock<C>(mut self, clock: C) -> Self
+    where
+        C: Clock,
+    {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Set the maximum time by which the received headers can be ahead of the [`Clock`].
+    ///
+    /// Headers further in the future are rejected. Devices with an unreliable clock can
+    /// increase it to avoid rejecting the fresh headers. Defaults to
+    /// [`DEFAULT_MAX_CLOCK_DRIFT`].
+    pub fn max_clock_drift(mut self, max_clock_drift: Duration) -> Self {
+        self.max_clock_drift = max_clock_drift;
+        self
+    }
+
+    /// Run an additional [`NetworkBehaviour`] in the swarm of the [`Node`].
+    ///
+    /// The behaviour is created with the keypair of the node when it starts. Events
+    /// it emits are sent to `events_tx` and the behaviour can be accessed with
+    /// [`Node::with_custom_behaviour`]. See [`CustomBehaviour`] for details.
+    pub fn custom_behaviour<B, F>(
+        mut self,
+        make_behaviour: F,
+        events_tx: mpsc::Sender<B::ToSwarm>,
+    ) -> Self
+    where
+        B: NetworkBehaviour + Send,
+        B::ToSwarm: Send,
+        F: FnOnce(&Keypair) -> B + Send + 'static,
+    {
+        self.p2p_starter = Some(Box::new(move |args: P2pArgs<S>| {
+            let behaviour = make_behaviour(&args.local_keypair);
+            let custom = CustomBehaviour {
+                behaviour,
+                events_tx,
+            };
+            P2p::start_with_custom_behaviour(args, Some(custom))
+        }));
+        self
+    }
+
+    /// Build and start the [`Node`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`NodeError::MissingStore`] if the store was not provided.
+    pub async fn start(self) -> Result<Node<S>> {
+        let store = self.store.ok_or(NodeError::MissingStore)?;
+        let genesis_hash = self
+            .genesis_hash
+            .unwrap_or_else(|| network_genesis(&self.network));
+        let bootnodes = self
+            .bootnodes
+            .unwrap_or_else(|| canonical_network_bootnodes(&self.network).collect());
+        let keypair = self.keypair.unwrap_or_else(Keypair::generate_ed25519);
+        let pruning_window = match self.sync_mode {
+            SyncMode::Forward => self.pruning_window,
+            SyncMode::Backward { .. } => None,
+        };
+
+        Node::start(NodeArgs {
+            network_id: network_id(&self.network).to_owned(),
+            genesis_hash,
+            keypair,
+            bootnodes,
+            listen_addrs: self.listen_addrs,
+            relay_addrs: self.relay_addrs,
+            transports: self.transports,
+            resource_limits: self.resource_limits,
+            store,
+            pruning_window,
+            sampling: self.sampling,
+            syncing: self.syncing,
+            sync_mode: self.sync_mode,
+            header_quorum: self.header_quorum,
+            provide_blocks: self.provide_blocks,
+            #[cfg(not(target_arch = "wasm32"))]
+            eds_store: self
+                .eds_store
+                .map(|eds_store| eds_store.with_id_layout(self.shwap_id_layout)),
+            retry: self.retry,
+            shwap_id_layout: self.shwap_id_layout,
+            protocol_versions: self
+                .protocol_versions
+                .unwrap_or_else(|| ProtocolVersions::new(self.shwap_id_layout)),
+            namespace_fetch_mode: self.namespace_fetch_mode,
+            #[cfg(feature = "gateway")]
+            gateway: self.gateway,
+            head_validators: self.head_validators,
+            clock: self.clock,
+            max_clock_drift: self.max_clock_drift,
+            p2p_starter: self.p2p_starter,
+        })
+        .await
+    }
 }
 
-/// Node conifguration.
-pub struct NodeConfig<S>
+impl<S> Default for NodeBuilder<S>
+where
+    S: Store,
+{
+    fn default() -> Self {
+        NodeBuilder::new()
+    }
+}
+
+struct NodeArgs<S>
 where
     S: Store + 'static,
 {
-    /// An id of the network to connect to.
-    pub network_id: String,
-    /// The hash of the genesis block in network.
-    pub genesis_hash: Option<Hash>,
-    /// The keypair to be used as [`Node`]s identity.
-    pub p2p_local_keypair: Keypair,
-    /// List of bootstrap nodes to connect to and trust.
-    pub p2p_bootnodes: Vec<Multiaddr>,
-    /// List of the addresses where [`Node`] will listen for incoming connections.
-    pub p2p_listen_on: Vec<Multiaddr>,
-    /// The store for headers.
-    pub store: S,
+    network_id: String,
+    genesis_hash: Option<Hash>,
+    keypair: Keypair,
+    bootnodes: Vec<Multiaddr>,
+    listen_addrs: Vec<Multiaddr>,
+    relay_addrs: Vec<Multiaddr>,
+    transports: TransportConfig,
+    resource_limits: ResourceLimits,
+    store: S,
+    pruning_window: Option<Duration>,
+    sampling: Option<DaserConfig>,
+    syncing: bool,
+    sync_mode: SyncMode,
+    header_quorum: usize,
+    provide_blocks: bool,
+    #[cfg(not(target_arch = "wasm32"))]
+    eds_store: Option<EdsStore>,
+    retry: RetryConfig,
+    shwap_id_layout: IdLayout,
+    protocol_versions: ProtocolVersions,
+    namespace_fetch_mode: NamespaceFetchMode,
+    #[cfg(feature = "gateway")]
+    gateway: Option<GatewayConfig>,
+    head_validators: Vec<Arc<dyn HeadValidator>>,
+    clock: Arc<dyn Clock>,
+    max_clock_drift: Duration,
+    p2p_starter: Option<P2pStarter<S>>,
 }
 
 /// Celestia node.
@@ -62,32 +615,118 @@ where
 {
     p2p: Arc<P2p<S>>,
     store: Arc<S>,
-    syncer: Arc<Syncer<S>>,
+    headers_source: HeadersSource<S>,
+    _pruner: Option<Pruner<S>>,
+    daser: Option<Daser<S>>,
+    event_channel: EventChannel,
+    header_clock: HeaderClock,
 }
 
 impl<S> Node<S>
 where
     S: Store,
 {
-    /// Creates and starts a new celestia node with a given config.
-    pub async fn new(config: NodeConfig<S>) -> Result<Self> {
-        let store = Arc::new(config.store);
-
-        let p2p = Arc::new(P2p::start(P2pArgs {
-            network_id: config.network_id,
-            local_keypair: config.p2p_local_keypair,
-            bootnodes: config.p2p_bootnodes,
-            listen_on: config.p2p_listen_on,
-            store: store.clone(),
-        })?);
+    /// Create a new [`NodeBuilder`].
+    pub fn builder() -> NodeBuilder<S> {
+        NodeBuilder::new()
+    }
+
+    async fn start(args: NodeArgs<S>) -> Result<Self> {
+        let store = Arc::new(args.store);
+        let event_channel = EventChannel::new();
 
-        let syncer = Arc::new(Syncer::start(SyncerArgs {
-            genesis_hash: config.genesis_hash,
+        let p2p_args = P2pArgs {
+            network_id: args.network_id,
+            local_keypair: args.keypair,
+            bootnodes: args.bootnodes,
+            listen_on: args.listen_addrs,
+            relay_addrs: args.relay_addrs,
+            transports: args.transports,
             store: store.clone(),
-            p2p: p2p.clone(),
-        })?);
+            event_pub: event_channel.publisher(),
+            provide_blocks: args.provide_blocks,
+            #[cfg(not(target_arch = "wasm32"))]
+            eds_store: args.eds_store,
+            retry: args.retry,
+            shwap_id_layout: args.shwap_id_layout,
+            protocol_versions: args.protocol_versions,
+            namespace_fetch_mode: args.namespace_fetch_mode,
+            resource_limits: args.resource_limits,
+            #[cfg(feature = "gateway")]
+            gateway: args.gateway,
+            head_validators: args.head_validators,
+            clock: args.clock.clone(),
+            max_clock_drift: args.max_clock_drift,
+        };
+        let p2p = Arc::new(match args.p2p_starter {
+            Some(start) => start(p2p_args)?,
+            None => P2p::start(p2p_args)?,
+        });
 
-        Ok(Node { p2p, store, syncer })
+        let headers_source = if args.syncing {
+            HeadersSource::Syncer(Arc::new(Syncer::start(SyncerArgs {
+                genesis_hash: args.genesis_hash,
+                store: store.clone(),
+                p2p: p2p.clone(),
+                sync_mode: args.sync_mode,
+                header_quorum: args.header_quorum,
+                event_pub: event_channel.publisher(),
+                clock: args.clock.clone(),
+            })?))
+        } else {
+            let (head_height, stored_ranges) = match store.head_height().await {
+                Ok(height) => (height, vec![store.tail_height().await?..=height]),
+                Err(StoreError::NotFound) => (0, Vec::new()),
+                Err(e) => return Err(e.into()),
+            };
+            HeadersSource::Manual {
+                genesis_hash: args.genesis_hash,
+                local_head_tx: watch::channel(head_height).0,
+                stored_ranges_tx: watch::channel(stored_ranges).0,
+            }
+        };
+
+        let pruner = args.pruning_window.map(|pruning_window| {
+            Pruner::start(PrunerArgs {
+                store: store.clone(),
+                pruning_window,
+                clock: args.clock.clone(),
+            })
+        });
+
+        let daser = args.sampling.map(|config| {
+            Daser::start(DaserArgs {
+                p2p: p2p.clone(),
+                store: store.clone(),
+                event_pub: event_channel.publisher(),
+                stored_ranges: headers_source.stored_ranges_watcher(),
+                config,
+            })
+        });
+
+        Ok(Node {
+            p2p,
+            store,
+            headers_source,
+            _pruner: pruner,
+            daser,
+            event_channel,
+            header_clock: HeaderClock::new(args.clock, args.max_clock_drift),
+        })
+    }
+
+    /// Subscribe to the [`NodeEvent`]s published by the components of the node.
+    ///
+    /// Only the events published after the subscription are received.
+    ///
+    /// [`NodeEvent`]: crate::events::NodeEvent
+    pub fn event_subscriber(&self) -> EventSubscriber {
+        self.event_channel.subscribe()
+    }
+
+    /// Get the [`Store`] of the node, e.g. to inspect the state specific to its implementation.
+    pub fn store(&self) -> &S {
+        &self.store
     }
 
     /// Get node's local peer ID.
@@ -112,6 +751,22 @@ where
         Ok(self.p2p.wait_connected_trusted().await?)
     }
 
+    /// Wait until the node is connected to at least 1 peer, failing after the `timeout`.
+    ///
+    /// The returned [`P2pError::ConnectTimedOut`] lists the bootnodes the node tried
+    /// to connect to.
+    pub async fn wait_connected_timeout(&self, timeout: Duration) -> Result<()> {
+        Ok(self.p2p.wait_connected_timeout(timeout).await?)
+    }
+
+    /// Wait until the node is connected to at least 1 trusted peer, failing after the `timeout`.
+    ///
+    /// The returned [`P2pError::ConnectTimedOut`] lists the bootnodes the node tried
+    /// to connect to.
+    pub async fn wait_connected_trusted_timeout(&self, timeout: Duration) -> Result<()> {
+        Ok(self.p2p.wait_connected_trusted_timeout(timeout).await?)
+    }
+
     /// Get current network info.
     pub async fn network_info(&self) -> Result<NetworkInfo> {
         Ok(self.p2p.network_info().await?)
@@ -127,11 +782,66 @@ where
         Ok(self.p2p.connected_peers().await?)
     }
 
+    /// Get the diagnostics of the connected peers, such as their agent version,
+    /// latency measured with ping and the protocols they support.
+    pub async fn peer_info(&self) -> Result<Vec<PeerDiagnostics>> {
+        Ok(self.p2p.peer_info().await?)
+    }
+
     /// Trust or untrust the peer with a given ID.
     pub async fn set_peer_trust(&self, peer_id: PeerId, is_trusted: bool) -> Result<()> {
         Ok(self.p2p.set_peer_trust(peer_id, is_trusted).await?)
     }
 
+    /// Run the operation of the node until it completes or the `timeout` elapses.
+    ///
+    /// Any of the [`Node`] operations can be limited this way, e.g. a request for
+    /// the header or the namespace data, or waiting for the syncer. Operations are
+    /// cancelled by dropping them, which stops their retries and withdraws the pending
+    /// bitswap wants from the peers.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`P2pError::RequestTimedOut`] if the operation didn't complete in time.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use lumina_node::node::{Node, NodeError};
+    /// # use lumina_node::store::InMemoryStore;
+    /// # async fn example(node: Node<InMemoryStore>) -> Result<(), NodeError> {
+    /// let header = node
+    ///     .with_timeout(Duration::from_secs(5), node.request_header_by_height(100))
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn with_timeout<F, T>(&self, timeout: Duration, operation: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        p2p::with_timeout(timeout, operation).await
+    }
+
+    /// Run the operation of the node until it completes or the `token` gets cancelled.
+    ///
+    /// See [`Node::with_timeout`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`P2pError::Cancelled`] if the token got cancelled first.
+    pub async fn with_cancellation<F, T>(
+        &self,
+        token: &CancellationToken,
+        operation: F,
+    ) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        p2p::with_cancellation(token, operation).await
+    }
+
     /// Request the head header from the network.
     pub async fn request_head_header(&self) -> Result<ExtendedHeader> {
         Ok(self.p2p.get_head_header().await?)
@@ -158,16 +868,438 @@ where
         Ok(self.p2p.get_verified_headers_range(from, amount).await?)
     }
 
+    /// Request a verified [`Share`] at the given coordinates of the block's
+    /// [`ExtendedDataSquare`] from the network.
+    ///
+    /// The share is retrieved as a [`Sample`], with the proof of its inclusion. Shares
+    /// outside of the original data square are the parity shares of the extension.
+    /// The header for the given height must already be present in the store. The result
+    /// of the sampling is published as the [`NodeEvent::SamplingFinished`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`P2pError::ShareOutOfSquare`] if the coordinates lie outside of the
+    /// [`ExtendedDataSquare`].
+    ///
+    /// [`ExtendedDataSquare`]: celestia_types::ExtendedDataSquare
+    /// [`Sample`]: celestia_types::sample::Sample
+    pub async fn request_sample(
+        &self,
+        height: u64,
+        row_index: u16,
+        column_index: u16,
+    ) -> Result<Share> {
+        let header = self.store.get_by_height(height).await?;
+        let square_len = header.dah.square_len();
+        p2p::check_share_coordinates(row_index, column_index, square_len)?;
+
+        let started = Instant::now();
+        let res = self.p2p.get_sample(row_index, column_index, &header).await;
+
+        self.event_channel
+            .publisher()
+            .send(NodeEvent::SamplingFinished {
+                height,
+                row: row_index,
+                column: column_index,
+                accepted: res.is_ok(),
+                took: started.elapsed(),
+            });
+
+        let sample = res?;
+        let ods_width = square_len / 2;
+
+        if usize::from(row_index) < ods_width && usize::from(column_index) < ods_width {
+            Ok(Share::from_raw(&sample.share)?)
+        } else {
+            Ok(Share::parity(&sample.share)?)
+        }
+    }
+
+    /// Request verified [`Row`]s with the given indexes of the block's
+    /// [`ExtendedDataSquare`] from the network.
+    ///
+    /// At most `concurrency` rows are requested at the same time and they are returned
+    /// in the order of `row_indexes`. The header for the given height must already be
+    /// present in the store.
+    ///
+    /// [`ExtendedDataSquare`]: celestia_types::ExtendedDataSquare
+    pub async fn request_rows(
+        &self,
+        height: u64,
+        row_indexes: &[u16],
+        concurrency: usize,
+    ) -> Result<Vec<Row>> {
+        let header = self.store.get_by_height(height).await?;
+        Ok(self.p2p.get_rows(row_indexes, concurrency, &header).await?)
+    }
+
+    /// Request the verified ordinary transactions of the block from the network.
+    ///
+    /// The transactions paying for blobs are not included, see
+    /// [`Node::request_pay_for_blobs`]. The header for the given height must already
+    /// be present in the store.
+    pub async fn request_txs(&self, height: u64) -> Result<Vec<Tx>> {
+        let shares = self
+            .request_namespace_shares(Namespace::TRANSACTION, height)
+            .await?;
+        Ok(decode_txs(&shares)?)
+    }
+
+    /// Request the verified [`MsgPayForBlobs`] of the block from the network.
+    ///
+    /// Their [`Commitment`]s can be used to match the blobs included in the block with
+    /// the accounts that paid for them. The header for the given height must already
+    /// be present in the store.
+    ///
+    /// [`Commitment`]: celestia_types::Commitment
+    pub async fn request_pay_for_blobs(&self, height: u64) -> Result<Vec<MsgPayForBlobs>> {
+        let shares = self
+            .request_namespace_shares(Namespace::PAY_FOR_BLOB, height)
+            .await?;
+        Ok(decode_pay_for_blobs(&shares)?)
+    }
+
+    /// Request the verified [`Share`]s of the [`Namespace`] in the block, together with
+    /// the proofs of their inclusion in each row, from the network.
+    ///
+    /// The header for the given height must already be present in the store.
+    pub async fn request_shares_by_namespace(
+        &self,
+        namespace: Namespace,
+        height: u64,
+    ) -> Result<NamespacedShares> {
+        let header = self.store.get_by_height(height).await?;
+        let rows = self
+            .p2p
+            .get_namespaced_data(namespace, &header)
+            .await?
+            .into_iter()
+            .map(|data| -> Result<_> {
+                let shares = data
+                    .shares
+                    .iter()
+                    .map(|share| Share::from_raw(share))
+                    .collect::<Result<_, _>>()?;
+
+                Ok(NamespacedRow {
+                    shares,
+                    proof: data.proof,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(NamespacedShares { rows })
+    }
+
+    /// Request the [`ExtendedDataSquare`] of the block from the network.
+    ///
+    /// Only the verified rows of the original data square are requested, the parity
+    /// data is computed from them. The header for the given height must already be
+    /// present in the store.
+    pub async fn request_eds(&self, height: u64) -> Result<ExtendedDataSquare> {
+        let header = self.store.get_by_height(height).await?;
+        let ods_width = header.dah.square_len() / 2;
+        let row_indexes: Vec<_> = (0..ods_width as u16).collect();
+
+        let ods = self
+            .p2p
+            .get_rows(&row_indexes, EDS_ROWS_CONCURRENCY, &header)
+            .await?
+            .into_iter()
+            .flat_map(|row| row.shares.into_iter().take(ods_width).map(Vec::from))
+            .collect();
+
+        Ok(ExtendedDataSquare::from_ods(ods)?)
+    }
+
+    async fn request_namespace_shares(
+        &self,
+        namespace: Namespace,
+        height: u64,
+    ) -> Result<Vec<Share>> {
+        let header = self.store.get_by_height(height).await?;
+        let rows = self.p2p.get_namespaced_data(namespace, &header).await?;
+
+        let shares = rows
+            .iter()
+            .flat_map(|row| &row.shares)
+            .map(|share| Share::from_raw(share))
+            .collect::<Result<_, _>>()?;
+
+        Ok(shares)
+    }
+
+    /// Subscribe to the data of the given namespaces in the newly synced blocks.
+    ///
+    /// For every header synced after the subscription is created, the data of each
+    /// [`Namespace`] is requested from the network, verified against the header and
+    /// emitted as a separate [`NamespacedDataEvent`]. The subscription is cancelled
+    /// when the returned receiver is dropped.
+    pub async fn subscribe_namespaces(
+        &self,
+        namespaces: &[Namespace],
+    ) -> Result<mpsc::Receiver<NamespacedDataEvent>> {
+        let start_height = match self.store.head_height().await {
+            Ok(height) => height + 1,
+            Err(StoreError::NotFound) => 1,
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(namespace_subscription::subscribe(SubscriptionArgs {
+            p2p: self.p2p.clone(),
+            store: self.store.clone(),
+            local_head_watcher: self.headers_source.local_head_watcher(),
+            namespaces: namespaces.to_vec(),
+            start_height,
+        }))
+    }
+
+    /// Subscribe to the selected [`Row`]s of the newly synced blocks.
+    ///
+    /// For every header synced after the subscription is created, `row_predicate` is
+    /// called with the index of each row and the width of the block's square. The
+    /// matching rows are requested from the network, verified against the header and
+    /// emitted together as a [`RowsEvent`]. The subscription is cancelled when the
+    /// returned receiver is dropped.
+    pub async fn subscribe_rows<F>(&self, row_predicate: F) -> Result<mpsc::Receiver<RowsEvent>>
+    where
+        F: Fn(u16, u16) -> bool + Send + 'static,
+    {
+        let start_height = match self.store.head_height().await {
+            Ok(height) => height + 1,
+            Err(StoreError::NotFound) => 1,
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(row_subscription::subscribe(
+            row_subscription::SubscriptionArgs {
+                p2p: self.p2p.clone(),
+                store: self.store.clone(),
+                local_head_watcher: self.headers_source.local_head_watcher(),
+                row_predicate,
+                start_height,
+            },
+        ))
+    }
+
+    /// Iterate over the verified data of the namespace in the inclusive range of heights
+    /// `from_height..=to_height`.
+    ///
+    /// For each height only the rows whose roots may contain the namespace are requested
+    /// from the network, heights without the namespace data yield no rows. The progress
+    /// is tracked by the [`NamespacedDataCursor`] of the returned range, which can be
+    /// used to continue after an interruption with
+    /// [`Node::resume_namespaced_data_range`].
+    pub fn get_namespaced_data_range(
+        &self,
+        namespace: Namespace,
+        from_height: u64,
+        to_height: u64,
+    ) -> NamespacedDataRange<S> {
+        self.resume_namespaced_data_range(NamespacedDataCursor::new(
+            namespace,
+            from_height,
+            to_height,
+        ))
+    }
+
+    /// Continue iterating over the namespace data from the position of the cursor.
+    ///
+    /// See [`Node::get_namespaced_data_range`].
+    pub fn resume_namespaced_data_range(
+        &self,
+        cursor: NamespacedDataCursor,
+    ) -> NamespacedDataRange<S> {
+        NamespacedDataRange::new(self.p2p.clone(), self.store.clone(), cursor)
+    }
+
+    /// Run the closure on the [`NetworkBehaviour`] added with
+    /// [`NodeBuilder::custom_behaviour`], returning its result.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`P2pError::CustomBehaviourMismatch`] if no behaviour of the given type
+    /// was added.
+    pub async fn with_custom_behaviour<B, F, R>(&self, f: F) -> Result<R>
+    where
+        B: NetworkBehaviour,
+        F: FnOnce(&mut B) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        Ok(self.p2p.with_custom_behaviour(f).await?)
+    }
+
+    /// Append the headers, sorted by ascending height, right above the head of the store.
+    ///
+    /// Only available when syncing is disabled with [`NodeBuilder::disable_syncing`]. The
+    /// headers are validated and verified against the current head. If the store is
+    /// empty, the first of them is trusted, unless it's the genesis header, which must
+    /// match the genesis hash of the node. Appended headers are sampled and their data
+    /// can be requested from the network like for the synchronized ones.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`NodeError::SyncingEnabled`] if the headers are synchronized by the node
+    /// and [`SyncerError::GenesisHashMismatch`] if the genesis header doesn't match.
+    pub async fn append_headers(&self, headers: Vec<ExtendedHeader>) -> Result<()> {
+        let HeadersSource::Manual {
+            genesis_hash,
+            ref local_head_tx,
+            ref stored_ranges_tx,
+        } = self.headers_source
+        else {
+            return Err(NodeError::SyncingEnabled);
+        };
+        let Some(first) = headers.first() else {
+            return Ok(());
+        };
+
+        if let Some(expected) = genesis_hash {
+            if first.height().value() == 1 && first.hash() != expected {
+                return Err(SyncerError::GenesisHashMismatch {
+                    expected,
+                    found: first.hash(),
+                }
+                .into());
+            }
+        }
+
+        self.store
+            .append_with_clock(headers, self.header_clock.check())
+            .await?;
+
+        // Concurrent appends may finish in any order, so the state is read back from
+        // the store and the watchers only ever move forward
+        let head_height = self.store.head_height().await?;
+        let tail_height = self.store.tail_height().await?;
+
+        local_head_tx.send_if_modified(|local_head| {
+            let newer = head_height > *local_head;
+            if newer {
+                *local_head = head_height;
+            }
+            newer
+        });
+        stored_ranges_tx.send_if_modified(|stored_ranges| {
+            let newer = stored_ranges
+                .last()
+                .map_or(true, |range| head_height > *range.end());
+            if newer {
+                *stored_ranges = vec![tail_height..=head_height];
+            }
+            newer
+        });
+
+        Ok(())
+    }
+
+    /// Announce the new head to the network, publishing it on the `header-sub` topic.
+    ///
+    /// Together with [`Node::append_headers`] it allows the node to act as the source
+    /// of the headers of a private network, eg. the one simulated in tests.
+    #[cfg(any(test, feature = "test-utils"))]
+    #[cfg_attr(docs_rs, doc(cfg(feature = "test-utils")))]
+    pub async fn publish_header(&self, header: ExtendedHeader) -> Result<()> {
+        Ok(self.p2p.publish_header(header).await?)
+    }
+
     /// Get current header syncing info.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`NodeError::SyncingDisabled`] if syncing was disabled.
     pub async fn syncer_info(&self) -> Result<SyncingInfo> {
-        Ok(self.syncer.info().await?)
+        Ok(self.syncer()?.info().await?)
+    }
+
+    /// Get the detailed state of the header synchronization, with its target height
+    /// and the ranges of the heights already stored.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`NodeError::SyncingDisabled`] if syncing was disabled.
+    pub async fn syncer_state(&self) -> Result<SyncState> {
+        Ok(self.syncer()?.state().await?)
+    }
+
+    /// Wait until the header at the given height is synchronized to the store.
+    ///
+    /// Any data of the block, like its [`Sample`]s or [`Row`]s, can be requested only
+    /// once its header is stored.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`NodeError::SyncingDisabled`] if syncing was disabled, or
+    /// [`SyncerError::HeightPruned`] if the header was pruned or is below the
+    /// target height of the [`SyncMode::Backward`] mode.
+    ///
+    /// [`Sample`]: celestia_types::sample::Sample
+    pub async fn await_synced(&self, height: u64) -> Result<()> {
+        Ok(self.syncer()?.wait_synced(height).await?)
+    }
+
+    /// Get the [`SamplingEvidence`] of the block at the given height, with the coordinates
+    /// of the sampled shares and the verified samples.
+    ///
+    /// The evidence is kept only for the number of most recently sampled heights set in
+    /// [`DaserConfig::retained_evidence`], `None` is returned for the other ones.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`NodeError::SamplingDisabled`] if sampling isn't enabled.
+    pub fn sampling_evidence(&self, height: u64) -> Result<Option<SamplingEvidence>> {
+        let daser = self.daser.as_ref().ok_or(NodeError::SamplingDisabled)?;
+        Ok(daser.sampling_evidence(height))
+    }
+
+    /// Returns `true` if the data availability sampling is enabled.
+    pub fn is_sampling_enabled(&self) -> bool {
+        self.daser.is_some()
+    }
+
+    fn syncer(&self) -> Result<&Syncer<S>> {
+        match self.headers_source {
+            HeadersSource::Syncer(ref syncer) => Ok(syncer),
+            HeadersSource::Manual { .. } => Err(NodeError::SyncingDisabled),
+        }
     }
 
     /// Get the latest header announced in the network.
+    ///
+    /// The head is kept up to date by the `header-sub` subscription and is validated
+    /// before it's accepted, so no network requests are made and it can be polled
+    /// as often as needed. `None` is returned until the first head is known.
     pub fn get_network_head_header(&self) -> Option<ExtendedHeader> {
         self.p2p.header_sub_watcher().borrow().clone()
     }
 
+    /// Get the height of the latest header announced in the network.
+    ///
+    /// See [`Node::get_network_head_header`].
+    pub fn get_network_head_height(&self) -> Option<u64> {
+        self.p2p
+            .header_sub_watcher()
+            .borrow()
+            .as_ref()
+            .map(|header| header.height().value())
+    }
+
+    /// Watch the latest header announced in the network.
+    ///
+    /// The receiver is notified each time a new valid head is announced on `header-sub`,
+    /// without polling the node.
+    pub fn network_head_watcher(&self) -> watch::Receiver<Option<ExtendedHeader>> {
+        self.p2p.header_sub_watcher()
+    }
+
+    /// Get the valid bad encoding fraud proof received from the network, if any.
+    ///
+    /// Once the fraud is proven, the node stops synchronizing new headers.
+    pub fn get_bad_encoding_fraud_proof(&self) -> Option<BadEncodingFraudProof> {
+        self.p2p.fraud_proof_watcher().borrow().clone()
+    }
+
     /// Get the latest locally synced header.
     pub async fn get_local_head_header(&self) -> Result<ExtendedHeader> {
         Ok(self.store.get_head().await?)
@@ -183,6 +1315,60 @@ where
         Ok(self.store.get_by_height(height).await?)
     }
 
+    /// Get the first header with the time at or after the given one.
+    ///
+    /// Synced headers are searched first. If the header could be older than the tail of
+    /// the store, the preceding headers are requested from the network in batches, going
+    /// down until an older header is found, and verified through the hash chain. If all
+    /// the synced headers are older than `time`, the remaining heights up to the network
+    /// head are binary searched by requesting their headers from the network, one request
+    /// per probed height, and verified against the head of the store. Peers can't steer
+    /// the search with forged headers either way.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError::NotFound`] if all the known headers are older than `time`.
+    pub async fn get_header_by_time(&self, time: Time) -> Result<ExtendedHeader> {
+        match self.store.get_by_time(time).await {
+            Ok(header) => {
+                let height = header.height().value();
+
+                // pruned headers below the tail could be newer than `time` too
+                if height > 1 && height == self.store.tail_height().await? {
+                    if let Some(older) = self.request_header_by_time_backward(time, &header).await?
+                    {
+                        return Ok(older);
+                    }
+                }
+
+                Ok(header)
+            }
+            Err(StoreError::NotFound) => {
+                let network_head = self.get_network_head_header();
+                let found = match self.store.get_head().await {
+                    Ok(head) => {
+                        let from = head.height().value() + 1;
+                        let to = network_head.map_or(0, |header| header.height().value());
+                        self.request_header_by_time(time, from, to, &head).await?
+                    }
+                    // without any synced headers, only the announced head can be trusted
+                    Err(StoreError::NotFound) => match network_head {
+                        Some(head) if head.time() >= time => Some(
+                            self.request_header_by_time_backward(time, &head)
+                                .await?
+                                .unwrap_or(head),
+                        ),
+                        _ => None,
+                    },
+                    Err(e) => return Err(e.into()),
+                };
+
+                found.ok_or_else(|| StoreError::NotFound.into())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
     /// Get synced headers from the given heights range.
     ///
     /// If start of the range is unbounded, the first returned header will be of height 1.
@@ -199,4 +1385,322 @@ where
     {
         Ok(self.store.get_range(range).await?)
     }
+
+    /// Binary search the heights `from..=to`, all above the `trusted` header, for the
+    /// first header with the time at or after the given one, requesting the headers from
+    /// the network and verifying them against the `trusted` one.
+    async fn request_header_by_time(
+        &self,
+        time: Time,
+        from: u64,
+        to: u64,
+        trusted: &ExtendedHeader,
+    ) -> Result<Option<ExtendedHeader>> {
+        let mut low = from;
+        // exclusive, so that an empty range or only older headers give `None`
+        let mut high = to.saturating_add(1);
+        let mut found = None;
+
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let header = self.p2p.get_verified_header_by_height(trusted, mid).await?;
+
+            if header.time() < time {
+                low = mid + 1;
+            } else {
+                high = mid;
+                found = Some(header);
+            }
+        }
+
+        Ok(found)
+    }
+
+    /// Request the headers preceding the `trusted` one in batches, until the first header
+    /// older than `time`, and get the oldest of them with the time at or after the given one.
+    ///
+    /// Each batch is verified through the hash chain against the batch following it.
+    async fn request_header_by_time_backward(
+        &self,
+        time: Time,
+        trusted: &ExtendedHeader,
+    ) -> Result<Option<ExtendedHeader>> {
+        let mut trusted = trusted.clone();
+        let mut found = None;
+
+        while trusted.height().value() > 1 {
+            let amount = (trusted.height().value() - 1).min(HEADERS_BY_TIME_BATCH);
+            let headers = self
+                .p2p
+                .get_verified_headers_range_backward(&trusted, amount)
+                .await?;
+
+            for header in headers.iter().rev() {
+                if header.time() < time {
+                    return Ok(found);
+                }
+                found = Some(header.clone());
+            }
+
+            match headers.into_iter().next() {
+                Some(first) => trusted = first,
+                None => break,
+            }
+        }
+
+        Ok(found)
+    }
+}
+
+/// Source of the headers appended to the store.
+enum HeadersSource<S>
+where
+    S: Store + 'static,
+{
+    /// Headers are synchronized from the network.
+    Syncer(Arc<Syncer<S>>),
+    /// Headers are provided with [`Node::append_headers`], the senders track the head
+    /// and the stored ranges.
+    Manual {
+        genesis_hash: Option<Hash>,
+        local_head_tx: watch::Sender<u64>,
+        stored_ranges_tx: watch::Sender<Vec<RangeInclusive<u64>>>,
+    },
+}
+
+impl<S> HeadersSource<S>
+where
+    S: Store,
+{
+    fn local_head_watcher(&self) -> watch::Receiver<u64> {
+        match self {
+            HeadersSource::Syncer(syncer) => syncer.local_head_watcher(),
+            HeadersSource::Manual { local_head_tx, .. } => local_head_tx.subscribe(),
+        }
+    }
+
+    fn stored_ranges_watcher(&self) -> watch::Receiver<Vec<RangeInclusive<u64>>> {
+        match self {
+            HeadersSource::Syncer(syncer) => syncer.stored_ranges_watcher(),
+            HeadersSource::Manual {
+                stored_ranges_tx, ..
+            } => stored_ranges_tx.subscribe(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use celestia_types::test_utils::ExtendedHeaderGenerator;
+
+    use crate::store::InMemoryStore;
+    use crate::test_utils::{gen_filled_store, test_node_builder};
+
+    #[cfg(not(target_arch = "wasm32"))]
+    use tokio::test as async_test;
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as async_test;
+
+    #[async_test]
+    async fn builder_requires_store() {
+        let res = NodeBuilder::<InMemoryStore>::new()
+            .network(Network::Private)
+            .start()
+            .await;
+
+        assert!(matches!(res, Err(NodeError::MissingStore)));
+    }
+
+    #[async_test]
+    async fn builder_starts_with_provided_store() {
+        let (store, _) = gen_filled_store(3);
+        let node = test_node_builder().store(store).start().await.unwrap();
+
+        let head = node.get_local_head_header().await.unwrap();
+        assert_eq!(head.height().value(), 3);
+        assert!(node.listeners().await.unwrap().is_empty());
+    }
+
+    #[async_test]
+    async fn append_headers_without_syncing() {
+        let (store, mut gen) = gen_filled_store(3);
+        let node = test_node_builder()
+            .store(store)
+            .disable_syncing()
+            .start()
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            node.syncer_info().await.unwrap_err(),
+            NodeError::SyncingDisabled
+        ));
+        assert!(matches!(
+            node.await_synced(1).await.unwrap_err(),
+            NodeError::SyncingDisabled
+        ));
+
+        let mut local_head = node.headers_source.local_head_watcher();
+        assert_eq!(*local_head.borrow(), 3);
+
+        let headers = gen.next_many(2);
+        node.append_headers(headers.clone()).await.unwrap();
+        assert_eq!(node.get_local_head_header().await.unwrap(), headers[1]);
+        assert_eq!(*local_head.borrow_and_update(), 5);
+
+        // headers not continuing the head are rejected
+        let fork = gen.next_many_of(&headers[0], 2);
+        node.append_headers(fork).await.unwrap_err();
+        assert_eq!(*local_head.borrow(), 5);
+
+        let node = test_node_builder().start().await.unwrap();
+        let err = node.append_headers(gen.next_many(1)).await.unwrap_err();
+        assert!(matches!(err, NodeError::SyncingEnabled));
+    }
+
+    #[async_test]
+    async fn append_headers_checks_genesis_hash() {
+        let mut gen = ExtendedHeaderGenerator::new();
+        let headers = gen.next_many(3);
+        let node = test_node_builder()
+            .genesis_hash(Some(Hash::Sha256([0xab; 32])))
+            .disable_syncing()
+            .start()
+            .await
+            .unwrap();
+
+        let err = node.append_headers(headers.clone()).await.unwrap_err();
+        assert!(matches!(
+            err,
+            NodeError::Syncer(SyncerError::GenesisHashMismatch { .. })
+        ));
+        assert_eq!(*node.headers_source.local_head_watcher().borrow(), 0);
+
+        let node = test_node_builder()
+            .genesis_hash(Some(headers[0].hash()))
+            .disable_syncing()
+            .start()
+            .await
+            .unwrap();
+        node.append_headers(headers).await.unwrap();
+        assert_eq!(*node.headers_source.local_head_watcher().borrow(), 3);
+    }
+
+    #[async_test]
+    async fn get_header_by_time() {
+        let store = InMemoryStore::new();
+        // seeded generator creates a header every second
+        let headers = ExtendedHeaderGenerator::new_with_seed(3).next_many(5);
+        store.append_unchecked(headers.clone()).await.unwrap();
+        let node = test_node_builder()
+            .store(store)
+            .disable_syncing()
+            .start()
+            .await
+            .unwrap();
+
+        for header in &headers {
+            assert_eq!(
+                &node.get_header_by_time(header.time()).await.unwrap(),
+                header
+            );
+        }
+
+        // nothing newer is synced and no network head was announced
+        assert_eq!(node.get_network_head_height(), None);
+        let after = headers[4]
+            .time()
+            .checked_add(Duration::from_secs(1))
+            .unwrap();
+        assert!(matches!(
+            node.get_header_by_time(after).await.unwrap_err(),
+            NodeError::Store(StoreError::NotFound)
+        ));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn network_head_follows_header_sub() {
+        use crate::test_utils::listening_test_node_builder;
+
+        let (store, mut gen) = gen_filled_store(5);
+        let bridge = listening_test_node_builder()
+            .store(store)
+            .disable_syncing()
+            .start()
+            .await
+            .unwrap();
+        let node = test_node_builder()
+            .bootnodes(bridge.listeners().await.unwrap())
+            .start()
+            .await
+            .unwrap();
+        node.wait_connected().await.unwrap();
+        node.await_synced(5).await.unwrap();
+
+        // the head is initialized with the one the syncer got from the peers
+        let mut watcher = node.network_head_watcher();
+        watcher.wait_for(|head| head.is_some()).await.unwrap();
+        assert_eq!(node.get_network_head_height(), Some(5));
+
+        let header = gen.next();
+        // publishing fails until the node's subscription to the topic is known
+        while bridge.publish_header(header.clone()).await.is_err() {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        tokio::time::timeout(Duration::from_secs(5), watcher.changed())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(watcher.borrow_and_update().as_ref(), Some(&header));
+        assert_eq!(node.get_network_head_height(), Some(6));
+        assert_eq!(node.get_network_head_header(), Some(header));
+
+        // the next announcement notifies the watcher again
+        let header = gen.next();
+        bridge.publish_header(header.clone()).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(5), watcher.changed())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(watcher.borrow().as_ref(), Some(&header));
+        assert_eq!(node.get_network_head_height(), Some(7));
+    }
+
+    #[async_test]
+    async fn custom_behaviour_access() {
+        let (events_tx, _events_rx) = mpsc::channel(1);
+        let node = test_node_builder()
+            .custom_behaviour(|_| libp2p::swarm::dummy::Behaviour, events_tx)
+            .start()
+            .await
+            .unwrap();
+
+        let res = node
+            .with_custom_behaviour(|_: &mut libp2p::swarm::dummy::Behaviour| 7)
+            .await
+            .unwrap();
+        assert_eq!(res, 7);
+
+        let err = node
+            .with_custom_behaviour(|_: &mut libp2p::ping::Behaviour| ())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            NodeError::P2p(P2pError::CustomBehaviourMismatch)
+        ));
+
+        let node = test_node_builder().start().await.unwrap();
+        let err = node
+            .with_custom_behaviour(|_: &mut libp2p::swarm::dummy::Behaviour| ())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            NodeError::P2p(P2pError::CustomBehaviourMismatch)
+        ));
+    }
 }
@@ -4,50 +4,99 @@
 //! Currently supporting:
 //! - libp2p-identitfy
 //! - header-sub topic on libp2p-gossipsub
+//! - fraud-sub topic for bad encoding fraud proofs on libp2p-gossipsub
 //! - libp2p-kad
 //! - libp2p-autonat
+//! - libp2p-relay client and libp2p-dcutr hole punching
 //! - libp2p-ping
 //! - header-ex client
 //! - header-ex server
+//! - bitswap client (shwap containers)
+//! - bitswap server and DHT provider records of the stored shwap containers
+//! - connection limits, per IP and memory based resource limits
+//! - HTTP gateway fallback for the shwap containers (with the `gateway` feature)
+//!
+//! Additional protocols can be run in the same swarm by injecting a [`CustomBehaviour`].
 
+use std::any::Any;
+#[cfg(not(target_arch = "wasm32"))]
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::fmt;
 use std::io;
 use std::marker::PhantomData;
 use std::sync::Arc;
 use std::time::Duration;
 
+use blockstore::block::CidError;
+use blockstore::BlockstoreError;
+use celestia_proto::bitswap::message::pb::Message as RawBitswapMessage;
 use celestia_proto::p2p::pb::{header_request, HeaderRequest};
+use celestia_proto::share::p2p::shwap::{
+    Data as RawNamespacedData, Row as RawRow, Sample as RawSample,
+};
+use celestia_tendermint::Time;
+use celestia_tendermint_proto::Protobuf;
+use celestia_types::fraud_proof::BadEncodingFraudProof;
 use celestia_types::hash::Hash;
-use celestia_types::ExtendedHeader;
-use futures::StreamExt;
+use celestia_types::namespaced_data::{NamespacedData, NamespacedDataId};
+use celestia_types::nmt::{Namespace, NamespacedSha2Hasher};
+use celestia_types::row::{IdLayout, Row, RowId};
+use celestia_types::sample::{Sample, SampleId};
+use celestia_types::{ClockCheck, ErrorKind, ExtendedHeader, FraudProof, ValidationError};
+use cid::CidGeneric;
+use futures::{stream, Future, StreamExt, TryStreamExt};
 use instant::Instant;
 use libp2p::{
-    autonat,
+    autonat, connection_limits,
     core::{ConnectedPoint, Endpoint},
+    dcutr,
     gossipsub::{self, SubscriptionError, TopicHash},
     identify,
     identity::Keypair,
     kad,
     multiaddr::Protocol,
-    ping,
-    swarm::{ConnectionId, DialError, NetworkBehaviour, NetworkInfo, Swarm, SwarmEvent},
-    Multiaddr, PeerId, TransportError,
+    ping, relay,
+    swarm::{
+        behaviour::toggle::Toggle,
+        dial_opts::{DialOpts, PeerCondition},
+        dummy, ConnectionId, DialError, NetworkBehaviour, NetworkInfo, Swarm, SwarmEvent,
+    },
+    Multiaddr, PeerId, StreamProtocol, TransportError,
 };
+use prost::Message as _;
+use rand::Rng;
 use tokio::select;
 use tokio::sync::{mpsc, oneshot, watch};
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, info, instrument, trace, warn};
 
-use crate::executor::{spawn, Interval};
+use crate::address_book::AddressBook;
+use crate::bitswap::{
+    self, BitswapBehaviour, BitswapClient, BitswapEvent, BitswapMessage, BlockReceiver,
+    BlockSender, Cid, MessageSender,
+};
+use crate::clock::{Clock, HeaderClock};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::eds_store::EdsStore;
+use crate::events::{EventPublisher, NodeEvent};
+use crate::executor::{sleep, spawn, timeout, Interval};
+#[cfg(feature = "gateway")]
+use crate::gateway::{Gateway, GatewayConfig};
 use crate::header_ex::{HeaderExBehaviour, HeaderExConfig};
+use crate::ip_limits::IpLimitsBehaviour;
+use crate::metrics;
 use crate::peer_tracker::PeerTracker;
-use crate::peer_tracker::PeerTrackerInfo;
+use crate::peer_tracker::{ConnectionDirection, Misbehavior, PeerDiagnostics, PeerTrackerInfo};
 use crate::session::Session;
-use crate::store::Store;
+use crate::store::{Store, StoreError};
 use crate::swarm::new_swarm;
 use crate::utils::{
-    celestia_protocol_id, gossipsub_ident_topic, MultiaddrExt, OneshotResultSender,
-    OneshotSenderExt,
+    celestia_protocol_id, fraud_sub_ident_topic, gossipsub_ident_topic, protocol_id, spawn_compute,
+    MultiaddrExt, OneshotResultSender, OneshotSenderExt,
 };
 
+pub use crate::bitswap::BitswapError;
 pub use crate::header_ex::HeaderExError;
 
 // Minimal number of peers that we want to maintain connection to.
@@ -61,9 +110,36 @@ const MIN_CONNECTED_PEERS: u64 = 4;
 //
 // libp2p team suggests to start bootstrap procedure every 5 minute
 const KADEMLIA_BOOTSTRAP_PERIOD: Duration = Duration::from_secs(5 * 60);
+// How often we check for the connected peers that got banned.
+const BANNED_PEERS_CHECK_PERIOD: Duration = Duration::from_secs(5);
+// How often the stale addresses of the known peers are evicted.
+const STALE_PEER_ADDRS_CHECK_PERIOD: Duration = Duration::from_secs(60);
+// How often the requests abandoned by their callers are cleaned up.
+const ABANDONED_REQUESTS_CHECK_PERIOD: Duration = Duration::from_secs(1);
+// Maximum time we wait for a shwap container to be delivered over bitswap.
+const GET_SHWAP_CID_TIMEOUT: Duration = Duration::from_secs(60);
+/// Maximum number of rows of [`NamespacedData`] requested at the same time.
+const NAMESPACED_DATA_CONCURRENCY: usize = 8;
+/// Maximum number of the stored shwap containers announced on the DHT, the oldest
+/// ones stop being announced first.
+const MAX_PROVIDED_BLOCKS: usize = 16 * 1024;
+/// Maximum number of the wants answered from the squares of the [`EdsStore`] at the
+/// same time, as each of them may need to reconstruct a whole square.
+#[cfg(not(target_arch = "wasm32"))]
+const MAX_EDS_WANTS_IN_PROGRESS: usize = 4;
+/// Maximum number of the wants of a single peer answered from the [`EdsStore`] at the
+/// same time.
+#[cfg(not(target_arch = "wasm32"))]
+const MAX_EDS_WANTS_PER_PEER: usize = 1;
 
 type Result<T, E = P2pError> = std::result::Result<T, E>;
 
+#[cfg(not(target_arch = "wasm32"))]
+type MemoryLimitsBehaviour = libp2p::memory_connection_limits::Behaviour;
+// Memory usage can't be checked in the browser
+#[cfg(target_arch = "wasm32")]
+type MemoryLimitsBehaviour = dummy::Behaviour;
+
 /// Representation of all the errors that can occur when interacting with [`P2p`].
 #[derive(Debug, thiserror::Error)]
 pub enum P2pError {
@@ -83,6 +159,14 @@ pub enum P2pError {
     #[error("Failed to initialize noise: {0}")]
     InitNoise(String),
 
+    /// None of the transports was enabled in the [`TransportConfig`].
+    #[error("No transport enabled")]
+    NoTransportEnabled,
+
+    /// The transport enabled in the [`TransportConfig`] is not available on this platform.
+    #[error("Transport not supported on this platform: {0}")]
+    UnsupportedTransport(&'static str),
+
     /// Error occured when trying to establish or upgrade an outbound connection.
     #[error("Dial error: {0}")]
     Dial(#[from] DialError),
@@ -106,6 +190,172 @@ pub enum P2pError {
     /// Bootnode address is missing its peer ID.
     #[error("Bootnode multiaddrs without peer ID: {0:?}")]
     BootnodeAddrsWithoutPeerId(Vec<Multiaddr>),
+
+    /// Relay address is missing its peer ID.
+    #[error("Relay multiaddrs without peer ID: {0:?}")]
+    RelayAddrsWithoutPeerId(Vec<Multiaddr>),
+
+    /// An error propagated from the bitswap.
+    #[error("Bitswap: {0}")]
+    Bitswap(#[from] BitswapError),
+
+    /// Failed to build a [`Cid`] for the request.
+    ///
+    /// [`Cid`]: cid::Cid
+    #[error("Invalid CID: {0}")]
+    Cid(#[from] CidError),
+
+    /// Request timed out.
+    #[error("Request timed out")]
+    RequestTimedOut,
+
+    /// Shwap container received from the network is invalid.
+    #[error("Invalid shwap container: {0}")]
+    InvalidShwap(celestia_types::Error),
+
+    /// No peer connected before the timeout elapsed.
+    #[error("Not connected to any peer within {timeout:?}, bootnodes tried: {bootnodes:?}")]
+    ConnectTimedOut {
+        /// The elapsed timeout.
+        timeout: Duration,
+        /// Bootnodes the node was dialing.
+        bootnodes: Vec<Multiaddr>,
+    },
+
+    /// No [`CustomBehaviour`] of the requested type is running in the swarm.
+    #[error("No custom behaviour of the requested type is running")]
+    CustomBehaviourMismatch,
+
+    /// Request was cancelled by the caller.
+    #[error("Request cancelled")]
+    Cancelled,
+
+    /// HTTP gateway failed to deliver the shwap container.
+    #[error("Gateway: {0}")]
+    Gateway(String),
+
+    /// Failed to publish a message on gossipsub.
+    #[error("Failed to publish on gossipsub: {0}")]
+    GossipsubPublish(String),
+
+    /// No version of the protocol is configured in the [`ProtocolVersions`].
+    #[error("No version of the {0} protocol configured")]
+    NoProtocolVersions(&'static str),
+
+    /// Requested share lies outside of the extended data square.
+    #[error("Share ({row}, {column}) out of the square of width {square_len}")]
+    ShareOutOfSquare {
+        /// Row index of the share.
+        row: u16,
+        /// Column index of the share.
+        column: u16,
+        /// Width of the extended data square.
+        square_len: usize,
+    },
+}
+
+impl P2pError {
+    /// Returns `true` if the request which failed with this error may succeed
+    /// when sent again, e.g. to a different peer.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            P2pError::HeaderEx(HeaderExError::InvalidRequest) => false,
+            P2pError::HeaderEx(_)
+            | P2pError::Bitswap(_)
+            | P2pError::RequestTimedOut
+            | P2pError::Gateway(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Get the [`ErrorKind`] of the error.
+    pub fn kind(&self) -> ErrorKind {
+        self.code_and_kind().1
+    }
+
+    /// Get the stable code of the error, see [`celestia_types::Error::code`].
+    ///
+    /// Errors of the [`P2p`] use the codes from `2001` to `2999`, with the ones
+    /// of the `header-ex` starting at `2101` and of the bitswap at `2201`.
+    pub fn code(&self) -> u16 {
+        self.code_and_kind().0
+    }
+
+    fn code_and_kind(&self) -> (u16, ErrorKind) {
+        match self {
+            P2pError::GossipsubInit(..) => (2001, ErrorKind::Internal),
+            P2pError::GossipsubSubscribe(..) => (2002, ErrorKind::Internal),
+            P2pError::Transport(..) => (2003, ErrorKind::P2p),
+            P2pError::InitNoise(..) => (2004, ErrorKind::Internal),
+            P2pError::NoTransportEnabled => (2005, ErrorKind::Config),
+            P2pError::UnsupportedTransport(..) => (2006, ErrorKind::Config),
+            P2pError::Dial(..) => (2007, ErrorKind::P2p),
+            P2pError::WorkerDied => (2008, ErrorKind::Internal),
+            P2pError::ChannelClosedUnexpectedly => (2009, ErrorKind::Internal),
+            P2pError::NoConnectedPeers => (2010, ErrorKind::P2p),
+            P2pError::HeaderEx(e) => (e.code(), e.kind()),
+            P2pError::BootnodeAddrsWithoutPeerId(..) => (2012, ErrorKind::Config),
+            P2pError::RelayAddrsWithoutPeerId(..) => (2013, ErrorKind::Config),
+            P2pError::Bitswap(e) => (e.code(), e.kind()),
+            P2pError::Cid(..) => (2015, ErrorKind::Encoding),
+            P2pError::RequestTimedOut => (2016, ErrorKind::P2p),
+            P2pError::InvalidShwap(e) => (2017, e.kind()),
+            P2pError::ConnectTimedOut { .. } => (2018, ErrorKind::P2p),
+            P2pError::CustomBehaviourMismatch => (2019, ErrorKind::Config),
+            P2pError::Cancelled => (2020, ErrorKind::P2p),
+            P2pError::Gateway(..) => (2021, ErrorKind::External),
+            P2pError::GossipsubPublish(..) => (2022, ErrorKind::P2p),
+            P2pError::NoProtocolVersions(..) => (2023, ErrorKind::Config),
+            P2pError::ShareOutOfSquare { .. } => (2024, ErrorKind::Validation),
+        }
+    }
+}
+
+/// Check that the share coordinates lie within the extended data square of the given width.
+pub(crate) fn check_share_coordinates(row: u16, column: u16, square_len: usize) -> Result<()> {
+    if usize::from(row) >= square_len || usize::from(column) >= square_len {
+        return Err(P2pError::ShareOutOfSquare {
+            row,
+            column,
+            square_len,
+        });
+    }
+
+    Ok(())
+}
+
+/// Run the request until it completes or the `duration` elapses.
+pub(crate) async fn with_timeout<F, T, E>(duration: Duration, request: F) -> Result<T, E>
+where
+    F: Future<Output = Result<T, E>>,
+    E: From<P2pError>,
+{
+    timeout(duration, request)
+        .await
+        .unwrap_or_else(|_| Err(P2pError::RequestTimedOut.into()))
+}
+
+/// Run the request until it completes or the `token` gets cancelled.
+pub(crate) async fn with_cancellation<F, T, E>(
+    token: &CancellationToken,
+    request: F,
+) -> Result<T, E>
+where
+    F: Future<Output = Result<T, E>>,
+    E: From<P2pError>,
+{
+    select! {
+        res = request => res,
+        _ = token.cancelled() => Err(P2pError::Cancelled.into()),
+    }
+}
+
+/// Record the duration of the successful sampling or the failure.
+fn record_sampling(res: &Result<Sample>, started: Instant) {
+    match res {
+        Ok(_) => metrics::record_duration(metrics::SAMPLING_DURATION, started),
+        Err(_) => metrics::increment(metrics::SAMPLING_FAILURES, 1),
+    }
 }
 
 impl From<oneshot::error::RecvError> for P2pError {
@@ -122,8 +372,16 @@ where
 {
     cmd_tx: mpsc::Sender<P2pCmd>,
     header_sub_watcher: watch::Receiver<Option<ExtendedHeader>>,
+    fraud_proof_watcher: watch::Receiver<Option<BadEncodingFraudProof>>,
     peer_tracker_info_watcher: watch::Receiver<PeerTrackerInfo>,
     local_peer_id: PeerId,
+    bootnodes: Vec<Multiaddr>,
+    retry: RetryConfig,
+    id_layout: IdLayout,
+    namespace_fetch_mode: NamespaceFetchMode,
+    header_clock: HeaderClock,
+    #[cfg(feature = "gateway")]
+    gateway: Option<Gateway>,
     _store: PhantomData<S>,
 }
 
@@ -137,11 +395,416 @@ where
     /// The keypair to be used as the identity.
     pub local_keypair: Keypair,
     /// List of bootstrap nodes to connect to and trust.
+    ///
+    /// Addresses of the previously seen peers, persisted in the [`Store`], are dialed
+    /// before them.
     pub bootnodes: Vec<Multiaddr>,
     /// List of the addresses on which to listen for incoming connections.
     pub listen_on: Vec<Multiaddr>,
+    /// List of the circuit relays on which to reserve a slot, so that the peers can
+    /// reach the node through them when it's behind a NAT.
+    pub relay_addrs: Vec<Multiaddr>,
+    /// Transports used to dial and accept the connections.
+    pub transports: TransportConfig,
     /// The store for headers.
     pub store: Arc<S>,
+    /// Publisher of the [`NodeEvent`]s.
+    pub event_pub: EventPublisher,
+    /// Whether to announce the stored shwap containers on the DHT and serve them
+    /// to the other peers over bitswap.
+    pub provide_blocks: bool,
+    /// Store of the whole squares from which the shwap containers are served
+    /// in addition to the ones in the [`Store`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub eds_store: Option<EdsStore>,
+    /// Policy of retrying the failed `header-ex` requests and shwap fetches.
+    pub retry: RetryConfig,
+    /// Layout of the shwap identifiers of the containers returned by the [`P2p`].
+    ///
+    /// It is also used with the peers which don't speak any of the shwap versions,
+    /// e.g. before they are identified.
+    pub shwap_id_layout: IdLayout,
+    /// Versions of the `header-ex` and shwap protocols spoken with the peers.
+    pub protocol_versions: ProtocolVersions,
+    /// How the [`NamespacedData`] is retrieved from the peers.
+    pub namespace_fetch_mode: NamespaceFetchMode,
+    /// Limits of the connections and the resources used by the swarm.
+    pub resource_limits: ResourceLimits,
+    /// HTTP gateway from which the shwap containers are retrieved when the bitswap
+    /// retrieval times out.
+    #[cfg(feature = "gateway")]
+    pub gateway: Option<GatewayConfig>,
+    /// Additional validators of the heads received over the header-sub.
+    pub head_validators: Vec<Arc<dyn HeadValidator>>,
+    /// Clock against which the received headers are checked not to be from the future.
+    pub clock: Arc<dyn Clock>,
+    /// Maximum time by which the received headers can be ahead of the `clock`.
+    pub max_clock_drift: Duration,
+}
+
+/// Selection of the transports used by the [`P2p`].
+///
+/// Only the transports supported by the platform can be enabled. Natively these are
+/// TCP, QUIC and the in-process memory transport, while in the browser it is only
+/// WebTransport. The default enables all the network transports of the current platform.
+///
+/// ```
+/// use lumina_node::p2p::TransportConfig;
+///
+/// let quic_only = TransportConfig::empty().quic(true);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransportConfig {
+    pub(crate) tcp: bool,
+    pub(crate) quic: bool,
+    pub(crate) webtransport: bool,
+    pub(crate) memory: bool,
+}
+
+impl TransportConfig {
+    /// Configuration without any transport, on which the selected ones are enabled.
+    pub fn empty() -> Self {
+        TransportConfig {
+            tcp: false,
+            quic: false,
+            webtransport: false,
+            memory: false,
+        }
+    }
+
+    /// Only the in-process transport listening on the `/memory/<port>` addresses, for
+    /// connecting the nodes running in the same process, eg. in tests.
+    pub fn in_memory() -> Self {
+        TransportConfig {
+            memory: true,
+            ..TransportConfig::empty()
+        }
+    }
+
+    /// Enable or disable TCP, secured with noise and multiplexed with yamux.
+    pub fn tcp(mut self, enabled: bool) -> Self {
+        self.tcp = enabled;
+        self
+    }
+
+    /// Enable or disable QUIC, preferred by most of the Go celestia-node peers.
+    pub fn quic(mut self, enabled: bool) -> Self {
+        self.quic = enabled;
+        self
+    }
+
+    /// Enable or disable WebTransport.
+    pub fn webtransport(mut self, enabled: bool) -> Self {
+        self.webtransport = enabled;
+        self
+    }
+}
+
+impl Default for TransportConfig {
+    fn default() -> Self {
+        let native = cfg!(not(target_arch = "wasm32"));
+
+        TransportConfig::empty()
+            .tcp(native)
+            .quic(native)
+            .webtransport(!native)
+    }
+}
+
+/// Limits of the connections and the resources used by the [`P2p`].
+///
+/// `None` disables the limit. The defaults are lower in the browser, where the node
+/// doesn't accept the incoming connections and runs next to the other tabs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResourceLimits {
+    /// Maximum number of the established connections.
+    pub max_established: Option<u32>,
+    /// Maximum number of the established incoming connections.
+    pub max_established_incoming: Option<u32>,
+    /// Maximum number of the established outgoing connections.
+    pub max_established_outgoing: Option<u32>,
+    /// Maximum number of the incoming connections being negotiated.
+    pub max_pending_incoming: Option<u32>,
+    /// Maximum number of the outgoing connections being negotiated.
+    pub max_pending_outgoing: Option<u32>,
+    /// Maximum number of the connections established with a single peer.
+    pub max_established_per_peer: Option<u32>,
+    /// Maximum number of the incoming connections from a single IP address.
+    pub max_incoming_per_ip: Option<u32>,
+    /// Fraction of the system memory, between `0.0` and `1.0`, above which the process
+    /// stops accepting and dialing the new connections. Not supported in the browser.
+    pub max_memory_fraction: Option<f64>,
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        if cfg!(target_arch = "wasm32") {
+            ResourceLimits {
+                max_established: Some(64),
+                max_established_incoming: Some(16),
+                max_established_outgoing: Some(64),
+                max_pending_incoming: Some(8),
+                max_pending_outgoing: Some(16),
+                max_established_per_peer: Some(2),
+                max_incoming_per_ip: Some(4),
+                max_memory_fraction: None,
+            }
+        } else {
+            ResourceLimits {
+                max_established: Some(256),
+                max_established_incoming: Some(160),
+                max_established_outgoing: Some(128),
+                max_pending_incoming: Some(64),
+                max_pending_outgoing: Some(64),
+                max_established_per_peer: Some(2),
+                max_incoming_per_ip: Some(8),
+                max_memory_fraction: Some(0.9),
+            }
+        }
+    }
+}
+
+impl ResourceLimits {
+    /// Disable all the limits.
+    pub fn unlimited() -> Self {
+        ResourceLimits {
+            max_established: None,
+            max_established_incoming: None,
+            max_established_outgoing: None,
+            max_pending_incoming: None,
+            max_pending_outgoing: None,
+            max_established_per_peer: None,
+            max_incoming_per_ip: None,
+            max_memory_fraction: None,
+        }
+    }
+
+    fn connection_limits(&self) -> connection_limits::ConnectionLimits {
+        connection_limits::ConnectionLimits::default()
+            .with_max_established(self.max_established)
+            .with_max_established_incoming(self.max_established_incoming)
+            .with_max_established_outgoing(self.max_established_outgoing)
+            .with_max_pending_incoming(self.max_pending_incoming)
+            .with_max_pending_outgoing(self.max_pending_outgoing)
+            .with_max_established_per_peer(self.max_established_per_peer)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn memory_limits(&self) -> Option<MemoryLimitsBehaviour> {
+        self.max_memory_fraction
+            .map(|fraction| MemoryLimitsBehaviour::with_max_percentage(fraction.clamp(0.0, 1.0)))
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn memory_limits(&self) -> Option<MemoryLimitsBehaviour> {
+        None
+    }
+}
+
+/// Policy of retrying the failed `header-ex` requests and shwap fetches of the [`P2p`].
+///
+/// The delay before each retry grows exponentially from the `base_delay`, up to the
+/// `max_delay`, and is randomized by the `jitter` so that the retries of concurrent
+/// requests don't hit the peers at the same time. The default suits most connections,
+/// on high-latency ones it's worth allowing more attempts with longer delays.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryConfig {
+    /// Maximum number of attempts of a single request, including the first one.
+    pub max_attempts: u32,
+    /// Delay before the first retry.
+    pub base_delay: Duration,
+    /// Upper bound of the delay before a retry.
+    pub max_delay: Duration,
+    /// Fraction by which each delay is randomly shortened or prolonged, between `0.0` and `1.0`.
+    pub jitter: f64,
+}
+
+impl RetryConfig {
+    /// Delay before retrying the request which failed the given attempt, counted from `1`.
+    pub(crate) fn delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(31);
+        let delay = self
+            .base_delay
+            .saturating_mul(1 << exponent)
+            .min(self.max_delay);
+        let jitter = if self.jitter.is_finite() {
+            self.jitter.clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+
+        if jitter == 0.0 {
+            delay
+        } else {
+            let factor = rand::thread_rng().gen_range(1.0 - jitter..=1.0 + jitter);
+            Duration::try_from_secs_f64(delay.as_secs_f64() * factor).unwrap_or(self.max_delay)
+        }
+    }
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        RetryConfig {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(10),
+            jitter: 0.5,
+        }
+    }
+}
+
+/// How the [`P2p`] retrieves the [`NamespacedData`] of a block.
+///
+/// In either mode only the rows whose roots cover the namespace are requested.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NamespaceFetchMode {
+    /// Request only the shares of the namespace with the proofs of their inclusion in
+    /// the rows. Parity shares are neither transferred nor decoded.
+    #[default]
+    NamespaceShares,
+    /// Request the whole rows containing the namespace and build the proofs locally.
+    ///
+    /// Transfers the original data half of each row and recomputes its parity half,
+    /// so it's only worth it with the peers not serving the namespaced data containers.
+    FullRows,
+}
+
+/// Versions of the `header-ex` and shwap protocols spoken by the [`P2p`].
+///
+/// Each list is ordered from the preferred version. Versions are chosen separately for
+/// each peer: requests are sent in the first version of the list which the peer supports,
+/// while the requests of the peers are answered in any of them. Listing the versions
+/// introduced by a network upgrade next to the current ones keeps the node talking to
+/// both the upgraded and the older peers.
+///
+/// Protocol ids are relative to the network, e.g. `/header-ex/v0.0.3` is spoken
+/// as `/<network>/header-ex/v0.0.3`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtocolVersions {
+    /// Versions of the `header-ex` protocol.
+    ///
+    /// All the versions share the same messages, only their protocol ids differ.
+    pub header_ex: Vec<String>,
+    /// Versions of the bitswap protocol carrying the shwap containers.
+    pub shwap: Vec<ShwapVersion>,
+}
+
+/// Version of the bitswap protocol carrying the shwap containers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShwapVersion {
+    /// Protocol id of the bitswap, spoken as `/celestia/<network><protocol>`.
+    pub protocol: String,
+    /// Layout of the shwap identifiers used by the peers speaking this version.
+    pub id_layout: IdLayout,
+}
+
+impl ProtocolVersions {
+    /// Versions spoken by the current celestia-node, with the shwap identifiers
+    /// in the given layout.
+    pub fn new(id_layout: IdLayout) -> Self {
+        ProtocolVersions {
+            header_ex: vec!["/header-ex/v0.0.3".to_owned()],
+            shwap: vec![ShwapVersion {
+                protocol: "/ipfs/bitswap/1.2.0".to_owned(),
+                id_layout,
+            }],
+        }
+    }
+
+    fn header_ex_protocols(&self, network_id: &str) -> Vec<StreamProtocol> {
+        self.header_ex
+            .iter()
+            .map(|version| protocol_id(network_id, version))
+            .collect()
+    }
+
+    fn shwap_protocols(&self, network_id: &str) -> Vec<(StreamProtocol, IdLayout)> {
+        self.shwap
+            .iter()
+            .map(|version| {
+                let protocol = celestia_protocol_id(network_id, &version.protocol);
+                (protocol, version.id_layout)
+            })
+            .collect()
+    }
+}
+
+impl Default for ProtocolVersions {
+    fn default() -> Self {
+        ProtocolVersions::new(IdLayout::default())
+    }
+}
+
+/// Additional [`NetworkBehaviour`] run in the swarm of the [`P2p`], next to the
+/// built-in protocols.
+///
+/// Allows extending the node with protocols of its own, like a gossipsub topic or
+/// a request-response protocol, without forking it. Events emitted by the behaviour
+/// are forwarded to the given channel and the behaviour itself can be accessed with
+/// [`P2p::with_custom_behaviour`]. Events are dropped if the channel is full.
+pub struct CustomBehaviour<B>
+where
+    B: NetworkBehaviour,
+{
+    /// The behaviour to run.
+    pub behaviour: B,
+    /// Sender of the events emitted by the behaviour.
+    pub events_tx: mpsc::Sender<B::ToSwarm>,
+}
+
+/// Outcome of the validation of a head received over the header-sub.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeadAcceptance {
+    /// The head is valid. It becomes the new network head and is propagated further.
+    Accept,
+    /// The head is not used nor propagated, but the peer which sent it may be honest,
+    /// e.g. the head is older than the known one.
+    Ignore,
+    /// The head is invalid. It is not propagated and the peer which sent it is penalized.
+    Reject,
+}
+
+impl From<HeadAcceptance> for gossipsub::MessageAcceptance {
+    fn from(acceptance: HeadAcceptance) -> Self {
+        match acceptance {
+            HeadAcceptance::Accept => gossipsub::MessageAcceptance::Accept,
+            HeadAcceptance::Ignore => gossipsub::MessageAcceptance::Ignore,
+            HeadAcceptance::Reject => gossipsub::MessageAcceptance::Reject,
+        }
+    }
+}
+
+/// Additional validation of the heads received over the header-sub.
+///
+/// Validators run after the built-in checks, so the new head is already validated
+/// and verified against the known network head. The head is accepted only if all the
+/// validators accept it. This allows e.g. rejecting the heads which don't follow the
+/// rules of an application built on top of the node.
+///
+/// It is implemented for the closures taking the known head and the new one.
+pub trait HeadValidator: Send + Sync {
+    /// Validate the `new_head` following the `known_head`.
+    fn validate(&self, known_head: &ExtendedHeader, new_head: &ExtendedHeader) -> HeadAcceptance;
+}
+
+impl<F> HeadValidator for F
+where
+    F: Fn(&ExtendedHeader, &ExtendedHeader) -> HeadAcceptance + Send + Sync,
+{
+    fn validate(&self, known_head: &ExtendedHeader, new_head: &ExtendedHeader) -> HeadAcceptance {
+        self(known_head, new_head)
+    }
+}
+
+/// Type-erased access to the [`CustomBehaviour`], given `None` if there is none.
+type CustomBehaviourAccess = dyn FnOnce(Option<&mut dyn Any>) + Send;
+
+pub(crate) struct CustomBehaviourFn(Box<CustomBehaviourAccess>);
+
+impl fmt::Debug for CustomBehaviourFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CustomBehaviourFn")
+    }
 }
 
 #[derive(Debug)]
@@ -153,38 +816,102 @@ pub(crate) enum P2pCmd {
         request: HeaderRequest,
         respond_to: OneshotResultSender<Vec<ExtendedHeader>, P2pError>,
     },
+    HeaderExQuorumRequest {
+        request: HeaderRequest,
+        quorum: usize,
+        respond_to: OneshotResultSender<Vec<ExtendedHeader>, P2pError>,
+    },
     Listeners {
         respond_to: oneshot::Sender<Vec<Multiaddr>>,
     },
     ConnectedPeers {
         respond_to: oneshot::Sender<Vec<PeerId>>,
     },
+    PeerDiagnostics {
+        respond_to: oneshot::Sender<Vec<PeerDiagnostics>>,
+    },
     InitHeaderSub {
         head: Box<ExtendedHeader>,
     },
+    #[cfg(any(test, feature = "test-utils"))]
+    PublishHeader {
+        header: Box<ExtendedHeader>,
+        respond_to: OneshotResultSender<(), P2pError>,
+    },
     SetPeerTrust {
         peer_id: PeerId,
         is_trusted: bool,
     },
+    GetShwapCid {
+        cid: Cid,
+        respond_to: BlockSender,
+    },
+    GetShwapCids {
+        wants: Vec<(Cid, BlockSender)>,
+    },
+    AcceptShwapBlock {
+        cid: Cid,
+        peer: PeerId,
+    },
+    RejectShwapBlock {
+        cid: Cid,
+        peer: PeerId,
+    },
+    PutShwapBlock {
+        cid: Cid,
+        data: Vec<u8>,
+    },
+    WithCustomBehaviour {
+        f: CustomBehaviourFn,
+    },
 }
 
 impl<S> P2p<S>
 where
-    S: Store,
+    S: Store + 'static,
 {
     /// Creates and starts a new p2p handler.
     pub fn start(args: P2pArgs<S>) -> Result<Self> {
+        P2p::start_with_custom_behaviour::<dummy::Behaviour>(args, None)
+    }
+
+    /// Create and start the [`P2p`] with an additional [`CustomBehaviour`] in its swarm.
+    pub fn start_with_custom_behaviour<B>(
+        args: P2pArgs<S>,
+        custom: Option<CustomBehaviour<B>>,
+    ) -> Result<Self>
+    where
+        B: NetworkBehaviour + Send,
+        B::ToSwarm: Send,
+    {
         validate_bootnode_addrs(&args.bootnodes)?;
+        validate_relay_addrs(&args.relay_addrs)?;
+        validate_protocol_versions(&args.protocol_versions)?;
 
         let local_peer_id = PeerId::from(args.local_keypair.public());
+        let retry = args.retry;
 
         let (cmd_tx, cmd_rx) = mpsc::channel(16);
         let (header_sub_tx, header_sub_rx) = watch::channel(None);
+        let (fraud_proof_tx, fraud_proof_rx) = watch::channel(None);
 
         let peer_tracker = Arc::new(PeerTracker::new());
         let peer_tracker_info_watcher = peer_tracker.info_watcher();
-
-        let mut worker = Worker::new(args, cmd_rx, header_sub_tx, peer_tracker)?;
+        let id_layout = args.shwap_id_layout;
+        let namespace_fetch_mode = args.namespace_fetch_mode;
+        let header_clock = HeaderClock::new(args.clock.clone(), args.max_clock_drift);
+        let bootnodes = args.bootnodes.clone();
+        #[cfg(feature = "gateway")]
+        let gateway = args.gateway.clone().map(Gateway::new);
+
+        let mut worker = Worker::new(
+            args,
+            custom,
+            cmd_rx,
+            header_sub_tx,
+            fraud_proof_tx,
+            peer_tracker,
+        )?;
 
         spawn(async move {
             worker.run().await;
@@ -193,8 +920,16 @@ where
         Ok(P2p {
             cmd_tx,
             header_sub_watcher: header_sub_rx,
+            fraud_proof_watcher: fraud_proof_rx,
             peer_tracker_info_watcher,
             local_peer_id,
+            bootnodes,
+            retry,
+            id_layout,
+            namespace_fetch_mode,
+            header_clock,
+            #[cfg(feature = "gateway")]
+            gateway,
             _store: PhantomData,
         })
     }
@@ -204,13 +939,22 @@ where
     pub fn mocked() -> (Self, crate::test_utils::MockP2pHandle) {
         let (cmd_tx, cmd_rx) = mpsc::channel(16);
         let (header_sub_tx, header_sub_rx) = watch::channel(None);
+        let (fraud_proof_tx, fraud_proof_rx) = watch::channel(None);
         let (peer_tracker_tx, peer_tracker_rx) = watch::channel(PeerTrackerInfo::default());
 
         let p2p = P2p {
             cmd_tx: cmd_tx.clone(),
             header_sub_watcher: header_sub_rx,
+            fraud_proof_watcher: fraud_proof_rx,
             peer_tracker_info_watcher: peer_tracker_rx,
             local_peer_id: PeerId::random(),
+            bootnodes: Vec::new(),
+            retry: RetryConfig::default(),
+            id_layout: IdLayout::default(),
+            namespace_fetch_mode: NamespaceFetchMode::default(),
+            header_clock: HeaderClock::default(),
+            #[cfg(feature = "gateway")]
+            gateway: None,
             _store: PhantomData,
         };
 
@@ -218,6 +962,7 @@ where
             cmd_tx,
             cmd_rx,
             header_sub_tx,
+            fraud_proof_tx,
             peer_tracker_tx,
         };
 
@@ -247,6 +992,11 @@ where
         self.header_sub_watcher.clone()
     }
 
+    /// Watcher for the valid [`BadEncodingFraudProof`] of the lowest height received on `fraud-sub`.
+    pub fn fraud_proof_watcher(&self) -> watch::Receiver<Option<BadEncodingFraudProof>> {
+        self.fraud_proof_watcher.clone()
+    }
+
     /// Watcher for the current [`PeerTrackerInfo`].
     pub fn peer_tracker_info_watcher(&self) -> watch::Receiver<PeerTrackerInfo> {
         self.peer_tracker_info_watcher.clone()
@@ -265,24 +1015,76 @@ where
         .await
     }
 
+    /// Publish the header on the `header-sub` topic, to the peers subscribed to it.
+    ///
+    /// The header is not validated before publishing. Peers reject invalid headers
+    /// and the ones not following their current head.
+    #[cfg(any(test, feature = "test-utils"))]
+    pub async fn publish_header(&self, header: ExtendedHeader) -> Result<()> {
+        let (tx, rx) = oneshot::channel();
+
+        self.send_command(P2pCmd::PublishHeader {
+            header: Box::new(header),
+            respond_to: tx,
+        })
+        .await?;
+
+        rx.await?
+    }
+
     /// Wait until the node is connected to any peer.
     pub async fn wait_connected(&self) -> Result<()> {
-        self.peer_tracker_info_watcher()
-            .wait_for(|info| info.num_connected_peers > 0)
-            .await
-            .map(drop)
-            .map_err(|_| P2pError::WorkerDied)
+        self.wait_for_peers(false).await
     }
 
     /// Wait until the node is connected to any trusted peer.
     pub async fn wait_connected_trusted(&self) -> Result<()> {
+        self.wait_for_peers(true).await
+    }
+
+    /// Wait until the node is connected to any peer, failing after the `timeout`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`P2pError::ConnectTimedOut`], listing the bootnodes, if no peer
+    /// connected in time.
+    pub async fn wait_connected_timeout(&self, timeout: Duration) -> Result<()> {
+        self.wait_for_peers_timeout(false, timeout).await
+    }
+
+    /// Wait until the node is connected to any trusted peer, failing after the `timeout`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`P2pError::ConnectTimedOut`], listing the bootnodes, if no trusted peer
+    /// connected in time.
+    pub async fn wait_connected_trusted_timeout(&self, timeout: Duration) -> Result<()> {
+        self.wait_for_peers_timeout(true, timeout).await
+    }
+
+    async fn wait_for_peers(&self, trusted: bool) -> Result<()> {
         self.peer_tracker_info_watcher()
-            .wait_for(|info| info.num_connected_trusted_peers > 0)
+            .wait_for(|info| {
+                if trusted {
+                    info.num_connected_trusted_peers > 0
+                } else {
+                    info.num_connected_peers > 0
+                }
+            })
             .await
             .map(drop)
             .map_err(|_| P2pError::WorkerDied)
     }
 
+    async fn wait_for_peers_timeout(&self, trusted: bool, duration: Duration) -> Result<()> {
+        timeout(duration, self.wait_for_peers(trusted))
+            .await
+            .map_err(|_| P2pError::ConnectTimedOut {
+                timeout: duration,
+                bootnodes: self.bootnodes.clone(),
+            })?
+    }
+
     /// Get current [`NetworkInfo`].
     pub async fn network_info(&self) -> Result<NetworkInfo> {
         let (tx, rx) = oneshot::channel();
@@ -294,11 +1096,37 @@ where
     }
 
     /// Send a request on the `header-ex` protocol.
+    ///
+    /// Failed request is retried according to the [`RetryConfig`].
     pub async fn header_ex_request(&self, request: HeaderRequest) -> Result<Vec<ExtendedHeader>> {
+        self.with_retries(|| async {
+            let (tx, rx) = oneshot::channel();
+
+            self.send_command(P2pCmd::HeaderExRequest {
+                request: request.clone(),
+                respond_to: tx,
+            })
+            .await?;
+
+            rx.await?
+        })
+        .await
+    }
+
+    /// Send the same request on the `header-ex` protocol to `quorum` different peers.
+    ///
+    /// The response is accepted only if all the peers responded with the same headers,
+    /// otherwise [`HeaderExError::QuorumNotReached`] is returned.
+    pub async fn header_ex_quorum_request(
+        &self,
+        request: HeaderRequest,
+        quorum: usize,
+    ) -> Result<Vec<ExtendedHeader>> {
         let (tx, rx) = oneshot::channel();
 
-        self.send_command(P2pCmd::HeaderExRequest {
+        self.send_command(P2pCmd::HeaderExQuorumRequest {
             request,
+            quorum,
             respond_to: tx,
         })
         .await?;
@@ -306,6 +1134,24 @@ where
         rx.await?
     }
 
+    /// Check that `quorum` different peers agree on the hash of the given header.
+    ///
+    /// Since the headers are hash-linked, agreement on a header implies agreement
+    /// on all the headers it was verified against.
+    pub async fn cross_check_header(&self, header: &ExtendedHeader, quorum: usize) -> Result<()> {
+        let request = HeaderRequest {
+            data: Some(header_request::Data::Origin(header.height().value())),
+            amount: 1,
+        };
+
+        let headers = self.header_ex_quorum_request(request, quorum).await?;
+
+        match headers.first() {
+            Some(agreed) if agreed.hash() == header.hash() => Ok(()),
+            _ => Err(HeaderExError::QuorumNotReached(quorum).into()),
+        }
+    }
+
     /// Request the head header on the `header-ex` protocol.
     pub async fn get_head_header(&self) -> Result<ExtendedHeader> {
         self.get_header_by_height(0).await
@@ -335,6 +1181,46 @@ where
         .ok_or(HeaderExError::HeaderNotFound.into())
     }
 
+    /// Request the header by height on the `header-ex` protocol and verify it against
+    /// the trusted one.
+    ///
+    /// A header above the trusted one must be signed by enough of its validators. A header
+    /// below it is verified through the hash chain, so all the headers between it and the
+    /// trusted one are requested too.
+    pub async fn get_verified_header_by_height(
+        &self,
+        trusted: &ExtendedHeader,
+        height: u64,
+    ) -> Result<ExtendedHeader> {
+        let trusted_height = trusted.height().value();
+
+        if height < trusted_height {
+            return self
+                .get_verified_headers_range_backward(trusted, trusted_height - height)
+                .await?
+                .into_iter()
+                .next()
+                .ok_or(HeaderExError::InvalidResponse.into());
+        }
+
+        let header = self.get_header_by_height(height).await?;
+
+        let verified = header.height().value() == height
+            && if height > trusted_height {
+                trusted
+                    .verify_with_clock(&header, self.header_clock.check())
+                    .is_ok()
+            } else {
+                header.hash() == trusted.hash()
+            };
+
+        if !verified {
+            return Err(HeaderExError::InvalidResponse.into());
+        }
+
+        Ok(header)
+    }
+
     /// Request the headers following the one given with the `header-ex` protocol.
     ///
     /// First header from the requested range will be verified against the provided one, then each subsequent is verified against the previous one.
@@ -347,83 +1233,715 @@ where
 
         let height = from.height().value() + 1;
 
-        let mut session = Session::new(height, amount, self.cmd_tx.clone())?;
+        let mut session = Session::new(height, amount, self.cmd_tx.clone(), self.retry)?;
         let headers = session.run().await?;
 
-        from.verify_adjacent_range(&headers)
-            .map_err(|_| HeaderExError::InvalidResponse)?;
+        let from = from.clone();
+        let clock = self.header_clock.check();
+        let headers = spawn_compute(move || {
+            from.verify_adjacent_range_with_clock(&headers, clock)
+                .map(|_| headers)
+        })
+        .await
+        .map_err(|_| P2pError::WorkerDied)?
+        .map_err(|_| HeaderExError::InvalidResponse)?;
 
         Ok(headers)
     }
 
-    /// Get the addresses where [`P2p`] listens on for incoming connections.
-    pub async fn listeners(&self) -> Result<Vec<Multiaddr>> {
-        let (tx, rx) = oneshot::channel();
+    /// Request the headers preceding the one given with the `header-ex` protocol.
+    ///
+    /// The requested range ends right below the provided header, which the last header of the range is verified against, then each preceding one is verified against the next one.
+    pub async fn get_verified_headers_range_backward(
+        &self,
+        to: &ExtendedHeader,
+        amount: u64,
+    ) -> Result<Vec<ExtendedHeader>> {
+        to.validate().map_err(|_| HeaderExError::InvalidRequest)?;
 
-        self.send_command(P2pCmd::Listeners { respond_to: tx })
-            .await?;
+        let height = to
+            .height()
+            .value()
+            .checked_sub(amount)
+            .filter(|height| *height > 0)
+            .ok_or(HeaderExError::InvalidRequest)?;
 
-        Ok(rx.await?)
+        let mut session = Session::new(height, amount, self.cmd_tx.clone(), self.retry)?;
+        let headers = session.run().await?;
+
+        let to = to.clone();
+        let clock = self.header_clock.check();
+        let headers = spawn_compute(move || {
+            if let (Some(first), Some(last)) = (headers.first(), headers.last()) {
+                first
+                    .verify_adjacent_range_with_clock(&headers[1..], clock)
+                    .and_then(|_| last.verify_adjacent_with_clock(&to, clock))?;
+            }
+            Ok::<_, celestia_types::Error>(headers)
+        })
+        .await
+        .map_err(|_| P2pError::WorkerDied)?
+        .map_err(|_| HeaderExError::InvalidResponse)?;
+
+        Ok(headers)
     }
 
-    /// Get the list of connected peers.
-    pub async fn connected_peers(&self) -> Result<Vec<PeerId>> {
-        let (tx, rx) = oneshot::channel();
+    /// Layout of the shwap identifiers used in the bitswap requests.
+    pub(crate) fn id_layout(&self) -> IdLayout {
+        self.id_layout
+    }
 
-        self.send_command(P2pCmd::ConnectedPeers { respond_to: tx })
+    /// Request a raw shwap container with the given [`Cid`] on the bitswap protocol.
+    ///
+    /// Failed request is retried according to the [`RetryConfig`]. If the HTTP gateway
+    /// is configured and the container isn't delivered over bitswap within its
+    /// `p2p_timeout`, it is requested from the gateway instead.
+    ///
+    /// Returned container is not verified, the first one delivered is accepted.
+    pub async fn get_shwap_cid<const SIZE: usize>(&self, cid: CidGeneric<SIZE>) -> Result<Vec<u8>> {
+        let cid = bitswap::convert_cid(&cid)?;
+        let ((), data) = self
+            .fetch_shwap_cid(cid, &|_: Vec<u8>| async { Ok(()) })
             .await?;
 
-        Ok(rx.await?)
+        Ok(data)
     }
 
-    /// Alter the trust status for a given peer.
-    pub async fn set_peer_trust(&self, peer_id: PeerId, is_trusted: bool) -> Result<()> {
-        self.send_command(P2pCmd::SetPeerTrust {
-            peer_id,
-            is_trusted,
-        })
+    /// Request a shwap container as in [`P2p::get_shwap_cid`], until one passing the
+    /// verification is delivered, and store it.
+    async fn get_verified_shwap_cid<T, F, Fut>(&self, cid: Cid, verify: &F) -> Result<T>
+    where
+        F: Fn(Vec<u8>) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let (verified, data) = self.fetch_shwap_cid(cid, verify).await?;
+        self.put_shwap_block(cid, data).await;
+
+        Ok(verified)
+    }
+
+    async fn fetch_shwap_cid<T, F, Fut>(&self, cid: Cid, verify: &F) -> Result<(T, Vec<u8>)>
+    where
+        F: Fn(Vec<u8>) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        #[cfg(feature = "gateway")]
+        if let Some(gateway) = &self.gateway {
+            return match with_timeout(gateway.p2p_timeout(), self.get_bitswap_cid(cid, verify))
+                .await
+            {
+                Err(e @ (P2pError::RequestTimedOut | P2pError::NoConnectedPeers)) => {
+                    debug!("Bitswap retrieval of {cid} failed: {e}. Falling back to the gateway.");
+                    let data = gateway.get(&cid).await?;
+                    Ok((verify(data.clone()).await?, data))
+                }
+                res => res,
+            };
+        }
+
+        self.get_bitswap_cid(cid, verify).await
+    }
+
+    async fn get_bitswap_cid<T, F, Fut>(&self, cid: Cid, verify: &F) -> Result<(T, Vec<u8>)>
+    where
+        F: Fn(Vec<u8>) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        self.with_retries(|| async {
+            let (tx, rx) = bitswap::block_channel();
+
+            self.send_command(P2pCmd::GetShwapCid {
+                cid,
+                respond_to: tx,
+            })
+            .await?;
+
+            timeout(
+                GET_SHWAP_CID_TIMEOUT,
+                self.receive_verified(cid, rx, verify),
+            )
+            .await
+            .map_err(|_| P2pError::RequestTimedOut)?
+        })
+        .await
+    }
+
+    /// Verify the blocks delivered for the want until one of them is valid.
+    ///
+    /// Each verdict is reported back to the bitswap, which keeps the want open and
+    /// penalizes the peers of the invalid blocks.
+    async fn receive_verified<T, F, Fut>(
+        &self,
+        cid: Cid,
+        mut rx: BlockReceiver,
+        verify: &F,
+    ) -> Result<(T, Vec<u8>)>
+    where
+        F: Fn(Vec<u8>) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        loop {
+            let block = rx.recv().await.ok_or(BitswapError::BlockNotFound)??;
+
+            match verify(block.data.clone()).await {
+                Ok(verified) => {
+                    let _ = self
+                        .send_command(P2pCmd::AcceptShwapBlock {
+                            cid,
+                            peer: block.peer,
+                        })
+                        .await;
+                    return Ok((verified, block.data));
+                }
+                Err(e) => {
+                    debug!("Invalid block {cid} from {}: {e}", block.peer);
+                    self.send_command(P2pCmd::RejectShwapBlock {
+                        cid,
+                        peer: block.peer,
+                    })
+                    .await?;
+                }
+            }
+        }
+    }
+
+    /// Run the request until it completes or the `timeout` elapses.
+    ///
+    /// Any of the [`P2p`] requests can be limited this way. Requests are cancelled
+    /// by dropping them, which stops their retries and withdraws the pending bitswap
+    /// wants from the peers.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`P2pError::RequestTimedOut`] if the request didn't complete in time.
+    pub async fn with_timeout<F, T>(&self, timeout: Duration, request: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        with_timeout(timeout, request).await
+    }
+
+    /// Run the request until it completes or the `token` gets cancelled.
+    ///
+    /// See [`P2p::with_timeout`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`P2pError::Cancelled`] if the token got cancelled first.
+    pub async fn with_cancellation<F, T>(&self, token: &CancellationToken, request: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        with_cancellation(token, request).await
+    }
+
+    /// Run the request until it succeeds, fails with an error that isn't retryable
+    /// or runs out of the attempts.
+    async fn with_retries<T, F, Fut>(&self, mut request: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 1;
+
+        loop {
+            match request().await {
+                Err(e) if e.is_retryable() && attempt < self.retry.max_attempts => {
+                    let delay = self.retry.delay(attempt);
+                    debug!("Request failed: {e}. Retrying in {delay:?}.");
+                    sleep(delay).await;
+                    attempt += 1;
+                }
+                res => return res,
+            }
+        }
+    }
+
+    /// Request a [`Sample`] at the given coordinates of the [`ExtendedDataSquare`]
+    /// on the bitswap protocol.
+    ///
+    /// Returned [`Sample`] is verified against the [`DataAvailabilityHeader`] of the given header.
+    ///
+    /// [`ExtendedDataSquare`]: celestia_types::ExtendedDataSquare
+    /// [`DataAvailabilityHeader`]: celestia_types::DataAvailabilityHeader
+    pub async fn get_sample(
+        &self,
+        row_index: u16,
+        column_index: u16,
+        header: &ExtendedHeader,
+    ) -> Result<Sample> {
+        let cid = self.sample_cid(row_index, column_index, header)?;
+
+        let started = Instant::now();
+        let res = self
+            .get_verified_shwap_cid(cid, &|data| self.verify_sample(data, header))
+            .await;
+
+        record_sampling(&res, started);
+        res
+    }
+
+    /// Request the [`Sample`]s at the given coordinates of the [`ExtendedDataSquare`]
+    /// on the bitswap protocol.
+    ///
+    /// Wants of all the samples are sent together, in a single message to each of the
+    /// peers, once the returned future completes. It resolves to the futures of the
+    /// samples, in the order of the `coordinates`, each verified as in
+    /// [`P2p::get_sample`]. Samples which failed to be delivered in the batch are
+    /// then requested separately, with the retries and the gateway fallback of
+    /// [`P2p::get_shwap_cid`].
+    ///
+    /// [`ExtendedDataSquare`]: celestia_types::ExtendedDataSquare
+    pub async fn get_samples<'a>(
+        &'a self,
+        coordinates: &[(u16, u16)],
+        header: &'a ExtendedHeader,
+    ) -> Result<Vec<impl Future<Output = Result<Sample>> + 'a>> {
+        let mut wants = Vec::with_capacity(coordinates.len());
+        let mut pending = Vec::with_capacity(coordinates.len());
+
+        for &(row_index, column_index) in coordinates {
+            match self.sample_cid(row_index, column_index, header) {
+                Ok(cid) => {
+                    let (tx, rx) = bitswap::block_channel();
+                    wants.push((cid, tx));
+                    pending.push(Ok((cid, rx)));
+                }
+                Err(e) => pending.push(Err(e)),
+            }
+        }
+
+        self.send_command(P2pCmd::GetShwapCids { wants }).await?;
+
+        let batch_timeout = self.batch_timeout();
+
+        Ok(pending
+            .into_iter()
+            .map(move |pending| async move {
+                let (cid, rx) = pending?;
+                let verify = |data| self.verify_sample(data, header);
+
+                let started = Instant::now();
+                let res = async {
+                    let (sample, data) =
+                        match timeout(batch_timeout, self.receive_verified(cid, rx, &verify))
+                            .await
+                            .map_err(|_| P2pError::RequestTimedOut)
+                            .and_then(|res| res)
+                        {
+                            Ok(res) => res,
+                            Err(e) if e.is_retryable() || self.has_gateway() => {
+                                debug!("Batched retrieval of {cid} failed: {e}. Retrying.");
+                                self.fetch_shwap_cid(cid, &verify).await?
+                            }
+                            Err(e) => return Err(e),
+                        };
+
+                    self.put_shwap_block(cid, data).await;
+                    Ok(sample)
+                }
+                .await;
+
+                record_sampling(&res, started);
+                res
+            })
+            .collect())
+    }
+
+    fn sample_cid(
+        &self,
+        row_index: u16,
+        column_index: u16,
+        header: &ExtendedHeader,
+    ) -> Result<Cid> {
+        let square_len = header.dah.square_len();
+        check_share_coordinates(row_index, column_index, square_len)?;
+        let index = usize::from(row_index) * square_len + usize::from(column_index);
+
+        let sample_id = SampleId::new(index, square_len, header.height().value())
+            .map_err(P2pError::InvalidShwap)?;
+
+        Ok(bitswap::convert_cid(&sample_id.to_cid(self.id_layout))?)
+    }
+
+    async fn verify_sample(&self, data: Vec<u8>, header: &ExtendedHeader) -> Result<Sample> {
+        let raw = RawSample::decode(&data[..]).map_err(|e| P2pError::InvalidShwap(e.into()))?;
+        let sample = Sample::from_raw(raw, self.id_layout).map_err(P2pError::InvalidShwap)?;
+
+        // Hashing the proof is computation heavy, so it's done off the executor
+        let dah = header.dah.clone();
+        spawn_compute(move || sample.validate(&dah).map(|_| sample))
+            .await
+            .map_err(|_| P2pError::WorkerDied)?
+            .map_err(P2pError::InvalidShwap)
+    }
+
+    /// Time the batched requests wait for the containers before they are retried
+    /// separately.
+    fn batch_timeout(&self) -> Duration {
+        #[cfg(feature = "gateway")]
+        if let Some(gateway) = &self.gateway {
+            return gateway.p2p_timeout().min(GET_SHWAP_CID_TIMEOUT);
+        }
+
+        GET_SHWAP_CID_TIMEOUT
+    }
+
+    #[cfg(feature = "gateway")]
+    fn has_gateway(&self) -> bool {
+        self.gateway.is_some()
+    }
+
+    #[cfg(not(feature = "gateway"))]
+    fn has_gateway(&self) -> bool {
+        false
+    }
+
+    /// Request a [`Row`] with the given index of the [`ExtendedDataSquare`] on the
+    /// bitswap protocol.
+    ///
+    /// Returned [`Row`] is verified against the [`DataAvailabilityHeader`] of the given header.
+    ///
+    /// [`ExtendedDataSquare`]: celestia_types::ExtendedDataSquare
+    /// [`DataAvailabilityHeader`]: celestia_types::DataAvailabilityHeader
+    pub async fn get_row(&self, row_index: u16, header: &ExtendedHeader) -> Result<Row> {
+        let row_id =
+            RowId::new(row_index, header.height().value()).map_err(P2pError::InvalidShwap)?;
+        let cid = bitswap::convert_cid(&row_id.to_cid(self.id_layout))?;
+
+        self.get_verified_shwap_cid(cid, &|data| self.verify_row(row_id, data, header))
+            .await
+    }
+
+    async fn verify_row(
+        &self,
+        row_id: RowId,
+        data: Vec<u8>,
+        header: &ExtendedHeader,
+    ) -> Result<Row> {
+        let raw = RawRow::decode(&data[..]).map_err(|e| P2pError::InvalidShwap(e.into()))?;
+        let row = Row::from_raw(raw, self.id_layout).map_err(P2pError::InvalidShwap)?;
+
+        if row.row_id != row_id {
+            let e = ValidationError::Other("received row with a different id".into());
+            return Err(P2pError::InvalidShwap(e.into()));
+        }
+
+        let dah = header.dah.clone();
+        spawn_compute(move || row.validate(&dah).map(|_| row))
+            .await
+            .map_err(|_| P2pError::WorkerDied)?
+            .map_err(P2pError::InvalidShwap)
+    }
+
+    /// Request multiple [`Row`]s of the block on the bitswap protocol.
+    ///
+    /// At most `concurrency` rows are requested at the same time. Rows are returned
+    /// in the order of `row_indexes`, each verified as in [`P2p::get_row`]. Fails on
+    /// the first row that couldn't be retrieved.
+    pub async fn get_rows(
+        &self,
+        row_indexes: &[u16],
+        concurrency: usize,
+        header: &ExtendedHeader,
+    ) -> Result<Vec<Row>> {
+        stream::iter(row_indexes.to_vec())
+            .map(|row_index| self.get_row(row_index, header))
+            .buffered(concurrency.max(1))
+            .try_collect()
+            .await
+    }
+
+    /// Request all the [`NamespacedData`] of the given [`Namespace`] in a block on the
+    /// bitswap protocol.
+    ///
+    /// Only the rows which may contain the namespace according to the
+    /// [`DataAvailabilityHeader`] are requested, a few of them at the same time, as
+    /// configured with the [`NamespaceFetchMode`]. Each returned [`NamespacedData`] is
+    /// verified against it.
+    ///
+    /// Rows in the lower half of the extended square hold only the parity shares, whose
+    /// roots cover just the parity namespace, so they are never requested for the data
+    /// of any other namespace.
+    ///
+    /// [`DataAvailabilityHeader`]: celestia_types::DataAvailabilityHeader
+    pub async fn get_namespaced_data(
+        &self,
+        namespace: Namespace,
+        header: &ExtendedHeader,
+    ) -> Result<Vec<NamespacedData>> {
+        let row_indexes = (0u16..header.dah.square_len() as u16).filter(|&index| {
+            header
+                .dah
+                .row_root(index.into())
+                .map(|root| root.contains::<NamespacedSha2Hasher>(*namespace))
+                .unwrap_or(false)
+        });
+
+        stream::iter(row_indexes)
+            .map(|row_index| async move {
+                match self.namespace_fetch_mode {
+                    NamespaceFetchMode::NamespaceShares => {
+                        self.get_namespaced_data_in_row(namespace, row_index, header)
+                            .await
+                    }
+                    NamespaceFetchMode::FullRows => {
+                        self.get_namespaced_data_from_row(namespace, row_index, header)
+                            .await
+                    }
+                }
+            })
+            .buffered(NAMESPACED_DATA_CONCURRENCY)
+            .try_collect()
+            .await
+    }
+
+    async fn get_namespaced_data_from_row(
+        &self,
+        namespace: Namespace,
+        row_index: u16,
+        header: &ExtendedHeader,
+    ) -> Result<NamespacedData> {
+        let row = self.get_row(row_index, header).await?;
+
+        // Building the proof hashes the whole row, so it's done off the executor
+        spawn_compute(move || row.namespaced_data(namespace))
+            .await
+            .map_err(|_| P2pError::WorkerDied)?
+            .map_err(P2pError::InvalidShwap)
+    }
+
+    async fn get_namespaced_data_in_row(
+        &self,
+        namespace: Namespace,
+        row_index: u16,
+        header: &ExtendedHeader,
+    ) -> Result<NamespacedData> {
+        let id = NamespacedDataId::new(namespace, row_index, header.height().value())
+            .map_err(P2pError::InvalidShwap)?;
+        let cid = bitswap::convert_cid(&id.to_cid(self.id_layout))?;
+
+        self.get_verified_shwap_cid(cid, &|data| self.verify_namespaced_data(data, header))
+            .await
+    }
+
+    async fn verify_namespaced_data(
+        &self,
+        data: Vec<u8>,
+        header: &ExtendedHeader,
+    ) -> Result<NamespacedData> {
+        let raw =
+            RawNamespacedData::decode(&data[..]).map_err(|e| P2pError::InvalidShwap(e.into()))?;
+        let namespaced_data =
+            NamespacedData::from_raw(raw, self.id_layout).map_err(P2pError::InvalidShwap)?;
+
+        let dah = header.dah.clone();
+        spawn_compute(move || {
+            namespaced_data.validate(&dah)?;
+            Ok(namespaced_data)
+        })
+        .await
+        .map_err(|_| P2pError::WorkerDied)?
+        .map_err(P2pError::InvalidShwap)
+    }
+
+    /// Store the validated shwap container, so it can be provided to the other peers.
+    async fn put_shwap_block(&self, cid: Cid, data: Vec<u8>) {
+        // Failing to store the container doesn't affect its retrieval
+        let _ = self.send_command(P2pCmd::PutShwapBlock { cid, data }).await;
+    }
+
+    /// Run the closure on the [`CustomBehaviour`] running in the swarm, returning its result.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`P2pError::CustomBehaviourMismatch`] if the [`P2p`] was started without
+    /// a [`CustomBehaviour`] or with one of a different type.
+    pub async fn with_custom_behaviour<B, F, R>(&self, f: F) -> Result<R>
+    where
+        B: NetworkBehaviour,
+        F: FnOnce(&mut B) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+
+        let f = CustomBehaviourFn(Box::new(move |behaviour: Option<&mut dyn Any>| {
+            let res = behaviour
+                .and_then(|behaviour| behaviour.downcast_mut::<B>())
+                .map(f)
+                .ok_or(P2pError::CustomBehaviourMismatch);
+            tx.maybe_send(res);
+        }));
+
+        self.send_command(P2pCmd::WithCustomBehaviour { f }).await?;
+
+        rx.await?
+    }
+
+    /// Get the addresses where [`P2p`] listens on for incoming connections.
+    pub async fn listeners(&self) -> Result<Vec<Multiaddr>> {
+        let (tx, rx) = oneshot::channel();
+
+        self.send_command(P2pCmd::Listeners { respond_to: tx })
+            .await?;
+
+        Ok(rx.await?)
+    }
+
+    /// Get the list of connected peers.
+    pub async fn connected_peers(&self) -> Result<Vec<PeerId>> {
+        let (tx, rx) = oneshot::channel();
+
+        self.send_command(P2pCmd::ConnectedPeers { respond_to: tx })
+            .await?;
+
+        Ok(rx.await?)
+    }
+
+    /// Get the diagnostics of the connected peers.
+    pub async fn peer_info(&self) -> Result<Vec<PeerDiagnostics>> {
+        let (tx, rx) = oneshot::channel();
+
+        self.send_command(P2pCmd::PeerDiagnostics { respond_to: tx })
+            .await?;
+
+        Ok(rx.await?)
+    }
+
+    /// Alter the trust status for a given peer.
+    pub async fn set_peer_trust(&self, peer_id: PeerId, is_trusted: bool) -> Result<()> {
+        self.send_command(P2pCmd::SetPeerTrust {
+            peer_id,
+            is_trusted,
+        })
         .await
     }
 }
 
 /// Our network behaviour.
 #[derive(NetworkBehaviour)]
-struct Behaviour<S>
+struct Behaviour<S, B>
 where
     S: Store + 'static,
+    B: NetworkBehaviour,
 {
+    // Limits go first, so that the connections get denied before the other
+    // behaviours accept them
+    connection_limits: connection_limits::Behaviour,
+    ip_limits: IpLimitsBehaviour,
+    memory_limits: Toggle<MemoryLimitsBehaviour>,
     autonat: autonat::Behaviour,
+    relay_client: relay::client::Behaviour,
+    dcutr: dcutr::Behaviour,
     ping: ping::Behaviour,
     identify: identify::Behaviour,
     header_ex: HeaderExBehaviour<S>,
     gossipsub: gossipsub::Behaviour,
     kademlia: kad::Behaviour<kad::store::MemoryStore>,
+    bitswap: BitswapBehaviour,
+    custom: Toggle<B>,
 }
 
-struct Worker<S>
+struct Worker<S, B>
 where
     S: Store + 'static,
+    B: NetworkBehaviour,
 {
-    swarm: Swarm<Behaviour<S>>,
+    swarm: Swarm<Behaviour<S, B>>,
     header_sub_topic_hash: TopicHash,
+    bad_encoding_fraud_sub_topic_hash: TopicHash,
     cmd_rx: mpsc::Receiver<P2pCmd>,
     peer_tracker: Arc<PeerTracker>,
     header_sub_watcher: watch::Sender<Option<ExtendedHeader>>,
+    head_validators: Vec<Arc<dyn HeadValidator>>,
+    header_clock: HeaderClock,
+    fraud_proof_watcher: watch::Sender<Option<BadEncodingFraudProof>>,
+    store: Arc<S>,
+    bitswap_client: BitswapClient,
+    event_pub: EventPublisher,
+    provide_blocks: bool,
+    provided_blocks: VecDeque<Cid>,
+    #[cfg(not(target_arch = "wasm32"))]
+    eds_store: Option<EdsStore>,
+    #[cfg(not(target_arch = "wasm32"))]
+    eds_wants: EdsWantsLimiter,
+    store_results_tx: mpsc::Sender<StoreResult>,
+    store_results_rx: mpsc::Receiver<StoreResult>,
+    custom_events_tx: Option<mpsc::Sender<B::ToSwarm>>,
+    bootnodes: Vec<Multiaddr>,
+    address_book: AddressBook,
+    kademlia_protocol: StreamProtocol,
+    header_ex_protocols: Vec<StreamProtocol>,
+    shwap_protocols: Vec<(StreamProtocol, IdLayout)>,
+}
+
+/// Results of the blockstore operations, run outside of the [`Worker`] loop.
+#[derive(Debug)]
+enum StoreResult {
+    /// Response to the wants of the peer.
+    BitswapResponse {
+        peer: PeerId,
+        response: Option<RawBitswapMessage>,
+        from_eds: bool,
+    },
+    /// Shwap container which is stored and should be announced.
+    BlockStored(Cid),
+}
+
+/// Limits of the wants answered from the squares of the [`EdsStore`].
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Default)]
+struct EdsWantsLimiter {
+    in_progress: HashMap<PeerId, usize>,
+    total: usize,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl EdsWantsLimiter {
+    /// Start answering the wants of the peer, if none of the limits is reached.
+    fn try_start(&mut self, peer: PeerId) -> bool {
+        let of_peer = self.in_progress.get(&peer).copied().unwrap_or_default();
+
+        if self.total >= MAX_EDS_WANTS_IN_PROGRESS || of_peer >= MAX_EDS_WANTS_PER_PEER {
+            return false;
+        }
+
+        self.in_progress.insert(peer, of_peer + 1);
+        self.total += 1;
+        true
+    }
+
+    fn finish(&mut self, peer: &PeerId) {
+        if let Some(of_peer) = self.in_progress.get_mut(peer) {
+            *of_peer -= 1;
+            self.total -= 1;
+
+            if *of_peer == 0 {
+                self.in_progress.remove(peer);
+            }
+        }
+    }
 }
 
-impl<S> Worker<S>
+impl<S, B> Worker<S, B>
 where
-    S: Store,
+    S: Store + 'static,
+    B: NetworkBehaviour + Send,
+    B::ToSwarm: Send,
 {
     fn new(
         args: P2pArgs<S>,
+        custom: Option<CustomBehaviour<B>>,
         cmd_rx: mpsc::Receiver<P2pCmd>,
         header_sub_watcher: watch::Sender<Option<ExtendedHeader>>,
+        fraud_proof_watcher: watch::Sender<Option<BadEncodingFraudProof>>,
         peer_tracker: Arc<PeerTracker>,
     ) -> Result<Self, P2pError> {
         let local_peer_id = PeerId::from(args.local_keypair.public());
+        let (store_results_tx, store_results_rx) = mpsc::channel(32);
 
         let autonat = autonat::Behaviour::new(local_peer_id, autonat::Config::default());
+        let dcutr = dcutr::Behaviour::new(local_peer_id);
         let ping = ping::Behaviour::new(ping::Config::default());
 
         let identify = identify::Behaviour::new(identify::Config::new(
@@ -432,45 +1950,98 @@ where
         ));
 
         let header_sub_topic = gossipsub_ident_topic(&args.network_id, "/header-sub/v0.0.1");
-        let gossipsub = init_gossipsub(&args, [&header_sub_topic])?;
+        let bad_encoding_fraud_sub_topic =
+            fraud_sub_ident_topic(&args.network_id, BadEncodingFraudProof::TYPE);
+        let gossipsub = init_gossipsub(&args, [&header_sub_topic, &bad_encoding_fraud_sub_topic])?;
 
         let kademlia = init_kademlia(&args)?;
 
+        let header_ex_protocols = args.protocol_versions.header_ex_protocols(&args.network_id);
         let header_ex = HeaderExBehaviour::new(HeaderExConfig {
-            network_id: &args.network_id,
+            protocols: header_ex_protocols.clone(),
             peer_tracker: peer_tracker.clone(),
             header_store: args.store.clone(),
         });
 
-        let behaviour = Behaviour {
-            autonat,
-            ping,
-            identify,
-            gossipsub,
-            header_ex,
-            kademlia,
+        let shwap_protocols = args.protocol_versions.shwap_protocols(&args.network_id);
+        let bitswap = bitswap::new_behaviour(shwap_protocols.iter().map(|(p, _)| p.clone()));
+
+        let (custom, custom_events_tx) = match custom {
+            Some(custom) => (Some(custom.behaviour), Some(custom.events_tx)),
+            None => (None, None),
         };
 
-        let mut swarm = new_swarm(args.local_keypair, behaviour)?;
+        let connection_limits =
+            connection_limits::Behaviour::new(args.resource_limits.connection_limits());
+        let ip_limits = IpLimitsBehaviour::new(args.resource_limits.max_incoming_per_ip);
+        let memory_limits = args.resource_limits.memory_limits();
+
+        let store = args.store.clone();
+        let mut swarm = new_swarm(args.local_keypair, args.transports, |relay_client| {
+            Behaviour {
+                connection_limits,
+                ip_limits,
+                memory_limits: memory_limits.into(),
+                autonat,
+                relay_client,
+                dcutr,
+                ping,
+                identify,
+                gossipsub,
+                header_ex,
+                kademlia,
+                bitswap,
+                custom: custom.into(),
+            }
+        })?;
 
         for addr in args.listen_on {
             swarm.listen_on(addr)?;
         }
 
-        for addr in args.bootnodes {
+        for addr in args.relay_addrs {
+            // Listening on a circuit address makes the relay client reserve a slot on the relay
+            swarm.listen_on(addr.with(Protocol::P2pCircuit))?;
+        }
+
+        for addr in &args.bootnodes {
             // Bootstrap peers are always trusted
             if let Some(peer_id) = addr.peer_id() {
                 peer_tracker.set_trusted(peer_id, true);
             }
-            swarm.dial(addr)?;
         }
 
         Ok(Worker {
             cmd_rx,
             swarm,
             header_sub_topic_hash: header_sub_topic.hash(),
-            peer_tracker,
+            bad_encoding_fraud_sub_topic_hash: bad_encoding_fraud_sub_topic.hash(),
             header_sub_watcher,
+            head_validators: args.head_validators,
+            header_clock: HeaderClock::new(args.clock, args.max_clock_drift),
+            fraud_proof_watcher,
+            store,
+            bitswap_client: BitswapClient::new(
+                peer_tracker.clone(),
+                args.shwap_id_layout,
+                shwap_protocols.clone(),
+            ),
+            peer_tracker,
+            event_pub: args.event_pub,
+            provide_blocks: args.provide_blocks,
+            provided_blocks: VecDeque::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            eds_store: args.eds_store,
+            #[cfg(not(target_arch = "wasm32"))]
+            eds_wants: EdsWantsLimiter::default(),
+            store_results_tx,
+            store_results_rx,
+            custom_events_tx,
+            bootnodes: args.bootnodes,
+            address_book: AddressBook::default(),
+            kademlia_protocol: celestia_protocol_id(&args.network_id, "/kad/1.0.0"),
+            header_ex_protocols,
+            shwap_protocols,
         })
     }
 
@@ -478,6 +2049,26 @@ where
         let mut report_interval = Interval::new(Duration::from_secs(60)).await;
         let mut kademlia_interval = Interval::new(Duration::from_secs(30)).await;
         let mut kademlia_last_bootstrap = Instant::now();
+        let mut banned_peers_interval = Interval::new(BANNED_PEERS_CHECK_PERIOD).await;
+        let mut stale_peer_addrs_interval = Interval::new(STALE_PEER_ADDRS_CHECK_PERIOD).await;
+        let mut abandoned_requests_interval = Interval::new(ABANDONED_REQUESTS_CHECK_PERIOD).await;
+
+        // Previously seen peers are dialed first, so that a cold start doesn't
+        // depend entirely on the bootnodes being up
+        self.dial_known_peers().await;
+        self.dial_bootnodes();
+
+        // Kademlia doesn't persist the provider records, so the containers stored
+        // before the restart need to be announced again
+        if self.provide_blocks {
+            let store = self.store.clone();
+            let results_tx = self.store_results_tx.clone();
+            spawn(async move {
+                if let Err(e) = reannounce_stored_blocks(&*store, results_tx).await {
+                    warn!("Failed to announce the stored shwap containers: {e}");
+                }
+            });
+        }
 
         // Initiate discovery
         let _ = self.swarm.behaviour_mut().kademlia.bootstrap();
@@ -496,29 +2087,64 @@ where
                         kademlia_last_bootstrap = Instant::now();
                     }
                 }
+                _ = banned_peers_interval.tick() => {
+                    self.disconnect_banned_peers();
+                    self.persist_peer_addrs().await;
+                }
+                _ = stale_peer_addrs_interval.tick() => {
+                    self.address_book.evict_stale(Time::now());
+                    self.persist_peer_addrs().await;
+                }
+                _ = abandoned_requests_interval.tick() => {
+                    let bitswap = &mut self.swarm.behaviour_mut().bitswap;
+                    self.bitswap_client.remove_abandoned(bitswap);
+                }
                 ev = self.swarm.select_next_some() => {
                     if let Err(e) = self.on_swarm_event(ev).await {
                         warn!("Failure while handling swarm event: {e}");
                     }
+                    // Changes of the known peers are persisted right away to survive a crash
+                    self.persist_peer_addrs().await;
                 },
                 Some(cmd) = self.cmd_rx.recv() => {
                     if let Err(e) = self.on_cmd(cmd).await {
                         warn!("Failure while handling command. (error: {e})");
                     }
                 }
+                Some(result) = self.store_results_rx.recv() => {
+                    self.on_store_result(result);
+                }
             }
         }
     }
 
-    async fn on_swarm_event(&mut self, ev: SwarmEvent<BehaviourEvent<S>>) -> Result<()> {
+    async fn on_swarm_event(&mut self, ev: SwarmEvent<BehaviourEvent<S, B>>) -> Result<()> {
         match ev {
             SwarmEvent::Behaviour(ev) => match ev {
                 BehaviourEvent::Identify(ev) => self.on_identify_event(ev).await?,
                 BehaviourEvent::Gossipsub(ev) => self.on_gossip_sub_event(ev).await,
                 BehaviourEvent::Kademlia(ev) => self.on_kademlia_event(ev).await?,
-                BehaviourEvent::Autonat(_)
-                | BehaviourEvent::Ping(_)
-                | BehaviourEvent::HeaderEx(_) => {}
+                BehaviourEvent::Bitswap(ev) => {
+                    if let BitswapEvent::Message {
+                        peer,
+                        message: BitswapMessage::Request { request, .. },
+                    } = &ev
+                    {
+                        self.on_bitswap_wants(*peer, request);
+                    }
+
+                    let bitswap = &mut self.swarm.behaviour_mut().bitswap;
+                    self.bitswap_client.on_event(bitswap, ev);
+                }
+                BehaviourEvent::Autonat(ev) => self.on_autonat_event(ev),
+                BehaviourEvent::RelayClient(ev) => self.on_relay_client_event(ev),
+                BehaviourEvent::Dcutr(ev) => self.on_dcutr_event(ev),
+                BehaviourEvent::Custom(ev) => self.on_custom_event(ev),
+                BehaviourEvent::Ping(ev) => self.on_ping_event(ev),
+                BehaviourEvent::HeaderEx(_)
+                | BehaviourEvent::ConnectionLimits(_)
+                | BehaviourEvent::IpLimits(_)
+                | BehaviourEvent::MemoryLimits(_) => {}
             },
             SwarmEvent::ConnectionEstablished {
                 peer_id,
@@ -535,6 +2161,16 @@ where
             } => {
                 self.on_peer_disconnected(peer_id, connection_id);
             }
+            SwarmEvent::OutgoingConnectionError {
+                peer_id: Some(peer_id),
+                error: DialError::Transport(errors),
+                ..
+            } => {
+                // Addresses that can no longer be dialed are evicted from the book
+                for (addr, _) in errors {
+                    self.address_book.remove_addr(peer_id, &addr);
+                }
+            }
             _ => {}
         }
 
@@ -555,6 +2191,16 @@ where
                     .header_ex
                     .send_request(request, respond_to);
             }
+            P2pCmd::HeaderExQuorumRequest {
+                request,
+                quorum,
+                respond_to,
+            } => {
+                self.swarm
+                    .behaviour_mut()
+                    .header_ex
+                    .send_quorum_request(request, quorum, respond_to);
+            }
             P2pCmd::Listeners { respond_to } => {
                 let local_peer_id = self.swarm.local_peer_id().to_owned();
                 let listeners = self
@@ -574,9 +2220,17 @@ where
             P2pCmd::ConnectedPeers { respond_to } => {
                 respond_to.maybe_send(self.peer_tracker.connected_peers());
             }
+            P2pCmd::PeerDiagnostics { respond_to } => {
+                respond_to.maybe_send(self.peer_tracker.connected_peers_diagnostics());
+            }
             P2pCmd::InitHeaderSub { head } => {
                 self.on_init_header_sub(*head);
             }
+            #[cfg(any(test, feature = "test-utils"))]
+            P2pCmd::PublishHeader { header, respond_to } => {
+                let res = self.publish_header(*header);
+                respond_to.maybe_send(res);
+            }
             P2pCmd::SetPeerTrust {
                 peer_id,
                 is_trusted,
@@ -585,63 +2239,270 @@ where
                     self.peer_tracker.set_trusted(peer_id, is_trusted);
                 }
             }
+            P2pCmd::GetShwapCid { cid, respond_to } => {
+                let bitswap = &mut self.swarm.behaviour_mut().bitswap;
+                self.bitswap_client.remove_abandoned(bitswap);
+
+                let peers = self.peer_tracker.best_n_peers(bitswap::MAX_PEERS);
+                self.bitswap_client.get(bitswap, cid, peers, respond_to);
+            }
+            P2pCmd::GetShwapCids { wants } => {
+                let bitswap = &mut self.swarm.behaviour_mut().bitswap;
+                self.bitswap_client.remove_abandoned(bitswap);
+
+                let peers = self.peer_tracker.best_n_peers(bitswap::MAX_PEERS);
+                self.bitswap_client.get_many(bitswap, wants, peers);
+            }
+            P2pCmd::AcceptShwapBlock { cid, peer } => {
+                let bitswap = &mut self.swarm.behaviour_mut().bitswap;
+                self.bitswap_client.accept(bitswap, &cid, peer);
+            }
+            P2pCmd::RejectShwapBlock { cid, peer } => {
+                let bitswap = &mut self.swarm.behaviour_mut().bitswap;
+                self.bitswap_client.reject(bitswap, &cid, peer);
+            }
+            P2pCmd::WithCustomBehaviour { f } => {
+                let custom = self.swarm.behaviour_mut().custom.as_mut();
+                (f.0)(custom.map(|behaviour| behaviour as &mut dyn Any));
+            }
+            P2pCmd::PutShwapBlock { cid, data } => {
+                self.on_put_shwap_block(cid, data);
+            }
         }
 
         Ok(())
     }
 
-    #[instrument(skip_all)]
-    fn report(&mut self) {
-        let tracker_info = self.peer_tracker.info();
+    #[instrument(level = "trace", skip(self, data))]
+    fn on_put_shwap_block(&mut self, cid: Cid, data: Vec<u8>) {
+        let store = self.store.clone();
+        let results_tx = self.store_results_tx.clone();
+        let provide_blocks = self.provide_blocks;
 
-        info!(
-            "peers: {}, trusted peers: {}",
-            tracker_info.num_connected_peers, tracker_info.num_connected_trusted_peers,
-        );
+        spawn(async move {
+            match store.put_keyed(&cid, &data).await {
+                Ok(()) => {}
+                // Already stored and provided
+                Err(BlockstoreError::CidExists) => return,
+                Err(e) => {
+                    warn!("Failed to store shwap container {cid}: {e}");
+                    return;
+                }
+            }
+
+            if provide_blocks {
+                let _ = results_tx.send(StoreResult::BlockStored(cid)).await;
+            }
+        });
     }
 
-    #[instrument(level = "trace", skip(self))]
-    async fn on_identify_event(&mut self, ev: identify::Event) -> Result<()> {
-        match ev {
-            identify::Event::Received { peer_id, info } => {
-                // Inform Kademlia about the listening addresses
-                // TODO: Remove this when rust-libp2p#4302 is implemented
-                for addr in info.listen_addrs {
+    fn on_store_result(&mut self, result: StoreResult) {
+        match result {
+            StoreResult::BitswapResponse {
+                peer,
+                response,
+                from_eds,
+            } => {
+                #[cfg(not(target_arch = "wasm32"))]
+                if from_eds {
+                    self.eds_wants.finish(&peer);
+                }
+                #[cfg(target_arch = "wasm32")]
+                let _ = from_eds;
+
+                if let Some(response) = response {
+                    trace!("Responding to wants");
                     self.swarm
                         .behaviour_mut()
-                        .kademlia
-                        .add_address(&peer_id, addr);
+                        .bitswap
+                        .send_message(&peer, response);
                 }
             }
-            _ => trace!("Unhandled identify event"),
+            StoreResult::BlockStored(cid) => self.provide_block(cid),
         }
-
-        Ok(())
     }
 
-    #[instrument(level = "trace", skip(self))]
-    async fn on_gossip_sub_event(&mut self, ev: gossipsub::Event) {
-        match ev {
-            gossipsub::Event::Message {
-                message,
-                message_id,
-                ..
-            } => {
-                let Some(peer) = message.source else {
-                    // Validation mode is `strict` so this will never happen
-                    return;
-                };
+    fn provide_block(&mut self, cid: Cid) {
+        if self.provided_blocks.contains(&cid) {
+            return;
+        }
+
+        let kademlia = &mut self.swarm.behaviour_mut().kademlia;
+
+        if let Err(e) = kademlia.start_providing(cid.to_bytes().into()) {
+            debug!("Failed to announce shwap container {cid}: {e}");
+            return;
+        }
+
+        self.provided_blocks.push_back(cid);
+
+        if self.provided_blocks.len() > MAX_PROVIDED_BLOCKS {
+            if let Some(oldest) = self.provided_blocks.pop_front() {
+                kademlia.stop_providing(&oldest.to_bytes().into());
+            }
+        }
+    }
+
+    /// Answer the wants in the background, so that the blockstore doesn't stall the loop.
+    #[instrument(level = "trace", skip(self, message))]
+    fn on_bitswap_wants(&mut self, peer: PeerId, message: &RawBitswapMessage) {
+        if !self.provide_blocks {
+            return;
+        }
+
+        let store = self.store.clone();
+        let results_tx = self.store_results_tx.clone();
+        let message = message.clone();
+        let local_layout = self.bitswap_client.id_layout();
+        let peer_layout = self.bitswap_client.peer_layout(&peer);
+
+        // Squares are reconstructed with a limited concurrency and only for a few
+        // wants of each peer. The others are answered only from the store.
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(eds_store) = &self.eds_store {
+            if self.eds_wants.try_start(peer) {
+                let eds_store = eds_store.clone();
+
+                spawn(async move {
+                    let blockstore = bitswap::ChainedBlockstore(&*store, &eds_store);
+                    let response =
+                        bitswap::respond_to_wants(&blockstore, &message, local_layout, peer_layout)
+                            .await;
+                    let _ = results_tx
+                        .send(StoreResult::BitswapResponse {
+                            peer,
+                            response,
+                            from_eds: true,
+                        })
+                        .await;
+                });
+                return;
+            }
+
+            debug!("Too many wants of {peer} in progress, answering from the store only");
+        }
+
+        spawn(async move {
+            let response =
+                bitswap::respond_to_wants(&*store, &message, local_layout, peer_layout).await;
+            let _ = results_tx
+                .send(StoreResult::BitswapResponse {
+                    peer,
+                    response,
+                    from_eds: false,
+                })
+                .await;
+        });
+    }
+
+    #[instrument(skip_all)]
+    fn report(&mut self) {
+        let tracker_info = self.peer_tracker.info();
+
+        info!(
+            "peers: {}, trusted peers: {}",
+            tracker_info.num_connected_peers, tracker_info.num_connected_trusted_peers,
+        );
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    fn on_ping_event(&mut self, ev: ping::Event) {
+        match ev.result {
+            Ok(rtt) => self.peer_tracker.set_latency(ev.peer, rtt),
+            Err(e) => trace!("Ping to {} failed: {e}", ev.peer),
+        }
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn on_identify_event(&mut self, ev: identify::Event) -> Result<()> {
+        match ev {
+            identify::Event::Received { peer_id, info } => {
+                if self.peer_tracker.set_identified(peer_id, &info) {
+                    self.record_protocol_versions(&info.protocols);
+                }
+
+                // Only the peers of our network are worth dialing after a restart
+                if info.protocols.contains(&self.kademlia_protocol)
+                    && !self.peer_tracker.is_banned(peer_id)
+                {
+                    self.address_book.on_identified(peer_id, Time::now());
+                } else {
+                    self.address_book.remove_peer(peer_id);
+                }
+
+                // Inform Kademlia about the listening addresses
+                // TODO: Remove this when rust-libp2p#4302 is implemented
+                for addr in info.listen_addrs {
+                    self.swarm
+                        .behaviour_mut()
+                        .kademlia
+                        .add_address(&peer_id, addr);
+                }
+            }
+            _ => trace!("Unhandled identify event"),
+        }
+
+        Ok(())
+    }
+
+    /// Record the versions of the `header-ex` and shwap spoken with a newly identified peer.
+    fn record_protocol_versions(&self, protocols: &[StreamProtocol]) {
+        let header_ex = self
+            .header_ex_protocols
+            .iter()
+            .find(|protocol| protocols.contains(protocol));
+        let shwap = self
+            .shwap_protocols
+            .iter()
+            .map(|(protocol, _)| protocol)
+            .find(|protocol| protocols.contains(protocol));
+
+        for (name, version) in [("header-ex", header_ex), ("shwap", shwap)] {
+            let version = version.map_or("none", |version| version.as_ref());
+            metrics::increment_labeled(
+                metrics::PEER_PROTOCOL_VERSIONS,
+                &[("protocol", name), ("version", version)],
+                1,
+            );
+        }
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn on_gossip_sub_event(&mut self, ev: gossipsub::Event) {
+        match ev {
+            gossipsub::Event::Message {
+                propagation_source,
+                message,
+                message_id,
+            } => {
+                let Some(peer) = message.source else {
+                    // Validation mode is `strict` so this will never happen
+                    return;
+                };
 
                 // We may discovered a new peer
                 self.peer_maybe_discovered(peer);
 
                 let acceptance = if message.topic == self.header_sub_topic_hash {
                     self.on_header_sub_message(&message.data[..]).await
+                } else if message.topic == self.bad_encoding_fraud_sub_topic_hash {
+                    self.on_bad_encoding_fraud_sub_message(&message.data[..])
+                        .await
                 } else {
                     trace!("Unhandled gossipsub message");
                     gossipsub::MessageAcceptance::Ignore
                 };
 
+                // Only the handled topics can reject a message
+                if matches!(acceptance, gossipsub::MessageAcceptance::Reject) {
+                    let misbehavior = if message.topic == self.header_sub_topic_hash {
+                        Misbehavior::InvalidHeaders
+                    } else {
+                        Misbehavior::InvalidProof
+                    };
+                    self.peer_tracker.penalize(propagation_source, misbehavior);
+                }
+
                 let _ = self
                     .swarm
                     .behaviour_mut()
@@ -666,6 +2527,56 @@ where
         Ok(())
     }
 
+    #[instrument(level = "trace", skip(self))]
+    fn on_autonat_event(&mut self, ev: autonat::Event) {
+        match ev {
+            autonat::Event::StatusChanged { old, new } => {
+                info!("NAT status changed from {old:?} to {new:?}");
+            }
+            _ => trace!("Unhandled autonat event"),
+        }
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    fn on_relay_client_event(&mut self, ev: relay::client::Event) {
+        match ev {
+            relay::client::Event::ReservationReqAccepted {
+                relay_peer_id,
+                renewal: false,
+                ..
+            } => {
+                info!("Reserved a slot on the relay {relay_peer_id}");
+            }
+            _ => trace!("Unhandled relay client event"),
+        }
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    fn on_dcutr_event(&mut self, ev: dcutr::Event) {
+        match ev.result {
+            Ok(_) => debug!("Hole punched a direct connection to {}", ev.remote_peer_id),
+            Err(e) => debug!(
+                "Hole punching a connection to {} failed: {e}",
+                ev.remote_peer_id
+            ),
+        }
+    }
+
+    fn on_custom_event(&mut self, ev: B::ToSwarm) {
+        let Some(events_tx) = &self.custom_events_tx else {
+            return;
+        };
+
+        match events_tx.try_send(ev) {
+            Ok(()) => {}
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                warn!("Custom behaviour events channel is full, dropping event")
+            }
+            // Nobody is interested in the events anymore
+            Err(mpsc::error::TrySendError::Closed(_)) => self.custom_events_tx = None,
+        }
+    }
+
     #[instrument(skip_all, fields(peer_id = %peer_id))]
     fn peer_maybe_discovered(&mut self, peer_id: PeerId) {
         if !self.peer_tracker.set_maybe_discovered(peer_id) {
@@ -689,6 +2600,11 @@ where
         // We do this because Kademlia send commands to Swarm
         // for dialing a peer and we may not have that address
         // in PeerTracker.
+        let direction = if endpoint.is_dialer() {
+            ConnectionDirection::Outbound
+        } else {
+            ConnectionDirection::Inbound
+        };
         let dialed_addr = match endpoint {
             ConnectedPoint::Dialer {
                 address,
@@ -697,8 +2613,88 @@ where
             _ => None,
         };
 
-        self.peer_tracker
-            .set_connected(peer_id, connection_id, dialed_addr);
+        // Dialed addresses are verified to be reachable, unlike the ones reported by the peer
+        if let Some(addr) = &dialed_addr {
+            self.address_book.on_dialed(peer_id, addr);
+        }
+
+        if self
+            .peer_tracker
+            .set_connected(peer_id, connection_id, direction, dialed_addr)
+        {
+            self.event_pub.send(NodeEvent::PeerConnected {
+                id: peer_id,
+                trusted: self.peer_tracker.is_trusted(peer_id),
+            });
+        }
+
+        if self.peer_tracker.is_banned(peer_id) {
+            debug!("Disconnecting banned peer");
+            self.address_book.remove_peer(peer_id);
+            let _ = self.swarm.disconnect_peer_id(peer_id);
+        }
+    }
+
+    fn disconnect_banned_peers(&mut self) {
+        for peer_id in self.peer_tracker.banned_connected_peers() {
+            debug!("Disconnecting banned peer {peer_id}");
+            self.address_book.remove_peer(peer_id);
+            let _ = self.swarm.disconnect_peer_id(peer_id);
+        }
+    }
+
+    /// Load the addresses of the previously seen peers from the store and dial them.
+    async fn dial_known_peers(&mut self) {
+        let addrs = match self.store.get_peer_addrs().await {
+            Ok(addrs) => addrs,
+            Err(e) => {
+                warn!("Failed to load the known peers' addresses: {e}");
+                return;
+            }
+        };
+
+        self.address_book = AddressBook::from_persisted(addrs, Time::now());
+
+        for (peer_id, addrs) in self.address_book.peers() {
+            if peer_id == *self.swarm.local_peer_id() || self.peer_tracker.is_banned(peer_id) {
+                continue;
+            }
+
+            for addr in &addrs {
+                self.swarm
+                    .behaviour_mut()
+                    .kademlia
+                    .add_address(&peer_id, addr.clone());
+            }
+
+            let opts = DialOpts::peer_id(peer_id)
+                .addresses(addrs)
+                .condition(PeerCondition::DisconnectedAndNotDialing)
+                .build();
+
+            if let Err(e) = self.swarm.dial(opts) {
+                debug!("Failed to dial known peer {peer_id}: {e}");
+            }
+        }
+    }
+
+    fn dial_bootnodes(&mut self) {
+        for addr in &self.bootnodes {
+            if let Err(e) = self.swarm.dial(addr.clone()) {
+                warn!("Failed to dial bootnode {addr}: {e}");
+            }
+        }
+    }
+
+    /// Persist the addresses of the known peers if they changed.
+    async fn persist_peer_addrs(&mut self) {
+        let Some(addrs) = self.address_book.take_changes() else {
+            return;
+        };
+
+        if let Err(e) = self.store.set_peer_addrs(addrs).await {
+            warn!("Failed to persist the known peers' addresses: {e}");
+        }
     }
 
     #[instrument(skip_all, fields(peer_id = %peer_id))]
@@ -708,6 +2704,11 @@ where
             .set_maybe_disconnected(peer_id, connection_id)
         {
             debug!("Peer disconnected");
+            self.address_book.forget_dialed(peer_id);
+            self.event_pub.send(NodeEvent::PeerDisconnected {
+                id: peer_id,
+                trusted: self.peer_tracker.is_trusted(peer_id),
+            });
         }
     }
 
@@ -717,6 +2718,23 @@ where
         trace!("HeaderSub initialized");
     }
 
+    #[cfg(any(test, feature = "test-utils"))]
+    #[instrument(skip_all, fields(header = %header))]
+    fn publish_header(&mut self, header: ExtendedHeader) -> Result<()> {
+        let data = header
+            .encode_vec()
+            .map_err(|e| P2pError::GossipsubPublish(e.to_string()))?;
+
+        self.swarm
+            .behaviour_mut()
+            .gossipsub
+            .publish(self.header_sub_topic_hash.clone(), data)
+            .map_err(|e| P2pError::GossipsubPublish(e.to_string()))?;
+
+        debug!("Published header on header-sub");
+        Ok(())
+    }
+
     #[instrument(skip_all)]
     async fn on_header_sub_message(&mut self, data: &[u8]) -> gossipsub::MessageAcceptance {
         let Ok(header) = ExtendedHeader::decode_and_validate(data) else {
@@ -726,14 +2744,20 @@ where
 
         trace!("Received header from header-sub ({header})");
 
-        let updated = self.header_sub_watcher.send_if_modified(move |state| {
+        let head_validators = &self.head_validators;
+        let clock = self.header_clock.check();
+        let mut acceptance = HeadAcceptance::Ignore;
+
+        self.header_sub_watcher.send_if_modified(|state| {
             let Some(known_header) = state else {
                 debug!("HeaderSub not initialized yet");
                 return false;
             };
 
-            if known_header.verify(&header).is_err() {
-                trace!("Failed to verify HeaderSub header. Ignoring {header}");
+            acceptance = validate_head(known_header, &header, clock, head_validators);
+
+            if acceptance != HeadAcceptance::Accept {
+                trace!("HeaderSub header not accepted ({acceptance:?}): {header}");
                 return false;
             }
 
@@ -742,23 +2766,123 @@ where
             true
         });
 
-        if updated {
-            gossipsub::MessageAcceptance::Accept
-        } else {
-            gossipsub::MessageAcceptance::Ignore
+        acceptance.into()
+    }
+
+    #[instrument(skip_all)]
+    async fn on_bad_encoding_fraud_sub_message(
+        &mut self,
+        data: &[u8],
+    ) -> gossipsub::MessageAcceptance {
+        let Ok(proof) = BadEncodingFraudProof::decode(data) else {
+            trace!("Malformed bad encoding fraud proof from fraud-sub");
+            return gossipsub::MessageAcceptance::Reject;
+        };
+
+        let height = proof.height().value();
+
+        if let Some(known) = self.fraud_proof_watcher.borrow().as_ref() {
+            if known.height().value() <= height {
+                trace!("Fraud already proven at height {}", known.height());
+                return gossipsub::MessageAcceptance::Ignore;
+            }
+        }
+
+        // We can only validate the proof against a header that we already verified
+        let Ok(header) = self.store.get_by_hash(&proof.header_hash()).await else {
+            trace!("Header of the bad encoding fraud proof at height {height} is unknown");
+            return gossipsub::MessageAcceptance::Ignore;
+        };
+
+        if let Err(e) = proof.validate(&header) {
+            debug!("Invalid bad encoding fraud proof at height {height}: {e}");
+            return gossipsub::MessageAcceptance::Reject;
         }
+
+        warn!("Received valid bad encoding fraud proof for block {header}");
+        self.fraud_proof_watcher.send_replace(Some(proof));
+
+        gossipsub::MessageAcceptance::Accept
     }
 }
 
-fn validate_bootnode_addrs(addrs: &[Multiaddr]) -> Result<(), P2pError> {
-    let mut invalid_addrs = Vec::new();
+/// Send the stored shwap containers of the most recently sampled blocks to be announced,
+/// up to [`MAX_PROVIDED_BLOCKS`] of them, the oldest first.
+async fn reannounce_stored_blocks<S>(
+    store: &S,
+    results_tx: mpsc::Sender<StoreResult>,
+) -> Result<(), StoreError>
+where
+    S: Store,
+{
+    let mut cids = Vec::new();
+
+    'heights: for height in store.sampled_heights(..).await?.into_iter().rev() {
+        let Some(metadata) = store.get_sampling_metadata(height).await? else {
+            continue;
+        };
+
+        for cid in metadata.cids_sampled {
+            if cids.len() >= MAX_PROVIDED_BLOCKS {
+                break 'heights;
+            }
+            if store.has(&cid).await.unwrap_or(false) {
+                cids.push(cid);
+            }
+        }
+    }
 
-    for addr in addrs {
-        if addr.peer_id().is_none() {
-            invalid_addrs.push(addr.to_owned());
+    for cid in cids.into_iter().rev() {
+        if results_tx
+            .send(StoreResult::BlockStored(cid))
+            .await
+            .is_err()
+        {
+            break;
         }
     }
 
+    Ok(())
+}
+
+/// Validate the head from the header-sub against the known one, followed by the
+/// custom [`HeadValidator`]s.
+fn validate_head(
+    known: &ExtendedHeader,
+    new: &ExtendedHeader,
+    clock: ClockCheck,
+    validators: &[Arc<dyn HeadValidator>],
+) -> HeadAcceptance {
+    if new.height() <= known.height() {
+        // most likely the head we already accepted, propagated by another peer
+        return HeadAcceptance::Ignore;
+    }
+
+    if known.verify_with_clock(new, clock).is_err() {
+        // Adjacent head must link to the known one, while the non-adjacent one may fail
+        // the verification only because the validator set changed in between
+        let invalid = new.chain_id() != known.chain_id()
+            || (known.height().increment() == new.height()
+                && (new.last_header_hash() != known.hash()
+                    || new.header.validators_hash != known.header.next_validators_hash));
+
+        return if invalid {
+            HeadAcceptance::Reject
+        } else {
+            HeadAcceptance::Ignore
+        };
+    }
+
+    validators
+        .iter()
+        .map(|validator| validator.validate(known, new))
+        .find(|acceptance| *acceptance != HeadAcceptance::Accept)
+        .unwrap_or(HeadAcceptance::Accept)
+}
+
+fn validate_bootnode_addrs(addrs: &[Multiaddr]) -> Result<(), P2pError> {
+    let invalid_addrs = addrs_without_peer_id(addrs);
+
     if invalid_addrs.is_empty() {
         Ok(())
     } else {
@@ -766,6 +2890,34 @@ fn validate_bootnode_addrs(addrs: &[Multiaddr]) -> Result<(), P2pError> {
     }
 }
 
+fn validate_relay_addrs(addrs: &[Multiaddr]) -> Result<(), P2pError> {
+    let invalid_addrs = addrs_without_peer_id(addrs);
+
+    if invalid_addrs.is_empty() {
+        Ok(())
+    } else {
+        Err(P2pError::RelayAddrsWithoutPeerId(invalid_addrs))
+    }
+}
+
+fn validate_protocol_versions(versions: &ProtocolVersions) -> Result<(), P2pError> {
+    if versions.header_ex.is_empty() {
+        Err(P2pError::NoProtocolVersions("header-ex"))
+    } else if versions.shwap.is_empty() {
+        Err(P2pError::NoProtocolVersions("shwap"))
+    } else {
+        Ok(())
+    }
+}
+
+fn addrs_without_peer_id(addrs: &[Multiaddr]) -> Vec<Multiaddr> {
+    addrs
+        .iter()
+        .filter(|addr| addr.peer_id().is_none())
+        .cloned()
+        .collect()
+}
+
 fn init_gossipsub<'a, S>(
     args: &'a P2pArgs<S>,
     topics: impl IntoIterator<Item = &'a gossipsub::IdentTopic>,
@@ -806,7 +2958,11 @@ where
 
     config.set_protocol_names(vec![protocol_id]);
 
-    let store = kad::store::MemoryStore::new(local_peer_id);
+    let store_config = kad::store::MemoryStoreConfig {
+        max_provided_keys: MAX_PROVIDED_BLOCKS,
+        ..Default::default()
+    };
+    let store = kad::store::MemoryStore::with_config(local_peer_id, store_config);
     let mut kademlia = kad::Behaviour::with_config(local_peer_id, store, config);
 
     for addr in &args.bootnodes {
@@ -821,3 +2977,535 @@ where
 
     Ok(kademlia)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitswap::ReceivedBlock;
+    use crate::executor::spawn;
+    use crate::store::InMemoryStore;
+    use crate::test_utils::deliver_shwap_block;
+    use celestia_types::test_utils::ExtendedHeaderGenerator;
+    use celestia_types::{DataAvailabilityHeader, ExtendedDataSquare};
+
+    #[cfg(not(target_arch = "wasm32"))]
+    use tokio::test as async_test;
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as async_test;
+
+    #[test]
+    fn error_codes() {
+        assert_eq!(P2pError::RequestTimedOut.code(), 2016);
+        assert_eq!(P2pError::RequestTimedOut.kind(), ErrorKind::P2p);
+        assert!(P2pError::RequestTimedOut.is_retryable());
+
+        // nested errors keep their codes
+        let err = P2pError::HeaderEx(HeaderExError::QuorumNotReached(2));
+        assert_eq!(err.code(), 2106);
+        assert!(err.is_retryable());
+
+        // invalid shwap reports the kind of the cause
+        let err = P2pError::InvalidShwap(celestia_types::Error::RootMismatch);
+        assert_eq!(err.code(), 2017);
+        assert_eq!(err.kind(), ErrorKind::Proof);
+        assert!(!err.is_retryable());
+    }
+
+    #[test]
+    fn head_validation() {
+        let mut gen = ExtendedHeaderGenerator::new();
+        let headers = gen.next_many(4);
+        let known = &headers[0];
+
+        assert_eq!(
+            validate_head(known, &headers[1], ClockCheck::System, &[]),
+            HeadAcceptance::Accept
+        );
+        assert_eq!(
+            validate_head(known, &headers[3], ClockCheck::System, &[]),
+            HeadAcceptance::Accept
+        );
+        // stale heads
+        assert_eq!(
+            validate_head(&headers[3], &headers[1], ClockCheck::System, &[]),
+            HeadAcceptance::Ignore
+        );
+        assert_eq!(
+            validate_head(known, known, ClockCheck::System, &[]),
+            HeadAcceptance::Ignore
+        );
+
+        // adjacent head not linking to the known one
+        let fork = gen.next_of(&gen.another_of(known));
+        assert_eq!(
+            validate_head(known, &fork, ClockCheck::System, &[]),
+            HeadAcceptance::Reject
+        );
+
+        let validators: Vec<Arc<dyn HeadValidator>> = vec![
+            Arc::new(|_: &ExtendedHeader, _: &ExtendedHeader| HeadAcceptance::Accept),
+            Arc::new(|_: &ExtendedHeader, new: &ExtendedHeader| {
+                if new.height().value() > 3 {
+                    HeadAcceptance::Reject
+                } else {
+                    HeadAcceptance::Accept
+                }
+            }),
+        ];
+        assert_eq!(
+            validate_head(known, &headers[1], ClockCheck::System, &validators),
+            HeadAcceptance::Accept
+        );
+        assert_eq!(
+            validate_head(known, &headers[3], ClockCheck::System, &validators),
+            HeadAcceptance::Reject
+        );
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn eds_wants_limits() {
+        let mut limiter = EdsWantsLimiter::default();
+        let peers: Vec<_> = (0..=MAX_EDS_WANTS_IN_PROGRESS)
+            .map(|_| PeerId::random())
+            .collect();
+
+        assert!(limiter.try_start(peers[0]));
+        // per peer limit
+        assert!(!limiter.try_start(peers[0]));
+
+        for peer in &peers[1..MAX_EDS_WANTS_IN_PROGRESS] {
+            assert!(limiter.try_start(*peer));
+        }
+        // total limit
+        assert!(!limiter.try_start(peers[MAX_EDS_WANTS_IN_PROGRESS]));
+
+        limiter.finish(&peers[0]);
+        assert!(limiter.try_start(peers[MAX_EDS_WANTS_IN_PROGRESS]));
+        assert!(!limiter.try_start(peers[0]));
+
+        // finishing an unknown peer is a no-op
+        limiter.finish(&PeerId::random());
+        assert_eq!(limiter.total, MAX_EDS_WANTS_IN_PROGRESS);
+    }
+
+    #[async_test]
+    async fn stored_blocks_are_reannounced() {
+        let store = InMemoryStore::new();
+        let mut gen = ExtendedHeaderGenerator::new();
+        store.append_unchecked(gen.next_many(3)).await.unwrap();
+
+        let cid = |height| {
+            let id = SampleId::new(0, 4, height).unwrap();
+            bitswap::convert_cid(&CidGeneric::try_from(id).unwrap()).unwrap()
+        };
+
+        store.put_keyed(&cid(1), b"1").await.unwrap();
+        store.put_keyed(&cid(3), b"3").await.unwrap();
+        // sampled blocks of height 2 were pruned in the meantime
+        for height in 1..=3 {
+            store
+                .update_sampling_metadata(height, true, vec![cid(height)])
+                .await
+                .unwrap();
+        }
+
+        let (tx, mut rx) = mpsc::channel(8);
+        reannounce_stored_blocks(&store, tx).await.unwrap();
+
+        let mut announced = Vec::new();
+        while let Some(result) = rx.recv().await {
+            let StoreResult::BlockStored(cid) = result else {
+                panic!("unexpected result: {result:?}");
+            };
+            announced.push(cid);
+        }
+        assert_eq!(announced, [cid(1), cid(3)]);
+    }
+
+    #[async_test]
+    async fn wait_connected_timeout() {
+        let (p2p, handle) = P2p::<InMemoryStore>::mocked();
+
+        let err = p2p
+            .wait_connected_timeout(Duration::from_millis(10))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            P2pError::ConnectTimedOut { timeout, .. } if timeout == Duration::from_millis(10)
+        ));
+
+        handle.announce_peer_connected();
+        p2p.wait_connected_timeout(Duration::from_millis(10))
+            .await
+            .unwrap();
+        // connected peer is not trusted
+        p2p.wait_connected_trusted_timeout(Duration::from_millis(10))
+            .await
+            .unwrap_err();
+
+        handle.announce_trusted_peer_connected();
+        p2p.wait_connected_trusted_timeout(Duration::from_millis(10))
+            .await
+            .unwrap();
+    }
+
+    #[async_test]
+    async fn older_header_is_verified_through_hash_chain() {
+        let (p2p, mut handle) = P2p::<InMemoryStore>::mocked();
+        let mut gen = ExtendedHeaderGenerator::new();
+        let headers = gen.next_many(5);
+        let trusted = &headers[4];
+
+        // signed by the same validators, so the trusted header's commit alone can't tell
+        let forged = gen.another_of(&headers[0]);
+        assert_eq!(forged.height(), headers[1].height());
+        forged.verify(trusted).unwrap();
+
+        let (res, ()) = futures::join!(p2p.get_verified_header_by_height(trusted, 2), async {
+            let (height, amount, respond_to) = handle.expect_header_request_for_height_cmd().await;
+            assert_eq!((height, amount), (2, 3));
+            let mut response = headers[1..4].to_vec();
+            response[0] = forged.clone();
+            respond_to.send(Ok(response)).unwrap();
+        });
+        assert!(matches!(
+            res.unwrap_err(),
+            P2pError::HeaderEx(HeaderExError::InvalidResponse)
+        ));
+
+        let (res, ()) = futures::join!(p2p.get_verified_header_by_height(trusted, 2), async {
+            let (_, _, respond_to) = handle.expect_header_request_for_height_cmd().await;
+            respond_to.send(Ok(headers[1..4].to_vec())).unwrap();
+        });
+        assert_eq!(res.unwrap(), headers[1]);
+    }
+
+    #[async_test]
+    async fn cancelled_request_is_dropped() {
+        let (p2p, mut handle) = P2p::<InMemoryStore>::mocked();
+        let cid = RowId::new(0, 1).unwrap().to_cid(IdLayout::default());
+        let token = CancellationToken::new();
+
+        let (res, respond_to) = futures::join!(
+            p2p.with_cancellation(&token, p2p.get_shwap_cid(cid)),
+            async {
+                let (_, respond_to) = handle.expect_get_shwap_cid().await;
+                token.cancel();
+                respond_to
+            },
+        );
+
+        let err = res.unwrap_err();
+        assert!(matches!(err, P2pError::Cancelled));
+        assert!(!err.is_retryable());
+        // the want gets withdrawn once nobody waits for it
+        assert!(respond_to.is_closed());
+
+        let (res, _) = futures::join!(
+            p2p.with_timeout(Duration::from_millis(10), p2p.get_shwap_cid(cid)),
+            handle.expect_get_shwap_cid(),
+        );
+        assert!(matches!(res.unwrap_err(), P2pError::RequestTimedOut));
+    }
+
+    #[async_test]
+    async fn get_rows_in_order_with_concurrency_limit() {
+        let eds_json = include_str!("../../types/test_data/shwap_samples/eds.json");
+        let eds: ExtendedDataSquare = serde_json::from_str(eds_json).unwrap();
+        let dah_json = include_str!("../../types/test_data/shwap_samples/dah.json");
+        let dah: DataAvailabilityHeader = serde_json::from_str(dah_json).unwrap();
+
+        let mut header = ExtendedHeaderGenerator::new().next();
+        header.dah = dah;
+        let height = header.height().value();
+
+        let (p2p, mut handle) = P2p::<InMemoryStore>::mocked();
+        let (tx, rx) = oneshot::channel();
+
+        spawn(async move {
+            let rows = p2p.get_rows(&[5, 1, 3], 2, &header).await;
+            tx.send(rows).unwrap();
+        });
+
+        let respond = |cid: bitswap::Cid, respond_to: BlockSender| {
+            let row_id = RowId::try_from(cid).unwrap();
+            let row = Row::new(row_id.index, &eds, height).unwrap();
+            deliver_shwap_block(&respond_to, row.encode_vec().unwrap());
+            row_id.index
+        };
+
+        let first = handle.expect_get_shwap_cid().await;
+        let second = handle.expect_get_shwap_cid().await;
+        // only 2 requests at the same time
+        handle.expect_no_cmd().await;
+
+        // respond out of order
+        assert_eq!(respond(second.0, second.1), 1);
+        assert_eq!(respond(first.0, first.1), 5);
+
+        // received rows are stored, while the last one gets requested
+        let mut stored = Vec::new();
+        let (cid, respond_to) = loop {
+            match handle.expect_cmd().await {
+                P2pCmd::AcceptShwapBlock { .. } => {}
+                P2pCmd::PutShwapBlock { cid, .. } => {
+                    stored.push(RowId::try_from(cid).unwrap().index);
+                }
+                P2pCmd::GetShwapCid { cid, respond_to } => break (cid, respond_to),
+                cmd => panic!("Unexpected command: {cmd:?}"),
+            }
+        };
+        assert_eq!(respond(cid, respond_to), 3);
+
+        let rows = rx.await.unwrap().unwrap();
+        let indexes: Vec<_> = rows.iter().map(|row| row.row_id.index).collect();
+        assert_eq!(indexes, vec![5, 1, 3]);
+
+        while stored.len() < 3 {
+            match handle.expect_cmd().await {
+                P2pCmd::AcceptShwapBlock { .. } => {}
+                P2pCmd::PutShwapBlock { cid, .. } => {
+                    stored.push(RowId::try_from(cid).unwrap().index);
+                }
+                cmd => panic!("Unexpected command: {cmd:?}"),
+            }
+        }
+        stored.sort();
+        assert_eq!(stored, vec![1, 3, 5]);
+    }
+
+    #[async_test]
+    async fn get_shwap_cid_retries_failed_requests() {
+        let row_id = RowId::new(0, 1).unwrap();
+        let cid = CidGeneric::try_from(row_id).unwrap();
+
+        let (p2p, mut handle) = P2p::<InMemoryStore>::mocked();
+        let (tx, rx) = oneshot::channel();
+
+        spawn(async move {
+            tx.send(p2p.get_shwap_cid(cid).await).unwrap();
+        });
+
+        let (_, respond_to) = handle.expect_get_shwap_cid().await;
+        respond_to.try_send(Err(P2pError::RequestTimedOut)).unwrap();
+
+        let (retried_cid, respond_to) = handle.expect_get_shwap_cid().await;
+        assert_eq!(retried_cid.to_bytes(), cid.to_bytes());
+        deliver_shwap_block(&respond_to, vec![1, 2, 3]);
+
+        assert_eq!(rx.await.unwrap().unwrap(), vec![1, 2, 3]);
+        handle.expect_accept_shwap_block().await;
+        handle.expect_no_cmd().await;
+    }
+
+    #[async_test]
+    async fn get_shwap_cid_doesnt_retry_fatal_errors() {
+        let row_id = RowId::new(0, 1).unwrap();
+        let cid = CidGeneric::try_from(row_id).unwrap();
+
+        let (p2p, mut handle) = P2p::<InMemoryStore>::mocked();
+        let (tx, rx) = oneshot::channel();
+
+        spawn(async move {
+            tx.send(p2p.get_shwap_cid(cid).await).unwrap();
+        });
+
+        let (_, respond_to) = handle.expect_get_shwap_cid().await;
+        respond_to
+            .try_send(Err(P2pError::NoConnectedPeers))
+            .unwrap();
+
+        assert!(matches!(rx.await.unwrap(), Err(P2pError::NoConnectedPeers)));
+        handle.expect_no_cmd().await;
+    }
+
+    #[async_test]
+    async fn invalid_container_is_rejected_and_waited_for_again() {
+        let eds_json = include_str!("../../types/test_data/shwap_samples/eds.json");
+        let eds: ExtendedDataSquare = serde_json::from_str(eds_json).unwrap();
+        let dah_json = include_str!("../../types/test_data/shwap_samples/dah.json");
+        let dah: DataAvailabilityHeader = serde_json::from_str(dah_json).unwrap();
+
+        let mut header = ExtendedHeaderGenerator::new().next();
+        header.dah = dah;
+        let height = header.height().value();
+
+        let (p2p, mut handle) = P2p::<InMemoryStore>::mocked();
+        let (tx, rx) = oneshot::channel();
+
+        spawn(async move {
+            tx.send(p2p.get_row(1, &header).await).unwrap();
+        });
+
+        let (cid, respond_to) = handle.expect_get_shwap_cid().await;
+        let bad_peer = PeerId::random();
+        let bad_row = Row::new(2, &eds, height).unwrap();
+        respond_to
+            .try_send(Ok(ReceivedBlock {
+                peer: bad_peer,
+                data: bad_row.encode_vec().unwrap(),
+            }))
+            .unwrap();
+
+        // invalid row is reported, while the same want waits for the other peers
+        assert_eq!(handle.expect_reject_shwap_block().await, (cid, bad_peer));
+        handle.expect_no_cmd().await;
+
+        let good_peer = PeerId::random();
+        let row = Row::new(1, &eds, height).unwrap();
+        respond_to
+            .try_send(Ok(ReceivedBlock {
+                peer: good_peer,
+                data: row.encode_vec().unwrap(),
+            }))
+            .unwrap();
+
+        assert_eq!(handle.expect_accept_shwap_block().await, (cid, good_peer));
+        let (stored_cid, _) = handle.expect_put_shwap_block().await;
+        assert_eq!(stored_cid, cid);
+        assert_eq!(rx.await.unwrap().unwrap().row_id.index, 1);
+    }
+
+    /// Serve the same response to all the requests, sending the received requests back.
+    #[cfg(all(feature = "gateway", not(target_arch = "wasm32")))]
+    async fn serve_gateway(body: Vec<u8>) -> (String, mpsc::UnboundedReceiver<String>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (requests_tx, requests_rx) = mpsc::unbounded_channel();
+
+        spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let n = stream.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                }
+                requests_tx
+                    .send(String::from_utf8(request).unwrap())
+                    .unwrap();
+
+                let head = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                );
+                stream.write_all(head.as_bytes()).await.unwrap();
+                stream.write_all(&body).await.unwrap();
+            }
+        });
+
+        (url, requests_rx)
+    }
+
+    #[cfg(all(feature = "gateway", not(target_arch = "wasm32")))]
+    #[async_test]
+    async fn gateway_fallback_after_p2p_timeout() {
+        use crate::gateway::{Gateway, GatewayConfig};
+
+        let eds_json = include_str!("../../types/test_data/shwap_samples/eds.json");
+        let eds: ExtendedDataSquare = serde_json::from_str(eds_json).unwrap();
+        let dah_json = include_str!("../../types/test_data/shwap_samples/dah.json");
+        let dah: DataAvailabilityHeader = serde_json::from_str(dah_json).unwrap();
+
+        let mut header = ExtendedHeaderGenerator::new().next();
+        header.dah = dah;
+        let height = header.height().value();
+        let row = Row::new(1, &eds, height).unwrap();
+
+        let (url, mut requests) = serve_gateway(row.encode_vec().unwrap()).await;
+        let (mut p2p, mut handle) = P2p::<InMemoryStore>::mocked();
+        p2p.gateway = Some(Gateway::new(GatewayConfig {
+            url,
+            p2p_timeout: Duration::from_millis(100),
+        }));
+
+        // the container is never delivered over bitswap
+        let (res, (cid, _respond_to)) =
+            futures::join!(p2p.get_row(1, &header), handle.expect_get_shwap_cid());
+        assert_eq!(
+            res.unwrap().encode_vec().unwrap(),
+            row.encode_vec().unwrap()
+        );
+
+        let request = requests.recv().await.unwrap();
+        assert!(request.starts_with(&format!("GET /ipfs/{cid}?format=raw HTTP/1.1\r\n")));
+        assert!(request
+            .to_lowercase()
+            .contains("accept: application/vnd.ipld.raw\r\n"));
+
+        // verified container is stored like the one from bitswap
+        let (stored_cid, _) = handle.expect_put_shwap_block().await;
+        assert_eq!(stored_cid, cid);
+
+        // container from the gateway is verified too
+        let (res, _) = futures::join!(p2p.get_row(2, &header), handle.expect_get_shwap_cid());
+        assert!(matches!(res.unwrap_err(), P2pError::InvalidShwap(_)));
+        handle.expect_no_cmd().await;
+    }
+
+    #[async_test]
+    async fn sample_out_of_square_is_rejected() {
+        let header = ExtendedHeaderGenerator::new().next();
+        let square_len = header.dah.square_len() as u16;
+        let (p2p, mut handle) = P2p::<InMemoryStore>::mocked();
+
+        for (row, column) in [(0, square_len), (square_len, 0)] {
+            let err = p2p.get_sample(row, column, &header).await.unwrap_err();
+            assert!(matches!(err, P2pError::ShareOutOfSquare { .. }));
+            assert_eq!(err.kind(), ErrorKind::Validation);
+        }
+
+        handle.expect_no_cmd().await;
+    }
+
+    #[test]
+    fn retry_delay_grows_exponentially() {
+        let retry = RetryConfig {
+            max_attempts: 10,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+            jitter: 0.0,
+        };
+
+        assert_eq!(retry.delay(1), Duration::from_millis(100));
+        assert_eq!(retry.delay(2), Duration::from_millis(200));
+        assert_eq!(retry.delay(4), Duration::from_millis(800));
+        assert_eq!(retry.delay(5), Duration::from_secs(1));
+        assert_eq!(retry.delay(100), Duration::from_secs(1));
+
+        let retry = RetryConfig {
+            jitter: 0.5,
+            ..retry
+        };
+        for _ in 0..100 {
+            let delay = retry.delay(2);
+            assert!(delay >= Duration::from_millis(100));
+            assert!(delay <= Duration::from_millis(300));
+        }
+    }
+
+    #[test]
+    fn retry_delay_with_extreme_config() {
+        let retry = RetryConfig {
+            max_attempts: 10,
+            base_delay: Duration::MAX,
+            max_delay: Duration::MAX,
+            jitter: 1.0,
+        };
+        for attempt in 1..10 {
+            retry.delay(attempt);
+        }
+
+        let retry = RetryConfig {
+            base_delay: Duration::from_millis(100),
+            jitter: f64::NAN,
+            ..retry
+        };
+        assert_eq!(retry.delay(1), Duration::from_millis(100));
+    }
+}
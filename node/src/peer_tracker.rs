@@ -1,16 +1,31 @@
 //! Primitives related to tracking the state of peers in the network.
 
 use std::borrow::Borrow;
+use std::cmp::Ordering;
 
 use dashmap::mapref::entry::Entry;
 use dashmap::mapref::one::RefMut;
 use dashmap::DashMap;
-use libp2p::{identify, swarm::ConnectionId, Multiaddr, PeerId};
+use instant::{Duration, Instant};
+use libp2p::{identify, swarm::ConnectionId, Multiaddr, PeerId, StreamProtocol};
 use rand::seq::SliceRandom;
 use serde::Serialize;
 use smallvec::SmallVec;
 use tokio::sync::watch;
 
+use crate::metrics;
+
+/// Score above which rewards no longer increase the score of a peer.
+const MAX_SCORE: f64 = 20.0;
+/// Score reward for a valid response.
+const VALID_RESPONSE_REWARD: f64 = 1.0;
+/// Score at or below which a peer is banned.
+const BAN_THRESHOLD: f64 = -100.0;
+/// How long a peer stays banned.
+const BAN_DURATION: Duration = Duration::from_secs(60 * 60);
+/// Time after which the score of a peer decays to half of its value.
+const SCORE_HALF_LIFE: Duration = Duration::from_secs(10 * 60);
+
 /// Keeps track various information about peers.
 #[derive(Debug)]
 pub struct PeerTracker {
@@ -27,12 +42,79 @@ pub struct PeerTrackerInfo {
     pub num_connected_trusted_peers: u64,
 }
 
+/// Direction of the first connection established with a peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ConnectionDirection {
+    /// Connection was dialed by the node.
+    Outbound,
+    /// Connection was dialed by the peer.
+    Inbound,
+}
+
+/// Diagnostics of a connected peer.
+#[derive(Debug, Clone)]
+pub struct PeerDiagnostics {
+    /// Id of the peer.
+    pub peer_id: PeerId,
+    /// Agent version reported by the peer with identify, if it was identified.
+    pub agent_version: Option<String>,
+    /// Protocols supported by the peer, reported with identify.
+    pub protocols: Vec<StreamProtocol>,
+    /// Latest round trip time measured with ping.
+    pub latency: Option<Duration>,
+    /// Direction of the connection.
+    pub direction: ConnectionDirection,
+    /// Known addresses of the peer.
+    pub addrs: Vec<Multiaddr>,
+    /// Whether the peer is trusted.
+    pub trusted: bool,
+}
+
+/// Kinds of misbehavior lowering the score of a peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Misbehavior {
+    /// Peer responded with headers that are malformed, invalid or not the requested ones,
+    /// or propagated an invalid head over the header-sub.
+    InvalidHeaders,
+    /// Request sent to the peer failed, e.g. it timed out or the stream was closed.
+    FailedRequest,
+    /// Peer sent a bitswap block that is not a supported shwap container or that failed
+    /// the verification.
+    InvalidBlock,
+    /// Peer propagated an invalid proof.
+    InvalidProof,
+}
+
+impl Misbehavior {
+    fn penalty(self) -> f64 {
+        match self {
+            Misbehavior::InvalidHeaders => 50.0,
+            Misbehavior::FailedRequest => 10.0,
+            Misbehavior::InvalidBlock => 50.0,
+            Misbehavior::InvalidProof => 100.0,
+        }
+    }
+}
+
 #[derive(Debug)]
 struct PeerInfo {
     state: PeerState,
     addrs: SmallVec<[Multiaddr; 4]>,
     connections: SmallVec<[ConnectionId; 1]>,
     trusted: bool,
+    score: PeerScore,
+    direction: ConnectionDirection,
+    agent_version: Option<String>,
+    protocols: Vec<StreamProtocol>,
+    latency: Option<Duration>,
+}
+
+/// Score of a peer, decaying exponentially towards zero.
+#[derive(Debug)]
+struct PeerScore {
+    value: f64,
+    updated_at: Instant,
+    banned_until: Option<Instant>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -44,9 +126,66 @@ enum PeerState {
 }
 
 impl PeerInfo {
+    fn new() -> Self {
+        PeerInfo {
+            state: PeerState::Discovered,
+            addrs: SmallVec::new(),
+            connections: SmallVec::new(),
+            trusted: false,
+            score: PeerScore::new(),
+            direction: ConnectionDirection::Outbound,
+            agent_version: None,
+            protocols: Vec::new(),
+            latency: None,
+        }
+    }
+
     fn is_connected(&self) -> bool {
         matches!(self.state, PeerState::Connected | PeerState::Identified)
     }
+
+    fn is_banned(&self, now: Instant) -> bool {
+        !self.trusted && self.score.is_banned(now)
+    }
+
+    /// Whether the peer can be selected for the requests.
+    fn is_available(&self, now: Instant) -> bool {
+        self.is_connected() && !self.is_banned(now)
+    }
+}
+
+impl PeerScore {
+    fn new() -> Self {
+        PeerScore {
+            value: 0.0,
+            updated_at: Instant::now(),
+            banned_until: None,
+        }
+    }
+
+    /// Score at the given time.
+    fn value_at(&self, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(self.updated_at);
+        let half_lives = elapsed.as_secs_f64() / SCORE_HALF_LIFE.as_secs_f64();
+        self.value * 0.5f64.powf(half_lives)
+    }
+
+    fn is_banned(&self, now: Instant) -> bool {
+        self.banned_until.map_or(false, |until| now < until)
+    }
+
+    fn add(&mut self, delta: f64, now: Instant) {
+        if self.banned_until.map_or(false, |until| now >= until) {
+            // Ban expired, give the peer a fresh start
+            self.banned_until = None;
+            self.value = 0.0;
+        } else {
+            self.value = self.value_at(now);
+        }
+
+        self.value = (self.value + delta).min(MAX_SCORE);
+        self.updated_at = now;
+    }
 }
 
 impl PeerTracker {
@@ -74,12 +213,7 @@ impl PeerTracker {
     pub fn set_maybe_discovered(&self, peer: PeerId) -> bool {
         match self.peers.entry(peer) {
             Entry::Vacant(entry) => {
-                entry.insert(PeerInfo {
-                    state: PeerState::Discovered,
-                    addrs: SmallVec::new(),
-                    connections: SmallVec::new(),
-                    trusted: false,
-                });
+                entry.insert(PeerInfo::new());
                 true
             }
             Entry::Occupied(_) => false,
@@ -90,12 +224,7 @@ impl PeerTracker {
     ///
     /// If peer is not found it is added as `PeerState::Discovered`.
     fn get(&self, peer: PeerId) -> RefMut<PeerId, PeerInfo> {
-        self.peers.entry(peer).or_insert_with(|| PeerInfo {
-            state: PeerState::Discovered,
-            addrs: SmallVec::new(),
-            connections: SmallVec::new(),
-            trusted: false,
-        })
+        self.peers.entry(peer).or_insert_with(PeerInfo::new)
     }
 
     /// Add an address for a peer.
@@ -140,17 +269,22 @@ impl PeerTracker {
                 } else {
                     tracker_info.num_connected_trusted_peers -= 1;
                 }
+
+                record_connected_peers(tracker_info);
             });
         }
     }
 
     /// Sets peer as connected.
+    ///
+    /// Returns `true` if was set to connected.
     pub fn set_connected(
         &self,
         peer: PeerId,
         connection_id: ConnectionId,
+        direction: ConnectionDirection,
         address: impl Into<Option<Multiaddr>>,
-    ) {
+    ) -> bool {
         let mut peer_info = self.get(peer);
 
         if let Some(address) = address.into() {
@@ -164,7 +298,11 @@ impl PeerTracker {
         // If peer was not already connected from before
         if !peer_info.is_connected() {
             peer_info.state = PeerState::Connected;
+            peer_info.direction = direction;
             increment_connected_peers(&self.info_tx, peer_info.trusted);
+            true
+        } else {
+            false
         }
     }
 
@@ -178,6 +316,8 @@ impl PeerTracker {
 
         // If this is the last connection from the peer
         if peer_info.connections.is_empty() {
+            peer_info.latency = None;
+
             if peer_info.addrs.is_empty() {
                 peer_info.state = PeerState::Discovered;
             } else {
@@ -192,8 +332,11 @@ impl PeerTracker {
     }
 
     /// Sets peer as identified.
-    pub fn set_identified(&self, peer: PeerId, info: &identify::Info) {
+    ///
+    /// Returns `true` if the peer wasn't identified since it connected.
+    pub fn set_identified(&self, peer: PeerId, info: &identify::Info) -> bool {
         let mut peer_info = self.get(peer);
+        let newly_identified = peer_info.state != PeerState::Identified;
 
         for addr in &info.listen_addrs {
             if !peer_info.addrs.contains(addr) {
@@ -201,7 +344,16 @@ impl PeerTracker {
             }
         }
 
+        peer_info.agent_version = Some(info.agent_version.clone());
+        peer_info.protocols = info.protocols.clone();
         peer_info.state = PeerState::Identified;
+
+        newly_identified
+    }
+
+    /// Sets the round trip time to the peer, measured with ping.
+    pub fn set_latency(&self, peer: PeerId, latency: Duration) {
+        self.get(peer).latency = Some(latency);
     }
 
     /// Returns true if peer is connected.
@@ -209,6 +361,74 @@ impl PeerTracker {
         self.get(peer).is_connected()
     }
 
+    /// Returns true if peer reported the protocol as supported with identify.
+    pub fn supports_protocol(&self, peer: PeerId, protocol: &StreamProtocol) -> bool {
+        self.get(peer).protocols.contains(protocol)
+    }
+
+    /// Returns true if peer is trusted.
+    pub fn is_trusted(&self, peer: PeerId) -> bool {
+        self.get(peer).trusted
+    }
+
+    /// Returns true if peer is banned because of its misbehavior.
+    ///
+    /// Trusted peers are never banned.
+    pub fn is_banned(&self, peer: PeerId) -> bool {
+        self.get(peer).is_banned(Instant::now())
+    }
+
+    /// Lowers the score of the peer according to its misbehavior.
+    ///
+    /// Once the score drops to the ban threshold, the peer is banned for an hour and
+    /// should be disconnected. Returns `true` if the peer is banned.
+    pub fn penalize(&self, peer: PeerId, misbehavior: Misbehavior) -> bool {
+        self.penalize_at(peer, misbehavior, Instant::now())
+    }
+
+    fn penalize_at(&self, peer: PeerId, misbehavior: Misbehavior, now: Instant) -> bool {
+        let mut peer_info = self.get(peer);
+
+        if peer_info.is_banned(now) {
+            return true;
+        }
+
+        peer_info.score.add(-misbehavior.penalty(), now);
+
+        if peer_info.trusted || peer_info.score.value > BAN_THRESHOLD {
+            return false;
+        }
+
+        peer_info.score.banned_until = Some(now + BAN_DURATION);
+        true
+    }
+
+    /// Raises the score of the peer after it served a valid response.
+    pub fn reward(&self, peer: PeerId) {
+        let now = Instant::now();
+        let mut peer_info = self.get(peer);
+
+        if !peer_info.is_banned(now) {
+            peer_info.score.add(VALID_RESPONSE_REWARD, now);
+        }
+    }
+
+    /// Returns the current score of the peer.
+    pub fn score(&self, peer: PeerId) -> f64 {
+        self.get(peer).score.value_at(Instant::now())
+    }
+
+    /// Returns connected peers which are banned and should be disconnected.
+    pub fn banned_connected_peers(&self) -> Vec<PeerId> {
+        let now = Instant::now();
+
+        self.peers
+            .iter()
+            .filter(|pair| pair.value().is_connected() && pair.value().is_banned(now))
+            .map(|pair| pair.key().to_owned())
+            .collect()
+    }
+
     /// Returns the addresses of the peer.
     pub fn addresses(&self, peer: PeerId) -> SmallVec<[Multiaddr; 4]> {
         self.get(peer).addrs.clone()
@@ -228,33 +448,71 @@ impl PeerTracker {
             .collect()
     }
 
+    /// Returns the diagnostics of the connected peers.
+    pub fn connected_peers_diagnostics(&self) -> Vec<PeerDiagnostics> {
+        self.peers
+            .iter()
+            .filter(|pair| pair.value().is_connected())
+            .map(|pair| {
+                let peer_info = pair.value();
+
+                PeerDiagnostics {
+                    peer_id: pair.key().to_owned(),
+                    agent_version: peer_info.agent_version.clone(),
+                    protocols: peer_info.protocols.clone(),
+                    latency: peer_info.latency,
+                    direction: peer_info.direction,
+                    addrs: peer_info.addrs.to_vec(),
+                    trusted: peer_info.trusted,
+                }
+            })
+            // collect instead of returning an iter to not block the dashmap
+            .collect()
+    }
+
     /// Returns one of the best peers.
+    ///
+    /// The peer is picked randomly among the not banned peers with the highest score.
     pub fn best_peer(&self) -> Option<PeerId> {
         const MAX_PEER_SAMPLE: usize = 128;
 
-        // TODO: Implement peer score and return the best.
+        let now = Instant::now();
         let mut peers = self
             .peers
             .iter()
-            .filter(|pair| pair.value().is_connected())
+            .filter(|pair| pair.value().is_available(now))
             .take(MAX_PEER_SAMPLE)
-            .map(|pair| pair.key().to_owned())
+            .map(|pair| (pair.key().to_owned(), pair.value().score.value_at(now)))
             .collect::<SmallVec<[_; MAX_PEER_SAMPLE]>>();
 
+        // Shuffle first, so that peers with equal scores are picked randomly
         peers.shuffle(&mut rand::thread_rng());
 
-        peers.first().copied()
+        peers
+            .into_iter()
+            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+            .map(|(peer, _)| peer)
     }
 
     /// Returns up to N amount of best peers.
+    ///
+    /// Banned peers are skipped and the rest is ordered by the score, highest first.
     pub fn best_n_peers(&self, limit: usize) -> Vec<PeerId> {
-        // TODO: Implement peer score and return the best N peers.
-        self.peers
+        let now = Instant::now();
+        let mut peers = self
+            .peers
             .iter()
-            .filter(|pair| pair.value().is_connected())
+            .filter(|pair| pair.value().is_available(now))
+            .map(|pair| (pair.key().to_owned(), pair.value().score.value_at(now)))
+            // collect instead of sorting an iter to not block the dashmap
+            .collect::<Vec<_>>();
+
+        peers.sort_by(|(_, a), (_, b)| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+
+        peers
+            .into_iter()
             .take(limit)
-            .map(|pair| pair.key().to_owned())
-            // collect instead of returning an iter to not block the dashmap
+            .map(|(peer, _)| peer)
             .collect()
     }
 
@@ -283,6 +541,8 @@ fn increment_connected_peers(info_tx: &watch::Sender<PeerTrackerInfo>, trusted:
         if trusted {
             tracker_info.num_connected_trusted_peers += 1;
         }
+
+        record_connected_peers(tracker_info);
     });
 }
 
@@ -293,9 +553,19 @@ fn decrement_connected_peers(info_tx: &watch::Sender<PeerTrackerInfo>, trusted:
         if trusted {
             tracker_info.num_connected_trusted_peers -= 1;
         }
+
+        record_connected_peers(tracker_info);
     });
 }
 
+fn record_connected_peers(tracker_info: &PeerTrackerInfo) {
+    metrics::set(metrics::CONNECTED_PEERS, tracker_info.num_connected_peers);
+    metrics::set(
+        metrics::CONNECTED_TRUSTED_PEERS,
+        tracker_info.num_connected_trusted_peers,
+    );
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -311,13 +581,103 @@ mod tests {
         tracker.set_trusted(peer, true);
         assert!(!watcher.has_changed().unwrap());
 
-        tracker.set_connected(peer, ConnectionId::new_unchecked(1), None);
+        tracker.set_connected(
+            peer,
+            ConnectionId::new_unchecked(1),
+            ConnectionDirection::Outbound,
+            None,
+        );
         assert!(watcher.has_changed().unwrap());
         let info = watcher.borrow_and_update().to_owned();
         assert_eq!(info.num_connected_peers, 1);
         assert_eq!(info.num_connected_trusted_peers, 1);
     }
 
+    #[test]
+    fn connected_peers_diagnostics() {
+        let tracker = PeerTracker::new();
+        let inbound = PeerId::random();
+        let outbound = PeerId::random();
+
+        tracker.set_connected(
+            inbound,
+            ConnectionId::new_unchecked(1),
+            ConnectionDirection::Inbound,
+            None,
+        );
+        tracker.set_connected(
+            outbound,
+            ConnectionId::new_unchecked(2),
+            ConnectionDirection::Outbound,
+            None,
+        );
+        tracker.set_latency(inbound, Duration::from_millis(40));
+
+        let mut diagnostics = tracker.connected_peers_diagnostics();
+        assert_eq!(diagnostics.len(), 2);
+        diagnostics.sort_by_key(|peer| peer.peer_id != inbound);
+        assert_eq!(diagnostics[0].direction, ConnectionDirection::Inbound);
+        assert_eq!(diagnostics[0].latency, Some(Duration::from_millis(40)));
+        assert_eq!(diagnostics[1].direction, ConnectionDirection::Outbound);
+        assert_eq!(diagnostics[1].latency, None);
+        assert!(diagnostics[1].agent_version.is_none());
+
+        // latency of the previous connection is not kept
+        tracker.set_maybe_disconnected(inbound, ConnectionId::new_unchecked(1));
+        assert_eq!(tracker.connected_peers_diagnostics().len(), 1);
+        tracker.set_connected(
+            inbound,
+            ConnectionId::new_unchecked(3),
+            ConnectionDirection::Outbound,
+            None,
+        );
+        let diagnostics = tracker.connected_peers_diagnostics();
+        let peer = diagnostics
+            .iter()
+            .find(|peer| peer.peer_id == inbound)
+            .unwrap();
+        assert_eq!(peer.latency, None);
+        assert_eq!(peer.direction, ConnectionDirection::Outbound);
+    }
+
+    #[test]
+    fn identified_protocols() {
+        let tracker = PeerTracker::new();
+        let peer = PeerId::random();
+        let protocol = StreamProtocol::new("/celestia/private/ipfs/bitswap/1.2.0");
+        let info = identify::Info {
+            public_key: libp2p::identity::Keypair::generate_ed25519().public(),
+            protocol_version: String::new(),
+            agent_version: "celestia-node".to_owned(),
+            listen_addrs: Vec::new(),
+            protocols: vec![protocol.clone()],
+            observed_addr: Multiaddr::empty(),
+        };
+
+        assert!(!tracker.supports_protocol(peer, &protocol));
+
+        tracker.set_connected(
+            peer,
+            ConnectionId::new_unchecked(1),
+            ConnectionDirection::Outbound,
+            None,
+        );
+        assert!(tracker.set_identified(peer, &info));
+        assert!(!tracker.set_identified(peer, &info));
+        assert!(tracker.supports_protocol(peer, &protocol));
+        assert!(!tracker.supports_protocol(peer, &StreamProtocol::new("/header-ex/v0.0.3")));
+
+        // identified again after reconnecting
+        tracker.set_maybe_disconnected(peer, ConnectionId::new_unchecked(1));
+        tracker.set_connected(
+            peer,
+            ConnectionId::new_unchecked(2),
+            ConnectionDirection::Outbound,
+            None,
+        );
+        assert!(tracker.set_identified(peer, &info));
+    }
+
     #[test]
     fn trust_after_connect() {
         let tracker = PeerTracker::new();
@@ -326,7 +686,12 @@ mod tests {
 
         assert!(!watcher.has_changed().unwrap());
 
-        tracker.set_connected(peer, ConnectionId::new_unchecked(1), None);
+        tracker.set_connected(
+            peer,
+            ConnectionId::new_unchecked(1),
+            ConnectionDirection::Outbound,
+            None,
+        );
         assert!(watcher.has_changed().unwrap());
         let info = watcher.borrow_and_update().to_owned();
         assert_eq!(info.num_connected_peers, 1);
@@ -350,7 +715,12 @@ mod tests {
         tracker.set_trusted(peer, true);
         assert!(!watcher.has_changed().unwrap());
 
-        tracker.set_connected(peer, ConnectionId::new_unchecked(1), None);
+        tracker.set_connected(
+            peer,
+            ConnectionId::new_unchecked(1),
+            ConnectionDirection::Outbound,
+            None,
+        );
         assert!(watcher.has_changed().unwrap());
         let info = watcher.borrow_and_update().to_owned();
         assert_eq!(info.num_connected_peers, 1);
@@ -362,4 +732,93 @@ mod tests {
         assert_eq!(info.num_connected_peers, 1);
         assert_eq!(info.num_connected_trusted_peers, 0);
     }
+
+    #[test]
+    fn ban_after_repeated_misbehavior() {
+        let tracker = PeerTracker::new();
+        let peer = PeerId::random();
+        let now = Instant::now();
+
+        tracker.set_connected(
+            peer,
+            ConnectionId::new_unchecked(1),
+            ConnectionDirection::Outbound,
+            None,
+        );
+
+        assert!(!tracker.penalize_at(peer, Misbehavior::InvalidHeaders, now));
+        assert!(!tracker.is_banned(peer));
+        assert_eq!(tracker.best_peer(), Some(peer));
+
+        assert!(tracker.penalize_at(peer, Misbehavior::InvalidHeaders, now));
+        assert!(tracker.is_banned(peer));
+        assert_eq!(tracker.banned_connected_peers(), vec![peer]);
+        assert_eq!(tracker.best_peer(), None);
+        assert!(tracker.best_n_peers(10).is_empty());
+
+        // Ban is lifted after its duration and the score starts from scratch
+        let later = now + BAN_DURATION;
+        assert!(!tracker.get(peer).is_banned(later));
+        assert!(!tracker.penalize_at(peer, Misbehavior::InvalidHeaders, later));
+        assert_eq!(tracker.get(peer).score.value, -50.0);
+    }
+
+    #[test]
+    fn score_decays() {
+        let tracker = PeerTracker::new();
+        let peer = PeerId::random();
+        let now = Instant::now();
+
+        tracker.penalize_at(peer, Misbehavior::InvalidHeaders, now);
+        let score = tracker.get(peer).score.value_at(now + SCORE_HALF_LIFE);
+        assert!((score + 25.0).abs() < 0.001);
+
+        // Penalty spread over time doesn't lead to the ban
+        assert!(!tracker.penalize_at(peer, Misbehavior::InvalidHeaders, now + SCORE_HALF_LIFE));
+        assert!(!tracker.is_banned(peer));
+    }
+
+    #[test]
+    fn trusted_peers_are_never_banned() {
+        let tracker = PeerTracker::new();
+        let peer = PeerId::random();
+
+        tracker.set_trusted(peer, true);
+        tracker.set_connected(
+            peer,
+            ConnectionId::new_unchecked(1),
+            ConnectionDirection::Outbound,
+            None,
+        );
+
+        for _ in 0..5 {
+            assert!(!tracker.penalize(peer, Misbehavior::InvalidProof));
+        }
+        assert!(!tracker.is_banned(peer));
+        assert_eq!(tracker.best_peer(), Some(peer));
+    }
+
+    #[test]
+    fn best_peers_ordered_by_score() {
+        let tracker = PeerTracker::new();
+        let good = PeerId::random();
+        let neutral = PeerId::random();
+        let bad = PeerId::random();
+
+        for (i, peer) in [bad, neutral, good].into_iter().enumerate() {
+            tracker.set_connected(
+                peer,
+                ConnectionId::new_unchecked(i),
+                ConnectionDirection::Outbound,
+                None,
+            );
+        }
+
+        tracker.reward(good);
+        tracker.penalize(bad, Misbehavior::FailedRequest);
+
+        assert_eq!(tracker.best_peer(), Some(good));
+        assert_eq!(tracker.best_n_peers(2), vec![good, neutral]);
+        assert_eq!(tracker.best_n_peers(10), vec![good, neutral, bad]);
+    }
 }
@@ -0,0 +1,308 @@
+//! Component responsible for removing the headers which fell out of the retention window.
+//!
+//! Every [`PRUNING_INTERVAL`] it removes the headers from the tail of the store,
+//! as long as they are older than the configured `pruning_window`. The head of
+//! the store is never removed. The shwap blocks of the removed heights are then
+//! removed from the blockstore too.
+
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+
+use celestia_tendermint::Time;
+use tokio::select;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::clock::Clock;
+use crate::executor::{spawn, yield_now, Interval};
+use crate::store::{Store, StoreError};
+
+/// Interval between the pruning runs.
+pub const PRUNING_INTERVAL: Duration = Duration::from_secs(60);
+/// Default retention window, matching the data sampling window of Celestia.
+pub const DEFAULT_PRUNING_WINDOW: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+// Number of headers removed before yielding to the executor.
+const REMOVALS_PER_YIELD: u64 = 64;
+
+type Result<T, E = StoreError> = std::result::Result<T, E>;
+
+/// Component responsible for removing the headers which fell out of the retention window.
+#[derive(Debug)]
+pub struct Pruner<S>
+where
+    S: Store + 'static,
+{
+    cancellation_token: CancellationToken,
+    _store: PhantomData<S>,
+}
+
+/// Arguments used to configure the [`Pruner`].
+pub struct PrunerArgs<S>
+where
+    S: Store + 'static,
+{
+    /// Headers storage.
+    pub store: Arc<S>,
+    /// Headers older than this are removed from the store.
+    pub pruning_window: Duration,
+    /// Clock against which the age of the headers is measured.
+    pub clock: Arc<dyn Clock>,
+}
+
+impl<S> Pruner<S>
+where
+    S: Store,
+{
+    /// Create and start the [`Pruner`].
+    pub fn start(args: PrunerArgs<S>) -> Self {
+        let cancellation_token = CancellationToken::new();
+        let mut worker = Worker::new(args, cancellation_token.child_token());
+
+        spawn(async move {
+            worker.run().await;
+        });
+
+        Pruner {
+            cancellation_token,
+            _store: PhantomData,
+        }
+    }
+
+    /// Stop the [`Pruner`].
+    pub fn stop(&self) {
+        // Singal the Worker to stop.
+        self.cancellation_token.cancel();
+    }
+}
+
+impl<S> Drop for Pruner<S>
+where
+    S: Store,
+{
+    fn drop(&mut self) {
+        self.cancellation_token.cancel();
+    }
+}
+
+struct Worker<S>
+where
+    S: Store + 'static,
+{
+    cancellation_token: CancellationToken,
+    store: Arc<S>,
+    pruning_window: Duration,
+    clock: Arc<dyn Clock>,
+}
+
+impl<S> Worker<S>
+where
+    S: Store,
+{
+    fn new(args: PrunerArgs<S>, cancellation_token: CancellationToken) -> Self {
+        Worker {
+            cancellation_token,
+            store: args.store,
+            pruning_window: args.pruning_window,
+            clock: args.clock,
+        }
+    }
+
+    async fn run(&mut self) {
+        let mut pruning_interval = Interval::new(PRUNING_INTERVAL).await;
+
+        loop {
+            select! {
+                _ = self.cancellation_token.cancelled() => {
+                    break;
+                }
+                _ = pruning_interval.tick() => {
+                    match self.prune().await {
+                        Ok(0) => {}
+                        Ok(removed) => info!("Pruned {removed} headers"),
+                        Err(e) => warn!("Pruning failed: {e}"),
+                    }
+                }
+            }
+        }
+
+        debug!("Pruner stopped");
+    }
+
+    /// Remove headers older than the pruning window, returning the number of removed headers.
+    async fn prune(&self) -> Result<u64> {
+        let Some(cutoff) = self.clock.now().checked_sub(self.pruning_window) else {
+            return Ok(0);
+        };
+
+        prune_older_than(&*self.store, cutoff, &self.cancellation_token).await
+    }
+}
+
+async fn prune_older_than<S>(
+    store: &S,
+    cutoff: Time,
+    cancellation_token: &CancellationToken,
+) -> Result<u64>
+where
+    S: Store,
+{
+    let mut removed = 0;
+
+    while !cancellation_token.is_cancelled() {
+        let tail_height = match store.tail_height().await {
+            Ok(height) => height,
+            // Empty store, nothing to prune
+            Err(StoreError::NotFound) => break,
+            Err(e) => return Err(e),
+        };
+
+        if tail_height >= store.head_height().await? {
+            break;
+        }
+
+        let tail = store.get_by_height(tail_height).await?;
+
+        if tail.time() >= cutoff {
+            break;
+        }
+
+        store.remove_tail().await?;
+        removed += 1;
+
+        if removed % REMOVALS_PER_YIELD == 0 {
+            yield_now().await;
+        }
+    }
+
+    let tail_height = match store.tail_height().await {
+        Ok(height) => height,
+        Err(StoreError::NotFound) => return Ok(removed),
+        Err(e) => return Err(e),
+    };
+
+    // Also removes the blocks left behind by the runs interrupted before getting here
+    let removed_blocks = store.remove_blocks_below(tail_height).await?;
+    if removed_blocks > 0 {
+        debug!("Pruned {removed_blocks} blocks below height {tail_height}");
+    }
+
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::InMemoryStore;
+    use crate::test_utils::MockClock;
+    use blockstore::Blockstore;
+    use celestia_types::row::{IdLayout, RowId};
+    use celestia_types::test_utils::ExtendedHeaderGenerator;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    use tokio::test as async_test;
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as async_test;
+
+    #[async_test]
+    async fn prune_older_than_cutoff() {
+        let store = InMemoryStore::new();
+        let mut gen = ExtendedHeaderGenerator::new();
+
+        let mut headers = gen.next_many(10);
+        let genesis_time = headers[0].time();
+
+        // make sure each header is a second apart
+        for (i, header) in headers.iter_mut().enumerate() {
+            header.header.time = genesis_time
+                .checked_add(Duration::from_secs(i as u64))
+                .unwrap();
+        }
+
+        let cutoff = headers[4].time();
+        store.append_unchecked(headers).await.unwrap();
+
+        let removed = prune_older_than(&store, cutoff, &CancellationToken::new())
+            .await
+            .unwrap();
+
+        assert_eq!(removed, 4);
+        assert_eq!(store.tail_height().await.unwrap(), 5);
+        assert_eq!(store.head_height().await.unwrap(), 10);
+    }
+
+    #[async_test]
+    async fn prune_blocks_of_removed_headers() {
+        let store = InMemoryStore::new();
+        let mut gen = ExtendedHeaderGenerator::new();
+        let row = |height| RowId::new(0, height).unwrap().to_cid(IdLayout::V1);
+
+        store.append_unchecked(gen.next_many(3)).await.unwrap();
+        for height in 1..=3 {
+            store.put_keyed(&row(height), b"row").await.unwrap();
+        }
+
+        let cutoff = Time::now().checked_add(Duration::from_secs(60)).unwrap();
+        prune_older_than(&store, cutoff, &CancellationToken::new())
+            .await
+            .unwrap();
+
+        assert!(!Blockstore::has(&store, &row(1)).await.unwrap());
+        assert!(!Blockstore::has(&store, &row(2)).await.unwrap());
+        assert!(Blockstore::has(&store, &row(3)).await.unwrap());
+    }
+
+    #[async_test]
+    async fn never_prune_head() {
+        let store = InMemoryStore::new();
+        let mut gen = ExtendedHeaderGenerator::new();
+
+        store.append_unchecked(gen.next_many(3)).await.unwrap();
+
+        let cutoff = Time::now().checked_add(Duration::from_secs(60)).unwrap();
+        let removed = prune_older_than(&store, cutoff, &CancellationToken::new())
+            .await
+            .unwrap();
+
+        assert_eq!(removed, 2);
+        assert_eq!(store.tail_height().await.unwrap(), 3);
+        assert_eq!(store.head_height().await.unwrap(), 3);
+    }
+
+    #[async_test]
+    async fn prune_empty_store() {
+        let store = InMemoryStore::new();
+
+        let removed = prune_older_than(&store, Time::now(), &CancellationToken::new())
+            .await
+            .unwrap();
+
+        assert_eq!(removed, 0);
+    }
+
+    #[async_test]
+    async fn prune_with_clock() {
+        let store = Arc::new(InMemoryStore::new());
+        let mut gen = ExtendedHeaderGenerator::new();
+        let headers = gen.next_many(3);
+        let clock = MockClock::new(headers[0].time());
+        store.append_unchecked(headers).await.unwrap();
+
+        let worker = Worker::new(
+            PrunerArgs {
+                store: store.clone(),
+                pruning_window: Duration::from_secs(60),
+                clock: Arc::new(clock.clone()),
+            },
+            CancellationToken::new(),
+        );
+
+        // nothing fell out of the window yet
+        assert_eq!(worker.prune().await.unwrap(), 0);
+
+        clock.advance(Duration::from_secs(120));
+        assert_eq!(worker.prune().await.unwrap(), 2);
+        assert_eq!(store.tail_height().await.unwrap(), 3);
+    }
+}
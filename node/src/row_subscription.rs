@@ -0,0 +1,223 @@
+//! Subscription to the selected rows of the data square in newly synchronized blocks.
+//!
+//! For every header appended to the store, the [`Row`]s matching the predicate
+//! are requested from the network, verified against the block's
+//! [`DataAvailabilityHeader`] and emitted as a [`RowsEvent`].
+//!
+//! [`DataAvailabilityHeader`]: celestia_types::DataAvailabilityHeader
+
+use std::sync::Arc;
+
+use celestia_types::row::Row;
+use tokio::select;
+use tokio::sync::{mpsc, watch};
+use tracing::{debug, warn};
+
+use crate::executor::spawn;
+use crate::p2p::{P2p, P2pError};
+use crate::store::{Store, StoreError};
+
+/// Maximum number of the rows of a single block requested at the same time.
+const ROWS_CONCURRENCY: usize = 4;
+/// Number of the events buffered before the subscription waits for the receiver.
+const EVENTS_CAPACITY: usize = 16;
+
+/// Selected rows of a synchronized block.
+#[derive(Debug)]
+pub struct RowsEvent {
+    /// Height of the block.
+    pub height: u64,
+    /// Verified rows in the order of their indexes, or an error if any of them could
+    /// not be retrieved.
+    ///
+    /// An empty list means that no row of the block matched the predicate.
+    pub result: Result<Vec<Row>, P2pError>,
+}
+
+/// Arguments used to configure the rows subscription.
+pub(crate) struct SubscriptionArgs<S, F>
+where
+    S: Store + 'static,
+{
+    /// Handler for the peer to peer messaging.
+    pub(crate) p2p: Arc<P2p<S>>,
+    /// Headers storage.
+    pub(crate) store: Arc<S>,
+    /// Watcher for the height of the latest synchronized header.
+    pub(crate) local_head_watcher: watch::Receiver<u64>,
+    /// Predicate called with the index of the row and the width of the square,
+    /// selecting the rows to retrieve.
+    pub(crate) row_predicate: F,
+    /// Height of the first block to retrieve the rows from.
+    pub(crate) start_height: u64,
+}
+
+/// Start the subscription, returning the receiving side of the events channel.
+///
+/// The subscription stops when the receiver is dropped or the syncer stops.
+pub(crate) fn subscribe<S, F>(args: SubscriptionArgs<S, F>) -> mpsc::Receiver<RowsEvent>
+where
+    S: Store,
+    F: Fn(u16, u16) -> bool + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel(EVENTS_CAPACITY);
+
+    spawn(async move {
+        run(args, tx).await;
+    });
+
+    rx
+}
+
+async fn run<S, F>(args: SubscriptionArgs<S, F>, tx: mpsc::Sender<RowsEvent>)
+where
+    S: Store,
+    F: Fn(u16, u16) -> bool,
+{
+    let SubscriptionArgs {
+        p2p,
+        store,
+        mut local_head_watcher,
+        row_predicate,
+        start_height,
+    } = args;
+    let mut next_height = start_height.max(1);
+
+    loop {
+        select! {
+            _ = tx.closed() => break,
+            res = local_head_watcher.wait_for(|height| *height >= next_height) => {
+                if res.is_err() {
+                    // Syncer stopped
+                    break;
+                }
+            }
+        }
+
+        let header = match store.get_by_height(next_height).await {
+            Ok(header) => header,
+            // Pruned before we got to it
+            Err(StoreError::NotFound) => {
+                next_height += 1;
+                continue;
+            }
+            Err(e) => {
+                warn!("Failed to get header {next_height} for rows subscription: {e}");
+                break;
+            }
+        };
+
+        let square_width = header.dah.square_len() as u16;
+        let row_indexes: Vec<_> = (0..square_width)
+            .filter(|&index| row_predicate(index, square_width))
+            .collect();
+
+        let result = select! {
+            _ = tx.closed() => break,
+            res = p2p.get_rows(&row_indexes, ROWS_CONCURRENCY, &header) => res,
+        };
+
+        let event = RowsEvent {
+            height: next_height,
+            result,
+        };
+
+        if tx.send(event).await.is_err() {
+            break;
+        }
+
+        next_height += 1;
+    }
+
+    debug!("Rows subscription stopped");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::InMemoryStore;
+    use crate::test_utils::{deliver_shwap_block, gen_filled_store};
+    use celestia_tendermint_proto::Protobuf;
+    use celestia_types::row::RowId;
+    use celestia_types::test_utils::ExtendedHeaderGenerator;
+    use celestia_types::{DataAvailabilityHeader, ExtendedDataSquare};
+
+    #[cfg(not(target_arch = "wasm32"))]
+    use tokio::test as async_test;
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as async_test;
+
+    #[async_test]
+    async fn emits_verified_rows_for_synced_headers() {
+        let eds_json = include_str!("../../types/test_data/shwap_samples/eds.json");
+        let eds: ExtendedDataSquare = serde_json::from_str(eds_json).unwrap();
+        let dah_json = include_str!("../../types/test_data/shwap_samples/dah.json");
+        let dah: DataAvailabilityHeader = serde_json::from_str(dah_json).unwrap();
+
+        let store = Arc::new(InMemoryStore::new());
+        let mut gen = ExtendedHeaderGenerator::new();
+        let mut header = gen.next();
+        header.dah = dah.clone();
+        store.append_single_unchecked(header).unwrap();
+
+        let (p2p, mut handle) = P2p::mocked();
+        let (local_head_tx, local_head_watcher) = watch::channel(0);
+
+        let mut rx = subscribe(SubscriptionArgs {
+            p2p: Arc::new(p2p),
+            store,
+            local_head_watcher,
+            row_predicate: |index, width| index == 1 || index == width - 1,
+            start_height: 1,
+        });
+
+        // Nothing is requested until the header is synced
+        handle.expect_no_cmd().await;
+        local_head_tx.send_replace(1);
+
+        let last_index = dah.square_len() as u16 - 1;
+        let requests = [
+            handle.expect_get_shwap_cid().await,
+            handle.expect_get_shwap_cid().await,
+        ];
+
+        for (cid, respond_to) in requests {
+            let row_id = RowId::try_from(cid).unwrap();
+            assert!(row_id.index == 1 || row_id.index == last_index);
+
+            let row = Row::new(row_id.index, &eds, 1).unwrap();
+            deliver_shwap_block(&respond_to, row.encode_vec().unwrap());
+        }
+
+        let event = rx.recv().await.unwrap();
+        assert_eq!(event.height, 1);
+
+        let rows = event.result.unwrap();
+        let indexes: Vec<_> = rows.iter().map(|row| row.row_id.index).collect();
+        assert_eq!(indexes, vec![1, last_index]);
+
+        // verified rows are stored to be provided to other peers
+        handle.expect_verified_shwap_blocks(rows.len()).await;
+
+        handle.expect_no_cmd().await;
+    }
+
+    #[async_test]
+    async fn stops_when_receiver_dropped() {
+        let (store, _) = gen_filled_store(1);
+        let (p2p, mut handle) = P2p::mocked();
+        let (local_head_tx, local_head_watcher) = watch::channel(0);
+
+        let rx = subscribe(SubscriptionArgs {
+            p2p: Arc::new(p2p),
+            store: Arc::new(store),
+            local_head_watcher,
+            row_predicate: |_, _| true,
+            start_height: 1,
+        });
+        drop(rx);
+
+        local_head_tx.send_replace(1);
+        handle.expect_no_cmd().await;
+    }
+}
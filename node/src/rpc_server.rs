@@ -0,0 +1,723 @@
+//! JSON-RPC server exposing the [`Node`] with the API of the celestia-node.
+//!
+//! It serves the methods of the `header`, `share` and `das` namespaces that a light node
+//! can answer from its [`Store`] and the network, so that the tooling built for
+//! celestia-node can be pointed at lumina without changes. Blocks are identified by their
+//! [`ExtendedHeader`]s the same way as in the `celestia-rpc` clients, and the header
+//! has to be synchronized to the store before its data can be requested.
+//!
+//! Errors are reported with the [`NodeError::code`] as the JSON-RPC error code.
+//!
+//! # Security
+//!
+//! Unlike celestia-node, the server doesn't support the authentication tokens and every
+//! client that can connect to it can call all the methods, including the ones which make
+//! the node request data from the network. It should only be bound to the loopback
+//! interface, like the [`DEFAULT_RPC_LISTEN_ADDR`], or be put behind a proxy which
+//! authenticates the clients.
+
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::sync::Arc;
+
+use celestia_tendermint::Time;
+use celestia_types::hash::Hash;
+use celestia_types::nmt::Namespace;
+use celestia_types::{ExtendedDataSquare, ExtendedHeader, NamespacedShares, Share, SyncState};
+use futures::future::try_join_all;
+use jsonrpsee::core::SubscriptionResult;
+use jsonrpsee::proc_macros::rpc;
+use jsonrpsee::server::{PendingSubscriptionSink, Server, ServerHandle, SubscriptionMessage};
+use jsonrpsee::types::error::{ErrorObjectOwned, INVALID_PARAMS_CODE};
+use jsonrpsee::RpcModule;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tokio::select;
+use tracing::{debug, info, warn};
+
+use crate::events::{NodeEvent, NodeEventInfo, RecvError};
+use crate::node::{Node, NodeError};
+use crate::p2p::P2pError;
+use crate::store::{Store, StoreError};
+
+/// Number of the random samples requested by `share.SharesAvailable` for the blocks
+/// which weren't sampled by the node yet.
+const AVAILABILITY_SAMPLES: usize = 16;
+/// Maximum number of headers returned by `header.GetRangeByHeight`.
+const MAX_HEADERS_RANGE: u64 = 512;
+
+/// Default address of the JSON-RPC server, the same port as used by celestia-node but
+/// only reachable from the local machine.
+pub const DEFAULT_RPC_LISTEN_ADDR: SocketAddr =
+    SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 26658));
+
+type RpcResult<T> = Result<T, ErrorObjectOwned>;
+
+/// Statistics of the data availability sampling, the result of `das.SamplingStats`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SamplingStats {
+    /// All the heights up to this one were sampled.
+    pub head_of_sampled_chain: u64,
+    /// Height up to which the historical blocks were sampled, same as the
+    /// `head_of_sampled_chain` in lumina.
+    pub head_of_catchup: u64,
+    /// The latest height announced in the network.
+    pub network_head_height: u64,
+    /// Whether all the stored headers were sampled.
+    pub catch_up_done: bool,
+}
+
+/// Running JSON-RPC server, stopped when dropped.
+#[derive(Debug)]
+pub struct RpcServer {
+    handle: ServerHandle,
+    local_addr: SocketAddr,
+}
+
+impl RpcServer {
+    /// Start serving the [`Node`] on the given address, accepting both the HTTP and
+    /// WebSocket connections.
+    ///
+    /// The server has no authentication, see the [module documentation](self).
+    pub async fn start<S>(node: Arc<Node<S>>, listen_addr: SocketAddr) -> std::io::Result<Self>
+    where
+        S: Store + 'static,
+    {
+        let server = Server::builder().build(listen_addr).await?;
+        let local_addr = server.local_addr()?;
+
+        if !local_addr.ip().is_loopback() {
+            warn!(
+                "JSON-RPC server on {local_addr} has no authentication and may be reachable from other machines"
+            );
+        }
+
+        let handler = RpcHandler { node };
+        let mut module = RpcModule::new(());
+        module
+            .merge(HeaderRpcServer::into_rpc(handler.clone()))
+            .and_then(|_| module.merge(ShareRpcServer::into_rpc(handler.clone())))
+            .and_then(|_| module.merge(DasRpcServer::into_rpc(handler)))
+            .expect("method names are unique");
+
+        let handle = server.start(module);
+        info!("Serving JSON-RPC on {local_addr}");
+
+        Ok(RpcServer { handle, local_addr })
+    }
+
+    /// Address the server is listening on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Stop the server.
+    pub fn stop(&self) {
+        // Only fails if it was already stopped
+        let _ = self.handle.stop();
+    }
+}
+
+impl Drop for RpcServer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[rpc(server)]
+trait HeaderRpc {
+    #[method(name = "header.LocalHead")]
+    async fn header_local_head(&self) -> RpcResult<ExtendedHeader>;
+
+    #[method(name = "header.GetByHash")]
+    async fn header_get_by_hash(&self, hash: Hash) -> RpcResult<ExtendedHeader>;
+
+    #[method(name = "header.GetByHeight")]
+    async fn header_get_by_height(&self, height: u64) -> RpcResult<ExtendedHeader>;
+
+    #[method(name = "header.GetRangeByHeight")]
+    async fn header_get_range_by_height(
+        &self,
+        from: ExtendedHeader,
+        to: u64,
+    ) -> RpcResult<Vec<ExtendedHeader>>;
+
+    #[method(name = "header.NetworkHead")]
+    async fn header_network_head(&self) -> RpcResult<ExtendedHeader>;
+
+    #[method(name = "header.SyncState")]
+    async fn header_sync_state(&self) -> RpcResult<SyncState>;
+
+    #[method(name = "header.SyncWait")]
+    async fn header_sync_wait(&self) -> RpcResult<()>;
+
+    #[method(name = "header.WaitForHeight")]
+    async fn header_wait_for_height(&self, height: u64) -> RpcResult<ExtendedHeader>;
+
+    #[subscription(name = "header.Subscribe", unsubscribe = "header.Unsubscribe", item = ExtendedHeader)]
+    async fn header_subscribe(&self) -> SubscriptionResult;
+}
+
+#[rpc(server)]
+trait ShareRpc {
+    #[method(name = "share.SharesAvailable")]
+    async fn share_shares_available(&self, root: ExtendedHeader) -> RpcResult<()>;
+
+    #[method(name = "share.GetShare")]
+    async fn share_get_share(&self, root: ExtendedHeader, row: u64, col: u64) -> RpcResult<Share>;
+
+    #[method(name = "share.GetEDS")]
+    async fn share_get_eds(&self, root: ExtendedHeader) -> RpcResult<ExtendedDataSquare>;
+
+    #[method(name = "share.GetSharesByNamespace")]
+    async fn share_get_shares_by_namespace(
+        &self,
+        root: ExtendedHeader,
+        namespace: Namespace,
+    ) -> RpcResult<NamespacedShares>;
+}
+
+#[rpc(server)]
+trait DasRpc {
+    #[method(name = "das.SamplingStats")]
+    async fn das_sampling_stats(&self) -> RpcResult<SamplingStats>;
+
+    #[method(name = "das.WaitCatchUp")]
+    async fn das_wait_catch_up(&self) -> RpcResult<()>;
+}
+
+struct RpcHandler<S>
+where
+    S: Store + 'static,
+{
+    node: Arc<Node<S>>,
+}
+
+impl<S> Clone for RpcHandler<S>
+where
+    S: Store + 'static,
+{
+    fn clone(&self) -> Self {
+        RpcHandler {
+            node: self.node.clone(),
+        }
+    }
+}
+
+impl<S> RpcHandler<S>
+where
+    S: Store + 'static,
+{
+    async fn network_head(&self) -> RpcResult<ExtendedHeader> {
+        match self.node.get_network_head_header() {
+            Some(header) => Ok(header),
+            // nothing was announced yet, our head is the best we know
+            None => self.node.get_local_head_header().await.map_err(rpc_error),
+        }
+    }
+
+    async fn header_hash(&self, height: u64) -> Hash {
+        self.node
+            .get_header_by_height(height)
+            .await
+            .map(|header| header.hash())
+            .unwrap_or_default()
+    }
+}
+
+#[async_trait::async_trait]
+impl<S> HeaderRpcServer for RpcHandler<S>
+where
+    S: Store + 'static,
+{
+    async fn header_local_head(&self) -> RpcResult<ExtendedHeader> {
+        self.node.get_local_head_header().await.map_err(rpc_error)
+    }
+
+    async fn header_get_by_hash(&self, hash: Hash) -> RpcResult<ExtendedHeader> {
+        self.node.get_header_by_hash(&hash).await.map_err(rpc_error)
+    }
+
+    async fn header_get_by_height(&self, height: u64) -> RpcResult<ExtendedHeader> {
+        self.node
+            .get_header_by_height(height)
+            .await
+            .map_err(rpc_error)
+    }
+
+    async fn header_get_range_by_height(
+        &self,
+        from: ExtendedHeader,
+        to: u64,
+    ) -> RpcResult<Vec<ExtendedHeader>> {
+        // headers between `from` and `to`, both exclusive
+        let from_height = from.height().value() + 1;
+
+        if from_height >= to {
+            return Ok(Vec::new());
+        }
+
+        if to - from_height > MAX_HEADERS_RANGE {
+            return Err(invalid_params(&format!(
+                "Range exceeds the maximum of {MAX_HEADERS_RANGE} headers"
+            )));
+        }
+
+        self.node
+            .get_headers(from_height..to)
+            .await
+            .map_err(rpc_error)
+    }
+
+    async fn header_network_head(&self) -> RpcResult<ExtendedHeader> {
+        self.network_head().await
+    }
+
+    async fn header_sync_state(&self) -> RpcResult<SyncState> {
+        let state = self.node.syncer_state().await.map_err(rpc_error)?;
+        let from_height = state
+            .stored_ranges
+            .first()
+            .map_or(state.head, |range| *range.start());
+
+        // lumina doesn't track the separate syncing jobs, so the state covers
+        // the whole store
+        Ok(SyncState {
+            id: 0,
+            height: state.head,
+            from_height,
+            to_height: state.target,
+            from_hash: self.header_hash(from_height).await,
+            to_hash: self.header_hash(state.target).await,
+            start: Time::unix_epoch(),
+            end: Time::unix_epoch(),
+            error: None,
+        })
+    }
+
+    async fn header_sync_wait(&self) -> RpcResult<()> {
+        let network_head = self.network_head().await?;
+
+        self.node
+            .await_synced(network_head.height().value())
+            .await
+            .map_err(rpc_error)
+    }
+
+    async fn header_wait_for_height(&self, height: u64) -> RpcResult<ExtendedHeader> {
+        self.node.await_synced(height).await.map_err(rpc_error)?;
+
+        self.node
+            .get_header_by_height(height)
+            .await
+            .map_err(rpc_error)
+    }
+
+    async fn header_subscribe(&self, pending: PendingSubscriptionSink) -> SubscriptionResult {
+        let mut events = self.node.event_subscriber();
+        let sink = pending.accept().await?;
+
+        loop {
+            let event = select! {
+                _ = sink.closed() => break,
+                event = events.recv() => event,
+            };
+
+            let height = match event {
+                Ok(NodeEventInfo {
+                    event: NodeEvent::AddedHeaderFromHeaderSub { height },
+                    ..
+                }) => height,
+                Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+
+            let header = self.node.get_header_by_height(height).await?;
+            sink.send(SubscriptionMessage::from_json(&header)?).await?;
+        }
+
+        debug!("Headers subscription finished");
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl<S> ShareRpcServer for RpcHandler<S>
+where
+    S: Store + 'static,
+{
+    async fn share_shares_available(&self, root: ExtendedHeader) -> RpcResult<()> {
+        let height = root.height().value();
+
+        match self.node.store().get_sampling_metadata(height).await {
+            Ok(Some(metadata)) if metadata.accepted => return Ok(()),
+            Ok(Some(_)) => {
+                return Err(ErrorObjectOwned::owned(
+                    NodeError::from(StoreError::NotFound).code().into(),
+                    format!("Data of the block at height {height} is not available"),
+                    None::<()>,
+                ))
+            }
+            // not sampled by the node yet
+            Ok(None) => {}
+            Err(e) => return Err(rpc_error(e.into())),
+        }
+
+        let square_width = self
+            .node
+            .get_header_by_height(height)
+            .await
+            .map_err(rpc_error)?
+            .dah
+            .square_len() as u16;
+        let coordinates: Vec<_> = {
+            let mut rng = rand::thread_rng();
+            (0..AVAILABILITY_SAMPLES)
+                .map(|_| {
+                    (
+                        rng.gen_range(0..square_width),
+                        rng.gen_range(0..square_width),
+                    )
+                })
+                .collect()
+        };
+
+        try_join_all(
+            coordinates
+                .into_iter()
+                .map(|(row, column)| self.node.request_sample(height, row, column)),
+        )
+        .await
+        .map_err(rpc_error)?;
+
+        Ok(())
+    }
+
+    async fn share_get_share(&self, root: ExtendedHeader, row: u64, col: u64) -> RpcResult<Share> {
+        let row = u16::try_from(row).map_err(|_| invalid_params("Row index out of range"))?;
+        let col = u16::try_from(col).map_err(|_| invalid_params("Column index out of range"))?;
+
+        self.node
+            .request_sample(root.height().value(), row, col)
+            .await
+            .map_err(rpc_error)
+    }
+
+    async fn share_get_eds(&self, root: ExtendedHeader) -> RpcResult<ExtendedDataSquare> {
+        self.node
+            .request_eds(root.height().value())
+            .await
+            .map_err(rpc_error)
+    }
+
+    async fn share_get_shares_by_namespace(
+        &self,
+        root: ExtendedHeader,
+        namespace: Namespace,
+    ) -> RpcResult<NamespacedShares> {
+        self.node
+            .request_shares_by_namespace(namespace, root.height().value())
+            .await
+            .map_err(rpc_error)
+    }
+}
+
+#[async_trait::async_trait]
+impl<S> DasRpcServer for RpcHandler<S>
+where
+    S: Store + 'static,
+{
+    async fn das_sampling_stats(&self) -> RpcResult<SamplingStats> {
+        let store = self.node.store();
+        let next_unsampled = store
+            .next_unsampled_height()
+            .await
+            .map_err(|e| rpc_error(e.into()))?;
+        let head_of_sampled_chain = match next_unsampled {
+            Some(height) => height - 1,
+            None => match store.head_height().await {
+                Ok(height) => height,
+                Err(StoreError::NotFound) => 0,
+                Err(e) => return Err(rpc_error(e.into())),
+            },
+        };
+        let network_head_height = self
+            .node
+            .get_network_head_height()
+            .unwrap_or(head_of_sampled_chain);
+
+        Ok(SamplingStats {
+            head_of_sampled_chain,
+            head_of_catchup: head_of_sampled_chain,
+            network_head_height,
+            catch_up_done: next_unsampled.is_none(),
+        })
+    }
+
+    async fn das_wait_catch_up(&self) -> RpcResult<()> {
+        // nothing would ever sample the stored headers
+        if !self.node.is_sampling_enabled() {
+            return Err(rpc_error(NodeError::SamplingDisabled));
+        }
+
+        // subscribe before the first check to not miss the sampling in between
+        let mut events = self.node.event_subscriber();
+
+        loop {
+            let next_unsampled = self
+                .node
+                .store()
+                .next_unsampled_height()
+                .await
+                .map_err(|e| rpc_error(e.into()))?;
+
+            if next_unsampled.is_none() {
+                return Ok(());
+            }
+
+            // the daser publishes the results of a block after storing its sampling metadata
+            loop {
+                match events.recv().await {
+                    Ok(NodeEventInfo {
+                        event: NodeEvent::SamplingFinished { .. },
+                        ..
+                    })
+                    | Err(RecvError::Lagged(_)) => break,
+                    Ok(_) => continue,
+                    Err(RecvError::Closed) => {
+                        return Err(rpc_error(P2pError::ChannelClosedUnexpectedly.into()))
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn rpc_error(e: NodeError) -> ErrorObjectOwned {
+    ErrorObjectOwned::owned(e.code().into(), e.to_string(), None::<()>)
+}
+
+fn invalid_params(message: &str) -> ErrorObjectOwned {
+    ErrorObjectOwned::owned(INVALID_PARAMS_CODE, message, None::<()>)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::daser::DaserConfig;
+    use crate::store::InMemoryStore;
+    use crate::test_utils::{gen_filled_store, listening_test_node_builder, test_node_builder};
+    use celestia_rpc::{Client, HeaderClient, ShareClient};
+    use futures::StreamExt;
+    use jsonrpsee::core::client::ClientT;
+    use jsonrpsee::rpc_params;
+    use std::time::Duration;
+    use tokio::time::timeout;
+
+    async fn start_server(store: InMemoryStore) -> (Arc<Node<InMemoryStore>>, RpcServer, Client) {
+        let node = test_node_builder()
+            .store(store)
+            .disable_syncing()
+            .start()
+            .await
+            .unwrap();
+
+        serve(node).await
+    }
+
+    async fn serve(node: Node<InMemoryStore>) -> (Arc<Node<InMemoryStore>>, RpcServer, Client) {
+        let node = Arc::new(node);
+
+        let server = RpcServer::start(node.clone(), "127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let url = format!("ws://{}", server.local_addr());
+        let client = Client::new(&url, None).await.unwrap();
+
+        (node, server, client)
+    }
+
+    #[tokio::test]
+    async fn serves_stored_headers() {
+        let (store, _) = gen_filled_store(5);
+        let (node, _server, client) = start_server(store).await;
+
+        let head = client.header_local_head().await.unwrap();
+        assert_eq!(head, node.get_local_head_header().await.unwrap());
+
+        let header = client.header_get_by_height(3).await.unwrap();
+        assert_eq!(header, node.get_header_by_height(3).await.unwrap());
+        assert_eq!(
+            client.header_get_by_hash(header.hash()).await.unwrap(),
+            header
+        );
+
+        let first = node.get_header_by_height(1).await.unwrap();
+        let range = client.header_get_range_by_height(&first, 4).await.unwrap();
+        assert_eq!(range, node.get_headers(2..4).await.unwrap());
+
+        client.header_get_by_height(6).await.unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn caps_headers_range() {
+        let (store, _) = gen_filled_store(5);
+        let (node, _server, client) = start_server(store).await;
+
+        let first = node.get_header_by_height(1).await.unwrap();
+        let error = client
+            .header_get_range_by_height(&first, MAX_HEADERS_RANGE + 3)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("maximum"));
+    }
+
+    #[tokio::test]
+    async fn serves_availability_of_sampled_blocks() {
+        let (store, _) = gen_filled_store(3);
+        store
+            .update_sampling_metadata(1, true, vec![])
+            .await
+            .unwrap();
+        store
+            .update_sampling_metadata(2, false, vec![])
+            .await
+            .unwrap();
+        let (node, _server, client) = start_server(store).await;
+
+        let header = node.get_header_by_height(1).await.unwrap();
+        client.share_shares_available(&header).await.unwrap();
+
+        let header = node.get_header_by_height(2).await.unwrap();
+        let error = client.share_shares_available(&header).await.unwrap_err();
+        assert!(error.to_string().contains("not available"));
+
+        let error = client
+            .share_get_share(&header, u64::from(u16::MAX) + 1, 0)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("Row index out of range"));
+    }
+
+    #[tokio::test]
+    async fn serves_sampling_stats() {
+        let (store, _) = gen_filled_store(3);
+        store
+            .update_sampling_metadata(1, true, vec![])
+            .await
+            .unwrap();
+        store
+            .update_sampling_metadata(2, true, vec![])
+            .await
+            .unwrap();
+        let (_node, _server, client) = start_server(store).await;
+
+        let stats: SamplingStats = client
+            .request("das.SamplingStats", rpc_params![])
+            .await
+            .unwrap();
+        assert_eq!(stats.head_of_sampled_chain, 2);
+        assert_eq!(stats.head_of_catchup, 2);
+        assert!(!stats.catch_up_done);
+    }
+
+    #[tokio::test]
+    async fn waits_for_catch_up() {
+        let (store, _) = gen_filled_store(2);
+        store
+            .update_sampling_metadata(1, true, vec![])
+            .await
+            .unwrap();
+        let node = test_node_builder()
+            .store(store)
+            .disable_syncing()
+            .daser_config(DaserConfig::default())
+            .start()
+            .await
+            .unwrap();
+        let (node, _server, client) = serve(node).await;
+
+        // the last header isn't sampled
+        timeout(
+            Duration::from_millis(200),
+            client.request::<(), _>("das.WaitCatchUp", rpc_params![]),
+        )
+        .await
+        .unwrap_err();
+
+        node.store()
+            .update_sampling_metadata(2, true, vec![])
+            .await
+            .unwrap();
+        client
+            .request::<(), _>("das.WaitCatchUp", rpc_params![])
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn wait_catch_up_fails_without_sampling() {
+        let (store, _) = gen_filled_store(2);
+        let (_node, _server, client) = start_server(store).await;
+
+        let error = timeout(
+            Duration::from_secs(1),
+            client.request::<(), _>("das.WaitCatchUp", rpc_params![]),
+        )
+        .await
+        .unwrap()
+        .unwrap_err();
+        assert!(error.to_string().contains("Sampling is disabled"));
+    }
+
+    #[tokio::test]
+    async fn subscription_receives_headers_from_header_sub() {
+        let (store, mut gen) = gen_filled_store(5);
+        let bridge = listening_test_node_builder()
+            .store(store)
+            .disable_syncing()
+            .start()
+            .await
+            .unwrap();
+
+        let node = test_node_builder()
+            .bootnodes(bridge.listeners().await.unwrap())
+            .start()
+            .await
+            .unwrap();
+        node.wait_connected().await.unwrap();
+        node.await_synced(5).await.unwrap();
+
+        let (_node, _server, client) = serve(node).await;
+        let mut headers = client.header_subscribe().await.unwrap();
+
+        let header = gen.next();
+        // publishing fails until the node's subscription to the topic is known
+        while bridge.publish_header(header.clone()).await.is_err() {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        let received = timeout(Duration::from_secs(5), headers.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(received, header);
+    }
+
+    #[tokio::test]
+    async fn stops_on_drop() {
+        let node = test_node_builder()
+            .store(InMemoryStore::new())
+            .start()
+            .await
+            .unwrap();
+
+        let server = RpcServer::start(Arc::new(node), "127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let url = format!("ws://{}", server.local_addr());
+        drop(server);
+
+        Client::new(&url, None).await.unwrap_err();
+    }
+}
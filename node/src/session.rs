@@ -1,28 +1,42 @@
+use std::time::Duration;
+
 use celestia_proto::p2p::pb::HeaderRequest;
 use celestia_types::ExtendedHeader;
 use tokio::sync::{mpsc, oneshot};
+use tokio_util::sync::CancellationToken;
 use tracing::debug;
 
-use crate::executor::spawn;
+use crate::executor::{sleep, spawn_cancellable};
 use crate::header_ex::utils::HeaderRequestExt;
-use crate::p2p::{HeaderExError, P2pCmd, P2pError};
+use crate::p2p::{HeaderExError, P2pCmd, P2pError, RetryConfig};
 
 const MAX_AMOUNT_PER_REQ: u64 = 64;
 const MAX_CONCURRENT_REQS: usize = 8;
 
 type Result<T, E = P2pError> = std::result::Result<T, E>;
 
+/// Height, amount and attempt of the request, with its result.
+type Response = (u64, u64, u32, Result<Vec<ExtendedHeader>>);
+
 pub(crate) struct Session {
     next_height: u64,
     remaining_amount: u64,
     cmd_tx: mpsc::Sender<P2pCmd>,
-    response_tx: mpsc::Sender<(u64, u64, Result<Vec<ExtendedHeader>>)>,
-    response_rx: mpsc::Receiver<(u64, u64, Result<Vec<ExtendedHeader>>)>,
+    retry: RetryConfig,
+    response_tx: mpsc::Sender<Response>,
+    response_rx: mpsc::Receiver<Response>,
     ongoing: usize,
+    // Cancels the retries still waiting for their delay when the session is dropped
+    cancellation_token: CancellationToken,
 }
 
 impl Session {
-    pub(crate) fn new(from_height: u64, amount: u64, cmd_tx: mpsc::Sender<P2pCmd>) -> Result<Self> {
+    pub(crate) fn new(
+        from_height: u64,
+        amount: u64,
+        cmd_tx: mpsc::Sender<P2pCmd>,
+        retry: RetryConfig,
+    ) -> Result<Self> {
         if from_height < 1 || amount < 1 {
             return Err(P2pError::HeaderEx(HeaderExError::InvalidRequest));
         }
@@ -33,9 +47,11 @@ impl Session {
             next_height: from_height,
             remaining_amount: amount,
             cmd_tx,
+            retry,
             response_tx,
             response_rx,
             ongoing: 0,
+            cancellation_token: CancellationToken::new(),
         })
     }
 
@@ -47,11 +63,11 @@ impl Session {
                 break;
             }
 
-            self.send_next_request().await?;
+            self.send_next_request();
         }
 
         while self.ongoing > 0 {
-            let (height, requested_amount, res) = self.recv_response().await;
+            let (height, requested_amount, attempt, res) = self.recv_response().await;
 
             match res {
                 Ok(headers) => {
@@ -63,15 +79,17 @@ impl Session {
                         // Reschedule the missing sub-range
                         let height = height + headers_len;
                         let amount = requested_amount - headers_len;
-                        self.send_request(height, amount).await?;
+                        self.send_request(height, amount, 1, None);
                     } else {
                         // Schedule next request
-                        self.send_next_request().await?;
+                        self.send_next_request();
                     }
                 }
-                Err(P2pError::HeaderEx(e)) => {
-                    debug!("HeaderEx error: {e}");
-                    self.send_request(height, requested_amount).await?;
+                Err(P2pError::HeaderEx(e)) if attempt < self.retry.max_attempts => {
+                    let delay = self.retry.delay(attempt);
+                    debug!("HeaderEx error: {e}. Retrying in {delay:?}.");
+                    // Responses of the other requests are handled while the retry waits
+                    self.send_request(height, requested_amount, attempt + 1, Some(delay));
                 }
                 Err(e) => return Err(e),
             }
@@ -84,56 +102,67 @@ impl Session {
         Ok(headers)
     }
 
-    async fn recv_response(&mut self) -> (u64, u64, Result<Vec<ExtendedHeader>>) {
-        let (height, requested_amount, res) =
-            self.response_rx.recv().await.expect("channel never closes");
+    async fn recv_response(&mut self) -> Response {
+        let response = self.response_rx.recv().await.expect("channel never closes");
 
         self.ongoing -= 1;
 
-        (height, requested_amount, res)
+        response
     }
 
-    pub(crate) async fn send_next_request(&mut self) -> Result<()> {
+    fn send_next_request(&mut self) {
         if self.remaining_amount == 0 {
-            return Ok(());
+            return;
         }
 
         let amount = self.remaining_amount.min(MAX_AMOUNT_PER_REQ);
-        self.send_request(self.next_height, amount).await?;
+        self.send_request(self.next_height, amount, 1, None);
 
         self.next_height += amount;
         self.remaining_amount -= amount;
-
-        Ok(())
     }
 
-    pub(crate) async fn send_request(&mut self, height: u64, amount: u64) -> Result<()> {
-        debug!("Fetching batch {} until {}", height, height + amount - 1);
-
-        let request = HeaderRequest::with_origin(height, amount);
-        let (tx, rx) = oneshot::channel();
-
-        self.cmd_tx
-            .send(P2pCmd::HeaderExRequest {
-                request,
-                respond_to: tx,
-            })
-            .await
-            .map_err(|_| P2pError::WorkerDied)?;
-
+    /// Send the request to the [`P2p`] worker, after the `delay` if given, and forward
+    /// its result to the session.
+    ///
+    /// [`P2p`]: crate::p2p::P2p
+    fn send_request(&mut self, height: u64, amount: u64, attempt: u32, delay: Option<Duration>) {
+        let cmd_tx = self.cmd_tx.clone();
         let response_tx = self.response_tx.clone();
 
-        spawn(async move {
-            let result = match rx.await {
-                Ok(result) => result,
+        spawn_cancellable(self.cancellation_token.child_token(), async move {
+            match delay {
+                Some(delay) => {
+                    sleep(delay).await;
+                    debug!("Retrying batch {} until {}", height, height + amount - 1);
+                }
+                None => debug!("Fetching batch {} until {}", height, height + amount - 1),
+            }
+
+            let request = HeaderRequest::with_origin(height, amount);
+            let (tx, rx) = oneshot::channel();
+
+            let result = match cmd_tx
+                .send(P2pCmd::HeaderExRequest {
+                    request,
+                    respond_to: tx,
+                })
+                .await
+            {
+                Ok(()) => rx.await.unwrap_or(Err(P2pError::WorkerDied)),
                 Err(_) => Err(P2pError::WorkerDied),
             };
-            let _ = response_tx.send((height, amount, result)).await;
+
+            let _ = response_tx.send((height, amount, attempt, result)).await;
         });
 
         self.ongoing += 1;
+    }
+}
 
-        Ok(())
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.cancellation_token.cancel();
     }
 }
 
@@ -156,7 +185,8 @@ mod tests {
         let mut gen = ExtendedHeaderGenerator::new();
         let headers = gen.next_many(64);
 
-        let mut session = Session::new(1, 64, p2p_mock.cmd_tx.clone()).unwrap();
+        let mut session =
+            Session::new(1, 64, p2p_mock.cmd_tx.clone(), RetryConfig::default()).unwrap();
         let (result_tx, result_rx) = oneshot::channel();
         spawn(async move {
             let res = session.run().await;
@@ -185,7 +215,8 @@ mod tests {
         let mut gen = ExtendedHeaderGenerator::new();
         let headers = gen.next_many(520);
 
-        let mut session = Session::new(1, 520, p2p_mock.cmd_tx.clone()).unwrap();
+        let mut session =
+            Session::new(1, 520, p2p_mock.cmd_tx.clone(), RetryConfig::default()).unwrap();
         let (result_tx, result_rx) = oneshot::channel();
         spawn(async move {
             let res = session.run().await;
@@ -221,7 +252,8 @@ mod tests {
         let mut gen = ExtendedHeaderGenerator::new();
         let headers = gen.next_many(64);
 
-        let mut session = Session::new(1, 64, p2p_mock.cmd_tx.clone()).unwrap();
+        let mut session =
+            Session::new(1, 64, p2p_mock.cmd_tx.clone(), RetryConfig::default()).unwrap();
         let (result_tx, result_rx) = oneshot::channel();
         spawn(async move {
             let res = session.run().await;
@@ -246,11 +278,100 @@ mod tests {
         assert_eq!(headers, received_headers);
     }
 
+    #[async_test]
+    async fn retry_does_not_block_other_requests() {
+        let (_p2p, mut p2p_mock) = P2p::<InMemoryStore>::mocked();
+        let mut gen = ExtendedHeaderGenerator::new();
+        let headers = gen.next_many(576);
+        let retry = RetryConfig {
+            base_delay: Duration::from_millis(500),
+            jitter: 0.0,
+            ..RetryConfig::default()
+        };
+
+        let mut session = Session::new(1, 576, p2p_mock.cmd_tx.clone(), retry).unwrap();
+        let (result_tx, result_rx) = oneshot::channel();
+        spawn(async move {
+            let res = session.run().await;
+            result_tx.send(res).unwrap();
+        });
+
+        for i in 0..8 {
+            let (height, amount, respond_to) =
+                p2p_mock.expect_header_request_for_height_cmd().await;
+            assert_eq!(height, 1 + 64 * i);
+            assert_eq!(amount, 64);
+
+            if height == 1 {
+                respond_to
+                    .send(Err(P2pError::HeaderEx(HeaderExError::HeaderNotFound)))
+                    .unwrap();
+            } else {
+                let start = (height - 1) as usize;
+                respond_to
+                    .send(Ok(headers[start..start + 64].to_vec()))
+                    .unwrap();
+            }
+        }
+
+        // The last batch is requested before the delay of the retry elapses
+        let (height, amount, respond_to) = p2p_mock.expect_header_request_for_height_cmd().await;
+        assert_eq!(height, 513);
+        assert_eq!(amount, 64);
+        respond_to.send(Ok(headers[512..].to_vec())).unwrap();
+        p2p_mock.expect_no_cmd().await;
+
+        let (height, amount, respond_to) = p2p_mock.expect_header_request_for_height_cmd().await;
+        assert_eq!(height, 1);
+        assert_eq!(amount, 64);
+        respond_to.send(Ok(headers[..64].to_vec())).unwrap();
+
+        p2p_mock.expect_no_cmd().await;
+
+        let received_headers = result_rx.await.unwrap().unwrap();
+        assert_eq!(headers, received_headers);
+    }
+
+    #[async_test]
+    async fn gives_up_after_max_attempts() {
+        let (_p2p, mut p2p_mock) = P2p::<InMemoryStore>::mocked();
+        let retry = RetryConfig {
+            max_attempts: 2,
+            jitter: 0.0,
+            ..RetryConfig::default()
+        };
+
+        let mut session = Session::new(1, 64, p2p_mock.cmd_tx.clone(), retry).unwrap();
+        let (result_tx, result_rx) = oneshot::channel();
+        spawn(async move {
+            let res = session.run().await;
+            result_tx.send(res).unwrap();
+        });
+
+        for _ in 0..2 {
+            let (height, amount, respond_to) =
+                p2p_mock.expect_header_request_for_height_cmd().await;
+            assert_eq!(height, 1);
+            assert_eq!(amount, 64);
+            respond_to
+                .send(Err(P2pError::HeaderEx(HeaderExError::HeaderNotFound)))
+                .unwrap();
+        }
+
+        p2p_mock.expect_no_cmd().await;
+
+        assert!(matches!(
+            result_rx.await,
+            Ok(Err(P2pError::HeaderEx(HeaderExError::HeaderNotFound)))
+        ));
+    }
+
     #[async_test]
     async fn no_peers_is_fatal() {
         let (_p2p, mut p2p_mock) = P2p::<InMemoryStore>::mocked();
 
-        let mut session = Session::new(1, 64, p2p_mock.cmd_tx.clone()).unwrap();
+        let mut session =
+            Session::new(1, 64, p2p_mock.cmd_tx.clone(), RetryConfig::default()).unwrap();
         let (result_tx, result_rx) = oneshot::channel();
         spawn(async move {
             let res = session.run().await;
@@ -1,23 +1,40 @@
 //! Primitives related to the [`ExtendedHeader`] storage.
 
 use std::fmt::Debug;
+use std::future::Future;
 use std::io;
 use std::ops::{Bound, RangeBounds, RangeInclusive};
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::{Component, Path, PathBuf};
 
 use async_trait::async_trait;
+use blockstore::Blockstore;
+use celestia_tendermint::Time;
 use celestia_types::hash::Hash;
-use celestia_types::ExtendedHeader;
+use celestia_types::nmt::{Namespace, NS_ID_V0_SIZE, NS_SIZE};
+use celestia_types::shwap::{IdLayout, ShwapCid};
+use celestia_types::{ClockCheck, DataAvailabilityHeader, ErrorKind, ExtendedHeader};
+use cid::{Cid, CidGeneric};
+use futures::stream::{self, BoxStream, StreamExt};
+use futures::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt, TryStreamExt};
+use libp2p::Multiaddr;
+use prost::Message;
 use thiserror::Error;
 
 pub use in_memory_store::InMemoryStore;
 #[cfg(target_arch = "wasm32")]
-pub use indexed_db_store::IndexedDbStore;
+pub use indexed_db_store::{BlockstoreQuota, BlockstoreUsage, IndexedDbStore};
+#[cfg(all(not(target_arch = "wasm32"), feature = "rocksdb"))]
+#[cfg_attr(docs_rs, doc(cfg(feature = "rocksdb")))]
+pub use rocksdb_store::RocksDbStore;
 #[cfg(not(target_arch = "wasm32"))]
 pub use sled_store::SledStore;
 
 mod in_memory_store;
 #[cfg(target_arch = "wasm32")]
 mod indexed_db_store;
+#[cfg(all(not(target_arch = "wasm32"), feature = "rocksdb"))]
+mod rocksdb_store;
 #[cfg(not(target_arch = "wasm32"))]
 mod sled_store;
 
@@ -25,12 +42,26 @@ use crate::utils::validate_headers;
 
 type Result<T, E = StoreError> = std::result::Result<T, E>;
 
+/// Number of the headers appended at once by [`Store::import`].
+const IMPORT_BATCH_SIZE: usize = 512;
+/// Number of the headers read at once by [`Store::headers_range`].
+pub(crate) const HEADERS_RANGE_BATCH_SIZE: u64 = 512;
+
 /// An asynchronous [`ExtendedHeader`] storage.
 ///
-/// Currently it is required that all the headers are inserted to the storage
-/// in order, starting from the genesis.
+/// The store always holds a continuous range of headers. New headers are appended
+/// above the head, starting from the genesis, or prepended below the tail when
+/// synchronizing the historical headers backwards.
+///
+/// Custom backends only need to implement the required methods, the remaining
+/// ones have default implementations built on top of them. Implementations should
+/// report failures of the underlying storage with [`StoreError::BackingStoreError`]
+/// or [`StoreError::StoredDataError`].
+///
+/// Each store is also the [`Blockstore`] of the shwap blocks retrieved by the node,
+/// which are then provided to the other peers.
 #[async_trait]
-pub trait Store: Send + Sync + Debug {
+pub trait Store: Blockstore + Send + Sync + Debug {
     /// Returns the [`ExtendedHeader`] with the highest height.
     async fn get_head(&self) -> Result<ExtendedHeader>;
 
@@ -77,15 +108,92 @@ pub trait Store: Send + Sync + Debug {
         Ok(headers)
     }
 
+    /// Returns the stream of the headers from the given heights range, in ascending order.
+    ///
+    /// Unlike [`Store::get_range`], the range is clamped to the heights present in the
+    /// store and the headers are read lazily, in batches with [`Store::snapshot`], so
+    /// the whole store can be iterated without holding it in memory.
+    ///
+    /// # Errors
+    ///
+    /// The stream yields [`StoreError::NotFound`] if the store is empty. Headers removed
+    /// from the tail while the stream is consumed are skipped.
+    fn headers_range<R>(&self, range: R) -> BoxStream<'_, Result<ExtendedHeader>>
+    where
+        R: RangeBounds<u64>,
+    {
+        let bounds = (range.start_bound().cloned(), range.end_bound().cloned());
+
+        headers_in_batches(self, bounds, move |heights| async move {
+            let snapshot = self.snapshot(heights).await?;
+            Ok(snapshot.headers.into_iter().map(|h| h.header).collect())
+        })
+    }
+
+    /// Returns the first stored header with the time at or after the given one.
+    ///
+    /// Header times increase with the height, so the header is found with a binary
+    /// search over the stored heights.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError::NotFound`] if all the stored headers are older than `time`.
+    async fn get_by_time(&self, time: Time) -> Result<ExtendedHeader> {
+        let head = self.get_head().await?;
+
+        if head.time() < time {
+            return Err(StoreError::NotFound);
+        }
+
+        let mut low = self.tail_height().await?;
+        let mut high = head.height().value();
+        let mut found = head;
+
+        // the header at `high` is always the `found` one, which is not older than `time`
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let header = self.get_by_height(mid).await?;
+
+            if header.time() < time {
+                low = mid + 1;
+            } else {
+                high = mid;
+                found = header;
+            }
+        }
+
+        Ok(found)
+    }
+
     /// Returns the highest known height.
     async fn head_height(&self) -> Result<u64>;
 
+    /// Returns the lowest height still present in the store.
+    ///
+    /// It is the genesis height, unless older headers were removed with [`Store::remove_tail`].
+    async fn tail_height(&self) -> Result<u64>;
+
     /// Returns true if hash exists in the store.
     async fn has(&self, hash: &Hash) -> bool;
 
     /// Returns true if height exists in the store.
     async fn has_at(&self, height: u64) -> bool;
 
+    /// Remove the header with the lowest height from the store.
+    ///
+    /// # Errors
+    ///
+    /// Head of the store is never removed, attempting to do so results in
+    /// [`StoreError::RemovingHead`].
+    async fn remove_tail(&self) -> Result<()>;
+
+    /// Remove the [`Blockstore`] blocks of the shwap containers of the heights below
+    /// the given one, returning the number of the removed blocks.
+    ///
+    /// The height of a block is taken from its [`Cid`] when it is stored. Blocks with
+    /// other CIDs than the ones of the shwap containers are never removed.
+    async fn remove_blocks_below(&self, height: u64) -> Result<u64>;
+
     /// Append single header maintaining continuity from the genesis to the head.
     ///
     /// # Note
@@ -98,21 +206,146 @@ pub trait Store: Send + Sync + Debug {
     /// # Note
     ///
     /// This method does not validate or verify that `headers` are indeed correct.
+    ///
+    /// The headers are written atomically with [`Store::write_batch`].
     async fn append_unchecked(&self, headers: Vec<ExtendedHeader>) -> Result<()> {
-        for header in headers.into_iter() {
-            self.append_single_unchecked(header).await?;
+        let mut batch = WriteBatch::new();
+
+        for header in headers {
+            batch.append(header);
+        }
+
+        self.write_batch(batch).await
+    }
+
+    /// Prepend single header right below the tail, maintaining continuity from the tail to the head.
+    ///
+    /// If the store is empty, the header becomes both the head and the tail of the store,
+    /// regardless of its height. This allows starting the store from a trusted header
+    /// and synchronizing the historical headers backwards.
+    ///
+    /// # Note
+    ///
+    /// This method does not validate or verify that `header` is indeed correct.
+    async fn prepend_single_unchecked(&self, header: ExtendedHeader) -> Result<()>;
+
+    /// Prepend a range of headers, sorted by ascending height, right below the tail.
+    ///
+    /// # Note
+    ///
+    /// This method does not validate or verify that `headers` are indeed correct.
+    ///
+    /// The headers are written atomically with [`Store::write_batch`].
+    async fn prepend_unchecked(&self, headers: Vec<ExtendedHeader>) -> Result<()> {
+        let mut batch = WriteBatch::new();
+
+        for header in headers.into_iter().rev() {
+            batch.prepend(header);
+        }
+
+        self.write_batch(batch).await
+    }
+
+    /// Apply all the writes of the [`WriteBatch`], or none of them if any fails.
+    ///
+    /// The writes are applied in the order they were added to the batch, with the same
+    /// checks as [`Store::append_single_unchecked`] and [`Store::prepend_single_unchecked`].
+    /// Other readers of the store never observe only a part of the batch.
+    ///
+    /// # Note
+    ///
+    /// The default implementation applies the writes one by one and stops at the first
+    /// failure, leaving the preceding ones in the store. Backends should override it.
+    async fn write_batch(&self, batch: WriteBatch) -> Result<()> {
+        for op in batch.into_ops() {
+            match op {
+                WriteOp::Append(header) => self.append_single_unchecked(header).await?,
+                WriteOp::Prepend(header) => self.prepend_single_unchecked(header).await?,
+            }
         }
 
         Ok(())
     }
 
+    /// Read a consistent view of the headers from the given heights range, together
+    /// with their [`SamplingMetadata`].
+    ///
+    /// The range is clamped to the heights present in the store, so it may hold fewer
+    /// headers than requested, eg. when the tail was removed in the meantime.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError::NotFound`] if the store is empty.
+    ///
+    /// # Note
+    ///
+    /// The default implementation reads the headers one by one, so it is consistent only
+    /// if nothing writes to the store at the same time. Backends should override it.
+    async fn snapshot<R>(&self, range: R) -> Result<StoreSnapshot>
+    where
+        R: RangeBounds<u64> + Send,
+    {
+        let head_height = self.head_height().await?;
+        let tail_height = self.tail_height().await?;
+        let mut headers = Vec::new();
+
+        for height in clamp_to_stored(range, tail_height, head_height) {
+            headers.push(SnapshotHeader {
+                header: self.get_by_height(height).await?,
+                sampling_metadata: self.get_sampling_metadata(height).await?,
+            });
+        }
+
+        Ok(StoreSnapshot {
+            tail_height,
+            head_height,
+            headers,
+        })
+    }
+
+    /// Prepend a range of headers, sorted by ascending height, right below the tail.
+    ///
+    /// The headers are verified backwards, starting from the current tail of the store.
+    async fn prepend(&self, headers: Vec<ExtendedHeader>) -> Result<()> {
+        let headers = validate_headers(headers).await?;
+
+        let Some((first, rest)) = headers.split_first() else {
+            return Ok(());
+        };
+        first.verify_adjacent_range(rest)?;
+
+        match self.tail_height().await {
+            Ok(tail_height) => {
+                let tail = self.get_by_height(tail_height).await?;
+                let last = rest.last().unwrap_or(first);
+                last.verify_adjacent(&tail)?;
+            }
+            // Empty store, we can not verify
+            Err(StoreError::NotFound) => {}
+            Err(e) => return Err(e),
+        }
+
+        self.prepend_unchecked(headers).await
+    }
+
     /// Append single header maintaining continuity from the genesis to the head.
     async fn append_single(&self, header: ExtendedHeader) -> Result<()> {
+        self.append_single_with_clock(header, ClockCheck::System)
+            .await
+    }
+
+    /// Same as [`Store::append_single`], but checks the time of the header with
+    /// the given [`ClockCheck`].
+    async fn append_single_with_clock(
+        &self,
+        header: ExtendedHeader,
+        clock: ClockCheck,
+    ) -> Result<()> {
         header.validate()?;
 
         match self.get_head().await {
             Ok(head) => {
-                head.verify(&header)?;
+                head.verify_with_clock(&header, clock)?;
             }
             // Empty store, we can not verify
             Err(StoreError::NotFound) => {}
@@ -124,11 +357,21 @@ pub trait Store: Send + Sync + Debug {
 
     /// Append a range of headers maintaining continuity from the genesis to the head.
     async fn append(&self, headers: Vec<ExtendedHeader>) -> Result<()> {
-        validate_headers(&headers).await?;
+        self.append_with_clock(headers, ClockCheck::System).await
+    }
+
+    /// Same as [`Store::append`], but checks the time of the headers with the given
+    /// [`ClockCheck`].
+    async fn append_with_clock(
+        &self,
+        headers: Vec<ExtendedHeader>,
+        clock: ClockCheck,
+    ) -> Result<()> {
+        let headers = validate_headers(headers).await?;
 
         match self.get_head().await {
             Ok(head) => {
-                head.verify_adjacent_range(&headers)?;
+                head.verify_adjacent_range_with_clock(&headers, clock)?;
             }
             // Empty store, we can not verify
             Err(StoreError::NotFound) => {}
@@ -137,6 +380,775 @@ pub trait Store: Send + Sync + Debug {
 
         self.append_unchecked(headers).await
     }
+
+    /// Export the headers from the given heights range as JSON lines.
+    ///
+    /// Each line holds a single [`ExtendedHeader`], together with its
+    /// [`DataAvailabilityHeader`], in the JSON format of the celestia node. Headers are
+    /// written in the ascending order of heights. If start of the range is unbounded,
+    /// the export starts from the tail of the store.
+    ///
+    /// Returns the number of exported headers.
+    ///
+    /// [`DataAvailabilityHeader`]: celestia_types::DataAvailabilityHeader
+    async fn export<R, W>(&self, range: R, writer: &mut W) -> Result<u64>
+    where
+        R: RangeBounds<u64> + Send,
+        W: AsyncWrite + Unpin + Send,
+    {
+        let head_height = self.head_height().await?;
+        let start = match range.start_bound() {
+            Bound::Unbounded => Bound::Included(self.tail_height().await?),
+            bound => bound.cloned(),
+        };
+        let range = to_headers_range((start, range.end_bound().cloned()), head_height)?;
+
+        let mut exported = 0;
+
+        for height in range {
+            let header = self.get_by_height(height).await?;
+            let mut line = serde_json::to_vec(&header)
+                .map_err(|e| StoreError::StoredDataError(e.to_string()))?;
+            line.push(b'\n');
+
+            writer.write_all(&line).await?;
+            exported += 1;
+        }
+
+        writer.flush().await?;
+
+        Ok(exported)
+    }
+
+    /// Import the headers written by [`Store::export`].
+    ///
+    /// Headers are validated and verified the same way as with [`Store::append`],
+    /// so they need to continue from the current head of the store. If the store
+    /// is empty, the snapshot can start at any height, but its first header can not
+    /// be verified, so the snapshot has to come from a trusted source.
+    ///
+    /// Returns the number of imported headers.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError::InvalidImport`] if a line doesn't hold a valid header. Headers
+    /// from the batches before the failure stay in the store.
+    async fn import<R>(&self, reader: R) -> Result<u64>
+    where
+        R: AsyncBufRead + Unpin + Send,
+    {
+        let mut lines = reader.lines();
+        let mut line_number = 0;
+        let mut imported = 0;
+        let mut batch = Vec::with_capacity(IMPORT_BATCH_SIZE);
+
+        while let Some(line) = lines.try_next().await? {
+            line_number += 1;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let header = serde_json::from_str(&line)
+                .map_err(|e| StoreError::InvalidImport(line_number, e.to_string()))?;
+            batch.push(header);
+
+            if batch.len() == IMPORT_BATCH_SIZE {
+                imported += batch.len() as u64;
+                append_imported(self, std::mem::take(&mut batch)).await?;
+            }
+        }
+
+        imported += batch.len() as u64;
+        append_imported(self, batch).await?;
+
+        Ok(imported)
+    }
+
+    /// Set or update the [`SamplingMetadata`] of the header at the given height.
+    ///
+    /// The `accepted` flag replaces the previous one, while the `cids` are merged
+    /// with the already sampled ones.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError::NotFound`] if there is no header at the given height.
+    async fn update_sampling_metadata(
+        &self,
+        height: u64,
+        accepted: bool,
+        cids: Vec<Cid>,
+    ) -> Result<()>;
+
+    /// Returns the [`SamplingMetadata`] of the header at the given height, or `None`
+    /// if it wasn't sampled yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError::NotFound`] if there is no header at the given height.
+    async fn get_sampling_metadata(&self, height: u64) -> Result<Option<SamplingMetadata>>;
+
+    /// Returns the lowest height in the store which wasn't sampled yet, or `None`
+    /// if all the stored headers were sampled.
+    ///
+    /// Allows resuming the sampling after a restart, without sampling the same
+    /// heights again.
+    async fn next_unsampled_height(&self) -> Result<Option<u64>> {
+        let ranges = self.unsampled_ranges().await?;
+        Ok(ranges.first().map(|range| *range.start()))
+    }
+
+    /// Returns the ranges of the stored heights without the [`SamplingMetadata`],
+    /// in ascending order.
+    ///
+    /// # Note
+    ///
+    /// The default implementation reads the sampled heights of the whole store. Backends
+    /// keeping the ranges of the sampled heights next to the metadata should override it.
+    async fn unsampled_ranges(&self) -> Result<Vec<RangeInclusive<u64>>> {
+        let head_height = match self.head_height().await {
+            Ok(height) => height,
+            Err(StoreError::NotFound) => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let tail_height = self.tail_height().await?;
+        let sampled = SampledRanges::from_heights(self.sampled_heights(..).await?);
+
+        Ok(sampled.missing(tail_height..=head_height))
+    }
+
+    /// Returns the heights from the given range with the [`SamplingMetadata`] stored,
+    /// in ascending order.
+    ///
+    /// Both the accepted and rejected blocks are included. The range is clamped to the
+    /// heights present in the store, so the result is empty for an empty store.
+    async fn sampled_heights<R>(&self, range: R) -> Result<Vec<u64>>
+    where
+        R: RangeBounds<u64> + Send,
+    {
+        let head_height = match self.head_height().await {
+            Ok(height) => height,
+            Err(StoreError::NotFound) => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let tail_height = self.tail_height().await?;
+        let mut heights = Vec::new();
+
+        for height in clamp_to_stored(range, tail_height, head_height) {
+            if self.get_sampling_metadata(height).await?.is_some() {
+                heights.push(height);
+            }
+        }
+
+        Ok(heights)
+    }
+
+    /// Returns the heights from the given range whose blocks may hold data of the
+    /// given namespace, in ascending order.
+    ///
+    /// A block is included if the namespace falls within the namespace range of any of
+    /// its row roots in the [`DataAvailabilityHeader`]. Row roots only commit to the
+    /// lowest and highest namespace of their shares, so a returned block doesn't have to
+    /// hold the data, but the blocks which are not returned certainly don't. The secondary
+    /// reserved namespaces, holding only the padding and parity shares, are never found.
+    /// The range is clamped to the heights present in the store, so the result is empty
+    /// for an empty store.
+    ///
+    /// # Note
+    ///
+    /// The default implementation reads all the headers of the range. Backends keeping
+    /// the namespace index of the stored headers, built with
+    /// `NamespaceRanges::index_keys`, should override it.
+    async fn heights_with_namespace<R>(&self, namespace: Namespace, range: R) -> Result<Vec<u64>>
+    where
+        R: RangeBounds<u64> + Send,
+    {
+        if let Err(StoreError::NotFound) = self.head_height().await {
+            return Ok(Vec::new());
+        }
+
+        self.headers_range(range)
+            .try_filter_map(|header| async move {
+                let height = header.height().value();
+                Ok(NamespaceRanges::new(&header.dah)
+                    .contains(namespace)
+                    .then_some(height))
+            })
+            .try_collect()
+            .await
+    }
+
+    /// Returns the latest verified network head, persisted with [`Store::set_network_head`].
+    ///
+    /// Allows a restarted node to continue from it within the trusting period, instead of
+    /// requesting the network head from the trusted peers.
+    ///
+    /// By default nothing is persisted and `None` is returned.
+    async fn get_network_head(&self) -> Result<Option<ExtendedHeader>> {
+        Ok(None)
+    }
+
+    /// Persist the latest verified network head.
+    ///
+    /// Unlike the headers appended to the store, it doesn't have to be adjacent to the
+    /// stored ones. By default the header is discarded.
+    async fn set_network_head(&self, _header: ExtendedHeader) -> Result<()> {
+        Ok(())
+    }
+
+    /// Returns the addresses of the known peers, persisted with [`Store::set_peer_addrs`].
+    ///
+    /// Allows dialing the previously seen peers after a restart, before relying
+    /// on the bootnodes.
+    ///
+    /// By default nothing is persisted and no addresses are returned.
+    async fn get_peer_addrs(&self) -> Result<Vec<PeerAddr>> {
+        Ok(Vec::new())
+    }
+
+    /// Replace the persisted addresses of the known peers. By default they are discarded.
+    async fn set_peer_addrs(&self, _addrs: Vec<PeerAddr>) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Returns the height of the block the shwap container with the given [`Cid`] belongs to,
+/// or `None` if it is not a CID of a shwap container.
+pub(crate) fn shwap_block_height<const S: usize>(cid: &CidGeneric<S>) -> Option<u64> {
+    [IdLayout::V1, IdLayout::Draft]
+        .into_iter()
+        .filter_map(|layout| ShwapCid::from_cid(cid, layout).ok())
+        .map(|id| id.block_height())
+        // Codecs of the namespaced data are the same in both layouts, so their ids decode
+        // in both, with the height byte swapped in the wrong one. Swapped heights are far
+        // above the real ones, so the lowest is the right one.
+        .min()
+}
+
+/// Writes applied to the [`Store`] atomically with [`Store::write_batch`].
+#[derive(Debug, Clone, Default)]
+pub struct WriteBatch {
+    ops: Vec<WriteOp>,
+}
+
+/// A single write of the [`WriteBatch`].
+#[derive(Debug, Clone)]
+pub enum WriteOp {
+    /// Append the header above the head, as with [`Store::append_single_unchecked`].
+    Append(ExtendedHeader),
+    /// Prepend the header below the tail, as with [`Store::prepend_single_unchecked`].
+    Prepend(ExtendedHeader),
+}
+
+impl WriteBatch {
+    /// Create an empty batch.
+    pub fn new() -> Self {
+        WriteBatch::default()
+    }
+
+    /// Add appending of the header to the batch.
+    pub fn append(&mut self, header: ExtendedHeader) -> &mut Self {
+        self.ops.push(WriteOp::Append(header));
+        self
+    }
+
+    /// Add prepending of the header to the batch.
+    pub fn prepend(&mut self, header: ExtendedHeader) -> &mut Self {
+        self.ops.push(WriteOp::Prepend(header));
+        self
+    }
+
+    /// Number of the writes in the batch.
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// Returns `true` if the batch has no writes.
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// Writes of the batch, in the order they were added.
+    pub fn ops(&self) -> &[WriteOp] {
+        &self.ops
+    }
+
+    /// Consume the batch, returning its writes in the order they were added.
+    pub fn into_ops(self) -> Vec<WriteOp> {
+        self.ops
+    }
+}
+
+/// Consistent view of a range of the [`Store`], read with [`Store::snapshot`].
+#[derive(Debug, Clone)]
+pub struct StoreSnapshot {
+    /// Height of the tail of the store when the snapshot was read.
+    pub tail_height: u64,
+    /// Height of the head of the store when the snapshot was read.
+    pub head_height: u64,
+    /// Headers of the requested range which were in the store, in ascending order.
+    pub headers: Vec<SnapshotHeader>,
+}
+
+/// A header read as a part of the [`StoreSnapshot`].
+#[derive(Debug, Clone)]
+pub struct SnapshotHeader {
+    /// The header.
+    pub header: ExtendedHeader,
+    /// Result of sampling the header's block, or `None` if it wasn't sampled yet.
+    pub sampling_metadata: Option<SamplingMetadata>,
+}
+
+/// Result of the data availability sampling of a block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SamplingMetadata {
+    /// Whether the block was accepted as available.
+    pub accepted: bool,
+    /// [`Cid`]s of the shwap containers that were sampled.
+    pub cids_sampled: Vec<Cid>,
+}
+
+/// Ranges of the heights with the [`SamplingMetadata`] stored, in ascending order.
+///
+/// Stores keep them next to the metadata and update both together, so that the unsampled
+/// heights are found without reading the metadata of each stored height.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct SampledRanges(Vec<RangeInclusive<u64>>);
+
+/// Serialized form of the [`SampledRanges`] in the persistent stores.
+#[derive(Clone, PartialEq, Message)]
+struct RawSampledRanges {
+    #[prost(uint64, repeated, tag = "1")]
+    starts: Vec<u64>,
+    #[prost(uint64, repeated, tag = "2")]
+    ends: Vec<u64>,
+}
+
+/// Serialized form of the [`SamplingMetadata`] in the persistent stores.
+#[derive(Clone, PartialEq, Message)]
+struct RawSamplingMetadata {
+    #[prost(bool, tag = "1")]
+    accepted: bool,
+    #[prost(bytes = "vec", repeated, tag = "2")]
+    cids_sampled: Vec<Vec<u8>>,
+}
+
+impl SampledRanges {
+    pub(crate) fn from_heights(heights: impl IntoIterator<Item = u64>) -> Self {
+        let mut ranges = SampledRanges::default();
+
+        for height in heights {
+            ranges.insert(height);
+        }
+
+        ranges
+    }
+
+    /// Add the height, merging it with the adjacent ranges.
+    pub(crate) fn insert(&mut self, height: u64) {
+        let ranges = &mut self.0;
+        // first range which contains the height or ends right below it, or is above it
+        let idx = ranges.partition_point(|range| range.end().saturating_add(1) < height);
+
+        match ranges.get(idx) {
+            Some(range) if range.contains(&height) => {}
+            Some(range) if *range.end() + 1 == height => {
+                let start = *range.start();
+
+                match ranges.get(idx + 1) {
+                    Some(next) if *next.start() == height + 1 => {
+                        ranges[idx] = start..=*next.end();
+                        ranges.remove(idx + 1);
+                    }
+                    _ => ranges[idx] = start..=height,
+                }
+            }
+            Some(range) if *range.start() == height + 1 => {
+                ranges[idx] = height..=*range.end();
+            }
+            _ => ranges.insert(idx, height..=height),
+        }
+    }
+
+    /// Remove all the heights below the given one.
+    pub(crate) fn remove_below(&mut self, height: u64) {
+        self.0.retain(|range| *range.end() >= height);
+
+        if let Some(first) = self.0.first_mut() {
+            if *first.start() < height {
+                *first = height..=*first.end();
+            }
+        }
+    }
+
+    /// Returns the sampled heights from the given range.
+    pub(crate) fn heights(&self, heights: RangeInclusive<u64>) -> Vec<u64> {
+        self.0
+            .iter()
+            .flat_map(|range| *range.start().max(heights.start())..=*range.end().min(heights.end()))
+            .collect()
+    }
+
+    /// Returns the ranges of the heights from the given range which weren't sampled.
+    pub(crate) fn missing(&self, heights: RangeInclusive<u64>) -> Vec<RangeInclusive<u64>> {
+        let (mut next, end) = heights.into_inner();
+        let mut missing = Vec::new();
+
+        for range in &self.0 {
+            if next > end || *range.start() > end {
+                break;
+            }
+
+            if *range.end() < next {
+                continue;
+            }
+
+            if *range.start() > next {
+                missing.push(next..=*range.start() - 1);
+            }
+
+            next = range.end().saturating_add(1);
+        }
+
+        if next <= end {
+            missing.push(next..=end);
+        }
+
+        missing
+    }
+
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        RawSampledRanges {
+            starts: self.0.iter().map(|range| *range.start()).collect(),
+            ends: self.0.iter().map(|range| *range.end()).collect(),
+        }
+        .encode_to_vec()
+    }
+
+    pub(crate) fn decode(bytes: &[u8]) -> Result<Self> {
+        let raw = RawSampledRanges::decode(bytes)
+            .map_err(|e| StoreError::StoredDataError(format!("Invalid sampled ranges: {e}")))?;
+
+        if raw.starts.len() != raw.ends.len() {
+            return Err(StoreError::StoredDataError(
+                "Invalid sampled ranges: unpaired bounds".to_string(),
+            ));
+        }
+
+        Ok(SampledRanges(
+            raw.starts
+                .into_iter()
+                .zip(raw.ends)
+                .map(|(start, end)| start..=end)
+                .collect(),
+        ))
+    }
+}
+
+impl SamplingMetadata {
+    /// Apply the new sampling result on top of the previous one.
+    pub(crate) fn updated(prev: Option<SamplingMetadata>, accepted: bool, cids: Vec<Cid>) -> Self {
+        let mut cids_sampled = prev.map(|prev| prev.cids_sampled).unwrap_or_default();
+
+        for cid in cids {
+            if !cids_sampled.contains(&cid) {
+                cids_sampled.push(cid);
+            }
+        }
+
+        SamplingMetadata {
+            accepted,
+            cids_sampled,
+        }
+    }
+
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        RawSamplingMetadata {
+            accepted: self.accepted,
+            cids_sampled: self.cids_sampled.iter().map(Cid::to_bytes).collect(),
+        }
+        .encode_to_vec()
+    }
+
+    pub(crate) fn decode(bytes: &[u8]) -> Result<Self> {
+        let raw = RawSamplingMetadata::decode(bytes)
+            .map_err(|e| StoreError::StoredDataError(format!("Invalid sampling metadata: {e}")))?;
+
+        let cids_sampled = raw
+            .cids_sampled
+            .iter()
+            .map(|cid| Cid::try_from(&cid[..]))
+            .collect::<Result<_, _>>()
+            .map_err(|e| StoreError::StoredDataError(format!("Invalid sampled cid: {e}")))?;
+
+        Ok(SamplingMetadata {
+            accepted: raw.accepted,
+            cids_sampled,
+        })
+    }
+}
+
+/// Size of the keys of the namespace index, see [`NamespaceRanges::index_keys`].
+pub(crate) const NAMESPACE_INDEX_KEY_SIZE: usize = 1 + NS_SIZE + 8;
+/// Number of bits of a [`Namespace`].
+const NAMESPACE_BITS: usize = NS_SIZE * 8;
+/// Number of bits below the first one differing between the bounds of a namespace range,
+/// up to which the range is indexed exactly.
+///
+/// A range is covered by at most `2 * NAMESPACE_INDEX_PRECISION + 2` keys, and the
+/// namespaces sharing all the higher bits with one of its bounds may be included too.
+const NAMESPACE_INDEX_PRECISION: usize = 8;
+/// Highest namespace which can hold the user data.
+const MAX_USER_NAMESPACE: Namespace = Namespace::const_v0([0xff; NS_ID_V0_SIZE]);
+
+/// Key of the namespace index, see [`NamespaceRanges::index_keys`].
+pub(crate) type NamespaceIndexKey = [u8; NAMESPACE_INDEX_KEY_SIZE];
+
+/// Namespace ranges covered by the row roots of a [`DataAvailabilityHeader`].
+///
+/// Overlapping ranges of the rows are merged, so a block holding only a few
+/// namespaces is described by a few ranges, regardless of its size. The secondary
+/// reserved namespaces are left out, as they only hold the padding and parity shares.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct NamespaceRanges(Vec<(Namespace, Namespace)>);
+
+impl NamespaceRanges {
+    pub(crate) fn new(dah: &DataAvailabilityHeader) -> Self {
+        let mut roots: Vec<_> = dah
+            .row_roots
+            .iter()
+            .filter_map(|root| {
+                let min = Namespace::from(root.min_namespace());
+                let max = Namespace::from(root.max_namespace());
+
+                if max >= Namespace::MIN_SECONDARY_RESERVED {
+                    (min <= MAX_USER_NAMESPACE).then_some((min, MAX_USER_NAMESPACE))
+                } else {
+                    Some((min, max))
+                }
+            })
+            .collect();
+        roots.sort_unstable();
+
+        let mut ranges: Vec<(Namespace, Namespace)> = Vec::with_capacity(roots.len());
+
+        for (min, max) in roots {
+            match ranges.last_mut() {
+                Some((_, last_max)) if min <= *last_max => *last_max = max.max(*last_max),
+                _ => ranges.push((min, max)),
+            }
+        }
+
+        NamespaceRanges(ranges)
+    }
+
+    /// Returns the keys indexing the block at the given height by its namespace ranges.
+    ///
+    /// Namespaces are treated as bit strings, and each range is covered by the prefixes
+    /// of the namespaces falling into it. A key is made of the length of the prefix,
+    /// its bits padded with zeros and the height, so the heights indexed under a prefix
+    /// are sorted and can be scanned with [`namespace_index_scans`].
+    pub(crate) fn index_keys(&self, height: u64) -> Vec<NamespaceIndexKey> {
+        let mut prefixes: Vec<_> = self
+            .0
+            .iter()
+            .flat_map(|(min, max)| covering_prefixes(min, max))
+            .collect();
+        prefixes.sort_unstable();
+        prefixes.dedup();
+
+        prefixes
+            .iter()
+            .map(|prefix| prefix.index_key(height))
+            .collect()
+    }
+
+    /// Returns `true` if any of the ranges contains the namespace.
+    pub(crate) fn contains(&self, namespace: Namespace) -> bool {
+        // ranges are disjoint and sorted, so only the last one starting at or below
+        // the namespace may contain it
+        let idx = self.0.partition_point(|(min, _)| *min <= namespace);
+
+        idx > 0 && namespace <= self.0[idx - 1].1
+    }
+
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.0.len() * 2 * NS_SIZE);
+
+        for (min, max) in &self.0 {
+            bytes.extend_from_slice(min.as_bytes());
+            bytes.extend_from_slice(max.as_bytes());
+        }
+
+        bytes
+    }
+
+    pub(crate) fn decode(bytes: &[u8]) -> Result<Self> {
+        let chunks = bytes.chunks_exact(2 * NS_SIZE);
+
+        if !chunks.remainder().is_empty() {
+            return Err(StoreError::StoredDataError(
+                "Invalid namespace ranges length".to_string(),
+            ));
+        }
+
+        chunks
+            .map(|chunk| {
+                let (min, max) = chunk.split_at(NS_SIZE);
+                Ok((Namespace::from_raw(min)?, Namespace::from_raw(max)?))
+            })
+            .collect::<Result<_, celestia_types::Error>>()
+            .map(NamespaceRanges)
+            .map_err(|e| StoreError::StoredDataError(format!("Invalid namespace range: {e}")))
+    }
+}
+
+/// Prefix of the bits of a [`Namespace`], with the remaining bits set to zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct NamespacePrefix {
+    len: usize,
+    bits: [u8; NS_SIZE],
+}
+
+impl NamespacePrefix {
+    fn new(namespace: &Namespace, len: usize) -> Self {
+        let mut bits = [0; NS_SIZE];
+        bits.copy_from_slice(namespace.as_bytes());
+
+        for (idx, byte) in bits.iter_mut().enumerate() {
+            let kept = len.saturating_sub(idx * 8).min(8) as u32;
+            *byte &= 0xffu8.checked_shl(8 - kept).unwrap_or(0);
+        }
+
+        NamespacePrefix { len, bits }
+    }
+
+    /// Prefix of the first `len + 1` bits of the namespace, with the last one flipped.
+    fn sibling(namespace: &Namespace, len: usize) -> Self {
+        let mut prefix = NamespacePrefix::new(namespace, len + 1);
+        prefix.bits[len / 8] ^= 0x80 >> (len % 8);
+        prefix
+    }
+
+    fn index_key(&self, height: u64) -> NamespaceIndexKey {
+        let mut key = [0; NAMESPACE_INDEX_KEY_SIZE];
+        key[0] = self.len as u8;
+        key[1..=NS_SIZE].copy_from_slice(&self.bits);
+        key[NS_SIZE + 1..].copy_from_slice(&height.to_be_bytes());
+        key
+    }
+}
+
+fn namespace_bit(namespace: &Namespace, idx: usize) -> bool {
+    namespace.as_bytes()[idx / 8] & (0x80 >> (idx % 8)) != 0
+}
+
+/// Returns the prefixes covering all the namespaces of the range.
+fn covering_prefixes(min: &Namespace, max: &Namespace) -> Vec<NamespacePrefix> {
+    let Some(diverged) =
+        (0..NAMESPACE_BITS).find(|&idx| namespace_bit(min, idx) != namespace_bit(max, idx))
+    else {
+        return vec![NamespacePrefix::new(min, NAMESPACE_BITS)];
+    };
+    let depth = (diverged + 1 + NAMESPACE_INDEX_PRECISION).min(NAMESPACE_BITS);
+    let mut prefixes = Vec::new();
+
+    // whole subtrees above the lower bound and below the upper bound
+    for idx in diverged + 1..depth {
+        if !namespace_bit(min, idx) {
+            prefixes.push(NamespacePrefix::sibling(min, idx));
+        }
+        if namespace_bit(max, idx) {
+            prefixes.push(NamespacePrefix::sibling(max, idx));
+        }
+    }
+
+    // and the subtrees of the bounds, which may reach beyond the range
+    prefixes.push(NamespacePrefix::new(min, depth));
+    prefixes.push(NamespacePrefix::new(max, depth));
+
+    prefixes
+}
+
+/// Returns the ranges of the namespace index keys to scan for the heights whose blocks
+/// may hold data of the namespace.
+///
+/// A block is indexed under the prefixes covering its namespace ranges, so the namespace
+/// is looked up under each of its prefixes. The same height may be found in several scans.
+pub(crate) fn namespace_index_scans(
+    namespace: Namespace,
+    heights: RangeInclusive<u64>,
+) -> impl Iterator<Item = RangeInclusive<NamespaceIndexKey>> {
+    (0..=NAMESPACE_BITS).map(move |len| {
+        let prefix = NamespacePrefix::new(&namespace, len);
+        prefix.index_key(*heights.start())..=prefix.index_key(*heights.end())
+    })
+}
+
+/// Returns the height of the namespace index key.
+pub(crate) fn namespace_index_height(key: &[u8]) -> Result<u64> {
+    key.get(NS_SIZE + 1..)
+        .and_then(|height| height.try_into().ok())
+        .map(u64::from_be_bytes)
+        .ok_or_else(|| StoreError::StoredDataError("Invalid namespace index key".to_string()))
+}
+
+/// Address of a known peer, persisted in the [`Store`] to bootstrap the node after a restart.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerAddr {
+    /// Address of the peer, ending with its `/p2p/<peer id>`.
+    pub addr: Multiaddr,
+    /// When the peer was last seen listening on the address.
+    pub last_seen: Time,
+}
+
+/// Serialized form of the [`PeerAddr`]s in the persistent stores.
+#[derive(Clone, PartialEq, Message)]
+struct RawPeerAddrs {
+    #[prost(message, repeated, tag = "1")]
+    addrs: Vec<RawPeerAddr>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct RawPeerAddr {
+    #[prost(bytes = "vec", tag = "1")]
+    addr: Vec<u8>,
+    #[prost(int64, tag = "2")]
+    last_seen: i64,
+}
+
+impl PeerAddr {
+    pub(crate) fn encode_all(addrs: &[PeerAddr]) -> Vec<u8> {
+        RawPeerAddrs {
+            addrs: addrs
+                .iter()
+                .map(|addr| RawPeerAddr {
+                    addr: addr.addr.to_vec(),
+                    last_seen: addr.last_seen.unix_timestamp(),
+                })
+                .collect(),
+        }
+        .encode_to_vec()
+    }
+
+    pub(crate) fn decode_all(bytes: &[u8]) -> Result<Vec<PeerAddr>> {
+        let raw = RawPeerAddrs::decode(bytes)
+            .map_err(|e| StoreError::StoredDataError(format!("Invalid peer addresses: {e}")))?;
+
+        raw.addrs
+            .into_iter()
+            .map(|raw| {
+                let addr = Multiaddr::try_from(raw.addr).map_err(|e| {
+                    StoreError::StoredDataError(format!("Invalid peer address: {e}"))
+                })?;
+                let last_seen = Time::from_unix_timestamp(raw.last_seen, 0).map_err(|e| {
+                    StoreError::StoredDataError(format!("Invalid peer last seen time: {e}"))
+                })?;
+
+                Ok(PeerAddr { addr, last_seen })
+            })
+            .collect()
+    }
 }
 
 /// Representation of all the errors that can occur when interacting with the [`Store`].
@@ -154,6 +1166,10 @@ pub enum StoreError {
     #[error("Failed to append header at height {1}, current head {0}")]
     NonContinuousAppend(u64, u64),
 
+    /// Inserted height is not directly preceding store's current tail.
+    #[error("Failed to prepend header at height {1}, current tail {0}")]
+    NonContinuousPrepend(u64, u64),
+
     /// Header validation has failed.
     #[error("Failed to validate header at height {0}")]
     HeaderChecksError(u64),
@@ -197,9 +1213,164 @@ pub enum StoreError {
     /// Invalid range of headers provided.
     #[error("Invalid headers range")]
     InvalidHeadersRange,
+
+    /// Header in the imported data could not be decoded.
+    #[error("Invalid header at line {0} of the import: {1}")]
+    InvalidImport(u64, String),
+
+    /// Attempted to remove the head of the store.
+    #[error("Head of the store cannot be removed")]
+    RemovingHead,
+}
+
+impl StoreError {
+    /// Returns `true` if the operation which failed with this error may succeed
+    /// when tried again.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            StoreError::ExecutorError(_) => true,
+            StoreError::CelestiaTypes(e) => e.is_retryable(),
+            _ => false,
+        }
+    }
+
+    /// Get the [`ErrorKind`] of the error.
+    pub fn kind(&self) -> ErrorKind {
+        self.code_and_kind().1
+    }
+
+    /// Get the stable code of the error, see [`celestia_types::Error::code`].
+    ///
+    /// Errors of the [`Store`] use the codes from `3001` to `3999`.
+    pub fn code(&self) -> u16 {
+        self.code_and_kind().0
+    }
+
+    fn code_and_kind(&self) -> (u16, ErrorKind) {
+        match self {
+            StoreError::HashExists(..) => (3001, ErrorKind::Validation),
+            StoreError::HeightExists(..) => (3002, ErrorKind::Validation),
+            StoreError::NonContinuousAppend(..) => (3003, ErrorKind::Validation),
+            StoreError::NonContinuousPrepend(..) => (3004, ErrorKind::Validation),
+            StoreError::HeaderChecksError(..) => (3005, ErrorKind::Validation),
+            StoreError::NotFound => (3006, ErrorKind::Store),
+            StoreError::LostHeight(..) => (3007, ErrorKind::Store),
+            StoreError::LostHash(..) => (3008, ErrorKind::Store),
+            StoreError::CelestiaTypes(e) => (e.code(), e.kind()),
+            StoreError::StoredDataError(..) => (3010, ErrorKind::Store),
+            StoreError::BackingStoreError(..) => (3011, ErrorKind::Store),
+            StoreError::ExecutorError(..) => (3012, ErrorKind::Internal),
+            StoreError::IoError(..) => (3013, ErrorKind::Store),
+            StoreError::OpenFailed(..) => (3014, ErrorKind::Store),
+            StoreError::InvalidHeadersRange => (3015, ErrorKind::Validation),
+            StoreError::InvalidImport(..) => (3016, ErrorKind::Encoding),
+            StoreError::RemovingHead => (3017, ErrorKind::Validation),
+        }
+    }
+}
+
+/// Append the imported headers, or start the empty store with them.
+async fn append_imported<S>(store: &S, headers: Vec<ExtendedHeader>) -> Result<()>
+where
+    S: Store + ?Sized,
+{
+    match store.head_height().await {
+        Ok(_) => store.append(headers).await,
+        // Only prepending allows the empty store to start at any height
+        Err(StoreError::NotFound) => store.prepend(headers).await,
+        Err(e) => Err(e),
+    }
+}
+
+/// Stream the headers of the `bounds` clamped to the heights present in the `store`,
+/// reading them with `read_batch` in the ranges of at most [`HEADERS_RANGE_BATCH_SIZE`]
+/// heights.
+pub(crate) fn headers_in_batches<'a, S, F, Fut>(
+    store: &'a S,
+    bounds: (Bound<u64>, Bound<u64>),
+    read_batch: F,
+) -> BoxStream<'a, Result<ExtendedHeader>>
+where
+    S: Store + ?Sized,
+    F: Fn(RangeInclusive<u64>) -> Fut + Copy + Send + 'a,
+    Fut: Future<Output = Result<Vec<ExtendedHeader>>> + Send + 'a,
+{
+    stream::once(async move {
+        let head_height = store.head_height().await?;
+        let tail_height = store.tail_height().await?;
+        Ok(clamp_to_stored(bounds, tail_height, head_height))
+    })
+    .map_ok(move |heights| {
+        stream::try_unfold(heights, move |heights| async move {
+            if heights.is_empty() {
+                return Ok(None);
+            }
+
+            let (start, end) = heights.into_inner();
+            let batch_end = end.min(start.saturating_add(HEADERS_RANGE_BATCH_SIZE - 1));
+            let headers = read_batch(start..=batch_end).await?;
+
+            // `batch_end` is lower than `end`, so it can't overflow
+            let rest = if batch_end < end {
+                batch_end + 1..=end
+            } else {
+                RangeInclusive::new(1, 0)
+            };
+
+            Ok(Some((stream::iter(headers.into_iter().map(Ok)), rest)))
+        })
+        .try_flatten()
+    })
+    .try_flatten()
+    .boxed()
+}
+
+/// Convert the range to the inclusive range of the heights between the tail and the head.
+///
+/// Unlike [`to_headers_range`], heights outside of the store are skipped instead of
+/// failing, so the result may be empty.
+pub(crate) fn clamp_to_stored(
+    bounds: impl RangeBounds<u64>,
+    tail_height: u64,
+    head_height: u64,
+) -> RangeInclusive<u64> {
+    let start = match bounds.start_bound() {
+        Bound::Unbounded => tail_height,
+        Bound::Included(&x) => x.max(tail_height),
+        Bound::Excluded(&x) => x.saturating_add(1).max(tail_height),
+    };
+    let end = match bounds.end_bound() {
+        Bound::Unbounded => head_height,
+        Bound::Included(&x) => x.min(head_height),
+        // empty range if it ends before the first height
+        Bound::Excluded(&0) => return RangeInclusive::new(1, 0),
+        Bound::Excluded(&x) => (x - 1).min(head_height),
+    };
+
+    start..=end
 }
 
 /// a helper function to convert any kind of range to the inclusive range of header heights.
+/// Returns the path of the network's database in the given directory.
+///
+/// The network id of the custom networks comes from the user, so it must be a single
+/// normal path component, not escaping the directory.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn network_db_path(dir: &Path, network_id: &str) -> Result<PathBuf> {
+    let mut components = Path::new(network_id).components();
+
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(name)), None)
+            if name == network_id && !network_id.contains(['/', '\\']) =>
+        {
+            Ok(dir.join(name))
+        }
+        _ => Err(StoreError::OpenFailed(format!(
+            "Invalid network id: {network_id:?}"
+        ))),
+    }
+}
+
 fn to_headers_range(bounds: impl RangeBounds<u64>, last_index: u64) -> Result<RangeInclusive<u64>> {
     let start = match bounds.start_bound() {
         // in case of unbounded, default to the first height
@@ -229,9 +1400,372 @@ fn to_headers_range(bounds: impl RangeBounds<u64>, last_index: u64) -> Result<Ra
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeSet;
     use std::ops::Bound;
+    use std::time::Duration;
+
+    use celestia_types::nmt::Namespace;
+    use celestia_types::shwap::{NamespacedDataId, RowId, SampleId};
+    use celestia_types::test_utils::{invalidate, ExtendedHeaderGenerator};
+    use celestia_types::DEFAULT_MAX_CLOCK_DRIFT;
+    use futures::io::Cursor;
+
+    use super::*;
+    use crate::test_utils::gen_filled_store;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    use tokio::test as async_test;
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as async_test;
+
+    #[async_test]
+    async fn get_by_time() {
+        let store = InMemoryStore::new();
+        // seeded generator creates a header every second
+        let mut gen = ExtendedHeaderGenerator::new_with_seed(7);
+        let headers = gen.next_many(20);
+        store.append_unchecked(headers.clone()).await.unwrap();
+
+        for header in &headers {
+            assert_eq!(&store.get_by_time(header.time()).await.unwrap(), header);
+        }
+
+        let between = headers[4]
+            .time()
+            .checked_add(Duration::from_millis(500))
+            .unwrap();
+        assert_eq!(store.get_by_time(between).await.unwrap(), headers[5]);
+
+        let before = headers[0]
+            .time()
+            .checked_sub(Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(store.get_by_time(before).await.unwrap(), headers[0]);
+
+        let after = headers[19]
+            .time()
+            .checked_add(Duration::from_secs(1))
+            .unwrap();
+        assert!(matches!(
+            store.get_by_time(after).await.unwrap_err(),
+            StoreError::NotFound
+        ));
+
+        // only the stored headers are searched
+        store.remove_tail().await.unwrap();
+        assert_eq!(store.get_by_time(before).await.unwrap(), headers[1]);
+    }
+
+    #[async_test]
+    async fn append_with_clock() {
+        let (store, mut gen) = gen_filled_store(3);
+        let headers = gen.next_many(2);
+        let lagging = headers[0]
+            .time()
+            .checked_sub(DEFAULT_MAX_CLOCK_DRIFT + Duration::from_secs(1))
+            .unwrap();
+
+        let clock = ClockCheck::At {
+            now: lagging,
+            max_clock_drift: DEFAULT_MAX_CLOCK_DRIFT,
+        };
+        store
+            .append_single_with_clock(headers[0].clone(), clock)
+            .await
+            .unwrap_err();
+        store
+            .append_with_clock(headers.clone(), clock)
+            .await
+            .unwrap_err();
+        assert_eq!(store.head_height().await.unwrap(), 3);
+
+        // skewed clock is tolerated with a larger drift
+        let clock = ClockCheck::At {
+            now: lagging,
+            max_clock_drift: DEFAULT_MAX_CLOCK_DRIFT + Duration::from_secs(2),
+        };
+        store.append_with_clock(headers, clock).await.unwrap();
+        assert_eq!(store.head_height().await.unwrap(), 5);
+    }
+
+    #[async_test]
+    async fn export_import_round_trip() {
+        let (store, _) = gen_filled_store(20);
+
+        let mut snapshot = Vec::new();
+        assert_eq!(store.export(..=15, &mut snapshot).await.unwrap(), 15);
+        assert_eq!(snapshot.iter().filter(|&&b| b == b'\n').count(), 15);
+
+        let imported = InMemoryStore::new();
+        assert_eq!(imported.import(Cursor::new(&snapshot)).await.unwrap(), 15);
+        assert_eq!(imported.head_height().await.unwrap(), 15);
+        assert_eq!(
+            imported.get_range(..).await.unwrap(),
+            store.get_range(..=15).await.unwrap()
+        );
+
+        // continue from the head of the store
+        let mut snapshot = Vec::new();
+        store.export(16.., &mut snapshot).await.unwrap();
+        assert_eq!(imported.import(Cursor::new(&snapshot)).await.unwrap(), 5);
+        assert_eq!(
+            imported.get_head().await.unwrap(),
+            store.get_head().await.unwrap()
+        );
+    }
+
+    #[async_test]
+    async fn export_starts_from_tail() {
+        let (store, _) = gen_filled_store(10);
+        store.remove_tail().await.unwrap();
+        store.remove_tail().await.unwrap();
+
+        let mut snapshot = Vec::new();
+        assert_eq!(store.export(.., &mut snapshot).await.unwrap(), 8);
+
+        let imported = InMemoryStore::new();
+        imported.import(Cursor::new(&snapshot)).await.unwrap();
+        assert_eq!(imported.tail_height().await.unwrap(), 3);
+        assert_eq!(imported.head_height().await.unwrap(), 10);
+    }
+
+    #[async_test]
+    async fn import_rejects_invalid_headers() {
+        let to_snapshot = |headers: &[ExtendedHeader]| {
+            headers
+                .iter()
+                .map(|header| serde_json::to_string(header).unwrap() + "\n")
+                .collect::<String>()
+        };
+
+        let mut gen = ExtendedHeaderGenerator::new();
+        let mut headers = gen.next_many(3);
+        invalidate(&mut headers[2]);
+
+        let store = InMemoryStore::new();
+        store
+            .import(Cursor::new(to_snapshot(&headers)))
+            .await
+            .unwrap_err();
+        assert!(store.get_head().await.is_err());
 
-    use super::to_headers_range;
+        let store = InMemoryStore::new();
+        let err = store.import(Cursor::new("{}\n")).await.unwrap_err();
+        assert!(matches!(err, StoreError::InvalidImport(1, _)));
+
+        // snapshot with a gap above the head
+        let (store, mut store_gen) = gen_filled_store(3);
+        let mut headers = store_gen.next_many(3);
+        let skipped = headers.remove(0);
+        let err = store
+            .import(Cursor::new(to_snapshot(&headers)))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, StoreError::CelestiaTypes(_)));
+        assert_eq!(store.head_height().await.unwrap(), 3);
+
+        // snapshot of a fork, at the right heights but not signed by the same validators
+        assert_eq!(gen.next().height().value(), 4);
+        let fork = gen.next_many(2);
+        assert_eq!(fork[0].height().value(), 5);
+        store.append_single(skipped).await.unwrap();
+        let err = store
+            .import(Cursor::new(to_snapshot(&fork)))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, StoreError::CelestiaTypes(_)));
+        assert_eq!(store.head_height().await.unwrap(), 4);
+        assert!(store.get_by_hash(&fork[0].hash()).await.is_err());
+    }
+
+    #[async_test]
+    async fn write_batch_and_snapshot() {
+        let (store, mut gen) = gen_filled_store(2);
+        let headers = gen.next_many(3);
+
+        let mut batch = WriteBatch::new();
+        batch.append(headers[0].clone()).append(headers[1].clone());
+        store.write_batch(batch).await.unwrap();
+        assert_eq!(store.head_height().await.unwrap(), 4);
+
+        // nothing is written if any of the writes fails
+        let mut batch = WriteBatch::new();
+        batch.append(headers[2].clone()).append(headers[2].clone());
+        store.write_batch(batch).await.unwrap_err();
+        assert_eq!(store.head_height().await.unwrap(), 4);
+        assert!(store.get_by_hash(&headers[2].hash()).await.is_err());
+
+        store
+            .update_sampling_metadata(3, true, vec![])
+            .await
+            .unwrap();
+
+        let snapshot = store.snapshot(2..).await.unwrap();
+        assert_eq!(snapshot.tail_height, 1);
+        assert_eq!(snapshot.head_height, 4);
+        assert_eq!(snapshot.headers.len(), 3);
+        assert_eq!(snapshot.headers[1].header, headers[0]);
+        assert!(snapshot.headers[0].sampling_metadata.is_none());
+        assert!(
+            snapshot.headers[1]
+                .sampling_metadata
+                .as_ref()
+                .unwrap()
+                .accepted
+        );
+
+        // out of the store heights are skipped
+        assert_eq!(store.snapshot(3..10).await.unwrap().headers.len(), 2);
+        assert!(store.snapshot(5..).await.unwrap().headers.is_empty());
+        assert!(store.snapshot(..1).await.unwrap().headers.is_empty());
+    }
+
+    #[async_test]
+    async fn headers_range_and_sampled_heights() {
+        let (store, _) = gen_filled_store(HEADERS_RANGE_BATCH_SIZE + 10);
+        let all: Vec<_> = store.headers_range(..).try_collect().await.unwrap();
+        assert_eq!(all, store.get_range(..).await.unwrap());
+
+        let heights: Vec<_> = store
+            .headers_range(5..=HEADERS_RANGE_BATCH_SIZE + 5)
+            .map_ok(|header| header.height().value())
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(
+            heights,
+            (5..=HEADERS_RANGE_BATCH_SIZE + 5).collect::<Vec<_>>()
+        );
+
+        // out of the store heights are skipped
+        store.remove_tail().await.unwrap();
+        let headers: Vec<_> = store.headers_range(..3).try_collect().await.unwrap();
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers[0].height().value(), 2);
+
+        assert!(store.sampled_heights(..).await.unwrap().is_empty());
+        for height in [2, 7, 8] {
+            store
+                .update_sampling_metadata(height, height != 7, vec![])
+                .await
+                .unwrap();
+        }
+        assert_eq!(store.sampled_heights(..).await.unwrap(), vec![2, 7, 8]);
+        assert_eq!(store.sampled_heights(3..8).await.unwrap(), vec![7]);
+
+        let empty = InMemoryStore::new();
+        assert!(empty.sampled_heights(..).await.unwrap().is_empty());
+        assert!(matches!(
+            empty.headers_range(..).next().await,
+            Some(Err(StoreError::NotFound))
+        ));
+    }
+
+    #[async_test]
+    async fn heights_with_namespace() {
+        let ns1 = Namespace::new_v0(&[1]).unwrap();
+        let ns2 = Namespace::new_v0(&[2]).unwrap();
+        let ns3 = Namespace::new_v0(&[3]).unwrap();
+        let mut gen = ExtendedHeaderGenerator::new();
+        let headers: Vec<_> = [vec![ns1], vec![ns3], vec![ns1, ns3], vec![ns2]]
+            .iter()
+            .map(|namespaces| gen.next_with_eds(4, namespaces).0)
+            .collect();
+
+        let ranges = NamespaceRanges::new(&headers[2].dah);
+        assert!(ranges.contains(ns1));
+        assert!(!ranges.contains(ns2));
+        assert!(ranges.contains(ns3));
+        assert_eq!(NamespaceRanges::decode(&ranges.encode()).unwrap(), ranges);
+        NamespaceRanges::decode(&[0; NS_SIZE]).unwrap_err();
+
+        let store = InMemoryStore::new();
+        assert!(store
+            .heights_with_namespace(ns1, ..)
+            .await
+            .unwrap()
+            .is_empty());
+        store.append_unchecked(headers).await.unwrap();
+
+        assert_eq!(
+            store.heights_with_namespace(ns1, ..).await.unwrap(),
+            vec![1, 3]
+        );
+        assert_eq!(
+            store.heights_with_namespace(ns2, ..).await.unwrap(),
+            vec![4]
+        );
+        assert_eq!(
+            store.heights_with_namespace(ns3, ..3).await.unwrap(),
+            vec![2]
+        );
+
+        // removed heights are not indexed anymore
+        store.remove_tail().await.unwrap();
+        assert_eq!(
+            store.heights_with_namespace(ns1, ..).await.unwrap(),
+            vec![3]
+        );
+    }
+
+    #[test]
+    fn namespace_index_covers_ranges() {
+        let ns = |id: u16| Namespace::new_v0(&id.to_be_bytes()).unwrap();
+        let ranges = NamespaceRanges(vec![(ns(0x0101), ns(0x0300)), (ns(0x1000), ns(0x1000))]);
+        let keys: BTreeSet<_> = ranges.index_keys(7).into_iter().collect();
+        let found = |namespace| {
+            namespace_index_scans(namespace, 7..=7).any(|scan| keys.range(scan).next().is_some())
+        };
+
+        assert!(keys.len() <= 2 * NAMESPACE_INDEX_PRECISION + 3);
+        assert!((0x0101..=0x0300).all(|id| found(ns(id))));
+        assert!(found(ns(0x1000)));
+        assert!(!found(ns(0x0400)));
+        assert!(!found(ns(0x0fff)));
+        assert!(!found(ns(0x1001)));
+        assert!(!found(Namespace::TAIL_PADDING));
+        // only the indexed height is found
+        assert!(
+            !namespace_index_scans(ns(0x1000), 8..=9).any(|scan| keys.range(scan).next().is_some())
+        );
+    }
+
+    #[test]
+    fn clamps_ranges_to_stored_heights() {
+        assert_eq!(clamp_to_stored(.., 3, 10), 3..=10);
+        assert_eq!(clamp_to_stored(1..20, 3, 10), 3..=10);
+        assert_eq!(clamp_to_stored(5..=7, 3, 10), 5..=7);
+        assert!(clamp_to_stored(11.., 3, 10).is_empty());
+        assert!(clamp_to_stored(..3, 3, 10).is_empty());
+        assert!(clamp_to_stored(..0, 1, 10).is_empty());
+    }
+
+    #[test]
+    fn sampled_ranges() {
+        let mut ranges = SampledRanges::from_heights([5, 3, 7, 8, 1]);
+        assert_eq!(ranges.0, vec![1..=1, 3..=3, 5..=5, 7..=8]);
+
+        ranges.insert(4);
+        ranges.insert(2);
+        ranges.insert(8);
+        assert_eq!(ranges.0, vec![1..=5, 7..=8]);
+        assert_eq!(ranges.missing(1..=10), vec![6..=6, 9..=10]);
+        assert_eq!(ranges.missing(2..=8), vec![6..=6]);
+        assert!(ranges.missing(7..=8).is_empty());
+        assert_eq!(ranges.heights(4..=7), vec![4, 5, 7]);
+
+        ranges.insert(6);
+        assert_eq!(ranges.0, vec![1..=8]);
+
+        ranges.remove_below(3);
+        assert_eq!(ranges.0, vec![3..=8]);
+        ranges.remove_below(9);
+        assert!(ranges.0.is_empty());
+        assert_eq!(ranges.missing(3..=4), vec![3..=4]);
+
+        let ranges = SampledRanges::from_heights([10, 11, 20]);
+        assert_eq!(SampledRanges::decode(&ranges.encode()).unwrap(), ranges);
+    }
 
     #[test]
     fn converts_bounded_ranges() {
@@ -284,6 +1818,26 @@ mod tests {
         }
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn network_db_path_is_sanitized() {
+        let dir = Path::new("/cache");
+
+        assert_eq!(
+            network_db_path(dir, "mocha-4").unwrap(),
+            Path::new("/cache/mocha-4")
+        );
+        assert_eq!(
+            network_db_path(dir, "my.network").unwrap(),
+            Path::new("/cache/my.network")
+        );
+
+        for network_id in ["", ".", "..", "../other", "a/b", "a\\b", "/abs", "net/"] {
+            let err = network_db_path(dir, network_id).unwrap_err();
+            assert!(matches!(err, StoreError::OpenFailed(_)));
+        }
+    }
+
     #[test]
     fn errors_if_zero_heigth_is_included() {
         let includes_zero_height = 0..5;
@@ -334,4 +1888,24 @@ mod tests {
             assert!(to_headers_range(bound, last_index).unwrap().is_empty());
         }
     }
+
+    #[test]
+    fn shwap_block_height_from_cid() {
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+
+        for layout in [IdLayout::V1, IdLayout::Draft] {
+            let row = RowId::new(3, 0x0102_0304).unwrap().to_cid(layout);
+            let sample = SampleId::new(5, 4, 0x0102_0305).unwrap().to_cid(layout);
+            let data = NamespacedDataId::new(namespace, 1, 0x0102_0306)
+                .unwrap()
+                .to_cid(layout);
+
+            assert_eq!(shwap_block_height(&row), Some(0x0102_0304));
+            assert_eq!(shwap_block_height(&sample), Some(0x0102_0305));
+            assert_eq!(shwap_block_height(&data), Some(0x0102_0306));
+        }
+
+        let other = Cid::new_v1(0x55, multihash::Multihash::wrap(0x12, &[1; 32]).unwrap());
+        assert_eq!(shwap_block_height(&other), None);
+    }
 }
@@ -1,20 +1,50 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::ops::{RangeBounds, RangeInclusive};
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError, RwLock, RwLockWriteGuard};
 
 use async_trait::async_trait;
+use blockstore::{Blockstore, BlockstoreError};
 use celestia_types::hash::Hash;
+use celestia_types::nmt::Namespace;
 use celestia_types::ExtendedHeader;
+use cid::{Cid, CidGeneric};
 use dashmap::mapref::entry::Entry;
 use dashmap::DashMap;
+use multihash::Multihash;
 use tracing::debug;
 
-use crate::store::{Result, Store, StoreError};
+use crate::bitswap::MAX_MH_SIZE;
+use crate::store::{
+    clamp_to_stored, namespace_index_height, namespace_index_scans, shwap_block_height,
+    NamespaceIndexKey, NamespaceRanges, PeerAddr, Result, SampledRanges, SamplingMetadata,
+    SnapshotHeader, Store, StoreError, StoreSnapshot, WriteBatch, WriteOp,
+};
 
 /// A non-persistent in memory [`Store`] implementation.
 #[derive(Debug)]
 pub struct InMemoryStore {
     headers: DashMap<Hash, ExtendedHeader>,
     height_to_hash: DashMap<u64, Hash>,
+    sampling: Mutex<Sampling>,
+    namespace_index: Mutex<BTreeSet<NamespaceIndexKey>>,
+    blocks: DashMap<CidGeneric<MAX_MH_SIZE>, Vec<u8>>,
+    block_heights: DashMap<u64, Vec<CidGeneric<MAX_MH_SIZE>>>,
+    peer_addrs: Mutex<Vec<PeerAddr>>,
+    network_head: Mutex<Option<ExtendedHeader>>,
     head_height: AtomicU64,
+    tail_height: AtomicU64,
+    // Taken exclusively by the writes and shared by the snapshots, so that the
+    // snapshots never observe a partially applied write
+    write_lock: RwLock<()>,
+}
+
+/// [`SamplingMetadata`] of the stored headers, updated together with the ranges of the
+/// sampled heights.
+#[derive(Debug, Clone, Default)]
+struct Sampling {
+    metadata: HashMap<u64, SamplingMetadata>,
+    ranges: SampledRanges,
 }
 
 impl InMemoryStore {
@@ -23,7 +53,15 @@ impl InMemoryStore {
         InMemoryStore {
             headers: DashMap::new(),
             height_to_hash: DashMap::new(),
+            sampling: Mutex::new(Sampling::default()),
+            namespace_index: Mutex::new(BTreeSet::new()),
+            blocks: DashMap::new(),
+            block_heights: DashMap::new(),
+            peer_addrs: Mutex::new(Vec::new()),
+            network_head: Mutex::new(None),
             head_height: AtomicU64::new(0),
+            tail_height: AtomicU64::new(1),
+            write_lock: RwLock::new(()),
         }
     }
 
@@ -38,7 +76,138 @@ impl InMemoryStore {
         }
     }
 
+    #[inline]
+    fn get_tail_height(&self) -> Result<u64> {
+        // Ensure the store is not empty
+        self.get_head_height()?;
+
+        Ok(self.tail_height.load(Ordering::Acquire))
+    }
+
+    fn lock_writes(&self) -> RwLockWriteGuard<'_, ()> {
+        // the lock guards no data, so it can't be left in an inconsistent state
+        self.write_lock
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn lock_sampling(&self) -> MutexGuard<'_, Sampling> {
+        // each update of the metadata and ranges is applied whole before it can panic
+        self.sampling.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn lock_namespace_index(&self) -> MutexGuard<'_, BTreeSet<NamespaceIndexKey>> {
+        // keys of a header are inserted or removed one by one, without panicking in between
+        self.namespace_index
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
     pub(crate) fn append_single_unchecked(&self, header: ExtendedHeader) -> Result<()> {
+        let _guard = self.lock_writes();
+        self.append_header(header)
+    }
+
+    pub(crate) fn prepend_single_unchecked(&self, header: ExtendedHeader) -> Result<()> {
+        let _guard = self.lock_writes();
+        self.prepend_header(header)
+    }
+
+    fn write_batch(&self, batch: WriteBatch) -> Result<()> {
+        let _guard = self.lock_writes();
+
+        // check everything up front, so that the batch is not applied only partially
+        self.check_batch(&batch)?;
+
+        for op in batch.into_ops() {
+            match op {
+                WriteOp::Append(header) => self.append_header(header)?,
+                WriteOp::Prepend(header) => self.prepend_header(header)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that all the writes of the batch can be applied on top of the current state.
+    fn check_batch(&self, batch: &WriteBatch) -> Result<()> {
+        let mut head_height = self.get_head_height().unwrap_or(0);
+        let mut tail_height = self.tail_height.load(Ordering::Acquire);
+        let mut hashes = HashSet::new();
+
+        for op in batch.ops() {
+            let header = match op {
+                WriteOp::Append(header) => {
+                    let height = header.height().value();
+
+                    if head_height > 0 && height <= head_height {
+                        return Err(StoreError::HeightExists(height));
+                    }
+
+                    if head_height + 1 != height {
+                        return Err(StoreError::NonContinuousAppend(head_height, height));
+                    }
+
+                    head_height = height;
+                    header
+                }
+                WriteOp::Prepend(header) => {
+                    let height = header.height().value();
+
+                    if head_height > 0 {
+                        if height >= tail_height {
+                            return Err(StoreError::HeightExists(height));
+                        }
+
+                        if height + 1 != tail_height {
+                            return Err(StoreError::NonContinuousPrepend(tail_height, height));
+                        }
+                    } else {
+                        head_height = height;
+                    }
+
+                    tail_height = height;
+                    header
+                }
+            };
+
+            let hash = header.hash();
+
+            if self.headers.contains_key(&hash) || !hashes.insert(hash) {
+                return Err(StoreError::HashExists(hash));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn snapshot(&self, range: impl RangeBounds<u64>) -> Result<StoreSnapshot> {
+        let _guard = self
+            .write_lock
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        let head_height = self.get_head_height()?;
+        let tail_height = self.get_tail_height()?;
+        let sampling = self.lock_sampling();
+
+        let headers = clamp_to_stored(range, tail_height, head_height)
+            .map(|height| {
+                Ok(SnapshotHeader {
+                    header: self.get_by_height(height)?,
+                    sampling_metadata: sampling.metadata.get(&height).cloned(),
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(StoreSnapshot {
+            tail_height,
+            head_height,
+            headers,
+        })
+    }
+
+    fn append_header(&self, header: ExtendedHeader) -> Result<()> {
         let hash = header.hash();
         let height = header.height().value();
         let head_height = self.get_head_height().unwrap_or(0);
@@ -70,6 +239,8 @@ impl InMemoryStore {
         }
 
         debug!("Inserting header {hash} with height {height}");
+        self.lock_namespace_index()
+            .extend(NamespaceRanges::new(&header.dah).index_keys(height));
         hash_entry.insert(header);
         height_entry.insert(hash);
 
@@ -78,6 +249,116 @@ impl InMemoryStore {
         Ok(())
     }
 
+    fn prepend_header(&self, header: ExtendedHeader) -> Result<()> {
+        let hash = header.hash();
+        let height = header.height().value();
+        let head_height = self.get_head_height().unwrap_or(0);
+
+        if head_height > 0 {
+            let tail_height = self.tail_height.load(Ordering::Acquire);
+
+            // A light check before checking the whole map
+            if height >= tail_height {
+                return Err(StoreError::HeightExists(height));
+            }
+
+            // Check if it's continuous before checking the whole map.
+            if height + 1 != tail_height {
+                return Err(StoreError::NonContinuousPrepend(tail_height, height));
+            }
+        }
+
+        // lock both maps to ensure consistency
+        let hash_entry = self.headers.entry(hash);
+        let height_entry = self.height_to_hash.entry(height);
+
+        if matches!(hash_entry, Entry::Occupied(_)) {
+            return Err(StoreError::HashExists(hash));
+        }
+
+        if matches!(height_entry, Entry::Occupied(_)) {
+            return Err(StoreError::HeightExists(height));
+        }
+
+        debug!("Prepending header {hash} with height {height}");
+        self.lock_namespace_index()
+            .extend(NamespaceRanges::new(&header.dah).index_keys(height));
+        hash_entry.insert(header);
+        height_entry.insert(hash);
+
+        self.tail_height.store(height, Ordering::Release);
+        if head_height == 0 {
+            self.head_height.store(height, Ordering::Release);
+        }
+
+        Ok(())
+    }
+
+    fn remove_tail(&self) -> Result<()> {
+        let _guard = self.lock_writes();
+        let head_height = self.get_head_height()?;
+        let tail_height = self.tail_height.load(Ordering::Acquire);
+
+        if tail_height >= head_height {
+            return Err(StoreError::RemovingHead);
+        }
+
+        // Claim the tail, so that concurrent calls don't remove the same header
+        if self
+            .tail_height
+            .compare_exchange(
+                tail_height,
+                tail_height + 1,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            )
+            .is_err()
+        {
+            // Someone else removed it in the meantime
+            return Ok(());
+        }
+
+        let Some((_, hash)) = self.height_to_hash.remove(&tail_height) else {
+            return Err(StoreError::LostHeight(tail_height));
+        };
+
+        debug!("Removing header {hash} with height {tail_height}");
+        if let Some((_, header)) = self.headers.remove(&hash) {
+            let mut namespace_index = self.lock_namespace_index();
+
+            for key in NamespaceRanges::new(&header.dah).index_keys(tail_height) {
+                namespace_index.remove(&key);
+            }
+        }
+
+        let mut sampling = self.lock_sampling();
+        sampling.metadata.remove(&tail_height);
+        sampling.ranges.remove_below(tail_height + 1);
+        drop(sampling);
+
+        Ok(())
+    }
+
+    fn remove_blocks_below(&self, height: u64) -> u64 {
+        let mut removed = 0;
+
+        self.block_heights.retain(|&block_height, cids| {
+            if block_height >= height {
+                return true;
+            }
+
+            for cid in cids.iter() {
+                if self.blocks.remove(cid).is_some() {
+                    removed += 1;
+                }
+            }
+
+            false
+        });
+
+        removed
+    }
+
     fn get_head(&self) -> Result<ExtendedHeader> {
         let head_height = self.get_head_height()?;
         self.get_by_height(head_height)
@@ -100,7 +381,7 @@ impl InMemoryStore {
             return false;
         };
 
-        height <= head_height
+        height >= self.tail_height.load(Ordering::Acquire) && height <= head_height
     }
 
     fn get_by_height(&self, height: u64) -> Result<ExtendedHeader> {
@@ -118,6 +399,115 @@ impl InMemoryStore {
             .cloned()
             .ok_or(StoreError::LostHash(hash))
     }
+
+    fn update_sampling_metadata(&self, height: u64, accepted: bool, cids: Vec<Cid>) -> Result<()> {
+        // the tail can't be removed while holding the write lock, and the metadata can't be
+        // changed by anyone else while holding the sampling lock
+        let _guard = self.lock_writes();
+        let mut sampling = self.lock_sampling();
+
+        if !self.contains_height(height) {
+            return Err(StoreError::NotFound);
+        }
+
+        let prev = sampling.metadata.remove(&height);
+        let metadata = SamplingMetadata::updated(prev, accepted, cids);
+        sampling.metadata.insert(height, metadata);
+        sampling.ranges.insert(height);
+
+        Ok(())
+    }
+
+    fn get_sampling_metadata(&self, height: u64) -> Result<Option<SamplingMetadata>> {
+        let sampling = self.lock_sampling();
+
+        if !self.contains_height(height) {
+            return Err(StoreError::NotFound);
+        }
+
+        Ok(sampling.metadata.get(&height).cloned())
+    }
+
+    fn unsampled_ranges(&self) -> Vec<RangeInclusive<u64>> {
+        let sampling = self.lock_sampling();
+
+        let Ok(head_height) = self.get_head_height() else {
+            return Vec::new();
+        };
+        let tail_height = self.tail_height.load(Ordering::Acquire);
+
+        sampling.ranges.missing(tail_height..=head_height)
+    }
+
+    fn sampled_heights(&self, range: impl RangeBounds<u64>) -> Vec<u64> {
+        let sampling = self.lock_sampling();
+
+        let Ok(head_height) = self.get_head_height() else {
+            return Vec::new();
+        };
+        let tail_height = self.tail_height.load(Ordering::Acquire);
+
+        sampling
+            .ranges
+            .heights(clamp_to_stored(range, tail_height, head_height))
+    }
+
+    fn get_peer_addrs(&self) -> Vec<PeerAddr> {
+        self.peer_addrs
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    fn set_peer_addrs(&self, addrs: Vec<PeerAddr>) {
+        *self
+            .peer_addrs
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = addrs;
+    }
+
+    fn heights_with_namespace(
+        &self,
+        namespace: Namespace,
+        range: impl RangeBounds<u64>,
+    ) -> Vec<u64> {
+        let _guard = self
+            .write_lock
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        let Ok(head_height) = self.get_head_height() else {
+            return Vec::new();
+        };
+        let tail_height = self.tail_height.load(Ordering::Acquire);
+        let heights = clamp_to_stored(range, tail_height, head_height);
+
+        if heights.is_empty() {
+            return Vec::new();
+        }
+
+        let namespace_index = self.lock_namespace_index();
+        let found: BTreeSet<_> = namespace_index_scans(namespace, heights)
+            .flat_map(|scan| namespace_index.range(scan))
+            .filter_map(|key| namespace_index_height(key).ok())
+            .collect();
+
+        found.into_iter().collect()
+    }
+
+    fn get_network_head(&self) -> Option<ExtendedHeader> {
+        self.network_head
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    fn set_network_head(&self, header: ExtendedHeader) {
+        *self
+            .network_head
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(header);
+    }
 }
 
 #[async_trait]
@@ -138,6 +528,10 @@ impl Store for InMemoryStore {
         self.get_head_height()
     }
 
+    async fn tail_height(&self) -> Result<u64> {
+        self.get_tail_height()
+    }
+
     async fn has(&self, hash: &Hash) -> bool {
         self.contains_hash(hash)
     }
@@ -146,9 +540,133 @@ impl Store for InMemoryStore {
         self.contains_height(height)
     }
 
+    async fn remove_tail(&self) -> Result<()> {
+        self.remove_tail()
+    }
+
+    async fn remove_blocks_below(&self, height: u64) -> Result<u64> {
+        Ok(self.remove_blocks_below(height))
+    }
+
     async fn append_single_unchecked(&self, header: ExtendedHeader) -> Result<()> {
         self.append_single_unchecked(header)
     }
+
+    async fn prepend_single_unchecked(&self, header: ExtendedHeader) -> Result<()> {
+        self.prepend_single_unchecked(header)
+    }
+
+    async fn write_batch(&self, batch: WriteBatch) -> Result<()> {
+        self.write_batch(batch)
+    }
+
+    async fn snapshot<R>(&self, range: R) -> Result<StoreSnapshot>
+    where
+        R: RangeBounds<u64> + Send,
+    {
+        self.snapshot(range)
+    }
+
+    async fn update_sampling_metadata(
+        &self,
+        height: u64,
+        accepted: bool,
+        cids: Vec<Cid>,
+    ) -> Result<()> {
+        self.update_sampling_metadata(height, accepted, cids)
+    }
+
+    async fn get_sampling_metadata(&self, height: u64) -> Result<Option<SamplingMetadata>> {
+        self.get_sampling_metadata(height)
+    }
+
+    async fn unsampled_ranges(&self) -> Result<Vec<RangeInclusive<u64>>> {
+        Ok(self.unsampled_ranges())
+    }
+
+    async fn sampled_heights<R>(&self, range: R) -> Result<Vec<u64>>
+    where
+        R: RangeBounds<u64> + Send,
+    {
+        Ok(self.sampled_heights(range))
+    }
+
+    async fn heights_with_namespace<R>(&self, namespace: Namespace, range: R) -> Result<Vec<u64>>
+    where
+        R: RangeBounds<u64> + Send,
+    {
+        Ok(self.heights_with_namespace(namespace, range))
+    }
+
+    async fn get_peer_addrs(&self) -> Result<Vec<PeerAddr>> {
+        Ok(self.get_peer_addrs())
+    }
+
+    async fn set_peer_addrs(&self, addrs: Vec<PeerAddr>) -> Result<()> {
+        self.set_peer_addrs(addrs);
+        Ok(())
+    }
+
+    async fn get_network_head(&self) -> Result<Option<ExtendedHeader>> {
+        Ok(self.get_network_head())
+    }
+
+    async fn set_network_head(&self, header: ExtendedHeader) -> Result<()> {
+        self.set_network_head(header);
+        Ok(())
+    }
+}
+
+#[cfg_attr(not(docs_rs), async_trait)]
+impl Blockstore for InMemoryStore {
+    async fn get<const S: usize>(
+        &self,
+        cid: &CidGeneric<S>,
+    ) -> Result<Option<Vec<u8>>, BlockstoreError> {
+        let cid = convert_cid(cid)?;
+        Ok(self.blocks.get(&cid).as_deref().cloned())
+    }
+
+    async fn put_keyed<const S: usize>(
+        &self,
+        cid: &CidGeneric<S>,
+        data: &[u8],
+    ) -> Result<(), BlockstoreError> {
+        let cid = convert_cid(cid)?;
+
+        match self.blocks.entry(cid) {
+            Entry::Occupied(_) => return Err(BlockstoreError::CidExists),
+            Entry::Vacant(entry) => {
+                entry.insert(data.to_vec());
+            }
+        }
+
+        if let Some(height) = shwap_block_height(&cid) {
+            self.block_heights.entry(height).or_default().push(cid);
+        }
+
+        Ok(())
+    }
+
+    async fn remove<const S: usize>(&self, cid: &CidGeneric<S>) -> Result<(), BlockstoreError> {
+        let cid = convert_cid(cid)?;
+
+        if self.blocks.remove(&cid).is_none() {
+            return Ok(());
+        }
+
+        if let Some(height) = shwap_block_height(&cid) {
+            if let Entry::Occupied(mut entry) = self.block_heights.entry(height) {
+                entry.get_mut().retain(|indexed| *indexed != cid);
+
+                if entry.get().is_empty() {
+                    entry.remove();
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl Default for InMemoryStore {
@@ -159,17 +677,40 @@ impl Default for InMemoryStore {
 
 impl Clone for InMemoryStore {
     fn clone(&self) -> Self {
+        let _guard = self
+            .write_lock
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+
         InMemoryStore {
             headers: self.headers.clone(),
             height_to_hash: self.height_to_hash.clone(),
+            sampling: Mutex::new(self.lock_sampling().clone()),
+            namespace_index: Mutex::new(self.lock_namespace_index().clone()),
+            blocks: self.blocks.clone(),
+            block_heights: self.block_heights.clone(),
+            peer_addrs: Mutex::new(self.get_peer_addrs()),
+            network_head: Mutex::new(self.get_network_head()),
             head_height: AtomicU64::new(self.head_height.load(Ordering::Acquire)),
+            tail_height: AtomicU64::new(self.tail_height.load(Ordering::Acquire)),
+            write_lock: RwLock::new(()),
         }
     }
 }
 
+fn convert_cid<const S: usize>(
+    cid: &CidGeneric<S>,
+) -> Result<CidGeneric<MAX_MH_SIZE>, BlockstoreError> {
+    let hash = Multihash::wrap(cid.hash().code(), cid.hash().digest())
+        .map_err(|_| BlockstoreError::CidTooLong)?;
+    Ok(CidGeneric::new_v1(cid.codec(), hash))
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
+    use celestia_types::row::{IdLayout, RowId};
+    use celestia_types::sample::SampleId;
     use celestia_types::test_utils::ExtendedHeaderGenerator;
     use celestia_types::Height;
 
@@ -306,6 +847,199 @@ pub mod tests {
         ));
     }
 
+    #[test]
+    fn test_prepend() {
+        let mut gen = ExtendedHeaderGenerator::new();
+        let headers = gen.next_many(10);
+
+        let s = InMemoryStore::new();
+
+        // Empty store can be started from any height
+        s.prepend_single_unchecked(headers[9].clone()).unwrap();
+        assert_eq!(s.get_head_height().unwrap(), 10);
+        assert_eq!(s.get_tail_height().unwrap(), 10);
+
+        s.prepend_single_unchecked(headers[8].clone()).unwrap();
+        assert_eq!(s.get_tail_height().unwrap(), 9);
+        assert_eq!(s.get_by_height(9).unwrap(), headers[8]);
+        assert!(!s.contains_height(8));
+
+        assert!(matches!(
+            s.prepend_single_unchecked(headers[8].clone()),
+            Err(StoreError::HeightExists(9))
+        ));
+        assert!(matches!(
+            s.prepend_single_unchecked(headers[6].clone()),
+            Err(StoreError::NonContinuousPrepend(9, 7))
+        ));
+
+        // Appending above the head still works
+        s.append_single_unchecked(gen.next()).unwrap();
+        assert_eq!(s.get_head_height().unwrap(), 11);
+        assert_eq!(s.get_tail_height().unwrap(), 9);
+    }
+
+    #[async_test]
+    async fn test_prepend_verified() {
+        let mut gen = ExtendedHeaderGenerator::new();
+        let headers = gen.next_many(10);
+
+        let s = InMemoryStore::new();
+        s.prepend_single_unchecked(headers[9].clone()).unwrap();
+
+        Store::prepend(&s, headers[4..9].to_vec()).await.unwrap();
+        assert_eq!(s.get_tail_height().unwrap(), 5);
+        assert_eq!(s.get_by_height(5).unwrap(), headers[4]);
+
+        // Headers from a different chain
+        let forged = ExtendedHeaderGenerator::new().next_many(4);
+        assert!(matches!(
+            Store::prepend(&s, forged).await,
+            Err(StoreError::CelestiaTypes(_))
+        ));
+
+        // Range not adjacent to the tail
+        assert!(matches!(
+            Store::prepend(&s, headers[0..3].to_vec()).await,
+            Err(StoreError::CelestiaTypes(_))
+        ));
+        assert_eq!(s.get_tail_height().unwrap(), 5);
+
+        Store::prepend(&s, headers[0..4].to_vec()).await.unwrap();
+        assert_eq!(s.get_tail_height().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_remove_tail() {
+        let (s, _) = gen_filled_store(3);
+        assert!(matches!(s.get_tail_height(), Ok(1)));
+
+        let header2 = s.get_by_height(2).unwrap();
+        let header1 = s.get_by_height(1).unwrap();
+
+        s.remove_tail().unwrap();
+        assert_eq!(s.get_tail_height().unwrap(), 2);
+        assert!(!s.contains_height(1));
+        assert!(!s.contains_hash(&header1.hash()));
+        assert!(matches!(s.get_by_height(1), Err(StoreError::NotFound)));
+        assert_eq!(s.get_by_height(2).unwrap(), header2);
+
+        s.remove_tail().unwrap();
+        assert!(matches!(s.remove_tail(), Err(StoreError::RemovingHead)));
+        assert_eq!(s.get_tail_height().unwrap(), 3);
+        assert_eq!(s.get_head_height().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_remove_tail_empty_store() {
+        let s = InMemoryStore::new();
+        assert!(matches!(s.get_tail_height(), Err(StoreError::NotFound)));
+        assert!(matches!(s.remove_tail(), Err(StoreError::NotFound)));
+    }
+
+    #[async_test]
+    async fn test_sampling_metadata() {
+        let (s, _) = gen_filled_store(3);
+        let cid = |n| Cid::new_v1(0x55, multihash::Multihash::wrap(0x12, &[n; 32]).unwrap());
+
+        assert_eq!(s.get_sampling_metadata(2).unwrap(), None);
+        assert_eq!(Store::next_unsampled_height(&s).await.unwrap(), Some(1));
+
+        s.update_sampling_metadata(1, true, vec![cid(1)]).unwrap();
+        s.update_sampling_metadata(2, true, vec![cid(1), cid(2)])
+            .unwrap();
+        s.update_sampling_metadata(2, false, vec![cid(2), cid(3)])
+            .unwrap();
+
+        assert_eq!(
+            s.get_sampling_metadata(2).unwrap(),
+            Some(SamplingMetadata {
+                accepted: false,
+                cids_sampled: vec![cid(1), cid(2), cid(3)],
+            })
+        );
+        assert_eq!(Store::next_unsampled_height(&s).await.unwrap(), Some(3));
+        assert_eq!(s.unsampled_ranges(), vec![3..=3]);
+        assert_eq!(s.sampled_heights(..), vec![1, 2]);
+
+        s.update_sampling_metadata(3, true, Vec::new()).unwrap();
+        assert_eq!(Store::next_unsampled_height(&s).await.unwrap(), None);
+        assert!(s.unsampled_ranges().is_empty());
+
+        assert!(matches!(
+            s.update_sampling_metadata(4, true, Vec::new()),
+            Err(StoreError::NotFound)
+        ));
+
+        s.remove_tail().unwrap();
+        assert!(matches!(
+            s.get_sampling_metadata(1),
+            Err(StoreError::NotFound)
+        ));
+        assert!(!s.lock_sampling().metadata.contains_key(&1));
+        assert_eq!(s.sampled_heights(..), vec![2, 3]);
+    }
+
+    #[async_test]
+    async fn test_blockstore() {
+        let s = InMemoryStore::new();
+        let cid = Cid::new_v1(0x55, multihash::Multihash::wrap(0x12, &[1; 32]).unwrap());
+
+        assert_eq!(s.get(&cid).await.unwrap(), None);
+
+        s.put_keyed(&cid, b"data").await.unwrap();
+        assert_eq!(s.get(&cid).await.unwrap().unwrap(), b"data");
+        assert!(Blockstore::has(&s, &cid).await.unwrap());
+
+        assert_eq!(
+            s.put_keyed(&cid, b"other").await.unwrap_err(),
+            BlockstoreError::CidExists
+        );
+
+        // blocks are copied with the store
+        assert!(Blockstore::has(&s.clone(), &cid).await.unwrap());
+    }
+
+    #[async_test]
+    async fn test_remove_blocks_below() {
+        let s = InMemoryStore::new();
+        let row = |height| RowId::new(0, height).unwrap().to_cid(IdLayout::V1);
+        let sample = |height| SampleId::new(1, 4, height).unwrap().to_cid(IdLayout::Draft);
+        let other = Cid::new_v1(0x55, multihash::Multihash::wrap(0x12, &[1; 32]).unwrap());
+
+        for height in 1..=3 {
+            s.put_keyed(&row(height), b"row").await.unwrap();
+            s.put_keyed(&sample(height), b"sample").await.unwrap();
+        }
+        s.put_keyed(&other, b"other").await.unwrap();
+
+        assert_eq!(s.remove_blocks_below(3), 4);
+        assert_eq!(s.get(&row(2)).await.unwrap(), None);
+        assert_eq!(s.get(&sample(1)).await.unwrap(), None);
+        assert!(Blockstore::has(&s, &row(3)).await.unwrap());
+        assert!(Blockstore::has(&s, &sample(3)).await.unwrap());
+        // blocks not belonging to any height are kept
+        assert!(Blockstore::has(&s, &other).await.unwrap());
+
+        assert_eq!(s.remove_blocks_below(3), 0);
+    }
+
+    #[async_test]
+    async fn test_remove_block() {
+        let s = InMemoryStore::new();
+        let row = |height| RowId::new(0, height).unwrap().to_cid(IdLayout::V1);
+
+        s.put_keyed(&row(1), b"row").await.unwrap();
+        s.put_keyed(&row(2), b"row").await.unwrap();
+        s.remove(&row(1)).await.unwrap();
+        assert!(!Blockstore::has(&s, &row(1)).await.unwrap());
+        // the index entry is gone with the block
+        assert!(!s.block_heights.contains_key(&1));
+
+        s.remove(&row(1)).await.unwrap();
+        assert_eq!(s.remove_blocks_below(3), 1);
+    }
+
     pub fn gen_filled_store(amount: u64) -> (InMemoryStore, ExtendedHeaderGenerator) {
         let s = InMemoryStore::new();
         let mut gen = ExtendedHeaderGenerator::new();
@@ -1,35 +1,147 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeSet;
 use std::convert::Infallible;
+use std::ops::{RangeBounds, RangeInclusive};
 
 use async_trait::async_trait;
+use blockstore::{Blockstore, BlockstoreError};
 use celestia_tendermint_proto::Protobuf;
 use celestia_types::hash::Hash;
+use celestia_types::nmt::Namespace;
 use celestia_types::ExtendedHeader;
-use rexie::{Direction, Index, KeyRange, ObjectStore, Rexie, TransactionMode};
+use cid::{Cid, CidGeneric};
+use futures::stream::BoxStream;
+use rexie::{
+    Direction, Index, KeyRange, ObjectStore, Rexie, Store as RexieStore, Transaction,
+    TransactionMode,
+};
 use send_wrapper::SendWrapper;
 use serde::{Deserialize, Serialize};
 use serde_wasm_bindgen::{from_value, to_value};
 
-use crate::store::{Result, Store, StoreError};
-
-const DB_VERSION: u32 = 1;
+use crate::bitswap::MAX_MH_SIZE;
+use crate::store::{
+    clamp_to_stored, headers_in_batches, namespace_index_scans, shwap_block_height,
+    NamespaceRanges, PeerAddr, Result, SampledRanges, SamplingMetadata, SnapshotHeader, Store,
+    StoreError, StoreSnapshot, WriteBatch, WriteOp,
+};
+
+const DB_VERSION: u32 = 9;
+/// Version of the layout of the data kept in the object stores.
+///
+/// Unlike the `DB_VERSION`, which only tracks the set of the object stores and indexes,
+/// bumping it requires adding a migration of the existing data to `migrate_schema`.
+const SCHEMA_VERSION: u32 = 4;
+const SCHEMA_STORE_NAME: &str = "schema";
+const SCHEMA_VERSION_KEY: &str = "version";
 const HEADER_STORE_NAME: &str = "headers";
+const SAMPLING_STORE_NAME: &str = "sampling";
+const BLOCKS_STORE_NAME: &str = "blocks";
+const BLOCKS_META_STORE_NAME: &str = "blocks_meta";
+const PEERS_STORE_NAME: &str = "peers";
+const PEER_ADDRS_KEY: &str = "addrs";
+const META_STORE_NAME: &str = "meta";
+/// Object store indexing the heights by the namespace, keyed by the
+/// [`NamespaceRanges::index_keys`].
+const NAMESPACE_INDEX_STORE_NAME: &str = "namespace_index";
+const NETWORK_HEAD_KEY: &str = "network_head";
+const SAMPLED_RANGES_KEY: &str = "sampled_ranges";
 const HASH_INDEX_NAME: &str = "hash";
 const HEIGHT_INDEX_NAME: &str = "height";
+const ACCESSED_INDEX_NAME: &str = "accessed";
+/// Number of the least recently used blocks read at once when evicting the blocks.
+const EVICTION_BATCH_SIZE: u32 = 64;
+const DATA_STORE_NAMES: [&str; 6] = [
+    HEADER_STORE_NAME,
+    SAMPLING_STORE_NAME,
+    BLOCKS_STORE_NAME,
+    BLOCKS_META_STORE_NAME,
+    META_STORE_NAME,
+    NAMESPACE_INDEX_STORE_NAME,
+];
 
 #[derive(Debug, Serialize, Deserialize)]
 struct ExtendedHeaderEntry {
+    // Auto incremented key, assigned by the database on insertion
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<u64>,
     // We use those fields as indexes, names need to match ones in `add_index`
     height: u64,
     hash: Hash,
     header: Vec<u8>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+struct SamplingMetadataEntry {
+    // Used as a key, name needs to match the one in `key_path`
+    height: u64,
+    metadata: Vec<u8>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BlockEntry {
+    // Used as a key, name needs to match the one in `key_path`
+    cid: Vec<u8>,
+    data: Vec<u8>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BlockMetaEntry {
+    // Used as a key, name needs to match the one in `key_path`
+    cid: Vec<u8>,
+    size: u64,
+    // Used as an index, name needs to match the one in `add_index`
+    accessed: u64,
+    // Height of the shwap container, used as an index, name needs to match the one
+    // in `add_index`. Entries without it are left out of the index.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    height: Option<u64>,
+}
+
+/// Limits of the space taken by the blocks in the [`IndexedDbStore`].
+///
+/// Once storing a block would exceed the `max_size`, the least recently used blocks
+/// are evicted until the blocks take at most the `target_size`, leaving some room
+/// for the following ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockstoreQuota {
+    /// Maximum total size of the stored blocks, in bytes.
+    pub max_size: u64,
+    /// Total size of the stored blocks left after the eviction, in bytes.
+    pub target_size: u64,
+}
+
+impl Default for BlockstoreQuota {
+    fn default() -> Self {
+        BlockstoreQuota {
+            max_size: 256 * 1024 * 1024,
+            target_size: 192 * 1024 * 1024,
+        }
+    }
+}
+
+/// Space taken by the blocks in the [`IndexedDbStore`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct BlockstoreUsage {
+    /// Number of the stored blocks.
+    pub blocks: u64,
+    /// Total size of the stored blocks, in bytes.
+    pub size: u64,
+}
+
 /// A [`Store`] implementation based on a `IndexedDB` browser database.
+///
+/// Blocks are kept within the [`BlockstoreQuota`], evicting the least recently
+/// used ones, so that long sessions don't run into the storage limits of the browser.
 #[derive(Debug)]
 pub struct IndexedDbStore {
     // SendWrapper usage is safe in wasm because we're running on a single thread
     head: SendWrapper<RefCell<Option<ExtendedHeader>>>,
+    tail_height: SendWrapper<Cell<u64>>,
+    blocks_usage: SendWrapper<Cell<BlockstoreUsage>>,
+    // Logical clock ordering the accesses to the blocks
+    blocks_clock: SendWrapper<Cell<u64>>,
+    blocks_quota: BlockstoreQuota,
     db: SendWrapper<Rexie>,
 }
 
@@ -46,22 +158,63 @@ impl IndexedDbStore {
                     .add_index(Index::new(HASH_INDEX_NAME, "hash").unique(true))
                     .add_index(Index::new(HEIGHT_INDEX_NAME, "height").unique(true)),
             )
+            .add_object_store(ObjectStore::new(SAMPLING_STORE_NAME).key_path("height"))
+            .add_object_store(ObjectStore::new(BLOCKS_STORE_NAME).key_path("cid"))
+            .add_object_store(
+                ObjectStore::new(BLOCKS_META_STORE_NAME)
+                    .key_path("cid")
+                    // These need to match the names in `BlockMetaEntry`
+                    .add_index(Index::new(ACCESSED_INDEX_NAME, "accessed"))
+                    .add_index(Index::new(HEIGHT_INDEX_NAME, "height")),
+            )
+            .add_object_store(ObjectStore::new(SCHEMA_STORE_NAME))
+            .add_object_store(ObjectStore::new(PEERS_STORE_NAME))
+            .add_object_store(ObjectStore::new(META_STORE_NAME))
+            .add_object_store(ObjectStore::new(NAMESPACE_INDEX_STORE_NAME))
             .build()
             .await
             .map_err(|e| StoreError::OpenFailed(e.to_string()))?;
 
+        migrate_schema(&rexie).await?;
+
         let db_head = match get_head_from_database(&rexie).await {
             Ok(v) => Some(v),
             Err(StoreError::NotFound) => None,
             Err(e) => return Err(e),
         };
 
+        let db_tail_height = match get_tail_entry_from_database(&rexie).await {
+            Ok(entry) => entry.height,
+            // Empty store, tail will be the genesis
+            Err(StoreError::NotFound) => 1,
+            Err(e) => return Err(e),
+        };
+
+        let (blocks_usage, blocks_clock) = get_blocks_usage_from_database(&rexie).await?;
+
         Ok(Self {
             head: SendWrapper::new(RefCell::new(db_head)),
+            tail_height: SendWrapper::new(Cell::new(db_tail_height)),
+            blocks_usage: SendWrapper::new(Cell::new(blocks_usage)),
+            blocks_clock: SendWrapper::new(Cell::new(blocks_clock)),
+            blocks_quota: BlockstoreQuota::default(),
             db: SendWrapper::new(rexie),
         })
     }
 
+    /// Set the [`BlockstoreQuota`] of the store.
+    ///
+    /// The store is brought within the quota when the next block is stored.
+    pub fn with_blockstore_quota(mut self, quota: BlockstoreQuota) -> Self {
+        self.blocks_quota = quota;
+        self
+    }
+
+    /// Get the current [`BlockstoreUsage`].
+    pub fn blockstore_usage(&self) -> BlockstoreUsage {
+        self.blocks_usage.get()
+    }
+
     /// Delete the persistent store.
     pub async fn delete_db(self) -> rexie::Result<()> {
         let name = self.db.name();
@@ -83,6 +236,13 @@ impl IndexedDbStore {
             .ok_or(StoreError::NotFound)
     }
 
+    fn get_tail_height(&self) -> Result<u64> {
+        // Ensure the store is not empty
+        self.get_head_height()?;
+
+        Ok(self.tail_height.get())
+    }
+
     async fn get_by_height(&self, height: u64) -> Result<ExtendedHeader> {
         // quick check with contains_height, which uses cached head
         if !self.contains_height(height) {
@@ -129,27 +289,1013 @@ impl IndexedDbStore {
     }
 
     async fn append_single_unchecked(&self, header: ExtendedHeader) -> Result<()> {
-        let height = header.height().value();
-        let hash = header.hash();
+        let mut batch = WriteBatch::new();
+        batch.append(header);
+        self.write_batch(batch).await
+    }
+
+    async fn prepend_single_unchecked(&self, header: ExtendedHeader) -> Result<()> {
+        let mut batch = WriteBatch::new();
+        batch.prepend(header);
+        self.write_batch(batch).await
+    }
+
+    async fn write_batch(&self, batch: WriteBatch) -> Result<()> {
+        let mut head_height = self.get_head_height().ok();
+        let mut tail_height = self.tail_height.get();
+        let mut new_head = None;
+        let mut new_tail = None;
+        let mut headers = Vec::with_capacity(batch.len());
+
+        // A light check of the continuity against the cached head and tail
+        for op in batch.into_ops() {
+            let header = match op {
+                WriteOp::Append(header) => {
+                    let height = header.height().value();
+                    let head = head_height.unwrap_or(0);
+
+                    if head > 0 && height <= head {
+                        return Err(StoreError::HeightExists(height));
+                    }
+
+                    if head + 1 != height {
+                        return Err(StoreError::NonContinuousAppend(head, height));
+                    }
+
+                    head_height = Some(height);
+                    new_head = Some(header.clone());
+                    header
+                }
+                WriteOp::Prepend(header) => {
+                    let height = header.height().value();
+
+                    if head_height.is_some() {
+                        if height >= tail_height {
+                            return Err(StoreError::HeightExists(height));
+                        }
+
+                        if height + 1 != tail_height {
+                            return Err(StoreError::NonContinuousPrepend(tail_height, height));
+                        }
+                    } else {
+                        // Empty store, header becomes the head too
+                        head_height = Some(height);
+                        new_head = Some(header.clone());
+                    }
+
+                    tail_height = height;
+                    new_tail = Some(height);
+                    header
+                }
+            };
+
+            headers.push(header);
+        }
+
+        let tx = self.db.transaction(
+            &[HEADER_STORE_NAME, NAMESPACE_INDEX_STORE_NAME],
+            TransactionMode::ReadWrite,
+        )?;
+        let header_store = tx.store(HEADER_STORE_NAME)?;
+        let namespace_index_store = tx.store(NAMESPACE_INDEX_STORE_NAME)?;
+
+        if let Err(e) = add_headers(&header_store, &namespace_index_store, headers).await {
+            // don't let the transaction commit the headers added before the failure
+            tx.abort().await?;
+            return Err(e);
+        }
+
+        tx.commit().await?;
+
+        if let Some(head) = new_head {
+            // this shouldn't panic, we don't borrow across await points and wasm is single threaded
+            self.head.replace(Some(head));
+        }
+        if let Some(tail_height) = new_tail {
+            self.tail_height.set(tail_height);
+        }
+
+        Ok(())
+    }
+
+    async fn read_headers(&self, heights: RangeInclusive<u64>) -> Result<Vec<ExtendedHeader>> {
+        let tx = self
+            .db
+            .transaction(&[HEADER_STORE_NAME], TransactionMode::ReadOnly)?;
+        let height_index = tx.store(HEADER_STORE_NAME)?.index(HEIGHT_INDEX_NAME)?;
+
+        let key_range = KeyRange::bound(
+            &to_value(heights.start())?,
+            &to_value(heights.end())?,
+            false,
+            false,
+        )?;
+
+        height_index
+            .get_all(Some(&key_range), None, None, Some(Direction::Next))
+            .await?
+            .into_iter()
+            .map(|(_, entry)| {
+                let entry = from_value::<ExtendedHeaderEntry>(entry)?;
+                ExtendedHeader::decode(entry.header.as_ref())
+                    .map_err(|e| StoreError::CelestiaTypes(e.into()))
+            })
+            .collect()
+    }
+
+    async fn read_snapshot(&self, range: impl RangeBounds<u64>) -> Result<StoreSnapshot> {
+        // All the reads are done in a single transaction, so they see the same state
+        let tx = self.db.transaction(
+            &[HEADER_STORE_NAME, SAMPLING_STORE_NAME],
+            TransactionMode::ReadOnly,
+        )?;
+        let header_store = tx.store(HEADER_STORE_NAME)?;
+        let sampling_store = tx.store(SAMPLING_STORE_NAME)?;
+        let height_index = header_store.index(HEIGHT_INDEX_NAME)?;
+
+        let (_, head_entry) = height_index
+            .get_all(None, Some(1), None, Some(Direction::Prev))
+            .await?
+            .into_iter()
+            .next()
+            .ok_or(StoreError::NotFound)?;
+        let head_height = from_value::<ExtendedHeaderEntry>(head_entry)?.height;
+        let tail_height = get_tail_entry(&header_store).await?.height;
+
+        let heights = clamp_to_stored(range, tail_height, head_height);
+        let mut headers = Vec::new();
+
+        if !heights.is_empty() {
+            let key_range = KeyRange::bound(
+                &to_value(heights.start())?,
+                &to_value(heights.end())?,
+                false,
+                false,
+            )?;
+
+            for (_, entry) in height_index
+                .get_all(Some(&key_range), None, None, Some(Direction::Next))
+                .await?
+            {
+                let entry = from_value::<ExtendedHeaderEntry>(entry)?;
+                let header = ExtendedHeader::decode(entry.header.as_ref())
+                    .map_err(|e| StoreError::CelestiaTypes(e.into()))?;
+                let sampling_metadata =
+                    get_sampling_metadata(&sampling_store, entry.height).await?;
+
+                headers.push(SnapshotHeader {
+                    header,
+                    sampling_metadata,
+                });
+            }
+        }
+
+        Ok(StoreSnapshot {
+            tail_height,
+            head_height,
+            headers,
+        })
+    }
+
+    async fn remove_tail(&self) -> Result<()> {
+        let head_height = self.get_head_height()?;
+        let tail_height = self.tail_height.get();
+
+        if tail_height >= head_height {
+            return Err(StoreError::RemovingHead);
+        }
+
+        let tx = self.db.transaction(
+            &[
+                HEADER_STORE_NAME,
+                SAMPLING_STORE_NAME,
+                META_STORE_NAME,
+                NAMESPACE_INDEX_STORE_NAME,
+            ],
+            TransactionMode::ReadWrite,
+        )?;
+        let header_store = tx.store(HEADER_STORE_NAME)?;
+        let sampling_store = tx.store(SAMPLING_STORE_NAME)?;
+        let meta_store = tx.store(META_STORE_NAME)?;
+        let namespace_index_store = tx.store(NAMESPACE_INDEX_STORE_NAME)?;
+
+        let entry = get_tail_entry(&header_store).await?;
+
+        if entry.height != tail_height {
+            return Err(StoreError::LostHeight(tail_height));
+        }
+
+        let key = entry
+            .id
+            .ok_or_else(|| StoreError::StoredDataError("header entry without id".into()))?;
+        header_store.delete(&to_value(&key)?).await?;
+        sampling_store.delete(&to_value(&tail_height)?).await?;
+
+        let header = ExtendedHeader::decode(entry.header.as_ref())
+            .map_err(|e| StoreError::CelestiaTypes(e.into()))?;
+        for key in NamespaceRanges::new(&header.dah).index_keys(tail_height) {
+            namespace_index_store
+                .delete(&to_value(key.as_slice())?)
+                .await?;
+        }
+
+        let mut sampled = get_sampled_ranges(&meta_store).await?;
+        sampled.remove_below(tail_height + 1);
+        put_sampled_ranges(&meta_store, &sampled).await?;
+
+        tx.commit().await?;
+
+        self.tail_height.set(tail_height + 1);
+
+        Ok(())
+    }
+
+    async fn contains_hash(&self, hash: &Hash) -> Result<bool> {
+        let tx = self
+            .db
+            .transaction(&[HEADER_STORE_NAME], TransactionMode::ReadOnly)?;
+
+        let header_store = tx.store(HEADER_STORE_NAME)?;
+        let hash_index = header_store.index(HASH_INDEX_NAME)?;
+
+        let hash_key = KeyRange::only(&to_value(&hash)?)?;
+
+        let hash_count = hash_index.count(Some(&hash_key)).await?;
+
+        Ok(hash_count > 0)
+    }
+
+    fn contains_height(&self, height: u64) -> bool {
+        let Ok(head_height) = self.get_head_height() else {
+            return false;
+        };
+
+        height >= self.tail_height.get() && height <= head_height
+    }
+
+    async fn update_sampling_metadata(
+        &self,
+        height: u64,
+        accepted: bool,
+        cids: Vec<Cid>,
+    ) -> Result<()> {
+        if !self.contains_height(height) {
+            return Err(StoreError::NotFound);
+        }
+
+        let tx = self.db.transaction(
+            &[SAMPLING_STORE_NAME, META_STORE_NAME],
+            TransactionMode::ReadWrite,
+        )?;
+        let sampling_store = tx.store(SAMPLING_STORE_NAME)?;
+        let meta_store = tx.store(META_STORE_NAME)?;
+
+        let prev = get_sampling_metadata(&sampling_store, height).await?;
+
+        if prev.is_none() {
+            let mut sampled = get_sampled_ranges(&meta_store).await?;
+            sampled.insert(height);
+            put_sampled_ranges(&meta_store, &sampled).await?;
+        }
+
+        let metadata = SamplingMetadata::updated(prev, accepted, cids);
+
+        let entry = SamplingMetadataEntry {
+            height,
+            metadata: metadata.encode(),
+        };
+        sampling_store.put(&to_value(&entry)?, None).await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn get_sampling_metadata(&self, height: u64) -> Result<Option<SamplingMetadata>> {
+        if !self.contains_height(height) {
+            return Err(StoreError::NotFound);
+        }
+
+        let tx = self
+            .db
+            .transaction(&[SAMPLING_STORE_NAME], TransactionMode::ReadOnly)?;
+        let sampling_store = tx.store(SAMPLING_STORE_NAME)?;
+
+        get_sampling_metadata(&sampling_store, height).await
+    }
+
+    async fn unsampled_ranges(&self) -> Result<Vec<RangeInclusive<u64>>> {
+        let Ok(head_height) = self.get_head_height() else {
+            return Ok(Vec::new());
+        };
+        let tail_height = self.tail_height.get();
+
+        let tx = self
+            .db
+            .transaction(&[META_STORE_NAME], TransactionMode::ReadOnly)?;
+        let sampled = get_sampled_ranges(&tx.store(META_STORE_NAME)?).await?;
+
+        Ok(sampled.missing(tail_height..=head_height))
+    }
+
+    async fn sampled_heights(&self, range: impl RangeBounds<u64>) -> Result<Vec<u64>> {
+        let Ok(head_height) = self.get_head_height() else {
+            return Ok(Vec::new());
+        };
+        let heights = clamp_to_stored(range, self.tail_height.get(), head_height);
+
+        if heights.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let tx = self
+            .db
+            .transaction(&[META_STORE_NAME], TransactionMode::ReadOnly)?;
+        let sampled = get_sampled_ranges(&tx.store(META_STORE_NAME)?).await?;
+
+        Ok(sampled.heights(heights))
+    }
+
+    async fn heights_with_namespace(
+        &self,
+        namespace: Namespace,
+        range: impl RangeBounds<u64>,
+    ) -> Result<Vec<u64>> {
+        let Ok(head_height) = self.get_head_height() else {
+            return Ok(Vec::new());
+        };
+        let heights = clamp_to_stored(range, self.tail_height.get(), head_height);
+
+        if heights.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let tx = self
+            .db
+            .transaction(&[NAMESPACE_INDEX_STORE_NAME], TransactionMode::ReadOnly)?;
+        let namespace_index_store = tx.store(NAMESPACE_INDEX_STORE_NAME)?;
+        let mut found = BTreeSet::new();
+
+        for scan in namespace_index_scans(namespace, heights) {
+            let key_range = KeyRange::bound(
+                &to_value(scan.start().as_slice())?,
+                &to_value(scan.end().as_slice())?,
+                false,
+                false,
+            )?;
+
+            for (_, height) in namespace_index_store
+                .get_all(Some(&key_range), None, None, Some(Direction::Next))
+                .await?
+            {
+                found.insert(from_value::<u64>(height)?);
+            }
+        }
+
+        Ok(found.into_iter().collect())
+    }
+
+    async fn get_peer_addrs(&self) -> Result<Vec<PeerAddr>> {
+        let tx = self
+            .db
+            .transaction(&[PEERS_STORE_NAME], TransactionMode::ReadOnly)?;
+        let addrs = tx
+            .store(PEERS_STORE_NAME)?
+            .get(&to_value(PEER_ADDRS_KEY)?)
+            .await?;
+
+        // querying unset key returns empty value
+        if addrs.is_undefined() {
+            return Ok(Vec::new());
+        }
+
+        PeerAddr::decode_all(&from_value::<Vec<u8>>(addrs)?)
+    }
+
+    async fn set_peer_addrs(&self, addrs: Vec<PeerAddr>) -> Result<()> {
+        let tx = self
+            .db
+            .transaction(&[PEERS_STORE_NAME], TransactionMode::ReadWrite)?;
+
+        tx.store(PEERS_STORE_NAME)?
+            .put(
+                &to_value(&PeerAddr::encode_all(&addrs))?,
+                Some(&to_value(PEER_ADDRS_KEY)?),
+            )
+            .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn get_network_head(&self) -> Result<Option<ExtendedHeader>> {
+        let tx = self
+            .db
+            .transaction(&[META_STORE_NAME], TransactionMode::ReadOnly)?;
+        let serialized = tx
+            .store(META_STORE_NAME)?
+            .get(&to_value(NETWORK_HEAD_KEY)?)
+            .await?;
+
+        // querying unset key returns empty value
+        if serialized.is_undefined() {
+            return Ok(None);
+        }
+
+        let serialized: Vec<u8> = from_value(serialized)?;
+        let header = ExtendedHeader::decode(serialized.as_ref())
+            .map_err(|e| StoreError::CelestiaTypes(e.into()))?;
+
+        Ok(Some(header))
+    }
+
+    async fn set_network_head(&self, header: ExtendedHeader) -> Result<()> {
+        // make sure Result is Infallible, we unwrap it later
+        let serialized: std::result::Result<_, Infallible> = header.encode_vec();
+
+        let tx = self
+            .db
+            .transaction(&[META_STORE_NAME], TransactionMode::ReadWrite)?;
+
+        tx.store(META_STORE_NAME)?
+            .put(
+                &to_value(&serialized.unwrap())?,
+                Some(&to_value(NETWORK_HEAD_KEY)?),
+            )
+            .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    fn next_block_access(&self) -> u64 {
+        let accessed = self.blocks_clock.get() + 1;
+        self.blocks_clock.set(accessed);
+        accessed
+    }
+
+    /// Apply the change of the blocks usage made by a committed transaction.
+    ///
+    /// Only the difference is applied, without awaiting in between reading and writing
+    /// the usage, so the changes of the concurrent transactions aren't lost.
+    fn update_blocks_usage(&self, added: BlockstoreUsage, removed: BlockstoreUsage) {
+        let usage = self.blocks_usage.get();
+        self.blocks_usage.set(BlockstoreUsage {
+            blocks: (usage.blocks + added.blocks).saturating_sub(removed.blocks),
+            size: (usage.size + added.size).saturating_sub(removed.size),
+        });
+    }
+
+    async fn get_block(&self, cid: Vec<u8>) -> Result<Option<Vec<u8>>> {
+        let cid_key = to_value(&cid)?;
+
+        let tx = self
+            .db
+            .transaction(&[BLOCKS_STORE_NAME], TransactionMode::ReadOnly)?;
+        let entry = tx.store(BLOCKS_STORE_NAME)?.get(&cid_key).await?;
+
+        // querying unset key returns empty value
+        if entry.is_falsy() {
+            return Ok(None);
+        }
+
+        let data = from_value::<BlockEntry>(entry)?.data;
+
+        // Only the access time is written, so the reads of the blocks don't wait for
+        // each other
+        let tx = self
+            .db
+            .transaction(&[BLOCKS_META_STORE_NAME], TransactionMode::ReadWrite)?;
+        let meta_store = tx.store(BLOCKS_META_STORE_NAME)?;
+
+        let meta_entry = meta_store.get(&cid_key).await?;
+        if !meta_entry.is_falsy() {
+            let mut meta_entry = from_value::<BlockMetaEntry>(meta_entry)?;
+            meta_entry.accessed = self.next_block_access();
+            meta_store.put(&to_value(&meta_entry)?, None).await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(Some(data))
+    }
+
+    async fn put_block(&self, cid: Vec<u8>, height: Option<u64>, data: Vec<u8>) -> Result<bool> {
+        let tx = self.db.transaction(
+            &[BLOCKS_STORE_NAME, BLOCKS_META_STORE_NAME],
+            TransactionMode::ReadWrite,
+        )?;
+        let blocks_store = tx.store(BLOCKS_STORE_NAME)?;
+        let meta_store = tx.store(BLOCKS_META_STORE_NAME)?;
+
+        let key = KeyRange::only(&to_value(&cid)?)?;
+        if blocks_store.count(Some(&key)).await? > 0 {
+            return Ok(false);
+        }
+
+        let size = data.len() as u64;
+        // Read once the transaction is running, after the previous writes were committed
+        let usage = self.blocks_usage.get();
+        let mut evicted = BlockstoreUsage::default();
+
+        if usage.size + size > self.blocks_quota.max_size {
+            let lru_index = meta_store.index(ACCESSED_INDEX_NAME)?;
+
+            // Walk the index from the least recently used blocks, reading only as many
+            // of them as needed. Evicted entries are gone from the index, so each batch
+            // starts from its beginning again.
+            'evict: loop {
+                let lru_entries = lru_index
+                    .get_all(None, Some(EVICTION_BATCH_SIZE), None, Some(Direction::Next))
+                    .await?;
+
+                if lru_entries.is_empty() {
+                    break;
+                }
+
+                for (_, meta_entry) in lru_entries {
+                    if usage.size.saturating_sub(evicted.size) + size
+                        <= self.blocks_quota.target_size
+                    {
+                        break 'evict;
+                    }
+
+                    let meta_entry = from_value::<BlockMetaEntry>(meta_entry)?;
+                    let cid_key = to_value(&meta_entry.cid)?;
+                    blocks_store.delete(&cid_key).await?;
+                    meta_store.delete(&cid_key).await?;
+
+                    evicted.blocks += 1;
+                    evicted.size += meta_entry.size;
+                }
+            }
+        }
+
+        let meta_entry = BlockMetaEntry {
+            cid: cid.clone(),
+            size,
+            accessed: self.next_block_access(),
+            height,
+        };
+        let entry = BlockEntry { cid, data };
+        blocks_store.put(&to_value(&entry)?, None).await?;
+        meta_store.put(&to_value(&meta_entry)?, None).await?;
+
+        tx.commit().await?;
+
+        self.update_blocks_usage(BlockstoreUsage { blocks: 1, size }, evicted);
+
+        Ok(true)
+    }
+
+    async fn remove_block(&self, cid: Vec<u8>) -> Result<()> {
+        let tx = self.db.transaction(
+            &[BLOCKS_STORE_NAME, BLOCKS_META_STORE_NAME],
+            TransactionMode::ReadWrite,
+        )?;
+        let blocks_store = tx.store(BLOCKS_STORE_NAME)?;
+        let meta_store = tx.store(BLOCKS_META_STORE_NAME)?;
+
+        let cid_key = to_value(&cid)?;
+        let meta_entry = meta_store.get(&cid_key).await?;
+
+        // querying unset key returns empty value
+        if meta_entry.is_falsy() {
+            return Ok(());
+        }
+
+        // the height index is kept in the metadata, so it goes away with it
+        let meta_entry = from_value::<BlockMetaEntry>(meta_entry)?;
+        blocks_store.delete(&cid_key).await?;
+        meta_store.delete(&cid_key).await?;
+
+        tx.commit().await?;
+
+        let removed = BlockstoreUsage {
+            blocks: 1,
+            size: meta_entry.size,
+        };
+        self.update_blocks_usage(BlockstoreUsage::default(), removed);
+
+        Ok(())
+    }
+
+    async fn remove_blocks_below(&self, height: u64) -> Result<u64> {
+        let tx = self.db.transaction(
+            &[BLOCKS_STORE_NAME, BLOCKS_META_STORE_NAME],
+            TransactionMode::ReadWrite,
+        )?;
+        let blocks_store = tx.store(BLOCKS_STORE_NAME)?;
+        let meta_store = tx.store(BLOCKS_META_STORE_NAME)?;
+
+        let below = KeyRange::upper_bound(&to_value(&height)?, true)?;
+        let entries = meta_store
+            .index(HEIGHT_INDEX_NAME)?
+            .get_all(Some(&below), None, None, None)
+            .await?;
+
+        let mut removed = BlockstoreUsage::default();
+
+        for (_, meta_entry) in entries {
+            let meta_entry = from_value::<BlockMetaEntry>(meta_entry)?;
+            let cid_key = to_value(&meta_entry.cid)?;
+            blocks_store.delete(&cid_key).await?;
+            meta_store.delete(&cid_key).await?;
+
+            removed.blocks += 1;
+            removed.size += meta_entry.size;
+        }
+
+        tx.commit().await?;
+        self.update_blocks_usage(BlockstoreUsage::default(), removed);
+
+        Ok(removed.blocks)
+    }
+}
+
+#[async_trait]
+impl Store for IndexedDbStore {
+    async fn get_head(&self) -> Result<ExtendedHeader> {
+        self.get_head()
+    }
+
+    async fn get_by_hash(&self, hash: &Hash) -> Result<ExtendedHeader> {
+        let fut = SendWrapper::new(self.get_by_hash(hash));
+        fut.await
+    }
+
+    async fn get_by_height(&self, height: u64) -> Result<ExtendedHeader> {
+        let fut = SendWrapper::new(self.get_by_height(height));
+        fut.await
+    }
+
+    async fn head_height(&self) -> Result<u64> {
+        self.get_head_height()
+    }
+
+    async fn tail_height(&self) -> Result<u64> {
+        self.get_tail_height()
+    }
+
+    async fn has(&self, hash: &Hash) -> bool {
+        let fut = SendWrapper::new(self.contains_hash(hash));
+        fut.await.unwrap_or(false)
+    }
+
+    async fn has_at(&self, height: u64) -> bool {
+        self.contains_height(height)
+    }
+
+    async fn remove_tail(&self) -> Result<()> {
+        let fut = SendWrapper::new(self.remove_tail());
+        fut.await
+    }
+
+    async fn remove_blocks_below(&self, height: u64) -> Result<u64> {
+        let fut = SendWrapper::new(self.remove_blocks_below(height));
+        fut.await
+    }
+
+    async fn append_single_unchecked(&self, header: ExtendedHeader) -> Result<()> {
+        let fut = SendWrapper::new(self.append_single_unchecked(header));
+        fut.await
+    }
+
+    async fn prepend_single_unchecked(&self, header: ExtendedHeader) -> Result<()> {
+        let fut = SendWrapper::new(self.prepend_single_unchecked(header));
+        fut.await
+    }
+
+    async fn write_batch(&self, batch: WriteBatch) -> Result<()> {
+        let fut = SendWrapper::new(self.write_batch(batch));
+        fut.await
+    }
+
+    async fn snapshot<R>(&self, range: R) -> Result<StoreSnapshot>
+    where
+        R: RangeBounds<u64> + Send,
+    {
+        let fut = SendWrapper::new(self.read_snapshot(range));
+        fut.await
+    }
+
+    fn headers_range<R>(&self, range: R) -> BoxStream<'_, Result<ExtendedHeader>>
+    where
+        R: RangeBounds<u64>,
+    {
+        let bounds = (range.start_bound().cloned(), range.end_bound().cloned());
+        headers_in_batches(self, bounds, move |heights| {
+            SendWrapper::new(self.read_headers(heights))
+        })
+    }
+
+    async fn update_sampling_metadata(
+        &self,
+        height: u64,
+        accepted: bool,
+        cids: Vec<Cid>,
+    ) -> Result<()> {
+        let fut = SendWrapper::new(self.update_sampling_metadata(height, accepted, cids));
+        fut.await
+    }
+
+    async fn get_sampling_metadata(&self, height: u64) -> Result<Option<SamplingMetadata>> {
+        let fut = SendWrapper::new(self.get_sampling_metadata(height));
+        fut.await
+    }
+
+    async fn unsampled_ranges(&self) -> Result<Vec<RangeInclusive<u64>>> {
+        let fut = SendWrapper::new(self.unsampled_ranges());
+        fut.await
+    }
+
+    async fn sampled_heights<R>(&self, range: R) -> Result<Vec<u64>>
+    where
+        R: RangeBounds<u64> + Send,
+    {
+        let fut = SendWrapper::new(self.sampled_heights(range));
+        fut.await
+    }
+
+    async fn heights_with_namespace<R>(&self, namespace: Namespace, range: R) -> Result<Vec<u64>>
+    where
+        R: RangeBounds<u64> + Send,
+    {
+        let fut = SendWrapper::new(self.heights_with_namespace(namespace, range));
+        fut.await
+    }
+
+    async fn get_peer_addrs(&self) -> Result<Vec<PeerAddr>> {
+        let fut = SendWrapper::new(self.get_peer_addrs());
+        fut.await
+    }
+
+    async fn set_peer_addrs(&self, addrs: Vec<PeerAddr>) -> Result<()> {
+        let fut = SendWrapper::new(self.set_peer_addrs(addrs));
+        fut.await
+    }
+
+    async fn get_network_head(&self) -> Result<Option<ExtendedHeader>> {
+        let fut = SendWrapper::new(self.get_network_head());
+        fut.await
+    }
+
+    async fn set_network_head(&self, header: ExtendedHeader) -> Result<()> {
+        let fut = SendWrapper::new(self.set_network_head(header));
+        fut.await
+    }
+}
+
+#[cfg_attr(not(docs_rs), async_trait)]
+impl Blockstore for IndexedDbStore {
+    async fn get<const S: usize>(
+        &self,
+        cid: &CidGeneric<S>,
+    ) -> Result<Option<Vec<u8>>, BlockstoreError> {
+        let fut = SendWrapper::new(self.get_block(cid.to_bytes()));
+        fut.await
+            .map_err(|e| BlockstoreError::StorageError(e.to_string()))
+    }
+
+    async fn put_keyed<const S: usize>(
+        &self,
+        cid: &CidGeneric<S>,
+        data: &[u8],
+    ) -> Result<(), BlockstoreError> {
+        let height = shwap_block_height(cid);
+        let fut = SendWrapper::new(self.put_block(cid.to_bytes(), height, data.to_vec()));
+
+        match fut.await {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(BlockstoreError::CidExists),
+            Err(e) => Err(BlockstoreError::StorageError(e.to_string())),
+        }
+    }
+
+    async fn remove<const S: usize>(&self, cid: &CidGeneric<S>) -> Result<(), BlockstoreError> {
+        let fut = SendWrapper::new(self.remove_block(cid.to_bytes()));
+        fut.await
+            .map_err(|e| BlockstoreError::StorageError(e.to_string()))
+    }
+}
+
+impl From<rexie::Error> for StoreError {
+    fn from(error: rexie::Error) -> StoreError {
+        use rexie::Error as E;
+        match error {
+            e @ E::AsyncChannelError => StoreError::ExecutorError(e.to_string()),
+            other => StoreError::BackingStoreError(other.to_string()),
+        }
+    }
+}
+
+impl From<serde_wasm_bindgen::Error> for StoreError {
+    fn from(error: serde_wasm_bindgen::Error) -> StoreError {
+        StoreError::StoredDataError(format!("Error de/serializing: {error}"))
+    }
+}
+
+async fn get_head_from_database(db: &Rexie) -> Result<ExtendedHeader> {
+    let tx = db.transaction(&[HEADER_STORE_NAME], TransactionMode::ReadOnly)?;
+    let store = tx.store(HEADER_STORE_NAME)?;
+
+    // headers can be prepended, so the insertion order doesn't match the height order
+    let store_head = store
+        .index(HEIGHT_INDEX_NAME)?
+        .get_all(None, Some(1), None, Some(Direction::Prev))
+        .await?
+        .first()
+        .ok_or(StoreError::NotFound)?
+        .1
+        .to_owned();
+
+    let serialized_header = from_value::<ExtendedHeaderEntry>(store_head)?.header;
+
+    ExtendedHeader::decode(serialized_header.as_ref())
+        .map_err(|e| StoreError::CelestiaTypes(e.into()))
+}
+
+async fn get_tail_entry_from_database(db: &Rexie) -> Result<ExtendedHeaderEntry> {
+    let tx = db.transaction(&[HEADER_STORE_NAME], TransactionMode::ReadOnly)?;
+    let store = tx.store(HEADER_STORE_NAME)?;
+
+    get_tail_entry(&store).await
+}
+
+async fn get_tail_entry(store: &RexieStore) -> Result<ExtendedHeaderEntry> {
+    let (_, value) = store
+        .index(HEIGHT_INDEX_NAME)?
+        .get_all(None, Some(1), None, Some(Direction::Next))
+        .await?
+        .into_iter()
+        .next()
+        .ok_or(StoreError::NotFound)?;
+
+    Ok(from_value(value)?)
+}
+
+/// Get the usage of the blockstore and the latest access to its blocks.
+async fn get_blocks_usage_from_database(db: &Rexie) -> Result<(BlockstoreUsage, u64)> {
+    let tx = db.transaction(&[BLOCKS_META_STORE_NAME], TransactionMode::ReadOnly)?;
+    let meta_store = tx.store(BLOCKS_META_STORE_NAME)?;
+
+    let mut usage = BlockstoreUsage::default();
+    let mut clock = 0;
+
+    for (_, meta_entry) in meta_store.get_all(None, None, None, None).await? {
+        let meta_entry = from_value::<BlockMetaEntry>(meta_entry)?;
+        usage.blocks += 1;
+        usage.size += meta_entry.size;
+        clock = clock.max(meta_entry.accessed);
+    }
+
+    Ok((usage, clock))
+}
+
+/// Bring the data in the database to the current [`SCHEMA_VERSION`].
+///
+/// Databases created before the schema was versioned are at the version 0. Each migration
+/// is run in a transaction over all the data object stores, which is committed together
+/// with the new version, so an interrupted migration is retried when the store is
+/// opened again.
+async fn migrate_schema(db: &Rexie) -> Result<()> {
+    let mut version = get_schema_version(db).await?;
+
+    if version > SCHEMA_VERSION {
+        return Err(StoreError::OpenFailed(format!(
+            "Unsupported schema version {version}, newest known is {SCHEMA_VERSION}"
+        )));
+    }
+
+    while version < SCHEMA_VERSION {
+        let mut store_names = DATA_STORE_NAMES.to_vec();
+        store_names.push(SCHEMA_STORE_NAME);
+
+        let tx = db.transaction(&store_names, TransactionMode::ReadWrite)?;
+        match version {
+            0 => migrate_to_v1(&tx).await?,
+            1 => migrate_to_v2(&tx).await?,
+            2 => migrate_to_v3(&tx).await?,
+            3 => migrate_to_v4(&tx).await?,
+            _ => unreachable!("missing migration from schema version {version}"),
+        }
+
+        version += 1;
+        tx.store(SCHEMA_STORE_NAME)?
+            .put(&to_value(&version)?, Some(&to_value(SCHEMA_VERSION_KEY)?))
+            .await?;
+        tx.commit().await?;
+    }
+
+    Ok(())
+}
+
+async fn get_schema_version(db: &Rexie) -> Result<u32> {
+    let tx = db.transaction(&[SCHEMA_STORE_NAME], TransactionMode::ReadOnly)?;
+    let version = tx
+        .store(SCHEMA_STORE_NAME)?
+        .get(&to_value(SCHEMA_VERSION_KEY)?)
+        .await?;
+
+    // querying unset key returns empty value
+    if version.is_undefined() {
+        return Ok(0);
+    }
+
+    Ok(from_value(version)?)
+}
+
+/// Remove the blocks without the metadata, stored before the usage was tracked.
+async fn migrate_to_v1(tx: &Transaction) -> Result<()> {
+    let blocks_store = tx.store(BLOCKS_STORE_NAME)?;
+    let meta_store = tx.store(BLOCKS_META_STORE_NAME)?;
+
+    if blocks_store.count(None).await? != meta_store.count(None).await? {
+        blocks_store.clear().await?;
+        meta_store.clear().await?;
+    }
+
+    Ok(())
+}
+
+/// Build the [`SampledRanges`] from the stored sampling metadata.
+async fn migrate_to_v2(tx: &Transaction) -> Result<()> {
+    let sampling_store = tx.store(SAMPLING_STORE_NAME)?;
+    let meta_store = tx.store(META_STORE_NAME)?;
+
+    let mut heights = Vec::new();
+    for (_, entry) in sampling_store.get_all(None, None, None, None).await? {
+        heights.push(from_value::<SamplingMetadataEntry>(entry)?.height);
+    }
+
+    put_sampled_ranges(&meta_store, &SampledRanges::from_heights(heights)).await
+}
+
+/// Index the stored headers by the namespace.
+async fn migrate_to_v3(tx: &Transaction) -> Result<()> {
+    let header_store = tx.store(HEADER_STORE_NAME)?;
+    let namespace_index_store = tx.store(NAMESPACE_INDEX_STORE_NAME)?;
+
+    for (_, entry) in header_store.get_all(None, None, None, None).await? {
+        let entry = from_value::<ExtendedHeaderEntry>(entry)?;
+        let header = ExtendedHeader::decode(entry.header.as_ref())
+            .map_err(|e| StoreError::CelestiaTypes(e.into()))?;
+
+        put_namespace_index(&namespace_index_store, &header).await?;
+    }
+
+    Ok(())
+}
+
+/// Index the blocks of the shwap containers stored before they were indexed by the height.
+async fn migrate_to_v4(tx: &Transaction) -> Result<()> {
+    let meta_store = tx.store(BLOCKS_META_STORE_NAME)?;
+
+    for (_, meta_entry) in meta_store.get_all(None, None, None, None).await? {
+        let mut meta_entry = from_value::<BlockMetaEntry>(meta_entry)?;
+
+        if meta_entry.height.is_some() {
+            continue;
+        }
+
+        meta_entry.height = CidGeneric::<MAX_MH_SIZE>::try_from(meta_entry.cid.as_slice())
+            .ok()
+            .and_then(|cid| shwap_block_height(&cid));
+
+        if meta_entry.height.is_some() {
+            meta_store.put(&to_value(&meta_entry)?, None).await?;
+        }
+    }
+
+    Ok(())
+}
 
-        let head_height = self.get_head_height().unwrap_or(0);
+/// Index the header by the namespace ranges of its rows.
+async fn put_namespace_index(store: &RexieStore, header: &ExtendedHeader) -> Result<()> {
+    let height = header.height().value();
 
-        // A light check before checking the whole map
-        if head_height > 0 && height <= head_height {
-            return Err(StoreError::HeightExists(height));
-        }
+    for key in NamespaceRanges::new(&header.dah).index_keys(height) {
+        store
+            .put(&to_value(&height)?, Some(&to_value(key.as_slice())?))
+            .await?;
+    }
 
-        // Check if it's continuous before checking the whole map.
-        if head_height + 1 != height {
-            return Err(StoreError::NonContinuousAppend(head_height, height));
-        }
+    Ok(())
+}
 
-        let tx = self
-            .db
-            .transaction(&[HEADER_STORE_NAME], TransactionMode::ReadWrite)?;
-        let header_store = tx.store(HEADER_STORE_NAME)?;
+/// Add the headers to the store, failing if any of the heights or hashes already exists.
+async fn add_headers(
+    header_store: &RexieStore,
+    namespace_index_store: &RexieStore,
+    headers: Vec<ExtendedHeader>,
+) -> Result<()> {
+    let height_index = header_store.index(HEIGHT_INDEX_NAME)?;
+    let hash_index = header_store.index(HASH_INDEX_NAME)?;
+
+    for header in headers {
+        let height = header.height().value();
+        let hash = header.hash();
 
-        let height_index = header_store.index(HEIGHT_INDEX_NAME)?;
         let jsvalue_height_key = KeyRange::only(&to_value(&height)?)?;
         if height_index
             .count(Some(&jsvalue_height_key))
@@ -160,132 +1306,76 @@ impl IndexedDbStore {
             return Err(StoreError::HeightExists(height));
         }
 
-        let hash_index = header_store.index(HASH_INDEX_NAME)?;
         let jsvalue_hash_key = KeyRange::only(&to_value(&hash)?)?;
         if hash_index.count(Some(&jsvalue_hash_key)).await.unwrap_or(0) != 0 {
             return Err(StoreError::HashExists(hash));
         }
 
+        put_namespace_index(namespace_index_store, &header).await?;
+
         // make sure Result is Infallible, we unwrap it later
         let serialized_header: std::result::Result<_, Infallible> = header.encode_vec();
 
         let header_entry = ExtendedHeaderEntry {
-            height: header.height().value(),
-            hash: header.hash(),
+            id: None,
+            height,
+            hash,
             header: serialized_header.unwrap(),
         };
 
-        let jsvalue_header = to_value(&header_entry)?;
-
-        header_store.add(&jsvalue_header, None).await?;
-
-        tx.commit().await?;
-
-        // this shouldn't panic, we don't borrow across await points and wasm is single threaded
-        self.head.replace(Some(header));
-
-        Ok(())
-    }
-
-    async fn contains_hash(&self, hash: &Hash) -> Result<bool> {
-        let tx = self
-            .db
-            .transaction(&[HEADER_STORE_NAME], TransactionMode::ReadOnly)?;
-
-        let header_store = tx.store(HEADER_STORE_NAME)?;
-        let hash_index = header_store.index(HASH_INDEX_NAME)?;
-
-        let hash_key = KeyRange::only(&to_value(&hash)?)?;
-
-        let hash_count = hash_index.count(Some(&hash_key)).await?;
-
-        Ok(hash_count > 0)
+        header_store.add(&to_value(&header_entry)?, None).await?;
     }
 
-    fn contains_height(&self, height: u64) -> bool {
-        let Ok(head_height) = self.get_head_height() else {
-            return false;
-        };
-
-        height <= head_height
-    }
+    Ok(())
 }
 
-#[async_trait]
-impl Store for IndexedDbStore {
-    async fn get_head(&self) -> Result<ExtendedHeader> {
-        self.get_head()
-    }
-
-    async fn get_by_hash(&self, hash: &Hash) -> Result<ExtendedHeader> {
-        let fut = SendWrapper::new(self.get_by_hash(hash));
-        fut.await
-    }
-
-    async fn get_by_height(&self, height: u64) -> Result<ExtendedHeader> {
-        let fut = SendWrapper::new(self.get_by_height(height));
-        fut.await
-    }
-
-    async fn head_height(&self) -> Result<u64> {
-        self.get_head_height()
-    }
-
-    async fn has(&self, hash: &Hash) -> bool {
-        let fut = SendWrapper::new(self.contains_hash(hash));
-        fut.await.unwrap_or(false)
-    }
+async fn get_sampling_metadata(
+    store: &RexieStore,
+    height: u64,
+) -> Result<Option<SamplingMetadata>> {
+    let entry = store.get(&to_value(&height)?).await?;
 
-    async fn has_at(&self, height: u64) -> bool {
-        self.contains_height(height)
+    // querying unset key returns empty value
+    if entry.is_falsy() {
+        return Ok(None);
     }
 
-    async fn append_single_unchecked(&self, header: ExtendedHeader) -> Result<()> {
-        let fut = SendWrapper::new(self.append_single_unchecked(header));
-        fut.await
-    }
+    let entry = from_value::<SamplingMetadataEntry>(entry)?;
+    SamplingMetadata::decode(&entry.metadata).map(Some)
 }
 
-impl From<rexie::Error> for StoreError {
-    fn from(error: rexie::Error) -> StoreError {
-        use rexie::Error as E;
-        match error {
-            e @ E::AsyncChannelError => StoreError::ExecutorError(e.to_string()),
-            other => StoreError::BackingStoreError(other.to_string()),
-        }
-    }
-}
+async fn get_sampled_ranges(store: &RexieStore) -> Result<SampledRanges> {
+    let serialized = store.get(&to_value(SAMPLED_RANGES_KEY)?).await?;
 
-impl From<serde_wasm_bindgen::Error> for StoreError {
-    fn from(error: serde_wasm_bindgen::Error) -> StoreError {
-        StoreError::StoredDataError(format!("Error de/serializing: {error}"))
+    // querying unset key returns empty value
+    if serialized.is_undefined() {
+        return Ok(SampledRanges::default());
     }
-}
-
-async fn get_head_from_database(db: &Rexie) -> Result<ExtendedHeader> {
-    let tx = db.transaction(&[HEADER_STORE_NAME], TransactionMode::ReadOnly)?;
-    let store = tx.store(HEADER_STORE_NAME)?;
 
-    let store_head = store
-        .get_all(None, Some(1), None, Some(Direction::Prev))
-        .await?
-        .first()
-        .ok_or(StoreError::NotFound)?
-        .1
-        .to_owned();
+    let serialized: Vec<u8> = from_value(serialized)?;
+    SampledRanges::decode(&serialized)
+}
 
-    let serialized_header = from_value::<ExtendedHeaderEntry>(store_head)?.header;
+async fn put_sampled_ranges(store: &RexieStore, sampled: &SampledRanges) -> Result<()> {
+    store
+        .put(
+            &to_value(&sampled.encode())?,
+            Some(&to_value(SAMPLED_RANGES_KEY)?),
+        )
+        .await?;
 
-    ExtendedHeader::decode(serialized_header.as_ref())
-        .map_err(|e| StoreError::CelestiaTypes(e.into()))
+    Ok(())
 }
 
 #[cfg(test)]
 pub mod tests {
     use super::*;
+    use crate::store::HEADERS_RANGE_BATCH_SIZE;
+    use celestia_types::row::{IdLayout, RowId};
     use celestia_types::test_utils::ExtendedHeaderGenerator;
     use celestia_types::Height;
     use function_name::named;
+    use futures::TryStreamExt;
     use wasm_bindgen_test::wasm_bindgen_test;
 
     wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
@@ -418,6 +1508,45 @@ pub mod tests {
             insert_existing_result,
             Err(StoreError::NonContinuousAppend(13, 15))
         ));
+        // the range is appended atomically
+        assert_eq!(s.head_height().await.unwrap(), 10);
+    }
+
+    #[named]
+    #[wasm_bindgen_test]
+    async fn test_write_batch_and_snapshot() {
+        let (s, mut gen) = gen_filled_store(3, function_name!()).await;
+        let hs = gen.next_many(3);
+
+        let mut batch = WriteBatch::new();
+        batch.append(hs[0].clone()).append(hs[1].clone());
+        s.write_batch(batch).await.unwrap();
+        assert_eq!(s.head_height().await.unwrap(), 5);
+
+        // failing write discards the preceding ones
+        let mut batch = WriteBatch::new();
+        batch.append(hs[2].clone()).append(hs[2].clone());
+        assert!(matches!(
+            s.write_batch(batch).await,
+            Err(StoreError::HeightExists(6))
+        ));
+        assert_eq!(s.head_height().await.unwrap(), 5);
+
+        s.update_sampling_metadata(4, true, Vec::new())
+            .await
+            .unwrap();
+        s.remove_tail().await.unwrap();
+
+        let snapshot = s.snapshot(..).await.unwrap();
+        assert_eq!(snapshot.tail_height, 2);
+        assert_eq!(snapshot.head_height, 5);
+        assert_eq!(snapshot.headers.len(), 4);
+        assert_eq!(snapshot.headers[2].header, hs[0]);
+        assert!(snapshot.headers[2].sampling_metadata.is_some());
+        assert!(snapshot.headers[3].sampling_metadata.is_none());
+
+        let snapshot = s.snapshot(5..10).await.unwrap();
+        assert_eq!(snapshot.headers.len(), 1);
     }
 
     #[named]
@@ -513,6 +1642,140 @@ pub mod tests {
         }
     }
 
+    #[named]
+    #[wasm_bindgen_test]
+    async fn test_remove_tail() {
+        let (s, _) = gen_filled_store(3, function_name!()).await;
+        assert_eq!(s.get_tail_height().unwrap(), 1);
+
+        s.remove_tail().await.unwrap();
+        assert_eq!(s.get_tail_height().unwrap(), 2);
+        assert!(!s.contains_height(1));
+        assert!(matches!(
+            s.get_by_height(1).await,
+            Err(StoreError::NotFound)
+        ));
+
+        s.remove_tail().await.unwrap();
+        assert!(matches!(
+            s.remove_tail().await,
+            Err(StoreError::RemovingHead)
+        ));
+        drop(s);
+
+        let s = IndexedDbStore::new(function_name!())
+            .await
+            .expect("failed to reopen store");
+        assert_eq!(s.get_tail_height().unwrap(), 3);
+        assert_eq!(s.get_head_height().unwrap(), 3);
+    }
+
+    #[named]
+    #[wasm_bindgen_test]
+    async fn test_prepend() {
+        let (s, mut gen) = gen_filled_store(0, function_name!()).await;
+        let headers = gen.next_many(10);
+
+        // Empty store can be started from any height
+        s.prepend_single_unchecked(headers[9].clone())
+            .await
+            .unwrap();
+        assert_eq!(s.get_head_height().unwrap(), 10);
+        assert_eq!(s.get_tail_height().unwrap(), 10);
+
+        s.prepend_single_unchecked(headers[8].clone())
+            .await
+            .unwrap();
+        assert!(matches!(
+            s.prepend_single_unchecked(headers[8].clone()).await,
+            Err(StoreError::HeightExists(9))
+        ));
+        assert!(matches!(
+            s.prepend_single_unchecked(headers[6].clone()).await,
+            Err(StoreError::NonContinuousPrepend(9, 7))
+        ));
+
+        s.append_single_unchecked(gen.next()).await.unwrap();
+        drop(s);
+
+        // head and tail are found by height, not by the insertion order
+        let s = IndexedDbStore::new(function_name!())
+            .await
+            .expect("failed to reopen store");
+        assert_eq!(s.get_tail_height().unwrap(), 9);
+        assert_eq!(s.get_head_height().unwrap(), 11);
+        assert_eq!(s.get_by_height(9).await.unwrap(), headers[8]);
+
+        s.remove_tail().await.unwrap();
+        assert_eq!(s.get_tail_height().unwrap(), 10);
+    }
+
+    #[named]
+    #[wasm_bindgen_test]
+    async fn test_sampling_metadata() {
+        let (s, _) = gen_filled_store(3, function_name!()).await;
+        let cid = |n| Cid::new_v1(0x55, multihash::Multihash::wrap(0x12, &[n; 32]).unwrap());
+
+        s.update_sampling_metadata(1, true, vec![cid(1)])
+            .await
+            .unwrap();
+        s.update_sampling_metadata(3, false, vec![cid(3)])
+            .await
+            .unwrap();
+        s.update_sampling_metadata(3, true, vec![cid(3), cid(4)])
+            .await
+            .unwrap();
+        assert!(matches!(
+            s.update_sampling_metadata(4, true, Vec::new()).await,
+            Err(StoreError::NotFound)
+        ));
+        drop(s);
+
+        let s = IndexedDbStore::new(function_name!())
+            .await
+            .expect("failed to reopen store");
+        assert_eq!(s.get_sampling_metadata(2).await.unwrap(), None);
+        assert_eq!(
+            s.get_sampling_metadata(3).await.unwrap(),
+            Some(SamplingMetadata {
+                accepted: true,
+                cids_sampled: vec![cid(3), cid(4)],
+            })
+        );
+        assert_eq!(Store::next_unsampled_height(&s).await.unwrap(), Some(2));
+        assert_eq!(s.unsampled_ranges().await.unwrap(), vec![2..=2]);
+        assert_eq!(s.sampled_heights(..).await.unwrap(), vec![1, 3]);
+        assert_eq!(s.sampled_heights(2..).await.unwrap(), vec![3]);
+
+        s.remove_tail().await.unwrap();
+        assert!(matches!(
+            s.get_sampling_metadata(1).await,
+            Err(StoreError::NotFound)
+        ));
+        assert_eq!(s.unsampled_ranges().await.unwrap(), vec![2..=2]);
+        assert_eq!(s.sampled_heights(..).await.unwrap(), vec![3]);
+    }
+
+    #[named]
+    #[wasm_bindgen_test]
+    async fn test_headers_range() {
+        let (s, _) = gen_filled_store(HEADERS_RANGE_BATCH_SIZE + 10, function_name!()).await;
+
+        let all: Vec<_> = Store::headers_range(&s, ..).try_collect().await.unwrap();
+        assert_eq!(all, s.get_range(..).await.unwrap());
+
+        s.remove_tail().await.unwrap();
+        let heights: Vec<_> = Store::headers_range(&s, ..=HEADERS_RANGE_BATCH_SIZE + 2)
+            .map_ok(|header| header.height().value())
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(
+            heights,
+            (2..=HEADERS_RANGE_BATCH_SIZE + 2).collect::<Vec<_>>()
+        );
+    }
+
     #[named]
     #[wasm_bindgen_test]
     async fn test_delete_db() {
@@ -531,6 +1794,300 @@ pub mod tests {
         ));
     }
 
+    #[named]
+    #[wasm_bindgen_test]
+    async fn test_blockstore() {
+        let (s, _) = gen_filled_store(0, function_name!()).await;
+        let cid = Cid::new_v1(0x55, multihash::Multihash::wrap(0x12, &[1; 32]).unwrap());
+
+        assert_eq!(s.get(&cid).await.unwrap(), None);
+
+        s.put_keyed(&cid, b"data").await.unwrap();
+        assert_eq!(s.get(&cid).await.unwrap().unwrap(), b"data");
+        assert!(Blockstore::has(&s, &cid).await.unwrap());
+
+        assert_eq!(
+            s.put_keyed(&cid, b"other").await.unwrap_err(),
+            BlockstoreError::CidExists
+        );
+
+        // blocks don't interfere with headers
+        assert!(matches!(s.get_head_height(), Err(StoreError::NotFound)));
+    }
+
+    #[named]
+    #[wasm_bindgen_test]
+    async fn test_blockstore_quota() {
+        let (s, _) = gen_filled_store(0, function_name!()).await;
+        let s = s.with_blockstore_quota(BlockstoreQuota {
+            max_size: 10,
+            target_size: 6,
+        });
+        let cid = |n: u8| Cid::new_v1(0x55, multihash::Multihash::wrap(0x12, &[n; 32]).unwrap());
+
+        s.put_keyed(&cid(1), b"1111").await.unwrap();
+        s.put_keyed(&cid(2), b"2222").await.unwrap();
+        assert_eq!(s.blockstore_usage(), BlockstoreUsage { blocks: 2, size: 8 });
+
+        // makes the second block the least recently used one
+        s.get(&cid(1)).await.unwrap().unwrap();
+
+        s.put_keyed(&cid(3), b"3333").await.unwrap();
+        assert_eq!(s.blockstore_usage(), BlockstoreUsage { blocks: 2, size: 8 });
+        assert_eq!(s.get(&cid(1)).await.unwrap().unwrap(), b"1111");
+        assert_eq!(s.get(&cid(2)).await.unwrap(), None);
+        assert_eq!(s.get(&cid(3)).await.unwrap().unwrap(), b"3333");
+
+        // usage is restored when the store is reopened
+        drop(s);
+        let s = IndexedDbStore::new(function_name!()).await.unwrap();
+        assert_eq!(s.blockstore_usage(), BlockstoreUsage { blocks: 2, size: 8 });
+    }
+
+    #[named]
+    #[wasm_bindgen_test]
+    async fn test_remove_block() {
+        let (s, _) = gen_filled_store(0, function_name!()).await;
+        let row = |height| RowId::new(0, height).unwrap().to_cid(IdLayout::V1);
+
+        s.put_keyed(&row(1), b"row").await.unwrap();
+        s.put_keyed(&row(2), b"row").await.unwrap();
+        s.remove(&row(1)).await.unwrap();
+        assert!(!Blockstore::has(&s, &row(1)).await.unwrap());
+        assert_eq!(s.blockstore_usage(), BlockstoreUsage { blocks: 1, size: 3 });
+        s.remove(&row(1)).await.unwrap();
+
+        // the index entry is gone with the block
+        assert_eq!(s.remove_blocks_below(3).await.unwrap(), 1);
+        assert_eq!(s.blockstore_usage(), BlockstoreUsage::default());
+    }
+
+    #[named]
+    #[wasm_bindgen_test]
+    async fn test_block_heights_migration() {
+        let (s, _) = gen_filled_store(0, function_name!()).await;
+        let row = |height| RowId::new(0, height).unwrap().to_cid(IdLayout::V1);
+
+        for height in 1..=3 {
+            s.put_keyed(&row(height), b"row").await.unwrap();
+        }
+
+        // pretend the blocks were stored by the schema version 3, without the heights
+        let tx =
+            s.db.transaction(
+                &[SCHEMA_STORE_NAME, BLOCKS_META_STORE_NAME],
+                TransactionMode::ReadWrite,
+            )
+            .unwrap();
+        let meta_store = tx.store(BLOCKS_META_STORE_NAME).unwrap();
+        for (_, meta_entry) in meta_store.get_all(None, None, None, None).await.unwrap() {
+            let mut meta_entry = from_value::<BlockMetaEntry>(meta_entry).unwrap();
+            meta_entry.height = None;
+            meta_store
+                .put(&to_value(&meta_entry).unwrap(), None)
+                .await
+                .unwrap();
+        }
+        tx.store(SCHEMA_STORE_NAME)
+            .unwrap()
+            .put(
+                &to_value(&3u32).unwrap(),
+                Some(&to_value(SCHEMA_VERSION_KEY).unwrap()),
+            )
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+        assert_eq!(s.remove_blocks_below(3).await.unwrap(), 0);
+        drop(s);
+
+        let s = IndexedDbStore::new(function_name!()).await.unwrap();
+        assert_eq!(s.remove_blocks_below(3).await.unwrap(), 2);
+        assert!(Blockstore::has(&s, &row(3)).await.unwrap());
+    }
+
+    #[named]
+    #[wasm_bindgen_test]
+    async fn test_blockstore_concurrent_puts() {
+        let (s, _) = gen_filled_store(0, function_name!()).await;
+        let s = s.with_blockstore_quota(BlockstoreQuota {
+            max_size: 100,
+            target_size: 10,
+        });
+        let cid = |n: u8| Cid::new_v1(0x55, multihash::Multihash::wrap(0x12, &[n; 32]).unwrap());
+
+        futures::future::try_join_all((0..100).map(|n| s.put_keyed(&cid(n), b"1")))
+            .await
+            .unwrap();
+        assert_eq!(
+            s.blockstore_usage(),
+            BlockstoreUsage {
+                blocks: 100,
+                size: 100
+            }
+        );
+
+        // evicts more than a single batch of the least recently used blocks
+        s.put_keyed(&cid(100), b"1").await.unwrap();
+        assert_eq!(
+            s.blockstore_usage(),
+            BlockstoreUsage {
+                blocks: 10,
+                size: 10
+            }
+        );
+        assert_eq!(s.get(&cid(100)).await.unwrap().unwrap(), b"1");
+
+        drop(s);
+        let s = IndexedDbStore::new(function_name!()).await.unwrap();
+        assert_eq!(
+            s.blockstore_usage(),
+            BlockstoreUsage {
+                blocks: 10,
+                size: 10
+            }
+        );
+    }
+
+    #[named]
+    #[wasm_bindgen_test]
+    async fn test_remove_blocks_below() {
+        let (s, _) = gen_filled_store(0, function_name!()).await;
+        let row = |height| RowId::new(0, height).unwrap().to_cid(IdLayout::V1);
+        let other = Cid::new_v1(0x55, multihash::Multihash::wrap(0x12, &[1; 32]).unwrap());
+
+        for height in 1..=3 {
+            s.put_keyed(&row(height), b"row").await.unwrap();
+        }
+        s.put_keyed(&other, b"other").await.unwrap();
+
+        assert_eq!(s.remove_blocks_below(3).await.unwrap(), 2);
+        assert_eq!(s.get(&row(1)).await.unwrap(), None);
+        assert_eq!(s.get(&row(2)).await.unwrap(), None);
+        assert!(Blockstore::has(&s, &row(3)).await.unwrap());
+        assert!(Blockstore::has(&s, &other).await.unwrap());
+        assert_eq!(s.blockstore_usage(), BlockstoreUsage { blocks: 2, size: 8 });
+    }
+
+    #[named]
+    #[wasm_bindgen_test]
+    async fn test_schema_migration() {
+        let (s, _) = gen_filled_store(2, function_name!()).await;
+        let cid = Cid::new_v1(0x55, multihash::Multihash::wrap(0x12, &[1; 32]).unwrap());
+        s.update_sampling_metadata(2, true, Vec::new())
+            .await
+            .unwrap();
+        let indexed = count_namespace_index(&s).await;
+
+        // pretend to be a store from before the schema was versioned, with a block
+        // stored before the usage was tracked
+        let tx =
+            s.db.transaction(
+                &[
+                    SCHEMA_STORE_NAME,
+                    BLOCKS_STORE_NAME,
+                    META_STORE_NAME,
+                    NAMESPACE_INDEX_STORE_NAME,
+                ],
+                TransactionMode::ReadWrite,
+            )
+            .unwrap();
+        tx.store(SCHEMA_STORE_NAME)
+            .unwrap()
+            .delete(&to_value(SCHEMA_VERSION_KEY).unwrap())
+            .await
+            .unwrap();
+        tx.store(META_STORE_NAME)
+            .unwrap()
+            .delete(&to_value(SAMPLED_RANGES_KEY).unwrap())
+            .await
+            .unwrap();
+        tx.store(NAMESPACE_INDEX_STORE_NAME)
+            .unwrap()
+            .clear()
+            .await
+            .unwrap();
+        let block = BlockEntry {
+            cid: cid.to_bytes(),
+            data: b"data".to_vec(),
+        };
+        tx.store(BLOCKS_STORE_NAME)
+            .unwrap()
+            .put(&to_value(&block).unwrap(), None)
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+        drop(s);
+
+        let s = IndexedDbStore::new(function_name!()).await.unwrap();
+        assert_eq!(get_schema_version(&s.db).await.unwrap(), SCHEMA_VERSION);
+        assert_eq!(s.get(&cid).await.unwrap(), None);
+        assert_eq!(s.blockstore_usage(), BlockstoreUsage::default());
+        // headers are kept
+        assert_eq!(s.get_head_height().unwrap(), 2);
+        // sampled ranges and the namespace index are rebuilt
+        assert_eq!(s.unsampled_ranges().await.unwrap(), vec![1..=1]);
+        assert_eq!(count_namespace_index(&s).await, indexed);
+    }
+
+    #[named]
+    #[wasm_bindgen_test]
+    async fn test_heights_with_namespace() {
+        let (s, mut gen) = gen_filled_store(0, function_name!()).await;
+        let ns1 = Namespace::new_v0(&[1]).unwrap();
+        let ns2 = Namespace::new_v0(&[2]).unwrap();
+        let headers: Vec<_> = [ns1, ns2, ns1, ns1]
+            .iter()
+            .map(|namespace| gen.next_with_eds(2, &[*namespace]).0)
+            .collect();
+
+        assert!(s.heights_with_namespace(ns1, ..).await.unwrap().is_empty());
+        s.append_unchecked(headers).await.unwrap();
+        s.remove_tail().await.unwrap();
+
+        assert_eq!(s.heights_with_namespace(ns1, ..).await.unwrap(), vec![3, 4]);
+        assert_eq!(s.heights_with_namespace(ns2, 2..=3).await.unwrap(), vec![2]);
+        drop(s);
+
+        let s = IndexedDbStore::new(function_name!()).await.unwrap();
+        assert_eq!(s.heights_with_namespace(ns1, ..4).await.unwrap(), vec![3]);
+    }
+
+    #[named]
+    #[wasm_bindgen_test]
+    async fn test_newer_schema_version() {
+        let (s, _) = gen_filled_store(0, function_name!()).await;
+
+        let tx =
+            s.db.transaction(&[SCHEMA_STORE_NAME], TransactionMode::ReadWrite)
+                .unwrap();
+        tx.store(SCHEMA_STORE_NAME)
+            .unwrap()
+            .put(
+                &to_value(&(SCHEMA_VERSION + 1)).unwrap(),
+                Some(&to_value(SCHEMA_VERSION_KEY).unwrap()),
+            )
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+        drop(s);
+
+        assert!(matches!(
+            IndexedDbStore::new(function_name!()).await,
+            Err(StoreError::OpenFailed(_))
+        ));
+    }
+
+    async fn count_namespace_index(s: &IndexedDbStore) -> u32 {
+        let tx =
+            s.db.transaction(&[NAMESPACE_INDEX_STORE_NAME], TransactionMode::ReadOnly)
+                .unwrap();
+        tx.store(NAMESPACE_INDEX_STORE_NAME)
+            .unwrap()
+            .count(None)
+            .await
+            .unwrap()
+    }
+
     // open IndexedDB with unique per-test name to avoid interference and make cleanup easier
     pub async fn gen_filled_store(
         amount: u64,
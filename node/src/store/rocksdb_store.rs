@@ -0,0 +1,1588 @@
+use std::collections::{BTreeSet, HashSet};
+use std::convert::Infallible;
+use std::ops::{Bound, RangeBounds, RangeInclusive};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use blockstore::{Blockstore, BlockstoreError};
+use celestia_tendermint_proto::Protobuf;
+use celestia_types::hash::Hash;
+use celestia_types::nmt::Namespace;
+use celestia_types::ExtendedHeader;
+use cid::{Cid, CidGeneric};
+use directories::ProjectDirs;
+use futures::stream::BoxStream;
+use rocksdb::{
+    ColumnFamily, Direction, Error as RocksDbError, ErrorKind, IteratorMode, Options, WriteBatch,
+    DB,
+};
+use tempdir::TempDir;
+use tokio::task::spawn_blocking;
+use tracing::debug;
+
+use crate::bitswap::MAX_MH_SIZE;
+use crate::store::Store;
+use crate::store::{
+    clamp_to_stored, headers_in_batches, namespace_index_height, namespace_index_scans,
+    network_db_path, shwap_block_height, NamespaceRanges, PeerAddr, Result, SampledRanges,
+    SamplingMetadata, SnapshotHeader, StoreError, StoreSnapshot, WriteBatch as StoreWriteBatch,
+    WriteOp,
+};
+
+const HEAD_HEIGHT_KEY: &[u8] = b"KEY.HEAD_HEIGHT";
+const TAIL_HEIGHT_KEY: &[u8] = b"KEY.TAIL_HEIGHT";
+const PEER_ADDRS_KEY: &[u8] = b"KEY.PEER_ADDRS";
+const NETWORK_HEAD_KEY: &[u8] = b"KEY.NETWORK_HEAD";
+/// Key of the [`SampledRanges`], updated together with the sampling metadata.
+const SAMPLED_RANGES_KEY: &[u8] = b"KEY.SAMPLED_RANGES";
+/// Key marking that the headers are indexed by the namespace.
+const NAMESPACE_INDEXED_KEY: &[u8] = b"KEY.NAMESPACE_INDEXED";
+/// Key marking that the blocks are indexed by the height.
+const BLOCK_HEIGHTS_INDEXED_KEY: &[u8] = b"KEY.BLOCK_HEIGHTS_INDEXED";
+
+/// Column family holding the store's metadata, like the current head height
+/// or the addresses of the known peers.
+const META_CF: &str = "meta";
+/// Column family mapping the header hash to the serialized header.
+const HEADERS_CF: &str = "headers";
+/// Column family mapping the header height to its hash.
+const HEIGHT_TO_HASH_CF: &str = "height_to_hash";
+/// Column family reserved for the data sampling metadata, keyed by height.
+const SAMPLING_METADATA_CF: &str = "sampling_metadata";
+/// Column family holding the [`Blockstore`] data.
+const BLOCKS_CF: &str = "blocks";
+/// Column family indexing the blocks of the shwap containers by the height, keyed
+/// by the height followed by the CID.
+const BLOCK_HEIGHTS_CF: &str = "block_heights";
+/// Column family indexing the heights by the namespace, keyed by the
+/// [`NamespaceRanges::index_keys`].
+const NAMESPACE_INDEX_CF: &str = "namespace_index";
+
+const COLUMN_FAMILIES: [&str; 7] = [
+    META_CF,
+    HEADERS_CF,
+    HEIGHT_TO_HASH_CF,
+    SAMPLING_METADATA_CF,
+    BLOCKS_CF,
+    BLOCK_HEIGHTS_CF,
+    NAMESPACE_INDEX_CF,
+];
+
+/// A [`Store`] implementation based on a [`rocksdb`] database.
+///
+/// Headers, sampling metadata and [`Blockstore`] data are kept in separate
+/// column families of the same database.
+#[derive(Debug)]
+pub struct RocksDbStore {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    db: DB,
+    // Serializes the appends, so that checking the head and inserting a header is atomic
+    write_lock: Mutex<()>,
+    // Removes the temporary directory on drop. Must be declared after the `db`.
+    _temp_dir: Option<TempDir>,
+}
+
+impl RocksDbStore {
+    /// Create or open a persistent store.
+    pub async fn new(network_id: String) -> Result<Self> {
+        spawn_blocking(move || {
+            let Some(project_dirs) = ProjectDirs::from("co", "eiger", "celestia") else {
+                return Err(StoreError::OpenFailed(
+                    "Unable to get system cache path to open header store".to_string(),
+                ));
+            };
+            let db_path = network_db_path(&project_dirs.cache_dir().join("rocksdb"), &network_id)?;
+
+            Self::open(&db_path, None)
+        })
+        .await?
+    }
+
+    /// Create a persistent store in a temporary directory.
+    pub async fn new_temp() -> Result<Self> {
+        spawn_blocking(move || {
+            let temp_dir = TempDir::new("celestia")?;
+            let path = temp_dir.path().to_owned();
+
+            Self::open(&path, Some(temp_dir))
+        })
+        .await?
+    }
+
+    /// Create a persistent store in a given path.
+    pub async fn new_in_path<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref().to_owned();
+
+        spawn_blocking(move || Self::open(&path, None)).await?
+    }
+
+    // Opening the database is blocking, make sure to call this from `spawn_blocking` or similar
+    fn open(path: &Path, temp_dir: Option<TempDir>) -> Result<Self> {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+
+        let db = DB::open_cf(&opts, path, COLUMN_FAMILIES)
+            .map_err(|e| StoreError::OpenFailed(e.to_string()))?;
+
+        let inner = Inner {
+            db,
+            write_lock: Mutex::new(()),
+            _temp_dir: temp_dir,
+        };
+
+        // stores created before the sampled ranges were kept
+        if inner
+            .db
+            .get_pinned_cf(inner.cf(META_CF)?, SAMPLED_RANGES_KEY)?
+            .is_none()
+        {
+            index_sampled_ranges(&inner)?;
+        }
+
+        // stores created before the headers were indexed by the namespace
+        if inner
+            .db
+            .get_pinned_cf(inner.cf(META_CF)?, NAMESPACE_INDEXED_KEY)?
+            .is_none()
+        {
+            index_namespaces(&inner)?;
+        }
+
+        // stores created before the blocks were indexed by the height
+        if inner
+            .db
+            .get_pinned_cf(inner.cf(META_CF)?, BLOCK_HEIGHTS_INDEXED_KEY)?
+            .is_none()
+        {
+            index_block_heights(&inner)?;
+        }
+
+        Ok(Self {
+            inner: Arc::new(inner),
+        })
+    }
+
+    async fn head_height(&self) -> Result<u64> {
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || inner.read_head_height()).await?
+    }
+
+    async fn tail_height(&self) -> Result<u64> {
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || inner.read_tail_height()).await?
+    }
+
+    async fn get_by_hash(&self, hash: &Hash) -> Result<ExtendedHeader> {
+        let inner = self.inner.clone();
+        let hash = *hash;
+
+        spawn_blocking(move || inner.read_header(&hash)).await?
+    }
+
+    async fn get_by_height(&self, height: u64) -> Result<ExtendedHeader> {
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || {
+            let hash = inner.read_hash(height)?;
+            inner.read_header(&hash)
+        })
+        .await?
+    }
+
+    async fn get_head(&self) -> Result<ExtendedHeader> {
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || {
+            let head_height = inner.read_head_height()?;
+            let hash = inner.read_hash(head_height)?;
+            inner.read_header(&hash)
+        })
+        .await?
+    }
+
+    async fn contains_hash(&self, hash: &Hash) -> bool {
+        let inner = self.inner.clone();
+        let hash = *hash;
+
+        spawn_blocking(move || {
+            let headers = inner.cf(HEADERS_CF)?;
+            Ok::<_, StoreError>(inner.db.get_pinned_cf(headers, hash.as_bytes())?.is_some())
+        })
+        .await
+        .map(|res| res.unwrap_or(false))
+        .unwrap_or(false)
+    }
+
+    async fn contains_height(&self, height: u64) -> bool {
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || {
+            let height_to_hash = inner.cf(HEIGHT_TO_HASH_CF)?;
+            Ok::<_, StoreError>(
+                inner
+                    .db
+                    .get_pinned_cf(height_to_hash, height_to_key(height))?
+                    .is_some(),
+            )
+        })
+        .await
+        .map(|res| res.unwrap_or(false))
+        .unwrap_or(false)
+    }
+
+    async fn append_single_unchecked(&self, header: ExtendedHeader) -> Result<()> {
+        let mut batch = StoreWriteBatch::new();
+        batch.append(header);
+        self.write_batch(batch).await
+    }
+
+    async fn prepend_single_unchecked(&self, header: ExtendedHeader) -> Result<()> {
+        let mut batch = StoreWriteBatch::new();
+        batch.prepend(header);
+        self.write_batch(batch).await
+    }
+
+    async fn write_batch(&self, batch: StoreWriteBatch) -> Result<()> {
+        let inner = self.inner.clone();
+
+        let written = spawn_blocking(move || {
+            let _guard = inner
+                .write_lock
+                .lock()
+                .map_err(|e| StoreError::BackingStoreError(e.to_string()))?;
+
+            let meta = inner.cf(META_CF)?;
+            let headers = inner.cf(HEADERS_CF)?;
+            let height_to_hash = inner.cf(HEIGHT_TO_HASH_CF)?;
+            let namespace_index = inner.cf(NAMESPACE_INDEX_CF)?;
+
+            let mut head_height = inner.read_head_height().ok();
+            let mut tail_height = match head_height {
+                Some(_) => inner.read_tail_height()?,
+                None => 1,
+            };
+            let mut hashes = HashSet::new();
+            let mut written = Vec::new();
+            // all the writes are committed at once, after checking each of them
+            let mut db_batch = WriteBatch::default();
+
+            for op in batch.into_ops() {
+                let (prepend, header) = match op {
+                    WriteOp::Append(header) => (false, header),
+                    WriteOp::Prepend(header) => (true, header),
+                };
+                let hash = header.hash();
+                let height = header.height().value();
+                let height_key = height_to_key(height);
+
+                if prepend {
+                    if head_height.is_some() {
+                        if height >= tail_height {
+                            return Err(StoreError::HeightExists(height));
+                        }
+
+                        if height + 1 != tail_height {
+                            return Err(StoreError::NonContinuousPrepend(tail_height, height));
+                        }
+                    } else {
+                        // Empty store, header becomes the head too
+                        head_height = Some(height);
+                        db_batch.put_cf(meta, HEAD_HEIGHT_KEY, height_key);
+                    }
+
+                    tail_height = height;
+                    db_batch.put_cf(meta, TAIL_HEIGHT_KEY, height_key);
+                } else {
+                    let head = head_height.unwrap_or(0);
+
+                    // A light check before checking the whole map
+                    if head > 0 && height <= head {
+                        return Err(StoreError::HeightExists(height));
+                    }
+
+                    // Check if it's continuous before checking the whole map.
+                    if head + 1 != height {
+                        return Err(StoreError::NonContinuousAppend(head, height));
+                    }
+
+                    head_height = Some(height);
+                    db_batch.put_cf(meta, HEAD_HEIGHT_KEY, height_key);
+                }
+
+                if inner
+                    .db
+                    .get_pinned_cf(height_to_hash, height_key)?
+                    .is_some()
+                {
+                    return Err(StoreError::HeightExists(height));
+                }
+
+                if !hashes.insert(hash)
+                    || inner.db.get_pinned_cf(headers, hash.as_bytes())?.is_some()
+                {
+                    return Err(StoreError::HashExists(hash));
+                }
+
+                for key in NamespaceRanges::new(&header.dah).index_keys(height) {
+                    db_batch.put_cf(namespace_index, key, []);
+                }
+
+                // make sure Result is Infallible, we unwrap it later
+                let serialized_header: std::result::Result<_, Infallible> = header.encode_vec();
+
+                db_batch.put_cf(height_to_hash, height_key, hash.as_bytes());
+                db_batch.put_cf(headers, hash.as_bytes(), serialized_header.unwrap());
+                written.push((prepend, hash, height));
+            }
+
+            inner.db.write(db_batch)?;
+
+            Ok::<_, StoreError>(written)
+        })
+        .await??;
+
+        for (prepend, hash, height) in written {
+            if prepend {
+                debug!("Prepending header {hash} with height {height}");
+            } else {
+                debug!("Inserting header {hash} with height {height}");
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn read_snapshot(&self, range: (Bound<u64>, Bound<u64>)) -> Result<StoreSnapshot> {
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || {
+            // All the writes hold the lock, so nothing changes while reading
+            let _guard = inner
+                .write_lock
+                .lock()
+                .map_err(|e| StoreError::BackingStoreError(e.to_string()))?;
+
+            let head_height = inner.read_head_height()?;
+            let tail_height = inner.read_tail_height()?;
+
+            let headers = clamp_to_stored(range, tail_height, head_height)
+                .map(|height| {
+                    let hash = inner
+                        .read_hash(height)
+                        .map_err(|_| StoreError::LostHeight(height))?;
+                    let header = inner
+                        .read_header(&hash)
+                        .map_err(|_| StoreError::LostHash(hash))?;
+
+                    Ok(SnapshotHeader {
+                        header,
+                        sampling_metadata: inner.read_sampling_metadata(height)?,
+                    })
+                })
+                .collect::<Result<_>>()?;
+
+            Ok(StoreSnapshot {
+                tail_height,
+                head_height,
+                headers,
+            })
+        })
+        .await?
+    }
+
+    async fn read_headers(&self, heights: RangeInclusive<u64>) -> Result<Vec<ExtendedHeader>> {
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || {
+            // All the writes hold the lock, so the headers of the scanned heights are there
+            let _guard = inner
+                .write_lock
+                .lock()
+                .map_err(|e| StoreError::BackingStoreError(e.to_string()))?;
+
+            let height_to_hash = inner.cf(HEIGHT_TO_HASH_CF)?;
+            let start_key = height_to_key(*heights.start());
+            let end_key = height_to_key(*heights.end());
+            let mode = IteratorMode::From(&start_key, Direction::Forward);
+            let mut headers = Vec::new();
+
+            for entry in inner.db.iterator_cf(height_to_hash, mode) {
+                let (key, hash) = entry?;
+
+                if key[..] > end_key[..] {
+                    break;
+                }
+
+                let hash = hash
+                    .as_ref()
+                    .try_into()
+                    .map(Hash::Sha256)
+                    .map_err(|_| StoreError::StoredDataError("Invalid hash".to_string()))?;
+                let header = inner
+                    .read_header(&hash)
+                    .map_err(|_| StoreError::LostHash(hash))?;
+
+                headers.push(header);
+            }
+
+            Ok(headers)
+        })
+        .await?
+    }
+
+    async fn remove_tail(&self) -> Result<()> {
+        let inner = self.inner.clone();
+
+        let (hash, height) = spawn_blocking(move || {
+            let _guard = inner
+                .write_lock
+                .lock()
+                .map_err(|e| StoreError::BackingStoreError(e.to_string()))?;
+
+            let head_height = inner.read_head_height()?;
+            let tail_height = inner.read_tail_height()?;
+
+            if tail_height >= head_height {
+                return Err(StoreError::RemovingHead);
+            }
+
+            let hash = inner
+                .read_hash(tail_height)
+                .map_err(|_| StoreError::LostHeight(tail_height))?;
+            let header = inner
+                .read_header(&hash)
+                .map_err(|_| StoreError::LostHash(hash))?;
+
+            let meta = inner.cf(META_CF)?;
+            let headers = inner.cf(HEADERS_CF)?;
+            let height_to_hash = inner.cf(HEIGHT_TO_HASH_CF)?;
+            let sampling_metadata = inner.cf(SAMPLING_METADATA_CF)?;
+            let namespace_index = inner.cf(NAMESPACE_INDEX_CF)?;
+
+            let tail_key = height_to_key(tail_height);
+
+            let mut sampled = inner.read_sampled_ranges()?;
+            sampled.remove_below(tail_height + 1);
+
+            let mut batch = WriteBatch::default();
+            batch.delete_cf(height_to_hash, tail_key);
+            batch.delete_cf(headers, hash.as_bytes());
+            batch.delete_cf(sampling_metadata, tail_key);
+            for key in NamespaceRanges::new(&header.dah).index_keys(tail_height) {
+                batch.delete_cf(namespace_index, key);
+            }
+            batch.put_cf(meta, SAMPLED_RANGES_KEY, sampled.encode());
+            batch.put_cf(meta, TAIL_HEIGHT_KEY, height_to_key(tail_height + 1));
+
+            inner.db.write(batch)?;
+
+            Ok((hash, tail_height))
+        })
+        .await??;
+
+        debug!("Removed header {hash} with height {height}");
+        Ok(())
+    }
+
+    async fn remove_blocks_below(&self, height: u64) -> Result<u64> {
+        let inner = self.inner.clone();
+
+        let removed = spawn_blocking(move || -> Result<u64> {
+            let blocks = inner.cf(BLOCKS_CF)?;
+            let block_heights = inner.cf(BLOCK_HEIGHTS_CF)?;
+            let end_key = height_to_key(height);
+
+            let mut batch = WriteBatch::default();
+            let mut removed = 0;
+
+            for entry in inner.db.iterator_cf(block_heights, IteratorMode::Start) {
+                let (index_key, _) = entry?;
+
+                if index_key[..] >= end_key[..] {
+                    break;
+                }
+
+                batch.delete_cf(blocks, &index_key[end_key.len()..]);
+                batch.delete_cf(block_heights, &index_key);
+                removed += 1;
+            }
+
+            inner.db.write(batch)?;
+
+            Ok(removed)
+        })
+        .await??;
+
+        debug!("Removed {removed} blocks below height {height}");
+        Ok(removed)
+    }
+
+    async fn update_sampling_metadata(
+        &self,
+        height: u64,
+        accepted: bool,
+        cids: Vec<Cid>,
+    ) -> Result<()> {
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || {
+            // Prevents concurrent updates and removal of the header in the meantime
+            let _guard = inner
+                .write_lock
+                .lock()
+                .map_err(|e| StoreError::BackingStoreError(e.to_string()))?;
+
+            inner.read_hash(height)?;
+
+            let prev = inner.read_sampling_metadata(height)?;
+            let mut batch = WriteBatch::default();
+
+            if prev.is_none() {
+                let mut sampled = inner.read_sampled_ranges()?;
+                sampled.insert(height);
+                batch.put_cf(inner.cf(META_CF)?, SAMPLED_RANGES_KEY, sampled.encode());
+            }
+
+            let metadata = SamplingMetadata::updated(prev, accepted, cids);
+            batch.put_cf(
+                inner.cf(SAMPLING_METADATA_CF)?,
+                height_to_key(height),
+                metadata.encode(),
+            );
+            inner.db.write(batch)?;
+
+            Ok::<_, StoreError>(())
+        })
+        .await??;
+
+        debug!("Updated sampling metadata for height {height}");
+        Ok(())
+    }
+
+    async fn get_sampling_metadata(&self, height: u64) -> Result<Option<SamplingMetadata>> {
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || {
+            inner.read_hash(height)?;
+            inner.read_sampling_metadata(height)
+        })
+        .await?
+    }
+
+    async fn unsampled_ranges(&self) -> Result<Vec<RangeInclusive<u64>>> {
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || {
+            // All the writes hold the lock, so the ranges match the stored heights
+            let _guard = inner
+                .write_lock
+                .lock()
+                .map_err(|e| StoreError::BackingStoreError(e.to_string()))?;
+
+            let head_height = match inner.read_head_height() {
+                Ok(height) => height,
+                Err(StoreError::NotFound) => return Ok(Vec::new()),
+                Err(e) => return Err(e),
+            };
+            let tail_height = inner.read_tail_height()?;
+
+            Ok(inner
+                .read_sampled_ranges()?
+                .missing(tail_height..=head_height))
+        })
+        .await?
+    }
+
+    async fn sampled_heights(&self, range: impl RangeBounds<u64>) -> Result<Vec<u64>> {
+        let range = (range.start_bound().cloned(), range.end_bound().cloned());
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || {
+            // All the writes hold the lock, so the ranges match the stored heights
+            let _guard = inner
+                .write_lock
+                .lock()
+                .map_err(|e| StoreError::BackingStoreError(e.to_string()))?;
+
+            let head_height = match inner.read_head_height() {
+                Ok(height) => height,
+                Err(StoreError::NotFound) => return Ok(Vec::new()),
+                Err(e) => return Err(e),
+            };
+            let tail_height = inner.read_tail_height()?;
+            let heights = clamp_to_stored(range, tail_height, head_height);
+
+            if heights.is_empty() {
+                return Ok(Vec::new());
+            }
+
+            Ok(inner.read_sampled_ranges()?.heights(heights))
+        })
+        .await?
+    }
+
+    async fn heights_with_namespace(
+        &self,
+        namespace: Namespace,
+        range: impl RangeBounds<u64>,
+    ) -> Result<Vec<u64>> {
+        let range = (range.start_bound().cloned(), range.end_bound().cloned());
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || {
+            // All the writes hold the lock, so the index matches the stored heights
+            let _guard = inner
+                .write_lock
+                .lock()
+                .map_err(|e| StoreError::BackingStoreError(e.to_string()))?;
+
+            let head_height = match inner.read_head_height() {
+                Ok(height) => height,
+                Err(StoreError::NotFound) => return Ok(Vec::new()),
+                Err(e) => return Err(e),
+            };
+            let tail_height = inner.read_tail_height()?;
+            let heights = clamp_to_stored(range, tail_height, head_height);
+
+            if heights.is_empty() {
+                return Ok(Vec::new());
+            }
+
+            let namespace_index = inner.cf(NAMESPACE_INDEX_CF)?;
+            let mut found = BTreeSet::new();
+
+            for scan in namespace_index_scans(namespace, heights) {
+                let mode = IteratorMode::From(&scan.start()[..], Direction::Forward);
+
+                for entry in inner.db.iterator_cf(namespace_index, mode) {
+                    let (key, _) = entry?;
+
+                    if key[..] > scan.end()[..] {
+                        break;
+                    }
+
+                    found.insert(namespace_index_height(&key)?);
+                }
+            }
+
+            Ok(found.into_iter().collect())
+        })
+        .await?
+    }
+
+    async fn get_peer_addrs(&self) -> Result<Vec<PeerAddr>> {
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || {
+            let meta = inner.cf(META_CF)?;
+
+            inner
+                .db
+                .get_pinned_cf(meta, PEER_ADDRS_KEY)?
+                .map(|bytes| PeerAddr::decode_all(&bytes))
+                .transpose()
+                .map(Option::unwrap_or_default)
+        })
+        .await?
+    }
+
+    async fn set_peer_addrs(&self, addrs: Vec<PeerAddr>) -> Result<()> {
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || {
+            let meta = inner.cf(META_CF)?;
+            inner
+                .db
+                .put_cf(meta, PEER_ADDRS_KEY, PeerAddr::encode_all(&addrs))?;
+
+            Ok(())
+        })
+        .await?
+    }
+
+    async fn get_network_head(&self) -> Result<Option<ExtendedHeader>> {
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || {
+            let meta = inner.cf(META_CF)?;
+
+            inner
+                .db
+                .get_pinned_cf(meta, NETWORK_HEAD_KEY)?
+                .map(|serialized| {
+                    ExtendedHeader::decode(serialized.as_ref())
+                        .map_err(|e| StoreError::CelestiaTypes(e.into()))
+                })
+                .transpose()
+        })
+        .await?
+    }
+
+    async fn set_network_head(&self, header: ExtendedHeader) -> Result<()> {
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || {
+            let meta = inner.cf(META_CF)?;
+            let serialized: std::result::Result<_, Infallible> = header.encode_vec();
+            inner
+                .db
+                .put_cf(meta, NETWORK_HEAD_KEY, serialized.unwrap())?;
+
+            Ok(())
+        })
+        .await?
+    }
+
+    /// Flush the store's state to the filesystem.
+    pub async fn flush_to_storage(&self) -> Result<()> {
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || -> Result<()> {
+            for name in COLUMN_FAMILIES {
+                inner.db.flush_cf(inner.cf(name)?)?;
+            }
+
+            Ok(())
+        })
+        .await?
+    }
+}
+
+impl Inner {
+    fn cf(&self, name: &str) -> Result<&ColumnFamily> {
+        self.db
+            .cf_handle(name)
+            .ok_or_else(|| StoreError::StoredDataError(format!("Missing column family: {name}")))
+    }
+
+    fn read_head_height(&self) -> Result<u64> {
+        let meta = self.cf(META_CF)?;
+
+        match self
+            .db
+            .get_pinned_cf(meta, HEAD_HEIGHT_KEY)?
+            .ok_or(StoreError::NotFound)?
+            .as_ref()
+            .try_into()
+        {
+            Ok(b) => Ok(u64::from_be_bytes(b)),
+            Err(_) => Err(StoreError::NotFound),
+        }
+    }
+
+    fn read_tail_height(&self) -> Result<u64> {
+        // Ensure the store is not empty
+        self.read_head_height()?;
+
+        let meta = self.cf(META_CF)?;
+
+        match self.db.get_pinned_cf(meta, TAIL_HEIGHT_KEY)? {
+            Some(bytes) => match bytes.as_ref().try_into() {
+                Ok(b) => Ok(u64::from_be_bytes(b)),
+                Err(_) => Err(StoreError::NotFound),
+            },
+            // Nothing was removed yet, tail is the genesis
+            None => Ok(1),
+        }
+    }
+
+    fn read_hash(&self, height: u64) -> Result<Hash> {
+        let height_to_hash = self.cf(HEIGHT_TO_HASH_CF)?;
+
+        match self
+            .db
+            .get_pinned_cf(height_to_hash, height_to_key(height))?
+            .ok_or(StoreError::NotFound)?
+            .as_ref()
+            .try_into()
+        {
+            Ok(b) => Ok(Hash::Sha256(b)),
+            Err(_) => Err(StoreError::NotFound),
+        }
+    }
+
+    fn read_sampling_metadata(&self, height: u64) -> Result<Option<SamplingMetadata>> {
+        let sampling_metadata = self.cf(SAMPLING_METADATA_CF)?;
+
+        self.db
+            .get_pinned_cf(sampling_metadata, height_to_key(height))?
+            .map(|bytes| SamplingMetadata::decode(&bytes))
+            .transpose()
+    }
+
+    fn read_sampled_ranges(&self) -> Result<SampledRanges> {
+        let meta = self.cf(META_CF)?;
+
+        self.db
+            .get_pinned_cf(meta, SAMPLED_RANGES_KEY)?
+            .map(|bytes| SampledRanges::decode(&bytes))
+            .transpose()
+            .map(Option::unwrap_or_default)
+    }
+
+    fn read_header(&self, hash: &Hash) -> Result<ExtendedHeader> {
+        let headers = self.cf(HEADERS_CF)?;
+        let serialized = self
+            .db
+            .get_pinned_cf(headers, hash.as_bytes())?
+            .ok_or(StoreError::NotFound)?;
+
+        ExtendedHeader::decode(serialized.as_ref()).map_err(|e| StoreError::CelestiaTypes(e.into()))
+    }
+}
+
+/// Build the [`SampledRanges`] from the stored sampling metadata.
+fn index_sampled_ranges(inner: &Inner) -> Result<()> {
+    let sampling_metadata = inner.cf(SAMPLING_METADATA_CF)?;
+    let mut heights = Vec::new();
+
+    for entry in inner.db.iterator_cf(sampling_metadata, IteratorMode::Start) {
+        let (height_key, _) = entry?;
+        let height = height_key
+            .as_ref()
+            .try_into()
+            .map(u64::from_be_bytes)
+            .map_err(|_| StoreError::StoredDataError("Invalid height key".to_string()))?;
+        heights.push(height);
+    }
+
+    let sampled = SampledRanges::from_heights(heights);
+    inner
+        .db
+        .put_cf(inner.cf(META_CF)?, SAMPLED_RANGES_KEY, sampled.encode())?;
+
+    Ok(())
+}
+
+/// Index the stored headers by the namespace.
+fn index_namespaces(inner: &Inner) -> Result<()> {
+    let height_to_hash = inner.cf(HEIGHT_TO_HASH_CF)?;
+    let namespace_index = inner.cf(NAMESPACE_INDEX_CF)?;
+    let mut batch = WriteBatch::default();
+
+    for entry in inner.db.iterator_cf(height_to_hash, IteratorMode::Start) {
+        let (_, hash) = entry?;
+        let hash = hash
+            .as_ref()
+            .try_into()
+            .map(Hash::Sha256)
+            .map_err(|_| StoreError::StoredDataError("Invalid hash".to_string()))?;
+        let header = inner.read_header(&hash)?;
+
+        for key in NamespaceRanges::new(&header.dah).index_keys(header.height().value()) {
+            batch.put_cf(namespace_index, key, []);
+        }
+    }
+
+    batch.put_cf(inner.cf(META_CF)?, NAMESPACE_INDEXED_KEY, []);
+    inner.db.write(batch)?;
+
+    Ok(())
+}
+
+/// Index the stored blocks of the shwap containers by the height.
+fn index_block_heights(inner: &Inner) -> Result<()> {
+    let blocks = inner.cf(BLOCKS_CF)?;
+    let block_heights = inner.cf(BLOCK_HEIGHTS_CF)?;
+    let mut batch = WriteBatch::default();
+
+    for entry in inner.db.iterator_cf(blocks, IteratorMode::Start) {
+        let (key, _) = entry?;
+        let height = CidGeneric::<MAX_MH_SIZE>::try_from(key.as_ref())
+            .ok()
+            .and_then(|cid| shwap_block_height(&cid));
+
+        if let Some(height) = height {
+            batch.put_cf(block_heights, block_height_key(height, &key), []);
+        }
+    }
+
+    batch.put_cf(inner.cf(META_CF)?, BLOCK_HEIGHTS_INDEXED_KEY, []);
+    inner.db.write(batch)?;
+
+    Ok(())
+}
+
+// divide errors into recoverable and not avoiding directly relying on passing rocksdb types
+impl From<RocksDbError> for StoreError {
+    fn from(error: RocksDbError) -> StoreError {
+        match error.kind() {
+            ErrorKind::Corruption | ErrorKind::NotFound | ErrorKind::ColumnFamilyDropped => {
+                StoreError::StoredDataError(error.into_string())
+            }
+            _ => StoreError::BackingStoreError(error.into_string()),
+        }
+    }
+}
+
+#[async_trait]
+impl Store for RocksDbStore {
+    async fn get_head(&self) -> Result<ExtendedHeader> {
+        self.get_head().await
+    }
+
+    async fn get_by_hash(&self, hash: &Hash) -> Result<ExtendedHeader> {
+        self.get_by_hash(hash).await
+    }
+
+    async fn get_by_height(&self, height: u64) -> Result<ExtendedHeader> {
+        self.get_by_height(height).await
+    }
+
+    async fn head_height(&self) -> Result<u64> {
+        self.head_height().await
+    }
+
+    async fn tail_height(&self) -> Result<u64> {
+        self.tail_height().await
+    }
+
+    async fn has(&self, hash: &Hash) -> bool {
+        self.contains_hash(hash).await
+    }
+
+    async fn has_at(&self, height: u64) -> bool {
+        self.contains_height(height).await
+    }
+
+    async fn remove_tail(&self) -> Result<()> {
+        self.remove_tail().await
+    }
+
+    async fn remove_blocks_below(&self, height: u64) -> Result<u64> {
+        self.remove_blocks_below(height).await
+    }
+
+    async fn append_single_unchecked(&self, header: ExtendedHeader) -> Result<()> {
+        self.append_single_unchecked(header).await
+    }
+
+    async fn prepend_single_unchecked(&self, header: ExtendedHeader) -> Result<()> {
+        self.prepend_single_unchecked(header).await
+    }
+
+    async fn write_batch(&self, batch: StoreWriteBatch) -> Result<()> {
+        self.write_batch(batch).await
+    }
+
+    async fn snapshot<R>(&self, range: R) -> Result<StoreSnapshot>
+    where
+        R: RangeBounds<u64> + Send,
+    {
+        let range = (range.start_bound().cloned(), range.end_bound().cloned());
+        self.read_snapshot(range).await
+    }
+
+    fn headers_range<R>(&self, range: R) -> BoxStream<'_, Result<ExtendedHeader>>
+    where
+        R: RangeBounds<u64>,
+    {
+        let bounds = (range.start_bound().cloned(), range.end_bound().cloned());
+        headers_in_batches(self, bounds, move |heights| self.read_headers(heights))
+    }
+
+    async fn update_sampling_metadata(
+        &self,
+        height: u64,
+        accepted: bool,
+        cids: Vec<Cid>,
+    ) -> Result<()> {
+        self.update_sampling_metadata(height, accepted, cids).await
+    }
+
+    async fn get_sampling_metadata(&self, height: u64) -> Result<Option<SamplingMetadata>> {
+        self.get_sampling_metadata(height).await
+    }
+
+    async fn unsampled_ranges(&self) -> Result<Vec<RangeInclusive<u64>>> {
+        self.unsampled_ranges().await
+    }
+
+    async fn sampled_heights<R>(&self, range: R) -> Result<Vec<u64>>
+    where
+        R: RangeBounds<u64> + Send,
+    {
+        self.sampled_heights(range).await
+    }
+
+    async fn heights_with_namespace<R>(&self, namespace: Namespace, range: R) -> Result<Vec<u64>>
+    where
+        R: RangeBounds<u64> + Send,
+    {
+        self.heights_with_namespace(namespace, range).await
+    }
+
+    async fn get_peer_addrs(&self) -> Result<Vec<PeerAddr>> {
+        self.get_peer_addrs().await
+    }
+
+    async fn set_peer_addrs(&self, addrs: Vec<PeerAddr>) -> Result<()> {
+        self.set_peer_addrs(addrs).await
+    }
+
+    async fn get_network_head(&self) -> Result<Option<ExtendedHeader>> {
+        self.get_network_head().await
+    }
+
+    async fn set_network_head(&self, header: ExtendedHeader) -> Result<()> {
+        self.set_network_head(header).await
+    }
+}
+
+#[cfg_attr(not(docs_rs), async_trait)]
+impl Blockstore for RocksDbStore {
+    async fn get<const S: usize>(
+        &self,
+        cid: &CidGeneric<S>,
+    ) -> Result<Option<Vec<u8>>, BlockstoreError> {
+        let inner = self.inner.clone();
+        let key = cid.to_bytes();
+
+        spawn_blocking(move || -> Result<_> {
+            let blocks = inner.cf(BLOCKS_CF)?;
+            Ok(inner.db.get_cf(blocks, key)?)
+        })
+        .await
+        .map_err(StoreError::from)
+        .and_then(|res| res)
+        .map_err(|e| BlockstoreError::StorageError(e.to_string()))
+    }
+
+    async fn put_keyed<const S: usize>(
+        &self,
+        cid: &CidGeneric<S>,
+        data: &[u8],
+    ) -> Result<(), BlockstoreError> {
+        let inner = self.inner.clone();
+        let key = cid.to_bytes();
+        let index_key = shwap_block_height(cid).map(|height| block_height_key(height, &key));
+        let data = data.to_vec();
+
+        spawn_blocking(move || -> Result<Result<(), BlockstoreError>> {
+            let _guard = inner
+                .write_lock
+                .lock()
+                .map_err(|e| StoreError::BackingStoreError(e.to_string()))?;
+
+            let blocks = inner.cf(BLOCKS_CF)?;
+
+            if inner.db.get_pinned_cf(blocks, &key)?.is_some() {
+                return Ok(Err(BlockstoreError::CidExists));
+            }
+
+            let mut batch = WriteBatch::default();
+            batch.put_cf(blocks, key, data);
+            if let Some(index_key) = index_key {
+                batch.put_cf(inner.cf(BLOCK_HEIGHTS_CF)?, index_key, []);
+            }
+            inner.db.write(batch)?;
+
+            Ok(Ok(()))
+        })
+        .await
+        .map_err(StoreError::from)
+        .and_then(|res| res)
+        .map_err(|e| BlockstoreError::StorageError(e.to_string()))?
+    }
+
+    async fn remove<const S: usize>(&self, cid: &CidGeneric<S>) -> Result<(), BlockstoreError> {
+        let inner = self.inner.clone();
+        let key = cid.to_bytes();
+        let index_key = shwap_block_height(cid).map(|height| block_height_key(height, &key));
+
+        spawn_blocking(move || -> Result<()> {
+            let mut batch = WriteBatch::default();
+            batch.delete_cf(inner.cf(BLOCKS_CF)?, key);
+            if let Some(index_key) = index_key {
+                batch.delete_cf(inner.cf(BLOCK_HEIGHTS_CF)?, index_key);
+            }
+            inner.db.write(batch)?;
+
+            Ok(())
+        })
+        .await
+        .map_err(StoreError::from)
+        .and_then(|res| res)
+        .map_err(|e| BlockstoreError::StorageError(e.to_string()))
+    }
+}
+
+#[inline]
+fn height_to_key(height: u64) -> [u8; 8] {
+    // BigEndian representation preserves expected int order when sorted lexicographically
+    height.to_be_bytes()
+}
+
+fn block_height_key(height: u64, cid: &[u8]) -> Vec<u8> {
+    let mut key = height_to_key(height).to_vec();
+    key.extend_from_slice(cid);
+    key
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::store::HEADERS_RANGE_BATCH_SIZE;
+    use celestia_types::row::{IdLayout, RowId};
+    use celestia_types::test_utils::ExtendedHeaderGenerator;
+    use celestia_types::Height;
+    use futures::TryStreamExt;
+    use multihash::Multihash;
+
+    #[tokio::test]
+    async fn test_empty_store() {
+        let s = RocksDbStore::new_temp().await.unwrap();
+        assert!(matches!(s.head_height().await, Err(StoreError::NotFound)));
+        assert!(matches!(s.get_head().await, Err(StoreError::NotFound)));
+        assert!(matches!(
+            s.get_by_height(1).await,
+            Err(StoreError::NotFound)
+        ));
+        assert!(matches!(
+            s.get_by_hash(&Hash::Sha256([0; 32])).await,
+            Err(StoreError::NotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_read_write() {
+        let s = RocksDbStore::new_temp().await.unwrap();
+        let mut gen = ExtendedHeaderGenerator::new();
+
+        let header = gen.next();
+
+        s.append_single_unchecked(header.clone()).await.unwrap();
+        assert_eq!(s.head_height().await.unwrap(), 1);
+        assert_eq!(s.get_head().await.unwrap(), header);
+        assert_eq!(s.get_by_height(1).await.unwrap(), header);
+        assert_eq!(s.get_by_hash(&header.hash()).await.unwrap(), header);
+        assert!(s.contains_hash(&header.hash()).await);
+        assert!(s.contains_height(1).await);
+        assert!(!s.contains_height(2).await);
+    }
+
+    #[tokio::test]
+    async fn test_overwrite_height() {
+        let (s, gen) = gen_filled_store(100, None).await;
+
+        // Height 30 with different hash
+        let header29 = s.get_by_height(29).await.unwrap();
+        let header30 = gen.next_of(&header29);
+
+        let insert_existing_result = s.append_single_unchecked(header30).await;
+        assert!(matches!(
+            insert_existing_result,
+            Err(StoreError::HeightExists(30))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_overwrite_hash() {
+        let (s, _) = gen_filled_store(100, None).await;
+        let mut dup_header = s.get_by_height(33).await.unwrap();
+        dup_header.header.height = Height::from(101u32);
+        let insert_existing_result = s.append_single_unchecked(dup_header).await;
+        assert!(matches!(
+            insert_existing_result,
+            Err(StoreError::HashExists(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_non_continuous_append() {
+        let (s, mut gen) = gen_filled_store(10, None).await;
+        let mut hs = gen.next_many(6);
+
+        // remove height 14
+        hs.remove(3);
+
+        let insert_existing_result = s.append_unchecked(hs).await;
+        assert!(matches!(
+            insert_existing_result,
+            Err(StoreError::NonContinuousAppend(13, 15))
+        ));
+        // the range is appended atomically
+        assert_eq!(s.head_height().await.unwrap(), 10);
+    }
+
+    #[tokio::test]
+    async fn test_write_batch_and_snapshot() {
+        let s = RocksDbStore::new_temp().await.unwrap();
+        let mut gen = ExtendedHeaderGenerator::new_from_height(5);
+        let hs = gen.next_many(4);
+
+        let mut batch = StoreWriteBatch::new();
+        batch
+            .prepend(hs[1].clone())
+            .append(hs[2].clone())
+            .prepend(hs[0].clone());
+        s.write_batch(batch).await.unwrap();
+        assert_eq!(s.tail_height().await.unwrap(), 5);
+        assert_eq!(s.head_height().await.unwrap(), 7);
+
+        // failing write discards the preceding ones
+        let mut batch = StoreWriteBatch::new();
+        batch.append(hs[3].clone()).append(hs[3].clone());
+        assert!(matches!(
+            s.write_batch(batch).await,
+            Err(StoreError::HeightExists(8))
+        ));
+        assert_eq!(s.head_height().await.unwrap(), 7);
+
+        s.update_sampling_metadata(6, true, Vec::new())
+            .await
+            .unwrap();
+
+        let snapshot = s.snapshot(..).await.unwrap();
+        assert_eq!(snapshot.tail_height, 5);
+        assert_eq!(snapshot.head_height, 7);
+        assert_eq!(snapshot.headers.len(), 3);
+        assert_eq!(snapshot.headers[1].header, hs[1]);
+        assert!(snapshot.headers[1].sampling_metadata.is_some());
+        assert!(snapshot.headers[2].sampling_metadata.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_remove_tail() {
+        let (s, _) = gen_filled_store(3, None).await;
+        assert_eq!(s.tail_height().await.unwrap(), 1);
+
+        let header1 = s.get_by_height(1).await.unwrap();
+
+        s.remove_tail().await.unwrap();
+        assert_eq!(s.tail_height().await.unwrap(), 2);
+        assert!(!s.contains_height(1).await);
+        assert!(!s.contains_hash(&header1.hash()).await);
+
+        s.remove_tail().await.unwrap();
+        assert!(matches!(
+            s.remove_tail().await,
+            Err(StoreError::RemovingHead)
+        ));
+        assert_eq!(s.head_height().await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_sampling_metadata() {
+        let db_dir = TempDir::new("celestia.test").unwrap();
+        let (s, _) = gen_filled_store(3, Some(db_dir.path())).await;
+        let cid = |n| Cid::new_v1(0x55, Multihash::wrap(0x12, &[n; 32]).unwrap());
+
+        s.update_sampling_metadata(1, true, vec![cid(1)])
+            .await
+            .unwrap();
+        s.update_sampling_metadata(3, false, vec![cid(3)])
+            .await
+            .unwrap();
+        s.update_sampling_metadata(3, true, vec![cid(3), cid(4)])
+            .await
+            .unwrap();
+        assert!(matches!(
+            s.update_sampling_metadata(4, true, Vec::new()).await,
+            Err(StoreError::NotFound)
+        ));
+        drop(s);
+
+        let s = RocksDbStore::new_in_path(db_dir.path()).await.unwrap();
+        assert_eq!(s.get_sampling_metadata(2).await.unwrap(), None);
+        assert_eq!(
+            s.get_sampling_metadata(3).await.unwrap(),
+            Some(SamplingMetadata {
+                accepted: true,
+                cids_sampled: vec![cid(3), cid(4)],
+            })
+        );
+        assert_eq!(Store::next_unsampled_height(&s).await.unwrap(), Some(2));
+        assert_eq!(s.unsampled_ranges().await.unwrap(), vec![2..=2]);
+
+        // stores without the sampled ranges rebuild them on open
+        s.inner
+            .db
+            .delete_cf(s.inner.cf(META_CF).unwrap(), SAMPLED_RANGES_KEY)
+            .unwrap();
+        drop(s);
+        let s = RocksDbStore::new_in_path(db_dir.path()).await.unwrap();
+        assert_eq!(s.unsampled_ranges().await.unwrap(), vec![2..=2]);
+        assert_eq!(s.sampled_heights(..).await.unwrap(), vec![1, 3]);
+        assert_eq!(s.sampled_heights(2..).await.unwrap(), vec![3]);
+
+        s.remove_tail().await.unwrap();
+        assert!(matches!(
+            s.get_sampling_metadata(1).await,
+            Err(StoreError::NotFound)
+        ));
+        assert!(s.inner.read_sampling_metadata(1).unwrap().is_none());
+        assert_eq!(s.sampled_heights(..).await.unwrap(), vec![3]);
+    }
+
+    #[tokio::test]
+    async fn test_headers_range() {
+        let (s, _) = gen_filled_store(HEADERS_RANGE_BATCH_SIZE + 10, None).await;
+
+        let all: Vec<_> = s.headers_range(..).try_collect().await.unwrap();
+        assert_eq!(all, s.get_range(..).await.unwrap());
+
+        s.remove_tail().await.unwrap();
+        let heights: Vec<_> = s
+            .headers_range(..=HEADERS_RANGE_BATCH_SIZE + 2)
+            .map_ok(|header| header.height().value())
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(
+            heights,
+            (2..=HEADERS_RANGE_BATCH_SIZE + 2).collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_heights_with_namespace() {
+        let db_dir = TempDir::new("celestia.test").unwrap();
+        let ns1 = Namespace::new_v0(&[1]).unwrap();
+        let ns2 = Namespace::new_v0(&[2]).unwrap();
+        let mut gen = ExtendedHeaderGenerator::new();
+        let headers: Vec<_> = [ns1, ns2, ns1, ns1]
+            .iter()
+            .map(|namespace| gen.next_with_eds(2, &[*namespace]).0)
+            .collect();
+
+        let s = RocksDbStore::new_in_path(db_dir.path()).await.unwrap();
+        assert!(s.heights_with_namespace(ns1, ..).await.unwrap().is_empty());
+        s.append_unchecked(headers).await.unwrap();
+        s.remove_tail().await.unwrap();
+
+        assert_eq!(s.heights_with_namespace(ns1, ..).await.unwrap(), vec![3, 4]);
+        assert_eq!(s.heights_with_namespace(ns2, 2..=3).await.unwrap(), vec![2]);
+
+        // stores without the index rebuild it on open
+        let namespace_index = s.inner.cf(NAMESPACE_INDEX_CF).unwrap();
+        let keys: Vec<_> = s
+            .inner
+            .db
+            .iterator_cf(namespace_index, IteratorMode::Start)
+            .map(|entry| entry.unwrap().0)
+            .collect();
+        for key in keys {
+            s.inner.db.delete_cf(namespace_index, key).unwrap();
+        }
+        s.inner
+            .db
+            .delete_cf(s.inner.cf(META_CF).unwrap(), NAMESPACE_INDEXED_KEY)
+            .unwrap();
+        drop(s);
+
+        let s = RocksDbStore::new_in_path(db_dir.path()).await.unwrap();
+        assert_eq!(s.heights_with_namespace(ns1, ..4).await.unwrap(), vec![3]);
+        assert_eq!(s.heights_with_namespace(ns2, ..).await.unwrap(), vec![2]);
+    }
+
+    #[tokio::test]
+    async fn test_prepend() {
+        let db_dir = TempDir::new("celestia.test").unwrap();
+        let (s, mut gen) = gen_filled_store(0, Some(db_dir.path())).await;
+        let headers = gen.next_many(10);
+
+        // Empty store can be started from any height
+        s.prepend_single_unchecked(headers[9].clone())
+            .await
+            .unwrap();
+        assert_eq!(s.head_height().await.unwrap(), 10);
+        assert_eq!(s.tail_height().await.unwrap(), 10);
+
+        s.prepend_single_unchecked(headers[8].clone())
+            .await
+            .unwrap();
+        assert!(matches!(
+            s.prepend_single_unchecked(headers[8].clone()).await,
+            Err(StoreError::HeightExists(9))
+        ));
+        assert!(matches!(
+            s.prepend_single_unchecked(headers[6].clone()).await,
+            Err(StoreError::NonContinuousPrepend(9, 7))
+        ));
+
+        s.append_single_unchecked(gen.next()).await.unwrap();
+        drop(s);
+
+        let s = RocksDbStore::new_in_path(db_dir.path()).await.unwrap();
+        assert_eq!(s.tail_height().await.unwrap(), 9);
+        assert_eq!(s.head_height().await.unwrap(), 11);
+        assert_eq!(s.get_by_height(9).await.unwrap(), headers[8]);
+        assert!(!s.contains_height(8).await);
+    }
+
+    #[tokio::test]
+    async fn test_peer_addrs_persistence() {
+        let db_dir = TempDir::new("celestia.test").unwrap();
+        let s = RocksDbStore::new_in_path(db_dir.path()).await.unwrap();
+        assert!(s.get_peer_addrs().await.unwrap().is_empty());
+
+        let addrs = vec![PeerAddr {
+            addr: format!("/ip4/1.2.3.4/tcp/2121/p2p/{}", libp2p::PeerId::random())
+                .parse()
+                .unwrap(),
+            last_seen: celestia_tendermint::Time::from_unix_timestamp(1000, 0).unwrap(),
+        }];
+        s.set_peer_addrs(addrs.clone()).await.unwrap();
+        drop(s);
+
+        let s = RocksDbStore::new_in_path(db_dir.path()).await.unwrap();
+        assert_eq!(s.get_peer_addrs().await.unwrap(), addrs);
+    }
+
+    #[tokio::test]
+    async fn test_network_head_persistence() {
+        let db_dir = TempDir::new("celestia.test").unwrap();
+        let s = RocksDbStore::new_in_path(db_dir.path()).await.unwrap();
+        assert!(s.get_network_head().await.unwrap().is_none());
+
+        // network head doesn't need to be adjacent to the stored headers
+        let header = ExtendedHeaderGenerator::new_from_height(100).next();
+        s.set_network_head(header.clone()).await.unwrap();
+        drop(s);
+
+        let s = RocksDbStore::new_in_path(db_dir.path()).await.unwrap();
+        assert_eq!(s.get_network_head().await.unwrap(), Some(header));
+        assert!(s.get_head().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_store_persistence() {
+        let db_dir = TempDir::new("celestia.test").unwrap();
+        let (original_store, mut gen) = gen_filled_store(20, Some(db_dir.path())).await;
+        let original_head = original_store.get_head().await.unwrap();
+        drop(original_store);
+
+        let reopened_store = RocksDbStore::new_in_path(db_dir.path())
+            .await
+            .expect("failed to reopen store");
+
+        assert_eq!(reopened_store.get_head().await.unwrap(), original_head);
+
+        let new_headers = gen.next_many(10);
+        reopened_store.append_unchecked(new_headers).await.unwrap();
+        assert_eq!(reopened_store.head_height().await.unwrap(), 30);
+    }
+
+    #[tokio::test]
+    async fn test_blockstore() {
+        let s = RocksDbStore::new_temp().await.unwrap();
+        let cid = Cid::new_v1(0x55, Multihash::wrap(0x12, &[1; 32]).unwrap());
+
+        assert_eq!(s.get(&cid).await.unwrap(), None);
+
+        s.put_keyed(&cid, b"data").await.unwrap();
+        assert_eq!(s.get(&cid).await.unwrap().unwrap(), b"data");
+        assert!(Blockstore::has(&s, &cid).await.unwrap());
+
+        assert_eq!(
+            s.put_keyed(&cid, b"other").await.unwrap_err(),
+            BlockstoreError::CidExists
+        );
+
+        // blocks don't interfere with headers
+        assert!(matches!(s.head_height().await, Err(StoreError::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_remove_blocks_below() {
+        let s = RocksDbStore::new_temp().await.unwrap();
+        let row = |height| RowId::new(0, height).unwrap().to_cid(IdLayout::V1);
+        let other = Cid::new_v1(0x55, Multihash::wrap(0x12, &[1; 32]).unwrap());
+
+        for height in 1..=3 {
+            s.put_keyed(&row(height), b"row").await.unwrap();
+        }
+        s.put_keyed(&other, b"other").await.unwrap();
+
+        assert_eq!(s.remove_blocks_below(3).await.unwrap(), 2);
+        assert_eq!(s.get(&row(1)).await.unwrap(), None);
+        assert_eq!(s.get(&row(2)).await.unwrap(), None);
+        assert!(Blockstore::has(&s, &row(3)).await.unwrap());
+        assert!(Blockstore::has(&s, &other).await.unwrap());
+
+        assert_eq!(s.remove_blocks_below(3).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_remove_block() {
+        let s = RocksDbStore::new_temp().await.unwrap();
+        let row = |height| RowId::new(0, height).unwrap().to_cid(IdLayout::V1);
+
+        s.put_keyed(&row(1), b"row").await.unwrap();
+        s.put_keyed(&row(2), b"row").await.unwrap();
+        s.remove(&row(1)).await.unwrap();
+        assert!(!Blockstore::has(&s, &row(1)).await.unwrap());
+        s.remove(&row(1)).await.unwrap();
+
+        // the index entry is gone with the block
+        assert_eq!(s.remove_blocks_below(3).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_block_heights_indexed_on_open() {
+        let db_dir = TempDir::new("celestia.test").unwrap();
+        let row = |height| RowId::new(0, height).unwrap().to_cid(IdLayout::V1);
+
+        let s = RocksDbStore::new_in_path(db_dir.path()).await.unwrap();
+        for height in 1..=3 {
+            s.put_keyed(&row(height), b"row").await.unwrap();
+        }
+
+        // blocks stored before the index was kept
+        let block_heights = s.inner.cf(BLOCK_HEIGHTS_CF).unwrap();
+        let keys: Vec<_> = s
+            .inner
+            .db
+            .iterator_cf(block_heights, IteratorMode::Start)
+            .map(|entry| entry.unwrap().0)
+            .collect();
+        for key in keys {
+            s.inner.db.delete_cf(block_heights, key).unwrap();
+        }
+        s.inner
+            .db
+            .delete_cf(s.inner.cf(META_CF).unwrap(), BLOCK_HEIGHTS_INDEXED_KEY)
+            .unwrap();
+        assert_eq!(s.remove_blocks_below(3).await.unwrap(), 0);
+        drop(s);
+
+        let s = RocksDbStore::new_in_path(db_dir.path()).await.unwrap();
+        assert_eq!(s.remove_blocks_below(3).await.unwrap(), 2);
+        assert!(Blockstore::has(&s, &row(3)).await.unwrap());
+    }
+
+    pub async fn gen_filled_store(
+        amount: u64,
+        path: Option<&Path>,
+    ) -> (RocksDbStore, ExtendedHeaderGenerator) {
+        let s = if let Some(path) = path {
+            RocksDbStore::new_in_path(path).await.unwrap()
+        } else {
+            RocksDbStore::new_temp().await.unwrap()
+        };
+
+        let mut gen = ExtendedHeaderGenerator::new();
+
+        let headers = gen.next_many(amount);
+
+        for header in headers {
+            s.append_single_unchecked(header)
+                .await
+                .expect("inserting test data failed");
+        }
+
+        (s, gen)
+    }
+}
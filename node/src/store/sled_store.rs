@@ -1,26 +1,71 @@
-use std::convert::Infallible;
-use std::ops::Deref;
+use std::collections::BTreeSet;
+use std::mem::size_of;
+use std::ops::{Bound, Deref, RangeBounds, RangeInclusive};
 use std::path::Path;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use celestia_tendermint_proto::Protobuf;
+use blockstore::{Blockstore, BlockstoreError};
+use celestia_proto::celestia::da::DataAvailabilityHeader as RawDataAvailabilityHeader;
+use celestia_proto::header::pb::ExtendedHeader as RawExtendedHeader;
+use celestia_tendermint_proto::v0_34::types::ValidatorSet as RawValidatorSet;
 use celestia_types::hash::Hash;
+use celestia_types::nmt::Namespace;
 use celestia_types::ExtendedHeader;
+use cid::{Cid, CidGeneric};
 use directories::ProjectDirs;
-use sled::transaction::{ConflictableTransactionError, TransactionError};
+use prost::Message;
+use sled::transaction::{
+    ConflictableTransactionError, ConflictableTransactionResult, TransactionError,
+    TransactionalTree,
+};
 use sled::{Db, Error as SledError, Transactional, Tree};
 use tempdir::TempDir;
 use tokio::task::spawn_blocking;
 use tokio::task::JoinError;
 use tracing::debug;
 
+use crate::bitswap::MAX_MH_SIZE;
 use crate::store::Store;
-use crate::store::{Result, StoreError};
+use crate::store::{
+    clamp_to_stored, namespace_index_height, namespace_index_scans, network_db_path,
+    shwap_block_height, NamespaceIndexKey, NamespaceRanges, PeerAddr, Result, SampledRanges,
+    SamplingMetadata, SnapshotHeader, StoreError, StoreSnapshot, WriteBatch, WriteOp,
+};
 
 const HEAD_HEIGHT_KEY: &[u8] = b"KEY.HEAD_HEIGHT";
-const HASH_TREE_ID: &[u8] = b"HASH";
+const TAIL_HEIGHT_KEY: &[u8] = b"KEY.TAIL_HEIGHT";
+const HEADERS_TREE_ID: &[u8] = b"HEADERS";
+/// Tree of the whole headers, used by the schema version 1.
+const LEGACY_HEADERS_TREE_ID: &[u8] = b"HASH";
 const HEIGHT_TO_HASH_TREE_ID: &[u8] = b"HEIGHT";
+const SAMPLING_METADATA_TREE_ID: &[u8] = b"SAMPLING_METADATA";
+const BLOCKS_TREE_ID: &[u8] = b"BLOCKS";
+/// Tree indexing the blocks of the shwap containers by the height, keyed by the height
+/// followed by the CID.
+const BLOCK_HEIGHTS_TREE_ID: &[u8] = b"BLOCK_HEIGHTS";
+const VALIDATOR_SETS_TREE_ID: &[u8] = b"VALIDATOR_SETS";
+const DAHS_TREE_ID: &[u8] = b"DAHS";
+/// Tree of the [`NamespaceRanges`] of the row roots of the headers, keyed by the height.
+const NAMESPACE_RANGES_TREE_ID: &[u8] = b"NAMESPACE_RANGES";
+/// Tree indexing the heights by the namespace, keyed by the [`NamespaceRanges::index_keys`].
+const NAMESPACE_INDEX_TREE_ID: &[u8] = b"NAMESPACE_INDEX";
+const SCHEMA_VERSION_KEY: &[u8] = b"KEY.SCHEMA_VERSION";
+const PEER_ADDRS_KEY: &[u8] = b"KEY.PEER_ADDRS";
+const NETWORK_HEAD_KEY: &[u8] = b"KEY.NETWORK_HEAD";
+/// Key of the [`SampledRanges`], updated together with the sampling metadata.
+const SAMPLED_RANGES_KEY: &[u8] = b"KEY.SAMPLED_RANGES";
+
+/// Version of the database schema.
+///
+/// Version 1 stored the whole headers. Since version 2 the validator sets and the data
+/// availability headers are stored in their own trees, shared by all the headers with
+/// the same hash of them. Version 3 indexes the namespace ranges of the stored headers.
+/// Version 4 keeps the ranges of the sampled heights. Version 5 indexes the heights by
+/// the namespace. Version 6 indexes the blocks of the shwap containers by the height.
+const SCHEMA_VERSION: u64 = 6;
+/// Size of the reference counter prefixing the shared parts of the headers.
+const REFS_SIZE: usize = 8;
 
 /// A [`Store`] implementation based on a [`sled`] database.
 #[derive(Debug)]
@@ -33,6 +78,56 @@ struct Inner {
     db: Db,
     headers: Tree,
     height_to_hash: Tree,
+    sampling_metadata: Tree,
+    blocks: Tree,
+    block_heights: Tree,
+    validator_sets: Tree,
+    dahs: Tree,
+    namespace_ranges: Tree,
+    namespace_index: Tree,
+}
+
+/// [`ExtendedHeader`] split into the parts stored in the separate trees.
+///
+/// The stored header record holds the keys of its validator set and data availability
+/// header, followed by the header and commit encoded as [`RawExtendedHeader`].
+struct CompactHeader {
+    record: Vec<u8>,
+    validators_key: Hash,
+    validator_set: Vec<u8>,
+    dah_key: Hash,
+    dah: Vec<u8>,
+}
+
+/// Header of the [`WriteBatch`] prepared for the insertion.
+struct PreparedWrite {
+    prepend: bool,
+    hash: Hash,
+    height: u64,
+    namespace_ranges: Vec<u8>,
+    namespace_index: Vec<NamespaceIndexKey>,
+    compact: CompactHeader,
+}
+
+impl PreparedWrite {
+    fn new(op: WriteOp) -> Self {
+        let (prepend, header) = match op {
+            WriteOp::Append(header) => (false, header),
+            WriteOp::Prepend(header) => (true, header),
+        };
+
+        let height = header.height().value();
+        let namespace_ranges = NamespaceRanges::new(&header.dah);
+
+        PreparedWrite {
+            prepend,
+            hash: header.hash(),
+            height,
+            namespace_ranges: namespace_ranges.encode(),
+            namespace_index: namespace_ranges.index_keys(height),
+            compact: CompactHeader::new(header),
+        }
+    }
 }
 
 impl SledStore {
@@ -44,12 +139,10 @@ impl SledStore {
                     "Unable to get system cache path to open header store".to_string(),
                 ));
             };
-            let mut db_path = project_dirs.cache_dir().to_owned();
-            db_path.push(network_id);
+            let db_path = network_db_path(project_dirs.cache_dir(), &network_id)?;
 
-            sled::open(db_path)
-                .and_then(Self::init)
-                .map_err(|e| StoreError::OpenFailed(e.to_string()))
+            let db = sled::open(db_path).map_err(|e| StoreError::OpenFailed(e.to_string()))?;
+            Self::init(db)
         })
         .await?
     }
@@ -59,15 +152,15 @@ impl SledStore {
         spawn_blocking(move || {
             let tmp_path = TempDir::new("celestia")?.into_path();
 
-            sled::Config::default()
+            let db = sled::Config::default()
                 .path(tmp_path)
                 .temporary(true)
                 .create_new(true) // make sure we fail if db is already there
                 .open()
-                .and_then(Self::init)
+                .map_err(|e| StoreError::OpenFailed(e.to_string()))?;
+            Self::init(db)
         })
         .await?
-        .map_err(|e| StoreError::OpenFailed(e.to_string()))
     }
 
     /// Create a persistent store in a given path.
@@ -77,24 +170,54 @@ impl SledStore {
     {
         let path = path.as_ref().to_owned();
         spawn_blocking(move || {
-            let db = sled::open(path)?;
+            let db = sled::open(path).map_err(|e| StoreError::OpenFailed(e.to_string()))?;
             Self::init(db)
         })
         .await?
-        .map_err(|e| StoreError::OpenFailed(e.to_string()))
     }
 
     // `open_tree` might be blocking, make sure to call this from `spawn_blocking` or similar
-    fn init(db: Db) -> sled::Result<Self> {
-        let headers = db.open_tree(HASH_TREE_ID)?;
-        let height_to_hash = db.open_tree(HEIGHT_TO_HASH_TREE_ID)?;
+    fn init(db: Db) -> Result<Self> {
+        let open_tree = |id: &[u8]| {
+            db.open_tree(id)
+                .map_err(|e| StoreError::OpenFailed(e.to_string()))
+        };
+
+        let inner = Inner {
+            headers: open_tree(HEADERS_TREE_ID)?,
+            height_to_hash: open_tree(HEIGHT_TO_HASH_TREE_ID)?,
+            sampling_metadata: open_tree(SAMPLING_METADATA_TREE_ID)?,
+            blocks: open_tree(BLOCKS_TREE_ID)?,
+            block_heights: open_tree(BLOCK_HEIGHTS_TREE_ID)?,
+            validator_sets: open_tree(VALIDATOR_SETS_TREE_ID)?,
+            dahs: open_tree(DAHS_TREE_ID)?,
+            namespace_ranges: open_tree(NAMESPACE_RANGES_TREE_ID)?,
+            namespace_index: open_tree(NAMESPACE_INDEX_TREE_ID)?,
+            db,
+        };
+
+        match read_height_by_db_key(&inner.db, SCHEMA_VERSION_KEY) {
+            Ok(SCHEMA_VERSION) => (),
+            Ok(version) if version > SCHEMA_VERSION => {
+                return Err(StoreError::OpenFailed(format!(
+                    "Unsupported schema version {version}"
+                )));
+            }
+            // stores created before the schema was versioned are of version 1
+            Ok(_) | Err(StoreError::NotFound) => {
+                migrate_to_compact_headers(&inner)?;
+                index_namespace_ranges(&inner)?;
+                index_sampled_ranges(&inner)?;
+                index_block_heights(&inner)?;
+                inner
+                    .db
+                    .insert(SCHEMA_VERSION_KEY, &SCHEMA_VERSION.to_be_bytes())?;
+            }
+            Err(e) => return Err(e),
+        }
 
         Ok(Self {
-            inner: Arc::new(Inner {
-                db,
-                headers,
-                height_to_hash,
-            }),
+            inner: Arc::new(inner),
         })
     }
 
@@ -104,11 +227,17 @@ impl SledStore {
         spawn_blocking(move || read_height_by_db_key(&inner.db, HEAD_HEIGHT_KEY)).await?
     }
 
+    async fn tail_height(&self) -> Result<u64> {
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || read_tail_height(&inner.db)).await?
+    }
+
     async fn get_by_hash(&self, hash: &Hash) -> Result<ExtendedHeader> {
         let inner = self.inner.clone();
         let hash = *hash;
 
-        spawn_blocking(move || read_header_by_db_key(&inner.headers, hash.as_bytes())).await?
+        spawn_blocking(move || read_header_by_db_key(&inner, hash.as_bytes())).await?
     }
 
     async fn get_by_height(&self, height: u64) -> Result<ExtendedHeader> {
@@ -116,7 +245,7 @@ impl SledStore {
 
         spawn_blocking(move || {
             let hash = read_hash_by_db_key(&inner.height_to_hash, &height_to_key(height))?;
-            read_header_by_db_key(&inner.headers, hash.as_bytes())
+            read_header_by_db_key(&inner, hash.as_bytes())
         })
         .await?
     }
@@ -127,7 +256,7 @@ impl SledStore {
         spawn_blocking(move || {
             let head_height = read_height_by_db_key(&inner.db, HEAD_HEIGHT_KEY)?;
             let hash = read_hash_by_db_key(&inner.height_to_hash, &height_to_key(head_height))?;
-            read_header_by_db_key(&inner.headers, hash.as_bytes())
+            read_header_by_db_key(&inner, hash.as_bytes())
         })
         .await?
     }
@@ -155,61 +284,542 @@ impl SledStore {
     }
 
     async fn append_single_unchecked(&self, header: ExtendedHeader) -> Result<()> {
-        let hash = header.hash();
-        let height = header.height().value();
-        let inner = self.inner.clone();
+        let mut batch = WriteBatch::new();
+        batch.append(header);
+        self.write_batch(batch).await
+    }
 
-        spawn_blocking(move || {
-            let head_height = read_height_by_db_key(&inner.db, HEAD_HEIGHT_KEY).unwrap_or(0);
+    async fn prepend_single_unchecked(&self, header: ExtendedHeader) -> Result<()> {
+        let mut batch = WriteBatch::new();
+        batch.prepend(header);
+        self.write_batch(batch).await
+    }
+
+    async fn write_batch(&self, batch: WriteBatch) -> Result<()> {
+        let inner = self.inner.clone();
+        let writes = batch
+            .into_ops()
+            .into_iter()
+            .map(PreparedWrite::new)
+            .collect::<Vec<_>>();
+
+        let writes = spawn_blocking(move || -> Result<_> {
+            // All the writes are a single transaction, failing as a whole if any of them fails
+            (
+                inner.db.deref(),
+                &inner.headers,
+                &inner.height_to_hash,
+                &inner.validator_sets,
+                &inner.dahs,
+                &inner.namespace_ranges,
+                &inner.namespace_index,
+            )
+                .transaction(
+                    |(
+                        db,
+                        headers,
+                        height_to_hash,
+                        validator_sets,
+                        dahs,
+                        namespace_ranges,
+                        namespace_index,
+                    )| {
+                        let read_height =
+                            |key: &[u8]| -> ConflictableTransactionResult<_, StoreError> {
+                                Ok(db
+                                    .get(key)?
+                                    .and_then(|v| v.as_ref().try_into().ok())
+                                    .map(u64::from_be_bytes))
+                            };
+
+                        for write in &writes {
+                            let height = write.height;
+                            let height_key = height_to_key(height);
+
+                            if write.prepend {
+                                if read_height(HEAD_HEIGHT_KEY)?.is_some() {
+                                    // Nothing was removed yet if tail is missing, so it is the genesis
+                                    let tail_height = read_height(TAIL_HEIGHT_KEY)?.unwrap_or(1);
+
+                                    if height >= tail_height {
+                                        return Err(ConflictableTransactionError::Abort(
+                                            StoreError::HeightExists(height),
+                                        ));
+                                    }
+
+                                    if height + 1 != tail_height {
+                                        return Err(ConflictableTransactionError::Abort(
+                                            StoreError::NonContinuousPrepend(tail_height, height),
+                                        ));
+                                    }
+                                } else {
+                                    // Empty store, header becomes the head too
+                                    db.insert(HEAD_HEIGHT_KEY, &height_key)?;
+                                }
+
+                                db.insert(TAIL_HEIGHT_KEY, &height_key)?;
+                            } else {
+                                let head_height = read_height(HEAD_HEIGHT_KEY)?.unwrap_or(0);
+
+                                if head_height > 0 && height <= head_height {
+                                    return Err(ConflictableTransactionError::Abort(
+                                        StoreError::HeightExists(height),
+                                    ));
+                                }
+
+                                if head_height + 1 != height {
+                                    return Err(ConflictableTransactionError::Abort(
+                                        StoreError::NonContinuousAppend(head_height, height),
+                                    ));
+                                }
+
+                                db.insert(HEAD_HEIGHT_KEY, &height_key)?;
+                            }
+
+                            if height_to_hash
+                                .insert(&height_key, write.hash.as_bytes())?
+                                .is_some()
+                            {
+                                return Err(ConflictableTransactionError::Abort(
+                                    StoreError::HeightExists(height),
+                                ));
+                            }
+
+                            write
+                                .compact
+                                .insert(write.hash, headers, validator_sets, dahs)?;
+                            namespace_ranges
+                                .insert(&height_key, write.namespace_ranges.as_slice())?;
+                            for key in &write.namespace_index {
+                                namespace_index.insert(key.as_slice(), Vec::new())?;
+                            }
+                        }
+
+                        Ok(())
+                    },
+                )?;
+
+            Ok(writes)
+        })
+        .await??;
 
-            // A light check before checking the whole map
-            if head_height > 0 && height <= head_height {
-                return Err(StoreError::HeightExists(height));
+        for write in writes {
+            if write.prepend {
+                debug!(
+                    "Prepending header {} with height {}",
+                    write.hash, write.height
+                );
+            } else {
+                debug!(
+                    "Inserting header {} with height {}",
+                    write.hash, write.height
+                );
             }
+        }
+
+        Ok(())
+    }
+
+    async fn read_snapshot(&self, range: (Bound<u64>, Bound<u64>)) -> Result<StoreSnapshot> {
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || -> Result<_> {
+            // Transactions are serializable, so all the reads see the same state
+            let snapshot = (
+                inner.db.deref(),
+                &inner.headers,
+                &inner.height_to_hash,
+                &inner.sampling_metadata,
+                &inner.validator_sets,
+                &inner.dahs,
+            )
+                .transaction(
+                    |(db, headers, height_to_hash, sampling_metadata, validator_sets, dahs)| {
+                        let read_height =
+                            |key: &[u8]| -> ConflictableTransactionResult<_, StoreError> {
+                                Ok(db
+                                    .get(key)?
+                                    .and_then(|v| v.as_ref().try_into().ok())
+                                    .map(u64::from_be_bytes))
+                            };
+
+                        let Some(head_height) = read_height(HEAD_HEIGHT_KEY)? else {
+                            return Err(ConflictableTransactionError::Abort(StoreError::NotFound));
+                        };
+                        // Nothing was removed yet if tail is missing, so it is the genesis
+                        let tail_height = read_height(TAIL_HEIGHT_KEY)?.unwrap_or(1);
+
+                        let mut snapshot_headers = Vec::new();
+
+                        for height in clamp_to_stored(range, tail_height, head_height) {
+                            let height_key = height_to_key(height);
+                            let abort = ConflictableTransactionError::Abort;
+
+                            let Some(hash) = height_to_hash.get(height_key)? else {
+                                return Err(abort(StoreError::LostHeight(height)));
+                            };
+                            let Some(record) = headers.get(&hash)? else {
+                                return Err(abort(StoreError::StoredDataError(format!(
+                                    "Missing header for height {height}"
+                                ))));
+                            };
+                            let (validators_key, dah_key, _) =
+                                split_record(&record).map_err(abort)?;
+                            let validator_set = read_shared_in_tx(validator_sets, validators_key)?;
+                            let dah = read_shared_in_tx(dahs, dah_key)?;
+                            let header =
+                                decode_header(&record, &validator_set, &dah).map_err(abort)?;
+
+                            let sampling_metadata = sampling_metadata
+                                .get(height_key)?
+                                .map(|bytes| SamplingMetadata::decode(&bytes))
+                                .transpose()
+                                .map_err(abort)?;
+
+                            snapshot_headers.push(SnapshotHeader {
+                                header,
+                                sampling_metadata,
+                            });
+                        }
+
+                        Ok(StoreSnapshot {
+                            tail_height,
+                            head_height,
+                            headers: snapshot_headers,
+                        })
+                    },
+                )?;
+
+            Ok(snapshot)
+        })
+        .await?
+    }
+
+    async fn remove_tail(&self) -> Result<()> {
+        let inner = self.inner.clone();
+
+        let (hash, height) = spawn_blocking(move || {
+            (
+                inner.db.deref(),
+                &inner.headers,
+                &inner.height_to_hash,
+                &inner.sampling_metadata,
+                &inner.validator_sets,
+                &inner.dahs,
+                &inner.namespace_ranges,
+                &inner.namespace_index,
+            )
+                .transaction(
+                    move |(
+                        db,
+                        headers,
+                        height_to_hash,
+                        sampling_metadata,
+                        validator_sets,
+                        dahs,
+                        namespace_ranges,
+                        namespace_index,
+                    )| {
+                        let read_height =
+                            |key: &[u8]| -> ConflictableTransactionResult<_, StoreError> {
+                                Ok(db
+                                    .get(key)?
+                                    .and_then(|v| v.as_ref().try_into().ok())
+                                    .map(u64::from_be_bytes))
+                            };
+
+                        let Some(head_height) = read_height(HEAD_HEIGHT_KEY)? else {
+                            return Err(ConflictableTransactionError::Abort(StoreError::NotFound));
+                        };
+                        // Nothing was removed yet if tail is missing, so it is the genesis
+                        let tail_height = read_height(TAIL_HEIGHT_KEY)?.unwrap_or(1);
+
+                        if tail_height >= head_height {
+                            return Err(ConflictableTransactionError::Abort(
+                                StoreError::RemovingHead,
+                            ));
+                        }
+
+                        let tail_key = height_to_key(tail_height);
+                        let Some(hash) = height_to_hash.remove(&tail_key)? else {
+                            return Err(ConflictableTransactionError::Abort(
+                                StoreError::LostHeight(tail_height),
+                            ));
+                        };
+                        let Ok(hash_bytes) = hash.as_ref().try_into() else {
+                            return Err(ConflictableTransactionError::Abort(
+                                StoreError::StoredDataError(format!(
+                                    "Invalid hash stored for height {tail_height}"
+                                )),
+                            ));
+                        };
+
+                        if let Some(record) = headers.remove(hash.clone())? {
+                            release_shared_parts(&record, validator_sets, dahs)?;
+                        }
+                        if sampling_metadata.remove(&tail_key)?.is_some() {
+                            let mut sampled = read_sampled_ranges_in_tx(db)?;
+                            sampled.remove_below(tail_height + 1);
+                            db.insert(SAMPLED_RANGES_KEY, sampled.encode())?;
+                        }
+                        if let Some(ranges) = namespace_ranges.remove(&tail_key)? {
+                            let ranges = NamespaceRanges::decode(&ranges)
+                                .map_err(ConflictableTransactionError::Abort)?;
+
+                            for key in ranges.index_keys(tail_height) {
+                                namespace_index.remove(key.as_slice())?;
+                            }
+                        }
+                        db.insert(TAIL_HEIGHT_KEY, &height_to_key(tail_height + 1))?;
+
+                        Ok((Hash::Sha256(hash_bytes), tail_height))
+                    },
+                )
+        })
+        .await??;
+
+        debug!("Removed header {hash} with height {height}");
+        Ok(())
+    }
+
+    async fn remove_blocks_below(&self, height: u64) -> Result<u64> {
+        let inner = self.inner.clone();
+
+        let removed = spawn_blocking(move || -> Result<u64> {
+            let mut removed = 0;
 
-            // Check if it's continuous before checking the whole map.
-            if head_height + 1 != height {
-                return Err(StoreError::NonContinuousAppend(head_height, height));
+            for entry in inner.block_heights.range(..height_to_key(height)) {
+                let (index_key, _) = entry?;
+                let cid = &index_key[size_of::<u64>()..];
+
+                if inner.blocks.remove(cid)?.is_some() {
+                    removed += 1;
+                }
+                inner.block_heights.remove(&index_key)?;
             }
 
-            // Do actual inserts as a transaction, failing if keys already exist
-            (inner.db.deref(), &inner.headers, &inner.height_to_hash).transaction(
-                move |(db, headers, height_to_hash)| {
+            Ok(removed)
+        })
+        .await??;
+
+        debug!("Removed {removed} blocks below height {height}");
+        Ok(removed)
+    }
+
+    async fn update_sampling_metadata(
+        &self,
+        height: u64,
+        accepted: bool,
+        cids: Vec<Cid>,
+    ) -> Result<()> {
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || -> Result<()> {
+            (
+                inner.db.deref(),
+                &inner.height_to_hash,
+                &inner.sampling_metadata,
+            )
+                .transaction(move |(db, height_to_hash, sampling_metadata)| {
                     let height_key = height_to_key(height);
-                    if height_to_hash
-                        .insert(&height_key, hash.as_bytes())?
-                        .is_some()
-                    {
-                        return Err(ConflictableTransactionError::Abort(
-                            StoreError::HeightExists(height),
-                        ));
-                    }
 
-                    db.insert(HEAD_HEIGHT_KEY, &height_key)?;
+                    if height_to_hash.get(height_key)?.is_none() {
+                        return Err(ConflictableTransactionError::Abort(StoreError::NotFound));
+                    }
 
-                    // make sure Result is Infallible, we unwrap it later
-                    let serialized_header: std::result::Result<_, Infallible> = header.encode_vec();
+                    let prev = sampling_metadata
+                        .get(height_key)?
+                        .map(|bytes| SamplingMetadata::decode(&bytes))
+                        .transpose()
+                        .map_err(ConflictableTransactionError::Abort)?;
 
-                    if headers
-                        .insert(hash.as_bytes(), serialized_header.unwrap())?
-                        .is_some()
-                    {
-                        return Err(ConflictableTransactionError::Abort(StoreError::HashExists(
-                            hash,
-                        )));
+                    if prev.is_none() {
+                        let mut sampled = read_sampled_ranges_in_tx(db)?;
+                        sampled.insert(height);
+                        db.insert(SAMPLED_RANGES_KEY, sampled.encode())?;
                     }
 
+                    let metadata = SamplingMetadata::updated(prev, accepted, cids.clone());
+                    sampling_metadata.insert(&height_key, metadata.encode())?;
+
                     Ok(())
-                },
-            )?;
+                })?;
             Ok(())
         })
         .await??;
 
-        debug!("Inserting header {hash} with height {height}");
+        debug!("Updated sampling metadata for height {height}");
         Ok(())
     }
 
+    async fn get_sampling_metadata(&self, height: u64) -> Result<Option<SamplingMetadata>> {
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || {
+            let height_key = height_to_key(height);
+
+            if !inner.height_to_hash.contains_key(height_key)? {
+                return Err(StoreError::NotFound);
+            }
+
+            inner
+                .sampling_metadata
+                .get(height_key)?
+                .map(|bytes| SamplingMetadata::decode(&bytes))
+                .transpose()
+        })
+        .await?
+    }
+
+    async fn unsampled_ranges(&self) -> Result<Vec<RangeInclusive<u64>>> {
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || {
+            // read in a transaction, so the ranges match the stored heights
+            let ranges = inner.db.transaction(|db| {
+                let read_height = |key: &[u8]| -> ConflictableTransactionResult<_, StoreError> {
+                    Ok(db
+                        .get(key)?
+                        .and_then(|v| v.as_ref().try_into().ok())
+                        .map(u64::from_be_bytes))
+                };
+
+                let Some(head_height) = read_height(HEAD_HEIGHT_KEY)? else {
+                    return Ok(Vec::new());
+                };
+                // Nothing was removed yet if tail is missing, so it is the genesis
+                let tail_height = read_height(TAIL_HEIGHT_KEY)?.unwrap_or(1);
+                let sampled = read_sampled_ranges_in_tx(db)?;
+
+                Ok(sampled.missing(tail_height..=head_height))
+            })?;
+
+            Ok(ranges)
+        })
+        .await?
+    }
+
+    async fn sampled_heights(&self, range: impl RangeBounds<u64>) -> Result<Vec<u64>> {
+        let range = (range.start_bound().cloned(), range.end_bound().cloned());
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || {
+            let head_height = match read_height_by_db_key(&inner.db, HEAD_HEIGHT_KEY) {
+                Ok(height) => height,
+                Err(StoreError::NotFound) => return Ok(Vec::new()),
+                Err(e) => return Err(e),
+            };
+            let tail_height = read_tail_height(&inner.db)?;
+            let heights = clamp_to_stored(range, tail_height, head_height);
+
+            if heights.is_empty() {
+                return Ok(Vec::new());
+            }
+
+            inner
+                .sampling_metadata
+                .range(height_to_key(*heights.start())..=height_to_key(*heights.end()))
+                .map(|entry| {
+                    let (key, _) = entry?;
+                    key.as_ref()
+                        .try_into()
+                        .map(u64::from_be_bytes)
+                        .map_err(|_| StoreError::StoredDataError("Invalid height key".to_string()))
+                })
+                .collect()
+        })
+        .await?
+    }
+
+    async fn heights_with_namespace(
+        &self,
+        namespace: Namespace,
+        range: impl RangeBounds<u64>,
+    ) -> Result<Vec<u64>> {
+        let range = (range.start_bound().cloned(), range.end_bound().cloned());
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || {
+            let head_height = match read_height_by_db_key(&inner.db, HEAD_HEIGHT_KEY) {
+                Ok(height) => height,
+                Err(StoreError::NotFound) => return Ok(Vec::new()),
+                Err(e) => return Err(e),
+            };
+            let tail_height = read_tail_height(&inner.db)?;
+            let heights = clamp_to_stored(range, tail_height, head_height);
+
+            if heights.is_empty() {
+                return Ok(Vec::new());
+            }
+
+            let mut found = BTreeSet::new();
+
+            for scan in namespace_index_scans(namespace, heights) {
+                for entry in inner.namespace_index.range(scan) {
+                    let (key, _) = entry?;
+                    found.insert(namespace_index_height(&key)?);
+                }
+            }
+
+            Ok(found.into_iter().collect())
+        })
+        .await?
+    }
+
+    async fn get_peer_addrs(&self) -> Result<Vec<PeerAddr>> {
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || {
+            inner
+                .db
+                .get(PEER_ADDRS_KEY)?
+                .map(|bytes| PeerAddr::decode_all(&bytes))
+                .transpose()
+                .map(Option::unwrap_or_default)
+        })
+        .await?
+    }
+
+    async fn set_peer_addrs(&self, addrs: Vec<PeerAddr>) -> Result<()> {
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || {
+            inner
+                .db
+                .insert(PEER_ADDRS_KEY, PeerAddr::encode_all(&addrs))?;
+            Ok(())
+        })
+        .await?
+    }
+
+    async fn get_network_head(&self) -> Result<Option<ExtendedHeader>> {
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || {
+            let Some(serialized) = inner.db.get(NETWORK_HEAD_KEY)? else {
+                return Ok(None);
+            };
+
+            let raw = RawExtendedHeader::decode(serialized.as_ref()).map_err(decode_error)?;
+            let header = ExtendedHeader::try_from(raw).map_err(StoreError::CelestiaTypes)?;
+
+            Ok(Some(header))
+        })
+        .await?
+    }
+
+    async fn set_network_head(&self, header: ExtendedHeader) -> Result<()> {
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || {
+            // stored whole, the shared validator sets and DAHs are only for the stored headers
+            let serialized = RawExtendedHeader::from(header).encode_to_vec();
+            inner.db.insert(NETWORK_HEAD_KEY, serialized)?;
+            Ok(())
+        })
+        .await?
+    }
+
     /// Flush the store's state to the filesystem.
     pub async fn flush_to_storage(&self) -> Result<()> {
         self.inner.db.flush_async().await?;
@@ -268,6 +878,10 @@ impl Store for SledStore {
         self.head_height().await
     }
 
+    async fn tail_height(&self) -> Result<u64> {
+        self.tail_height().await
+    }
+
     async fn has(&self, hash: &Hash) -> bool {
         self.contains_hash(hash).await
     }
@@ -276,9 +890,175 @@ impl Store for SledStore {
         self.contains_height(height).await
     }
 
+    async fn remove_tail(&self) -> Result<()> {
+        self.remove_tail().await
+    }
+
+    async fn remove_blocks_below(&self, height: u64) -> Result<u64> {
+        self.remove_blocks_below(height).await
+    }
+
     async fn append_single_unchecked(&self, header: ExtendedHeader) -> Result<()> {
         self.append_single_unchecked(header).await
     }
+
+    async fn prepend_single_unchecked(&self, header: ExtendedHeader) -> Result<()> {
+        self.prepend_single_unchecked(header).await
+    }
+
+    async fn write_batch(&self, batch: WriteBatch) -> Result<()> {
+        self.write_batch(batch).await
+    }
+
+    async fn snapshot<R>(&self, range: R) -> Result<StoreSnapshot>
+    where
+        R: RangeBounds<u64> + Send,
+    {
+        let range = (range.start_bound().cloned(), range.end_bound().cloned());
+        self.read_snapshot(range).await
+    }
+
+    async fn update_sampling_metadata(
+        &self,
+        height: u64,
+        accepted: bool,
+        cids: Vec<Cid>,
+    ) -> Result<()> {
+        self.update_sampling_metadata(height, accepted, cids).await
+    }
+
+    async fn get_sampling_metadata(&self, height: u64) -> Result<Option<SamplingMetadata>> {
+        self.get_sampling_metadata(height).await
+    }
+
+    async fn unsampled_ranges(&self) -> Result<Vec<RangeInclusive<u64>>> {
+        self.unsampled_ranges().await
+    }
+
+    async fn sampled_heights<R>(&self, range: R) -> Result<Vec<u64>>
+    where
+        R: RangeBounds<u64> + Send,
+    {
+        self.sampled_heights(range).await
+    }
+
+    async fn heights_with_namespace<R>(&self, namespace: Namespace, range: R) -> Result<Vec<u64>>
+    where
+        R: RangeBounds<u64> + Send,
+    {
+        self.heights_with_namespace(namespace, range).await
+    }
+
+    async fn get_peer_addrs(&self) -> Result<Vec<PeerAddr>> {
+        self.get_peer_addrs().await
+    }
+
+    async fn set_peer_addrs(&self, addrs: Vec<PeerAddr>) -> Result<()> {
+        self.set_peer_addrs(addrs).await
+    }
+
+    async fn get_network_head(&self) -> Result<Option<ExtendedHeader>> {
+        self.get_network_head().await
+    }
+
+    async fn set_network_head(&self, header: ExtendedHeader) -> Result<()> {
+        self.set_network_head(header).await
+    }
+}
+
+#[cfg_attr(not(docs_rs), async_trait)]
+impl Blockstore for SledStore {
+    async fn get<const S: usize>(
+        &self,
+        cid: &CidGeneric<S>,
+    ) -> Result<Option<Vec<u8>>, BlockstoreError> {
+        let inner = self.inner.clone();
+        let key = cid.to_bytes();
+
+        spawn_blocking(move || -> Result<_> {
+            Ok(inner.blocks.get(key)?.map(|data| data.to_vec()))
+        })
+        .await
+        .map_err(StoreError::from)
+        .and_then(|res| res)
+        .map_err(|e| BlockstoreError::StorageError(e.to_string()))
+    }
+
+    async fn put_keyed<const S: usize>(
+        &self,
+        cid: &CidGeneric<S>,
+        data: &[u8],
+    ) -> Result<(), BlockstoreError> {
+        let inner = self.inner.clone();
+        let key = cid.to_bytes();
+        let index_key = shwap_block_height(cid).map(|height| block_height_key(height, &key));
+        let data = data.to_vec();
+
+        let inserted = spawn_blocking(move || -> Result<_> {
+            let inserted = (&inner.blocks, &inner.block_heights).transaction(
+                |(blocks, block_heights)| -> ConflictableTransactionResult<_, StoreError> {
+                    // Only insert if the key doesn't exist yet
+                    if blocks.get(&key)?.is_some() {
+                        return Ok(false);
+                    }
+
+                    blocks.insert(key.as_slice(), data.as_slice())?;
+                    if let Some(index_key) = &index_key {
+                        block_heights.insert(index_key.as_slice(), Vec::new())?;
+                    }
+
+                    Ok(true)
+                },
+            )?;
+            Ok(inserted)
+        })
+        .await
+        .map_err(StoreError::from)
+        .and_then(|res| res)
+        .map_err(|e| BlockstoreError::StorageError(e.to_string()))?;
+
+        if inserted {
+            Ok(())
+        } else {
+            Err(BlockstoreError::CidExists)
+        }
+    }
+
+    async fn remove<const S: usize>(&self, cid: &CidGeneric<S>) -> Result<(), BlockstoreError> {
+        let inner = self.inner.clone();
+        let key = cid.to_bytes();
+        let index_key = shwap_block_height(cid).map(|height| block_height_key(height, &key));
+
+        spawn_blocking(move || -> Result<_> {
+            (&inner.blocks, &inner.block_heights).transaction(
+                |(blocks, block_heights)| -> ConflictableTransactionResult<_, StoreError> {
+                    blocks.remove(key.as_slice())?;
+                    if let Some(index_key) = &index_key {
+                        block_heights.remove(index_key.as_slice())?;
+                    }
+
+                    Ok(())
+                },
+            )?;
+            Ok(())
+        })
+        .await
+        .map_err(StoreError::from)
+        .and_then(|res| res)
+        .map_err(|e| BlockstoreError::StorageError(e.to_string()))
+    }
+}
+
+#[inline]
+fn read_tail_height(db: &Tree) -> Result<u64> {
+    // Ensure the store is not empty
+    read_height_by_db_key(db, HEAD_HEIGHT_KEY)?;
+
+    match read_height_by_db_key(db, TAIL_HEIGHT_KEY) {
+        // Nothing was removed yet, tail is the genesis
+        Err(StoreError::NotFound) => Ok(1),
+        res => res,
+    }
 }
 
 #[inline]
@@ -308,10 +1088,312 @@ fn read_hash_by_db_key(tree: &Tree, db_key: &[u8]) -> Result<Hash> {
 }
 
 #[inline]
-fn read_header_by_db_key(tree: &Tree, db_key: &[u8]) -> Result<ExtendedHeader> {
-    let serialized = tree.get(db_key)?.ok_or(StoreError::NotFound)?;
+fn read_header_by_db_key(inner: &Inner, db_key: &[u8]) -> Result<ExtendedHeader> {
+    let record = inner.headers.get(db_key)?.ok_or(StoreError::NotFound)?;
+    let (validators_key, dah_key, _) = split_record(&record)?;
+
+    let validator_set = read_shared(&inner.validator_sets, validators_key)?;
+    let dah = read_shared(&inner.dahs, dah_key)?;
+
+    decode_header(&record, &validator_set, &dah)
+}
+
+/// Decode the header from its record and the shared parts it points to.
+fn decode_header(record: &[u8], validator_set: &[u8], dah: &[u8]) -> Result<ExtendedHeader> {
+    let (_, _, header) = split_record(record)?;
+
+    let mut raw = RawExtendedHeader::decode(header).map_err(decode_error)?;
+    raw.validator_set = Some(RawValidatorSet::decode(validator_set).map_err(decode_error)?);
+    raw.dah = Some(RawDataAvailabilityHeader::decode(dah).map_err(decode_error)?);
+
+    ExtendedHeader::try_from(raw).map_err(StoreError::CelestiaTypes)
+}
+
+impl CompactHeader {
+    fn new(header: ExtendedHeader) -> Self {
+        // keys are computed from the content, so that invalid headers don't override them
+        let validators_key = header.validator_set.hash();
+        let dah_key = header.dah.hash();
+
+        let mut raw = RawExtendedHeader::from(header);
+        let validator_set = raw.validator_set.take().unwrap_or_default().encode_to_vec();
+        let dah = raw.dah.take().unwrap_or_default().encode_to_vec();
+
+        let mut record = Vec::new();
+        record.extend_from_slice(validators_key.as_bytes());
+        record.extend_from_slice(dah_key.as_bytes());
+        raw.encode(&mut record)
+            .expect("vector has unlimited capacity");
+
+        CompactHeader {
+            record,
+            validators_key,
+            validator_set,
+            dah_key,
+            dah,
+        }
+    }
+
+    /// Insert the header record, sharing the validator set and dah with the other headers.
+    fn insert(
+        &self,
+        hash: Hash,
+        headers: &TransactionalTree,
+        validator_sets: &TransactionalTree,
+        dahs: &TransactionalTree,
+    ) -> ConflictableTransactionResult<(), StoreError> {
+        if headers
+            .insert(hash.as_bytes(), self.record.as_slice())?
+            .is_some()
+        {
+            return Err(ConflictableTransactionError::Abort(StoreError::HashExists(
+                hash,
+            )));
+        }
+
+        acquire_shared(
+            validator_sets,
+            self.validators_key.as_bytes(),
+            &self.validator_set,
+        )?;
+        acquire_shared(dahs, self.dah_key.as_bytes(), &self.dah)?;
+
+        Ok(())
+    }
+}
+
+/// Split the stored header record into the keys of its shared parts and the header itself.
+fn split_record(record: &[u8]) -> Result<(&[u8], &[u8], &[u8])> {
+    const KEY_SIZE: usize = 32;
+
+    if record.len() < 2 * KEY_SIZE {
+        return Err(StoreError::StoredDataError(
+            "Header record too short".to_string(),
+        ));
+    }
+
+    let (validators_key, rest) = record.split_at(KEY_SIZE);
+    let (dah_key, header) = rest.split_at(KEY_SIZE);
+
+    Ok((validators_key, dah_key, header))
+}
+
+fn release_shared_parts(
+    record: &[u8],
+    validator_sets: &TransactionalTree,
+    dahs: &TransactionalTree,
+) -> ConflictableTransactionResult<(), StoreError> {
+    let (validators_key, dah_key, _) =
+        split_record(record).map_err(ConflictableTransactionError::Abort)?;
+
+    release_shared(validator_sets, validators_key)?;
+    release_shared(dahs, dah_key)?;
+
+    Ok(())
+}
+
+/// Store the value shared between the headers, or increase its reference count.
+fn acquire_shared(
+    tree: &TransactionalTree,
+    key: &[u8],
+    value: &[u8],
+) -> ConflictableTransactionResult<(), StoreError> {
+    let entry = match tree.get(key)? {
+        Some(stored) => {
+            let refs = read_refs(&stored).map_err(ConflictableTransactionError::Abort)?;
+            let mut entry = stored.to_vec();
+            entry[..REFS_SIZE].copy_from_slice(&(refs + 1).to_be_bytes());
+            entry
+        }
+        None => {
+            let mut entry = 1u64.to_be_bytes().to_vec();
+            entry.extend_from_slice(value);
+            entry
+        }
+    };
+
+    tree.insert(key, entry)?;
+
+    Ok(())
+}
+
+/// Decrease the reference count of the shared value, removing it if no longer used.
+fn release_shared(
+    tree: &TransactionalTree,
+    key: &[u8],
+) -> ConflictableTransactionResult<(), StoreError> {
+    let Some(stored) = tree.get(key)? else {
+        return Ok(());
+    };
+    let refs = read_refs(&stored).map_err(ConflictableTransactionError::Abort)?;
+
+    if refs <= 1 {
+        tree.remove(key)?;
+    } else {
+        let mut entry = stored.to_vec();
+        entry[..REFS_SIZE].copy_from_slice(&(refs - 1).to_be_bytes());
+        tree.insert(key, entry)?;
+    }
+
+    Ok(())
+}
+
+fn read_shared(tree: &Tree, key: &[u8]) -> Result<Vec<u8>> {
+    let stored = tree.get(key)?.ok_or_else(|| {
+        StoreError::StoredDataError("Missing shared part of the header".to_string())
+    })?;
+    read_refs(&stored)?;
+
+    Ok(stored[REFS_SIZE..].to_vec())
+}
+
+fn read_shared_in_tx(
+    tree: &TransactionalTree,
+    key: &[u8],
+) -> ConflictableTransactionResult<Vec<u8>, StoreError> {
+    let stored = tree.get(key)?.ok_or_else(|| {
+        ConflictableTransactionError::Abort(StoreError::StoredDataError(
+            "Missing shared part of the header".to_string(),
+        ))
+    })?;
+    read_refs(&stored).map_err(ConflictableTransactionError::Abort)?;
+
+    Ok(stored[REFS_SIZE..].to_vec())
+}
+
+fn read_refs(stored: &[u8]) -> Result<u64> {
+    stored
+        .get(..REFS_SIZE)
+        .and_then(|refs| refs.try_into().ok())
+        .map(u64::from_be_bytes)
+        .ok_or_else(|| StoreError::StoredDataError("Invalid reference count".to_string()))
+}
+
+fn decode_error(error: prost::DecodeError) -> StoreError {
+    StoreError::CelestiaTypes(error.into())
+}
+
+/// Rewrite the whole headers stored by the schema version 1 into the compact ones.
+///
+/// The compact headers are written to a new tree and the legacy one is dropped only
+/// after all of them are migrated, so an interrupted migration is started over.
+fn migrate_to_compact_headers(inner: &Inner) -> Result<()> {
+    let has_legacy_headers = inner
+        .db
+        .tree_names()
+        .iter()
+        .any(|name| name.as_ref() == LEGACY_HEADERS_TREE_ID);
+
+    if !has_legacy_headers {
+        return Ok(());
+    }
+
+    let legacy_headers = inner.db.open_tree(LEGACY_HEADERS_TREE_ID)?;
+
+    inner.headers.clear()?;
+    inner.validator_sets.clear()?;
+    inner.dahs.clear()?;
+
+    for entry in legacy_headers.iter() {
+        let (_, serialized) = entry?;
+        let raw = RawExtendedHeader::decode(serialized.as_ref()).map_err(decode_error)?;
+        let header = ExtendedHeader::try_from(raw).map_err(StoreError::CelestiaTypes)?;
+        let hash = header.hash();
+        let compact = CompactHeader::new(header);
+
+        (&inner.headers, &inner.validator_sets, &inner.dahs).transaction(
+            |(headers, validator_sets, dahs)| compact.insert(hash, headers, validator_sets, dahs),
+        )?;
+    }
+
+    inner.db.drop_tree(LEGACY_HEADERS_TREE_ID)?;
+    debug!(
+        "Migrated {} headers to the compact schema",
+        inner.headers.len()
+    );
+
+    Ok(())
+}
+
+/// Index the namespace ranges of the headers stored before the schema version 5.
+///
+/// The index is rebuilt from scratch, so an interrupted migration is started over.
+fn index_namespace_ranges(inner: &Inner) -> Result<()> {
+    inner.namespace_ranges.clear()?;
+    inner.namespace_index.clear()?;
+
+    for entry in inner.height_to_hash.iter() {
+        let (height_key, hash) = entry?;
+        let header = read_header_by_db_key(inner, &hash)?;
+        let ranges = NamespaceRanges::new(&header.dah);
+
+        for key in ranges.index_keys(header.height().value()) {
+            inner.namespace_index.insert(key.as_slice(), Vec::new())?;
+        }
+        inner.namespace_ranges.insert(height_key, ranges.encode())?;
+    }
+
+    debug!(
+        "Indexed the namespace ranges of {} headers",
+        inner.namespace_ranges.len()
+    );
+
+    Ok(())
+}
 
-    ExtendedHeader::decode(serialized.as_ref()).map_err(|e| StoreError::CelestiaTypes(e.into()))
+fn index_sampled_ranges(inner: &Inner) -> Result<()> {
+    let mut heights = Vec::new();
+
+    for entry in inner.sampling_metadata.iter() {
+        let (height_key, _) = entry?;
+        let height = height_key
+            .as_ref()
+            .try_into()
+            .map(u64::from_be_bytes)
+            .map_err(|_| StoreError::StoredDataError("Invalid height key".to_string()))?;
+        heights.push(height);
+    }
+
+    let sampled = SampledRanges::from_heights(heights);
+    inner.db.insert(SAMPLED_RANGES_KEY, sampled.encode())?;
+
+    debug!("Indexed the ranges of the sampled heights");
+
+    Ok(())
+}
+
+/// Index the blocks of the shwap containers stored before the schema version 6.
+///
+/// The index is rebuilt from scratch, so an interrupted migration is started over.
+fn index_block_heights(inner: &Inner) -> Result<()> {
+    inner.block_heights.clear()?;
+
+    for entry in inner.blocks.iter() {
+        let (key, _) = entry?;
+        let height = CidGeneric::<MAX_MH_SIZE>::try_from(key.as_ref())
+            .ok()
+            .and_then(|cid| shwap_block_height(&cid));
+
+        if let Some(height) = height {
+            inner
+                .block_heights
+                .insert(block_height_key(height, &key), Vec::new())?;
+        }
+    }
+
+    debug!("Indexed {} blocks by the height", inner.block_heights.len());
+
+    Ok(())
+}
+
+fn read_sampled_ranges_in_tx(
+    db: &TransactionalTree,
+) -> ConflictableTransactionResult<SampledRanges, StoreError> {
+    db.get(SAMPLED_RANGES_KEY)?
+        .map(|bytes| SampledRanges::decode(&bytes))
+        .transpose()
+        .map(Option::unwrap_or_default)
+        .map_err(ConflictableTransactionError::Abort)
 }
 
 #[inline]
@@ -321,9 +1403,16 @@ fn height_to_key(height: u64) -> [u8; 8] {
     height.to_be_bytes()
 }
 
+fn block_height_key(height: u64, cid: &[u8]) -> Vec<u8> {
+    let mut key = height_to_key(height).to_vec();
+    key.extend_from_slice(cid);
+    key
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
+    use celestia_types::row::{IdLayout, RowId};
     use celestia_types::test_utils::ExtendedHeaderGenerator;
     use celestia_types::Height;
 
@@ -446,6 +1535,65 @@ pub mod tests {
             insert_existing_result,
             Err(StoreError::NonContinuousAppend(13, 15))
         ));
+        // the range is appended atomically
+        assert_eq!(s.head_height().await.unwrap(), 10);
+    }
+
+    #[tokio::test]
+    async fn test_write_batch() {
+        let s = SledStore::new_temp().await.unwrap();
+        let mut gen = ExtendedHeaderGenerator::new_from_height(5);
+        let hs = gen.next_many(4);
+
+        let mut batch = WriteBatch::new();
+        batch
+            .prepend(hs[1].clone())
+            .append(hs[2].clone())
+            .prepend(hs[0].clone());
+        s.write_batch(batch).await.unwrap();
+        assert_eq!(s.tail_height().await.unwrap(), 5);
+        assert_eq!(s.head_height().await.unwrap(), 7);
+
+        // failing write discards the preceding ones
+        let mut batch = WriteBatch::new();
+        batch.append(hs[3].clone()).append(hs[3].clone());
+        assert!(matches!(
+            s.write_batch(batch).await,
+            Err(StoreError::HeightExists(8))
+        ));
+        assert_eq!(s.head_height().await.unwrap(), 7);
+        assert!(!s.has_at(8).await);
+        assert!(s.get_by_hash(&hs[3].hash()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_snapshot() {
+        let (s, _) = gen_filled_store(5, None).await;
+        s.update_sampling_metadata(4, false, Vec::new())
+            .await
+            .unwrap();
+        s.remove_tail().await.unwrap();
+
+        let snapshot = s.snapshot(..).await.unwrap();
+        assert_eq!(snapshot.tail_height, 2);
+        assert_eq!(snapshot.head_height, 5);
+        assert_eq!(snapshot.headers.len(), 4);
+
+        for entry in &snapshot.headers {
+            let height = entry.header.height().value();
+            assert_eq!(entry.header, s.get_by_height(height).await.unwrap());
+            assert_eq!(
+                entry.sampling_metadata,
+                s.get_sampling_metadata(height).await.unwrap()
+            );
+        }
+        assert!(snapshot.headers[2].sampling_metadata.is_some());
+
+        let snapshot = s.snapshot(1..=3).await.unwrap();
+        assert_eq!(snapshot.headers.len(), 2);
+
+        let s = SledStore::new_temp().await.unwrap();
+        assert!(matches!(s.snapshot(..).await, Err(StoreError::NotFound)));
     }
 
     #[tokio::test]
@@ -461,6 +1609,105 @@ pub mod tests {
         ));
     }
 
+    #[tokio::test]
+    async fn test_remove_tail() {
+        let db_dir = TempDir::new("celestia.test").unwrap();
+        let (s, _) = gen_filled_store(3, Some(db_dir.path())).await;
+        assert_eq!(s.tail_height().await.unwrap(), 1);
+
+        let header1 = s.get_by_height(1).await.unwrap();
+
+        s.remove_tail().await.unwrap();
+        assert_eq!(s.tail_height().await.unwrap(), 2);
+        assert!(!s.contains_height(1).await);
+        assert!(!s.contains_hash(&header1.hash()).await);
+        assert!(matches!(
+            s.get_by_height(1).await,
+            Err(StoreError::NotFound)
+        ));
+
+        s.remove_tail().await.unwrap();
+        assert!(matches!(
+            s.remove_tail().await,
+            Err(StoreError::RemovingHead)
+        ));
+        drop(s);
+
+        let s = SledStore::new_in_path(db_dir.path()).await.unwrap();
+        assert_eq!(s.tail_height().await.unwrap(), 3);
+        assert_eq!(s.head_height().await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_prepend() {
+        let db_dir = TempDir::new("celestia.test").unwrap();
+        let (s, mut gen) = gen_filled_store(0, Some(db_dir.path())).await;
+        let headers = gen.next_many(10);
+
+        // Empty store can be started from any height
+        s.prepend_single_unchecked(headers[9].clone())
+            .await
+            .unwrap();
+        assert_eq!(s.head_height().await.unwrap(), 10);
+        assert_eq!(s.tail_height().await.unwrap(), 10);
+
+        s.prepend_single_unchecked(headers[8].clone())
+            .await
+            .unwrap();
+        assert!(matches!(
+            s.prepend_single_unchecked(headers[8].clone()).await,
+            Err(StoreError::HeightExists(9))
+        ));
+        assert!(matches!(
+            s.prepend_single_unchecked(headers[6].clone()).await,
+            Err(StoreError::NonContinuousPrepend(9, 7))
+        ));
+
+        s.append_single_unchecked(gen.next()).await.unwrap();
+        drop(s);
+
+        let s = SledStore::new_in_path(db_dir.path()).await.unwrap();
+        assert_eq!(s.tail_height().await.unwrap(), 9);
+        assert_eq!(s.head_height().await.unwrap(), 11);
+        assert_eq!(s.get_by_height(9).await.unwrap(), headers[8]);
+        assert!(!s.contains_height(8).await);
+    }
+
+    #[tokio::test]
+    async fn test_peer_addrs_persistence() {
+        let db_dir = TempDir::new("celestia.test").unwrap();
+        let s = SledStore::new_in_path(db_dir.path()).await.unwrap();
+        assert!(s.get_peer_addrs().await.unwrap().is_empty());
+
+        let addrs = vec![PeerAddr {
+            addr: format!("/ip4/1.2.3.4/tcp/2121/p2p/{}", libp2p::PeerId::random())
+                .parse()
+                .unwrap(),
+            last_seen: celestia_tendermint::Time::from_unix_timestamp(1000, 0).unwrap(),
+        }];
+        s.set_peer_addrs(addrs.clone()).await.unwrap();
+        drop(s);
+
+        let s = SledStore::new_in_path(db_dir.path()).await.unwrap();
+        assert_eq!(s.get_peer_addrs().await.unwrap(), addrs);
+    }
+
+    #[tokio::test]
+    async fn test_network_head_persistence() {
+        let db_dir = TempDir::new("celestia.test").unwrap();
+        let s = SledStore::new_in_path(db_dir.path()).await.unwrap();
+        assert!(s.get_network_head().await.unwrap().is_none());
+
+        // network head doesn't need to be adjacent to the stored headers
+        let header = ExtendedHeaderGenerator::new_from_height(100).next();
+        s.set_network_head(header.clone()).await.unwrap();
+        drop(s);
+
+        let s = SledStore::new_in_path(db_dir.path()).await.unwrap();
+        assert_eq!(s.get_network_head().await.unwrap(), Some(header));
+        assert!(s.get_head().await.is_err());
+    }
+
     #[tokio::test]
     async fn test_store_persistence() {
         let db_dir = TempDir::new("celestia.test").unwrap();
@@ -518,6 +1765,74 @@ pub mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_sampling_metadata_persistence() {
+        let db_dir = TempDir::new("celestia.test").unwrap();
+        let (s, _) = gen_filled_store(5, Some(db_dir.path())).await;
+        let cid = |n| Cid::new_v1(0x55, multihash::Multihash::wrap(0x12, &[n; 32]).unwrap());
+
+        s.update_sampling_metadata(1, true, vec![cid(1)])
+            .await
+            .unwrap();
+        s.update_sampling_metadata(2, true, vec![cid(2)])
+            .await
+            .unwrap();
+        s.update_sampling_metadata(4, false, vec![cid(4)])
+            .await
+            .unwrap();
+        s.update_sampling_metadata(4, false, vec![cid(4), cid(5)])
+            .await
+            .unwrap();
+        assert!(matches!(
+            s.update_sampling_metadata(6, true, Vec::new()).await,
+            Err(StoreError::NotFound)
+        ));
+        drop(s);
+
+        let s = SledStore::new_in_path(db_dir.path()).await.unwrap();
+        assert_eq!(s.get_sampling_metadata(3).await.unwrap(), None);
+        assert_eq!(
+            s.get_sampling_metadata(4).await.unwrap(),
+            Some(SamplingMetadata {
+                accepted: false,
+                cids_sampled: vec![cid(4), cid(5)],
+            })
+        );
+        assert_eq!(s.next_unsampled_height().await.unwrap(), Some(3));
+        assert_eq!(s.unsampled_ranges().await.unwrap(), vec![3..=3, 5..=5]);
+        assert_eq!(s.sampled_heights(..).await.unwrap(), vec![1, 2, 4]);
+        assert_eq!(s.sampled_heights(2..4).await.unwrap(), vec![2]);
+
+        // stores of the previous schema get the sampled ranges indexed when opened
+        s.inner.db.remove(SAMPLED_RANGES_KEY).unwrap();
+        s.inner
+            .db
+            .insert(SCHEMA_VERSION_KEY, &3u64.to_be_bytes())
+            .unwrap();
+        drop(s);
+
+        let s = SledStore::new_in_path(db_dir.path()).await.unwrap();
+        assert_eq!(s.unsampled_ranges().await.unwrap(), vec![3..=3, 5..=5]);
+
+        s.update_sampling_metadata(3, true, Vec::new())
+            .await
+            .unwrap();
+        assert_eq!(s.next_unsampled_height().await.unwrap(), Some(5));
+        s.update_sampling_metadata(5, true, Vec::new())
+            .await
+            .unwrap();
+        assert_eq!(s.next_unsampled_height().await.unwrap(), None);
+
+        // Metadata is removed together with the header
+        s.remove_tail().await.unwrap();
+        assert!(matches!(
+            s.get_sampling_metadata(1).await,
+            Err(StoreError::NotFound)
+        ));
+        assert_eq!(s.next_unsampled_height().await.unwrap(), None);
+        assert_eq!(s.sampled_heights(..).await.unwrap(), vec![2, 3, 4, 5]);
+    }
+
     #[tokio::test]
     async fn test_separate_stores() {
         let (store0, mut gen0) = gen_filled_store(0, None).await;
@@ -549,6 +1864,174 @@ pub mod tests {
         assert_eq!(store1.head_height().await.unwrap(), 16);
     }
 
+    #[tokio::test]
+    async fn test_blockstore() {
+        let s = SledStore::new_temp().await.unwrap();
+        let cid = Cid::new_v1(0x55, multihash::Multihash::wrap(0x12, &[1; 32]).unwrap());
+
+        assert_eq!(s.get(&cid).await.unwrap(), None);
+
+        s.put_keyed(&cid, b"data").await.unwrap();
+        assert_eq!(s.get(&cid).await.unwrap().unwrap(), b"data");
+        assert!(Blockstore::has(&s, &cid).await.unwrap());
+
+        assert_eq!(
+            s.put_keyed(&cid, b"other").await.unwrap_err(),
+            BlockstoreError::CidExists
+        );
+
+        // blocks don't interfere with headers
+        assert!(matches!(s.head_height().await, Err(StoreError::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_remove_blocks_below() {
+        let s = SledStore::new_temp().await.unwrap();
+        let row = |height| RowId::new(0, height).unwrap().to_cid(IdLayout::V1);
+        let other = Cid::new_v1(0x55, multihash::Multihash::wrap(0x12, &[1; 32]).unwrap());
+
+        for height in 1..=3 {
+            s.put_keyed(&row(height), b"row").await.unwrap();
+        }
+        s.put_keyed(&other, b"other").await.unwrap();
+
+        assert_eq!(s.remove_blocks_below(3).await.unwrap(), 2);
+        assert_eq!(s.get(&row(1)).await.unwrap(), None);
+        assert_eq!(s.get(&row(2)).await.unwrap(), None);
+        assert!(Blockstore::has(&s, &row(3)).await.unwrap());
+        assert!(Blockstore::has(&s, &other).await.unwrap());
+
+        assert_eq!(s.remove_blocks_below(3).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_remove_block() {
+        let s = SledStore::new_temp().await.unwrap();
+        let row = |height| RowId::new(0, height).unwrap().to_cid(IdLayout::V1);
+
+        s.put_keyed(&row(1), b"row").await.unwrap();
+        s.put_keyed(&row(2), b"row").await.unwrap();
+        s.remove(&row(1)).await.unwrap();
+        assert!(!Blockstore::has(&s, &row(1)).await.unwrap());
+        s.remove(&row(1)).await.unwrap();
+
+        // the index entry is gone with the block
+        assert_eq!(s.inner.block_heights.len(), 1);
+        assert_eq!(s.remove_blocks_below(3).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_block_heights_indexed_on_migration() {
+        let db_dir = TempDir::new("celestia.test").unwrap();
+        let row = |height| RowId::new(0, height).unwrap().to_cid(IdLayout::V1);
+
+        let s = SledStore::new_in_path(db_dir.path()).await.unwrap();
+        for height in 1..=3 {
+            s.put_keyed(&row(height), b"row").await.unwrap();
+        }
+
+        // blocks stored by the schema version 5
+        s.inner.block_heights.clear().unwrap();
+        s.inner
+            .db
+            .insert(SCHEMA_VERSION_KEY, &5u64.to_be_bytes())
+            .unwrap();
+        drop(s);
+
+        let s = SledStore::new_in_path(db_dir.path()).await.unwrap();
+        assert_eq!(s.remove_blocks_below(3).await.unwrap(), 2);
+        assert!(Blockstore::has(&s, &row(3)).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_shared_parts_deduplicated() {
+        let (s, _) = gen_filled_store(10, None).await;
+
+        // generated headers have the same validator set
+        assert_eq!(s.inner.validator_sets.len(), 1);
+        assert_eq!(s.inner.headers.len(), 10);
+
+        for _ in 0..9 {
+            s.remove_tail().await.unwrap();
+        }
+
+        assert_eq!(s.inner.validator_sets.len(), 1);
+        assert_eq!(s.inner.dahs.len(), 1);
+        assert_eq!(s.get_head().await.unwrap().height().value(), 10);
+    }
+
+    #[tokio::test]
+    async fn test_heights_with_namespace() {
+        let db_dir = TempDir::new("celestia.test").unwrap();
+        let ns1 = Namespace::new_v0(&[1]).unwrap();
+        let ns2 = Namespace::new_v0(&[2]).unwrap();
+        let mut gen = ExtendedHeaderGenerator::new();
+        let headers: Vec<_> = [ns1, ns2, ns1, ns1]
+            .iter()
+            .map(|namespace| gen.next_with_eds(2, &[*namespace]).0)
+            .collect();
+
+        let s = SledStore::new_in_path(db_dir.path()).await.unwrap();
+        assert!(s.heights_with_namespace(ns1, ..).await.unwrap().is_empty());
+        s.append_unchecked(headers).await.unwrap();
+        s.remove_tail().await.unwrap();
+
+        assert_eq!(s.heights_with_namespace(ns1, ..).await.unwrap(), vec![3, 4]);
+        assert_eq!(s.heights_with_namespace(ns2, 2..=3).await.unwrap(), vec![2]);
+        assert_eq!(s.inner.namespace_ranges.len(), 3);
+        // the keys of the removed header are gone from the index
+        for entry in s.inner.namespace_index.iter() {
+            let (key, _) = entry.unwrap();
+            assert_ne!(namespace_index_height(&key).unwrap(), 1);
+        }
+        drop(s);
+
+        // the index is persisted
+        let s = SledStore::new_in_path(db_dir.path()).await.unwrap();
+        assert_eq!(s.heights_with_namespace(ns1, ..4).await.unwrap(), vec![3]);
+    }
+
+    #[tokio::test]
+    async fn test_migrate_legacy_headers() {
+        let db_dir = TempDir::new("celestia.test").unwrap();
+        let headers = ExtendedHeaderGenerator::new().next_many(5);
+
+        // fill the store with the schema version 1
+        let db = sled::open(db_dir.path()).unwrap();
+        let legacy_headers = db.open_tree(LEGACY_HEADERS_TREE_ID).unwrap();
+        let height_to_hash = db.open_tree(HEIGHT_TO_HASH_TREE_ID).unwrap();
+        for header in &headers {
+            let hash = header.hash();
+            let serialized = RawExtendedHeader::from(header.clone()).encode_to_vec();
+            legacy_headers.insert(hash.as_bytes(), serialized).unwrap();
+            height_to_hash
+                .insert(height_to_key(header.height().value()), hash.as_bytes())
+                .unwrap();
+        }
+        db.insert(HEAD_HEIGHT_KEY, &height_to_key(5)).unwrap();
+        drop((legacy_headers, height_to_hash, db));
+
+        let s = SledStore::new_in_path(db_dir.path()).await.unwrap();
+
+        assert_eq!(s.head_height().await.unwrap(), 5);
+        for header in &headers {
+            assert_eq!(&s.get_by_hash(&header.hash()).await.unwrap(), header);
+        }
+        assert_eq!(s.inner.validator_sets.len(), 1);
+        assert_eq!(s.inner.namespace_ranges.len(), 5);
+        assert!(!s.inner.namespace_index.is_empty());
+        assert!(!s
+            .inner
+            .db
+            .tree_names()
+            .iter()
+            .any(|name| name.as_ref() == LEGACY_HEADERS_TREE_ID));
+        assert_eq!(
+            read_height_by_db_key(&s.inner.db, SCHEMA_VERSION_KEY).unwrap(),
+            SCHEMA_VERSION
+        );
+    }
+
     pub async fn gen_filled_store(
         amount: u64,
         path: Option<&Path>,
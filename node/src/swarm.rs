@@ -1,43 +1,76 @@
 use instant::Duration;
-use libp2p::{identity::Keypair, swarm::NetworkBehaviour, Swarm, SwarmBuilder};
+use libp2p::{identity::Keypair, noise, relay, swarm::NetworkBehaviour, Swarm, SwarmBuilder};
 
-use crate::p2p::P2pError;
+use crate::p2p::{P2pError, TransportConfig};
 
 pub(crate) use self::imp::new_swarm;
 
+impl From<noise::Error> for P2pError {
+    fn from(e: noise::Error) -> Self {
+        P2pError::InitNoise(e.to_string())
+    }
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 mod imp {
     use super::*;
-    use libp2p::{dns, noise, tcp, yamux};
+    use libp2p::core::transport::{upgrade, MemoryTransport, OptionalTransport};
+    use libp2p::{dns, quic, tcp, yamux, Transport};
 
-    pub(crate) fn new_swarm<B>(keypair: Keypair, behaviour: B) -> Result<Swarm<B>, P2pError>
+    /// Build the swarm with the enabled transports and the circuit relay client.
+    ///
+    /// The `behaviour` is constructed from the relay client's part of the behaviour.
+    pub(crate) fn new_swarm<B, F>(
+        keypair: Keypair,
+        transports: TransportConfig,
+        behaviour: F,
+    ) -> Result<Swarm<B>, P2pError>
     where
         B: NetworkBehaviour,
+        F: FnOnce(relay::client::Behaviour) -> B,
     {
+        if transports.webtransport {
+            return Err(P2pError::UnsupportedTransport("webtransport"));
+        }
+        if !transports.tcp && !transports.quic && !transports.memory {
+            return Err(P2pError::NoTransportEnabled);
+        }
+
+        let tcp = if transports.tcp {
+            let transport = tcp::tokio::Transport::new(tcp::Config::default())
+                .upgrade(upgrade::Version::V1Lazy)
+                .authenticate(noise::Config::new(&keypair)?)
+                .multiplex(yamux_config());
+            OptionalTransport::some(transport)
+        } else {
+            OptionalTransport::none()
+        };
+
+        let quic = if transports.quic {
+            let config = quic::Config::new(&keypair);
+            OptionalTransport::some(quic::tokio::Transport::new(config))
+        } else {
+            OptionalTransport::none()
+        };
+
+        let memory = if transports.memory {
+            let transport = MemoryTransport::default()
+                .upgrade(upgrade::Version::V1Lazy)
+                .authenticate(noise::Config::new(&keypair)?)
+                .multiplex(yamux_config());
+            OptionalTransport::some(transport)
+        } else {
+            OptionalTransport::none()
+        };
+
         Ok(SwarmBuilder::with_existing_identity(keypair)
             .with_tokio()
-            .with_tcp(tcp::Config::default(), noise::Config::new, || {
-                // This increases bandwidth utilitation. With 1MB of receive
-                // window, we can utilize 81.92mbps (10mb/s) per stream when
-                // latency is 100ms: 1mb / 100ms * 8bits = 81.92mbps
-                //
-                // This means that machine needs 8gb of ram to handle 8192
-                // streams (the default). For this reason we lower max streams
-                // to 2048, so the maximum memory usage will be 2gb.
-                //
-                // More info: https://github.com/libp2p/rust-yamux/issues/162
-                //
-                // NOTE: go-libp2p sets 16mb for receive window, but they have
-                // connection and memory limits in a higher layer. rust-libp2p
-                // doesn't implement this, and if we used 16mb here we would be
-                // vulnerable to DoS attacks.
-                let mut config = yamux::Config::default();
-                config.set_receive_window_size(1024 * 1024);
-                config.set_max_buffer_size(1024 * 1024);
-                config.set_max_num_streams(2048);
-                config
-            })?
-            .with_quic()
+            .with_other_transport(|_| tcp)
+            .expect("tcp::tokio::Transport is infallible")
+            .with_other_transport(|_| quic)
+            .expect("quic::tokio::Transport is infallible")
+            .with_other_transport(|_| memory)
+            .expect("MemoryTransport is infallible")
             // We do not use system's DNS because libp2p loads DNS servers only when
             // `Swarm` get constructed. This is not a problem for server machines, but
             // it is for movable machines such as laptops and smart phones. Because of
@@ -55,7 +88,8 @@ mod imp {
                 dns::ResolverConfig::cloudflare(),
                 dns::ResolverOpts::default(),
             )
-            .with_behaviour(|_| behaviour)
+            .with_relay_client(noise::Config::new, yamux_config)?
+            .with_behaviour(|_, relay_client| behaviour(relay_client))
             .expect("Moving behaviour doesn't fail")
             .with_swarm_config(|config| {
                 // TODO: Refactor code to avoid being idle. This can be done by preloading a
@@ -66,22 +100,59 @@ mod imp {
             .build())
     }
 
-    impl From<noise::Error> for P2pError {
-        fn from(e: noise::Error) -> Self {
-            P2pError::InitNoise(e.to_string())
-        }
+    fn yamux_config() -> yamux::Config {
+        // This increases bandwidth utilitation. With 1MB of receive
+        // window, we can utilize 81.92mbps (10mb/s) per stream when
+        // latency is 100ms: 1mb / 100ms * 8bits = 81.92mbps
+        //
+        // This means that machine needs 8gb of ram to handle 8192
+        // streams (the default). For this reason we lower max streams
+        // to 2048, so the maximum memory usage will be 2gb.
+        //
+        // More info: https://github.com/libp2p/rust-yamux/issues/162
+        //
+        // NOTE: go-libp2p sets 16mb for receive window, but they have
+        // connection and memory limits in a higher layer. rust-libp2p
+        // doesn't implement this, and if we used 16mb here we would be
+        // vulnerable to DoS attacks.
+        let mut config = yamux::Config::default();
+        config.set_receive_window_size(1024 * 1024);
+        config.set_max_buffer_size(1024 * 1024);
+        config.set_max_num_streams(2048);
+        config
     }
 }
 
 #[cfg(target_arch = "wasm32")]
 mod imp {
     use super::*;
-    use libp2p::webtransport_websys;
+    use libp2p::{webtransport_websys, yamux};
 
-    pub(crate) fn new_swarm<B>(keypair: Keypair, behaviour: B) -> Result<Swarm<B>, P2pError>
+    /// Build the swarm with the WebTransport and the circuit relay client.
+    ///
+    /// The `behaviour` is constructed from the relay client's part of the behaviour.
+    pub(crate) fn new_swarm<B, F>(
+        keypair: Keypair,
+        transports: TransportConfig,
+        behaviour: F,
+    ) -> Result<Swarm<B>, P2pError>
     where
         B: NetworkBehaviour,
+        F: FnOnce(relay::client::Behaviour) -> B,
     {
+        if transports.tcp {
+            return Err(P2pError::UnsupportedTransport("tcp"));
+        }
+        if transports.quic {
+            return Err(P2pError::UnsupportedTransport("quic"));
+        }
+        if transports.memory {
+            return Err(P2pError::UnsupportedTransport("memory"));
+        }
+        if !transports.webtransport {
+            return Err(P2pError::NoTransportEnabled);
+        }
+
         Ok(SwarmBuilder::with_existing_identity(keypair)
             .with_wasm_bindgen()
             .with_other_transport(|local_keypair| {
@@ -89,7 +160,8 @@ mod imp {
                 webtransport_websys::Transport::new(config)
             })
             .expect("webtransport_websys::Transport is infallible")
-            .with_behaviour(|_| behaviour)
+            .with_relay_client(noise::Config::new, yamux::Config::default)?
+            .with_behaviour(|_, relay_client| behaviour(relay_client))
             .expect("Moving behaviour doesn't fail")
             .with_swarm_config(|config| {
                 // TODO: Refactor code to avoid being idle. This can be done by preloading a
@@ -100,3 +172,188 @@ mod imp {
             .build())
     }
 }
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::executor::{spawn, timeout};
+    use futures::StreamExt;
+    use libp2p::multiaddr::Protocol;
+    use libp2p::swarm::SwarmEvent;
+    use libp2p::{dcutr, identify, Multiaddr};
+
+    #[derive(NetworkBehaviour)]
+    struct RelayServer {
+        relay: relay::Behaviour,
+        identify: identify::Behaviour,
+    }
+
+    #[derive(NetworkBehaviour)]
+    struct Client {
+        relay_client: relay::client::Behaviour,
+        dcutr: dcutr::Behaviour,
+        identify: identify::Behaviour,
+    }
+
+    fn identify(keypair: &Keypair) -> identify::Behaviour {
+        identify::Behaviour::new(identify::Config::new(
+            "/lumina-test/1.0.0".to_owned(),
+            keypair.public(),
+        ))
+    }
+
+    fn relay_server() -> Swarm<RelayServer> {
+        let keypair = Keypair::generate_ed25519();
+        let peer_id = keypair.public().to_peer_id();
+
+        new_swarm(keypair.clone(), TransportConfig::in_memory(), |_| {
+            RelayServer {
+                relay: relay::Behaviour::new(peer_id, Default::default()),
+                identify: identify(&keypair),
+            }
+        })
+        .unwrap()
+    }
+
+    fn client() -> Swarm<Client> {
+        let keypair = Keypair::generate_ed25519();
+        let peer_id = keypair.public().to_peer_id();
+
+        new_swarm(
+            keypair.clone(),
+            TransportConfig::in_memory(),
+            |relay_client| Client {
+                relay_client,
+                dcutr: dcutr::Behaviour::new(peer_id),
+                identify: identify(&keypair),
+            },
+        )
+        .unwrap()
+    }
+
+    async fn listen_on_memory<B: NetworkBehaviour>(swarm: &mut Swarm<B>) -> Multiaddr {
+        swarm.listen_on("/memory/0".parse().unwrap()).unwrap();
+
+        loop {
+            if let SwarmEvent::NewListenAddr { address, .. } = swarm.select_next_some().await {
+                return address;
+            }
+        }
+    }
+
+    async fn wait_for_addr_candidate(swarm: &mut Swarm<Client>, addr: &Multiaddr) {
+        loop {
+            if let SwarmEvent::NewExternalAddrCandidate { address } = swarm.select_next_some().await
+            {
+                if &address == addr {
+                    return;
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn relayed_connection_is_upgraded_to_direct() {
+        let mut relay = relay_server();
+        let mut dst = client();
+        let mut src = client();
+
+        let relay_peer_id = *relay.local_peer_id();
+        let dst_peer_id = *dst.local_peer_id();
+
+        let relay_addr = listen_on_memory(&mut relay).await;
+        relay.add_external_address(relay_addr.clone());
+        let dst_addr = listen_on_memory(&mut dst).await;
+        let src_addr = listen_on_memory(&mut src).await;
+
+        // The port a memory connection is dialed from can't be dialed back, so the
+        // relay dials the clients to observe the addresses they can be reached on.
+        relay.dial(dst_addr.clone()).unwrap();
+        relay.dial(src_addr.clone()).unwrap();
+        spawn(async move {
+            loop {
+                relay.select_next_some().await;
+            }
+        });
+
+        timeout(Duration::from_secs(30), async {
+            wait_for_addr_candidate(&mut dst, &dst_addr).await;
+            wait_for_addr_candidate(&mut src, &src_addr).await;
+
+            let circuit_addr = relay_addr
+                .with(Protocol::P2p(relay_peer_id))
+                .with(Protocol::P2pCircuit);
+            dst.listen_on(circuit_addr.clone()).unwrap();
+
+            loop {
+                if let SwarmEvent::Behaviour(ClientEvent::RelayClient(
+                    relay::client::Event::ReservationReqAccepted { .. },
+                )) = dst.select_next_some().await
+                {
+                    break;
+                }
+            }
+
+            spawn(async move {
+                loop {
+                    dst.select_next_some().await;
+                }
+            });
+
+            src.dial(circuit_addr.with(Protocol::P2p(dst_peer_id)))
+                .unwrap();
+
+            let mut relayed = false;
+            let mut direct = Vec::new();
+            let upgraded = loop {
+                match src.select_next_some().await {
+                    SwarmEvent::ConnectionEstablished {
+                        peer_id,
+                        connection_id,
+                        endpoint,
+                        ..
+                    } if peer_id == dst_peer_id => {
+                        if endpoint
+                            .get_remote_address()
+                            .iter()
+                            .any(|proto| proto == Protocol::P2pCircuit)
+                        {
+                            relayed = true;
+                        } else {
+                            direct.push(connection_id);
+                        }
+                    }
+                    SwarmEvent::Behaviour(ClientEvent::Dcutr(dcutr::Event {
+                        remote_peer_id,
+                        result,
+                    })) if remote_peer_id == dst_peer_id => {
+                        break result.unwrap();
+                    }
+                    _ => {}
+                }
+            };
+
+            assert!(relayed);
+
+            // the direct connection may be reported before or after the dcutr event
+            while !direct.contains(&upgraded) {
+                if let SwarmEvent::ConnectionEstablished {
+                    connection_id,
+                    endpoint,
+                    ..
+                } = src.select_next_some().await
+                {
+                    if !endpoint
+                        .get_remote_address()
+                        .iter()
+                        .any(|proto| proto == Protocol::P2pCircuit)
+                    {
+                        direct.push(connection_id);
+                    }
+                }
+            }
+        })
+        .await
+        .unwrap();
+    }
+}
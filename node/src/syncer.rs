@@ -8,32 +8,56 @@
 //! on the `header-ex` p2p protocol. In the meantime, it constantly checks for the latest
 //! headers announced on the `header-sub` p2p protocol to keep the `subjective_head` as close
 //! to the `network_head` as possible.
+//!
+//! In the [`SyncMode::Backward`] mode, the store is instead initialized with the
+//! `subjective_head` and, once the headers above the local head are synchronized, the
+//! historical headers are requested below the tail of the store, down to the target height.
+//! Each range is verified against the header above it and stored as soon as it completes.
+//!
+//! The latest verified network head is persisted in the store. When the node restarts
+//! within the [`TRUSTING_PERIOD`] of it, it becomes the initial `subjective_head` without
+//! asking the trusted peers. Once it's older than that, the trust in it expired and the
+//! syncer re-initializes from the trusted peers.
+//!
+//! Once a valid bad encoding fraud proof is received on the `fraud-sub` p2p protocol,
+//! the block it proves is malicious and any header built on top of it can't be trusted,
+//! so synchronization halts.
 
-use std::marker::PhantomData;
+use std::ops::RangeInclusive;
 use std::sync::Arc;
 use std::time::Duration;
 
 use backoff::backoff::Backoff;
 use backoff::ExponentialBackoffBuilder;
+use celestia_tendermint::Time;
+use celestia_types::fraud_proof::BadEncodingFraudProof;
 use celestia_types::hash::Hash;
-use celestia_types::ExtendedHeader;
-use futures::FutureExt;
+use celestia_types::{ErrorKind, ExtendedHeader, FraudProof};
+use futures::{Future, FutureExt};
+use instant::Instant;
 use serde::Serialize;
 use tokio::select;
 use tokio::sync::{mpsc, oneshot, watch};
 use tokio_util::sync::CancellationToken;
-use tracing::{debug, info, info_span, instrument, warn, Instrument};
+use tracing::{debug, error, info, info_span, instrument, warn, Instrument};
 
+use crate::clock::Clock;
+use crate::events::{EventPublisher, NodeEvent};
 use crate::executor::{sleep, spawn, spawn_cancellable, Interval};
+use crate::metrics;
 use crate::p2p::{P2p, P2pError};
 use crate::store::{Store, StoreError};
-use crate::utils::OneshotSenderExt;
+use crate::utils::{OneshotResultSenderExt, OneshotSenderExt};
 
 type Result<T, E = SyncerError> = std::result::Result<T, E>;
 
 const MAX_HEADERS_IN_BATCH: u64 = 512;
 const TRY_INIT_BACKOFF_MAX_INTERVAL: Duration = Duration::from_secs(60);
 
+/// Period during which the persisted network head is trusted to initialize the syncer,
+/// matching the trusting period of celestia-node.
+pub const TRUSTING_PERIOD: Duration = Duration::from_secs(14 * 24 * 60 * 60);
+
 /// Representation of all the errors that can occur when interacting with the [`Syncer`].
 #[derive(Debug, thiserror::Error)]
 pub enum SyncerError {
@@ -56,6 +80,63 @@ pub enum SyncerError {
     /// Channel has been closed unexpectedly.
     #[error("Channel closed unexpectedly")]
     ChannelClosedUnexpectedly,
+
+    /// The header at height 1 doesn't match the configured genesis hash.
+    #[error("Genesis hash mismatch, expected {expected}, found {found}")]
+    GenesisHashMismatch {
+        /// The configured genesis hash.
+        expected: Hash,
+        /// The hash of the header received from the network.
+        found: Hash,
+    },
+
+    /// The headers fetched for the historical syncing weren't stored, because
+    /// it was stopped in the meantime.
+    #[error("Historical syncing stopped, batch discarded")]
+    BatchDiscarded,
+
+    /// The header at the given height is never going to be synchronized, because it
+    /// was pruned or it is below the target height of the [`SyncMode::Backward`] mode.
+    #[error("Header at height {0} was pruned or is out of the synchronized range")]
+    HeightPruned(u64),
+}
+
+impl SyncerError {
+    /// Returns `true` if the operation which failed with this error may succeed
+    /// when tried again.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            SyncerError::P2p(e) => e.is_retryable(),
+            SyncerError::Store(e) => e.is_retryable(),
+            SyncerError::Celestia(e) => e.is_retryable(),
+            _ => false,
+        }
+    }
+
+    /// Get the [`ErrorKind`] of the error.
+    pub fn kind(&self) -> ErrorKind {
+        self.code_and_kind().1
+    }
+
+    /// Get the stable code of the error, see [`celestia_types::Error::code`].
+    ///
+    /// Errors of the [`Syncer`] use the codes from `4001` to `4999`.
+    pub fn code(&self) -> u16 {
+        self.code_and_kind().0
+    }
+
+    fn code_and_kind(&self) -> (u16, ErrorKind) {
+        match self {
+            SyncerError::P2p(e) => (e.code(), e.kind()),
+            SyncerError::Store(e) => (e.code(), e.kind()),
+            SyncerError::Celestia(e) => (e.code(), e.kind()),
+            SyncerError::WorkerDied => (4004, ErrorKind::Internal),
+            SyncerError::ChannelClosedUnexpectedly => (4005, ErrorKind::Internal),
+            SyncerError::GenesisHashMismatch { .. } => (4006, ErrorKind::Config),
+            SyncerError::BatchDiscarded => (4007, ErrorKind::Internal),
+            SyncerError::HeightPruned(_) => (4008, ErrorKind::Store),
+        }
+    }
 }
 
 impl From<oneshot::error::RecvError> for SyncerError {
@@ -72,7 +153,26 @@ where
 {
     cmd_tx: mpsc::Sender<SyncerCmd>,
     cancellation_token: CancellationToken,
-    _store: PhantomData<S>,
+    local_head_watcher: watch::Receiver<u64>,
+    stored_ranges_watcher: watch::Receiver<Vec<RangeInclusive<u64>>>,
+    store: Arc<S>,
+    sync_mode: SyncMode,
+}
+
+/// Direction in which the [`Syncer`] synchronizes the headers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SyncMode {
+    /// Synchronize from the genesis up to the subjective head.
+    #[default]
+    Forward,
+    /// Start from the subjective head and synchronize backwards, down to the target height.
+    ///
+    /// New headers above the local head are still synchronized and take the precedence
+    /// over the historical ones.
+    Backward {
+        /// The lowest height to synchronize, `1` for synchronizing down to the genesis.
+        target_height: u64,
+    },
 }
 
 /// Arguments used to configure the [`Syncer`].
@@ -86,12 +186,25 @@ where
     pub p2p: Arc<P2p<S>>,
     /// Headers storage.
     pub store: Arc<S>,
+    /// Direction of the synchronization.
+    pub sync_mode: SyncMode,
+    /// Number of peers that must agree on each synchronized batch of headers.
+    ///
+    /// Values lower than `2` disable the cross-checking.
+    pub header_quorum: usize,
+    /// Publisher of the [`NodeEvent`]s.
+    pub event_pub: EventPublisher,
+    /// Clock deciding whether the persisted network head is within the [`TRUSTING_PERIOD`].
+    pub clock: Arc<dyn Clock>,
 }
 
 #[derive(Debug)]
 enum SyncerCmd {
     GetInfo {
-        respond_to: oneshot::Sender<SyncingInfo>,
+        respond_to: oneshot::Sender<Result<SyncingInfo>>,
+    },
+    GetState {
+        respond_to: oneshot::Sender<Result<SyncState>>,
     },
 }
 
@@ -100,10 +213,43 @@ enum SyncerCmd {
 pub struct SyncingInfo {
     /// The height the [`Syncer`] is currently synchronized to.
     pub local_head: u64,
+    /// The lowest height synchronized to the store.
+    ///
+    /// In the [`SyncMode::Backward`] mode it shows the progress of the historical synchronization.
+    pub local_tail: u64,
     /// Syncing target. The latest height seen in the network that was successfully verified.
     pub subjective_head: u64,
 }
 
+/// Detailed state of the synchronization, the equivalent of `header.SyncState` of
+/// the Go node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SyncState {
+    /// Height of the latest header synchronized to the store.
+    pub head: u64,
+    /// Height the [`Syncer`] is synchronizing to. The latest height seen in the network
+    /// that was successfully verified, or `0` if it isn't known yet.
+    pub target: u64,
+    /// Ranges of the heights present in the store, in ascending order.
+    pub stored_ranges: Vec<RangeInclusive<u64>>,
+    /// Range of the heights being requested from the network right now.
+    pub ongoing_batch: Option<RangeInclusive<u64>>,
+}
+
+impl SyncState {
+    /// Returns `true` if the header at the given height is in the store.
+    pub fn is_stored(&self, height: u64) -> bool {
+        self.stored_ranges
+            .iter()
+            .any(|range| range.contains(&height))
+    }
+
+    /// Returns `true` if the store is synchronized up to the target height.
+    pub fn is_synced(&self) -> bool {
+        self.target != 0 && self.head >= self.target
+    }
+}
+
 impl<S> Syncer<S>
 where
     S: Store,
@@ -112,7 +258,17 @@ where
     pub fn start(args: SyncerArgs<S>) -> Result<Self> {
         let cancellation_token = CancellationToken::new();
         let (cmd_tx, cmd_rx) = mpsc::channel(16);
-        let mut worker = Worker::new(args, cancellation_token.child_token(), cmd_rx)?;
+        let (local_head_tx, local_head_rx) = watch::channel(0);
+        let (stored_ranges_tx, stored_ranges_rx) = watch::channel(Vec::new());
+        let store = args.store.clone();
+        let sync_mode = args.sync_mode;
+        let mut worker = Worker::new(
+            args,
+            cancellation_token.child_token(),
+            cmd_rx,
+            local_head_tx,
+            stored_ranges_tx,
+        )?;
 
         spawn(async move {
             worker.run().await;
@@ -121,7 +277,10 @@ where
         Ok(Syncer {
             cancellation_token,
             cmd_tx,
-            _store: PhantomData,
+            local_head_watcher: local_head_rx,
+            stored_ranges_watcher: stored_ranges_rx,
+            store,
+            sync_mode,
         })
     }
 
@@ -139,18 +298,101 @@ where
             .map_err(|_| SyncerError::WorkerDied)
     }
 
+    /// Watcher for the height of the latest header synchronized to the store.
+    ///
+    /// It is `0` until the [`Syncer`] inspects the store for the first time.
+    pub fn local_head_watcher(&self) -> watch::Receiver<u64> {
+        self.local_head_watcher.clone()
+    }
+
+    /// Watcher for the ranges of the headers present in the store.
+    ///
+    /// It is updated whenever the headers are synchronized, both above the head and below
+    /// the tail.
+    pub fn stored_ranges_watcher(&self) -> watch::Receiver<Vec<RangeInclusive<u64>>> {
+        self.stored_ranges_watcher.clone()
+    }
+
     /// Get the current synchronization status.
     ///
     /// # Errors
     ///
-    /// This function will return an error if the [`Syncer`] has been stopped.
+    /// This function will return an error if the [`Syncer`] has been stopped, or
+    /// halted because the genesis header didn't match the genesis hash.
     pub async fn info(&self) -> Result<SyncingInfo> {
         let (tx, rx) = oneshot::channel();
 
         self.send_command(SyncerCmd::GetInfo { respond_to: tx })
             .await?;
 
-        Ok(rx.await?)
+        rx.await?
+    }
+
+    /// Get the detailed state of the synchronization.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the [`Syncer`] has been stopped, or
+    /// halted because the genesis header didn't match the genesis hash.
+    pub async fn state(&self) -> Result<SyncState> {
+        let (tx, rx) = oneshot::channel();
+
+        self.send_command(SyncerCmd::GetState { respond_to: tx })
+            .await?;
+
+        rx.await?
+    }
+
+    /// Wait until the header at the given height is synchronized to the store.
+    ///
+    /// Returns immediately if the header is already stored. In the [`SyncMode::Backward`]
+    /// mode this also waits for the historical headers.
+    ///
+    /// # Errors
+    ///
+    /// This function will return [`SyncerError::HeightPruned`] if the header is below
+    /// the tail of the store in the [`SyncMode::Forward`] mode, i.e. it was removed by
+    /// the pruner, or below the target height in the [`SyncMode::Backward`] mode, as
+    /// such headers are never synchronized. It will also return an error if the
+    /// [`Syncer`] stops before the header is synchronized.
+    pub async fn wait_synced(&self, height: u64) -> Result<()> {
+        if self.is_out_of_synced_range(height).await {
+            return Err(SyncerError::HeightPruned(height));
+        }
+
+        let mut stored_ranges = self.stored_ranges_watcher.clone();
+
+        let ranges = stored_ranges
+            .wait_for(|ranges| {
+                let stored = ranges.iter().any(|range| range.contains(&height));
+                // Headers are synchronized only above the tail in the forward mode,
+                // so the header was pruned while waiting.
+                let pruned = self.sync_mode == SyncMode::Forward
+                    && ranges.first().is_some_and(|range| height < *range.start());
+
+                stored || pruned
+            })
+            .await
+            .map_err(|_| SyncerError::WorkerDied)?;
+
+        if ranges.iter().any(|range| range.contains(&height)) {
+            Ok(())
+        } else {
+            Err(SyncerError::HeightPruned(height))
+        }
+    }
+
+    /// Returns `true` if the header at the given height is never going to be synchronized.
+    async fn is_out_of_synced_range(&self, height: u64) -> bool {
+        match self.sync_mode {
+            // Store always starts from the genesis, so only the pruner moves the tail
+            SyncMode::Forward => self
+                .store
+                .tail_height()
+                .await
+                .is_ok_and(|tail| height < tail),
+            SyncMode::Backward { target_height } => height < target_height.max(1),
+        }
     }
 }
 
@@ -172,16 +414,27 @@ where
     p2p: Arc<P2p<S>>,
     store: Arc<S>,
     header_sub_watcher: watch::Receiver<Option<ExtendedHeader>>,
+    fraud_proof_watcher: watch::Receiver<Option<BadEncodingFraudProof>>,
+    local_head_tx: watch::Sender<u64>,
+    stored_ranges_tx: watch::Sender<Vec<RangeInclusive<u64>>>,
     genesis_hash: Option<Hash>,
+    // Hash of the genesis header received from the network, if it didn't match
+    genesis_mismatch: Option<Hash>,
+    sync_mode: SyncMode,
+    header_quorum: usize,
     subjective_head_height: Option<u64>,
     headers_tx: mpsc::Sender<Result<Vec<ExtendedHeader>, P2pError>>,
     headers_rx: mpsc::Receiver<Result<Vec<ExtendedHeader>, P2pError>>,
     ongoing_batch: Option<Ongoing>,
+    event_pub: EventPublisher,
+    clock: Arc<dyn Clock>,
 }
 
 struct Ongoing {
     start: u64,
     end: u64,
+    backward: bool,
+    started: Instant,
     cancellation_token: CancellationToken,
 }
 
@@ -193,8 +446,11 @@ where
         args: SyncerArgs<S>,
         cancellation_token: CancellationToken,
         cmd_rx: mpsc::Receiver<SyncerCmd>,
+        local_head_tx: watch::Sender<u64>,
+        stored_ranges_tx: watch::Sender<Vec<RangeInclusive<u64>>>,
     ) -> Result<Self> {
         let header_sub_watcher = args.p2p.header_sub_watcher();
+        let fraud_proof_watcher = args.p2p.fraud_proof_watcher();
         let (headers_tx, headers_rx) = mpsc::channel(1);
 
         Ok(Worker {
@@ -203,29 +459,43 @@ where
             p2p: args.p2p,
             store: args.store,
             header_sub_watcher,
+            fraud_proof_watcher,
+            local_head_tx,
+            stored_ranges_tx,
             genesis_hash: args.genesis_hash,
+            genesis_mismatch: None,
+            sync_mode: args.sync_mode,
+            header_quorum: args.header_quorum,
             subjective_head_height: None,
             headers_tx,
             headers_rx,
             ongoing_batch: None,
+            event_pub: args.event_pub,
+            clock: args.clock,
         })
     }
 
     async fn run(&mut self) {
+        self.update_stored_range().await;
+
         loop {
-            if self.cancellation_token.is_cancelled() {
+            if self.cancellation_token.is_cancelled() || self.is_halted() {
                 break;
             }
 
             self.connecting_event_loop().await;
 
-            if self.cancellation_token.is_cancelled() {
+            if self.cancellation_token.is_cancelled() || self.is_halted() {
                 break;
             }
 
             self.connected_event_loop().await;
         }
 
+        if self.is_halted() {
+            self.halted_event_loop().await;
+        }
+
         debug!("Syncer stopped");
     }
 
@@ -247,9 +517,16 @@ where
                 _ = report_interval.tick() => {
                     self.report().await;
                 }
+                Ok(()) = self.fraud_proof_watcher.changed() => {
+                    if self.is_halted() {
+                        break;
+                    }
+                }
                 Ok(network_head_height) = &mut try_init_result => {
                     info!("Setting initial subjective head to {network_head_height}");
                     self.subjective_head_height = Some(network_head_height);
+                    // Genesis may have been stored during the initialization
+                    self.update_stored_range().await;
                     break;
                 }
                 Some(cmd) = self.cmd_rx.recv() => {
@@ -286,10 +563,17 @@ where
                         warn!("All peers disconnected");
                         break;
                     }
+                    // Quorum may have been reached with the new peers
+                    self.fetch_next_batch().await;
                 }
                 _ = report_interval.tick() => {
                     self.report().await;
                 }
+                Ok(()) = self.fraud_proof_watcher.changed() => {
+                    if self.is_halted() {
+                        break;
+                    }
+                }
                 _ = self.header_sub_watcher.changed() => {
                     self.on_header_sub_message().await;
                     self.fetch_next_batch().await;
@@ -299,6 +583,9 @@ where
                 }
                 Some(res) = self.headers_rx.recv() => {
                     self.on_fetch_next_batch_result(res).await;
+                    if self.is_halted() {
+                        break;
+                    }
                     self.fetch_next_batch().await;
                 }
             }
@@ -313,9 +600,48 @@ where
         }
     }
 
+    /// The responsibility of this event loop is to keep accepting commands
+    /// after a fraud was proven or the genesis didn't match, without synchronizing
+    /// any more headers.
+    async fn halted_event_loop(&mut self) {
+        if let Some(proof) = self.fraud_proof_watcher.borrow().as_ref() {
+            error!(
+                "Block {} was proven to be incorrectly encoded. Syncing halted.",
+                proof.height()
+            );
+        }
+
+        if let Some(err) = self.genesis_mismatch_error() {
+            error!("{err}. Syncing halted.");
+        }
+
+        loop {
+            select! {
+                _ = self.cancellation_token.cancelled() => {
+                    break;
+                }
+                Some(cmd) = self.cmd_rx.recv() => {
+                    self.on_cmd(cmd).await;
+                }
+            }
+        }
+    }
+
+    fn is_halted(&self) -> bool {
+        self.fraud_proof_watcher.borrow().is_some() || self.genesis_mismatch.is_some()
+    }
+
+    fn genesis_mismatch_error(&self) -> Option<SyncerError> {
+        Some(SyncerError::GenesisHashMismatch {
+            expected: self.genesis_hash?,
+            found: self.genesis_mismatch?,
+        })
+    }
+
     async fn syncing_info(&self) -> SyncingInfo {
         SyncingInfo {
             local_head: self.store.head_height().await.unwrap_or(0),
+            local_tail: self.store.tail_height().await.unwrap_or(0),
             subjective_head: self.subjective_head_height.unwrap_or(0),
         }
     }
@@ -324,6 +650,7 @@ where
     async fn report(&mut self) {
         let SyncingInfo {
             local_head,
+            local_tail,
             subjective_head,
         } = self.syncing_info().await;
 
@@ -333,13 +660,22 @@ where
             .map(|ongoing| format!("[{}, {}]", ongoing.start, ongoing.end))
             .unwrap_or_else(|| "None".to_string());
 
-        info!("syncing: {local_head}/{subjective_head}, ongoing batch: {ongoing_batch}",);
+        match self.sync_mode {
+            SyncMode::Forward => {
+                info!("syncing: {local_head}/{subjective_head}, ongoing batch: {ongoing_batch}")
+            }
+            SyncMode::Backward { target_height } => info!(
+                "syncing: {local_head}/{subjective_head}, historical: {local_tail}/{target_height}, ongoing batch: {ongoing_batch}"
+            ),
+        }
     }
 
     fn spawn_try_init(&self) -> oneshot::Receiver<u64> {
         let p2p = self.p2p.clone();
         let store = self.store.clone();
         let genesis_hash = self.genesis_hash;
+        let sync_mode = self.sync_mode;
+        let clock = self.clock.clone();
         let (tx, rx) = oneshot::channel();
 
         let fut = async move {
@@ -349,7 +685,7 @@ where
                 .build();
 
             loop {
-                match try_init(&p2p, &store, genesis_hash).await {
+                match try_init(&p2p, &store, genesis_hash, sync_mode, &*clock).await {
                     Ok(network_height) => {
                         tx.maybe_send(network_height);
                         break;
@@ -377,8 +713,28 @@ where
     async fn on_cmd(&mut self, cmd: SyncerCmd) {
         match cmd {
             SyncerCmd::GetInfo { respond_to } => {
+                if let Some(err) = self.genesis_mismatch_error() {
+                    respond_to.maybe_send_err(err);
+                    return;
+                }
                 let info = self.syncing_info().await;
-                respond_to.maybe_send(info);
+                respond_to.maybe_send_ok(info);
+            }
+            SyncerCmd::GetState { respond_to } => {
+                if let Some(err) = self.genesis_mismatch_error() {
+                    respond_to.maybe_send_err(err);
+                    return;
+                }
+                let state = SyncState {
+                    head: self.store.head_height().await.unwrap_or(0),
+                    target: self.subjective_head_height.unwrap_or(0),
+                    stored_ranges: stored_ranges(&*self.store).await,
+                    ongoing_batch: self
+                        .ongoing_batch
+                        .as_ref()
+                        .map(|ongoing| ongoing.start..=ongoing.end),
+                };
+                respond_to.maybe_send_ok(state);
             }
         }
     }
@@ -398,14 +754,31 @@ where
 
         let new_head_height = new_head.height().value();
 
-        // We don't want to interfere with any ongoing batch fetching
-        if self.ongoing_batch.is_none() {
+        if let Err(e) = self.store.set_network_head(new_head.clone()).await {
+            warn!("Failed to persist network head {new_head_height}: {e}");
+        }
+
+        // We don't want to interfere with any ongoing batch fetching above the head
+        if !matches!(
+            self.ongoing_batch,
+            Some(Ongoing {
+                backward: false,
+                ..
+            })
+        ) {
             if let Ok(store_head_height) = self.store.head_height().await {
                 // If our new header is adjacent to the HEAD of the store
                 if store_head_height + 1 == new_head_height {
                     // Header is already verified by HeaderSub
+                    let started = Instant::now();
                     if self.store.append_single_unchecked(new_head).await.is_ok() {
+                        metrics::record_duration(metrics::STORE_WRITE_DURATION, started);
+                        metrics::increment(metrics::HEADERS_SYNCED, 1);
                         info!("Added header {new_head_height} from HeaderSub");
+                        self.event_pub.send(NodeEvent::AddedHeaderFromHeaderSub {
+                            height: new_head_height,
+                        });
+                        self.update_stored_range().await;
                     }
                 }
             }
@@ -439,33 +812,109 @@ where
             .saturating_sub(local_head.height().value())
             .min(MAX_HEADERS_IN_BATCH);
 
+        let quorum = self.header_quorum;
+
+        if amount > 0 {
+            let start = local_head.height().value() + 1;
+            self.spawn_batch(start, amount, false, move |p2p| async move {
+                let headers = p2p.get_verified_headers_range(&local_head, amount).await?;
+
+                // The range is verified against the local head, so cross-checking
+                // the last header covers the whole batch.
+                if let Some(last) = headers.last().filter(|_| quorum > 1) {
+                    p2p.cross_check_header(last, quorum).await?;
+                }
+
+                Ok(headers)
+            });
+            return;
+        }
+
+        let SyncMode::Backward { target_height } = self.sync_mode else {
+            // Nothing to schedule
+            return;
+        };
+
+        let Ok(local_tail) = self.store.tail_height().await else {
+            // Nothing to schedule
+            return;
+        };
+
+        // Genesis is the lowest possible height
+        let amount = local_tail
+            .saturating_sub(target_height.max(1))
+            .min(MAX_HEADERS_IN_BATCH);
+
         if amount == 0 {
             // Nothing to schedule
             return;
         }
 
-        if self.p2p.peer_tracker_info().num_connected_peers == 0 {
+        let Ok(local_tail) = self.store.get_by_height(local_tail).await else {
+            // Nothing to schedule
+            return;
+        };
+
+        let start = local_tail.height().value() - amount;
+        self.spawn_batch(start, amount, true, move |p2p| async move {
+            let headers = p2p
+                .get_verified_headers_range_backward(&local_tail, amount)
+                .await?;
+
+            // The range is verified against the local tail, so cross-checking
+            // the first header covers the whole batch.
+            if let Some(first) = headers.first().filter(|_| quorum > 1) {
+                p2p.cross_check_header(first, quorum).await?;
+            }
+
+            Ok(headers)
+        });
+    }
+
+    fn spawn_batch<F, Fut>(&mut self, start: u64, amount: u64, backward: bool, fetch: F)
+    where
+        F: FnOnce(Arc<P2p<S>>) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<Vec<ExtendedHeader>, P2pError>> + Send,
+    {
+        let num_connected_peers = self.p2p.peer_tracker_info().num_connected_peers;
+
+        if num_connected_peers == 0 {
             // No connected peers. We can't do the request.
             // This will be recovered by `run`.
             return;
         }
 
-        let start = local_head.height().value() + 1;
+        if num_connected_peers < self.header_quorum as u64 {
+            // Not enough peers to cross-check the batch. This will be
+            // retried when more peers connect.
+            debug!(
+                "Waiting for {} peers to fetch the next batch",
+                self.header_quorum
+            );
+            return;
+        }
+
         let end = start + amount - 1;
         let cancellation_token = self.cancellation_token.child_token();
 
         self.ongoing_batch = Some(Ongoing {
             start,
             end,
+            backward,
+            started: Instant::now(),
             cancellation_token: cancellation_token.clone(),
         });
         info!("Fetching batch {start} until {end}");
+        self.event_pub.send(NodeEvent::FetchingHeadersStarted {
+            from_height: start,
+            to_height: end,
+        });
 
         let tx = self.headers_tx.clone();
         let p2p = self.p2p.clone();
 
         spawn_cancellable(cancellation_token, async move {
-            let res = p2p.get_verified_headers_range(&local_head, amount).await;
+            let res = fetch(p2p).await;
             let _ = tx.send(res).await;
         });
     }
@@ -480,30 +929,163 @@ where
         let start = ongoing.start;
         let end = ongoing.end;
 
-        let headers = match res {
-            Ok(headers) => headers,
+        let res = match res {
+            Ok(headers) if ongoing.backward => self.store_backward_batch(start, headers).await,
+            Ok(headers) => {
+                // Headers are already verified by `get_verified_headers_range`,
+                // so `append_unchecked` is used for optimization.
+                let started = Instant::now();
+                let res = self.store.append_unchecked(headers).await;
+                metrics::record_duration(metrics::STORE_WRITE_DURATION, started);
+
+                self.update_stored_range().await;
+
+                res.map_err(SyncerError::from)
+            }
+            Err(e) => Err(e.into()),
+        };
+
+        let took = ongoing.started.elapsed();
+
+        match res {
+            Ok(()) => {
+                metrics::increment(metrics::HEADERS_SYNCED, end - start + 1);
+                metrics::record_duration(metrics::HEADER_BATCH_DURATION, ongoing.started);
+                self.event_pub.send(NodeEvent::FetchingHeadersFinished {
+                    from_height: start,
+                    to_height: end,
+                    took,
+                });
+            }
             Err(e) => {
-                warn!("Failed to receive batch {start} until {end}: {e}");
-                return;
+                metrics::increment(metrics::HEADER_BATCH_FAILURES, 1);
+                warn!("Failed to sync batch {start} until {end}: {e}");
+                self.event_pub.send(NodeEvent::FetchingHeadersFailed {
+                    from_height: start,
+                    to_height: end,
+                    error: e.to_string(),
+                    took,
+                });
             }
+        }
+    }
+
+    async fn store_backward_batch(
+        &mut self,
+        start: u64,
+        headers: Vec<ExtendedHeader>,
+    ) -> Result<()> {
+        let SyncMode::Backward { target_height } = self.sync_mode else {
+            return Err(SyncerError::BatchDiscarded);
         };
 
-        // Headers are already verified by `get_verified_headers_range`,
-        // so `append_unchecked` is used for optimization.
-        if let Err(e) = self.store.append_unchecked(headers).await {
-            warn!("Failed to store batch {start} until {end}: {e}");
+        // The range is verified only against the subjective head, so the genesis
+        // needs to be checked explicitly.
+        if let (Some(expected), Some(first)) = (self.genesis_hash, headers.first()) {
+            if first.height().value() == 1 && first.hash() != expected {
+                // Syncing is halted, as the node is configured for another network
+                self.genesis_mismatch = Some(first.hash());
+                return Err(SyncerError::GenesisHashMismatch {
+                    expected,
+                    found: first.hash(),
+                });
+            }
+        }
+
+        // Headers are already verified by `get_verified_headers_range_backward`,
+        // so `prepend_unchecked` is used for optimization.
+        let started = Instant::now();
+        self.store.prepend_unchecked(headers).await?;
+        metrics::record_duration(metrics::STORE_WRITE_DURATION, started);
+        self.update_stored_range().await;
+
+        if start <= target_height.max(1) {
+            info!("Historical syncing reached target height {target_height}");
+        }
+
+        Ok(())
+    }
+
+    async fn update_stored_range(&mut self) {
+        if let Ok(height) = self.store.head_height().await {
+            metrics::set(metrics::LOCAL_HEAD_HEIGHT, height);
+            self.local_head_tx.send_if_modified(|local_head| {
+                let modified = *local_head != height;
+                *local_head = height;
+                modified
+            });
         }
+
+        let ranges = stored_ranges(&*self.store).await;
+        self.stored_ranges_tx.send_if_modified(|stored| {
+            let modified = *stored != ranges;
+            *stored = ranges;
+            modified
+        });
+    }
+}
+
+/// Ranges of the heights present in the store.
+///
+/// Headers are always stored in a single contiguous range, from the tail to the head.
+async fn stored_ranges<S>(store: &S) -> Vec<RangeInclusive<u64>>
+where
+    S: Store,
+{
+    match (store.tail_height().await, store.head_height().await) {
+        (Ok(tail), Ok(head)) => vec![tail..=head],
+        _ => Vec::new(),
     }
 }
 
-async fn try_init<S>(p2p: &P2p<S>, store: &S, genesis_hash: Option<Hash>) -> Result<u64>
+async fn try_init<S>(
+    p2p: &P2p<S>,
+    store: &S,
+    genesis_hash: Option<Hash>,
+    sync_mode: SyncMode,
+    clock: &dyn Clock,
+) -> Result<u64>
 where
     S: Store,
 {
+    let is_empty = store.head_height().await.is_err();
+
+    if !is_empty {
+        match store.get_network_head().await? {
+            Some(network_head) if is_within_trusting_period(&network_head, clock.now()) => {
+                let network_head_height = network_head.height().value();
+                info!("Initializing from the persisted network head {network_head_height}");
+
+                p2p.init_header_sub(network_head).await?;
+
+                return Ok(network_head_height);
+            }
+            Some(network_head) => {
+                warn!(
+                    "Trust in the persisted network head {} expired, re-initializing from the trusted peers",
+                    network_head.height()
+                );
+            }
+            None => {}
+        }
+    }
+
     p2p.wait_connected_trusted().await?;
 
+    if is_empty && matches!(sync_mode, SyncMode::Backward { .. }) {
+        // Historical syncing starts from the network head
+        let network_head = p2p.get_head_header().await?;
+        let network_head_height = network_head.height().value();
+
+        store.prepend_single_unchecked(network_head.clone()).await?;
+        store.set_network_head(network_head.clone()).await?;
+        p2p.init_header_sub(network_head).await?;
+
+        return Ok(network_head_height);
+    }
+
     // IF store is empty, intialize it with genesis
-    if store.head_height().await.is_err() {
+    if is_empty {
         let genesis = match genesis_hash {
             Some(hash) => p2p.get_header(hash).await?,
             None => {
@@ -519,19 +1101,30 @@ where
     let network_head = p2p.get_head_header().await?;
     let network_head_height = network_head.height().value();
 
+    store.set_network_head(network_head.clone()).await?;
     p2p.init_header_sub(network_head).await?;
 
     Ok(network_head_height)
 }
 
+fn is_within_trusting_period(header: &ExtendedHeader, now: Time) -> bool {
+    header
+        .time()
+        .checked_add(TRUSTING_PERIOD)
+        .is_some_and(|expires| expires > now)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
-        executor::sleep,
+        clock::SystemClock,
+        events::EventChannel,
+        executor::{sleep, timeout},
         store::InMemoryStore,
-        test_utils::{gen_filled_store, MockP2pHandle},
+        test_utils::{gen_filled_store, MockClock, MockP2pHandle},
     };
+    use celestia_types::fraud_proof::Proof;
     use celestia_types::test_utils::ExtendedHeaderGenerator;
     use std::time::Duration;
 
@@ -550,6 +1143,10 @@ mod tests {
             genesis_hash: None,
             p2p: Arc::new(mock),
             store: Arc::new(InMemoryStore::new()),
+            sync_mode: SyncMode::Forward,
+            header_quorum: 1,
+            event_pub: EventChannel::new().publisher(),
+            clock: Arc::new(SystemClock),
         })
         .unwrap();
 
@@ -591,6 +1188,92 @@ mod tests {
         p2p_mock.expect_no_cmd().await;
     }
 
+    #[async_test]
+    async fn sync_state_and_wait_synced() {
+        let mut gen = ExtendedHeaderGenerator::new();
+        let genesis = gen.next();
+        let headers_2_26 = gen.next_many(25);
+
+        let (syncer, _store, mut p2p_mock) =
+            initialized_syncer(genesis.clone(), headers_2_26[24].clone()).await;
+
+        let (_, _, respond_to) = p2p_mock.expect_header_request_for_height_cmd().await;
+
+        let state = syncer.state().await.unwrap();
+        assert_eq!(state.head, 1);
+        assert_eq!(state.target, 26);
+        assert_eq!(state.stored_ranges, vec![1..=1]);
+        assert_eq!(state.ongoing_batch, Some(2..=26));
+        assert!(state.is_stored(1));
+        assert!(!state.is_stored(2));
+        assert!(!state.is_synced());
+
+        syncer.wait_synced(1).await.unwrap();
+        timeout(Duration::from_millis(10), syncer.wait_synced(26))
+            .await
+            .unwrap_err();
+
+        respond_to.send(Ok(headers_2_26)).unwrap();
+
+        timeout(Duration::from_secs(1), syncer.wait_synced(26))
+            .await
+            .unwrap()
+            .unwrap();
+
+        let state = syncer.state().await.unwrap();
+        assert_eq!(state.head, 26);
+        assert_eq!(state.stored_ranges, vec![1..=26]);
+        assert_eq!(state.ongoing_batch, None);
+        assert!(state.is_synced());
+
+        syncer.stop();
+        sleep(Duration::from_millis(1)).await;
+        syncer.wait_synced(27).await.unwrap_err();
+    }
+
+    #[async_test]
+    async fn wait_synced_for_pruned_height() {
+        let mut gen = ExtendedHeaderGenerator::new();
+        let genesis = gen.next();
+        let headers_2_26 = gen.next_many(25);
+
+        let (syncer, store, mut p2p_mock) =
+            initialized_syncer(genesis.clone(), headers_2_26[24].clone()).await;
+
+        let (_, _, respond_to) = p2p_mock.expect_header_request_for_height_cmd().await;
+        respond_to.send(Ok(headers_2_26)).unwrap();
+        syncer.wait_synced(26).await.unwrap();
+
+        store.remove_tail().await.unwrap();
+        store.remove_tail().await.unwrap();
+
+        let err = timeout(Duration::from_millis(100), syncer.wait_synced(2))
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert!(matches!(err, SyncerError::HeightPruned(2)));
+        syncer.wait_synced(3).await.unwrap();
+    }
+
+    #[async_test]
+    async fn wait_synced_below_backward_target() {
+        let mut gen = ExtendedHeaderGenerator::new();
+        let headers = gen.next_many(30);
+
+        let (syncer, _store, _p2p_mock) = backward_syncer(None, headers[29].clone(), 20).await;
+
+        let err = timeout(Duration::from_millis(100), syncer.wait_synced(10))
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert!(matches!(err, SyncerError::HeightPruned(10)));
+
+        // Heights above the target are still synchronized backwards
+        timeout(Duration::from_millis(10), syncer.wait_synced(20))
+            .await
+            .unwrap_err();
+    }
+
     #[async_test]
     async fn syncing() {
         let mut gen = ExtendedHeaderGenerator::new();
@@ -712,6 +1395,10 @@ mod tests {
             genesis_hash: Some(genesis.hash()),
             p2p: Arc::new(p2p),
             store: store.clone(),
+            sync_mode: SyncMode::Forward,
+            header_quorum: 1,
+            event_pub: EventChannel::new().publisher(),
+            clock: Arc::new(SystemClock),
         })
         .unwrap();
 
@@ -827,6 +1514,269 @@ mod tests {
         p2p_mock.expect_no_cmd().await;
     }
 
+    #[async_test]
+    async fn fraud_proof_halts_syncing() {
+        let mut gen = ExtendedHeaderGenerator::new();
+        let genesis = gen.next();
+        let headers_2_26 = gen.next_many(25);
+
+        let (syncer, store, mut p2p_mock) =
+            initialized_syncer(genesis.clone(), headers_2_26[24].clone()).await;
+
+        // Wait for the request but do not reply to it
+        let (height, amount, respond_to) = p2p_mock.expect_header_request_for_height_cmd().await;
+        assert_eq!(height, 2);
+        assert_eq!(amount, 25);
+
+        let befp_json =
+            include_str!("../../types/test_data/fraud/honest_bad_encoding_fraud_proof.json");
+        let Proof::BadEncoding(proof) = serde_json::from_str(befp_json).unwrap() else {
+            unreachable!()
+        };
+        p2p_mock.announce_fraud_proof(proof);
+        sleep(Duration::from_millis(1)).await;
+
+        // Ongoing batch is cancelled and nothing is appended anymore
+        let _ = respond_to.send(Ok(headers_2_26));
+        p2p_mock.announce_new_head(gen.next());
+        p2p_mock.expect_no_cmd().await;
+
+        // Syncer still answers the commands
+        let info = syncer.info().await.unwrap();
+        assert_eq!(info.local_head, 1);
+        assert_eq!(store.head_height().await.unwrap(), 1);
+    }
+
+    #[async_test]
+    async fn backward_syncing() {
+        let mut gen = ExtendedHeaderGenerator::new();
+        let genesis = gen.next();
+        let mut headers = gen.next_many(599);
+        let network_head = headers.pop().unwrap();
+
+        let (syncer, store, mut p2p_mock) =
+            backward_syncer(Some(genesis.hash()), network_head.clone(), 50).await;
+
+        // Store starts from the network head
+        assert_syncing(&syncer, &store, 600, 600).await;
+        assert_eq!(syncer.info().await.unwrap().local_tail, 600);
+
+        // Syncer requested the first historical batch ([88, 599])
+        let mut batch = headers.split_off(86);
+        handle_session_batch(
+            &mut p2p_mock,
+            &mut batch,
+            vec![
+                (88, 64),
+                (152, 64),
+                (216, 64),
+                (280, 64),
+                (344, 64),
+                (408, 64),
+                (472, 64),
+                (536, 64),
+            ],
+        )
+        .await;
+        assert_syncing(&syncer, &store, 600, 600).await;
+        assert_eq!(syncer.info().await.unwrap().local_tail, 88);
+
+        // New HEAD is appended while syncing backwards
+        let header601 = gen.next();
+        p2p_mock.announce_new_head(header601.clone());
+
+        // Syncer requested the last historical batch ([50, 87])
+        let (height, amount, respond_to) = p2p_mock.expect_header_request_for_height_cmd().await;
+        assert_eq!(height, 50);
+        assert_eq!(amount, 38);
+        let headers_50_87 = headers.split_off(48);
+        respond_to
+            .send(Ok(headers_50_87.clone()))
+            .map_err(|_| "headers [50, 87]")
+            .unwrap();
+        assert_syncing(&syncer, &store, 601, 601).await;
+        assert_eq!(syncer.info().await.unwrap().local_tail, 50);
+        assert_eq!(store.get_by_height(50).await.unwrap(), headers_50_87[0]);
+
+        // Target height was reached
+        p2p_mock.expect_no_cmd().await;
+    }
+
+    #[async_test]
+    async fn backward_syncing_genesis_mismatch() {
+        let mut gen = ExtendedHeaderGenerator::new();
+        let headers = gen.next_many(10);
+
+        let (syncer, store, mut p2p_mock) =
+            backward_syncer(Some(Hash::Sha256([0xff; 32])), headers[9].clone(), 1).await;
+
+        // Syncer requested the historical batch ([1, 9])
+        let (height, amount, respond_to) = p2p_mock.expect_header_request_for_height_cmd().await;
+        assert_eq!(height, 1);
+        assert_eq!(amount, 9);
+        respond_to
+            .send(Ok(headers[..9].to_vec()))
+            .map_err(|_| "headers [1, 9]")
+            .unwrap();
+
+        // Genesis doesn't match, so the batch is discarded and syncing is halted
+        p2p_mock.expect_no_cmd().await;
+        assert!(matches!(
+            syncer.info().await.unwrap_err(),
+            SyncerError::GenesisHashMismatch { found, .. } if found == headers[0].hash()
+        ));
+        assert!(matches!(
+            syncer.state().await.unwrap_err(),
+            SyncerError::GenesisHashMismatch { .. }
+        ));
+        assert_eq!(store.head_height().await.unwrap(), 10);
+        assert_eq!(store.tail_height().await.unwrap(), 10);
+    }
+
+    #[async_test]
+    async fn syncing_events() {
+        let mut gen = ExtendedHeaderGenerator::new();
+        let genesis = gen.next();
+        let headers_2_26 = gen.next_many(25);
+
+        let events = EventChannel::new();
+        let mut event_sub = events.subscribe();
+        let (syncer, store, mut p2p_mock) = initialized_syncer_with_events(
+            genesis.clone(),
+            headers_2_26[24].clone(),
+            events.publisher(),
+        )
+        .await;
+
+        let (height, amount, respond_to) = p2p_mock.expect_header_request_for_height_cmd().await;
+        assert_eq!((height, amount), (2, 25));
+        assert_eq!(
+            event_sub.try_recv().unwrap().event,
+            NodeEvent::FetchingHeadersStarted {
+                from_height: 2,
+                to_height: 26
+            }
+        );
+
+        respond_to
+            .send(Ok(headers_2_26))
+            .map_err(|_| "headers [2, 26]")
+            .unwrap();
+        assert_syncing(&syncer, &store, 26, 26).await;
+        assert!(matches!(
+            event_sub.try_recv().unwrap().event,
+            NodeEvent::FetchingHeadersFinished {
+                from_height: 2,
+                to_height: 26,
+                ..
+            }
+        ));
+
+        p2p_mock.announce_new_head(gen.next());
+        assert_syncing(&syncer, &store, 27, 27).await;
+        assert_eq!(
+            event_sub.try_recv().unwrap().event,
+            NodeEvent::AddedHeaderFromHeaderSub { height: 27 }
+        );
+
+        // Invalid range is reported as a failure
+        let headers_28_30 = gen.next_many(3);
+        p2p_mock.announce_new_head(headers_28_30[2].clone());
+        let (_, _, respond_to) = p2p_mock.expect_header_request_for_height_cmd().await;
+        let forged_28_30 = ExtendedHeaderGenerator::new_from_height(28).next_many(3);
+        respond_to
+            .send(Ok(forged_28_30))
+            .map_err(|_| "headers [28, 30]")
+            .unwrap();
+        assert_syncing(&syncer, &store, 27, 30).await;
+        assert!(matches!(
+            event_sub.try_recv().unwrap().event,
+            NodeEvent::FetchingHeadersStarted {
+                from_height: 28,
+                to_height: 30
+            }
+        ));
+        assert!(matches!(
+            event_sub.try_recv().unwrap().event,
+            NodeEvent::FetchingHeadersFailed {
+                from_height: 28,
+                to_height: 30,
+                ..
+            }
+        ));
+    }
+
+    #[async_test]
+    async fn quorum_syncing() {
+        let mut gen = ExtendedHeaderGenerator::new();
+        let genesis = gen.next();
+        let headers_2_26 = gen.next_many(25);
+
+        let (mock, mut p2p_mock) = P2p::mocked();
+        let store = Arc::new(InMemoryStore::new());
+
+        let syncer = Syncer::start(SyncerArgs {
+            genesis_hash: Some(genesis.hash()),
+            p2p: Arc::new(mock),
+            store: store.clone(),
+            sync_mode: SyncMode::Forward,
+            header_quorum: 2,
+            event_pub: EventChannel::new().publisher(),
+            clock: Arc::new(SystemClock),
+        })
+        .unwrap();
+
+        p2p_mock.announce_trusted_peer_connected();
+
+        let (_, respond_to) = p2p_mock.expect_header_request_for_hash_cmd().await;
+        respond_to.send(Ok(vec![genesis])).unwrap();
+        let (_, _, respond_to) = p2p_mock.expect_header_request_for_height_cmd().await;
+        respond_to.send(Ok(vec![headers_2_26[24].clone()])).unwrap();
+        p2p_mock.expect_init_header_sub().await;
+
+        // Batch is not fetched until there are enough peers for the quorum
+        p2p_mock.expect_no_cmd().await;
+        assert_syncing(&syncer, &store, 1, 26).await;
+
+        p2p_mock.announce_peer_connected();
+
+        let (height, amount, respond_to) = p2p_mock.expect_header_request_for_height_cmd().await;
+        assert_eq!((height, amount), (2, 25));
+        respond_to
+            .send(Ok(headers_2_26.clone()))
+            .map_err(|_| "headers [2, 26]")
+            .unwrap();
+
+        // Last header of the batch is cross-checked with the quorum
+        let (height, amount, quorum, respond_to) =
+            p2p_mock.expect_header_quorum_request_for_height_cmd().await;
+        assert_eq!((height, amount, quorum), (26, 1, 2));
+        respond_to.send(Ok(vec![headers_2_26[24].clone()])).unwrap();
+        assert_syncing(&syncer, &store, 26, 26).await;
+
+        // Batch is rejected if the quorum responded with a different header
+        let headers_27_30 = gen.next_many(4);
+        p2p_mock.announce_new_head(headers_27_30[3].clone());
+
+        let (height, amount, respond_to) = p2p_mock.expect_header_request_for_height_cmd().await;
+        assert_eq!((height, amount), (27, 4));
+        respond_to
+            .send(Ok(headers_27_30.clone()))
+            .map_err(|_| "headers [27, 30]")
+            .unwrap();
+
+        let (height, _, _, respond_to) =
+            p2p_mock.expect_header_quorum_request_for_height_cmd().await;
+        assert_eq!(height, 30);
+        let another_header30 = gen.another_of(&headers_27_30[3]);
+        respond_to.send(Ok(vec![another_header30])).unwrap();
+        assert_syncing(&syncer, &store, 26, 30).await;
+
+        // Batch is fetched again
+        let (height, amount, _) = p2p_mock.expect_header_request_for_height_cmd().await;
+        assert_eq!((height, amount), (27, 4));
+    }
+
     async fn assert_syncing(
         syncer: &Syncer<InMemoryStore>,
         store: &InMemoryStore,
@@ -848,6 +1798,122 @@ mod tests {
     async fn initialized_syncer(
         genesis: ExtendedHeader,
         head: ExtendedHeader,
+    ) -> (Syncer<InMemoryStore>, Arc<InMemoryStore>, MockP2pHandle) {
+        initialized_syncer_with_events(genesis, head, EventChannel::new().publisher()).await
+    }
+
+    #[async_test]
+    async fn init_from_persisted_network_head() {
+        let (store, mut gen) = gen_filled_store(25);
+        let store = Arc::new(store);
+        let headers_26_30 = gen.next_many(5);
+        let network_head = headers_26_30.last().cloned().unwrap();
+        store.set_network_head(network_head.clone()).await.unwrap();
+
+        let (mock, mut p2p_mock) = P2p::mocked();
+        let syncer = Syncer::start(SyncerArgs {
+            genesis_hash: None,
+            p2p: Arc::new(mock),
+            store: store.clone(),
+            sync_mode: SyncMode::Forward,
+            header_quorum: 1,
+            event_pub: EventChannel::new().publisher(),
+            clock: Arc::new(SystemClock),
+        })
+        .unwrap();
+
+        // Trusted peers are not needed and the network head is not requested
+        let head_from_syncer = p2p_mock.expect_init_header_sub().await;
+        assert_eq!(head_from_syncer, network_head);
+        assert_syncing(&syncer, &store, 25, 30).await;
+
+        let (height, amount, respond_to) = p2p_mock.expect_header_request_for_height_cmd().await;
+        assert_eq!((height, amount), (26, 5));
+        respond_to
+            .send(Ok(headers_26_30))
+            .map_err(|_| "headers [26, 30]")
+            .unwrap();
+        assert_syncing(&syncer, &store, 30, 30).await;
+    }
+
+    #[async_test]
+    async fn init_with_expired_network_head() {
+        // seeded headers are timestamped long before the trusting period
+        let mut gen = ExtendedHeaderGenerator::new_with_seed(7);
+        let genesis = gen.next();
+        let expired_head = gen.next_many(5).pop().unwrap();
+        let network_head = gen.next();
+
+        let store = Arc::new(InMemoryStore::new());
+        store.append_single_unchecked(genesis).unwrap();
+        store.set_network_head(expired_head).await.unwrap();
+
+        let (mock, mut p2p_mock) = P2p::mocked();
+        let _syncer = Syncer::start(SyncerArgs {
+            genesis_hash: None,
+            p2p: Arc::new(mock),
+            store: store.clone(),
+            sync_mode: SyncMode::Forward,
+            header_quorum: 1,
+            event_pub: EventChannel::new().publisher(),
+            clock: Arc::new(SystemClock),
+        })
+        .unwrap();
+
+        // Syncer re-initializes from the trusted peers
+        p2p_mock.expect_no_cmd().await;
+        p2p_mock.announce_trusted_peer_connected();
+
+        let (height, amount, respond_to) = p2p_mock.expect_header_request_for_height_cmd().await;
+        assert_eq!((height, amount), (0, 1));
+        respond_to.send(Ok(vec![network_head.clone()])).unwrap();
+
+        let head_from_syncer = p2p_mock.expect_init_header_sub().await;
+        assert_eq!(head_from_syncer, network_head);
+        sleep(Duration::from_millis(1)).await;
+        assert_eq!(store.get_network_head().await.unwrap(), Some(network_head));
+    }
+
+    #[async_test]
+    async fn init_with_network_head_expired_by_clock() {
+        let (store, mut gen) = gen_filled_store(25);
+        let store = Arc::new(store);
+        let persisted_head = gen.next_many(5).pop().unwrap();
+        let network_head = gen.next();
+
+        // clock moved past the trusting period of the persisted head
+        let clock = MockClock::new(persisted_head.time());
+        clock.advance(TRUSTING_PERIOD + Duration::from_secs(1));
+        store.set_network_head(persisted_head).await.unwrap();
+
+        let (mock, mut p2p_mock) = P2p::mocked();
+        let _syncer = Syncer::start(SyncerArgs {
+            genesis_hash: None,
+            p2p: Arc::new(mock),
+            store: store.clone(),
+            sync_mode: SyncMode::Forward,
+            header_quorum: 1,
+            event_pub: EventChannel::new().publisher(),
+            clock: Arc::new(clock),
+        })
+        .unwrap();
+
+        // Syncer re-initializes from the trusted peers
+        p2p_mock.expect_no_cmd().await;
+        p2p_mock.announce_trusted_peer_connected();
+
+        let (height, amount, respond_to) = p2p_mock.expect_header_request_for_height_cmd().await;
+        assert_eq!((height, amount), (0, 1));
+        respond_to.send(Ok(vec![network_head.clone()])).unwrap();
+
+        let head_from_syncer = p2p_mock.expect_init_header_sub().await;
+        assert_eq!(head_from_syncer, network_head);
+    }
+
+    async fn initialized_syncer_with_events(
+        genesis: ExtendedHeader,
+        head: ExtendedHeader,
+        event_pub: EventPublisher,
     ) -> (Syncer<InMemoryStore>, Arc<InMemoryStore>, MockP2pHandle) {
         let (mock, mut handle) = P2p::mocked();
         let store = Arc::new(InMemoryStore::new());
@@ -856,6 +1922,10 @@ mod tests {
             genesis_hash: Some(genesis.hash()),
             p2p: Arc::new(mock),
             store: store.clone(),
+            sync_mode: SyncMode::Forward,
+            header_quorum: 1,
+            event_pub,
+            clock: Arc::new(SystemClock),
         })
         .unwrap();
 
@@ -883,6 +1953,40 @@ mod tests {
         (syncer, store, handle)
     }
 
+    async fn backward_syncer(
+        genesis_hash: Option<Hash>,
+        head: ExtendedHeader,
+        target_height: u64,
+    ) -> (Syncer<InMemoryStore>, Arc<InMemoryStore>, MockP2pHandle) {
+        let (mock, mut handle) = P2p::mocked();
+        let store = Arc::new(InMemoryStore::new());
+
+        let syncer = Syncer::start(SyncerArgs {
+            genesis_hash,
+            p2p: Arc::new(mock),
+            store: store.clone(),
+            sync_mode: SyncMode::Backward { target_height },
+            header_quorum: 1,
+            event_pub: EventChannel::new().publisher(),
+            clock: Arc::new(SystemClock),
+        })
+        .unwrap();
+
+        handle.announce_trusted_peer_connected();
+
+        // Genesis is not requested, Syncer asks for the current HEAD
+        let (height, amount, respond_to) = handle.expect_header_request_for_height_cmd().await;
+        assert_eq!(height, 0);
+        assert_eq!(amount, 1);
+        respond_to.send(Ok(vec![head.clone()])).unwrap();
+
+        // Now Syncer initializes HeaderSub with the latest HEAD
+        let head_from_syncer = handle.expect_init_header_sub().await;
+        assert_eq!(head_from_syncer, head);
+
+        (syncer, store, handle)
+    }
+
     async fn handle_session_batch(
         p2p_mock: &mut MockP2pHandle,
         remaining_headers: &mut Vec<ExtendedHeader>,
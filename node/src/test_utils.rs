@@ -1,21 +1,38 @@
 //! Utilities for writing tests.
 
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use celestia_proto::p2p::pb::{header_request::Data, HeaderRequest};
-use celestia_types::{hash::Hash, test_utils::ExtendedHeaderGenerator, ExtendedHeader};
-use libp2p::identity::{self, Keypair};
+use celestia_tendermint::Time;
+use celestia_types::{
+    fraud_proof::BadEncodingFraudProof, hash::Hash, test_utils::ExtendedHeaderGenerator,
+    ExtendedHeader,
+};
+use libp2p::PeerId;
 use tokio::sync::{mpsc, watch};
 
 use crate::{
+    bitswap::{BlockSender, Cid, ReceivedBlock},
+    clock::Clock,
     executor::timeout,
-    node::NodeConfig,
+    network::Network,
+    node::NodeBuilder,
     p2p::{P2pCmd, P2pError},
     peer_tracker::PeerTrackerInfo,
     store::InMemoryStore,
     utils::OneshotResultSender,
 };
 
+/// Deliver the shwap container from a random peer to the requester of the want.
+pub(crate) fn deliver_shwap_block(respond_to: &BlockSender, data: Vec<u8>) {
+    let block = ReceivedBlock {
+        peer: PeerId::random(),
+        data,
+    };
+    respond_to.try_send(Ok(block)).unwrap();
+}
+
 /// Generate a store pre-filled with headers.
 pub fn gen_filled_store(amount: u64) -> (InMemoryStore, ExtendedHeaderGenerator) {
     let s = InMemoryStore::new();
@@ -31,37 +48,55 @@ pub fn gen_filled_store(amount: u64) -> (InMemoryStore, ExtendedHeaderGenerator)
     (s, gen)
 }
 
-/// [`NodeConfig`] with default values for the usage in tests.
+/// [`Clock`] which moves only when told to.
 ///
-/// Can be used to fill the missing fields with `..test_node_config()` syntax.
-pub fn test_node_config() -> NodeConfig<InMemoryStore> {
-    let node_keypair = identity::Keypair::generate_ed25519();
-    NodeConfig {
-        network_id: "private".to_string(),
-        genesis_hash: None,
-        p2p_local_keypair: node_keypair,
-        p2p_bootnodes: vec![],
-        p2p_listen_on: vec![],
-        store: InMemoryStore::new(),
-    }
+/// Clones share the same time, so the clock given to the node can be moved from the test.
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    now: Arc<Mutex<Time>>,
 }
 
-/// [`NodeConfig`] with listen address and default values for the usage in tests.
-pub fn listening_test_node_config() -> NodeConfig<InMemoryStore> {
-    NodeConfig {
-        p2p_listen_on: vec!["/ip4/0.0.0.0/tcp/0".parse().unwrap()],
-        ..test_node_config()
+impl MockClock {
+    /// Create a new clock stopped at the given time.
+    pub fn new(now: Time) -> Self {
+        MockClock {
+            now: Arc::new(Mutex::new(now)),
+        }
+    }
+
+    /// Set the time of the clock.
+    pub fn set(&self, now: Time) {
+        *self.now.lock().expect("clock lock poisoned") = now;
+    }
+
+    /// Move the clock forward by the `duration`.
+    pub fn advance(&self, duration: Duration) {
+        let mut now = self.now.lock().expect("clock lock poisoned");
+        *now = now.checked_add(duration).expect("time overflow");
     }
 }
 
-/// [`NodeConfig`] with given keypair and default values for the usage in tests.
-pub fn test_node_config_with_keypair(keypair: Keypair) -> NodeConfig<InMemoryStore> {
-    NodeConfig {
-        p2p_local_keypair: keypair,
-        ..test_node_config()
+impl Clock for MockClock {
+    fn now(&self) -> Time {
+        *self.now.lock().expect("clock lock poisoned")
     }
 }
 
+/// [`NodeBuilder`] of a node in the private network, with in-memory store, pruning
+/// and sampling disabled.
+pub fn test_node_builder() -> NodeBuilder<InMemoryStore> {
+    NodeBuilder::new()
+        .network(Network::Private)
+        .store(InMemoryStore::new())
+        .disable_pruning()
+        .disable_sampling()
+}
+
+/// [`test_node_builder`] with a listen address.
+pub fn listening_test_node_builder() -> NodeBuilder<InMemoryStore> {
+    test_node_builder().listen_addrs(["/ip4/0.0.0.0/tcp/0".parse().unwrap()])
+}
+
 /// A handle to the mocked [`P2p`] component.
 ///
 /// [`P2p`]: crate::p2p::P2p
@@ -70,6 +105,7 @@ pub struct MockP2pHandle {
     pub(crate) cmd_tx: mpsc::Sender<P2pCmd>,
     pub(crate) cmd_rx: mpsc::Receiver<P2pCmd>,
     pub(crate) header_sub_tx: watch::Sender<Option<ExtendedHeader>>,
+    pub(crate) fraud_proof_tx: watch::Sender<Option<BadEncodingFraudProof>>,
     pub(crate) peer_tracker_tx: watch::Sender<PeerTrackerInfo>,
 }
 
@@ -102,10 +138,15 @@ impl MockP2pHandle {
         self.header_sub_tx.send_replace(Some(header));
     }
 
+    /// Simulate a valid bad encoding fraud proof announced in the network.
+    pub fn announce_fraud_proof(&self, proof: BadEncodingFraudProof) {
+        self.fraud_proof_tx.send_replace(Some(proof));
+    }
+
     /// Assert that a command was sent to the [`P2p`] worker.
     ///
     /// [`P2p`]: crate::p2p::P2p
-    async fn expect_cmd(&mut self) -> P2pCmd {
+    pub(crate) async fn expect_cmd(&mut self) -> P2pCmd {
         timeout(Duration::from_millis(300), async move {
             self.cmd_rx.recv().await.expect("P2p dropped")
         })
@@ -174,6 +215,107 @@ impl MockP2pHandle {
         }
     }
 
+    /// Assert that a quorum header request for height was sent to the [`P2p`] worker and obtain
+    /// a response channel.
+    ///
+    /// [`P2p`]: crate::p2p::P2p
+    pub async fn expect_header_quorum_request_for_height_cmd(
+        &mut self,
+    ) -> (
+        u64,
+        u64,
+        usize,
+        OneshotResultSender<Vec<ExtendedHeader>, P2pError>,
+    ) {
+        match self.expect_cmd().await {
+            P2pCmd::HeaderExQuorumRequest {
+                request:
+                    HeaderRequest {
+                        data: Some(Data::Origin(height)),
+                        amount,
+                    },
+                quorum,
+                respond_to,
+            } if amount > 0 => (height, amount, quorum, respond_to),
+            cmd => panic!("Expecting HeaderExQuorumRequest for height, but received: {cmd:?}"),
+        }
+    }
+
+    /// Assert that a request for a shwap container was sent to the [`P2p`] worker and obtain
+    /// a response channel.
+    ///
+    /// [`P2p`]: crate::p2p::P2p
+    pub(crate) async fn expect_get_shwap_cid(&mut self) -> (Cid, BlockSender) {
+        match self.expect_cmd().await {
+            P2pCmd::GetShwapCid { cid, respond_to } => (cid, respond_to),
+            cmd => panic!("Expecting GetShwapCid, but received: {cmd:?}"),
+        }
+    }
+
+    /// Assert that a batch of requests for the shwap containers was sent to the [`P2p`]
+    /// worker and obtain their response channels.
+    ///
+    /// [`P2p`]: crate::p2p::P2p
+    pub(crate) async fn expect_get_shwap_cids(&mut self) -> Vec<(Cid, BlockSender)> {
+        match self.expect_cmd().await {
+            P2pCmd::GetShwapCids { wants } => wants,
+            cmd => panic!("Expecting GetShwapCids, but received: {cmd:?}"),
+        }
+    }
+
+    /// Assert that the shwap container delivered by the peer was accepted by the requester.
+    ///
+    /// [`P2p`]: crate::p2p::P2p
+    pub async fn expect_accept_shwap_block(&mut self) -> (Cid, PeerId) {
+        match self.expect_cmd().await {
+            P2pCmd::AcceptShwapBlock { cid, peer } => (cid, peer),
+            cmd => panic!("Expecting AcceptShwapBlock, but received: {cmd:?}"),
+        }
+    }
+
+    /// Assert that the shwap container delivered by the peer failed the verification of
+    /// the requester.
+    ///
+    /// [`P2p`]: crate::p2p::P2p
+    pub async fn expect_reject_shwap_block(&mut self) -> (Cid, PeerId) {
+        match self.expect_cmd().await {
+            P2pCmd::RejectShwapBlock { cid, peer } => (cid, peer),
+            cmd => panic!("Expecting RejectShwapBlock, but received: {cmd:?}"),
+        }
+    }
+
+    /// Assert that a validated shwap container was sent to the [`P2p`] worker to be stored.
+    ///
+    /// [`P2p`]: crate::p2p::P2p
+    pub async fn expect_put_shwap_block(&mut self) -> (Cid, Vec<u8>) {
+        match self.expect_cmd().await {
+            P2pCmd::PutShwapBlock { cid, data } => (cid, data),
+            cmd => panic!("Expecting PutShwapBlock, but received: {cmd:?}"),
+        }
+    }
+
+    /// Assert that `amount` shwap containers were accepted and sent to the [`P2p`] worker
+    /// to be stored, in any order.
+    ///
+    /// [`P2p`]: crate::p2p::P2p
+    pub async fn expect_verified_shwap_blocks(&mut self, amount: usize) -> Vec<(Cid, Vec<u8>)> {
+        let mut accepted = Vec::new();
+        let mut stored = Vec::new();
+
+        while stored.len() < amount {
+            match self.expect_cmd().await {
+                P2pCmd::AcceptShwapBlock { cid, .. } => accepted.push(cid),
+                P2pCmd::PutShwapBlock { cid, data } => {
+                    assert!(accepted.contains(&cid), "Stored {cid} wasn't accepted");
+                    stored.push((cid, data));
+                }
+                cmd => panic!("Expecting AcceptShwapBlock or PutShwapBlock, but received: {cmd:?}"),
+            }
+        }
+
+        stored
+    }
+
     /// Assert that a header-sub initialization command was sent to the [`P2p`] worker.
     ///
     /// [`P2p`]: crate::p2p::P2p
@@ -0,0 +1,559 @@
+//! Submission of blobs with the fee estimation and resubmission during congestion.
+//!
+//! [`TxClient`] wraps the [`BlobSubmitter`], estimating the gas needed by the
+//! `PayForBlobs` transaction from the sizes of the blobs and pricing it with the
+//! configured [`GasPriceCurve`]. When the transaction is rejected because the mempool
+//! is full or the fee is too low, it is resubmitted with the next, higher gas price
+//! from the curve. After a sequence mismatch, the sequence of the account is queried
+//! from the [`SequenceProvider`] before resubmitting. If the submission timed out,
+//! the transaction is looked up first and resubmitted only if it won't be included.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use base64::prelude::*;
+use celestia_proto::cosmos::auth::v1beta1::{
+    BaseAccount, QueryAccountRequest, QueryAccountResponse,
+};
+use celestia_rpc::{AbciClient, HeaderClient, StateClient};
+use celestia_types::blob::SubmitOptions;
+use celestia_types::consts::appconsts::{
+    DEFAULT_GAS_PER_BLOB_BYTE, DEFAULT_TX_SIZE_COST_PER_BYTE, SHARE_SIZE,
+};
+use celestia_types::hash::Hash;
+use celestia_types::state::{AccAddress, TxResponse, TxStatus, TxStatusResponse};
+use celestia_types::Blob;
+use prost::Message;
+use tracing::{debug, warn};
+
+use crate::blob_submitter::{BlobSubmitter, BlobSubmitterError, Keyring, DEFAULT_GAS_PRICE};
+use crate::executor::sleep;
+
+/// Fixed amount of gas consumed by each `PayForBlobs` transaction.
+pub const PFB_GAS_FIXED_COST: u64 = 75_000;
+/// Estimated size of the blob's metadata in the transaction.
+pub const BYTES_PER_BLOB_INFO: u64 = 70;
+
+/// Code of the cosmos-sdk error returned when the fee is below the node's minimum.
+const INSUFFICIENT_FEE_CODE: u32 = 13;
+/// Code of the cosmos-sdk error returned when the mempool has no room for the transaction.
+const MEMPOOL_IS_FULL_CODE: u32 = 20;
+/// Code of the cosmos-sdk error returned when the transaction has a wrong sequence.
+const WRONG_SEQUENCE_CODE: u32 = 32;
+
+/// Path of the query of the account in the auth module.
+const ACCOUNT_QUERY_PATH: &str = "/cosmos.auth.v1beta1.Query/Account";
+const BASE_ACCOUNT_TYPE_URL: &str = "/cosmos.auth.v1beta1.BaseAccount";
+
+type Result<T, E = BlobSubmitterError> = std::result::Result<T, E>;
+
+/// Price of the gas depending on the number of the submission attempt.
+///
+/// The price starts at `initial` and is multiplied by the `multiplier` with each
+/// resubmission, up to the `max`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GasPriceCurve {
+    /// Gas price used in the first attempt, in `utia` per unit of gas.
+    pub initial: f64,
+    /// Multiplier of the price applied with each resubmission.
+    pub multiplier: f64,
+    /// Maximum gas price that can be paid.
+    pub max: f64,
+}
+
+impl GasPriceCurve {
+    /// Gas price of the given attempt, starting from `0`.
+    pub fn price(&self, attempt: u32) -> f64 {
+        let exp = i32::try_from(attempt).unwrap_or(i32::MAX);
+        (self.initial * self.multiplier.powi(exp)).min(self.max)
+    }
+}
+
+impl Default for GasPriceCurve {
+    fn default() -> Self {
+        GasPriceCurve {
+            initial: DEFAULT_GAS_PRICE,
+            multiplier: 1.5,
+            max: DEFAULT_GAS_PRICE * 50.0,
+        }
+    }
+}
+
+/// Configuration of the [`TxClient`].
+#[derive(Debug, Clone)]
+pub struct TxConfig {
+    /// Curve of the gas prices used in the subsequent attempts.
+    pub gas_price: GasPriceCurve,
+    /// Multiplier applied to the estimated gas to leave a safety margin.
+    pub gas_adjustment: f64,
+    /// Maximum number of submission attempts.
+    pub max_attempts: u32,
+    /// Delay before resubmitting the transaction.
+    pub retry_delay: Duration,
+}
+
+impl Default for TxConfig {
+    fn default() -> Self {
+        TxConfig {
+            gas_price: GasPriceCurve::default(),
+            gas_adjustment: 1.1,
+            max_attempts: 5,
+            retry_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Source of the current sequence of the account and the statuses of its transactions,
+/// used to resynchronize the [`BlobSubmitter`] with the chain.
+///
+/// Implemented for the [`AbciClient`]s connected to a consensus node.
+#[async_trait]
+pub trait SequenceProvider: Send + Sync {
+    /// Get the sequence of the next transaction of the account.
+    async fn sequence(&self, address: &AccAddress) -> Result<u64>;
+
+    /// Get the status of the transaction with the given hash.
+    async fn tx_status(&self, hash: &Hash) -> Result<TxStatusResponse>;
+}
+
+#[async_trait]
+impl<T> SequenceProvider for T
+where
+    T: AbciClient + Send + Sync,
+{
+    async fn sequence(&self, address: &AccAddress) -> Result<u64> {
+        Ok(query_account(self, address).await?.sequence)
+    }
+
+    async fn tx_status(&self, hash: &Hash) -> Result<TxStatusResponse> {
+        let hash = BASE64_STANDARD.encode(hash.as_bytes());
+
+        Ok(AbciClient::tx_status(self, &hash)
+            .await
+            .map_err(celestia_rpc::Error::from)?)
+    }
+}
+
+/// Query the account from the consensus node, e.g. to get the account number and
+/// the sequence needed to create the [`BlobSubmitter`].
+pub async fn query_account<C>(client: &C, address: &AccAddress) -> Result<BaseAccount>
+where
+    C: AbciClient + Sync,
+{
+    let request = QueryAccountRequest {
+        address: address.to_string(),
+    };
+
+    let response = client
+        .abci_query(
+            ACCOUNT_QUERY_PATH,
+            &hex::encode_upper(request.encode_to_vec()),
+            "0",
+            false,
+        )
+        .await
+        .map_err(celestia_rpc::Error::from)?
+        .response;
+
+    if response.code != 0 {
+        return Err(celestia_types::Error::AbciQuery(response.code, response.log).into());
+    }
+
+    decode_account(&response.value)
+}
+
+/// Client submitting blobs with the estimated gas, bumping fees when congested.
+pub struct TxClient<C, K> {
+    submitter: BlobSubmitter<C, K>,
+    config: TxConfig,
+    sequence_provider: Option<Box<dyn SequenceProvider>>,
+}
+
+impl<C, K> TxClient<C, K>
+where
+    C: StateClient + HeaderClient + Send + Sync,
+    K: Keyring,
+{
+    /// Create a new [`TxClient`] submitting transactions with the given [`BlobSubmitter`].
+    pub fn new(submitter: BlobSubmitter<C, K>, config: TxConfig) -> Self {
+        TxClient {
+            submitter,
+            config,
+            sequence_provider: None,
+        }
+    }
+
+    /// Query the sequence of the account from the given [`SequenceProvider`] when
+    /// the sequence didn't match, and the status of the transaction when the submission
+    /// timed out.
+    ///
+    /// Without it, transactions that timed out or had a mismatched sequence are not
+    /// resubmitted.
+    pub fn with_sequence_provider(mut self, provider: impl SequenceProvider + 'static) -> Self {
+        self.sequence_provider = Some(Box::new(provider));
+        self
+    }
+
+    /// The underlying [`BlobSubmitter`].
+    pub fn submitter(&self) -> &BlobSubmitter<C, K> {
+        &self.submitter
+    }
+
+    /// Submit the [`Blob`]s, resubmitting with higher fees if the network is congested.
+    ///
+    /// The gas limit is estimated from the sizes of the blobs, see [`estimate_gas`].
+    ///
+    /// If the submission timed out, it waits for the transaction to leave the mempool.
+    /// The response of the transaction is returned if it was included, so that the blobs
+    /// are never paid for twice.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the last attempt if the transaction failed for a reason
+    /// other than congestion, a stale sequence or a timeout, or all the attempts were
+    /// exhausted. Errors of querying the sequence or the transaction are returned as well.
+    pub async fn submit_blobs(&self, blobs: &[Blob]) -> Result<TxResponse> {
+        let gas_limit = (estimate_gas(blobs) as f64 * self.config.gas_adjustment).ceil() as u64;
+        let mut attempt = 0;
+        let mut price_step = 0;
+
+        loop {
+            let gas_price = self.config.gas_price.price(price_step);
+            let opts = SubmitOptions {
+                fee: Some((gas_limit as f64 * gas_price).ceil() as u64),
+                gas_limit: Some(gas_limit),
+            };
+
+            let err = match self.submitter.submit_blob(blobs, opts).await {
+                Err(e) => e,
+                res => return res,
+            };
+
+            let mut resync = is_sequence_mismatch(&err);
+
+            if let (Some(hash), Some(provider)) = (timed_out_tx(&err), &self.sequence_provider) {
+                let included =
+                    wait_for_tx_outcome(provider.as_ref(), hash, self.config.retry_delay).await?;
+
+                if let Some(status) = included {
+                    // the sequence was consumed without the submitter knowing
+                    self.resync_sequence().await?;
+                    return included_tx_response(hash, status);
+                }
+
+                resync = true;
+            }
+
+            if attempt + 1 >= self.config.max_attempts {
+                return Err(err);
+            }
+
+            if is_congestion(&err) {
+                price_step += 1;
+            } else if !resync || self.sequence_provider.is_none() {
+                return Err(err);
+            }
+
+            warn!("Submission with gas price {gas_price} failed: {err}, resubmitting");
+            attempt += 1;
+            sleep(self.config.retry_delay).await;
+
+            if resync {
+                self.resync_sequence().await?;
+            }
+        }
+    }
+
+    async fn resync_sequence(&self) -> Result<()> {
+        if let Some(provider) = &self.sequence_provider {
+            let sequence = provider.sequence(self.submitter.address()).await?;
+            debug!("Resubmitting with the account sequence {sequence}");
+            self.submitter.set_sequence(sequence).await;
+        }
+
+        Ok(())
+    }
+}
+
+/// Wait until the transaction leaves the mempool, polling its status every `poll_interval`.
+///
+/// Returns the status of the included transaction, or `None` if it won't be included.
+async fn wait_for_tx_outcome(
+    provider: &dyn SequenceProvider,
+    hash: Hash,
+    poll_interval: Duration,
+) -> Result<Option<TxStatusResponse>> {
+    loop {
+        let status = provider.tx_status(&hash).await?;
+
+        match status.status {
+            TxStatus::Committed => return Ok(Some(status)),
+            TxStatus::Unknown | TxStatus::Evicted => return Ok(None),
+            TxStatus::Pending => {
+                debug!("Transaction {hash} is still pending");
+                sleep(poll_interval).await;
+            }
+        }
+    }
+}
+
+/// Response of the included transaction, with the error if its execution failed.
+fn included_tx_response(hash: Hash, status: TxStatusResponse) -> Result<TxResponse> {
+    if status.execution_code != 0 {
+        return Err(BlobSubmitterError::TxFailed {
+            code: status.execution_code,
+            log: status.error,
+        });
+    }
+
+    Ok(TxResponse {
+        height: status.height as i64,
+        txhash: hash.to_string(),
+        ..Default::default()
+    })
+}
+
+/// Decode the account from the encoded `QueryAccountResponse`.
+fn decode_account(bytes: &[u8]) -> Result<BaseAccount> {
+    let invalid = |e: &dyn std::fmt::Display| BlobSubmitterError::InvalidAccount(e.to_string());
+
+    let account = QueryAccountResponse::decode(bytes)
+        .map_err(|e| invalid(&e))?
+        .account
+        .ok_or_else(|| invalid(&"missing account"))?;
+
+    if account.type_url != BASE_ACCOUNT_TYPE_URL {
+        return Err(invalid(&format!("unsupported type {}", account.type_url)));
+    }
+
+    BaseAccount::decode(&account.value[..]).map_err(|e| invalid(&e))
+}
+
+/// Estimate the gas consumed by the `PayForBlobs` transaction of the given [`Blob`]s.
+///
+/// It follows the estimation of the `celestia-app`, with the gas paid for the shares
+/// occupied by each blob, the size of the blob's metadata and a fixed cost of
+/// the transaction.
+pub fn estimate_gas(blobs: &[Blob]) -> u64 {
+    let shares: u64 = blobs.iter().map(|blob| blob.shares_len() as u64).sum();
+
+    shares * SHARE_SIZE as u64 * DEFAULT_GAS_PER_BLOB_BYTE
+        + DEFAULT_TX_SIZE_COST_PER_BYTE * BYTES_PER_BLOB_INFO * blobs.len() as u64
+        + PFB_GAS_FIXED_COST
+}
+
+/// Whether the submission failed due to the congestion and can be retried with a higher fee.
+fn is_congestion(error: &BlobSubmitterError) -> bool {
+    match error {
+        BlobSubmitterError::TxFailed { code, log } => {
+            *code == MEMPOOL_IS_FULL_CODE
+                || *code == INSUFFICIENT_FEE_CODE
+                || log.contains("mempool is full")
+        }
+        BlobSubmitterError::Broadcast { error, .. } => {
+            error.to_string().to_lowercase().contains("mempool is full")
+        }
+        _ => false,
+    }
+}
+
+/// Hash of the transaction whose submission timed out, so it may or may not have landed.
+fn timed_out_tx(error: &BlobSubmitterError) -> Option<Hash> {
+    match error {
+        BlobSubmitterError::Broadcast { hash, error } => {
+            let msg = error.to_string().to_lowercase();
+            (msg.contains("timeout") || msg.contains("timed out")).then_some(*hash)
+        }
+        _ => None,
+    }
+}
+
+/// Whether the transaction was signed with another sequence than the account's one.
+fn is_sequence_mismatch(error: &BlobSubmitterError) -> bool {
+    match error {
+        BlobSubmitterError::TxFailed { code, log } => {
+            *code == WRONG_SEQUENCE_CODE || log.contains("account sequence mismatch")
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use celestia_types::consts::appconsts::AppVersion;
+    use celestia_types::nmt::Namespace;
+
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    use tokio::test as async_test;
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as async_test;
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    /// Provider answering with the given statuses of the transaction, one per query.
+    struct MockProvider(Mutex<VecDeque<TxStatus>>);
+
+    #[async_trait]
+    impl SequenceProvider for MockProvider {
+        async fn sequence(&self, _address: &AccAddress) -> Result<u64> {
+            Ok(0)
+        }
+
+        async fn tx_status(&self, _hash: &Hash) -> Result<TxStatusResponse> {
+            let status = self
+                .0
+                .lock()
+                .unwrap()
+                .pop_front()
+                .expect("unexpected query");
+
+            Ok(TxStatusResponse {
+                height: if status == TxStatus::Committed { 10 } else { 0 },
+                index: 0,
+                execution_code: 0,
+                error: String::new(),
+                status,
+            })
+        }
+    }
+
+    #[test]
+    fn gas_estimation() {
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+        let small = Blob::new(namespace, vec![1], AppVersion::V2).unwrap();
+        let large = Blob::new(namespace, vec![1; 1000], AppVersion::V2).unwrap();
+
+        assert_eq!(estimate_gas(&[small.clone()]), 79_796);
+        // 1000 bytes occupy 3 shares
+        assert_eq!(
+            estimate_gas(&[small, large]),
+            4 * 512 * 8 + 2 * 700 + 75_000
+        );
+    }
+
+    #[test]
+    fn gas_price_curve() {
+        let curve = GasPriceCurve {
+            initial: 0.002,
+            multiplier: 2.0,
+            max: 0.01,
+        };
+
+        assert_eq!(curve.price(0), 0.002);
+        assert_eq!(curve.price(1), 0.004);
+        assert_eq!(curve.price(2), 0.008);
+        assert_eq!(curve.price(3), 0.01);
+        assert_eq!(curve.price(u32::MAX), 0.01);
+    }
+
+    #[test]
+    fn congestion_errors() {
+        let tx_failed = |code, log: &str| BlobSubmitterError::TxFailed {
+            code,
+            log: log.to_owned(),
+        };
+
+        assert!(is_congestion(&tx_failed(20, "")));
+        assert!(is_congestion(&tx_failed(13, "insufficient fees")));
+        assert!(is_congestion(&tx_failed(
+            1,
+            "mempool is full: number of txs 5000"
+        )));
+        assert!(!is_congestion(&tx_failed(32, "account sequence mismatch")));
+        assert!(!is_congestion(&BlobSubmitterError::MissingGasLimit));
+    }
+
+    #[async_test]
+    async fn timed_out_tx_is_waited_for() {
+        let hash = Hash::Sha256([1; 32]);
+        let delay = Duration::from_millis(1);
+
+        let provider = MockProvider(Mutex::new(
+            [TxStatus::Pending, TxStatus::Pending, TxStatus::Committed].into(),
+        ));
+        let status = wait_for_tx_outcome(&provider, hash, delay)
+            .await
+            .unwrap()
+            .unwrap();
+        let response = included_tx_response(hash, status).unwrap();
+        assert_eq!(response.height, 10);
+        assert_eq!(response.txhash, hash.to_string());
+        assert!(provider.0.lock().unwrap().is_empty());
+
+        // only the transactions that won't be included are resubmitted
+        for status in [TxStatus::Unknown, TxStatus::Evicted] {
+            let provider = MockProvider(Mutex::new([TxStatus::Pending, status].into()));
+            assert!(wait_for_tx_outcome(&provider, hash, delay)
+                .await
+                .unwrap()
+                .is_none());
+        }
+    }
+
+    #[test]
+    fn failed_included_tx() {
+        let status = TxStatusResponse {
+            height: 10,
+            index: 0,
+            execution_code: 11,
+            error: "out of gas".to_owned(),
+            status: TxStatus::Committed,
+        };
+
+        assert!(matches!(
+            included_tx_response(Hash::Sha256([1; 32]), status),
+            Err(BlobSubmitterError::TxFailed { code: 11, .. })
+        ));
+    }
+
+    #[test]
+    fn sequence_mismatch_errors() {
+        let tx_failed = |code, log: &str| BlobSubmitterError::TxFailed {
+            code,
+            log: log.to_owned(),
+        };
+
+        assert!(is_sequence_mismatch(&tx_failed(32, "")));
+        assert!(is_sequence_mismatch(&tx_failed(
+            1,
+            "account sequence mismatch, expected 5, got 4: incorrect account sequence"
+        )));
+        assert!(!is_sequence_mismatch(&tx_failed(20, "mempool is full")));
+        assert!(timed_out_tx(&tx_failed(32, "timed out")).is_none());
+    }
+
+    #[test]
+    fn decode_base_account() {
+        let account = BaseAccount {
+            address: "celestia1".to_owned(),
+            pub_key: None,
+            account_number: 3,
+            sequence: 7,
+        };
+        let response = QueryAccountResponse {
+            account: Some(prost_types::Any {
+                type_url: BASE_ACCOUNT_TYPE_URL.to_owned(),
+                value: account.encode_to_vec(),
+            }),
+        };
+        let decoded = decode_account(&response.encode_to_vec()).unwrap();
+        assert_eq!(decoded.account_number, 3);
+        assert_eq!(decoded.sequence, 7);
+
+        let response = QueryAccountResponse {
+            account: Some(prost_types::Any {
+                type_url: "/cosmos.vesting.v1beta1.DelayedVestingAccount".to_owned(),
+                value: Vec::new(),
+            }),
+        };
+        assert!(matches!(
+            decode_account(&response.encode_to_vec()),
+            Err(BlobSubmitterError::InvalidAccount(_))
+        ));
+        decode_account(&[0xff]).unwrap_err();
+    }
+}
@@ -5,6 +5,7 @@ use libp2p::{PeerId, StreamProtocol};
 use tokio::sync::oneshot;
 
 use crate::executor::yield_now;
+use crate::store::Result as StoreResult;
 
 pub(crate) const VALIDATIONS_PER_YIELD: usize = 4;
 
@@ -28,6 +29,10 @@ pub(crate) fn gossipsub_ident_topic(network: &str, topic: &str) -> IdentTopic {
     IdentTopic::new(s)
 }
 
+pub(crate) fn fraud_sub_ident_topic(network: &str, proof_type: &str) -> IdentTopic {
+    gossipsub_ident_topic(network, &format!("/fraud-sub/{proof_type}/v0.0.1"))
+}
+
 pub(crate) type OneshotResultSender<T, E> = oneshot::Sender<Result<T, E>>;
 
 pub(crate) trait OneshotSenderExt<T>
@@ -82,9 +87,81 @@ impl MultiaddrExt for Multiaddr {
     }
 }
 
-pub(crate) async fn validate_headers(headers: &[ExtendedHeader]) -> celestia_types::Result<()> {
-    for headers in headers.chunks(VALIDATIONS_PER_YIELD) {
-        for header in headers {
+/// Check if the computations can be moved to the rayon thread pool.
+///
+/// In the browser the pool is backed by the Web Workers, started from javascript with
+/// `initThreadPool`. Without them rayon falls back to the pool of only the current
+/// thread, which never runs the spawned jobs, as it doesn't block on the pool.
+#[cfg(rayon_pool)]
+pub(crate) fn rayon_pool_available() -> bool {
+    !cfg!(target_arch = "wasm32") || rayon::current_num_threads() > 1
+}
+
+/// The closure run off the executor panicked.
+#[derive(Debug, thiserror::Error)]
+#[error("Computation panicked")]
+pub(crate) struct ComputePanicked;
+
+/// Run the computation heavy closure on the rayon thread pool, without blocking the executor.
+///
+/// The pool must be available, see [`rayon_pool_available`].
+#[cfg(rayon_pool)]
+pub(crate) async fn spawn_rayon<F, T>(f: F) -> Result<T, ComputePanicked>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let (tx, rx) = oneshot::channel();
+
+    rayon::spawn(move || {
+        // Panics of the spawned jobs abort the process in the global pool
+        let res =
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)).map_err(|_| ComputePanicked);
+        let _ = tx.send(res);
+    });
+
+    rx.await.unwrap_or(Err(ComputePanicked))
+}
+
+/// Run the computation heavy closure off the executor, on the rayon thread pool.
+///
+/// If the pool isn't available, the closure runs in place. Executor is yielded to first
+/// then, so that the other tasks, e.g. rendering of the page in the browser, don't wait
+/// for both the previous computation and this one.
+pub(crate) async fn spawn_compute<F, T>(f: F) -> Result<T, ComputePanicked>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    #[cfg(rayon_pool)]
+    if rayon_pool_available() {
+        return spawn_rayon(f).await;
+    }
+
+    yield_now().await;
+    Ok(f())
+}
+
+/// Validate the headers, on the rayon thread pool if it's available.
+///
+/// The headers are moved to the pool and given back once validated.
+pub(crate) async fn validate_headers(
+    headers: Vec<ExtendedHeader>,
+) -> StoreResult<Vec<ExtendedHeader>> {
+    #[cfg(rayon_pool)]
+    if rayon_pool_available() {
+        use rayon::prelude::*;
+
+        return spawn_rayon(move || {
+            headers.par_iter().try_for_each(ExtendedHeader::validate)?;
+            Ok(headers)
+        })
+        .await
+        .map_err(|e| crate::store::StoreError::ExecutorError(e.to_string()))?;
+    }
+
+    for chunk in headers.chunks(VALIDATIONS_PER_YIELD) {
+        for header in chunk {
             header.validate()?;
         }
 
@@ -92,5 +169,36 @@ pub(crate) async fn validate_headers(headers: &[ExtendedHeader]) -> celestia_typ
         yield_now().await;
     }
 
-    Ok(())
+    Ok(headers)
+}
+
+#[cfg(all(test, rayon_pool))]
+mod tests {
+    use super::*;
+    use crate::store::StoreError;
+    use celestia_types::test_utils::{invalidate, ExtendedHeaderGenerator};
+
+    #[tokio::test]
+    async fn spawn_rayon_maps_panic_to_error() {
+        assert_eq!(spawn_rayon(|| 7).await.unwrap(), 7);
+        spawn_rayon(|| -> u32 { panic!("expected panic") })
+            .await
+            .unwrap_err();
+
+        // the pool still works after the panic
+        assert_eq!(spawn_compute(|| 7).await.unwrap(), 7);
+    }
+
+    #[tokio::test]
+    async fn validate_headers_on_rayon_pool() {
+        assert!(rayon_pool_available());
+
+        let mut headers = ExtendedHeaderGenerator::new().next_many(10);
+        let validated = validate_headers(headers.clone()).await.unwrap();
+        assert_eq!(validated, headers);
+
+        invalidate(&mut headers[7]);
+        let err = validate_headers(headers).await.unwrap_err();
+        assert!(matches!(err, StoreError::CelestiaTypes(_)));
+    }
 }
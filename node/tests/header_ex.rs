@@ -4,10 +4,10 @@ use std::time::Duration;
 
 use celestia_types::test_utils::{invalidate, unverify};
 use lumina_node::{
-    node::{Node, NodeConfig, NodeError},
+    node::NodeError,
     p2p::{HeaderExError, P2pError},
     store::Store,
-    test_utils::{gen_filled_store, listening_test_node_config, test_node_config},
+    test_utils::{gen_filled_store, listening_test_node_builder, test_node_builder},
 };
 use tokio::time::{sleep, timeout};
 
@@ -63,24 +63,22 @@ async fn client_server() {
         .await
         .unwrap();
 
-    let server = Node::new(NodeConfig {
-        store: server_store,
-        ..listening_test_node_config()
-    })
-    .await
-    .unwrap();
+    let server = listening_test_node_builder()
+        .store(server_store)
+        .start()
+        .await
+        .unwrap();
 
     // give server a sec to breathe, otherwise occiasionally client has problems with connecting
     sleep(Duration::from_millis(100)).await;
     let server_addrs = server.listeners().await.unwrap();
 
     // Client node
-    let client = Node::new(NodeConfig {
-        p2p_bootnodes: server_addrs.clone(),
-        ..test_node_config()
-    })
-    .await
-    .unwrap();
+    let client = test_node_builder()
+        .bootnodes(server_addrs.clone())
+        .start()
+        .await
+        .unwrap();
 
     client.wait_connected().await.unwrap();
 
@@ -149,24 +147,21 @@ async fn head_selection_with_multiple_peers() {
 
     // Server group A, nodes with synced stores
     let mut servers = vec![
-        Node::new(NodeConfig {
-            store: server_store.clone(),
-            ..listening_test_node_config()
-        })
-        .await
-        .unwrap(),
-        Node::new(NodeConfig {
-            store: server_store.clone(),
-            ..listening_test_node_config()
-        })
-        .await
-        .unwrap(),
-        Node::new(NodeConfig {
-            store: server_store.clone(),
-            ..listening_test_node_config()
-        })
-        .await
-        .unwrap(),
+        listening_test_node_builder()
+            .store(server_store.clone())
+            .start()
+            .await
+            .unwrap(),
+        listening_test_node_builder()
+            .store(server_store.clone())
+            .start()
+            .await
+            .unwrap(),
+        listening_test_node_builder()
+            .store(server_store.clone())
+            .start()
+            .await
+            .unwrap(),
     ];
 
     // Server group B, single node with additional headers
@@ -177,12 +172,11 @@ async fn head_selection_with_multiple_peers() {
         .unwrap();
 
     servers.push(
-        Node::new(NodeConfig {
-            store: server_store.clone(),
-            ..listening_test_node_config()
-        })
-        .await
-        .unwrap(),
+        listening_test_node_builder()
+            .store(server_store.clone())
+            .start()
+            .await
+            .unwrap(),
     );
 
     // give server a sec to breathe, otherwise occiasionally client has problems with connecting
@@ -194,12 +188,11 @@ async fn head_selection_with_multiple_peers() {
     }
 
     // Client Node
-    let client = Node::new(NodeConfig {
-        p2p_bootnodes: server_addrs,
-        ..listening_test_node_config()
-    })
-    .await
-    .unwrap();
+    let client = listening_test_node_builder()
+        .bootnodes(server_addrs)
+        .start()
+        .await
+        .unwrap();
 
     client.wait_connected().await.unwrap();
 
@@ -208,13 +201,12 @@ async fn head_selection_with_multiple_peers() {
     let client_addr = client.listeners().await.unwrap();
 
     // Rogue node, connects to client so isn't trusted
-    let rogue_node = Node::new(NodeConfig {
-        store: gen_filled_store(26).0,
-        p2p_bootnodes: client_addr.clone(),
-        ..listening_test_node_config()
-    })
-    .await
-    .unwrap();
+    let rogue_node = listening_test_node_builder()
+        .store(gen_filled_store(26).0)
+        .bootnodes(client_addr.clone())
+        .start()
+        .await
+        .unwrap();
 
     rogue_node.wait_connected().await.unwrap();
     // small delay needed for client to include rogue_node in head selection process
@@ -225,13 +217,12 @@ async fn head_selection_with_multiple_peers() {
     assert_eq!(common_server_headers.last().unwrap(), &network_head);
 
     // new node from group B joins, head should go up
-    let new_b_node = Node::new(NodeConfig {
-        store: server_store.clone(),
-        p2p_bootnodes: client_addr,
-        ..test_node_config()
-    })
-    .await
-    .unwrap();
+    let new_b_node = test_node_builder()
+        .store(server_store.clone())
+        .bootnodes(client_addr)
+        .start()
+        .await
+        .unwrap();
 
     // Head requests are send only to trusted peers, so we add
     // `new_b_node` as trusted.
@@ -261,23 +252,21 @@ async fn replaced_header_server_store() {
         .await
         .unwrap();
 
-    let server = Node::new(NodeConfig {
-        store: server_store,
-        ..listening_test_node_config()
-    })
-    .await
-    .unwrap();
+    let server = listening_test_node_builder()
+        .store(server_store)
+        .start()
+        .await
+        .unwrap();
 
     // give server a sec to breathe, otherwise occiasionally client has problems with connecting
     sleep(Duration::from_millis(100)).await;
     let server_addrs = server.listeners().await.unwrap();
 
-    let client = Node::new(NodeConfig {
-        p2p_bootnodes: server_addrs,
-        ..listening_test_node_config()
-    })
-    .await
-    .unwrap();
+    let client = listening_test_node_builder()
+        .bootnodes(server_addrs)
+        .start()
+        .await
+        .unwrap();
 
     client.wait_connected().await.unwrap();
 
@@ -321,23 +310,21 @@ async fn invalidated_header_server_store() {
         .await
         .unwrap();
 
-    let server = Node::new(NodeConfig {
-        store: server_store,
-        ..listening_test_node_config()
-    })
-    .await
-    .unwrap();
+    let server = listening_test_node_builder()
+        .store(server_store)
+        .start()
+        .await
+        .unwrap();
 
     // give server a sec to breathe, otherwise occiasionally client has problems with connecting
     sleep(Duration::from_millis(100)).await;
     let server_addrs = server.listeners().await.unwrap();
 
-    let client = Node::new(NodeConfig {
-        p2p_bootnodes: server_addrs,
-        ..listening_test_node_config()
-    })
-    .await
-    .unwrap();
+    let client = listening_test_node_builder()
+        .bootnodes(server_addrs)
+        .start()
+        .await
+        .unwrap();
 
     client.wait_connected().await.unwrap();
 
@@ -383,23 +370,21 @@ async fn unverified_header_server_store() {
         .await
         .unwrap();
 
-    let server = Node::new(NodeConfig {
-        store: server_store,
-        ..listening_test_node_config()
-    })
-    .await
-    .unwrap();
+    let server = listening_test_node_builder()
+        .store(server_store)
+        .start()
+        .await
+        .unwrap();
 
     // give server a sec to breathe, otherwise occiasionally client has problems with connecting
     sleep(Duration::from_millis(100)).await;
     let server_addrs = server.listeners().await.unwrap();
 
-    let client = Node::new(NodeConfig {
-        p2p_bootnodes: server_addrs,
-        ..listening_test_node_config()
-    })
-    .await
-    .unwrap();
+    let client = listening_test_node_builder()
+        .bootnodes(server_addrs)
+        .start()
+        .await
+        .unwrap();
 
     client.wait_connected().await.unwrap();
 
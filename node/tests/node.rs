@@ -2,14 +2,22 @@
 
 use std::time::Duration;
 
+use blockstore::Blockstore;
+use celestia_types::row::IdLayout;
+use celestia_types::sample::Sample;
+use celestia_types::test_utils::ExtendedHeaderGenerator;
 use celestia_types::{consts::HASH_SIZE, hash::Hash};
-use libp2p::identity;
-use lumina_node::{
-    node::{Node, NodeConfig},
-    test_utils::{gen_filled_store, test_node_config, test_node_config_with_keypair},
-};
+use celestia_types::{AxisType, DataAvailabilityHeader, ExtendedDataSquare};
+use libp2p::{identity, ping};
+use lumina_node::node::NodeError;
+use lumina_node::p2p::{P2pError, TransportConfig};
+use lumina_node::store::{InMemoryStore, Store, StoreError};
+use lumina_node::syncer::SyncMode;
+use lumina_node::test_utils::{gen_filled_store, test_node_builder};
+use prost::Message;
 use rand::Rng;
-use tokio::time::sleep;
+use tokio::sync::mpsc;
+use tokio::time::{sleep, timeout};
 
 use crate::utils::{fetch_bridge_info, new_connected_node};
 
@@ -26,12 +34,7 @@ async fn connects_to_the_go_bridge_node() {
 #[tokio::test]
 async fn header_store_access() {
     let (store, _) = gen_filled_store(100);
-    let node = Node::new(NodeConfig {
-        store,
-        ..test_node_config()
-    })
-    .await
-    .unwrap();
+    let node = test_node_builder().store(store).start().await.unwrap();
 
     // check local head
     let head = node.get_local_head_header().await.unwrap();
@@ -81,6 +84,53 @@ async fn header_store_access() {
     }
 }
 
+#[tokio::test]
+async fn header_by_time_below_the_tail_is_requested_from_network() {
+    // seeded generator creates a header every second
+    let headers = ExtendedHeaderGenerator::new_with_seed(7).next_many(10);
+    let server_store = InMemoryStore::new();
+    server_store
+        .append_unchecked(headers.clone())
+        .await
+        .unwrap();
+
+    let server = test_node_builder()
+        .store(server_store)
+        .disable_syncing()
+        .listen_addrs(vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()])
+        .start()
+        .await
+        .unwrap();
+
+    // client syncs only the 3 newest headers
+    let client = test_node_builder()
+        .sync_mode(SyncMode::Backward { target_height: 8 })
+        .bootnodes(server.listeners().await.unwrap())
+        .start()
+        .await
+        .unwrap();
+
+    timeout(Duration::from_secs(10), client.await_synced(8))
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(
+        client.get_header_by_height(7).await.unwrap_err(),
+        NodeError::Store(StoreError::NotFound)
+    ));
+
+    for header in &headers[..7] {
+        let found = timeout(
+            Duration::from_secs(10),
+            client.get_header_by_time(header.time()),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(&found, header);
+    }
+}
+
 #[tokio::test]
 async fn peer_discovery() {
     // Bridge node cannot connect to other nodes because it is behind Docker's NAT.
@@ -91,13 +141,13 @@ async fn peer_discovery() {
     //
     // This node connects to Bridge node.
     let node1_keypair = identity::Keypair::generate_ed25519();
-    let node1 = Node::new(NodeConfig {
-        p2p_bootnodes: vec![bridge_ma],
-        p2p_listen_on: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
-        ..test_node_config_with_keypair(node1_keypair)
-    })
-    .await
-    .unwrap();
+    let node1 = test_node_builder()
+        .keypair(node1_keypair)
+        .bootnodes(vec![bridge_ma])
+        .listen_addrs(vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()])
+        .start()
+        .await
+        .unwrap();
 
     node1.wait_connected().await.unwrap();
 
@@ -107,13 +157,13 @@ async fn peer_discovery() {
     //
     // This node connects to Node1 and will discover Bridge node.
     let node2_keypair = identity::Keypair::generate_ed25519();
-    let node2 = Node::new(NodeConfig {
-        p2p_bootnodes: node1_addrs.clone(),
-        p2p_listen_on: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
-        ..test_node_config_with_keypair(node2_keypair)
-    })
-    .await
-    .unwrap();
+    let node2 = test_node_builder()
+        .keypair(node2_keypair)
+        .bootnodes(node1_addrs.clone())
+        .listen_addrs(vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()])
+        .start()
+        .await
+        .unwrap();
 
     node2.wait_connected().await.unwrap();
 
@@ -121,12 +171,12 @@ async fn peer_discovery() {
     //
     // This node connects to Node1 and will discover Node2 and Bridge node.
     let node3_keypair = identity::Keypair::generate_ed25519();
-    let node3 = Node::new(NodeConfig {
-        p2p_bootnodes: node1_addrs.clone(),
-        ..test_node_config_with_keypair(node3_keypair)
-    })
-    .await
-    .unwrap();
+    let node3 = test_node_builder()
+        .keypair(node3_keypair)
+        .bootnodes(node1_addrs.clone())
+        .start()
+        .await
+        .unwrap();
 
     node3.wait_connected().await.unwrap();
 
@@ -164,3 +214,129 @@ async fn peer_discovery() {
     assert!(tracker_info.num_connected_peers >= 3);
     assert_eq!(tracker_info.num_connected_trusted_peers, 1);
 }
+
+#[tokio::test]
+async fn quic_only_nodes_connect() {
+    let quic_only = TransportConfig::empty().quic(true);
+
+    let server = test_node_builder()
+        .transports(quic_only)
+        .listen_addrs(vec!["/ip4/127.0.0.1/udp/0/quic-v1".parse().unwrap()])
+        .start()
+        .await
+        .unwrap();
+
+    let server_addrs = server.listeners().await.unwrap();
+
+    let client = test_node_builder()
+        .transports(quic_only)
+        .bootnodes(server_addrs)
+        .start()
+        .await
+        .unwrap();
+
+    client.wait_connected().await.unwrap();
+
+    let connected_peers = client.connected_peers().await.unwrap();
+    assert_eq!(connected_peers, vec![*server.local_peer_id()]);
+
+    // Listening on the disabled transport fails
+    let res = test_node_builder()
+        .transports(quic_only)
+        .listen_addrs(vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()])
+        .start()
+        .await;
+    assert!(matches!(res, Err(NodeError::P2p(P2pError::Transport(_)))));
+
+    let res = test_node_builder()
+        .transports(TransportConfig::empty())
+        .start()
+        .await;
+    assert!(matches!(
+        res,
+        Err(NodeError::P2p(P2pError::NoTransportEnabled))
+    ));
+}
+
+#[tokio::test]
+async fn custom_behaviour_receives_events() {
+    let server = test_node_builder()
+        .listen_addrs(vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()])
+        .start()
+        .await
+        .unwrap();
+
+    let server_addrs = server.listeners().await.unwrap();
+
+    let (events_tx, mut events_rx) = mpsc::channel(8);
+    let ping_config = ping::Config::new().with_interval(Duration::from_millis(100));
+    let client = test_node_builder()
+        .bootnodes(server_addrs)
+        .custom_behaviour(|_| ping::Behaviour::new(ping_config), events_tx)
+        .start()
+        .await
+        .unwrap();
+
+    let event: ping::Event = timeout(Duration::from_secs(5), events_rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(event.peer, *server.local_peer_id());
+
+    client
+        .with_custom_behaviour(|_: &mut ping::Behaviour| ())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn nodes_with_different_shwap_layouts_exchange_samples() {
+    let eds_json = include_str!("../../types/test_data/shwap_samples/eds.json");
+    let eds: ExtendedDataSquare = serde_json::from_str(eds_json).unwrap();
+    let dah = DataAvailabilityHeader::from_eds(&eds).unwrap();
+    let header = ExtendedHeaderGenerator::new().next_with_dah(dah);
+    let height = header.height().value();
+    let sample = Sample::new(AxisType::Row, 0, &eds, height).unwrap();
+
+    // server keeps the sample in the draft layout
+    let server_store = InMemoryStore::new();
+    server_store
+        .append_single_unchecked(header.clone())
+        .await
+        .unwrap();
+    let data = sample.clone().into_raw(IdLayout::Draft).encode_to_vec();
+    server_store
+        .put_keyed(&sample.sample_id.to_cid(IdLayout::Draft), &data)
+        .await
+        .unwrap();
+
+    let server = test_node_builder()
+        .store(server_store)
+        .shwap_id_layout(IdLayout::Draft)
+        .disable_syncing()
+        .listen_addrs(vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()])
+        .start()
+        .await
+        .unwrap();
+
+    // client asks for it in the V1 layout over the same protocol
+    let client_store = InMemoryStore::new();
+    client_store.append_single_unchecked(header).await.unwrap();
+
+    let client = test_node_builder()
+        .store(client_store)
+        .shwap_id_layout(IdLayout::V1)
+        .disable_syncing()
+        .bootnodes(server.listeners().await.unwrap())
+        .start()
+        .await
+        .unwrap();
+
+    client.wait_connected().await.unwrap();
+
+    let share = timeout(Duration::from_secs(10), client.request_sample(height, 0, 0))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(share.data(), &sample.share[..]);
+}
@@ -3,8 +3,7 @@ use std::time::Duration;
 
 use celestia_rpc::{prelude::*, Client};
 use libp2p::{multiaddr::Protocol, Multiaddr, PeerId};
-use lumina_node::node::NodeConfig;
-use lumina_node::test_utils::test_node_config;
+use lumina_node::test_utils::test_node_builder;
 use lumina_node::{node::Node, store::InMemoryStore};
 use tokio::time::sleep;
 
@@ -33,12 +32,11 @@ pub async fn fetch_bridge_info() -> (PeerId, Multiaddr) {
 pub async fn new_connected_node() -> Node<InMemoryStore> {
     let (_, bridge_ma) = fetch_bridge_info().await;
 
-    let node = Node::new(NodeConfig {
-        p2p_bootnodes: vec![bridge_ma],
-        ..test_node_config()
-    })
-    .await
-    .unwrap();
+    let node = test_node_builder()
+        .bootnodes(vec![bridge_ma])
+        .start()
+        .await
+        .unwrap();
 
     node.wait_connected_trusted().await.unwrap();
 
@@ -56,6 +56,30 @@ static CUSTOM_FIELD_ATTRIBUTES: &[(&str, &str)] = &[
     (".share.p2p.shrex.nd.NamespaceRowResponse.shares", VEC_BASE64STRING),
 ];
 
+const PROTO_FILES: &[&str] = &[
+    "vendor/bitswap/pb/message.proto",
+    "vendor/celestia/da/data_availability_header.proto",
+    "vendor/celestia/blob/v1/query.proto",
+    "vendor/celestia/blob/v1/tx.proto",
+    "vendor/header/pb/extended_header.proto",
+    "vendor/share/p2p/shrexnd/pb/share.proto",
+    "vendor/share/p2p/shwap/pb/shwap.proto",
+    "vendor/share/eds/byzantine/pb/share.proto",
+    "vendor/cosmos/auth/v1beta1/auth.proto",
+    "vendor/cosmos/auth/v1beta1/query.proto",
+    "vendor/cosmos/base/node/v1beta1/query.proto",
+    "vendor/cosmos/base/v1beta1/coin.proto",
+    "vendor/cosmos/base/abci/v1beta1/abci.proto",
+    "vendor/cosmos/crypto/multisig/v1beta1/multisig.proto",
+    "vendor/cosmos/crypto/secp256k1/keys.proto",
+    "vendor/cosmos/staking/v1beta1/query.proto",
+    "vendor/cosmos/tx/v1beta1/service.proto",
+    "vendor/cosmos/tx/v1beta1/tx.proto",
+    "vendor/go-header/p2p/pb/header_request.proto",
+];
+
+const INCLUDES: &[&str] = &["vendor", "vendor/nmt"];
+
 fn main() -> Result<()> {
     let mut config = prost_build::Config::new();
 
@@ -67,6 +91,13 @@ fn main() -> Result<()> {
         config.field_attribute(field_path, attr);
     }
 
+    // shares are kept as `Bytes` in celestia-types, so decode them without copying
+    config.bytes([
+        ".share.p2p.shwap.Row.row_half",
+        ".share.p2p.shwap.Sample.sample_share",
+        ".share.p2p.shwap.Data.data_shares",
+    ]);
+
     config
         .include_file("mod.rs")
         .extern_path(".tendermint", "::celestia_tendermint_proto::v0_34")
@@ -79,24 +110,17 @@ fn main() -> Result<()> {
             "::celestia_tendermint_proto::google::protobuf::Duration",
         )
         // Comments in Google's protobuf are causing issues with cargo-test
-        .disable_comments([".google"])
-        .compile_protos(
-            &[
-                "vendor/celestia/da/data_availability_header.proto",
-                "vendor/celestia/blob/v1/tx.proto",
-                "vendor/header/pb/extended_header.proto",
-                "vendor/share/p2p/shrexnd/pb/share.proto",
-                "vendor/share/p2p/shwap/pb/shwap.proto",
-                "vendor/share/eds/byzantine/pb/share.proto",
-                "vendor/cosmos/base/v1beta1/coin.proto",
-                "vendor/cosmos/base/abci/v1beta1/abci.proto",
-                "vendor/cosmos/crypto/multisig/v1beta1/multisig.proto",
-                "vendor/cosmos/staking/v1beta1/query.proto",
-                "vendor/cosmos/tx/v1beta1/tx.proto",
-                "vendor/go-header/p2p/pb/header_request.proto",
-            ],
-            &["vendor", "vendor/nmt"],
-        )?;
+        .disable_comments([".google"]);
+
+    #[cfg(not(feature = "tonic"))]
+    config.compile_protos(PROTO_FILES, INCLUDES)?;
+
+    #[cfg(feature = "tonic")]
+    tonic_build::configure()
+        .build_client(true)
+        .build_server(false)
+        .build_transport(false)
+        .compile_with_config(config, PROTO_FILES, INCLUDES)?;
 
     Ok(())
 }
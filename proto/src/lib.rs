@@ -1,7 +1,10 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![allow(clippy::all)]
 #![allow(missing_docs)]
 #![doc = include_str!("../README.md")]
 
+extern crate alloc;
+
 pub mod serializers;
 
 include!(concat!(env!("OUT_DIR"), "/mod.rs"));
@@ -1,5 +1,8 @@
 //! [`serde`] serializer for the optional [`Any`].
 
+use alloc::string::String;
+use alloc::vec::Vec;
+
 use prost_types::Any;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
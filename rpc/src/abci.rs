@@ -0,0 +1,90 @@
+use async_trait::async_trait;
+use celestia_types::state::{
+    balance_key, AbciQueryResponse, AccAddress, Balance, TxStatusResponse,
+};
+use celestia_types::ExtendedHeader;
+use jsonrpsee::proc_macros::rpc;
+
+/// Path of the raw key-value queries to the bank module's store.
+const BANK_STORE_QUERY_PATH: &str = "/store/bank/key";
+
+/// Denomination of the Celestia's native coin.
+const DENOM: &str = "utia";
+
+/// Queries of the application state, served by the consensus node.
+///
+/// # NOTE
+///
+/// Unlike the rest of the traits in this crate, this one needs to be used with a client
+/// connected to the CometBFT RPC of a consensus node, not to the Celestia node.
+#[rpc(client)]
+pub trait Abci {
+    /// Query the application state under the path at the given height, optionally with a merkle proof.
+    #[method(name = "abci_query", param_kind = map)]
+    async fn abci_query(
+        &self,
+        path: &str,
+        data: &str,
+        height: &str,
+        prove: bool,
+    ) -> Result<AbciQueryResponse, Error>;
+
+    /// Get the status of the transaction with the given hash, encoded in base64.
+    #[method(name = "tx_status", param_kind = map)]
+    async fn tx_status(&self, hash: &str) -> Result<TxStatusResponse, Error>;
+}
+
+/// Extension of the [`AbciClient`] with queries verified against the header's `AppHash`.
+#[async_trait]
+pub trait AbciClientExt: AbciClient + Sync {
+    /// Get the balance of the address with a proof and verify it against the `AppHash` of the header.
+    ///
+    /// The header is trusted, so it must be obtained from a verified source, eg. a light node.
+    /// The balance is queried at the height preceding the header's height, as it is the state
+    /// that the header commits to.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NoPrecedingState`] for the first header, which commits to the genesis
+    /// state, and [`Error::UnexpectedQueryHeight`] if the node answered for another height.
+    ///
+    /// [`Error::NoPrecedingState`]: crate::Error::NoPrecedingState
+    /// [`Error::UnexpectedQueryHeight`]: crate::Error::UnexpectedQueryHeight
+    async fn get_balance_with_proof(
+        &self,
+        address: &AccAddress,
+        header: &ExtendedHeader,
+    ) -> crate::Result<Balance> {
+        let key = balance_key(address, DENOM);
+        // state committed to in the first header is the genesis, which can't be queried
+        let height = header
+            .height()
+            .value()
+            .checked_sub(1)
+            .filter(|height| *height > 0)
+            .ok_or(crate::Error::NoPrecedingState(header.height().value()))?;
+
+        let response = self
+            .abci_query(
+                BANK_STORE_QUERY_PATH,
+                &hex::encode_upper(key),
+                &height.to_string(),
+                true,
+            )
+            .await?;
+
+        if response.response.height != height {
+            return Err(crate::Error::UnexpectedQueryHeight {
+                expected: height,
+                got: response.response.height,
+            });
+        }
+
+        let (balance, proof) = response.response.into_balance_with_proof(DENOM)?;
+        proof.verify(address, &balance, header)?;
+
+        Ok(balance)
+    }
+}
+
+impl<T> AbciClientExt for T where T: AbciClient + Sync {}
@@ -1,5 +1,7 @@
+use async_trait::async_trait;
+use celestia_types::blob::{BlobsAtHeight, SubmitOptions};
 use celestia_types::nmt::{Namespace, NamespaceProof};
-use celestia_types::{blob::SubmitOptions, Blob, Commitment};
+use celestia_types::{Blob, Commitment, ExtendedHeader, VerificationError};
 use jsonrpsee::proc_macros::rpc;
 
 #[rpc(client)]
@@ -37,7 +39,92 @@ pub trait Blob {
         commitment: Commitment,
     ) -> Result<bool, Error>;
 
+    /// Subscribe to the blobs of the given namespace in every new block.
+    ///
+    /// # Notes
+    ///
+    /// Unsubscribe is not implemented by Celestia nodes.
+    #[subscription(name = "blob.Subscribe", unsubscribe = "blob.Unsubscribe", item = BlobsAtHeight)]
+    async fn blob_subscribe(&self, namespace: Namespace) -> SubcriptionResult;
+
     /// Submit sends Blobs and reports the height in which they were included. Allows sending multiple Blobs atomically synchronously. Uses default wallet registered on the Node.
     #[method(name = "blob.Submit")]
     async fn blob_submit(&self, blobs: &[Blob], opts: SubmitOptions) -> Result<u64, Error>;
 }
+
+/// Extension of the [`BlobClient`] with blobs verified against the header's
+/// [`DataAvailabilityHeader`].
+///
+/// The verification needs the [`Blob::index`] to locate the blob's shares, so the
+/// methods fail with [`MissingBlobIndex`] with the nodes that don't return it.
+///
+/// [`DataAvailabilityHeader`]: celestia_types::DataAvailabilityHeader
+/// [`MissingBlobIndex`]: celestia_types::Error::MissingBlobIndex
+#[async_trait]
+pub trait BlobClientExt: BlobClient + Sync {
+    /// Get the blob by commitment under the given namespace and verify its inclusion in the block.
+    ///
+    /// The header is trusted, so it must be obtained from a verified source, eg. a light node.
+    /// The proof of the blob is fetched with the `blob.GetProof` and checked locally
+    /// with [`Blob::verify_inclusion`].
+    async fn blob_get_verified(
+        &self,
+        header: &ExtendedHeader,
+        namespace: Namespace,
+        commitment: Commitment,
+    ) -> crate::Result<Blob> {
+        let height = header.height().value();
+        let blob = self.blob_get(height, namespace, commitment).await?;
+
+        if blob.namespace != namespace || blob.commitment != commitment {
+            let err = VerificationError::Other(format!(
+                "received blob of namespace {:?} and commitment {}",
+                blob.namespace, blob.commitment
+            ));
+            return Err(celestia_types::Error::from(err).into());
+        }
+
+        let proofs = self.blob_get_proof(height, namespace, commitment).await?;
+        blob.verify_inclusion(&proofs, &header.dah, header.app_version()?)?;
+
+        Ok(blob)
+    }
+
+    /// Get all the blobs under the given namespaces and verify their inclusion in the block.
+    ///
+    /// The header is trusted, so it must be obtained from a verified source, eg. a light node.
+    /// The proof of each of the blobs is fetched with the `blob.GetProof` and checked locally
+    /// with [`Blob::verify_inclusion`].
+    ///
+    /// # NOTE
+    ///
+    /// The proofs show that the returned blobs are included in the block, but not that
+    /// none of the blobs in the namespaces were left out.
+    async fn blob_get_all_verified(
+        &self,
+        header: &ExtendedHeader,
+        namespaces: &[Namespace],
+    ) -> crate::Result<Vec<Blob>> {
+        let height = header.height().value();
+        let blobs = self.blob_get_all(height, namespaces).await?;
+
+        for blob in &blobs {
+            if !namespaces.contains(&blob.namespace) {
+                let err = VerificationError::Other(format!(
+                    "received blob of unrequested namespace {:?}",
+                    blob.namespace
+                ));
+                return Err(celestia_types::Error::from(err).into());
+            }
+
+            let proofs = self
+                .blob_get_proof(height, blob.namespace, blob.commitment)
+                .await?;
+            blob.verify_inclusion(&proofs, &header.dah, header.app_version()?)?;
+        }
+
+        Ok(blobs)
+    }
+}
+
+impl<T> BlobClientExt for T where T: BlobClient + Sync {}
@@ -4,14 +4,23 @@
 //! you need more configuration options and / or some custom client you can create
 //! one using [`jsonrpsee`] crate directly.
 
+#[cfg(not(target_arch = "wasm32"))]
+pub use self::failover::{
+    EndpointHealth, FailoverClient, FailoverConfig, FailoverPolicy, DEFAULT_MAX_RECHECK_INTERVAL,
+    DEFAULT_RECHECK_INTERVAL,
+};
 #[cfg(not(target_arch = "wasm32"))]
 pub use self::native::Client;
 
+#[cfg(not(target_arch = "wasm32"))]
+mod failover;
+
 #[cfg(not(target_arch = "wasm32"))]
 mod native {
     use std::fmt;
     use std::result::Result as StdResult;
 
+    use super::{FailoverClient, FailoverConfig};
     use crate::{Error, Result};
     use async_trait::async_trait;
     use http::{header, HeaderValue};
@@ -29,6 +38,8 @@ mod native {
         Http(HttpClient),
         /// A client using 'ws\[s\]' protocol.
         Ws(WsClient),
+        /// A client failing over between multiple endpoints.
+        Failover(FailoverClient),
     }
 
     impl Client {
@@ -66,6 +77,25 @@ mod native {
 
             Ok(client)
         }
+
+        /// Create a new Json RPC client sending the requests to multiple endpoints.
+        ///
+        /// Endpoints are tried in the order of the [`FailoverPolicy`] and the unreachable
+        /// ones are skipped until they pass the health check. Creating the client fails
+        /// only if none of the endpoints could be connected, or [`Error::NoEndpoints`] if
+        /// none were given. See [`FailoverClient`] for details.
+        ///
+        /// [`FailoverPolicy`]: super::FailoverPolicy
+        /// [`Error::NoEndpoints`]: crate::Error::NoEndpoints
+        pub async fn new_with_failover(
+            conn_strs: &[&str],
+            auth_token: Option<&str>,
+            config: FailoverConfig,
+        ) -> Result<Self> {
+            FailoverClient::new(conn_strs, auth_token, config)
+                .await
+                .map(Client::Failover)
+        }
     }
 
     #[async_trait]
@@ -81,6 +111,7 @@ mod native {
             match self {
                 Client::Http(client) => client.notification(method, params).await,
                 Client::Ws(client) => client.notification(method, params).await,
+                Client::Failover(client) => client.notification(method, params).await,
             }
         }
 
@@ -92,6 +123,7 @@ mod native {
             match self {
                 Client::Http(client) => client.request(method, params).await,
                 Client::Ws(client) => client.request(method, params).await,
+                Client::Failover(client) => client.request(method, params).await,
             }
         }
 
@@ -105,6 +137,7 @@ mod native {
             match self {
                 Client::Http(client) => client.batch_request(batch).await,
                 Client::Ws(client) => client.batch_request(batch).await,
+                Client::Failover(client) => client.batch_request(batch).await,
             }
         }
     }
@@ -132,6 +165,11 @@ mod native {
                         .subscribe(subscribe_method, params, unsubscribe_method)
                         .await
                 }
+                Client::Failover(client) => {
+                    client
+                        .subscribe(subscribe_method, params, unsubscribe_method)
+                        .await
+                }
             }
         }
 
@@ -145,6 +183,7 @@ mod native {
             match self {
                 Client::Http(client) => client.subscribe_to_method(method).await,
                 Client::Ws(client) => client.subscribe_to_method(method).await,
+                Client::Failover(client) => client.subscribe_to_method(method).await,
             }
         }
     }
@@ -0,0 +1,556 @@
+//! Client multiplexing the requests over multiple endpoints.
+
+use std::fmt;
+use std::future::Future;
+use std::result::Result as StdResult;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use jsonrpsee::core::client::{BatchResponse, ClientT, Subscription, SubscriptionClientT};
+use jsonrpsee::core::params::BatchRequestBuilder;
+use jsonrpsee::core::traits::ToRpcParams;
+use jsonrpsee::core::Error as JrpcError;
+use serde::de::DeserializeOwned;
+use serde_json::value::RawValue;
+use tracing::{debug, warn};
+
+use super::Client;
+use crate::{Error, HeaderClient, Result};
+
+/// Default time after which an unhealthy endpoint is checked again.
+pub const DEFAULT_RECHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// Default limit of the time between the checks of an endpoint that keeps failing.
+pub const DEFAULT_MAX_RECHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Methods changing the state, which are not sent again once they could have reached
+/// the node.
+const NON_IDEMPOTENT_METHODS: &[&str] = &[
+    "blob.Submit",
+    "state.BeginRedelegate",
+    "state.CancelUnbondingDelegation",
+    "state.Delegate",
+    "state.SubmitPayForBlob",
+    "state.SubmitTx",
+    "state.Transfer",
+    "state.Undelegate",
+];
+
+/// Order in which the endpoints are tried by the [`Client::Failover`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FailoverPolicy {
+    /// Send the requests to the first healthy endpoint in the order they were given,
+    /// returning to the preferred endpoints once they are healthy again.
+    #[default]
+    Priority,
+    /// Spread the requests over the healthy endpoints in turns.
+    RoundRobin,
+}
+
+/// Configuration of the [`Client::Failover`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailoverConfig {
+    /// Order in which the endpoints are tried.
+    pub policy: FailoverPolicy,
+    /// Time after which an unhealthy endpoint is reconnected and checked again.
+    ///
+    /// It doubles with each consecutive failure of the endpoint, up to the
+    /// `max_recheck_interval`.
+    pub recheck_interval: Duration,
+    /// Limit of the time between the checks of an endpoint that keeps failing.
+    pub max_recheck_interval: Duration,
+}
+
+impl FailoverConfig {
+    /// Time until the next check of an endpoint that failed the given number of times in a row.
+    fn backoff(&self, failures: u32) -> Duration {
+        let exp = failures.saturating_sub(1).min(31);
+        self.recheck_interval
+            .saturating_mul(1 << exp)
+            .min(self.max_recheck_interval.max(self.recheck_interval))
+    }
+}
+
+impl Default for FailoverConfig {
+    fn default() -> Self {
+        FailoverConfig {
+            policy: FailoverPolicy::default(),
+            recheck_interval: DEFAULT_RECHECK_INTERVAL,
+            max_recheck_interval: DEFAULT_MAX_RECHECK_INTERVAL,
+        }
+    }
+}
+
+/// Health of an endpoint of the [`Client::Failover`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EndpointHealth {
+    /// Connection string of the endpoint.
+    pub url: String,
+    /// Whether the endpoint responded to the last request or health check.
+    pub healthy: bool,
+}
+
+/// Json RPC client sending the requests to one of the multiple endpoints and failing
+/// over to the next one when it is unreachable.
+///
+/// Only the connection errors cause the failover, errors returned by the node are
+/// passed to the caller. Methods changing the state, like `blob.Submit`, are sent to
+/// another endpoint only if the request surely didn't leave, as they must not be
+/// executed twice. Failed endpoints are skipped until the
+/// [`FailoverConfig::recheck_interval`] elapses, backing off with each consecutive
+/// failure, then they are reconnected and their liveness is checked with
+/// `header.NetworkHead` before sending them a request again.
+pub struct FailoverClient {
+    endpoints: Vec<Endpoint>,
+    auth_token: Option<String>,
+    config: FailoverConfig,
+    next: AtomicUsize,
+}
+
+struct Endpoint {
+    url: String,
+    state: Mutex<EndpointState>,
+}
+
+struct EndpointState {
+    client: Option<Arc<Client>>,
+    // `None` while the endpoint is healthy
+    recheck_at: Option<Instant>,
+    // consecutive failures, reset once the endpoint responds
+    failures: u32,
+}
+
+impl FailoverClient {
+    pub(super) async fn new(
+        conn_strs: &[&str],
+        auth_token: Option<&str>,
+        config: FailoverConfig,
+    ) -> Result<Self> {
+        let mut endpoints = Vec::with_capacity(conn_strs.len());
+        let mut last_error = None;
+
+        for conn_str in conn_strs {
+            let state = match Client::new(conn_str, auth_token).await {
+                Ok(client) => EndpointState {
+                    client: Some(Arc::new(client)),
+                    recheck_at: None,
+                    failures: 0,
+                },
+                // misconfiguration is not recovered by a failover
+                Err(e @ (Error::ProtocolNotSupported(_) | Error::InvalidCharactersInToken(_))) => {
+                    return Err(e)
+                }
+                Err(e) => {
+                    warn!("Connecting to {conn_str} failed: {e}");
+                    last_error = Some(e);
+                    EndpointState {
+                        client: None,
+                        recheck_at: Some(Instant::now() + config.backoff(1)),
+                        failures: 1,
+                    }
+                }
+            };
+
+            endpoints.push(Endpoint {
+                url: conn_str.to_string(),
+                state: Mutex::new(state),
+            });
+        }
+
+        if endpoints.iter().all(|endpoint| !endpoint.is_healthy()) {
+            return Err(last_error.unwrap_or(Error::NoEndpoints));
+        }
+
+        Ok(FailoverClient {
+            endpoints,
+            auth_token: auth_token.map(ToOwned::to_owned),
+            config,
+            next: AtomicUsize::new(0),
+        })
+    }
+
+    /// Check the liveness of all the endpoints with `header.NetworkHead`, reconnecting
+    /// the unhealthy ones.
+    pub async fn check_health(&self) -> Vec<EndpointHealth> {
+        let mut health = Vec::with_capacity(self.endpoints.len());
+
+        for endpoint in &self.endpoints {
+            let healthy = self.check_endpoint(endpoint).await.is_some();
+
+            health.push(EndpointHealth {
+                url: endpoint.url.clone(),
+                healthy,
+            });
+        }
+
+        health
+    }
+
+    /// Health of the endpoints, as of the last request or health check.
+    pub fn health(&self) -> Vec<EndpointHealth> {
+        self.endpoints
+            .iter()
+            .map(|endpoint| EndpointHealth {
+                url: endpoint.url.clone(),
+                healthy: endpoint.is_healthy(),
+            })
+            .collect()
+    }
+
+    /// Indexes of the endpoints in the order they should be tried.
+    ///
+    /// Endpoints that can't be used yet are at the end, as the last resort.
+    fn candidates(&self, now: Instant) -> Vec<usize> {
+        let len = self.endpoints.len();
+        let start = match self.config.policy {
+            FailoverPolicy::Priority => 0,
+            FailoverPolicy::RoundRobin => self.next.fetch_add(1, Ordering::Relaxed) % len,
+        };
+
+        let (mut ready, waiting): (Vec<_>, Vec<_>) = (0..len)
+            .map(|i| (start + i) % len)
+            .partition(|&i| self.endpoints[i].is_ready(now));
+
+        ready.extend(waiting);
+        ready
+    }
+
+    /// Returns the client of the endpoint, reconnecting and checking it first if it
+    /// was unhealthy.
+    async fn check_endpoint(&self, endpoint: &Endpoint) -> Option<Arc<Client>> {
+        let client = match endpoint.client() {
+            Some(client) if endpoint.is_healthy() => client,
+            _ => match Client::new(&endpoint.url, self.auth_token.as_deref()).await {
+                Ok(client) => Arc::new(client),
+                Err(e) => {
+                    debug!("Reconnecting to {} failed: {e}", endpoint.url);
+                    endpoint.mark_unhealthy(&self.config);
+                    return None;
+                }
+            },
+        };
+
+        match client.header_network_head().await {
+            Ok(_) => {
+                endpoint.mark_healthy(client.clone());
+                Some(client)
+            }
+            Err(e) => {
+                debug!("Health check of {} failed: {e}", endpoint.url);
+                endpoint.mark_unhealthy(&self.config);
+                None
+            }
+        }
+    }
+
+    /// Execute the call on the endpoints until one of them responds.
+    ///
+    /// Calls which are not `resendable` are tried on the next endpoint only if they
+    /// surely didn't reach the previous one.
+    async fn call<F, Fut, T>(&self, resendable: bool, f: F) -> StdResult<T, JrpcError>
+    where
+        F: Fn(Arc<Client>) -> Fut,
+        Fut: Future<Output = StdResult<T, JrpcError>>,
+    {
+        let mut last_error = None;
+
+        for i in self.candidates(Instant::now()) {
+            let endpoint = &self.endpoints[i];
+
+            let client = match endpoint.client() {
+                Some(client) if endpoint.is_healthy() => client,
+                _ => match self.check_endpoint(endpoint).await {
+                    Some(client) => client,
+                    None => continue,
+                },
+            };
+
+            match f(client).await {
+                Err(e) if is_connection_error(&e) => {
+                    endpoint.mark_unhealthy(&self.config);
+
+                    if !resendable && !is_unsent_error(&e) {
+                        warn!("Request to {} failed, not resending: {e}", endpoint.url);
+                        return Err(e);
+                    }
+
+                    warn!("Request to {} failed, failing over: {e}", endpoint.url);
+                    last_error = Some(e);
+                }
+                res => return res,
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| JrpcError::Custom("No healthy endpoint".into())))
+    }
+}
+
+impl Endpoint {
+    fn state(&self) -> std::sync::MutexGuard<'_, EndpointState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn client(&self) -> Option<Arc<Client>> {
+        self.state().client.clone()
+    }
+
+    fn is_healthy(&self) -> bool {
+        self.state().recheck_at.is_none()
+    }
+
+    fn is_ready(&self, now: Instant) -> bool {
+        self.state()
+            .recheck_at
+            .map_or(true, |recheck_at| recheck_at <= now)
+    }
+
+    fn mark_healthy(&self, client: Arc<Client>) {
+        let mut state = self.state();
+        state.client = Some(client);
+        state.recheck_at = None;
+        state.failures = 0;
+    }
+
+    fn mark_unhealthy(&self, config: &FailoverConfig) {
+        let mut state = self.state();
+        state.failures = state.failures.saturating_add(1);
+        state.recheck_at = Some(Instant::now() + config.backoff(state.failures));
+    }
+}
+
+impl fmt::Debug for FailoverClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FailoverClient")
+            .field("endpoints", &self.health())
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Whether the error means the endpoint couldn't be reached, rather than the node
+/// rejecting the request.
+fn is_connection_error(error: &JrpcError) -> bool {
+    matches!(
+        error,
+        JrpcError::Transport(_) | JrpcError::RestartNeeded(_) | JrpcError::RequestTimeout
+    )
+}
+
+/// Whether the request surely wasn't sent, because the connection was already gone.
+fn is_unsent_error(error: &JrpcError) -> bool {
+    matches!(error, JrpcError::RestartNeeded(_))
+}
+
+/// Whether the method can be sent again without the risk of executing it twice.
+fn is_idempotent(method: &str) -> bool {
+    !NON_IDEMPOTENT_METHODS.contains(&method)
+}
+
+/// Parameters serialized once, so that they can be resent to another endpoint.
+#[derive(Clone)]
+struct RawParams(Option<Box<RawValue>>);
+
+impl RawParams {
+    fn new(params: impl ToRpcParams) -> StdResult<Self, JrpcError> {
+        params
+            .to_rpc_params()
+            .map(RawParams)
+            .map_err(JrpcError::ParseError)
+    }
+}
+
+impl ToRpcParams for RawParams {
+    fn to_rpc_params(self) -> StdResult<Option<Box<RawValue>>, serde_json::Error> {
+        Ok(self.0)
+    }
+}
+
+impl FailoverClient {
+    pub(super) async fn notification<Params>(
+        &self,
+        method: &str,
+        params: Params,
+    ) -> StdResult<(), JrpcError>
+    where
+        Params: ToRpcParams + Send,
+    {
+        let params = RawParams::new(params)?;
+        self.call(is_idempotent(method), |client| {
+            let params = params.clone();
+            async move { client.notification(method, params).await }
+        })
+        .await
+    }
+
+    pub(super) async fn request<R, Params>(
+        &self,
+        method: &str,
+        params: Params,
+    ) -> StdResult<R, JrpcError>
+    where
+        R: DeserializeOwned,
+        Params: ToRpcParams + Send,
+    {
+        let params = RawParams::new(params)?;
+        self.call(is_idempotent(method), |client| {
+            let params = params.clone();
+            async move { client.request(method, params).await }
+        })
+        .await
+    }
+
+    pub(super) async fn batch_request<'a, R>(
+        &self,
+        batch: BatchRequestBuilder<'a>,
+    ) -> StdResult<BatchResponse<'a, R>, JrpcError>
+    where
+        R: DeserializeOwned + fmt::Debug + 'a,
+    {
+        // methods of the batch are not known, so it's not resent to be safe
+        self.call(false, |client| {
+            let batch = batch.clone();
+            async move { client.batch_request(batch).await }
+        })
+        .await
+    }
+
+    pub(super) async fn subscribe<'a, N, Params>(
+        &self,
+        subscribe_method: &'a str,
+        params: Params,
+        unsubscribe_method: &'a str,
+    ) -> StdResult<Subscription<N>, JrpcError>
+    where
+        Params: ToRpcParams + Send,
+        N: DeserializeOwned,
+    {
+        let params = RawParams::new(params)?;
+        self.call(true, |client| {
+            let params = params.clone();
+            async move {
+                client
+                    .subscribe(subscribe_method, params, unsubscribe_method)
+                    .await
+            }
+        })
+        .await
+    }
+
+    pub(super) async fn subscribe_to_method<'a, N>(
+        &self,
+        method: &'a str,
+    ) -> StdResult<Subscription<N>, JrpcError>
+    where
+        N: DeserializeOwned,
+    {
+        self.call(true, |client| async move {
+            client.subscribe_to_method(method).await
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failover_client(healthy: &[bool], policy: FailoverPolicy) -> FailoverClient {
+        let now = Instant::now();
+        let endpoints = healthy
+            .iter()
+            .enumerate()
+            .map(|(i, &healthy)| Endpoint {
+                url: format!("http://localhost:{}", 26658 + i),
+                state: Mutex::new(EndpointState {
+                    client: None,
+                    recheck_at: (!healthy).then(|| now + DEFAULT_RECHECK_INTERVAL),
+                    failures: u32::from(!healthy),
+                }),
+            })
+            .collect();
+
+        FailoverClient {
+            endpoints,
+            auth_token: None,
+            config: FailoverConfig {
+                policy,
+                ..FailoverConfig::default()
+            },
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    #[test]
+    fn priority_candidates() {
+        let client = failover_client(&[false, true, true], FailoverPolicy::Priority);
+        let now = Instant::now();
+
+        assert_eq!(client.candidates(now), vec![1, 2, 0]);
+        assert_eq!(client.candidates(now), vec![1, 2, 0]);
+
+        // unhealthy endpoint is preferred again once it should be rechecked
+        let later = now + DEFAULT_RECHECK_INTERVAL + Duration::from_secs(1);
+        assert_eq!(client.candidates(later), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn round_robin_candidates() {
+        let client = failover_client(&[true, false, true], FailoverPolicy::RoundRobin);
+        let now = Instant::now();
+
+        assert_eq!(client.candidates(now), vec![0, 2, 1]);
+        assert_eq!(client.candidates(now), vec![2, 0, 1]);
+        assert_eq!(client.candidates(now), vec![2, 0, 1]);
+        assert_eq!(client.candidates(now), vec![0, 2, 1]);
+    }
+
+    #[test]
+    fn health() {
+        let client = failover_client(&[true, false], FailoverPolicy::Priority);
+
+        let health = client.health();
+        assert!(health[0].healthy);
+        assert!(!health[1].healthy);
+
+        client.endpoints[0].mark_unhealthy(&client.config);
+        assert!(!client.health()[0].healthy);
+    }
+
+    #[test]
+    fn backoff_of_failing_endpoint() {
+        let config = FailoverConfig {
+            recheck_interval: Duration::from_secs(10),
+            max_recheck_interval: Duration::from_secs(60),
+            ..FailoverConfig::default()
+        };
+
+        assert_eq!(config.backoff(1), Duration::from_secs(10));
+        assert_eq!(config.backoff(2), Duration::from_secs(20));
+        assert_eq!(config.backoff(3), Duration::from_secs(40));
+        assert_eq!(config.backoff(4), Duration::from_secs(60));
+        assert_eq!(config.backoff(u32::MAX), Duration::from_secs(60));
+
+        let client = failover_client(&[true], FailoverPolicy::Priority);
+        let endpoint = &client.endpoints[0];
+        endpoint.mark_unhealthy(&client.config);
+        endpoint.mark_unhealthy(&client.config);
+        assert_eq!(endpoint.state().failures, 2);
+        let now = Instant::now();
+        assert!(!endpoint.is_ready(now + DEFAULT_RECHECK_INTERVAL));
+        assert!(endpoint.is_ready(now + 2 * DEFAULT_RECHECK_INTERVAL));
+    }
+
+    #[test]
+    fn only_idempotent_methods_are_resent() {
+        assert!(is_idempotent("header.GetByHeight"));
+        assert!(is_idempotent("blob.Get"));
+        assert!(!is_idempotent("blob.Submit"));
+        assert!(!is_idempotent("state.SubmitPayForBlob"));
+
+        assert!(is_connection_error(&JrpcError::RequestTimeout));
+        assert!(!is_unsent_error(&JrpcError::RequestTimeout));
+        assert!(!is_unsent_error(&JrpcError::Transport(anyhow::anyhow!(
+            "connection reset"
+        ))));
+    }
+}
@@ -17,7 +17,28 @@ pub enum Error {
     #[error("Protocol not supported or missing: {0}")]
     ProtocolNotSupported(String),
 
+    /// No endpoints were given to the failover client.
+    #[error("No endpoints given")]
+    NoEndpoints,
+
+    /// Error propagated from the [`celestia_types`].
+    #[error(transparent)]
+    CelestiaTypes(#[from] celestia_types::Error),
+
     /// Error propagated from the [`jsonrpsee`].
     #[error(transparent)]
     JsonRpc(#[from] jsonrpsee::core::Error),
+
+    /// State committed to in the header can't be queried, as it precedes the first block.
+    #[error("No state to query preceding the header at height {0}")]
+    NoPrecedingState(u64),
+
+    /// Consensus node answered the query at a different height than requested.
+    #[error("Query answered at height {got}, expected {expected}")]
+    UnexpectedQueryHeight {
+        /// Requested height.
+        expected: u64,
+        /// Height of the response.
+        got: u64,
+    },
 }
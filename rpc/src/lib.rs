@@ -1,6 +1,7 @@
 #![cfg_attr(docs_rs, feature(doc_cfg))]
 #![doc = include_str!("../README.md")]
 
+mod abci;
 mod blob;
 pub mod client;
 mod error;
@@ -10,7 +11,8 @@ mod p2p;
 mod share;
 mod state;
 
-pub use crate::blob::BlobClient;
+pub use crate::abci::{AbciClient, AbciClientExt};
+pub use crate::blob::{BlobClient, BlobClientExt};
 #[cfg(not(target_arch = "wasm32"))]
 pub use crate::client::Client;
 pub use crate::error::{Error, Result};
@@ -23,7 +25,10 @@ pub use crate::state::StateClient;
 
 /// Re-exports of all the RPC traits.
 pub mod prelude {
+    pub use crate::AbciClient;
+    pub use crate::AbciClientExt;
     pub use crate::BlobClient;
+    pub use crate::BlobClientExt;
     pub use crate::HeaderClient;
     #[cfg(feature = "p2p")]
     pub use crate::P2PClient;
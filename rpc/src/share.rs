@@ -1,5 +1,7 @@
 use celestia_types::nmt::Namespace;
-use celestia_types::{ExtendedDataSquare, ExtendedHeader, NamespacedShares, Share};
+use celestia_types::{
+    ExtendedDataSquare, ExtendedHeader, GetRangeResponse, NamespacedShares, Share, SharesAtHeight,
+};
 use jsonrpsee::proc_macros::rpc;
 
 #[rpc(client)]
@@ -17,6 +19,19 @@ pub trait Share {
         col: u64,
     ) -> Result<Share, Error>;
 
+    /// GetRange gets a list of shares and their corresponding proof.
+    ///
+    /// The range is given by the indexes of the shares in the original data square, with
+    /// the `end` excluded. The returned proof can be verified against the block's data root
+    /// with [`GetRangeResponse::verify`], given the same range.
+    #[method(name = "share.GetRange")]
+    async fn share_get_range(
+        &self,
+        height: u64,
+        start: u64,
+        end: u64,
+    ) -> Result<GetRangeResponse, Error>;
+
     /// GetSharesByNamespace gets all shares from an EDS within the given namespace. Shares are returned in a row-by-row order if the namespace spans multiple rows.
     #[method(name = "share.GetSharesByNamespace")]
     async fn share_get_shares_by_namespace(
@@ -28,4 +43,12 @@ pub trait Share {
     /// SharesAvailable subjectively validates if Shares committed to the given Root are available on the Network.
     #[method(name = "share.SharesAvailable")]
     async fn share_shares_available(&self, root: &ExtendedHeader) -> Result<(), Error>;
+
+    /// SubscribeToShares subscribes to the shares of the given namespace in every new block.
+    ///
+    /// # Notes
+    ///
+    /// Unsubscribe is not implemented by Celestia nodes.
+    #[subscription(name = "share.SubscribeToShares", unsubscribe = "share.Unsubscribe", item = SharesAtHeight)]
+    async fn share_subscribe_to_shares(&self, namespace: Namespace) -> SubcriptionResult;
 }
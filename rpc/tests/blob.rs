@@ -3,6 +3,7 @@
 use std::time::Duration;
 
 use celestia_rpc::prelude::*;
+use celestia_types::consts::appconsts::AppVersion;
 use celestia_types::{Blob, Commitment};
 
 pub mod utils;
@@ -15,7 +16,7 @@ async fn blob_submit_and_get() {
     let client = new_test_client(AuthLevel::Write).await.unwrap();
     let namespace = random_ns();
     let data = random_bytes(5);
-    let blob = Blob::new(namespace, data).unwrap();
+    let blob = Blob::new(namespace, data, AppVersion::V2).unwrap();
 
     let submitted_height = blob_submit(&client, &[blob.clone()]).await.unwrap();
 
@@ -31,7 +32,7 @@ async fn blob_submit_and_get() {
         .await
         .unwrap();
 
-    received_blob.validate().unwrap();
+    received_blob.validate(AppVersion::V2).unwrap();
     assert_eq!(received_blob, blob);
 
     let proofs = client
@@ -54,8 +55,8 @@ async fn blob_submit_and_get_all() {
     let namespaces = &[random_ns(), random_ns()];
 
     let blobs = &[
-        Blob::new(namespaces[0], random_bytes(5)).unwrap(),
-        Blob::new(namespaces[1], random_bytes(15)).unwrap(),
+        Blob::new(namespaces[0], random_bytes(5), AppVersion::V2).unwrap(),
+        Blob::new(namespaces[1], random_bytes(15), AppVersion::V2).unwrap(),
     ];
 
     let submitted_height = blob_submit(&client, &blobs[..]).await.unwrap();
@@ -70,7 +71,7 @@ async fn blob_submit_and_get_all() {
     for (idx, (blob, received_blob)) in blobs.iter().zip(received_blobs.iter()).enumerate() {
         let namespace = namespaces[idx];
 
-        received_blob.validate().unwrap();
+        received_blob.validate(AppVersion::V2).unwrap();
         assert_eq!(received_blob, blob);
 
         let proofs = client
@@ -87,7 +88,7 @@ async fn blob_submit_and_get_large() {
     let client = new_test_client(AuthLevel::Write).await.unwrap();
     let namespace = random_ns();
     let data = random_bytes(1024 * 1024);
-    let blob = Blob::new(namespace, data).unwrap();
+    let blob = Blob::new(namespace, data, AppVersion::V2).unwrap();
 
     let submitted_height = blob_submit(&client, &[blob.clone()]).await.unwrap();
 
@@ -100,7 +101,7 @@ async fn blob_submit_and_get_large() {
         .await
         .unwrap();
 
-    blob.validate().unwrap();
+    blob.validate(AppVersion::V2).unwrap();
     assert_eq!(received_blob, blob);
 
     let proofs = client
@@ -118,7 +119,7 @@ async fn blob_submit_too_large() {
     let client = new_test_client(AuthLevel::Write).await.unwrap();
     let namespace = random_ns();
     let data = random_bytes(5 * 1024 * 1024);
-    let blob = Blob::new(namespace, data).unwrap();
+    let blob = Blob::new(namespace, data, AppVersion::V2).unwrap();
 
     blob_submit(&client, &[blob]).await.unwrap_err();
 }
@@ -128,7 +129,7 @@ async fn blob_get_get_proof_wrong_ns() {
     let client = new_test_client(AuthLevel::Write).await.unwrap();
     let namespace = random_ns();
     let data = random_bytes(5);
-    let blob = Blob::new(namespace, data).unwrap();
+    let blob = Blob::new(namespace, data, AppVersion::V2).unwrap();
 
     let submitted_height = blob_submit(&client, &[blob.clone()]).await.unwrap();
 
@@ -148,7 +149,7 @@ async fn blob_get_get_proof_wrong_commitment() {
     let client = new_test_client(AuthLevel::Write).await.unwrap();
     let namespace = random_ns();
     let data = random_bytes(5);
-    let blob = Blob::new(namespace, data).unwrap();
+    let blob = Blob::new(namespace, data, AppVersion::V2).unwrap();
     let commitment = Commitment(random_bytes_array());
 
     let submitted_height = blob_submit(&client, &[blob.clone()]).await.unwrap();
@@ -163,3 +164,27 @@ async fn blob_get_get_proof_wrong_commitment() {
         .await
         .unwrap_err();
 }
+
+#[tokio::test]
+async fn blob_subscribe() {
+    let client = new_test_client(AuthLevel::Write).await.unwrap();
+    let namespace = random_ns();
+    let blob = Blob::new(namespace, random_bytes(5), AppVersion::V2).unwrap();
+
+    let mut incoming_blobs = client.blob_subscribe(namespace).await.unwrap();
+
+    let submitted_height = blob_submit(&client, &[blob.clone()]).await.unwrap();
+
+    loop {
+        let blobs_at_height = incoming_blobs.next().await.unwrap().unwrap();
+
+        if blobs_at_height.height < submitted_height {
+            assert!(blobs_at_height.blobs.is_empty());
+            continue;
+        }
+
+        assert_eq!(blobs_at_height.height, submitted_height);
+        assert_eq!(blobs_at_height.blobs, vec![blob]);
+        break;
+    }
+}
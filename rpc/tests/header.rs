@@ -1,10 +1,11 @@
 #![cfg(not(target_arch = "wasm32"))]
 
 use celestia_rpc::prelude::*;
+use celestia_rpc::Client;
 
 pub mod utils;
 
-use crate::utils::client::{new_test_client, AuthLevel};
+use crate::utils::client::{new_test_client, new_test_failover_client, AuthLevel};
 
 #[tokio::test]
 async fn local_head() {
@@ -116,3 +117,22 @@ async fn sync_state() {
     let state2 = client.header_sync_state().await.unwrap();
     assert!(state2.height > state1.height);
 }
+
+#[tokio::test]
+async fn network_head_with_failover() {
+    let client = new_test_failover_client(AuthLevel::Read).await.unwrap();
+
+    let network_head = client.header_network_head().await.unwrap();
+    network_head.validate().unwrap();
+
+    let Client::Failover(failover) = &client else {
+        panic!("failover client expected");
+    };
+    let health = failover.health();
+    assert!(!health[0].healthy);
+    assert!(health[1].healthy);
+
+    let health = failover.check_health().await;
+    assert!(!health[0].healthy);
+    assert!(health[1].healthy);
+}
@@ -2,8 +2,8 @@
 
 use celestia_rpc::prelude::*;
 use celestia_types::consts::appconsts::{
-    CONTINUATION_SPARSE_SHARE_CONTENT_SIZE, FIRST_SPARSE_SHARE_CONTENT_SIZE, SEQUENCE_LEN_BYTES,
-    SHARE_INFO_BYTES,
+    AppVersion, CONTINUATION_SPARSE_SHARE_CONTENT_SIZE, FIRST_SPARSE_SHARE_CONTENT_SIZE,
+    SEQUENCE_LEN_BYTES, SHARE_INFO_BYTES,
 };
 use celestia_types::nmt::{Namespace, NamespacedSha2Hasher, Nmt};
 use celestia_types::{Blob, Share};
@@ -19,7 +19,7 @@ async fn get_shares_by_namespace() {
     let client = new_test_client(AuthLevel::Write).await.unwrap();
     let namespace = random_ns();
     let data = random_bytes(1024);
-    let blob = Blob::new(namespace, data.clone()).unwrap();
+    let blob = Blob::new(namespace, data.clone(), AppVersion::V2).unwrap();
 
     let submitted_height = blob_submit(&client, &[blob]).await.unwrap();
 
@@ -57,7 +57,7 @@ async fn get_shares_by_namespace_wrong_ns() {
     let client = new_test_client(AuthLevel::Write).await.unwrap();
     let namespace = random_ns();
     let data = random_bytes(1024);
-    let blob = Blob::new(namespace, data.clone()).unwrap();
+    let blob = Blob::new(namespace, data.clone(), AppVersion::V2).unwrap();
 
     let submitted_height = blob_submit(&client, &[blob]).await.unwrap();
 
@@ -98,7 +98,7 @@ async fn get_shares_by_namespace_wrong_ns_out_of_range() {
     let client = new_test_client(AuthLevel::Write).await.unwrap();
     let namespace = random_ns();
     let data = random_bytes(1024);
-    let blob = Blob::new(namespace, data.clone()).unwrap();
+    let blob = Blob::new(namespace, data.clone(), AppVersion::V2).unwrap();
 
     let submitted_height = blob_submit(&client, &[blob]).await.unwrap();
 
@@ -124,7 +124,7 @@ async fn get_shares_by_namespace_wrong_roots() {
     let client = new_test_client(AuthLevel::Write).await.unwrap();
     let namespace = random_ns();
     let data = random_bytes(1024);
-    let blob = Blob::new(namespace, data.clone()).unwrap();
+    let blob = Blob::new(namespace, data.clone(), AppVersion::V2).unwrap();
 
     blob_submit(&client, &[blob]).await.unwrap();
 
@@ -143,7 +143,7 @@ async fn get_eds() {
     let client = new_test_client(AuthLevel::Write).await.unwrap();
     let namespace = random_ns();
     let data = vec![1, 2, 3, 4];
-    let blob = Blob::new(namespace, data.clone()).unwrap();
+    let blob = Blob::new(namespace, data.clone(), AppVersion::V2).unwrap();
 
     let submitted_height = blob_submit(&client, &[blob]).await.unwrap();
 
@@ -172,3 +172,61 @@ async fn get_eds() {
         assert_eq!(root, header.dah.row_root(y).unwrap());
     }
 }
+
+#[tokio::test]
+async fn get_range() {
+    let client = new_test_client(AuthLevel::Write).await.unwrap();
+    let namespace = random_ns();
+    let blob = Blob::new(namespace, random_bytes(1024), AppVersion::V2).unwrap();
+
+    let submitted_height = blob_submit(&client, &[blob.clone()]).await.unwrap();
+
+    let header = client.header_get_by_height(submitted_height).await.unwrap();
+    let blob = client
+        .blob_get(submitted_height, namespace, blob.commitment)
+        .await
+        .unwrap();
+    let range = blob.share_range(header.dah.square_len() / 2).unwrap();
+
+    let response = client
+        .share_get_range(submitted_height, range.start as u64, range.end as u64)
+        .await
+        .unwrap();
+
+    assert_eq!(response.shares, blob.to_shares().unwrap());
+    response
+        .verify(range.start, range.end, &header.header.data_hash)
+        .unwrap();
+
+    // the proof is valid only for the requested range
+    response
+        .verify(range.start + 1, range.end, &header.header.data_hash)
+        .unwrap_err();
+    response
+        .verify(range.start, range.end - 1, &header.header.data_hash)
+        .unwrap_err();
+}
+
+#[tokio::test]
+async fn subscribe_to_shares() {
+    let client = new_test_client(AuthLevel::Write).await.unwrap();
+    let namespace = random_ns();
+    let blob = Blob::new(namespace, random_bytes(1024), AppVersion::V2).unwrap();
+
+    let mut incoming_shares = client.share_subscribe_to_shares(namespace).await.unwrap();
+
+    let submitted_height = blob_submit(&client, &[blob.clone()]).await.unwrap();
+
+    loop {
+        let shares_at_height = incoming_shares.next().await.unwrap().unwrap();
+
+        if shares_at_height.height < submitted_height {
+            assert!(shares_at_height.shares.is_empty());
+            continue;
+        }
+
+        assert_eq!(shares_at_height.height, submitted_height);
+        assert_eq!(shares_at_height.shares, blob.to_shares().unwrap());
+        break;
+    }
+}
@@ -2,14 +2,16 @@ use std::env;
 use std::sync::OnceLock;
 
 use anyhow::Result;
+use celestia_rpc::client::FailoverConfig;
 use celestia_rpc::prelude::*;
 use celestia_rpc::Client;
 use celestia_types::{blob::SubmitOptions, Blob};
-use jsonrpsee::core::client::ClientT;
+use jsonrpsee::core::client::SubscriptionClientT;
 use jsonrpsee::core::Error;
 use tokio::sync::{Mutex, MutexGuard};
 
 const CELESTIA_RPC_URL: &str = "ws://localhost:26658";
+const UNREACHABLE_RPC_URL: &str = "ws://localhost:1";
 
 async fn write_lock() -> MutexGuard<'static, ()> {
     static LOCK: OnceLock<Mutex<()>> = OnceLock::new();
@@ -50,9 +52,25 @@ pub async fn new_test_client(auth_level: AuthLevel) -> Result<Client> {
     Ok(client)
 }
 
+/// Client with an unreachable endpoint preferred over the test node.
+pub async fn new_test_failover_client(auth_level: AuthLevel) -> Result<Client> {
+    let _ = dotenvy::dotenv();
+    let token = token_from_env(auth_level)?;
+    let url = env_or("CELESTIA_RPC_URL", CELESTIA_RPC_URL);
+
+    let client = Client::new_with_failover(
+        &[UNREACHABLE_RPC_URL, &url],
+        token.as_deref(),
+        FailoverConfig::default(),
+    )
+    .await?;
+
+    Ok(client)
+}
+
 pub async fn blob_submit<C>(client: &C, blobs: &[Blob]) -> Result<u64, Error>
 where
-    C: ClientT + Sync,
+    C: SubscriptionClientT + Sync,
 {
     let _guard = write_lock().await;
     client.blob_submit(blobs, SubmitOptions::default()).await
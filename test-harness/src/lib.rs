@@ -0,0 +1,35 @@
+#![cfg(not(target_arch = "wasm32"))]
+#![doc = include_str!("../README.md")]
+
+use std::io;
+
+use lumina_node::eds_store::EdsStoreError;
+use lumina_node::node::NodeError;
+
+mod network;
+mod producer;
+
+pub use crate::network::{
+    SimulatedNetwork, SimulatedNetworkBuilder, DEFAULT_BLOCK_TIME, DEFAULT_GENESIS_AGE,
+    DEFAULT_NAMESPACE, DEFAULT_SQUARE_SIZE,
+};
+pub use crate::producer::{BlockProducer, ProducedBlock};
+
+/// Alias for a `Result` with the error type [`HarnessError`].
+pub type Result<T, E = HarnessError> = std::result::Result<T, E>;
+
+/// Representation of all the errors that can occur when running the [`SimulatedNetwork`].
+#[derive(Debug, thiserror::Error)]
+pub enum HarnessError {
+    /// An error propagated from one of the nodes.
+    #[error(transparent)]
+    Node(#[from] NodeError),
+
+    /// An error propagated from the store of the produced squares.
+    #[error(transparent)]
+    EdsStore(#[from] EdsStoreError),
+
+    /// Failed to create the directory of the produced squares.
+    #[error("Failed to create the eds store directory: {0}")]
+    Io(#[from] io::Error),
+}
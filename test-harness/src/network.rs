@@ -0,0 +1,375 @@
+//! The simulated network and its nodes.
+
+use std::time::Duration;
+
+use celestia_tendermint::Time;
+use celestia_types::nmt::Namespace;
+use celestia_types::test_utils::ExtendedHeaderGenerator;
+use libp2p::Multiaddr;
+use lumina_node::clock::Clock;
+use lumina_node::daser::DaserConfig;
+use lumina_node::eds_store::EdsStore;
+use lumina_node::network::Network;
+use lumina_node::node::{Node, NodeBuilder, NodeError};
+use lumina_node::p2p::TransportConfig;
+use lumina_node::store::InMemoryStore;
+use lumina_node::test_utils::MockClock;
+use tempdir::TempDir;
+use tokio::sync::Mutex;
+
+use crate::producer::{BlockProducer, ProducedBlock};
+use crate::{HarnessError, Result};
+
+/// Default width of the original data square of the produced blocks.
+pub const DEFAULT_SQUARE_SIZE: usize = 4;
+/// Default time between the produced blocks.
+pub const DEFAULT_BLOCK_TIME: Duration = Duration::from_secs(12);
+/// Default age of the genesis block when the network is started.
+pub const DEFAULT_GENESIS_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+/// Namespace of the shares of the produced blocks, unless configured otherwise.
+pub const DEFAULT_NAMESPACE: Namespace = Namespace::const_v0([0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+
+type NodeBuilderFn = Box<dyn Fn(NodeBuilder<InMemoryStore>) -> NodeBuilder<InMemoryStore> + Send>;
+
+/// Builder of the [`SimulatedNetwork`].
+pub struct SimulatedNetworkBuilder {
+    nodes: usize,
+    square_size: usize,
+    namespaces: Vec<Namespace>,
+    genesis_time: Option<Time>,
+    block_time: Duration,
+    seed: Option<u64>,
+    sampling: bool,
+    map_node: Option<NodeBuilderFn>,
+}
+
+/// An in-process Celestia network.
+///
+/// The network is served by a bridge-like node holding all the produced blocks. It
+/// serves their headers over `header-ex`, announces them on `header-sub` and serves the
+/// shwap containers of their squares over bitswap. The other nodes are regular Lumina
+/// nodes with in-memory stores, bootstrapped from the bridge node with the in-memory
+/// libp2p transport.
+///
+/// All the nodes follow the [`MockClock`] of the network, which is moved forward
+/// together with the produced blocks, so the checks of the header times don't depend
+/// on the wall clock.
+pub struct SimulatedNetwork {
+    producer: Mutex<BlockProducer>,
+    clock: MockClock,
+    bridge: Node<InMemoryStore>,
+    eds_store: EdsStore,
+    nodes: Vec<Node<InMemoryStore>>,
+    // squares are removed when the network is dropped
+    _eds_dir: TempDir,
+}
+
+impl SimulatedNetworkBuilder {
+    /// Create a new builder with a single node.
+    pub fn new() -> Self {
+        SimulatedNetworkBuilder {
+            nodes: 1,
+            square_size: DEFAULT_SQUARE_SIZE,
+            namespaces: vec![DEFAULT_NAMESPACE],
+            genesis_time: None,
+            block_time: DEFAULT_BLOCK_TIME,
+            seed: None,
+            sampling: true,
+            map_node: None,
+        }
+    }
+
+    /// Number of the Lumina nodes in the network, besides the bridge node.
+    pub fn nodes(mut self, nodes: usize) -> Self {
+        self.nodes = nodes;
+        self
+    }
+
+    /// Width of the original data square of the produced blocks, a power of two.
+    pub fn square_size(mut self, square_size: usize) -> Self {
+        self.square_size = square_size;
+        self
+    }
+
+    /// Namespaces of the shares of the produced blocks.
+    pub fn namespaces(mut self, namespaces: Vec<Namespace>) -> Self {
+        self.namespaces = namespaces;
+        self
+    }
+
+    /// Time of the genesis block, [`DEFAULT_GENESIS_AGE`] before starting the network
+    /// by default.
+    pub fn genesis_time(mut self, time: Time) -> Self {
+        self.genesis_time = Some(time);
+        self
+    }
+
+    /// Time between the produced blocks.
+    pub fn block_time(mut self, block_time: Duration) -> Self {
+        self.block_time = block_time;
+        self
+    }
+
+    /// Produce the blocks deterministically, from the given seed.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Disable the data availability sampling of the nodes.
+    pub fn disable_sampling(mut self) -> Self {
+        self.sampling = false;
+        self
+    }
+
+    /// Customize the [`NodeBuilder`]s of the nodes, eg. to change their syncing
+    /// or sampling config.
+    ///
+    /// The network, store, transports, clock and bootnodes are already set when the
+    /// function is called.
+    pub fn map_node_builder<F>(mut self, f: F) -> Self
+    where
+        F: Fn(NodeBuilder<InMemoryStore>) -> NodeBuilder<InMemoryStore> + Send + 'static,
+    {
+        self.map_node = Some(Box::new(f));
+        self
+    }
+
+    /// Produce the genesis block and start the nodes of the network.
+    ///
+    /// Returns once all the nodes are connected to the bridge node and synchronized
+    /// the genesis block.
+    pub async fn start(self) -> Result<SimulatedNetwork> {
+        let gen = match self.seed {
+            Some(seed) => ExtendedHeaderGenerator::new_with_seed(seed),
+            None => ExtendedHeaderGenerator::new(),
+        };
+        let genesis_time = match self.genesis_time {
+            Some(time) => time,
+            None => Time::now()
+                .checked_sub(DEFAULT_GENESIS_AGE)
+                .expect("time underflow"),
+        };
+        let mut producer = BlockProducer::new(
+            gen,
+            self.square_size,
+            self.namespaces,
+            genesis_time,
+            self.block_time,
+        );
+        let genesis = producer.produce();
+        let genesis_hash = Some(genesis.header.hash());
+        let clock = MockClock::new(genesis_time);
+
+        let eds_dir = TempDir::new("lumina-test-harness")?;
+        let eds_store = EdsStore::new(eds_dir.path()).await?;
+
+        let bridge = in_memory_node_builder()
+            .clock(clock.clone())
+            .genesis_hash(genesis_hash)
+            .bootnodes([])
+            .eds_store(eds_store.clone())
+            .disable_syncing()
+            .disable_sampling()
+            .start()
+            .await?;
+        let bridge_addrs = bridge.listeners().await?;
+
+        let mut network = SimulatedNetwork {
+            producer: Mutex::new(producer),
+            clock: clock.clone(),
+            bridge,
+            eds_store,
+            nodes: Vec::new(),
+            _eds_dir: eds_dir,
+        };
+        network.add_block(&genesis).await?;
+
+        for _ in 0..self.nodes {
+            let mut builder = in_memory_node_builder()
+                .clock(clock.clone())
+                .genesis_hash(genesis_hash)
+                .bootnodes(bridge_addrs.clone());
+
+            if self.sampling {
+                builder = builder.daser_config(DaserConfig::default());
+            }
+            if let Some(map_node) = &self.map_node {
+                builder = map_node(builder);
+            }
+
+            network.nodes.push(builder.start().await?);
+        }
+
+        for node in &network.nodes {
+            node.wait_connected_trusted().await?;
+
+            // nodes subscribe to the header-sub before they are initialized, so the
+            // following blocks are announced to them
+            match node.await_synced(genesis.header.height().value()).await {
+                Ok(()) | Err(NodeError::SyncingDisabled) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(network)
+    }
+}
+
+impl Default for SimulatedNetworkBuilder {
+    fn default() -> Self {
+        SimulatedNetworkBuilder::new()
+    }
+}
+
+impl SimulatedNetwork {
+    /// Create a builder of the network.
+    pub fn builder() -> SimulatedNetworkBuilder {
+        SimulatedNetworkBuilder::new()
+    }
+
+    /// The bridge node serving the produced blocks.
+    pub fn bridge(&self) -> &Node<InMemoryStore> {
+        &self.bridge
+    }
+
+    /// Addresses of the bridge node, to bootstrap additional nodes from.
+    ///
+    /// Only the nodes with the [`TransportConfig::in_memory`] transport can connect to them.
+    pub async fn bridge_addrs(&self) -> Result<Vec<Multiaddr>> {
+        Ok(self.bridge.listeners().await?)
+    }
+
+    /// The Lumina nodes of the network.
+    pub fn nodes(&self) -> &[Node<InMemoryStore>] {
+        &self.nodes
+    }
+
+    /// The Lumina node with the given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds.
+    pub fn node(&self, index: usize) -> &Node<InMemoryStore> {
+        &self.nodes[index]
+    }
+
+    /// Produce the next block, with the shares of the configured namespaces, and
+    /// announce it to the network.
+    pub async fn produce_block(&self) -> Result<ProducedBlock> {
+        let mut producer = self.producer.lock().await;
+        let block = producer.produce();
+
+        self.add_block(&block).await?;
+        Ok(block)
+    }
+
+    /// Produce the next block, with the shares of the given namespaces, and announce
+    /// it to the network.
+    pub async fn produce_block_with(&self, namespaces: &[Namespace]) -> Result<ProducedBlock> {
+        let mut producer = self.producer.lock().await;
+        let block = producer.produce_with(namespaces);
+
+        self.add_block(&block).await?;
+        Ok(block)
+    }
+
+    /// Produce the given number of blocks.
+    ///
+    /// Only the last of them is announced on `header-sub`, the nodes synchronize the
+    /// preceding ones over `header-ex`.
+    pub async fn produce_blocks(&self, amount: u64) -> Result<Vec<ProducedBlock>> {
+        let mut producer = self.producer.lock().await;
+        let mut blocks = Vec::with_capacity(amount as usize);
+
+        for _ in 0..amount {
+            let block = producer.produce();
+            self.store_block(&block).await?;
+            blocks.push(block);
+        }
+
+        if let Some(last) = blocks.last() {
+            self.announce_block(last).await?;
+        }
+
+        Ok(blocks)
+    }
+
+    /// Time of the next produced block.
+    pub async fn next_block_time(&self) -> Time {
+        self.producer.lock().await.next_time()
+    }
+
+    /// The clock followed by all the nodes of the network.
+    ///
+    /// It shows the time of the latest produced block, unless moved further with
+    /// [`SimulatedNetwork::advance_time`].
+    pub fn clock(&self) -> &MockClock {
+        &self.clock
+    }
+
+    /// Move the clock of the network forward, delaying the next block by the `duration`.
+    pub async fn advance_time(&self, duration: Duration) {
+        self.producer.lock().await.advance_time(duration);
+        self.clock.advance(duration);
+    }
+
+    /// Wait until all the nodes synchronized the header at the given height.
+    ///
+    /// Nodes with the syncing disabled are skipped.
+    pub async fn wait_synced(&self, height: u64) -> Result<()> {
+        for node in &self.nodes {
+            match node.await_synced(height).await {
+                Ok(()) | Err(NodeError::SyncingDisabled) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn add_block(&self, block: &ProducedBlock) -> Result<()> {
+        self.store_block(block).await?;
+        self.announce_block(block).await
+    }
+
+    async fn store_block(&self, block: &ProducedBlock) -> Result<()> {
+        let height = block.header.height().value();
+
+        // the block is produced at the current time of the network
+        if self.clock.now() < block.header.time() {
+            self.clock.set(block.header.time());
+        }
+
+        self.eds_store
+            .put(height, &block.eds, &block.header.dah)
+            .await?;
+        self.bridge
+            .append_headers(vec![block.header.clone()])
+            .await?;
+
+        Ok(())
+    }
+
+    async fn announce_block(&self, block: &ProducedBlock) -> Result<()> {
+        // publishing fails without any peers to publish to
+        if self.bridge.connected_peers().await?.is_empty() {
+            return Ok(());
+        }
+
+        self.bridge
+            .publish_header(block.header.clone())
+            .await
+            .map_err(HarnessError::from)
+    }
+}
+
+/// [`NodeBuilder`] of a node in the simulated network, listening on the in-memory
+/// transport.
+fn in_memory_node_builder() -> NodeBuilder<InMemoryStore> {
+    Node::builder()
+        .network(Network::Private)
+        .transports(TransportConfig::in_memory())
+        .listen_addrs(["/memory/0".parse().expect("valid multiaddr")])
+        .store(InMemoryStore::new())
+}
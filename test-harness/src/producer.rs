@@ -0,0 +1,133 @@
+//! Generation of the valid blocks of the simulated network.
+
+use std::time::Duration;
+
+use celestia_tendermint::Time;
+use celestia_types::nmt::Namespace;
+use celestia_types::test_utils::ExtendedHeaderGenerator;
+use celestia_types::{ExtendedDataSquare, ExtendedHeader};
+
+/// A block produced by the [`BlockProducer`].
+#[derive(Debug, Clone)]
+pub struct ProducedBlock {
+    /// Header of the block, signed by the validator of the simulated network.
+    pub header: ExtendedHeader,
+    /// Square of the block, committed to by the header.
+    pub eds: ExtendedDataSquare,
+}
+
+/// Producer of the blocks of the simulated network.
+///
+/// Each block follows the previous one and holds a square filled with random shares
+/// of the configured namespaces. Blocks are timestamped with the producer's clock,
+/// which advances by the block time with each block and can be moved forward
+/// with [`BlockProducer::advance_time`].
+///
+/// Nodes reject the headers from the future, so when used outside of the
+/// [`SimulatedNetwork`], which moves the clock of its nodes with the produced blocks,
+/// the clock should stay behind the clock of the nodes.
+///
+/// [`SimulatedNetwork`]: crate::SimulatedNetwork
+#[derive(Debug)]
+pub struct BlockProducer {
+    gen: ExtendedHeaderGenerator,
+    square_size: usize,
+    namespaces: Vec<Namespace>,
+    block_time: Duration,
+    next_time: Time,
+}
+
+impl BlockProducer {
+    /// Create a new producer, starting the chain with the genesis block at `genesis_time`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `square_size` is not a power of two.
+    pub fn new(
+        gen: ExtendedHeaderGenerator,
+        square_size: usize,
+        namespaces: Vec<Namespace>,
+        genesis_time: Time,
+        block_time: Duration,
+    ) -> Self {
+        assert!(
+            square_size.is_power_of_two(),
+            "square size must be a power of two"
+        );
+
+        BlockProducer {
+            gen,
+            square_size,
+            namespaces,
+            block_time,
+            next_time: genesis_time,
+        }
+    }
+
+    /// Produce the next block, with the shares of the configured namespaces.
+    pub fn produce(&mut self) -> ProducedBlock {
+        let namespaces = self.namespaces.clone();
+        self.produce_with(&namespaces)
+    }
+
+    /// Produce the next block, with the shares of the given namespaces.
+    pub fn produce_with(&mut self, namespaces: &[Namespace]) -> ProducedBlock {
+        self.gen.set_next_time(self.next_time);
+        let (header, eds) = self.gen.next_with_eds(self.square_size, namespaces);
+
+        self.next_time = self
+            .next_time
+            .checked_add(self.block_time)
+            .expect("time overflow");
+
+        ProducedBlock { header, eds }
+    }
+
+    /// Time of the next produced block.
+    pub fn next_time(&self) -> Time {
+        self.next_time
+    }
+
+    /// Move the clock forward, delaying the next block by the `duration`.
+    pub fn advance_time(&mut self, duration: Duration) {
+        self.next_time = self.next_time.checked_add(duration).expect("time overflow");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocks_follow_the_clock() {
+        let genesis_time = Time::from_unix_timestamp(1_700_000_000, 0).unwrap();
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+        let mut producer = BlockProducer::new(
+            ExtendedHeaderGenerator::new(),
+            4,
+            vec![namespace],
+            genesis_time,
+            Duration::from_secs(12),
+        );
+
+        let genesis = producer.produce();
+        assert_eq!(genesis.header.height().value(), 1);
+        assert_eq!(genesis.header.time(), genesis_time);
+        genesis.header.dah.verify_eds(&genesis.eds).unwrap();
+
+        producer.advance_time(Duration::from_secs(60));
+        let block = producer.produce();
+        assert_eq!(
+            block.header.time(),
+            genesis_time.checked_add(Duration::from_secs(72)).unwrap()
+        );
+        genesis.header.verify(&block.header).unwrap();
+
+        let other = Namespace::new_v0(&[4, 5, 6]).unwrap();
+        let block = producer.produce_with(&[other]);
+        assert!(block
+            .eds
+            .get_namespaced_data(other, &block.header.dah, 3)
+            .is_ok_and(|data| !data.is_empty()));
+    }
+}
@@ -0,0 +1,115 @@
+use std::time::Duration;
+
+use celestia_tendermint::Time;
+use celestia_types::nmt::Namespace;
+use lumina_node::clock::Clock;
+use lumina_node::p2p::NamespaceFetchMode;
+use lumina_test_harness::SimulatedNetwork;
+
+#[tokio::test]
+async fn nodes_sync_and_sample_produced_blocks() {
+    let network = SimulatedNetwork::builder().nodes(2).start().await.unwrap();
+
+    let blocks = network.produce_blocks(3).await.unwrap();
+    let block = network.produce_block().await.unwrap();
+    assert_eq!(block.header.height().value(), 5);
+    network.wait_synced(5).await.unwrap();
+
+    for node in network.nodes() {
+        let headers = node.get_headers(2..=4).await.unwrap();
+        let produced: Vec<_> = blocks.iter().map(|block| block.header.clone()).collect();
+        assert_eq!(headers, produced);
+
+        let eds = node.request_eds(5).await.unwrap();
+        assert_eq!(eds, block.eds);
+    }
+}
+
+#[tokio::test]
+async fn blocks_follow_the_controlled_clock() {
+    let network = SimulatedNetwork::builder()
+        .block_time(Duration::from_secs(6))
+        .disable_sampling()
+        .start()
+        .await
+        .unwrap();
+    let genesis = network.bridge().get_local_head_header().await.unwrap();
+
+    network.advance_time(Duration::from_secs(60)).await;
+    let namespace = Namespace::new_v0(&[7, 7, 7]).unwrap();
+    let block = network.produce_block_with(&[namespace]).await.unwrap();
+
+    let expected_time = genesis.time().checked_add(Duration::from_secs(66)).unwrap();
+    assert_eq!(block.header.time(), expected_time);
+
+    network.wait_synced(2).await.unwrap();
+    let shares = network
+        .node(0)
+        .request_shares_by_namespace(namespace, 2)
+        .await
+        .unwrap();
+    assert!(!shares.rows.is_empty());
+}
+
+#[tokio::test]
+async fn nodes_follow_the_network_clock() {
+    // blocks produced ahead of the wall clock are accepted by the nodes
+    let genesis_time = Time::now().checked_add(Duration::from_secs(3600)).unwrap();
+    let network = SimulatedNetwork::builder()
+        .genesis_time(genesis_time)
+        .disable_sampling()
+        .start()
+        .await
+        .unwrap();
+    assert_eq!(network.clock().now(), genesis_time);
+
+    let block = network.produce_block().await.unwrap();
+    assert_eq!(network.clock().now(), block.header.time());
+    network.wait_synced(2).await.unwrap();
+
+    network.advance_time(Duration::from_secs(60)).await;
+    assert_eq!(
+        network.clock().now(),
+        block
+            .header
+            .time()
+            .checked_add(Duration::from_secs(60))
+            .unwrap()
+    );
+}
+
+#[tokio::test]
+async fn namespaced_data_is_built_from_full_rows() {
+    let namespace = Namespace::new_v0(&[7, 8, 9]).unwrap();
+    let network = SimulatedNetwork::builder()
+        .disable_sampling()
+        .map_node_builder(|builder| builder.namespace_fetch_mode(NamespaceFetchMode::FullRows))
+        .start()
+        .await
+        .unwrap();
+
+    let block = network.produce_block_with(&[namespace]).await.unwrap();
+    let height = block.header.height().value();
+    network.wait_synced(height).await.unwrap();
+
+    let expected = block
+        .eds
+        .get_namespaced_data(namespace, &block.header.dah, height)
+        .unwrap();
+    assert!(!expected.is_empty());
+
+    let namespaced = network
+        .node(0)
+        .request_shares_by_namespace(namespace, height)
+        .await
+        .unwrap();
+    assert_eq!(namespaced.rows.len(), expected.len());
+
+    for (row, expected) in namespaced.rows.iter().zip(&expected) {
+        assert_eq!(row.shares.len(), expected.shares.len());
+
+        for (share, expected) in row.shares.iter().zip(&expected.shares) {
+            assert_eq!(share.as_ref(), &expected[..]);
+        }
+    }
+}
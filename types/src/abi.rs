@@ -0,0 +1,262 @@
+//! Encoding of the proofs compatible with the Solidity verifiers.
+//!
+//! Proofs are encoded with the [`Solidity ABI`], as `abi.encode(proof)` would do
+//! for the structs used by the [`Blobstream`] contracts, so they can be passed
+//! directly to the on-chain verifiers.
+//!
+//! All the values are encoded as 32 byte words. Integers are big endian and left padded,
+//! fixed size byte arrays are right padded with zeros. As the encoded structs are dynamic,
+//! the encoding starts with the offset of the struct, which is always `0x20`.
+//!
+//! # [`NamespaceProof`]
+//!
+//! Encoded as the `NamespaceMerkleMultiproof`:
+//!
+//! ```solidity
+//! struct Namespace {
+//!     bytes1 version;
+//!     bytes28 id;
+//! }
+//!
+//! struct NamespaceNode {
+//!     Namespace min;
+//!     Namespace max;
+//!     bytes32 digest;
+//! }
+//!
+//! struct NamespaceMerkleMultiproof {
+//!     uint256 beginKey;
+//!     uint256 endKey;
+//!     NamespaceNode[] sideNodes;
+//! }
+//! ```
+//!
+//! | offset          | value                                              |
+//! |-----------------|----------------------------------------------------|
+//! | `0x00`          | `0x20`, offset of the struct                       |
+//! | `0x20`          | `beginKey`, index of the first proven leaf         |
+//! | `0x40`          | `endKey`, index after the last proven leaf         |
+//! | `0x60`          | `0x60`, offset of the `sideNodes` in the struct    |
+//! | `0x80`          | number of the `sideNodes`                          |
+//! | `0xa0 + i*0xa0` | `sideNodes[i]` as 5 words: min version, min id,    |
+//! |                 | max version, max id and digest                     |
+//!
+//! # [`merkle::Proof`]
+//!
+//! Binary merkle proofs, e.g. of the row roots in the data root, are encoded
+//! as the `BinaryMerkleProof`:
+//!
+//! ```solidity
+//! struct BinaryMerkleProof {
+//!     bytes32[] sideNodes;
+//!     uint256 key;
+//!     uint256 numLeaves;
+//! }
+//! ```
+//!
+//! | offset          | value                                              |
+//! |-----------------|----------------------------------------------------|
+//! | `0x00`          | `0x20`, offset of the struct                       |
+//! | `0x20`          | `0x60`, offset of the `sideNodes` in the struct    |
+//! | `0x40`          | `key`, index of the proven leaf                    |
+//! | `0x60`          | `numLeaves`, number of the leaves in the tree      |
+//! | `0x80`          | number of the `sideNodes`                          |
+//! | `0xa0 + i*0x20` | `sideNodes[i]`                                     |
+//!
+//! [`Solidity ABI`]: https://docs.soliditylang.org/en/latest/abi-spec.html
+//! [`Blobstream`]: https://github.com/celestiaorg/blobstream-contracts
+//! [`merkle::Proof`]: celestia_tendermint::merkle::proof::Proof
+
+use celestia_tendermint::merkle::proof::Proof as MerkleProof;
+
+use crate::nmt::{NamespaceProof, NamespacedHash};
+
+/// Size of a single word of the ABI encoding.
+const WORD_SIZE: usize = 32;
+/// Number of the static words in the encoded `NamespaceMerkleMultiproof`.
+const NAMESPACE_PROOF_STATIC_WORDS: usize = 3;
+/// Number of the static words in the encoded `BinaryMerkleProof`.
+const BINARY_PROOF_STATIC_WORDS: usize = 3;
+
+/// Encoding of the type with the Solidity ABI.
+///
+/// See the [module documentation](self) for the exact layouts.
+pub trait AbiEncode {
+    /// Encode the value as the corresponding Solidity struct.
+    fn to_abi_encoded(&self) -> Vec<u8>;
+}
+
+impl AbiEncode for NamespaceProof {
+    /// Encode the proof as the `NamespaceMerkleMultiproof`.
+    ///
+    /// The Solidity verifiers only support the proofs of presence, the leaf of the
+    /// proof of absence is not a part of the encoding.
+    fn to_abi_encoded(&self) -> Vec<u8> {
+        let mut encoder = Encoder::new();
+
+        encoder.push_uint(WORD_SIZE as u64);
+        encoder.push_uint(self.start_idx().into());
+        encoder.push_uint(self.end_idx().into());
+        encoder.push_uint((NAMESPACE_PROOF_STATIC_WORDS * WORD_SIZE) as u64);
+
+        encoder.push_uint(self.siblings().len() as u64);
+        for node in self.siblings() {
+            encoder.push_namespaced_hash(node);
+        }
+
+        encoder.finish()
+    }
+}
+
+impl AbiEncode for MerkleProof {
+    /// Encode the proof as the `BinaryMerkleProof`.
+    fn to_abi_encoded(&self) -> Vec<u8> {
+        let mut encoder = Encoder::new();
+
+        encoder.push_uint(WORD_SIZE as u64);
+        encoder.push_uint((BINARY_PROOF_STATIC_WORDS * WORD_SIZE) as u64);
+        encoder.push_uint(self.index);
+        encoder.push_uint(self.total);
+
+        encoder.push_uint(self.aunts.len() as u64);
+        for aunt in &self.aunts {
+            encoder.push_bytes(aunt.as_bytes());
+        }
+
+        encoder.finish()
+    }
+}
+
+struct Encoder {
+    buf: Vec<u8>,
+}
+
+impl Encoder {
+    fn new() -> Self {
+        Encoder { buf: Vec::new() }
+    }
+
+    /// Push the integer as a left padded `uint256`.
+    fn push_uint(&mut self, value: u64) {
+        self.buf.extend_from_slice(&[0; WORD_SIZE - 8]);
+        self.buf.extend_from_slice(&value.to_be_bytes());
+    }
+
+    /// Push the bytes as a right padded `bytesN`.
+    fn push_bytes(&mut self, bytes: &[u8]) {
+        debug_assert!(bytes.len() <= WORD_SIZE);
+        self.buf.extend_from_slice(bytes);
+        self.buf.resize(self.buf.len() + WORD_SIZE - bytes.len(), 0);
+    }
+
+    /// Push the node as the `NamespaceNode`.
+    fn push_namespaced_hash(&mut self, hash: &NamespacedHash) {
+        for namespace in [hash.min_namespace(), hash.max_namespace()] {
+            let (version, id) = namespace.0.split_at(1);
+            self.push_bytes(version);
+            self.push_bytes(id);
+        }
+        self.push_bytes(&hash.hash());
+    }
+
+    fn finish(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::Hash;
+    use crate::nmt::{Namespace, NamespacedSha2Hasher, Nmt};
+    use nmt_rs::NamespaceMerkleHasher;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    fn word(encoded: &[u8], index: usize) -> &[u8] {
+        &encoded[index * WORD_SIZE..(index + 1) * WORD_SIZE]
+    }
+
+    fn uint_word(value: u64) -> [u8; WORD_SIZE] {
+        let mut word = [0; WORD_SIZE];
+        word[WORD_SIZE - 8..].copy_from_slice(&value.to_be_bytes());
+        word
+    }
+
+    #[test]
+    fn namespace_proof_layout() {
+        let ns1 = Namespace::new_v0(&[1]).unwrap();
+        let ns2 = Namespace::new_v0(&[2]).unwrap();
+
+        let mut nmt = Nmt::with_hasher(NamespacedSha2Hasher::with_ignore_max_ns(true));
+        for (namespace, data) in [(ns1, b"leaf0"), (ns2, b"leaf1"), (ns2, b"leaf2")] {
+            nmt.push_leaf(data, *namespace).unwrap();
+        }
+
+        let proof: NamespaceProof = nmt.get_namespace_proof(*ns2).into();
+        let encoded = proof.to_abi_encoded();
+
+        assert_eq!(proof.siblings().len(), 1);
+        assert_eq!(encoded.len(), (5 + 5) * WORD_SIZE);
+
+        assert_eq!(word(&encoded, 0), uint_word(0x20));
+        assert_eq!(word(&encoded, 1), uint_word(1));
+        assert_eq!(word(&encoded, 2), uint_word(3));
+        assert_eq!(word(&encoded, 3), uint_word(0x60));
+        assert_eq!(word(&encoded, 4), uint_word(1));
+
+        // the only sibling is the leaf of namespace 1
+        let sibling = &proof.siblings()[0];
+        let mut version = [0; WORD_SIZE];
+        version[0] = ns1.version();
+        let mut id = [0; WORD_SIZE];
+        id[..28].copy_from_slice(ns1.id());
+
+        assert_eq!(word(&encoded, 5), version);
+        assert_eq!(word(&encoded, 6), id);
+        assert_eq!(word(&encoded, 7), version);
+        assert_eq!(word(&encoded, 8), id);
+        assert_eq!(word(&encoded, 9), sibling.hash());
+    }
+
+    #[test]
+    fn binary_merkle_proof_layout() {
+        let proof = MerkleProof {
+            total: 7,
+            index: 5,
+            leaf_hash: Hash::Sha256([9; 32]),
+            aunts: vec![
+                Hash::Sha256([1; 32]),
+                Hash::Sha256([2; 32]),
+                Hash::Sha256([3; 32]),
+            ],
+        };
+
+        let encoded = proof.to_abi_encoded();
+        assert_eq!(encoded.len(), (5 + 3) * WORD_SIZE);
+
+        assert_eq!(word(&encoded, 0), uint_word(0x20));
+        assert_eq!(word(&encoded, 1), uint_word(0x60));
+        assert_eq!(word(&encoded, 2), uint_word(5));
+        assert_eq!(word(&encoded, 3), uint_word(7));
+        assert_eq!(word(&encoded, 4), uint_word(3));
+        assert_eq!(word(&encoded, 5), [1; 32]);
+        assert_eq!(word(&encoded, 6), [2; 32]);
+        assert_eq!(word(&encoded, 7), [3; 32]);
+    }
+
+    #[test]
+    fn empty_binary_merkle_proof() {
+        let proof = MerkleProof {
+            total: 1,
+            index: 0,
+            leaf_hash: Hash::Sha256([9; 32]),
+            aunts: vec![],
+        };
+
+        let encoded = proof.to_abi_encoded();
+        assert_eq!(encoded.len(), 5 * WORD_SIZE);
+        assert_eq!(word(&encoded, 4), uint_word(0));
+    }
+}
@@ -1,16 +1,30 @@
 //! Types related to creation and submission of blobs.
 
+use std::ops::Range;
+
 use celestia_tendermint_proto::v0_34::types::Blob as RawBlob;
-use celestia_tendermint_proto::Protobuf;
+use nmt_rs::NamespaceMerkleHasher;
 use serde::{Deserialize, Serialize};
 
-mod commitment;
+pub(crate) mod commitment;
+mod compression;
+mod msg_pay_for_blobs;
+mod span_proof;
 
-pub use self::commitment::Commitment;
-use crate::consts::appconsts;
-use crate::nmt::Namespace;
-use crate::serializers::none_as_negative_one;
-use crate::{bail_validation, Error, Result, Share};
+pub use self::commitment::{commitment_serde, Commitment};
+pub use self::compression::{Compression, COMPRESSED_BLOB_MAGIC, MAX_DECOMPRESSED_BLOB_SIZE};
+pub use self::msg_pay_for_blobs::{
+    decode_pay_for_blobs, MsgPayForBlobs, MSG_PAY_FOR_BLOBS_TYPE_URL,
+};
+pub use self::span_proof::{BlobSpanProof, SubtreeRootProof};
+use crate::consts::appconsts::{self, AppVersion};
+use crate::namespaced_data::NamespacedData;
+use crate::nmt::{Namespace, NamespaceProof, NamespacedSha2Hasher};
+use crate::serializers::{none_as_negative_one, null_as_default};
+use crate::{
+    bail_validation, bail_verification, DataAvailabilityHeader, Error, ExtendedDataSquare, Result,
+    Share,
+};
 
 /// Options for configuring the blob submission to the network.
 ///
@@ -29,6 +43,18 @@ pub struct SubmitOptions {
     pub gas_limit: Option<u64>,
 }
 
+/// [`Blob`]s of a single [`Namespace`] included in the block at the given height.
+///
+/// It is the item of the `blob.Subscribe` subscription of the Celestia nodes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlobsAtHeight {
+    /// [`Blob`]s included in the block, empty if there were none in the [`Namespace`].
+    #[serde(deserialize_with = "null_as_default")]
+    pub blobs: Vec<Blob>,
+    /// Height of the block.
+    pub height: u64,
+}
+
 /// Arbitrary data that can be stored in the network within certain [`Namespace`].
 // NOTE: We don't use the `serde(try_from)` pattern for this type
 // becase JSON representation needs to have `commitment` field but
@@ -46,23 +72,35 @@ pub struct Blob {
     pub share_version: u8,
     /// A [`Commitment`] computed from the [`Blob`]s data.
     pub commitment: Commitment,
+    /// Index of the [`Blob`]s first share in the original data square, if it was read
+    /// from a block.
+    #[serde(default, with = "none_as_negative_one")]
+    index: Option<u64>,
 }
 
 impl Blob {
-    /// Create a new blob with the given data within the [`Namespace`].
+    /// Create a new blob with the given data within the [`Namespace`], with the
+    /// [`Commitment`] computed following the rules of the given [`AppVersion`].
     ///
     /// # Errors
     ///
-    /// This function propagates any error from the [`Commitment`] creation.
+    /// This function will return an error if the [`Namespace`] is reserved or
+    /// isn't a version `0` namespace, see [`Namespace::validate_for_blob`].
+    /// It also propagates any error from the [`Commitment`] creation.
     ///
     /// # Example
     ///
     /// ```
+    /// use celestia_types::consts::appconsts::AppVersion;
     /// use celestia_types::{Blob, nmt::Namespace};
     ///
     /// let my_namespace = Namespace::new_v0(&[1, 2, 3, 4, 5]).expect("Invalid namespace");
-    /// let blob = Blob::new(my_namespace, b"some data to store on blockchain".to_vec())
-    ///     .expect("Failed to create a blob");
+    /// let blob = Blob::new(
+    ///     my_namespace,
+    ///     b"some data to store on blockchain".to_vec(),
+    ///     AppVersion::V2,
+    /// )
+    /// .expect("Failed to create a blob");
     ///
     /// assert_eq!(
     ///     &serde_json::to_string_pretty(&blob).unwrap(),
@@ -70,23 +108,104 @@ impl Blob {
     ///       "namespace": "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAQIDBAU=",
     ///       "data": "c29tZSBkYXRhIHRvIHN0b3JlIG9uIGJsb2NrY2hhaW4=",
     ///       "share_version": 0,
-    ///       "commitment": "m0A4feU6Fqd5Zy9td3M7lntG8A3PKqe6YdugmAsWz28="
+    ///       "commitment": "m0A4feU6Fqd5Zy9td3M7lntG8A3PKqe6YdugmAsWz28=",
+    ///       "index": -1
     ///     }"#},
     /// );
     /// ```
-    pub fn new(namespace: Namespace, data: Vec<u8>) -> Result<Blob> {
-        let commitment =
-            Commitment::from_blob(namespace, appconsts::SHARE_VERSION_ZERO, &data[..])?;
+    pub fn new(namespace: Namespace, data: Vec<u8>, app_version: AppVersion) -> Result<Blob> {
+        namespace.validate_for_blob()?;
+
+        let commitment = Commitment::from_blob(
+            namespace,
+            appconsts::SHARE_VERSION_ZERO,
+            &data[..],
+            app_version,
+        )?;
 
         Ok(Blob {
             namespace,
             data,
             share_version: appconsts::SHARE_VERSION_ZERO,
             commitment,
+            index: None,
         })
     }
 
-    /// Validate [`Blob`]s data with the [`Commitment`] it has.
+    /// Index of the [`Blob`]s first share in the original data square.
+    ///
+    /// The index is known only for the blobs read from a block, e.g. with
+    /// [`ExtendedDataSquare::get_blobs`] or received from the celestia node,
+    /// otherwise it is `None`.
+    ///
+    /// [`ExtendedDataSquare::get_blobs`]: crate::ExtendedDataSquare::get_blobs
+    pub fn index(&self) -> Option<u64> {
+        self.index
+    }
+
+    /// Number of the [`Share`]s the [`Blob`] takes.
+    pub fn shares_len(&self) -> usize {
+        Blob::shares_len_for(self.data.len())
+    }
+
+    /// Number of the [`Share`]s a [`Blob`] with the data of the given length would take.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use celestia_types::Blob;
+    ///
+    /// assert_eq!(Blob::shares_len_for(400), 1);
+    /// assert_eq!(Blob::shares_len_for(1000), 3);
+    /// ```
+    pub fn shares_len_for(data_len: usize) -> usize {
+        let remaining = data_len.saturating_sub(appconsts::FIRST_SPARSE_SHARE_CONTENT_SIZE);
+
+        1 + remaining.div_ceil(appconsts::CONTINUATION_SPARSE_SHARE_CONTENT_SIZE)
+    }
+
+    /// Range of indexes of the [`Blob`]s shares in the original data square of the given width.
+    ///
+    /// Shares are indexed row by row, so a range longer than the square width spans
+    /// multiple rows.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::MissingBlobIndex`] if the index of the blob is not known and
+    /// [`Error::EdsIndexOutOfRange`] if the blob doesn't fit in the square.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use celestia_types::consts::appconsts::AppVersion;
+    /// use celestia_types::{Blob, ExtendedDataSquare};
+    /// # use celestia_types::nmt::Namespace;
+    /// # let namespace = Namespace::new_v0(&[1, 2, 3, 4, 5]).expect("Invalid namespace");
+    ///
+    /// let blob = Blob::new(namespace, vec![1; 1000], AppVersion::V2).unwrap();
+    /// let eds = ExtendedDataSquare::build(&[blob], &[], 4, AppVersion::V2).unwrap();
+    ///
+    /// let blobs = eds.get_blobs(namespace, AppVersion::V2).unwrap();
+    /// assert_eq!(blobs[0].share_range(4).unwrap(), 0..3);
+    /// ```
+    pub fn share_range(&self, square_size: usize) -> Result<Range<usize>> {
+        let start = self.index.ok_or(Error::MissingBlobIndex)? as usize;
+        let end = start + self.shares_len();
+
+        if end > square_size * square_size {
+            return Err(Error::EdsIndexOutOfRange(end - 1));
+        }
+
+        Ok(start..end)
+    }
+
+    /// Validate [`Blob`]s data with the [`Commitment`] it has and check that it would be
+    /// accepted by the network.
+    ///
+    /// Follows the blob acceptance rules of the given [`AppVersion`] of celestia-app: the
+    /// [`Namespace`] must be a non-reserved version `0` namespace, the share version must
+    /// be supported, and the blob must fit in the square of the
+    /// [`DEFAULT_GOV_MAX_SQUARE_SIZE`] width, see [`Blob::validate_for_square_size`].
     ///
     /// # Errors
     ///
@@ -95,23 +214,78 @@ impl Blob {
     /// # Example
     ///
     /// ```
+    /// use celestia_types::consts::appconsts::AppVersion;
     /// use celestia_types::Blob;
     /// # use celestia_types::nmt::Namespace;
     /// #
     /// # let namespace = Namespace::new_v0(&[1, 2, 3, 4, 5]).expect("Invalid namespace");
     ///
-    /// let mut blob = Blob::new(namespace, b"foo".to_vec()).unwrap();
+    /// let mut blob = Blob::new(namespace, b"foo".to_vec(), AppVersion::V2).unwrap();
     ///
-    /// assert!(blob.validate().is_ok());
+    /// assert!(blob.validate(AppVersion::V2).is_ok());
     ///
-    /// let other_blob = Blob::new(namespace, b"bar".to_vec()).unwrap();
+    /// let other_blob = Blob::new(namespace, b"bar".to_vec(), AppVersion::V2).unwrap();
     /// blob.commitment = other_blob.commitment;
     ///
-    /// assert!(blob.validate().is_err());
+    /// assert!(blob.validate(AppVersion::V2).is_err());
+    /// ```
+    ///
+    /// [`DEFAULT_GOV_MAX_SQUARE_SIZE`]: appconsts::DEFAULT_GOV_MAX_SQUARE_SIZE
+    pub fn validate(&self, app_version: AppVersion) -> Result<()> {
+        self.validate_for_square_size(appconsts::DEFAULT_GOV_MAX_SQUARE_SIZE, app_version)
+    }
+
+    /// Same as [`Blob::validate`], but checks that the blob fits in the original data
+    /// square of the given width, e.g. the maximum square size of the network.
+    ///
+    /// At least one share of the square is taken by the transaction paying for the blob,
+    /// so the blob can take at most `square_size * square_size - 1` shares.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::BlobTooLarge`] if the blob doesn't fit in the square, or the
+    /// other error if the validation fails.
+    ///
+    /// # Example
+    ///
     /// ```
-    pub fn validate(&self) -> Result<()> {
+    /// use celestia_types::consts::appconsts::AppVersion;
+    /// use celestia_types::{Blob, Error};
+    /// # use celestia_types::nmt::Namespace;
+    /// # let namespace = Namespace::new_v0(&[1, 2, 3, 4, 5]).expect("Invalid namespace");
+    ///
+    /// let blob = Blob::new(namespace, vec![1; 2000], AppVersion::V2).unwrap();
+    ///
+    /// assert!(blob.validate_for_square_size(4, AppVersion::V2).is_ok());
+    /// assert!(matches!(
+    ///     blob.validate_for_square_size(2, AppVersion::V2).unwrap_err(),
+    ///     Error::BlobTooLarge(5, 3)
+    /// ));
+    /// ```
+    pub fn validate_for_square_size(
+        &self,
+        square_size: usize,
+        app_version: AppVersion,
+    ) -> Result<()> {
+        self.namespace.validate_for_blob()?;
+
+        if !appconsts::supported_share_versions(app_version).contains(&self.share_version) {
+            return Err(Error::UnsupportedShareVersion(self.share_version));
+        }
+
+        if self.data.is_empty() {
+            return Err(Error::EmptyBlob);
+        }
+
+        let max_shares = (square_size * square_size).saturating_sub(1);
+        let shares_len = self.shares_len();
+
+        if shares_len > max_shares {
+            return Err(Error::BlobTooLarge(shares_len, max_shares));
+        }
+
         let computed_commitment =
-            Commitment::from_blob(self.namespace, self.share_version, &self.data)?;
+            Commitment::from_blob(self.namespace, self.share_version, &self.data, app_version)?;
 
         if self.commitment != computed_commitment {
             bail_validation!("blob commitment != localy computed commitment")
@@ -120,6 +294,191 @@ impl Blob {
         Ok(())
     }
 
+    /// Verify the proofs of the [`Blob`]s inclusion in the block, as returned by the
+    /// `blob.GetProof`, against its [`DataAvailabilityHeader`].
+    ///
+    /// There is one [`NamespaceProof`] for each row spanned by the blob, proving its
+    /// shares in that row against the row root. The blob is validated for the square of
+    /// the header first, following the rules of the block's [`AppVersion`], so that its
+    /// data is known to match the [`Commitment`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::MissingBlobIndex`] if the index of the blob is not known, or an
+    /// error if the blob is invalid or the proofs don't prove its shares.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use celestia_types::nmt::NamespaceProof;
+    /// use celestia_types::Blob;
+    /// # use celestia_types::ExtendedHeader;
+    /// # fn get_extended_header(_: u64) -> ExtendedHeader {
+    /// #     unimplemented!()
+    /// # }
+    /// # fn get_blob_with_proofs(_: u64) -> (Blob, Vec<NamespaceProof>) {
+    /// #     unimplemented!()
+    /// # }
+    /// let header = get_extended_header(100);
+    /// let (blob, proofs) = get_blob_with_proofs(100);
+    ///
+    /// let app_version = header.app_version().unwrap();
+    /// blob.verify_inclusion(&proofs, &header.dah, app_version).unwrap();
+    /// ```
+    pub fn verify_inclusion(
+        &self,
+        proofs: &[NamespaceProof],
+        dah: &DataAvailabilityHeader,
+        app_version: AppVersion,
+    ) -> Result<()> {
+        let square_size = dah.square_len() / 2;
+        self.validate_for_square_size(square_size, app_version)?;
+
+        let range = self.share_range(square_size)?;
+        let first_row = range.start / square_size;
+        let last_row = (range.end - 1) / square_size;
+
+        if proofs.len() != last_row - first_row + 1 {
+            bail_verification!(
+                "blob spans rows {first_row}..={last_row}, got {} proofs",
+                proofs.len()
+            );
+        }
+
+        let shares = self.to_shares()?;
+        let mut shares = &shares[..];
+
+        for (row, proof) in (first_row..=last_row).zip(proofs) {
+            let row_start = row * square_size;
+            let start = range.start.max(row_start);
+            let end = range.end.min(row_start + square_size);
+
+            if proof.is_of_absence() {
+                bail_verification!("unexpected proof of absence in row {row}");
+            }
+
+            if proof.start_idx() as usize != start - row_start {
+                bail_verification!("proof of row {row} doesn't start at the blob's share");
+            }
+
+            let Some(root) = dah.row_root(row) else {
+                bail_verification!("row {row} not found in the DAH");
+            };
+
+            let (row_shares, rest) = shares.split_at(end - start);
+            proof
+                .verify_range(&root, row_shares, *self.namespace)
+                .map_err(Error::RangeProofError)?;
+
+            shares = rest;
+        }
+
+        Ok(())
+    }
+
+    /// Create the [`BlobSpanProof`] of the [`Blob`]s inclusion in the block, through the
+    /// subtree roots of its [`Commitment`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::MissingBlobIndex`] if the index of the blob is not known,
+    /// [`Error::BlobNotInSquare`] if the square doesn't hold the blob at its index,
+    /// [`Error::UnalignedBlob`] if the blob placement doesn't follow the share
+    /// commitment rules, or [`Error::RootMismatch`] if the [`DataAvailabilityHeader`]
+    /// isn't the one of the square.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use celestia_types::consts::appconsts::AppVersion;
+    /// use celestia_types::nmt::Namespace;
+    /// use celestia_types::{Blob, DataAvailabilityHeader, ExtendedDataSquare};
+    ///
+    /// let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+    /// let blob = Blob::new(namespace, vec![1; 5000], AppVersion::V2).unwrap();
+    /// let eds = ExtendedDataSquare::build(&[blob], &[], 4, AppVersion::V2).unwrap();
+    /// let dah = DataAvailabilityHeader::from_eds(&eds).unwrap();
+    ///
+    /// let blob = eds.get_blobs(namespace, AppVersion::V2).unwrap().remove(0);
+    /// let proof = blob.span_proof(&eds, &dah, AppVersion::V2).unwrap();
+    ///
+    /// proof.verify_commitment(&blob.commitment, &dah.hash()).unwrap();
+    /// ```
+    pub fn span_proof(
+        &self,
+        eds: &ExtendedDataSquare,
+        dah: &DataAvailabilityHeader,
+        app_version: AppVersion,
+    ) -> Result<BlobSpanProof> {
+        let ods_width = eds.square_len() / 2;
+        let range = self.share_range(ods_width)?;
+        let shares = self.to_shares()?;
+        let subtree_roots = commitment::subtree_roots(self.namespace, &shares, app_version)?;
+        let hasher = NamespacedSha2Hasher::with_ignore_max_ns(true);
+
+        let mut subtree_root_proofs = Vec::with_capacity(subtree_roots.len());
+        let mut row_leaf_hashes = Vec::new();
+        let mut start = range.start;
+
+        for (size, root) in commitment::subtree_sizes(shares.len(), app_version)
+            .into_iter()
+            .zip(&subtree_roots)
+        {
+            let row = start / ods_width;
+            let col = start % ods_width;
+
+            if col % size != 0 || col + size > ods_width {
+                return Err(Error::UnalignedBlob(range.start));
+            }
+
+            if col == 0 || row_leaf_hashes.is_empty() {
+                row_leaf_hashes = eds.row_leaf_hashes(row)?;
+
+                let row_root = span_proof::compute_subtree_root(&hasher, &row_leaf_hashes);
+                if dah.row_root(row) != Some(row_root) {
+                    return Err(Error::RootMismatch);
+                }
+            }
+
+            if span_proof::compute_subtree_root(&hasher, &row_leaf_hashes[col..col + size]) != *root
+            {
+                return Err(Error::BlobNotInSquare(range.start));
+            }
+
+            let mut siblings = Vec::new();
+            let mut node_start = col;
+            let mut width = size;
+
+            while width < row_leaf_hashes.len() {
+                let sibling_start = node_start ^ width;
+                siblings.push(span_proof::compute_subtree_root(
+                    &hasher,
+                    &row_leaf_hashes[sibling_start..sibling_start + width],
+                ));
+                node_start &= !width;
+                width *= 2;
+            }
+
+            subtree_root_proofs.push(SubtreeRootProof {
+                row: row as u32,
+                start: col as u32,
+                width: size as u32,
+                siblings,
+            });
+
+            start += size;
+        }
+
+        let first_row = range.start / ods_width;
+        let last_row = (range.end - 1) / ods_width;
+
+        Ok(BlobSpanProof {
+            subtree_roots,
+            subtree_root_proofs,
+            row_proof: dah.row_proof(first_row..=last_row)?,
+        })
+    }
+
     /// Encode the blob into a sequence of shares.
     ///
     /// Check the [`Share`] documentation for more information about the share format.
@@ -132,11 +491,12 @@ impl Blob {
     /// # Example
     ///
     /// ```
+    /// use celestia_types::consts::appconsts::AppVersion;
     /// use celestia_types::Blob;
     /// # use celestia_types::nmt::Namespace;
     /// # let namespace = Namespace::new_v0(&[1, 2, 3, 4, 5]).expect("Invalid namespace");
     ///
-    /// let blob = Blob::new(namespace, b"foo".to_vec()).unwrap();
+    /// let blob = Blob::new(namespace, b"foo".to_vec(), AppVersion::V2).unwrap();
     /// let shares = blob.to_shares().unwrap();
     ///
     /// assert_eq!(shares.len(), 1);
@@ -147,23 +507,147 @@ impl Blob {
     pub fn to_shares(&self) -> Result<Vec<Share>> {
         commitment::split_blob_to_shares(self.namespace, self.share_version, &self.data)
     }
-}
 
-impl Protobuf<RawBlob> for Blob {}
+    /// Reconstruct all the [`Blob`]s from the shares of the [`NamespacedData`] rows.
+    ///
+    /// Rows are expected to be in order and to cover all the shares of the namespace,
+    /// so that blobs spanning multiple rows can be stitched together. Namespace padding
+    /// shares found between the blobs are skipped.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any of the shares is invalid, uses an
+    /// unsupported share version, or if the shares don't form complete sequences.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use celestia_types::Blob;
+    /// use celestia_types::nmt::Namespace;
+    /// # use celestia_types::{ExtendedDataSquare, ExtendedHeader};
+    /// # fn get_extended_data_square(height: usize) -> ExtendedDataSquare {
+    /// #    unimplemented!()
+    /// # }
+    /// # fn get_extended_header(height: usize) -> ExtendedHeader {
+    /// #    unimplemented!()
+    /// # }
+    ///
+    /// let block_height = 100;
+    /// let eds = get_extended_data_square(block_height);
+    /// let header = get_extended_header(block_height);
+    ///
+    /// let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+    /// let rows = eds.get_namespaced_data(namespace, &header.dah, block_height as u64).unwrap();
+    ///
+    /// let app_version = header.app_version().unwrap();
+    /// let blobs = Blob::reconstruct_all(&rows, app_version).unwrap();
+    /// ```
+    pub fn reconstruct_all(rows: &[NamespacedData], app_version: AppVersion) -> Result<Vec<Blob>> {
+        let shares = rows
+            .iter()
+            .flat_map(|row| &row.shares)
+            .map(|share| (None, &share[..]));
+
+        Blob::reconstruct_from_shares(shares, app_version)
+    }
+
+    /// Reconstruct the [`Blob`]s from the consecutive shares of a single namespace,
+    /// each with its optional index in the original data square.
+    pub(crate) fn reconstruct_from_shares<'a, I>(
+        shares: I,
+        app_version: AppVersion,
+    ) -> Result<Vec<Blob>>
+    where
+        I: IntoIterator<Item = (Option<u64>, &'a [u8])>,
+    {
+        let mut blobs = Vec::new();
+        let mut current: Option<(Namespace, Option<u64>, usize, Vec<u8>)> = None;
+
+        for (index, raw_share) in shares {
+            let share = Share::from_raw(raw_share)?;
+            let info_byte = share.info_byte();
+
+            // only the blobs of the share version zero can be parsed
+            if info_byte.version() != appconsts::SHARE_VERSION_ZERO
+                || !appconsts::supported_share_versions(app_version).contains(&info_byte.version())
+            {
+                return Err(Error::UnsupportedShareVersion(info_byte.version()));
+            }
+
+            let (namespace, start, len, mut data) = match current.take() {
+                Some((namespace, start, len, data)) => {
+                    if info_byte.is_sequence_start() || share.namespace() != namespace {
+                        return Err(Error::IncompleteShareSequence(len, data.len()));
+                    }
+                    (namespace, start, len, data)
+                }
+                None => {
+                    let Some(len) = share.sequence_length() else {
+                        return Err(Error::UnexpectedContinuationShare);
+                    };
+                    let len = len as usize;
+
+                    // namespace padding shares have zero sequence length
+                    if len == 0 {
+                        continue;
+                    }
 
-impl TryFrom<RawBlob> for Blob {
-    type Error = Error;
+                    (share.namespace(), index, len, Vec::with_capacity(len))
+                }
+            };
 
-    fn try_from(value: RawBlob) -> Result<Self, Self::Error> {
-        let namespace = Namespace::new(value.namespace_version as u8, &value.namespace_id)?;
-        let commitment =
-            Commitment::from_blob(namespace, value.share_version as u8, &value.data[..])?;
+            let offset = if info_byte.is_sequence_start() {
+                appconsts::SHARE_INFO_BYTES + appconsts::SEQUENCE_LEN_BYTES
+            } else {
+                appconsts::SHARE_INFO_BYTES
+            };
+            let content = &share.data()[offset..];
+            let read_amount = content.len().min(len - data.len());
+            data.extend_from_slice(&content[..read_amount]);
+
+            if data.len() == len {
+                let mut blob = Blob::new(namespace, data, app_version)?;
+                blob.index = start;
+                blobs.push(blob);
+            } else {
+                current = Some((namespace, start, len, data));
+            }
+        }
+
+        if let Some((_, _, len, data)) = current {
+            return Err(Error::IncompleteShareSequence(len, data.len()));
+        }
+
+        Ok(blobs)
+    }
+}
+
+impl Blob {
+    /// Create the [`Blob`] from its protobuf representation, computing the
+    /// [`Commitment`] following the rules of the given [`AppVersion`].
+    ///
+    /// Unlike the JSON one, the protobuf representation doesn't carry the
+    /// [`Commitment`], so the blob can't be decoded without knowing the app version.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the namespace is invalid or the
+    /// [`Commitment`] can't be computed.
+    pub fn from_raw(raw: RawBlob, app_version: AppVersion) -> Result<Blob> {
+        let namespace = Namespace::new(raw.namespace_version as u8, &raw.namespace_id)?;
+        let commitment = Commitment::from_blob(
+            namespace,
+            raw.share_version as u8,
+            &raw.data[..],
+            app_version,
+        )?;
 
         Ok(Blob {
             commitment,
             namespace,
-            data: value.data,
-            share_version: value.share_version as u8,
+            data: raw.data,
+            share_version: raw.share_version as u8,
+            index: None,
         })
     }
 }
@@ -182,6 +666,11 @@ impl From<Blob> for RawBlob {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::namespaced_data::NamespacedDataId;
+    use crate::nmt::{NamespacedSha2Hasher, Nmt};
+    use crate::{DataAvailabilityHeader, ExtendedDataSquare};
+    use bytes::Bytes;
+    use nmt_rs::NamespaceProof as NmtNamespaceProof;
 
     #[cfg(target_arch = "wasm32")]
     use wasm_bindgen_test::wasm_bindgen_test as test;
@@ -202,14 +691,30 @@ mod tests {
     fn create_from_raw() {
         let expected = sample_blob();
         let raw = RawBlob::from(expected.clone());
-        let created = Blob::try_from(raw).unwrap();
+        let created = Blob::from_raw(raw, AppVersion::V2).unwrap();
 
         assert_eq!(created, expected);
     }
 
+    #[test]
+    fn deserialize_blobs_at_height() {
+        let blobs: BlobsAtHeight = serde_json::from_str(r#"{"blobs":null,"height":5}"#).unwrap();
+        assert!(blobs.blobs.is_empty());
+        assert_eq!(blobs.height, 5);
+
+        let json = serde_json::to_string(&BlobsAtHeight {
+            blobs: vec![sample_blob()],
+            height: 7,
+        })
+        .unwrap();
+        let blobs: BlobsAtHeight = serde_json::from_str(&json).unwrap();
+        assert_eq!(blobs.blobs, vec![sample_blob()]);
+        assert_eq!(blobs.height, 7);
+    }
+
     #[test]
     fn validate_blob() {
-        sample_blob().validate().unwrap();
+        sample_blob().validate(AppVersion::V2).unwrap();
     }
 
     #[test]
@@ -217,6 +722,324 @@ mod tests {
         let mut blob = sample_blob();
         blob.commitment.0.fill(7);
 
-        blob.validate().unwrap_err();
+        blob.validate(AppVersion::V2).unwrap_err();
+    }
+
+    #[test]
+    fn validate_blob_share_version() {
+        let mut blob = sample_blob();
+        blob.share_version = 1;
+
+        assert!(matches!(
+            blob.validate(AppVersion::V2).unwrap_err(),
+            Error::UnsupportedShareVersion(1)
+        ));
+    }
+
+    #[test]
+    fn validate_blob_size() {
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+        let max_shares = appconsts::DEFAULT_GOV_MAX_SQUARE_SIZE.pow(2) - 1;
+        let max_len = appconsts::FIRST_SPARSE_SHARE_CONTENT_SIZE
+            + (max_shares - 1) * appconsts::CONTINUATION_SPARSE_SHARE_CONTENT_SIZE;
+
+        let blob = Blob::new(namespace, vec![1; max_len], AppVersion::V2).unwrap();
+        assert_eq!(blob.shares_len(), max_shares);
+        blob.validate(AppVersion::V2).unwrap();
+
+        let blob = Blob::new(namespace, vec![1; max_len + 1], AppVersion::V2).unwrap();
+        assert!(matches!(
+            blob.validate(AppVersion::V2).unwrap_err(),
+            Error::BlobTooLarge(len, max) if len == max_shares + 1 && max == max_shares
+        ));
+        blob.validate_for_square_size(128, AppVersion::V2).unwrap();
+    }
+
+    #[test]
+    fn reserved_namespace_rejected() {
+        for namespace in [
+            Namespace::TRANSACTION,
+            Namespace::PRIMARY_RESERVED_PADDING,
+            Namespace::TAIL_PADDING,
+        ] {
+            assert!(matches!(
+                Blob::new(namespace, vec![1; 10], AppVersion::V2).unwrap_err(),
+                Error::ReservedNamespace(ns) if ns == namespace
+            ));
+        }
+
+        let mut blob = sample_blob();
+        blob.namespace = Namespace::PAY_FOR_BLOB;
+        assert!(matches!(
+            blob.validate(AppVersion::V2).unwrap_err(),
+            Error::ReservedNamespace(_)
+        ));
+    }
+
+    fn namespaced_data(shares: &[Share], row: u16) -> NamespacedData {
+        let namespace = shares[0].namespace();
+        let mut tree = Nmt::with_hasher(NamespacedSha2Hasher::with_ignore_max_ns(true));
+        for share in shares {
+            tree.push_leaf(share.as_ref(), *namespace).unwrap();
+        }
+
+        NamespacedData {
+            namespaced_data_id: NamespacedDataId::new(namespace, row, 1).unwrap(),
+            proof: tree.get_namespace_proof(*namespace).into(),
+            shares: shares
+                .iter()
+                .map(|share| Bytes::from(share.to_vec()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn reconstruct_blobs_across_rows() {
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+        let small = Blob::new(namespace, vec![1; 100], AppVersion::V2).unwrap();
+        let large = Blob::new(
+            namespace,
+            vec![2; 3 * appconsts::SHARE_SIZE],
+            AppVersion::V2,
+        )
+        .unwrap();
+
+        // namespace padding share, a sequence start with zero length
+        let mut padding = [0; appconsts::SHARE_SIZE];
+        padding[..appconsts::NAMESPACE_SIZE].copy_from_slice(namespace.as_bytes());
+        padding[appconsts::NAMESPACE_SIZE] = 1;
+
+        let mut shares = small.to_shares().unwrap();
+        shares.push(Share::from_raw(&padding).unwrap());
+        shares.extend(large.to_shares().unwrap());
+        assert_eq!(shares.len(), 6);
+
+        let rows = [
+            namespaced_data(&shares[..3], 0),
+            namespaced_data(&shares[3..], 1),
+        ];
+
+        let blobs = Blob::reconstruct_all(&rows, AppVersion::V2).unwrap();
+        assert_eq!(blobs, vec![small, large]);
+    }
+
+    #[test]
+    fn reconstruct_blobs_from_eds() {
+        let eds_json = include_str!("../test_data/shwap_samples/eds.json");
+        let eds: ExtendedDataSquare = serde_json::from_str(eds_json).unwrap();
+        let dah_json = include_str!("../test_data/shwap_samples/dah.json");
+        let dah: DataAvailabilityHeader = serde_json::from_str(dah_json).unwrap();
+
+        let namespace = Namespace::new_v0(&[1, 187]).unwrap();
+        let rows = eds.get_namespaced_data(namespace, &dah, 45577).unwrap();
+
+        let blobs = Blob::reconstruct_all(&rows, AppVersion::V2).unwrap();
+        assert!(!blobs.is_empty());
+        for blob in blobs {
+            assert_eq!(blob.namespace, namespace);
+            blob.validate(AppVersion::V2).unwrap();
+        }
+    }
+
+    #[test]
+    fn shares_len() {
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+
+        for len in [1, 478, 479, 960, 961, 5000] {
+            let blob = Blob::new(namespace, vec![1; len], AppVersion::V2).unwrap();
+            assert_eq!(blob.shares_len(), blob.to_shares().unwrap().len());
+        }
+    }
+
+    #[test]
+    fn share_range() {
+        let mut blob = sample_blob();
+        assert!(matches!(blob.share_range(4), Err(Error::MissingBlobIndex)));
+
+        blob.index = Some(15);
+        assert_eq!(blob.share_range(4).unwrap(), 15..16);
+        assert!(matches!(
+            blob.share_range(2),
+            Err(Error::EdsIndexOutOfRange(15))
+        ));
+    }
+
+    #[test]
+    fn deserialize_index() {
+        let mut blob = sample_blob();
+        assert_eq!(blob.index(), None);
+
+        blob.index = Some(8);
+        let json = serde_json::to_string(&blob).unwrap();
+        assert!(json.ends_with(r#""index":8}"#));
+        assert_eq!(serde_json::from_str::<Blob>(&json).unwrap(), blob);
+    }
+
+    /// Prove the shares of the blob in each of the rows it spans.
+    fn blob_proofs(eds: &ExtendedDataSquare, blob: &Blob) -> Vec<NamespaceProof> {
+        let square_size = eds.square_len() / 2;
+        let range = blob.share_range(square_size).unwrap();
+
+        (range.start / square_size..=(range.end - 1) / square_size)
+            .map(|row| {
+                let mut tree = Nmt::with_hasher(NamespacedSha2Hasher::with_ignore_max_ns(true));
+                for (col, share) in eds.row(row).unwrap().iter().enumerate() {
+                    let ns = if col < square_size {
+                        Namespace::from_raw(&share[..appconsts::NAMESPACE_SIZE]).unwrap()
+                    } else {
+                        Namespace::PARITY_SHARE
+                    };
+                    tree.push_leaf(share, *ns).unwrap();
+                }
+
+                let start = range.start.max(row * square_size) - row * square_size;
+                let end = range.end.min((row + 1) * square_size) - row * square_size;
+                NmtNamespaceProof::PresenceProof {
+                    proof: tree.build_range_proof(start..end),
+                    ignore_max_ns: true,
+                }
+                .into()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn verify_inclusion() {
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+        let small = Blob::new(namespace, vec![1; 100], AppVersion::V2).unwrap();
+        let large = Blob::new(
+            namespace,
+            vec![2; 10 * appconsts::SHARE_SIZE],
+            AppVersion::V2,
+        )
+        .unwrap();
+        let eds = ExtendedDataSquare::build(&[small, large], &[], 4, AppVersion::V2).unwrap();
+        let dah = DataAvailabilityHeader::from_eds(&eds).unwrap();
+
+        let blobs = eds.get_blobs(namespace, AppVersion::V2).unwrap();
+        let small_proofs = blob_proofs(&eds, &blobs[0]);
+        let large_proofs = blob_proofs(&eds, &blobs[1]);
+        assert_eq!(small_proofs.len(), 1);
+        assert_eq!(large_proofs.len(), 3);
+
+        blobs[0]
+            .verify_inclusion(&small_proofs, &dah, AppVersion::V2)
+            .unwrap();
+        blobs[1]
+            .verify_inclusion(&large_proofs, &dah, AppVersion::V2)
+            .unwrap();
+
+        // proofs of the other blob
+        blobs[1]
+            .verify_inclusion(&small_proofs, &dah, AppVersion::V2)
+            .unwrap_err();
+        blobs[1]
+            .verify_inclusion(&large_proofs[..2], &dah, AppVersion::V2)
+            .unwrap_err();
+
+        // wrong index
+        let mut moved = blobs[0].clone();
+        moved.index = Some(1);
+        moved
+            .verify_inclusion(&small_proofs, &dah, AppVersion::V2)
+            .unwrap_err();
+
+        // no index
+        let blob = Blob::new(namespace, vec![1; 100], AppVersion::V2).unwrap();
+        assert!(matches!(
+            blob.verify_inclusion(&small_proofs, &dah, AppVersion::V2)
+                .unwrap_err(),
+            Error::MissingBlobIndex
+        ));
+    }
+
+    #[test]
+    fn span_proof() {
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+        let small = Blob::new(namespace, vec![1; 100], AppVersion::V2).unwrap();
+        let large = Blob::new(
+            namespace,
+            vec![2; 20 * appconsts::SHARE_SIZE],
+            AppVersion::V2,
+        )
+        .unwrap();
+        let eds = ExtendedDataSquare::build(&[small, large], &[], 8, AppVersion::V2).unwrap();
+        let dah = DataAvailabilityHeader::from_eds(&eds).unwrap();
+        let data_root = dah.hash();
+
+        let blobs = eds.get_blobs(namespace, AppVersion::V2).unwrap();
+        let small_proof = blobs[0].span_proof(&eds, &dah, AppVersion::V2).unwrap();
+        let large_proof = blobs[1].span_proof(&eds, &dah, AppVersion::V2).unwrap();
+
+        assert_eq!(small_proof.subtree_roots.len(), 1);
+        assert!(large_proof.row_proof.end_row > large_proof.row_proof.start_row);
+
+        small_proof
+            .verify(&blobs[0], &data_root, AppVersion::V2)
+            .unwrap();
+        large_proof
+            .verify(&blobs[1], &data_root, AppVersion::V2)
+            .unwrap();
+        large_proof
+            .verify_commitment(&blobs[1].commitment, &data_root)
+            .unwrap();
+
+        // proof of the other blob
+        small_proof
+            .verify(&blobs[1], &data_root, AppVersion::V2)
+            .unwrap_err();
+        small_proof
+            .verify_commitment(&blobs[1].commitment, &data_root)
+            .unwrap_err();
+
+        // different data root
+        let other_eds = ExtendedDataSquare::build(&blobs[1..], &[], 8, AppVersion::V2).unwrap();
+        let other_dah = DataAvailabilityHeader::from_eds(&other_eds).unwrap();
+        large_proof
+            .verify(&blobs[1], &other_dah.hash())
+            .unwrap_err();
+
+        // missing subtree
+        let mut proof = large_proof.clone();
+        proof.subtree_roots.pop();
+        proof.subtree_root_proofs.pop();
+        proof.verify(&blobs[1], &data_root).unwrap_err();
+
+        // subtree moved in its row
+        let mut proof = large_proof.clone();
+        proof.subtree_root_proofs[0].start += proof.subtree_root_proofs[0].width;
+        proof.verify(&blobs[1], &data_root).unwrap_err();
+
+        // dah of the other square
+        assert!(matches!(
+            blobs[1]
+                .span_proof(&eds, &other_dah, AppVersion::V2)
+                .unwrap_err(),
+            Error::RootMismatch
+        ));
+
+        let json = serde_json::to_string(&large_proof).unwrap();
+        let decoded: BlobSpanProof = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, large_proof);
+    }
+
+    #[test]
+    fn reconstruct_incomplete_blob() {
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+        let blob = Blob::new(
+            namespace,
+            vec![2; 3 * appconsts::SHARE_SIZE],
+            AppVersion::V2,
+        )
+        .unwrap();
+        let shares = blob.to_shares().unwrap();
+
+        let rows = [namespaced_data(&shares[..2], 0)];
+        let err = Blob::reconstruct_all(&rows, AppVersion::V2).unwrap_err();
+        assert!(matches!(err, Error::IncompleteShareSequence(..)));
+
+        let rows = [namespaced_data(&shares[1..], 0)];
+        let err = Blob::reconstruct_all(&rows, AppVersion::V2).unwrap_err();
+        assert!(matches!(err, Error::UnexpectedContinuationShare));
     }
 }
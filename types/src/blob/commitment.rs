@@ -1,16 +1,18 @@
+use std::fmt;
 use std::io::Cursor;
 use std::num::NonZeroU64;
+use std::str::FromStr;
 
-use base64::prelude::*;
 use bytes::{Buf, BufMut, BytesMut};
-use celestia_tendermint::crypto::sha256::HASH_SIZE;
 use celestia_tendermint::{crypto, merkle};
-use celestia_tendermint_proto::serializers::cow_str::CowStr;
 use nmt_rs::NamespaceMerkleHasher;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-use crate::consts::appconsts;
-use crate::nmt::{Namespace, NamespacedHashExt, NamespacedSha2Hasher, Nmt, RawNamespacedHash};
+use crate::consts::appconsts::{self, AppVersion};
+use crate::nmt::{
+    Namespace, NamespacedHash, NamespacedHashExt, NamespacedSha2Hasher, Nmt, RawNamespacedHash,
+};
+use crate::serializers::decode_hex;
 use crate::{Error, Result};
 use crate::{InfoByte, Share};
 
@@ -55,46 +57,137 @@ pub struct Commitment(pub merkle::Hash);
 
 impl Commitment {
     /// Generate the share commitment from the given blob data.
+    ///
+    /// The data is split into the [`Share`]s of the given version and the
+    /// commitment is computed over them, with the subtree root threshold of the
+    /// given [`AppVersion`], so it can be obtained offline before submitting the
+    /// [`MsgPayForBlobs`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the data is empty, its length overflows
+    /// [`u32`] or the share version is not supported.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use celestia_types::consts::appconsts::{self, AppVersion};
+    /// use celestia_types::{nmt::Namespace, Blob, Commitment};
+    ///
+    /// let namespace = Namespace::new_v0(&[1, 2, 3, 4, 5]).unwrap();
+    /// let data = b"some data to store on blockchain";
+    ///
+    /// let commitment = Commitment::from_blob(
+    ///     namespace,
+    ///     appconsts::SHARE_VERSION_ZERO,
+    ///     data,
+    ///     AppVersion::V2,
+    /// )
+    /// .unwrap();
+    ///
+    /// let blob = Blob::new(namespace, data.to_vec(), AppVersion::V2).unwrap();
+    /// assert_eq!(commitment, blob.commitment);
+    /// ```
+    ///
+    /// [`AppVersion`]: appconsts::AppVersion
+    /// [`MsgPayForBlobs`]: crate::blob::MsgPayForBlobs
     pub fn from_blob(
         namespace: Namespace,
         share_version: u8,
         blob_data: &[u8],
+        app_version: AppVersion,
     ) -> Result<Commitment> {
         let shares = split_blob_to_shares(namespace, share_version, blob_data)?;
-        Self::from_shares(namespace, &shares)
+        Self::from_shares(namespace, &shares, app_version)
     }
 
     /// Generate the commitment from the given shares.
-    pub fn from_shares(namespace: Namespace, mut shares: &[Share]) -> Result<Commitment> {
-        // the commitment is the root of a merkle mountain range with max tree size
-        // determined by the number of roots required to create a share commitment
-        // over that blob. The size of the tree is only increased if the number of
-        // subtree roots surpasses a constant threshold.
-        let subtree_width = subtree_width(shares.len() as u64, appconsts::SUBTREE_ROOT_THRESHOLD);
-        let tree_sizes = merkle_mountain_range_sizes(shares.len() as u64, subtree_width);
-
-        let mut leaf_sets: Vec<&[_]> = Vec::with_capacity(tree_sizes.len());
-
-        for size in tree_sizes {
-            let (leafs, rest) = shares.split_at(size as usize);
-            leaf_sets.push(leafs);
-            shares = rest;
-        }
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if no shares are provided.
+    pub fn from_shares(
+        namespace: Namespace,
+        shares: &[Share],
+        app_version: AppVersion,
+    ) -> Result<Commitment> {
+        let subtree_roots = subtree_roots(namespace, shares, app_version)?;
+        Ok(Commitment::from_subtree_roots(&subtree_roots))
+    }
+
+    /// Generate the commitment from the roots of the subtrees created from the blob shares.
+    pub(crate) fn from_subtree_roots(subtree_roots: &[NamespacedHash]) -> Commitment {
+        let subtree_roots: Vec<RawNamespacedHash> =
+            subtree_roots.iter().map(|root| root.to_array()).collect();
+        let hash = merkle::simple_hash_from_byte_vectors::<crypto::default::Sha256>(&subtree_roots);
 
-        // create the commitments by pushing each leaf set onto an nmt
-        let mut subtree_roots: Vec<RawNamespacedHash> = Vec::with_capacity(leaf_sets.len());
-        for leaf_set in leaf_sets {
-            // create the nmt
-            let mut tree = Nmt::with_hasher(NamespacedSha2Hasher::with_ignore_max_ns(true));
-            for leaf_share in leaf_set {
-                tree.push_leaf(leaf_share.as_ref(), namespace.into())
-                    .map_err(Error::Nmt)?;
-            }
-            // add the root
-            subtree_roots.push(tree.root().to_array());
+        Commitment(hash)
+    }
+}
+
+/// Compute the roots of the subtrees created from the blob shares, over which the
+/// [`Commitment`] is computed.
+pub(crate) fn subtree_roots(
+    namespace: Namespace,
+    mut shares: &[Share],
+    app_version: AppVersion,
+) -> Result<Vec<NamespacedHash>> {
+    if shares.is_empty() {
+        return Err(Error::EmptyBlob);
+    }
+
+    let tree_sizes = subtree_sizes(shares.len(), app_version);
+    let mut subtree_roots = Vec::with_capacity(tree_sizes.len());
+
+    // create the subtree roots by pushing each leaf set onto an nmt
+    for size in tree_sizes {
+        let (leaf_set, rest) = shares.split_at(size);
+        shares = rest;
+
+        let mut tree = Nmt::with_hasher(NamespacedSha2Hasher::with_ignore_max_ns(true));
+        for leaf_share in leaf_set {
+            tree.push_leaf(leaf_share.as_ref(), namespace.into())
+                .map_err(Error::Nmt)?;
         }
+        subtree_roots.push(tree.root());
+    }
 
-        let hash = merkle::simple_hash_from_byte_vectors::<crypto::default::Sha256>(&subtree_roots);
+    Ok(subtree_roots)
+}
+
+/// Sizes of the subtrees created from the given number of blob shares.
+///
+/// The commitment is the root of a merkle mountain range with max tree size
+/// determined by the number of roots required to create a share commitment
+/// over that blob. The size of the tree is only increased if the number of
+/// subtree roots surpasses a constant threshold.
+pub(crate) fn subtree_sizes(shares_len: usize, app_version: AppVersion) -> Vec<usize> {
+    let threshold = appconsts::subtree_root_threshold(app_version);
+    let subtree_width = subtree_width(shares_len as u64, threshold);
+
+    merkle_mountain_range_sizes(shares_len as u64, subtree_width)
+        .into_iter()
+        .map(|size| size as usize)
+        .collect()
+}
+
+impl fmt::Display for Commitment {
+    /// Formats the [`Commitment`] as a `0x` prefixed hex string.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{}", hex::encode(self.0))
+    }
+}
+
+impl FromStr for Commitment {
+    type Err = Error;
+
+    /// Parses the [`Commitment`] from a hex string, optionally prefixed with `0x`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = decode_hex(s)?;
+        let hash: merkle::Hash = bytes
+            .as_slice()
+            .try_into()
+            .map_err(|_| Error::InvalidCommitmentSize(bytes.len()))?;
 
         Ok(Commitment(hash))
     }
@@ -105,8 +198,7 @@ impl Serialize for Commitment {
     where
         S: Serializer,
     {
-        let s = BASE64_STANDARD.encode(self.0);
-        serializer.serialize_str(&s)
+        commitment_serde::base64::serialize(self, serializer)
     }
 }
 
@@ -115,20 +207,93 @@ impl<'de> Deserialize<'de> for Commitment {
     where
         D: Deserializer<'de>,
     {
-        // base64 needs more buffer size than the final output
-        let mut buf = [0u8; HASH_SIZE * 2];
+        commitment_serde::base64::deserialize(deserializer)
+    }
+}
 
-        let s = CowStr::deserialize(deserializer)?;
+/// Serde representations of the [`Commitment`] for use with `#[serde(with = "...")]`.
+///
+/// ```
+/// use celestia_types::blob::commitment_serde;
+/// use celestia_types::Commitment;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Explorer {
+///     #[serde(with = "commitment_serde::hex")]
+///     commitment: Commitment,
+/// }
+///
+/// let explorer = Explorer { commitment: Commitment([0xab; 32]) };
+/// let json = serde_json::to_string(&explorer).unwrap();
+///
+/// assert_eq!(json, format!(r#"{{"commitment":"0x{}"}}"#, "ab".repeat(32)));
+/// ```
+pub mod commitment_serde {
+    /// Base64 representation, used by celestia-node's JSON RPC. This is the default one.
+    pub mod base64 {
+        use base64::prelude::*;
+        use celestia_tendermint::crypto::sha256::HASH_SIZE;
+        use celestia_tendermint::merkle;
+        use celestia_tendermint_proto::serializers::cow_str::CowStr;
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        use crate::Commitment;
+
+        /// Serialize the [`Commitment`] as a base64 string.
+        pub fn serialize<S>(commitment: &Commitment, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            let s = BASE64_STANDARD.encode(commitment.0);
+            serializer.serialize_str(&s)
+        }
 
-        let len = BASE64_STANDARD
-            .decode_slice(s, &mut buf)
-            .map_err(|e| serde::de::Error::custom(e.to_string()))?;
+        /// Deserialize the [`Commitment`] from a base64 string.
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<Commitment, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            // base64 needs more buffer size than the final output
+            let mut buf = [0u8; HASH_SIZE * 2];
 
-        let hash: merkle::Hash = buf[..len]
-            .try_into()
-            .map_err(|_| serde::de::Error::custom("commitment is not a size of a sha256"))?;
+            let s = CowStr::deserialize(deserializer)?;
 
-        Ok(Commitment(hash))
+            let len = BASE64_STANDARD
+                .decode_slice(s, &mut buf)
+                .map_err(|e| serde::de::Error::custom(e.to_string()))?;
+
+            let hash: merkle::Hash = buf[..len]
+                .try_into()
+                .map_err(|_| serde::de::Error::custom("commitment is not a size of a sha256"))?;
+
+            Ok(Commitment(hash))
+        }
+    }
+
+    /// Hex representation with the `0x` prefix, as used by the explorers.
+    pub mod hex {
+        use celestia_tendermint_proto::serializers::cow_str::CowStr;
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        use crate::Commitment;
+
+        /// Serialize the [`Commitment`] as a `0x` prefixed hex string.
+        pub fn serialize<S>(commitment: &Commitment, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            serializer.collect_str(commitment)
+        }
+
+        /// Deserialize the [`Commitment`] from a hex string, optionally prefixed with `0x`.
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<Commitment, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let s = CowStr::deserialize(deserializer)?;
+            s.parse().map_err(serde::de::Error::custom)
+        }
     }
 }
 
@@ -231,7 +396,7 @@ fn blob_min_square_size(share_count: u64) -> u64 {
 /// used by that blob. The reasoning behind this algorithm is discussed in depth
 /// in ADR013
 /// (celestia-app/docs/architecture/adr-013-non-interative-default-rules-for-zero-padding).
-fn subtree_width(share_count: u64, subtree_root_threshold: u64) -> u64 {
+pub(crate) fn subtree_width(share_count: u64, subtree_root_threshold: u64) -> u64 {
     // per ADR013, we use a predetermined threshold to determine width of sub
     // trees used to create share commitments
     let mut s = share_count / subtree_root_threshold;
@@ -251,7 +416,7 @@ fn subtree_width(share_count: u64, subtree_root_threshold: u64) -> u64 {
     s.min(blob_min_square_size(share_count))
 }
 
-/// round_up_to_power_of_2 returns the next power of two that is strictly greater than input.
+/// round_up_to_power_of_2 returns the next power of two greater than or equal to input.
 fn round_up_to_power_of_2(x: u64) -> Option<u64> {
     let mut po2 = 1;
 
@@ -285,6 +450,52 @@ mod tests {
     #[cfg(target_arch = "wasm32")]
     use wasm_bindgen_test::wasm_bindgen_test as test;
 
+    #[test]
+    fn commitment_hex_round_trip() {
+        let commitment = Commitment([0x1f; 32]);
+        let s = commitment.to_string();
+
+        assert_eq!(s, format!("0x{}", "1f".repeat(32)));
+        assert_eq!(s.parse::<Commitment>().unwrap(), commitment);
+        assert_eq!(s[2..].parse::<Commitment>().unwrap(), commitment);
+
+        assert!(matches!(
+            "0x1f1f".parse::<Commitment>(),
+            Err(Error::InvalidCommitmentSize(2))
+        ));
+        assert!(matches!(
+            "0x1".parse::<Commitment>(),
+            Err(Error::InvalidHex(_))
+        ));
+    }
+
+    #[test]
+    fn commitment_serde_representations() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Both {
+            base64: Commitment,
+            #[serde(with = "commitment_serde::hex")]
+            hex: Commitment,
+        }
+
+        let commitment = Commitment([0xff; 32]);
+        let both = Both {
+            base64: commitment,
+            hex: commitment,
+        };
+
+        let json = serde_json::to_string(&both).unwrap();
+        assert_eq!(
+            json,
+            format!(
+                r#"{{"base64":"{}","hex":"0x{}"}}"#,
+                "/".repeat(42) + "8=",
+                "ff".repeat(32)
+            )
+        );
+        assert_eq!(serde_json::from_str::<Both>(&json).unwrap(), both);
+    }
+
     #[test]
     fn test_single_sparse_share() {
         let namespace = Namespace::new(0, &[1, 1, 1, 1, 1, 1, 1, 1, 1, 1]).unwrap();
@@ -407,6 +618,69 @@ mod tests {
         );
     }
 
+    #[test]
+    fn subtree_widths() {
+        let threshold = appconsts::subtree_root_threshold(AppVersion::V2);
+
+        assert_eq!(subtree_width(1, threshold), 1);
+        assert_eq!(subtree_width(2, threshold), 1);
+        assert_eq!(subtree_width(threshold, threshold), 1);
+        assert_eq!(subtree_width(threshold + 1, threshold), 2);
+        assert_eq!(subtree_width(threshold * 2, threshold), 2);
+        assert_eq!(subtree_width(threshold * 3, threshold), 4);
+        assert_eq!(subtree_width(threshold * 64, threshold), 64);
+        // limited by the minimum square size of the blob
+        assert_eq!(subtree_width(threshold * 128, threshold), 128);
+        assert_eq!(subtree_width(threshold * 129, threshold), 128);
+    }
+
+    #[test]
+    fn commitment_of_empty_blob() {
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+
+        let err = Commitment::from_blob(
+            namespace,
+            appconsts::SHARE_VERSION_ZERO,
+            &[],
+            AppVersion::V2,
+        )
+        .unwrap_err();
+        assert!(matches!(err, Error::EmptyBlob));
+
+        let err = Commitment::from_shares(namespace, &[], AppVersion::V2).unwrap_err();
+        assert!(matches!(err, Error::EmptyBlob));
+    }
+
+    #[test]
+    fn commitment_from_blob_matches_shares() {
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+        // spans multiple subtrees
+        let data = vec![0xff; appconsts::CONTINUATION_SPARSE_SHARE_CONTENT_SIZE * 150];
+
+        let shares = split_blob_to_shares(namespace, appconsts::SHARE_VERSION_ZERO, &data).unwrap();
+        let commitment = Commitment::from_blob(
+            namespace,
+            appconsts::SHARE_VERSION_ZERO,
+            &data,
+            AppVersion::V2,
+        )
+        .unwrap();
+
+        assert_eq!(shares.len(), 151);
+        assert_eq!(
+            commitment,
+            Commitment::from_shares(namespace, &shares, AppVersion::V2).unwrap()
+        );
+    }
+
+    #[test]
+    fn commitment_of_unsupported_share_version() {
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+
+        let err = Commitment::from_blob(namespace, 1, b"foo", AppVersion::V2).unwrap_err();
+        assert!(matches!(err, Error::UnsupportedShareVersion(1)));
+    }
+
     #[test]
     fn merkle_mountain_ranges() {
         struct TestCase {
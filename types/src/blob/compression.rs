@@ -0,0 +1,304 @@
+//! Client side convention for the compression of the [`Blob`]s data.
+//!
+//! The network doesn't know about the compression, so it is recorded in the data itself:
+//! a compressed blob starts with the [`COMPRESSED_BLOB_MAGIC`] followed by a byte of the
+//! [`Compression`] codec, and the compressed payload.
+
+use std::borrow::Cow;
+#[cfg(any(feature = "zstd", feature = "gzip"))]
+use std::io::Read;
+
+use crate::consts::appconsts::AppVersion;
+use crate::nmt::Namespace;
+use crate::{Blob, Error, Result};
+
+/// Prefix of the data of the compressed [`Blob`]s.
+pub const COMPRESSED_BLOB_MAGIC: [u8; 4] = [0xce, 0x1e, 0xc0, 0xde];
+
+/// Maximum size of the decompressed data, protecting from the decompression bombs.
+pub const MAX_DECOMPRESSED_BLOB_SIZE: usize = 64 * 1024 * 1024;
+
+const HEADER_LEN: usize = COMPRESSED_BLOB_MAGIC.len() + 1;
+
+/// Codec used to compress the [`Blob`]s data.
+///
+/// Each codec requires its crate feature, `zstd` or `gzip`, to compress or decompress the data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+#[repr(u8)]
+pub enum Compression {
+    /// [Zstandard](https://facebook.github.io/zstd/) with the default compression level.
+    Zstd = 1,
+    /// [Gzip](https://www.rfc-editor.org/rfc/rfc1952) with the default compression level.
+    Gzip = 2,
+}
+
+impl Compression {
+    fn from_u8(codec: u8) -> Result<Self> {
+        match codec {
+            1 => Ok(Compression::Zstd),
+            2 => Ok(Compression::Gzip),
+            codec => Err(Error::UnsupportedCompression(codec)),
+        }
+    }
+
+    fn compress(self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut compressed = COMPRESSED_BLOB_MAGIC.to_vec();
+        compressed.push(self as u8);
+
+        match self {
+            Compression::Zstd => zstd_encode(data, &mut compressed)?,
+            Compression::Gzip => gzip_encode(data, &mut compressed)?,
+        }
+
+        Ok(compressed)
+    }
+
+    fn decompress(self, payload: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Compression::Zstd => zstd_decode(payload),
+            Compression::Gzip => gzip_decode(payload),
+        }
+    }
+}
+
+#[cfg(feature = "zstd")]
+fn zstd_encode(data: &[u8], out: &mut Vec<u8>) -> Result<()> {
+    zstd::stream::copy_encode(data, out, zstd::DEFAULT_COMPRESSION_LEVEL)
+        .map_err(Error::Compression)
+}
+
+#[cfg(feature = "zstd")]
+fn zstd_decode(payload: &[u8]) -> Result<Vec<u8>> {
+    let decoder = zstd::stream::read::Decoder::new(payload).map_err(Error::Compression)?;
+    read_limited(decoder)
+}
+
+#[cfg(not(feature = "zstd"))]
+fn zstd_encode(_data: &[u8], _out: &mut Vec<u8>) -> Result<()> {
+    Err(Error::UnsupportedCompression(Compression::Zstd as u8))
+}
+
+#[cfg(not(feature = "zstd"))]
+fn zstd_decode(_payload: &[u8]) -> Result<Vec<u8>> {
+    Err(Error::UnsupportedCompression(Compression::Zstd as u8))
+}
+
+#[cfg(feature = "gzip")]
+fn gzip_encode(data: &[u8], out: &mut Vec<u8>) -> Result<()> {
+    use std::io::Write;
+
+    let mut encoder = flate2::write::GzEncoder::new(out, flate2::Compression::default());
+    encoder.write_all(data).map_err(Error::Compression)?;
+    encoder.finish().map_err(Error::Compression)?;
+
+    Ok(())
+}
+
+#[cfg(feature = "gzip")]
+fn gzip_decode(payload: &[u8]) -> Result<Vec<u8>> {
+    read_limited(flate2::read::GzDecoder::new(payload))
+}
+
+#[cfg(not(feature = "gzip"))]
+fn gzip_encode(_data: &[u8], _out: &mut Vec<u8>) -> Result<()> {
+    Err(Error::UnsupportedCompression(Compression::Gzip as u8))
+}
+
+#[cfg(not(feature = "gzip"))]
+fn gzip_decode(_payload: &[u8]) -> Result<Vec<u8>> {
+    Err(Error::UnsupportedCompression(Compression::Gzip as u8))
+}
+
+#[cfg(any(feature = "zstd", feature = "gzip"))]
+fn read_limited(decoder: impl Read) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    decoder
+        .take(MAX_DECOMPRESSED_BLOB_SIZE as u64 + 1)
+        .read_to_end(&mut data)
+        .map_err(Error::Compression)?;
+
+    if data.len() > MAX_DECOMPRESSED_BLOB_SIZE {
+        return Err(Error::DecompressedBlobTooLarge(MAX_DECOMPRESSED_BLOB_SIZE));
+    }
+
+    Ok(data)
+}
+
+impl Blob {
+    /// Create a new blob within the [`Namespace`], with the data compressed using the given codec.
+    ///
+    /// The [`Compression`] is recorded in the blob data, so that the readers can get the
+    /// original data back with [`Blob::decompressed_data`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnsupportedCompression`] if the feature of the codec is not enabled,
+    /// and propagates the errors of the compression and [`Blob::new`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(feature = "zstd")]
+    /// # {
+    /// use celestia_types::consts::appconsts::AppVersion;
+    /// use celestia_types::blob::Compression;
+    /// use celestia_types::{Blob, nmt::Namespace};
+    ///
+    /// let namespace = Namespace::new_v0(&[1, 2, 3, 4, 5]).unwrap();
+    /// let data = vec![7; 10_000];
+    /// let blob = Blob::new_compressed(namespace, &data, Compression::Zstd, AppVersion::V2).unwrap();
+    ///
+    /// assert!(blob.data.len() < data.len());
+    /// assert_eq!(blob.compression().unwrap(), Some(Compression::Zstd));
+    /// assert_eq!(blob.decompressed_data().unwrap(), &data[..]);
+    /// # }
+    /// ```
+    pub fn new_compressed(
+        namespace: Namespace,
+        data: &[u8],
+        compression: Compression,
+        app_version: AppVersion,
+    ) -> Result<Blob> {
+        Blob::new(namespace, compression.compress(data)?, app_version)
+    }
+
+    /// Get the [`Compression`] of the blob data, or `None` if it wasn't compressed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnsupportedCompression`] if the data is marked as compressed
+    /// with an unknown codec.
+    pub fn compression(&self) -> Result<Option<Compression>> {
+        match self.data.strip_prefix(&COMPRESSED_BLOB_MAGIC) {
+            Some([codec, ..]) => Compression::from_u8(*codec).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Get the original data of the blob, decompressing it if it was created with
+    /// [`Blob::new_compressed`].
+    ///
+    /// The data of the uncompressed blobs is returned as is.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnsupportedCompression`] if the codec is unknown or its feature
+    /// is not enabled, [`Error::DecompressedBlobTooLarge`] if the data exceeds
+    /// [`MAX_DECOMPRESSED_BLOB_SIZE`], and [`Error::Compression`] if it is malformed.
+    pub fn decompressed_data(&self) -> Result<Cow<'_, [u8]>> {
+        match self.compression()? {
+            Some(compression) => compression
+                .decompress(&self.data[HEADER_LEN..])
+                .map(Cow::Owned),
+            None => Ok(Cow::Borrowed(&self.data)),
+        }
+    }
+
+    /// Estimate the number of the [`Share`]s that a [`Blob`] with the given data would
+    /// take after compressing it, without creating the blob.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnsupportedCompression`] if the feature of the codec is not enabled,
+    /// and propagates the errors of the compression.
+    ///
+    /// [`Share`]: crate::Share
+    pub fn compressed_shares_len(data: &[u8], compression: Compression) -> Result<usize> {
+        let compressed = compression.compress(data)?;
+        Ok(Blob::shares_len_for(compressed.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    fn namespace() -> Namespace {
+        Namespace::new_v0(&[1, 2, 3, 4, 5]).unwrap()
+    }
+
+    #[test]
+    fn uncompressed_data_as_is() {
+        let blob = Blob::new(namespace(), b"foo".to_vec(), AppVersion::V2).unwrap();
+
+        assert_eq!(blob.compression().unwrap(), None);
+        assert!(matches!(
+            blob.decompressed_data().unwrap(),
+            Cow::Borrowed(b"foo")
+        ));
+    }
+
+    #[test]
+    fn unknown_codec() {
+        let mut data = COMPRESSED_BLOB_MAGIC.to_vec();
+        data.extend_from_slice(&[0xff, 1, 2, 3]);
+        let blob = Blob::new(namespace(), data, AppVersion::V2).unwrap();
+
+        assert!(matches!(
+            blob.compression().unwrap_err(),
+            Error::UnsupportedCompression(0xff)
+        ));
+        assert!(matches!(
+            blob.decompressed_data().unwrap_err(),
+            Error::UnsupportedCompression(0xff)
+        ));
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd_round_trip() {
+        let data = vec![1; 5000];
+        let blob =
+            Blob::new_compressed(namespace(), &data, Compression::Zstd, AppVersion::V2).unwrap();
+
+        assert_eq!(blob.compression().unwrap(), Some(Compression::Zstd));
+        assert_eq!(blob.decompressed_data().unwrap(), &data[..]);
+        assert_eq!(
+            Blob::compressed_shares_len(&data, Compression::Zstd).unwrap(),
+            blob.shares_len()
+        );
+        assert!(blob.shares_len() < Blob::shares_len_for(data.len()));
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn gzip_round_trip() {
+        let data = vec![1; 5000];
+        let blob =
+            Blob::new_compressed(namespace(), &data, Compression::Gzip, AppVersion::V2).unwrap();
+
+        assert_eq!(blob.compression().unwrap(), Some(Compression::Gzip));
+        assert_eq!(blob.decompressed_data().unwrap(), &data[..]);
+        assert_eq!(
+            Blob::compressed_shares_len(&data, Compression::Gzip).unwrap(),
+            blob.shares_len()
+        );
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn malformed_payload() {
+        let mut data = COMPRESSED_BLOB_MAGIC.to_vec();
+        data.extend_from_slice(&[Compression::Gzip as u8, 1, 2, 3]);
+        let blob = Blob::new(namespace(), data, AppVersion::V2).unwrap();
+
+        assert!(matches!(
+            blob.decompressed_data().unwrap_err(),
+            Error::Compression(_)
+        ));
+    }
+
+    #[cfg(not(feature = "zstd"))]
+    #[test]
+    fn codec_feature_disabled() {
+        assert!(matches!(
+            Blob::new_compressed(namespace(), b"foo", Compression::Zstd, AppVersion::V2)
+                .unwrap_err(),
+            Error::UnsupportedCompression(1)
+        ));
+    }
+}
@@ -0,0 +1,276 @@
+use celestia_proto::celestia::blob::v1::MsgPayForBlobs as RawMsgPayForBlobs;
+use celestia_tendermint_proto::v0_34::types::IndexWrapper;
+use celestia_tendermint_proto::Protobuf;
+use prost::Message as _;
+
+use crate::blob::{Blob, Commitment};
+use crate::consts::appconsts::AppVersion;
+use crate::nmt::Namespace;
+use crate::square::INDEX_WRAPPER_TYPE_ID;
+use crate::state::{AccAddress, Tx};
+use crate::{bail_validation, parse_compact_shares, validation_error, Error, Result, Share};
+
+/// Type url of the [`MsgPayForBlobs`] used when wrapping it in the protobuf `Any`.
+pub const MSG_PAY_FOR_BLOBS_TYPE_URL: &str = "/celestia.blob.v1.MsgPayForBlobs";
+
+/// A message paying for the inclusion of [`Blob`]s in the block.
+///
+/// The [`Blob`]s themselves are not part of the message. Instead it commits to
+/// their sizes, [`Namespace`]s and [`Commitment`]s, while the data is sent alongside
+/// the transaction in a `BlobTx`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MsgPayForBlobs {
+    /// An address of the account paying for the blobs.
+    pub signer: AccAddress,
+    /// [`Namespace`]s of the [`Blob`]s.
+    pub namespaces: Vec<Namespace>,
+    /// Sizes of the data of the [`Blob`]s in bytes.
+    pub blob_sizes: Vec<u32>,
+    /// [`Commitment`]s of the [`Blob`]s.
+    pub share_commitments: Vec<Commitment>,
+    /// Share versions used to compute the [`Commitment`]s.
+    pub share_versions: Vec<u32>,
+}
+
+impl MsgPayForBlobs {
+    /// Create a [`MsgPayForBlobs`] paying for the given [`Blob`]s.
+    ///
+    /// The [`Commitment`]s are computed locally from the blobs' data, so the
+    /// message is valid even if a blob carries a stale commitment.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the list of blobs is empty, size of any
+    /// of them overflows [`u32`] or the [`Commitment`] cannot be computed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use celestia_types::consts::appconsts::AppVersion;
+    /// use celestia_types::blob::MsgPayForBlobs;
+    /// use celestia_types::state::AccAddress;
+    /// use celestia_types::{nmt::Namespace, Blob};
+    ///
+    /// let namespace = Namespace::new_v0(&[1, 2, 3, 4, 5]).unwrap();
+    /// let blob = Blob::new(namespace, b"foo".to_vec(), AppVersion::V2).unwrap();
+    /// let signer: AccAddress = "celestia1qypqxpq9qcrsszg2pvxq6rs0zqg3yyc5wgawu3"
+    ///     .parse()
+    ///     .unwrap();
+    ///
+    /// let msg = MsgPayForBlobs::new(&[blob.clone()], signer, AppVersion::V2).unwrap();
+    ///
+    /// assert_eq!(msg.blob_sizes, vec![3]);
+    /// assert_eq!(msg.share_commitments, vec![blob.commitment]);
+    /// ```
+    pub fn new(blobs: &[Blob], signer: AccAddress, app_version: AppVersion) -> Result<Self> {
+        if blobs.is_empty() {
+            bail_validation!("no blobs to pay for")
+        }
+
+        let mut msg = MsgPayForBlobs {
+            signer,
+            namespaces: Vec::with_capacity(blobs.len()),
+            blob_sizes: Vec::with_capacity(blobs.len()),
+            share_commitments: Vec::with_capacity(blobs.len()),
+            share_versions: Vec::with_capacity(blobs.len()),
+        };
+
+        for blob in blobs {
+            let blob_size = u32::try_from(blob.data.len())
+                .map_err(|_| Error::ShareSequenceLenExceeded(blob.data.len()))?;
+            let commitment =
+                Commitment::from_blob(blob.namespace, blob.share_version, &blob.data, app_version)?;
+
+            msg.namespaces.push(blob.namespace);
+            msg.blob_sizes.push(blob_size);
+            msg.share_commitments.push(commitment);
+            msg.share_versions.push(blob.share_version.into());
+        }
+
+        Ok(msg)
+    }
+}
+
+/// Decode the [`MsgPayForBlobs`] of the transactions stored in the [`Namespace::PAY_FOR_BLOB`].
+///
+/// Shares of the other namespaces are skipped, so it can be given either the
+/// whole original data square or only the shares of the pay-for-blobs namespace.
+/// The messages are returned in the order of the transactions, so that their
+/// [`Commitment`]s can be matched with the [`Blob`]s of the block.
+///
+/// # Errors
+///
+/// This function will return an error if the shares don't form a valid sequence or
+/// any of the transactions, or the messages in them, cannot be decoded.
+pub fn decode_pay_for_blobs<'a, I>(shares: I) -> Result<Vec<MsgPayForBlobs>>
+where
+    I: IntoIterator<Item = &'a Share>,
+{
+    let mut msgs = Vec::new();
+
+    for unit in parse_compact_shares(Namespace::PAY_FOR_BLOB, shares)? {
+        let wrapper = IndexWrapper::decode(&unit[..])?;
+        if wrapper.type_id != INDEX_WRAPPER_TYPE_ID {
+            bail_validation!("unexpected type id of index wrapper: {}", wrapper.type_id)
+        }
+
+        let tx = Tx::decode(&wrapper.tx[..])?;
+
+        for msg in tx.body.into_iter().flat_map(|body| body.messages) {
+            if msg.type_url == MSG_PAY_FOR_BLOBS_TYPE_URL {
+                let raw = RawMsgPayForBlobs::decode(&msg.value[..])?;
+                msgs.push(raw.try_into()?);
+            }
+        }
+    }
+
+    Ok(msgs)
+}
+
+impl Protobuf<RawMsgPayForBlobs> for MsgPayForBlobs {}
+
+impl TryFrom<RawMsgPayForBlobs> for MsgPayForBlobs {
+    type Error = Error;
+
+    fn try_from(value: RawMsgPayForBlobs) -> Result<Self, Self::Error> {
+        let len = value.namespaces.len();
+
+        if value.blob_sizes.len() != len
+            || value.share_commitments.len() != len
+            || value.share_versions.len() != len
+        {
+            bail_validation!("lengths of the blobs' fields don't match")
+        }
+
+        let namespaces = value
+            .namespaces
+            .iter()
+            .map(|ns| Namespace::from_raw(ns))
+            .collect::<Result<_>>()?;
+
+        let share_commitments = value
+            .share_commitments
+            .iter()
+            .map(|commitment| {
+                let hash = commitment
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| validation_error!("commitment is not a size of a sha256"))?;
+                Ok(Commitment(hash))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(MsgPayForBlobs {
+            signer: value.signer.parse()?,
+            namespaces,
+            blob_sizes: value.blob_sizes,
+            share_commitments,
+            share_versions: value.share_versions,
+        })
+    }
+}
+
+impl From<MsgPayForBlobs> for RawMsgPayForBlobs {
+    fn from(value: MsgPayForBlobs) -> Self {
+        RawMsgPayForBlobs {
+            signer: value.signer.to_string(),
+            namespaces: value
+                .namespaces
+                .iter()
+                .map(|ns| ns.as_bytes().to_vec())
+                .collect(),
+            blob_sizes: value.blob_sizes,
+            share_commitments: value
+                .share_commitments
+                .iter()
+                .map(|commitment| commitment.0.to_vec())
+                .collect(),
+            share_versions: value.share_versions,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::square::build_ods;
+    use celestia_proto::cosmos::tx::v1beta1::TxBody;
+    use celestia_tendermint_proto::v0_34::types::BlobTx;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    fn signer() -> AccAddress {
+        "celestia1qypqxpq9qcrsszg2pvxq6rs0zqg3yyc5wgawu3"
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn new_computes_commitments() {
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+        let blob1 = Blob::new(namespace, vec![1; 1000], AppVersion::V2).unwrap();
+        let mut blob2 = Blob::new(namespace, vec![2; 10], AppVersion::V2).unwrap();
+        let commitment2 = blob2.commitment;
+        // stale commitment is recomputed
+        blob2.commitment = blob1.commitment;
+
+        let msg = MsgPayForBlobs::new(&[blob1.clone(), blob2], signer(), AppVersion::V2).unwrap();
+
+        assert_eq!(msg.namespaces, vec![namespace, namespace]);
+        assert_eq!(msg.blob_sizes, vec![1000, 10]);
+        assert_eq!(msg.share_commitments, vec![blob1.commitment, commitment2]);
+        assert_eq!(msg.share_versions, vec![0, 0]);
+    }
+
+    #[test]
+    fn new_without_blobs() {
+        MsgPayForBlobs::new(&[], signer(), AppVersion::V2).unwrap_err();
+    }
+
+    #[test]
+    fn protobuf_roundtrip() {
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+        let blob = Blob::new(namespace, b"foo".to_vec(), AppVersion::V2).unwrap();
+        let msg = MsgPayForBlobs::new(&[blob], signer(), AppVersion::V2).unwrap();
+
+        let bytes = msg.clone().encode_vec().unwrap();
+        let decoded = MsgPayForBlobs::decode(&bytes[..]).unwrap();
+
+        assert_eq!(msg, decoded);
+    }
+
+    #[test]
+    fn decode_from_square() {
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+        let blob = Blob::new(namespace, vec![1; 1000], AppVersion::V2).unwrap();
+        let msg = MsgPayForBlobs::new(&[blob.clone()], signer(), AppVersion::V2).unwrap();
+
+        let tx = Tx {
+            body: Some(TxBody {
+                messages: vec![prost_types::Any {
+                    type_url: MSG_PAY_FOR_BLOBS_TYPE_URL.to_owned(),
+                    value: RawMsgPayForBlobs::from(msg.clone()).encode_to_vec(),
+                }],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let blob_tx = BlobTx {
+            tx: tx.encode_to_vec(),
+            blobs: vec![blob.into()],
+            type_id: "BLOB".to_owned(),
+        };
+
+        let ordinary_tx = Tx {
+            signatures: vec![vec![7; 600]],
+            ..Default::default()
+        };
+
+        let txs = [blob_tx.encode_to_vec(), ordinary_tx.encode_to_vec()];
+        let ods = build_ods(&[], &txs, 4, AppVersion::V2).unwrap();
+
+        assert_eq!(decode_pay_for_blobs(&ods).unwrap(), vec![msg]);
+        assert_eq!(crate::state::decode_txs(&ods).unwrap(), vec![ordinary_tx]);
+    }
+}
@@ -0,0 +1,303 @@
+use celestia_tendermint::Hash;
+use nmt_rs::simple_merkle::tree::MerkleHash;
+use nmt_rs::NamespaceMerkleHasher;
+use serde::{Deserialize, Serialize};
+
+use crate::blob::commitment::{subtree_roots, subtree_sizes};
+use crate::consts::appconsts::AppVersion;
+use crate::nmt::{NamespacedHash, NamespacedHashExt, NamespacedSha2Hasher};
+use crate::{bail_verification, Blob, Commitment, Error, Result, RowProof};
+
+/// Proof of inclusion of a [`Blob`] in the data root of a block, through the subtree
+/// roots its [`Commitment`] is computed from.
+///
+/// The proof consists of the roots of the subtrees created from the blob shares, a
+/// [`SubtreeRootProof`] of each of them in the root of its row, and the [`RowProof`] of
+/// the rows spanned by the blob in the data root. Unlike the per row [`NamespaceProof`]s,
+/// it can be verified against the [`Commitment`] alone, without the blob data.
+///
+/// [`NamespaceProof`]: crate::nmt::NamespaceProof
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RawBlobSpanProof", into = "RawBlobSpanProof")]
+pub struct BlobSpanProof {
+    /// Roots of the subtrees created from the blob shares, in order.
+    pub subtree_roots: Vec<NamespacedHash>,
+    /// Proofs of each of the subtree roots in the root of its row.
+    pub subtree_root_proofs: Vec<SubtreeRootProof>,
+    /// Proof of the rows spanned by the blob in the data root.
+    pub row_proof: RowProof,
+}
+
+impl BlobSpanProof {
+    /// Verify that the [`Blob`] is included in the data root of the block.
+    ///
+    /// The subtree roots are recomputed from the blob shares, with the subtree root
+    /// threshold of the block's [`AppVersion`], and then verified as in
+    /// [`BlobSpanProof::verify_commitment`] with the blob's [`Commitment`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the blob shares don't match the subtree
+    /// roots or the proof is invalid.
+    pub fn verify(&self, blob: &Blob, data_root: &Hash, app_version: AppVersion) -> Result<()> {
+        let shares = blob.to_shares()?;
+
+        if subtree_roots(blob.namespace, &shares, app_version)? != self.subtree_roots {
+            bail_verification!("blob shares don't match the subtree roots");
+        }
+
+        let sizes = subtree_sizes(shares.len(), app_version);
+        if self
+            .subtree_root_proofs
+            .iter()
+            .zip(&sizes)
+            .any(|(proof, &size)| proof.width as usize != size)
+        {
+            bail_verification!("subtree proofs don't match the blob size");
+        }
+
+        self.verify_commitment(&blob.commitment, data_root)
+    }
+
+    /// Verify that the blob with the given [`Commitment`] is included in the data root
+    /// of the block.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the subtree roots don't match the
+    /// commitment, aren't contiguous or aren't included in the data root.
+    pub fn verify_commitment(&self, commitment: &Commitment, data_root: &Hash) -> Result<()> {
+        if self.subtree_roots.is_empty() {
+            bail_verification!("no subtree roots");
+        }
+
+        if Commitment::from_subtree_roots(&self.subtree_roots) != *commitment {
+            bail_verification!("subtree roots don't match the commitment");
+        }
+
+        if self.subtree_root_proofs.len() != self.subtree_roots.len() {
+            bail_verification!(
+                "subtree root proofs len ({}) != subtree roots len ({})",
+                self.subtree_root_proofs.len(),
+                self.subtree_roots.len()
+            );
+        }
+
+        self.row_proof.verify(data_root)?;
+
+        let mut prev: Option<&SubtreeRootProof> = None;
+
+        for (root, proof) in self.subtree_roots.iter().zip(&self.subtree_root_proofs) {
+            if let Some(prev) = prev {
+                if !proof.follows(prev) {
+                    bail_verification!("subtree in row {} is not contiguous", proof.row);
+                }
+            }
+
+            let Some(row_root) = proof
+                .row
+                .checked_sub(self.row_proof.start_row)
+                .and_then(|index| self.row_proof.row_roots.get(index as usize))
+            else {
+                bail_verification!("row {} is not covered by the row proof", proof.row);
+            };
+
+            proof.verify(root, row_root)?;
+            prev = Some(proof);
+        }
+
+        let first_row = self.subtree_root_proofs[0].row;
+        let last_row = prev.map_or(first_row, |proof| proof.row);
+
+        if first_row != self.row_proof.start_row || last_row != self.row_proof.end_row {
+            bail_verification!("row proof covers rows not spanned by the blob");
+        }
+
+        Ok(())
+    }
+}
+
+/// Proof of inclusion of a subtree root in the root of a row.
+///
+/// The subtree is identified by the range of shares it covers in the row, which must
+/// be aligned to its width.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RawSubtreeRootProof", into = "RawSubtreeRootProof")]
+pub struct SubtreeRootProof {
+    /// Index of the row of the subtree.
+    pub row: u32,
+    /// Index of the first share covered by the subtree in the row.
+    pub start: u32,
+    /// Number of the shares covered by the subtree, a power of two.
+    pub width: u32,
+    /// Roots of the sibling subtrees on the path to the row root, from the lowest one.
+    pub siblings: Vec<NamespacedHash>,
+}
+
+impl SubtreeRootProof {
+    /// Verify that the subtree root is included in the row root.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the proof is malformed or the root
+    /// computed from it doesn't match the row root.
+    pub fn verify(&self, subtree_root: &NamespacedHash, row_root: &NamespacedHash) -> Result<()> {
+        if !self.width.is_power_of_two() || self.start % self.width != 0 {
+            bail_verification!(
+                "subtree of width {} at {} is not aligned",
+                self.width,
+                self.start
+            );
+        }
+
+        let Some(row_width) = self.row_width() else {
+            bail_verification!("subtree proof longer than the row");
+        };
+
+        if self.start >= row_width {
+            bail_verification!("subtree at {} is outside of the row", self.start);
+        }
+
+        let hasher = NamespacedSha2Hasher::with_ignore_max_ns(true);
+        let mut node = subtree_root.clone();
+        let mut index = self.start / self.width;
+
+        for sibling in &self.siblings {
+            let (left, right) = if index % 2 == 0 {
+                (&node, sibling)
+            } else {
+                (sibling, &node)
+            };
+
+            if left.max_namespace() > right.min_namespace() {
+                bail_verification!("subtree proof nodes are not ordered by namespace");
+            }
+
+            node = hasher.hash_nodes(left, right);
+            index /= 2;
+        }
+
+        if node != *row_root {
+            bail_verification!("subtree root is not included in row {}", self.row);
+        }
+
+        Ok(())
+    }
+
+    /// Width of the whole row tree, as implied by the number of siblings.
+    fn row_width(&self) -> Option<u32> {
+        let levels = u32::try_from(self.siblings.len()).ok()?;
+        self.width.checked_mul(2u32.checked_pow(levels)?)
+    }
+
+    /// Returns `true` if the subtree directly follows the other one in the square.
+    ///
+    /// Subtrees cover only the shares of the original data square, so a subtree
+    /// ending at the half of the row is followed by the one at the start of the next row.
+    fn follows(&self, other: &SubtreeRootProof) -> bool {
+        let other_end = other.start.saturating_add(other.width);
+
+        if self.row == other.row {
+            self.start == other_end
+        } else {
+            self.row == other.row + 1
+                && self.start == 0
+                && other
+                    .row_width()
+                    .is_some_and(|width| other_end == width / 2)
+        }
+    }
+}
+
+/// Compute the root of the complete subtree over the leaves with the given hashes.
+///
+/// The number of the leaf hashes must be a power of two.
+pub(crate) fn compute_subtree_root(
+    hasher: &NamespacedSha2Hasher,
+    leaf_hashes: &[NamespacedHash],
+) -> NamespacedHash {
+    if leaf_hashes.len() == 1 {
+        return leaf_hashes[0].clone();
+    }
+
+    let (left, right) = leaf_hashes.split_at(leaf_hashes.len() / 2);
+    hasher.hash_nodes(
+        &compute_subtree_root(hasher, left),
+        &compute_subtree_root(hasher, right),
+    )
+}
+
+#[derive(Serialize, Deserialize)]
+struct RawBlobSpanProof {
+    #[serde(with = "celestia_tendermint_proto::serializers::bytes::vec_base64string")]
+    subtree_roots: Vec<Vec<u8>>,
+    subtree_root_proofs: Vec<SubtreeRootProof>,
+    row_proof: RowProof,
+}
+
+impl TryFrom<RawBlobSpanProof> for BlobSpanProof {
+    type Error = Error;
+
+    fn try_from(value: RawBlobSpanProof) -> Result<Self, Self::Error> {
+        Ok(BlobSpanProof {
+            subtree_roots: value
+                .subtree_roots
+                .iter()
+                .map(|bytes| NamespacedHash::from_raw(bytes))
+                .collect::<Result<_>>()?,
+            subtree_root_proofs: value.subtree_root_proofs,
+            row_proof: value.row_proof,
+        })
+    }
+}
+
+impl From<BlobSpanProof> for RawBlobSpanProof {
+    fn from(value: BlobSpanProof) -> Self {
+        RawBlobSpanProof {
+            subtree_roots: value
+                .subtree_roots
+                .iter()
+                .map(|root| root.to_vec())
+                .collect(),
+            subtree_root_proofs: value.subtree_root_proofs,
+            row_proof: value.row_proof,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct RawSubtreeRootProof {
+    row: u32,
+    start: u32,
+    width: u32,
+    #[serde(with = "celestia_tendermint_proto::serializers::bytes::vec_base64string")]
+    siblings: Vec<Vec<u8>>,
+}
+
+impl TryFrom<RawSubtreeRootProof> for SubtreeRootProof {
+    type Error = Error;
+
+    fn try_from(value: RawSubtreeRootProof) -> Result<Self, Self::Error> {
+        Ok(SubtreeRootProof {
+            row: value.row,
+            start: value.start,
+            width: value.width,
+            siblings: value
+                .siblings
+                .iter()
+                .map(|bytes| NamespacedHash::from_raw(bytes))
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+impl From<SubtreeRootProof> for RawSubtreeRootProof {
+    fn from(value: SubtreeRootProof) -> Self {
+        RawSubtreeRootProof {
+            row: value.row,
+            start: value.start,
+            width: value.width,
+            siblings: value.siblings.iter().map(|hash| hash.to_vec()).collect(),
+        }
+    }
+}
@@ -0,0 +1,245 @@
+//! Self-contained bundles of the data inclusion proofs, verifiable offline.
+//!
+//! A [`VerifiedBundle`] gathers everything needed to show that the [`NamespacedData`]
+//! was included in a block: the [`ExtendedHeader`] with its commit, the
+//! [`DataAvailabilityHeader`] and the shares with their proofs. It can be archived in
+//! its protobuf form and re-verified later against a trusted validator set, without
+//! access to the network.
+
+use celestia_proto::celestia::da::DataAvailabilityHeader as RawDataAvailabilityHeader;
+use celestia_proto::header::pb::ExtendedHeader as RawExtendedHeader;
+use celestia_proto::share::p2p::shwap::Data as RawNamespacedData;
+use celestia_tendermint::validator;
+use celestia_tendermint_proto::Protobuf;
+use prost::Message;
+
+use crate::namespaced_data::NamespacedData;
+use crate::row::IdLayout;
+use crate::trust_level::DEFAULT_TRUST_LEVEL;
+use crate::{
+    bail_verification, DataAvailabilityHeader, Error, ExtendedHeader, Result, ValidatorSetExt,
+};
+
+/// Proof of the inclusion of the [`NamespacedData`] in a block, down from the commit
+/// signatures of the block.
+///
+/// # Example
+///
+/// ```no_run
+/// use celestia_types::bundle::VerifiedBundle;
+/// use celestia_types::nmt::Namespace;
+/// use celestia_tendermint_proto::Protobuf;
+/// # use celestia_tendermint::validator;
+/// # use celestia_types::{ExtendedDataSquare, ExtendedHeader};
+/// # fn get_extended_data_square(height: u64) -> ExtendedDataSquare {
+/// #    unimplemented!()
+/// # }
+/// # fn get_extended_header(height: u64) -> ExtendedHeader {
+/// #    unimplemented!()
+/// # }
+/// # fn get_trusted_validators() -> validator::Set {
+/// #    unimplemented!()
+/// # }
+///
+/// let block_height = 100;
+/// let eds = get_extended_data_square(block_height);
+/// let header = get_extended_header(block_height);
+///
+/// let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+/// let namespaced_data = eds
+///     .get_namespaced_data(namespace, &header.dah, block_height)
+///     .unwrap();
+/// let bytes = VerifiedBundle::new(header, namespaced_data)
+///     .encode_vec()
+///     .unwrap();
+///
+/// // years later, without the network
+/// let bundle = VerifiedBundle::decode_vec(&bytes).unwrap();
+/// bundle.verify(&get_trusted_validators()).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct VerifiedBundle {
+    /// Header of the block the data was included in.
+    pub header: ExtendedHeader,
+    /// [`DataAvailabilityHeader`] of the block, holding the roots of the rows.
+    pub dah: DataAvailabilityHeader,
+    /// Shares of the namespace with the proofs of their inclusion in the rows.
+    pub namespaced_data: Vec<NamespacedData>,
+}
+
+/// Protobuf representation of the [`VerifiedBundle`].
+///
+/// Ids of the [`NamespacedData`] are encoded with the [`IdLayout::V1`].
+#[derive(Clone, PartialEq, Message)]
+pub struct RawVerifiedBundle {
+    /// Header of the block.
+    #[prost(message, optional, tag = "1")]
+    pub header: Option<RawExtendedHeader>,
+    /// Data availability header of the block.
+    #[prost(message, optional, tag = "2")]
+    pub dah: Option<RawDataAvailabilityHeader>,
+    /// Namespaced data with the proofs.
+    #[prost(message, repeated, tag = "3")]
+    pub namespaced_data: Vec<RawNamespacedData>,
+}
+
+impl VerifiedBundle {
+    /// Create a new [`VerifiedBundle`] of the [`NamespacedData`] included in the block
+    /// of the given [`ExtendedHeader`].
+    pub fn new(header: ExtendedHeader, namespaced_data: Vec<NamespacedData>) -> Self {
+        VerifiedBundle {
+            dah: header.dah.clone(),
+            header,
+            namespaced_data,
+        }
+    }
+
+    /// Verify the whole chain of the proofs, from the commit to the shares.
+    ///
+    /// The header is validated on its own, including its commit, and accepted only if the
+    /// `trusted_validators` holding more than 1/3 of their voting power signed it, as per
+    /// [`DEFAULT_TRUST_LEVEL`]. Then the [`DataAvailabilityHeader`] is checked against the
+    /// data hash of the header and each of the [`NamespacedData`] against the row roots.
+    ///
+    /// # Errors
+    ///
+    /// If verification fails, this function will return an error with a reason of failure.
+    pub fn verify(&self, trusted_validators: &validator::Set) -> Result<()> {
+        self.header.validate()?;
+
+        trusted_validators.verify_commit_light_trusting(
+            self.header.chain_id(),
+            &self.header.commit,
+            DEFAULT_TRUST_LEVEL,
+        )?;
+
+        self.dah
+            .validate_with_data_hash(self.header.header.data_hash)?;
+
+        let height = self.header.height().value();
+
+        for namespaced_data in &self.namespaced_data {
+            let data_height = namespaced_data.namespaced_data_id.row.block_height;
+
+            if data_height != height {
+                bail_verification!(
+                    "namespaced data height ({}) != header height ({})",
+                    data_height,
+                    height
+                );
+            }
+
+            namespaced_data.validate(&self.dah)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Protobuf<RawVerifiedBundle> for VerifiedBundle {}
+
+impl TryFrom<RawVerifiedBundle> for VerifiedBundle {
+    type Error = Error;
+
+    fn try_from(value: RawVerifiedBundle) -> Result<Self, Self::Error> {
+        let header = value.header.ok_or(Error::MissingHeader)?.try_into()?;
+        let dah = value
+            .dah
+            .ok_or(Error::MissingDataAvailabilityHeader)?
+            .try_into()?;
+        let namespaced_data = value
+            .namespaced_data
+            .into_iter()
+            .map(|raw| NamespacedData::from_raw(raw, IdLayout::V1))
+            .collect::<Result<_>>()?;
+
+        Ok(VerifiedBundle {
+            header,
+            dah,
+            namespaced_data,
+        })
+    }
+}
+
+impl From<VerifiedBundle> for RawVerifiedBundle {
+    fn from(value: VerifiedBundle) -> RawVerifiedBundle {
+        RawVerifiedBundle {
+            header: Some(value.header.into()),
+            dah: Some(value.dah.into()),
+            namespaced_data: value
+                .namespaced_data
+                .into_iter()
+                .map(|data| data.into_raw(IdLayout::V1))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nmt::Namespace;
+    use crate::test_utils::ExtendedHeaderGenerator;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    fn sample_bundle() -> VerifiedBundle {
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+        let other_namespace = Namespace::new_v0(&[4, 5, 6]).unwrap();
+        let mut gen = ExtendedHeaderGenerator::new();
+        let (header, eds) = gen.next_with_eds(4, &[namespace, other_namespace]);
+
+        let namespaced_data = eds
+            .get_namespaced_data(namespace, &header.dah, header.height().value())
+            .unwrap();
+
+        VerifiedBundle::new(header, namespaced_data)
+    }
+
+    #[test]
+    fn round_trip_and_verify() {
+        let bundle = sample_bundle();
+        let trusted_validators = bundle.header.validator_set.clone();
+        assert!(!bundle.namespaced_data.is_empty());
+
+        let bytes = bundle.clone().encode_vec().unwrap();
+        let decoded = VerifiedBundle::decode_vec(&bytes).unwrap();
+        assert_eq!(decoded.header, bundle.header);
+        assert_eq!(decoded.dah, bundle.dah);
+        assert_eq!(decoded.clone().encode_vec().unwrap(), bytes);
+
+        decoded.verify(&trusted_validators).unwrap();
+    }
+
+    #[test]
+    fn untrusted_validators() {
+        let bundle = sample_bundle();
+        let other_validators = ExtendedHeaderGenerator::new().next().validator_set;
+
+        bundle.verify(&other_validators).unwrap_err();
+    }
+
+    #[test]
+    fn tampered_bundle() {
+        let bundle = sample_bundle();
+        let trusted_validators = bundle.header.validator_set.clone();
+
+        let mut tampered = bundle.clone();
+        tampered.dah = ExtendedHeaderGenerator::new().next().dah;
+        tampered.verify(&trusted_validators).unwrap_err();
+
+        let mut tampered = bundle.clone();
+        let mut share = tampered.namespaced_data[0].shares[0].to_vec();
+        share[100] ^= 1;
+        tampered.namespaced_data[0].shares[0] = share.into();
+        tampered.verify(&trusted_validators).unwrap_err();
+
+        let mut tampered = bundle;
+        tampered.namespaced_data[0]
+            .namespaced_data_id
+            .row
+            .block_height += 1;
+        tampered.verify(&trusted_validators).unwrap_err();
+    }
+}
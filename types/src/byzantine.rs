@@ -12,7 +12,7 @@ use crate::nmt::{
     Namespace, NamespaceProof, NamespacedHash, NamespacedHashExt, NMT_CODEC, NMT_ID_SIZE,
     NMT_MULTIHASH_CODE, NS_SIZE,
 };
-use crate::rsmt2d::AxisType;
+use crate::rsmt2d::{compute_axis_root, AxisType};
 use crate::{Error, ExtendedHeader, Result, Share};
 
 type Cid = CidGeneric<NMT_ID_SIZE>;
@@ -120,8 +120,30 @@ impl FraudProof for BadEncodingFraudProof {
                 .map_err(Error::RangeProofError)?;
         }
 
-        // TODO: Add leopard reed solomon decoding and encoding of shares
-        //       and verify the nmt roots.
+        // rebuild the parity half of the axis from the original half
+        let width = self.shares.len();
+        let mut shares: Vec<_> = self
+            .shares
+            .iter()
+            .map(|share| share.leaf.share.as_ref().to_vec())
+            .collect();
+
+        if leopard_codec::encode(&mut shares, width / 2).is_err() {
+            // shares can't be erasure coded, so the block is indeed invalid
+            return Ok(());
+        }
+
+        let Ok(rebuilt_root) = compute_axis_root(&shares, self.index) else {
+            return Ok(());
+        };
+
+        if rebuilt_root == root {
+            bail_validation!(
+                "recomputed {:?} root at index {} matches the dah root",
+                self.axis,
+                self.index
+            );
+        }
 
         Ok(())
     }
@@ -240,7 +262,6 @@ mod tests {
         (proof, serde_json::from_str(eh_json).unwrap())
     }
 
-    #[cfg(not(target_arch = "wasm32"))] // related to ignored validate_fake_befp test, see below
     fn fake_befp() -> (BadEncodingFraudProof, ExtendedHeader) {
         let befp_json = include_str!("../test_data/fraud/fake_bad_encoding_fraud_proof.json");
         let eh_json = include_str!("../test_data/fraud/fake_bad_encoding_extended_header.json");
@@ -287,9 +308,7 @@ mod tests {
         proof.validate(&eh).unwrap_err();
     }
 
-    #[cfg(not(target_arch = "wasm32"))] // wasm_bindgen_test doesn't seem to support #[ignore]
     #[test]
-    #[ignore = "TODO: we can't catch fake proofs without rebuilding the row using reedsolomon"]
     fn validate_fake_befp() {
         let (proof, eh) = fake_befp();
         proof.validate(&eh).unwrap_err();
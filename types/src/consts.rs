@@ -24,14 +24,152 @@ pub mod version {
 /// [`celestia-app`]: https://github.com/celestiaorg/celestia-app
 pub mod appconsts {
     pub use global_consts::*;
-    pub use v1::*;
+    pub use initial_consts::*;
+
+    /// Version of the celestia-app state machine, determining the consensus rules of
+    /// the block data, like the square size limits and the accepted share versions.
+    ///
+    /// It is found in the [`Header::version`] of each block.
+    ///
+    /// [`Header::version`]: celestia_tendermint::block::Header::version
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    #[repr(u64)]
+    pub enum AppVersion {
+        /// The first version of the celestia-app.
+        V1 = 1,
+        /// The version introduced with the Lemongrass upgrade.
+        V2 = 2,
+        /// The version introduced with the Ginger upgrade.
+        V3 = 3,
+    }
+
+    impl AppVersion {
+        /// The latest app version known to this crate.
+        pub const fn latest() -> AppVersion {
+            AppVersion::V3
+        }
+
+        /// Get the app version from its number, if it is known to this crate.
+        pub const fn from_u64(version: u64) -> Option<AppVersion> {
+            match version {
+                1 => Some(AppVersion::V1),
+                2 => Some(AppVersion::V2),
+                3 => Some(AppVersion::V3),
+                _ => None,
+            }
+        }
+
+        /// Number of the app version.
+        pub const fn as_u64(self) -> u64 {
+            self as u64
+        }
+    }
+
+    impl TryFrom<u64> for AppVersion {
+        type Error = crate::Error;
+
+        fn try_from(version: u64) -> Result<Self, Self::Error> {
+            AppVersion::from_u64(version).ok_or(crate::Error::UnsupportedAppVersion(version))
+        }
+    }
+
+    impl From<AppVersion> for u64 {
+        fn from(version: AppVersion) -> u64 {
+            version.as_u64()
+        }
+    }
+
+    /// Maximum width of a single subtree root when generating blob's commitment.
+    pub const fn subtree_root_threshold(app_version: AppVersion) -> u64 {
+        match app_version {
+            AppVersion::V1 => v1::SUBTREE_ROOT_THRESHOLD,
+            AppVersion::V2 => v2::SUBTREE_ROOT_THRESHOLD,
+            AppVersion::V3 => v3::SUBTREE_ROOT_THRESHOLD,
+        }
+    }
+
+    /// Maximum width of the original data square.
+    pub const fn square_size_upper_bound(app_version: AppVersion) -> usize {
+        match app_version {
+            AppVersion::V1 => v1::SQUARE_SIZE_UPPER_BOUND,
+            AppVersion::V2 => v2::SQUARE_SIZE_UPPER_BOUND,
+            AppVersion::V3 => v3::SQUARE_SIZE_UPPER_BOUND,
+        }
+    }
+
+    /// The share versions accepted for the blobs.
+    pub const fn supported_share_versions(app_version: AppVersion) -> &'static [u8] {
+        match app_version {
+            AppVersion::V1 => v1::SUPPORTED_SHARE_VERSIONS,
+            AppVersion::V2 => v2::SUPPORTED_SHARE_VERSIONS,
+            AppVersion::V3 => v3::SUPPORTED_SHARE_VERSIONS,
+        }
+    }
+
+    /// The largest width of the original data square among all the app versions.
+    pub const MAX_SQUARE_SIZE_UPPER_BOUND: usize = max(
+        max(v1::SQUARE_SIZE_UPPER_BOUND, v2::SQUARE_SIZE_UPPER_BOUND),
+        v3::SQUARE_SIZE_UPPER_BOUND,
+    );
+
+    const fn max(a: usize, b: usize) -> usize {
+        if a > b {
+            a
+        } else {
+            b
+        }
+    }
 
     // celestia-app/pkg/appconsts/v1/app_consts
-    mod v1 {
+    /// Constants of the [`AppVersion::V1`].
+    pub mod v1 {
+        use super::SHARE_VERSION_ZERO;
+
+        /// Maximum width of a single subtree root when generating blob's commitment.
+        pub const SUBTREE_ROOT_THRESHOLD: u64 = 64;
+        /// Maximum width of the original data square.
+        pub const SQUARE_SIZE_UPPER_BOUND: usize = 128;
+        /// The share versions accepted for the blobs.
+        pub const SUPPORTED_SHARE_VERSIONS: &[u8] = &[SHARE_VERSION_ZERO];
+    }
+
+    // celestia-app/pkg/appconsts/v2/app_consts
+    /// Constants of the [`AppVersion::V2`].
+    pub mod v2 {
+        use super::SHARE_VERSION_ZERO;
+
         /// Maximum width of a single subtree root when generating blob's commitment.
         pub const SUBTREE_ROOT_THRESHOLD: u64 = 64;
         /// Maximum width of the original data square.
         pub const SQUARE_SIZE_UPPER_BOUND: usize = 128;
+        /// The share versions accepted for the blobs.
+        pub const SUPPORTED_SHARE_VERSIONS: &[u8] = &[SHARE_VERSION_ZERO];
+    }
+
+    // celestia-app/pkg/appconsts/v3/app_consts
+    /// Constants of the [`AppVersion::V3`].
+    pub mod v3 {
+        use super::SHARE_VERSION_ZERO;
+
+        /// Maximum width of a single subtree root when generating blob's commitment.
+        pub const SUBTREE_ROOT_THRESHOLD: u64 = 64;
+        /// Maximum width of the original data square.
+        pub const SQUARE_SIZE_UPPER_BOUND: usize = 512;
+        /// The share versions accepted for the blobs.
+        ///
+        /// The share version `1`, of the blobs with a signer, is not supported by this
+        /// crate yet, so its blobs are rejected.
+        pub const SUPPORTED_SHARE_VERSIONS: &[u8] = &[SHARE_VERSION_ZERO];
+    }
+
+    // celestia-app/pkg/appconsts/initial_consts
+    mod initial_consts {
+        /// The default amount of gas consumed per byte of the blob's shares.
+        pub const DEFAULT_GAS_PER_BLOB_BYTE: u64 = 8;
+        /// The default amount of gas consumed per byte of the transaction.
+        pub const DEFAULT_TX_SIZE_COST_PER_BYTE: u64 = 10;
+        /// The default maximum width of the original data square set by the governance.
+        pub const DEFAULT_GOV_MAX_SQUARE_SIZE: usize = 64;
     }
 
     // celestia-app/pkg/appconsts/global_consts
@@ -97,7 +235,7 @@ pub mod data_availability_header {
     /// A maximum width of the [`ExtendedDataSquare`].
     ///
     /// [`ExtendedDataSquare`]: crate::rsmt2d::ExtendedDataSquare
-    pub const MAX_EXTENDED_SQUARE_WIDTH: usize = super::appconsts::SQUARE_SIZE_UPPER_BOUND * 2;
+    pub const MAX_EXTENDED_SQUARE_WIDTH: usize = super::appconsts::MAX_SQUARE_SIZE_UPPER_BOUND * 2;
     /// A minimum width of the [`ExtendedDataSquare`].
     ///
     /// [`ExtendedDataSquare`]: crate::rsmt2d::ExtendedDataSquare
@@ -1,3 +1,6 @@
+use alloc::vec::Vec;
+use core::ops::RangeInclusive;
+
 use celestia_proto::celestia::da::DataAvailabilityHeader as RawDataAvailabilityHeader;
 use celestia_tendermint::merkle::simple_hash_from_byte_vectors;
 use celestia_tendermint_proto::Protobuf;
@@ -9,8 +12,11 @@ use crate::consts::data_availability_header::{
 };
 use crate::hash::Hash;
 use crate::nmt::{NamespacedHash, NamespacedHashExt};
+use crate::range_proof::merkle_proof;
 use crate::rsmt2d::AxisType;
-use crate::{bail_validation, Error, Result, ValidateBasic, ValidationError};
+#[cfg(feature = "std")]
+use crate::rsmt2d::ExtendedDataSquare;
+use crate::{bail_validation, Error, Result, RowProof, ValidateBasic, ValidationError};
 
 /// Header with commitments of the data availability.
 ///
@@ -109,6 +115,162 @@ impl DataAvailabilityHeader {
         Hash::Sha256(simple_hash_from_byte_vectors::<Sha256>(&all_roots))
     }
 
+    /// Validate the header and check that it is the one committed to by the `data_hash`
+    /// of a block.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header fails the [`ValidateBasic::validate_basic`] checks
+    /// or its [`hash`] doesn't match the `data_hash`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use celestia_types::ExtendedHeader;
+    /// # let s = include_str!("../test_data/chain1/extended_header_block_1.json");
+    /// # let eh: ExtendedHeader = serde_json::from_str(s).unwrap();
+    /// let dah = eh.dah;
+    ///
+    /// dah.validate_with_data_hash(eh.header.data_hash).unwrap();
+    /// ```
+    ///
+    /// [`hash`]: DataAvailabilityHeader::hash
+    pub fn validate_with_data_hash(&self, data_hash: Hash) -> Result<(), ValidationError> {
+        self.validate_basic()?;
+
+        if self.hash() != data_hash {
+            bail_validation!(
+                "dah hash ({}) != header dah hash ({})",
+                self.hash(),
+                data_hash
+            )
+        }
+
+        Ok(())
+    }
+
+    /// Compute the header from the roots of the [`ExtendedDataSquare`] rows and columns.
+    ///
+    /// Each root is hashed incrementally straight from the shares of the square, without
+    /// building the whole tree or copying the shares.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the shares of the original data square have
+    /// invalid namespaces or aren't sorted by them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use celestia_types::consts::appconsts::AppVersion;
+    /// use celestia_types::nmt::Namespace;
+    /// use celestia_types::{Blob, DataAvailabilityHeader, ExtendedDataSquare};
+    ///
+    /// let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+    /// let blob = Blob::new(namespace, b"foo".to_vec(), AppVersion::V2).unwrap();
+    /// let eds = ExtendedDataSquare::build(&[blob], &[], 2, AppVersion::V2).unwrap();
+    ///
+    /// let dah = DataAvailabilityHeader::from_eds(&eds).unwrap();
+    /// assert_eq!(dah.square_len(), eds.square_len());
+    /// ```
+    ///
+    /// [`ExtendedDataSquare`]: crate::rsmt2d::ExtendedDataSquare
+    #[cfg(feature = "std")]
+    #[cfg_attr(docs_rs, doc(cfg(feature = "std")))]
+    pub fn from_eds(eds: &ExtendedDataSquare) -> Result<Self> {
+        let roots = |axis| {
+            (0..eds.square_len())
+                .map(|index| eds.axis_root(axis, index))
+                .collect::<Result<Vec<_>>>()
+        };
+
+        Ok(DataAvailabilityHeader {
+            row_roots: roots(AxisType::Row)?,
+            column_roots: roots(AxisType::Col)?,
+        })
+    }
+
+    /// Verify that the roots of the [`ExtendedDataSquare`] match the ones in this header.
+    ///
+    /// Useful for validating a locally reconstructed square. Roots are compared as they
+    /// are computed, stopping at the first mismatch.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the square has different size than the header
+    /// or any of its roots doesn't match.
+    ///
+    /// [`ExtendedDataSquare`]: crate::rsmt2d::ExtendedDataSquare
+    #[cfg(feature = "std")]
+    #[cfg_attr(docs_rs, doc(cfg(feature = "std")))]
+    pub fn verify_eds(&self, eds: &ExtendedDataSquare) -> Result<()> {
+        if eds.square_len() != self.square_len() {
+            return Err(Error::EdsInvalidDimentions);
+        }
+
+        for axis in [AxisType::Row, AxisType::Col] {
+            for index in 0..self.square_len() {
+                if self.root(axis, index) != Some(eds.axis_root(axis, index)?) {
+                    return Err(Error::RootMismatch);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the [`RowProof`] of inclusion of the given rows' roots in the [`hash`] of
+    /// this header.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the range is empty or any of the rows
+    /// is out of the square.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use celestia_types::consts::appconsts::AppVersion;
+    /// use celestia_types::nmt::Namespace;
+    /// use celestia_types::{Blob, DataAvailabilityHeader, ExtendedDataSquare};
+    ///
+    /// let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+    /// let blob = Blob::new(namespace, b"foo".to_vec(), AppVersion::V2).unwrap();
+    /// let eds = ExtendedDataSquare::build(&[blob], &[], 2, AppVersion::V2).unwrap();
+    /// let dah = DataAvailabilityHeader::from_eds(&eds).unwrap();
+    ///
+    /// let proof = dah.row_proof(0..=1).unwrap();
+    /// proof.verify(&dah.hash()).unwrap();
+    /// ```
+    ///
+    /// [`hash`]: DataAvailabilityHeader::hash
+    pub fn row_proof(&self, rows: RangeInclusive<usize>) -> Result<RowProof> {
+        let (start_row, end_row) = rows.into_inner();
+
+        if end_row >= self.square_len() {
+            return Err(Error::EdsIndexOutOfRange(end_row));
+        }
+        if start_row > end_row {
+            return Err(Error::EdsIndexOutOfRange(start_row));
+        }
+
+        let leaves: Vec<_> = self
+            .row_roots
+            .iter()
+            .chain(&self.column_roots)
+            .map(|root| root.to_array().to_vec())
+            .collect();
+
+        Ok(RowProof {
+            row_roots: self.row_roots[start_row..=end_row].to_vec(),
+            proofs: (start_row..=end_row)
+                .map(|row| merkle_proof(&leaves, row))
+                .collect(),
+            start_row: start_row as u32,
+            end_row: end_row as u32,
+        })
+    }
+
     /// Get the size of the [`ExtendedDataSquare`] for which this header was built.
     ///
     /// [`ExtendedDataSquare`]: crate::rsmt2d::ExtendedDataSquare
@@ -178,6 +340,13 @@ impl ValidateBasic for DataAvailabilityHeader {
             )
         }
 
+        if !self.row_roots.len().is_power_of_two() {
+            bail_validation!(
+                "row_roots len ({}) is not a power of two",
+                self.row_roots.len()
+            )
+        }
+
         Ok(())
     }
 }
@@ -202,6 +371,36 @@ mod tests {
         }"#).unwrap()
     }
 
+    #[test]
+    fn from_eds() {
+        let eds_json = include_str!("../test_data/shwap_samples/eds.json");
+        let eds: ExtendedDataSquare = serde_json::from_str(eds_json).unwrap();
+        let dah_json = include_str!("../test_data/shwap_samples/dah.json");
+        let dah: DataAvailabilityHeader = serde_json::from_str(dah_json).unwrap();
+
+        assert_eq!(DataAvailabilityHeader::from_eds(&eds).unwrap(), dah);
+        dah.verify_eds(&eds).unwrap();
+    }
+
+    #[test]
+    fn verify_eds_mismatch() {
+        let eds_json = include_str!("../test_data/shwap_samples/eds.json");
+        let mut eds: ExtendedDataSquare = serde_json::from_str(eds_json).unwrap();
+        let dah_json = include_str!("../test_data/shwap_samples/dah.json");
+        let dah: DataAvailabilityHeader = serde_json::from_str(dah_json).unwrap();
+
+        let last = eds.data_square.len() - 1;
+        let mut share = eds.data_square[last].to_vec();
+        share[0] ^= 1;
+        eds.data_square[last] = share.into();
+
+        assert!(matches!(dah.verify_eds(&eds), Err(Error::RootMismatch)));
+        assert!(matches!(
+            sample_dah().verify_eds(&eds),
+            Err(Error::EdsInvalidDimentions)
+        ));
+    }
+
     #[test]
     fn validate_correct() {
         let dah = sample_dah();
@@ -243,6 +442,9 @@ mod tests {
 
     #[test]
     fn validate_too_big_square() {
+        // celestia-app v3 raised the upper bound of the original square to 512
+        assert_eq!(MAX_EXTENDED_SQUARE_WIDTH, 1024);
+
         let mut dah = sample_dah();
         dah.row_roots = dah
             .row_roots
@@ -264,4 +466,24 @@ mod tests {
 
         dah.validate_basic().unwrap_err();
     }
+
+    #[test]
+    fn validate_square_not_power_of_two() {
+        let mut dah = sample_dah();
+        dah.row_roots = dah.row_roots.into_iter().cycle().take(6).collect();
+        dah.column_roots = dah.column_roots.into_iter().cycle().take(6).collect();
+
+        dah.validate_basic().unwrap_err();
+    }
+
+    #[test]
+    fn validate_with_data_hash() {
+        let eds_json = include_str!("../test_data/shwap_samples/eds.json");
+        let eds: ExtendedDataSquare = serde_json::from_str(eds_json).unwrap();
+        let dah = DataAvailabilityHeader::from_eds(&eds).unwrap();
+
+        dah.validate_with_data_hash(dah.hash()).unwrap();
+        dah.validate_with_data_hash(sample_dah().hash())
+            .unwrap_err();
+    }
 }
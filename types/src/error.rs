@@ -1,9 +1,11 @@
+use alloc::string::String;
+
 use crate::consts::appconsts;
 
 /// Alias for a `Result` with the error type [`celestia_types::Error`].
 ///
 /// [`celestia_types::Error`]: crate::Error
-pub type Result<T, E = Error> = std::result::Result<T, E>;
+pub type Result<T, E = Error> = core::result::Result<T, E>;
 
 /// Representation of all the errors that can occur when interacting with [`celestia_types`].
 ///
@@ -18,19 +20,31 @@ pub enum Error {
     #[error("Invalid namespace size")]
     InvalidNamespaceSize,
 
+    /// Invalid size of the commitment.
+    #[error("Invalid commitment size: {0}")]
+    InvalidCommitmentSize(usize),
+
+    /// String is not a valid hex encoding, optionally prefixed with `0x`.
+    #[error("Invalid hex: {0}")]
+    InvalidHex(String),
+
     /// Error propagated from the [`celestia_tendermint`].
-    #[error(transparent)]
-    Tendermint(#[from] celestia_tendermint::Error),
+    #[cfg_attr(feature = "std", error(transparent))]
+    #[cfg_attr(not(feature = "std"), error("{0}"))]
+    Tendermint(#[cfg_attr(feature = "std", from)] celestia_tendermint::Error),
 
     /// Error propagated from the [`celestia_tendermint_proto`].
+    #[cfg(feature = "std")]
     #[error(transparent)]
     Protobuf(#[from] celestia_tendermint_proto::Error),
 
     /// Error propagated from the [`cid::multihash`].
+    #[cfg(feature = "std")]
     #[error(transparent)]
     Multihash(#[from] cid::multihash::Error),
 
     /// Error returned when trying to compute new or parse existing CID. See [`blockstore::block`]
+    #[cfg(feature = "std")]
     #[error(transparent)]
     CidError(#[from] blockstore::block::CidError),
 
@@ -66,6 +80,10 @@ pub enum Error {
     #[error("Invalid share size: {0}")]
     InvalidShareSize(usize),
 
+    /// Blob without any data.
+    #[error("Blob data cannot be empty")]
+    EmptyBlob,
+
     /// Invalid nmt leaf size.
     #[error("Invalid nmt leaf size: {0}")]
     InvalidNmtLeafSize(usize),
@@ -89,9 +107,14 @@ pub enum Error {
     #[error("Invalid namespace v255")]
     InvalidNamespaceV255,
 
+    /// Namespace is reserved by the protocol and can't be used for the blobs.
+    #[error("Namespace {0} is reserved")]
+    ReservedNamespace(crate::nmt::Namespace),
+
     /// Invalid namespaced hash.
-    #[error(transparent)]
-    InvalidNamespacedHash(#[from] nmt_rs::InvalidNamespacedHash),
+    #[cfg_attr(feature = "std", error(transparent))]
+    #[cfg_attr(not(feature = "std"), error("Invalid namespaced hash"))]
+    InvalidNamespacedHash(#[cfg_attr(feature = "std", from)] nmt_rs::InvalidNamespacedHash),
 
     /// Invalid index of signature in commit.
     #[error("Invalid index of signature in commit {0}, height {1}")]
@@ -164,9 +187,250 @@ pub enum Error {
     #[error("Invalid dimensions of EDS")]
     EdsInvalidDimentions,
 
+    /// Width of the original data square is not a power of two or exceeds the upper bound.
+    #[error("Invalid square size: {0}")]
+    InvalidSquareSize(usize),
+
+    /// Transactions and blobs don't fit in the original data square of the given width.
+    #[error("Data doesn't fit in the square of size {0}")]
+    SquareTooSmall(usize),
+
+    /// Index of the blob in the square is not known.
+    #[error("Blob index is unknown")]
+    MissingBlobIndex,
+
+    /// Shares in the square at the index of the blob don't match it.
+    #[error("Blob not found in the square at index {0}")]
+    BlobNotInSquare(usize),
+
+    /// Not enough shares were provided to reconstruct the EDS.
+    #[error("Not enough shares to reconstruct EDS")]
+    EdsNotRecoverable,
+
+    /// Namespace has shares in the row, so its absence can't be proven.
+    #[error("Namespace is present in row {0}")]
+    NamespacePresent(usize),
+
+    /// Error propagated from the Leopard Reed-Solomon codec.
+    #[cfg(feature = "std")]
+    #[error(transparent)]
+    Leopard(#[from] leopard_codec::LeopardError),
+
     /// Zero block height.
     #[error("Invalid zero block height")]
     ZeroBlockHeight,
+
+    /// Query to the application state failed.
+    #[error("ABCI query failed with code {0}: {1}")]
+    AbciQuery(u32, String),
+
+    /// Unexpected operation in the state proof.
+    #[error("Unexpected state proof operation: {0}")]
+    UnexpectedProofOp(String),
+
+    /// Error decoding a protobuf message.
+    #[cfg_attr(feature = "std", error(transparent))]
+    #[cfg_attr(not(feature = "std"), error("{0}"))]
+    ProstDecode(#[cfg_attr(feature = "std", from)] prost::DecodeError),
+
+    /// Continuation share found without the preceding start of a sequence.
+    #[error("Unexpected continuation share")]
+    UnexpectedContinuationShare,
+
+    /// Share sequence ended before reaching its declared length.
+    #[error("Incomplete share sequence: expected {0} bytes, got {1}")]
+    IncompleteShareSequence(usize, usize),
+
+    /// Unit in the compact shares is longer than the remaining data of the sequence.
+    #[error("Malformed compact share unit: length {0}, remaining {1} bytes")]
+    MalformedCompactShareUnit(usize, usize),
+
+    /// Blob placement in the square doesn't follow the share commitment rules.
+    #[error("Blob at index {0} is not aligned to its commitment subtrees")]
+    UnalignedBlob(usize),
+
+    /// Blob data is compressed with an unknown codec or the one whose feature is not enabled.
+    #[error("Unsupported blob compression codec: {0}")]
+    UnsupportedCompression(u8),
+
+    /// Compressing or decompressing the blob data failed.
+    #[cfg(feature = "std")]
+    #[error("Blob compression failed: {0}")]
+    Compression(std::io::Error),
+
+    /// Decompressed blob data exceeds the maximum size.
+    #[error("Decompressed blob exceeds {0} bytes")]
+    DecompressedBlobTooLarge(usize),
+
+    /// Blob takes more shares than fit in the square next to its transaction.
+    #[error("Blob takes {0} shares, only {1} fit in the square")]
+    BlobTooLarge(usize, usize),
+
+    /// App version is not known to this crate.
+    #[error("Unsupported app version: {0}")]
+    UnsupportedAppVersion(u64),
+
+    /// Range of shares spans more than a single namespace.
+    #[error("Shares in range {0}..{1} belong to different namespaces")]
+    MixedNamespaceRange(usize, usize),
+
+    /// Block doesn't have a transaction with the given index.
+    #[error("Transaction {0} not found in the block")]
+    TxNotFound(usize),
+
+    /// Reserved bytes of the compact share point outside of its payload.
+    #[error("Invalid reserved bytes of the compact share: {0}")]
+    InvalidReservedBytes(u32),
+}
+
+// Without `std` the errors of the dependencies don't implement `core::error::Error`,
+// so they can't be the error sources
+#[cfg(not(feature = "std"))]
+impl From<celestia_tendermint::Error> for Error {
+    fn from(error: celestia_tendermint::Error) -> Self {
+        Error::Tendermint(error)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl From<nmt_rs::InvalidNamespacedHash> for Error {
+    fn from(error: nmt_rs::InvalidNamespacedHash) -> Self {
+        Error::InvalidNamespacedHash(error)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl From<prost::DecodeError> for Error {
+    fn from(error: prost::DecodeError) -> Self {
+        Error::ProstDecode(error)
+    }
+}
+
+impl Error {
+    /// Returns `true` if the operation which failed with this error may succeed
+    /// when tried again.
+    ///
+    /// Invalid data stays invalid, so only the failures reported by the services the
+    /// data was queried from, like the [`Error::AbciQuery`], are retryable.
+    pub fn is_retryable(&self) -> bool {
+        self.kind() == ErrorKind::External
+    }
+
+    /// Get the [`ErrorKind`] of the error.
+    pub fn kind(&self) -> ErrorKind {
+        self.code_and_kind().1
+    }
+
+    /// Get the code of the error.
+    ///
+    /// Codes are stable: each variant keeps its code across releases and codes of the
+    /// removed variants are never reused, so they can be stored or sent over the wire.
+    /// The errors of [`celestia_types`] use the codes from `1001` to `1999`.
+    ///
+    /// [`celestia_types`]: crate
+    pub fn code(&self) -> u16 {
+        self.code_and_kind().0
+    }
+
+    fn code_and_kind(&self) -> (u16, ErrorKind) {
+        match self {
+            Error::UnsupportedNamespaceVersion(..) => (1001, ErrorKind::Encoding),
+            Error::InvalidNamespaceSize => (1002, ErrorKind::Encoding),
+            Error::InvalidCommitmentSize(..) => (1003, ErrorKind::Encoding),
+            Error::InvalidHex(..) => (1004, ErrorKind::Encoding),
+            Error::Tendermint(..) => (1005, ErrorKind::Encoding),
+            #[cfg(feature = "std")]
+            Error::Protobuf(..) => (1006, ErrorKind::Encoding),
+            #[cfg(feature = "std")]
+            Error::Multihash(..) => (1007, ErrorKind::Encoding),
+            #[cfg(feature = "std")]
+            Error::CidError(..) => (1008, ErrorKind::Encoding),
+            Error::MissingHeader => (1009, ErrorKind::Validation),
+            Error::MissingCommit => (1010, ErrorKind::Validation),
+            Error::MissingValidatorSet => (1011, ErrorKind::Validation),
+            Error::MissingDataAvailabilityHeader => (1012, ErrorKind::Validation),
+            Error::MissingProof => (1013, ErrorKind::Proof),
+            Error::WrongProofType => (1014, ErrorKind::Proof),
+            Error::UnsupportedShareVersion(..) => (1015, ErrorKind::Encoding),
+            Error::InvalidShareSize(..) => (1016, ErrorKind::Encoding),
+            Error::EmptyBlob => (1017, ErrorKind::Validation),
+            Error::InvalidNmtLeafSize(..) => (1018, ErrorKind::Encoding),
+            Error::InvalidNmtNodeOrder => (1019, ErrorKind::Validation),
+            Error::ShareSequenceLenExceeded(..) => (1020, ErrorKind::Encoding),
+            Error::InvalidNamespaceV0 => (1021, ErrorKind::Encoding),
+            Error::InvalidNamespaceV255 => (1022, ErrorKind::Encoding),
+            Error::ReservedNamespace(..) => (1023, ErrorKind::Validation),
+            Error::InvalidNamespacedHash(..) => (1024, ErrorKind::Encoding),
+            Error::InvalidSignatureIndex(..) => (1025, ErrorKind::Validation),
+            Error::InvalidAxis(..) => (1026, ErrorKind::Encoding),
+            Error::RangeProofError(..) => (1027, ErrorKind::Proof),
+            Error::RootMismatch => (1028, ErrorKind::Proof),
+            Error::UnexpectedAbsentSignature => (1029, ErrorKind::Validation),
+            Error::Validation(..) => (1030, ErrorKind::Validation),
+            Error::Verification(..) => (1031, ErrorKind::Proof),
+            Error::MaxShareVersionExceeded(..) => (1032, ErrorKind::Encoding),
+            Error::Nmt(..) => (1033, ErrorKind::Validation),
+            Error::InvalidAddressPrefix(..) => (1034, ErrorKind::Encoding),
+            Error::InvalidAddressSize(..) => (1035, ErrorKind::Encoding),
+            Error::InvalidAddress(..) => (1036, ErrorKind::Encoding),
+            Error::InvalidBalanceDenomination(..) => (1037, ErrorKind::Encoding),
+            Error::InvalidBalanceAmount(..) => (1038, ErrorKind::Encoding),
+            Error::UnsupportedFraudProofType(..) => (1039, ErrorKind::Encoding),
+            Error::EdsIndexOutOfRange(..) => (1040, ErrorKind::Validation),
+            Error::EdsInvalidDimentions => (1041, ErrorKind::Validation),
+            Error::InvalidSquareSize(..) => (1042, ErrorKind::Validation),
+            Error::SquareTooSmall(..) => (1043, ErrorKind::Validation),
+            Error::MissingBlobIndex => (1044, ErrorKind::Validation),
+            Error::BlobNotInSquare(..) => (1045, ErrorKind::Proof),
+            Error::EdsNotRecoverable => (1046, ErrorKind::Validation),
+            Error::NamespacePresent(..) => (1047, ErrorKind::Proof),
+            #[cfg(feature = "std")]
+            Error::Leopard(..) => (1048, ErrorKind::Encoding),
+            Error::ZeroBlockHeight => (1049, ErrorKind::Validation),
+            Error::AbciQuery(..) => (1050, ErrorKind::External),
+            Error::UnexpectedProofOp(..) => (1051, ErrorKind::Proof),
+            Error::ProstDecode(..) => (1052, ErrorKind::Encoding),
+            Error::UnexpectedContinuationShare => (1053, ErrorKind::Encoding),
+            Error::IncompleteShareSequence(..) => (1054, ErrorKind::Encoding),
+            Error::MalformedCompactShareUnit(..) => (1055, ErrorKind::Encoding),
+            Error::UnalignedBlob(..) => (1056, ErrorKind::Validation),
+            Error::UnsupportedCompression(..) => (1057, ErrorKind::Encoding),
+            #[cfg(feature = "std")]
+            Error::Compression(..) => (1058, ErrorKind::Encoding),
+            Error::DecompressedBlobTooLarge(..) => (1059, ErrorKind::Validation),
+            Error::BlobTooLarge(..) => (1060, ErrorKind::Validation),
+            Error::UnsupportedAppVersion(..) => (1061, ErrorKind::Validation),
+            Error::MixedNamespaceRange(..) => (1062, ErrorKind::Validation),
+            Error::TxNotFound(..) => (1063, ErrorKind::Validation),
+            Error::InvalidReservedBytes(..) => (1064, ErrorKind::Encoding),
+        }
+    }
+}
+
+/// Category of an error, allowing to branch on the errors without matching each of the variants.
+///
+/// It is shared by the errors of the crates built on top of [`celestia_types`].
+///
+/// [`celestia_types`]: crate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// Data doesn't conform to the protocol rules.
+    Validation,
+    /// Proof of the data or the signatures over it are invalid.
+    Proof,
+    /// Data couldn't be encoded or decoded.
+    Encoding,
+    /// A service the data was requested from reported a failure.
+    External,
+    /// Communication with the other peers failed.
+    P2p,
+    /// Reading or writing the persistent data failed.
+    Store,
+    /// The component was misconfigured.
+    Config,
+    /// An internal component failed, e.g. a worker stopped.
+    Internal,
 }
 
 /// Representation of the errors that can occur when validating data.
@@ -203,10 +467,10 @@ pub enum VerificationError {
 
 macro_rules! validation_error {
     ($fmt:literal $(,)?) => {
-        $crate::ValidationError::Other(std::format!($fmt))
+        $crate::ValidationError::Other(alloc::format!($fmt))
     };
     ($fmt:literal, $($arg:tt)*) => {
-        $crate::ValidationError::Other(std::format!($fmt, $($arg)*))
+        $crate::ValidationError::Other(alloc::format!($fmt, $($arg)*))
     };
 }
 
@@ -218,10 +482,10 @@ macro_rules! bail_validation {
 
 macro_rules! verification_error {
     ($fmt:literal $(,)?) => {
-        $crate::VerificationError::Other(std::format!($fmt))
+        $crate::VerificationError::Other(alloc::format!($fmt))
     };
     ($fmt:literal, $($arg:tt)*) => {
-        $crate::VerificationError::Other(std::format!($fmt, $($arg)*))
+        $crate::VerificationError::Other(alloc::format!($fmt, $($arg)*))
     };
 }
 
@@ -236,3 +500,36 @@ pub(crate) use bail_validation;
 pub(crate) use bail_verification;
 pub(crate) use validation_error;
 pub(crate) use verification_error;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    #[test]
+    fn stable_codes() {
+        assert_eq!(Error::UnsupportedNamespaceVersion(1).code(), 1001);
+        assert_eq!(Error::MissingHeader.code(), 1009);
+        assert_eq!(Error::MalformedCompactShareUnit(1, 0).code(), 1055);
+
+        assert_eq!(Error::MissingHeader.kind(), ErrorKind::Validation);
+        assert_eq!(Error::RootMismatch.kind(), ErrorKind::Proof);
+        assert_eq!(Error::InvalidShareSize(1).kind(), ErrorKind::Encoding);
+        assert_eq!(
+            Error::from(verification_error!("invalid")).kind(),
+            ErrorKind::Proof
+        );
+    }
+
+    #[test]
+    fn retryable_errors() {
+        assert!(Error::AbciQuery(5, "height not available".into()).is_retryable());
+
+        assert!(!Error::MissingHeader.is_retryable());
+        assert!(!Error::RootMismatch.is_retryable());
+        assert!(!Error::InvalidShareSize(1).is_retryable());
+        assert!(!Error::from(verification_error!("invalid")).is_retryable());
+    }
+}
@@ -1,5 +1,4 @@
 use std::fmt::{Display, Formatter};
-#[cfg(any(not(target_arch = "wasm32"), feature = "wasm-bindgen"))]
 use std::time::Duration;
 
 use celestia_proto::header::pb::ExtendedHeader as RawExtendedHeader;
@@ -10,7 +9,8 @@ use celestia_tendermint::{validator, Hash, Time};
 use celestia_tendermint_proto::Protobuf;
 use serde::{Deserialize, Serialize};
 
-use crate::trust_level::DEFAULT_TRUST_LEVEL;
+use crate::consts::appconsts::AppVersion;
+use crate::trust_level::{TrustLevelRatio, DEFAULT_TRUST_LEVEL};
 use crate::validator_set::ValidatorSetExt;
 use crate::{
     bail_validation, bail_verification, DataAvailabilityHeader, Error, Result, ValidateBasic,
@@ -21,8 +21,44 @@ pub type Validator = validator::Info;
 /// A collection of the tendermint validators.
 pub type ValidatorSet = validator::Set;
 
-#[cfg(any(not(target_arch = "wasm32"), feature = "wasm-bindgen"))]
-const VERIFY_CLOCK_DRIFT: Duration = Duration::from_secs(10);
+/// Default maximum time by which the verified headers can be ahead of the local clock.
+pub const DEFAULT_MAX_CLOCK_DRIFT: Duration = Duration::from_secs(10);
+
+/// Check of the time of the verified headers, rejecting the ones from the future.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockCheck {
+    /// Compare with the system time, tolerating the [`DEFAULT_MAX_CLOCK_DRIFT`].
+    ///
+    /// The time isn't checked on `wasm32` targets without the `wasm-bindgen` feature,
+    /// where the system time is not available.
+    System,
+    /// Compare with the given time.
+    At {
+        /// Current time of the local clock.
+        now: Time,
+        /// Maximum time by which the headers can be ahead of `now`.
+        max_clock_drift: Duration,
+    },
+    /// Don't check the time of the headers.
+    Skip,
+}
+
+impl ClockCheck {
+    /// Current time and the tolerated drift, if the time is checked.
+    fn now_and_drift(self) -> Option<(Time, Duration)> {
+        match self {
+            #[cfg(any(not(target_arch = "wasm32"), feature = "wasm-bindgen"))]
+            ClockCheck::System => Some((Time::now(), DEFAULT_MAX_CLOCK_DRIFT)),
+            #[cfg(not(any(not(target_arch = "wasm32"), feature = "wasm-bindgen")))]
+            ClockCheck::System => None,
+            ClockCheck::At {
+                now,
+                max_clock_drift,
+            } => Some((now, max_clock_drift)),
+            ClockCheck::Skip => None,
+        }
+    }
+}
 
 /// Block header together with the relevant Data Availability metadata.
 ///
@@ -93,6 +129,15 @@ impl ExtendedHeader {
         self.header.time
     }
 
+    /// Get the version of the celestia-app the block was produced with.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnsupportedAppVersion`] if the version is not known to this crate.
+    pub fn app_version(&self) -> Result<AppVersion> {
+        AppVersion::try_from(self.header.version.app)
+    }
+
     /// Get the block hash.
     pub fn hash(&self) -> Hash {
         self.commit.block_id.hash
@@ -134,6 +179,101 @@ impl ExtendedHeader {
         self.commit.validate_basic()?;
         self.validator_set.validate_basic()?;
 
+        // ensure data root from raw header matches computed root
+        self.dah.validate_with_data_hash(self.header.data_hash)?;
+
+        self.verify_commit()?;
+
+        Ok(())
+    }
+
+    /// Verify that the block was committed by its validator set.
+    ///
+    /// Checks that the validator set is the one included in the header, and that
+    /// the commit is for this header and carries valid signatures of more than 2/3
+    /// of the voting power of the validator set. This is a part of
+    /// [`ExtendedHeader::validate`], which additionally checks the consistency of
+    /// the header and its [`DataAvailabilityHeader`].
+    ///
+    /// # Errors
+    ///
+    /// If verification fails, this function will return an error with a reason of failure.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use celestia_types::ExtendedHeader;
+    /// # let s = include_str!("../test_data/chain1/extended_header_block_27.json");
+    /// # let header: ExtendedHeader = serde_json::from_str(s).unwrap();
+    /// header.verify_commit().unwrap();
+    /// ```
+    pub fn verify_commit(&self) -> Result<()> {
+        self.validate_commit_consistency()?;
+
+        self.validator_set
+            .verify_commit_light(&self.header.chain_id, &self.height(), &self.commit)
+    }
+
+    /// Validate a batch of headers, same as [`ExtendedHeader::validate`] of each of them,
+    /// but verifying the commit signatures of all the headers at once with the ed25519
+    /// batch verification, which is a few times faster.
+    ///
+    /// Batch verification only tells whether all the signatures are valid, so if it
+    /// fails, the headers are validated one by one to find the invalid one.
+    ///
+    /// # Errors
+    ///
+    /// Returns the index of the first invalid header in the batch with its error.
+    /// All the headers preceding it are valid.
+    #[cfg(feature = "batch-verify")]
+    #[cfg_attr(docs_rs, doc(cfg(feature = "batch-verify")))]
+    pub fn validate_batch(headers: &[ExtendedHeader]) -> Result<(), (usize, Error)> {
+        let mut batch = ed25519_consensus::batch::Verifier::new();
+        let mut invalid = None;
+
+        for (idx, header) in headers.iter().enumerate() {
+            if let Err(e) = header.queue_validation(&mut batch) {
+                invalid = Some((idx, e));
+                break;
+            }
+        }
+
+        // signatures of the headers preceding the invalid one are queued
+        let queued = invalid.as_ref().map_or(headers.len(), |(idx, _)| *idx);
+
+        if batch.verify(rand::thread_rng()).is_err() {
+            for (idx, header) in headers[..queued].iter().enumerate() {
+                header.validate().map_err(|e| (idx, e))?;
+            }
+        }
+
+        match invalid {
+            Some(invalid) => Err(invalid),
+            None => Ok(()),
+        }
+    }
+
+    /// Validate the header, queueing its commit signatures in the `batch` instead of
+    /// verifying them.
+    #[cfg(feature = "batch-verify")]
+    fn queue_validation(&self, batch: &mut ed25519_consensus::batch::Verifier) -> Result<()> {
+        self.header.validate_basic()?;
+        self.commit.validate_basic()?;
+        self.validator_set.validate_basic()?;
+        self.dah.validate_with_data_hash(self.header.data_hash)?;
+        self.validate_commit_consistency()?;
+
+        crate::validator_set::queue_commit_light(
+            &self.validator_set,
+            &self.header.chain_id,
+            &self.height(),
+            &self.commit,
+            batch,
+        )
+    }
+
+    /// Check that the validator set and the commit are the ones of the header.
+    fn validate_commit_consistency(&self) -> Result<()> {
         // make sure the validator set is consistent with the header
         if self.validator_set.hash() != self.header.validators_hash {
             bail_validation!(
@@ -143,15 +283,6 @@ impl ExtendedHeader {
             )
         }
 
-        // ensure data root from raw header matches computed root
-        if self.dah.hash() != self.header.data_hash {
-            bail_validation!(
-                "dah hash ({}) != header dah hash ({})",
-                self.dah.hash(),
-                self.header.data_hash,
-            )
-        }
-
         // Make sure the header is consistent with the commit.
         if self.commit.height != self.height() {
             bail_validation!(
@@ -169,14 +300,6 @@ impl ExtendedHeader {
             )
         }
 
-        self.validator_set.verify_commit_light(
-            &self.header.chain_id,
-            &self.height(),
-            &self.commit,
-        )?;
-
-        self.dah.validate_basic()?;
-
         Ok(())
     }
 
@@ -192,6 +315,33 @@ impl ExtendedHeader {
     /// fail if the validator set commiting those blocks was changed. If that is the case,
     /// consider verifying the untrusted header with a more recent or even previous header.
     pub fn verify(&self, untrusted: &ExtendedHeader) -> Result<()> {
+        self.verify_with_trust_level(untrusted, DEFAULT_TRUST_LEVEL)
+    }
+
+    /// Same as [`ExtendedHeader::verify`], but checks the time of the untrusted header
+    /// with the given [`ClockCheck`].
+    pub fn verify_with_clock(&self, untrusted: &ExtendedHeader, clock: ClockCheck) -> Result<()> {
+        self.verify_with_trust_level_and_clock(untrusted, DEFAULT_TRUST_LEVEL, clock)
+    }
+
+    /// Same as [`ExtendedHeader::verify`], but requires the given `trust_level` of
+    /// the voting power of `self` validators to sign the non-adjacent header.
+    pub(crate) fn verify_with_trust_level(
+        &self,
+        untrusted: &ExtendedHeader,
+        trust_level: TrustLevelRatio,
+    ) -> Result<()> {
+        self.verify_with_trust_level_and_clock(untrusted, trust_level, ClockCheck::System)
+    }
+
+    /// Same as [`ExtendedHeader::verify_with_trust_level`], but checks the time of
+    /// the untrusted header with the given [`ClockCheck`].
+    pub(crate) fn verify_with_trust_level_and_clock(
+        &self,
+        untrusted: &ExtendedHeader,
+        trust_level: TrustLevelRatio,
+        clock: ClockCheck,
+    ) -> Result<()> {
         if untrusted.height() <= self.height() {
             bail_verification!(
                 "untrusted header height({}) <= current trusted header({})",
@@ -216,17 +366,15 @@ impl ExtendedHeader {
             );
         }
 
-        #[cfg(any(not(target_arch = "wasm32"), feature = "wasm-bindgen"))]
-        {
-            let now = Time::now();
-            let valid_until = now.checked_add(VERIFY_CLOCK_DRIFT).unwrap();
+        if let Some((now, max_clock_drift)) = clock.now_and_drift() {
+            let valid_until = now.checked_add(max_clock_drift).unwrap();
 
             if !untrusted.time().before(valid_until) {
                 bail_verification!(
                     "new untrusted header has a time from the future {} (now: {}, clock_drift: {:?})",
                     untrusted.time(),
                     now,
-                    VERIFY_CLOCK_DRIFT
+                    max_clock_drift
                 );
             }
         }
@@ -257,7 +405,7 @@ impl ExtendedHeader {
         self.validator_set.verify_commit_light_trusting(
             self.chain_id(),
             &untrusted.commit,
-            DEFAULT_TRUST_LEVEL,
+            trust_level,
         )?;
 
         Ok(())
@@ -293,25 +441,70 @@ impl ExtendedHeader {
     /// assert!(genesis_header.verify_range(&next_headers).is_ok());
     /// ```
     pub fn verify_range(&self, untrusted: &[ExtendedHeader]) -> Result<()> {
-        let mut trusted = self;
+        self.verify_range_with_clock(untrusted, ClockCheck::System)
+    }
+
+    /// Same as [`ExtendedHeader::verify_range`], but checks the time of the untrusted
+    /// headers with the given [`ClockCheck`].
+    pub fn verify_range_with_clock(
+        &self,
+        untrusted: &[ExtendedHeader],
+        clock: ClockCheck,
+    ) -> Result<()> {
+        // The first untrusted header doesn't need to be adjacent to `self`,
+        // however all the following ones must be adjacent to their previous one.
+        let Some((first, rest)) = untrusted.split_first() else {
+            return Ok(());
+        };
 
-        for (i, untrusted) in untrusted.iter().enumerate() {
-            // All headers in `untrusted` must be adjacent to their previous
-            // one. However we do not check if the first untrusted is adjacent
-            // to `self`. This check is done in `verify_adjacent_range`.
-            if i != 0 && trusted.height().increment() != untrusted.height() {
-                bail_verification!(
-                    "untrusted header height ({}) not adjacent to the current trusted ({})",
-                    untrusted.height(),
-                    trusted.height(),
-                );
-            }
+        self.verify_with_clock(first, clock)?;
+        first.verify_adjacent_range_with_clock(rest, clock)
+    }
 
-            trusted.verify(untrusted)?;
-            trusted = untrusted;
+    /// Verify an untrusted header which directly follows `self`.
+    ///
+    /// Same as [`ExtendedHeader::verify`], but additionally requires the untrusted
+    /// header to be at the height right above `self`. It is thus guaranteed to
+    /// point to the hash of `self`.
+    ///
+    /// # Errors
+    ///
+    /// If verification fails, this function will return an error with a reason of failure.
+    /// This function will also return an error if the untrusted header isn't adjacent to `self`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use celestia_types::ExtendedHeader;
+    /// # let s = include_str!("../test_data/chain3/extended_header_block_1_to_256.json");
+    /// # let headers: Vec<ExtendedHeader> = serde_json::from_str(s).unwrap();
+    /// let (genesis_header, next_header) = (&headers[0], &headers[1]);
+    ///
+    /// genesis_header.verify_adjacent(next_header).unwrap();
+    ///
+    /// // fails, not adjacent to genesis
+    /// assert!(genesis_header.verify_adjacent(&headers[2]).is_err());
+    /// ```
+    pub fn verify_adjacent(&self, untrusted: &ExtendedHeader) -> Result<()> {
+        self.verify_adjacent_with_clock(untrusted, ClockCheck::System)
+    }
+
+    /// Same as [`ExtendedHeader::verify_adjacent`], but checks the time of the untrusted
+    /// header with the given [`ClockCheck`].
+    pub fn verify_adjacent_with_clock(
+        &self,
+        untrusted: &ExtendedHeader,
+        clock: ClockCheck,
+    ) -> Result<()> {
+        if self.height().increment() != untrusted.height() {
+            bail_verification!(
+                "untrusted header height ({}) not adjacent to the current trusted ({})",
+                untrusted.height(),
+                self.height(),
+            );
         }
 
-        Ok(())
+        self.verify_with_clock(untrusted, clock)
     }
 
     /// Verify a chain of adjacent untrusted headers and make sure
@@ -350,20 +543,24 @@ impl ExtendedHeader {
     /// genesis_header.verify_adjacent_range(&next_headers).unwrap();
     /// ```
     pub fn verify_adjacent_range(&self, untrusted: &[ExtendedHeader]) -> Result<()> {
-        if untrusted.is_empty() {
-            return Ok(());
-        }
+        self.verify_adjacent_range_with_clock(untrusted, ClockCheck::System)
+    }
 
-        // Check is first untrusted is adjacent to `self`.
-        if self.height().increment() != untrusted[0].height() {
-            bail_verification!(
-                "untrusted header height ({}) not adjacent to the current trusted ({})",
-                untrusted[0].height(),
-                self.height(),
-            );
+    /// Same as [`ExtendedHeader::verify_adjacent_range`], but checks the time of the
+    /// untrusted headers with the given [`ClockCheck`].
+    pub fn verify_adjacent_range_with_clock(
+        &self,
+        untrusted: &[ExtendedHeader],
+        clock: ClockCheck,
+    ) -> Result<()> {
+        let mut trusted = self;
+
+        for untrusted in untrusted {
+            trusted.verify_adjacent_with_clock(untrusted, clock)?;
+            trusted = untrusted;
         }
 
-        self.verify_range(untrusted)
+        Ok(())
     }
 }
 
@@ -458,6 +655,18 @@ mod tests {
         sample_eh_chain_2_block_35().validate().unwrap();
     }
 
+    #[test]
+    fn app_version() {
+        let mut eh = sample_eh_chain_1_block_1();
+        assert_eq!(eh.app_version().unwrap(), AppVersion::V1);
+
+        eh.header.version.app = 4;
+        assert!(matches!(
+            eh.app_version().unwrap_err(),
+            Error::UnsupportedAppVersion(4)
+        ));
+    }
+
     #[test]
     fn validate_validator_hash_mismatch() {
         let mut eh = sample_eh_chain_1_block_27();
@@ -509,6 +718,7 @@ mod tests {
         let eh_block_28 = sample_eh_chain_2_block_28();
 
         eh_block_27.verify(&eh_block_28).unwrap();
+        eh_block_27.verify_adjacent(&eh_block_28).unwrap();
     }
 
     #[test]
@@ -580,6 +790,57 @@ mod tests {
         eh_block_1.verify(&eh_block_27).unwrap_err();
     }
 
+    #[test]
+    fn verify_time_with_clock() {
+        let eh_block_1 = sample_eh_chain_1_block_1();
+        let eh_block_27 = sample_eh_chain_1_block_27();
+        let behind = eh_block_27
+            .time()
+            .checked_sub(Duration::from_secs(60))
+            .unwrap();
+
+        let clock = ClockCheck::At {
+            now: behind,
+            max_clock_drift: DEFAULT_MAX_CLOCK_DRIFT,
+        };
+        eh_block_1
+            .verify_with_clock(&eh_block_27, clock)
+            .unwrap_err();
+
+        // skewed clock is tolerated with a larger drift
+        let clock = ClockCheck::At {
+            now: behind,
+            max_clock_drift: Duration::from_secs(120),
+        };
+        eh_block_1.verify_with_clock(&eh_block_27, clock).unwrap();
+
+        eh_block_1
+            .verify_with_clock(&eh_block_27, ClockCheck::Skip)
+            .unwrap();
+    }
+
+    #[cfg(feature = "batch-verify")]
+    #[test]
+    fn validate_batch() {
+        let mut headers = sample_eh_chain_3_block_1_to_256();
+        ExtendedHeader::validate_batch(&headers[..100]).unwrap();
+        ExtendedHeader::validate_batch(&[]).unwrap();
+
+        // signatures of the other block
+        headers[50].commit.signatures = headers[51].commit.signatures.clone();
+        headers[50].validate().unwrap_err();
+        let (idx, _) = ExtendedHeader::validate_batch(&headers[..100]).unwrap_err();
+        assert_eq!(idx, 50);
+        let (idx, _) = ExtendedHeader::validate_batch(&headers[40..100]).unwrap_err();
+        assert_eq!(idx, 10);
+        ExtendedHeader::validate_batch(&headers[..50]).unwrap();
+
+        // invalid without verifying the signatures
+        headers[20].commit.height = headers[21].commit.height;
+        let (idx, _) = ExtendedHeader::validate_batch(&headers[..100]).unwrap_err();
+        assert_eq!(idx, 20);
+    }
+
     #[test]
     fn verify_range() {
         let eh_chain = sample_eh_chain_3_block_1_to_256();
@@ -634,6 +895,18 @@ mod tests {
         eh_chain[0].verify_range(&headers).unwrap();
     }
 
+    #[test]
+    fn verify_adjacent_not_adjacent_height() {
+        let eh_chain = sample_eh_chain_3_block_1_to_256();
+
+        eh_chain[0].verify_adjacent(&eh_chain[1]).unwrap();
+        eh_chain[10].verify_adjacent(&eh_chain[11]).unwrap();
+
+        eh_chain[0].verify_adjacent(&eh_chain[0]).unwrap_err();
+        eh_chain[0].verify_adjacent(&eh_chain[2]).unwrap_err();
+        eh_chain[1].verify_adjacent(&eh_chain[0]).unwrap_err();
+    }
+
     #[test]
     fn verify_adjacent_range() {
         let eh_chain = sample_eh_chain_3_block_1_to_256();
@@ -1,41 +1,88 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![cfg_attr(docs_rs, feature(doc_cfg))]
 #![doc = include_str!("../README.md")]
 
+extern crate alloc;
+
+#[cfg(feature = "std")]
+#[cfg_attr(docs_rs, doc(cfg(feature = "std")))]
+pub mod abi;
+#[cfg(feature = "std")]
+#[cfg_attr(docs_rs, doc(cfg(feature = "std")))]
 pub mod blob;
+#[cfg(feature = "std")]
 mod block;
+#[cfg(feature = "std")]
+#[cfg_attr(docs_rs, doc(cfg(feature = "std")))]
+pub mod bundle;
+#[cfg(feature = "std")]
 mod byzantine;
 pub mod consts;
 mod data_availability_header;
 mod error;
+#[cfg(feature = "std")]
 mod extended_header;
+#[cfg(feature = "std")]
+#[cfg_attr(docs_rs, doc(cfg(feature = "std")))]
 pub mod fraud_proof;
 pub mod hash;
+#[cfg(feature = "std")]
+#[cfg_attr(docs_rs, doc(cfg(feature = "std")))]
+pub mod light_client;
+#[cfg(feature = "std")]
+#[cfg_attr(docs_rs, doc(cfg(feature = "std")))]
 pub mod namespaced_data;
 pub mod nmt;
 #[cfg(feature = "p2p")]
 #[cfg_attr(docs_rs, doc(cfg(feature = "p2p")))]
 pub mod p2p;
+mod range_proof;
+#[cfg(feature = "std")]
+#[cfg_attr(docs_rs, doc(cfg(feature = "std")))]
 pub mod row;
 mod rsmt2d;
+#[cfg(feature = "std")]
+#[cfg_attr(docs_rs, doc(cfg(feature = "std")))]
 pub mod sample;
 pub(crate) mod serializers;
 mod share;
+#[cfg(feature = "std")]
+#[cfg_attr(docs_rs, doc(cfg(feature = "std")))]
+pub mod shwap;
+#[cfg(feature = "std")]
+mod square;
+#[cfg(feature = "std")]
+#[cfg_attr(docs_rs, doc(cfg(feature = "std")))]
 pub mod state;
+#[cfg(feature = "std")]
 mod sync;
 #[cfg(any(test, feature = "test-utils"))]
 #[cfg_attr(docs_rs, doc(cfg(feature = "test-utils")))]
 pub mod test_utils;
+#[cfg(feature = "std")]
+#[cfg_attr(docs_rs, doc(cfg(feature = "std")))]
 pub mod trust_level;
 mod validate;
+#[cfg(feature = "std")]
 mod validator_set;
 
+#[cfg(feature = "std")]
 pub use crate::blob::{Blob, Commitment};
+#[cfg(feature = "std")]
 pub use crate::block::*;
 pub use crate::data_availability_header::*;
 pub use crate::error::*;
+#[cfg(feature = "std")]
 pub use crate::extended_header::*;
+#[cfg(feature = "std")]
 pub use crate::fraud_proof::FraudProof;
-pub use crate::rsmt2d::{AxisType, ExtendedDataSquare};
+pub use crate::range_proof::*;
+pub use crate::rsmt2d::AxisType;
+#[cfg(feature = "std")]
+pub use crate::rsmt2d::{ExtendedDataSquare, LEOPARD_CODEC};
 pub use crate::share::*;
+#[cfg(feature = "std")]
 pub use crate::sync::*;
 pub use crate::validate::*;
+#[cfg(feature = "std")]
+pub use crate::validator_set::ValidatorSetExt;
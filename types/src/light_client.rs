@@ -0,0 +1,220 @@
+//! Standalone verification of the [`ExtendedHeader`]s, following the light client
+//! rules of the Tendermint.
+//!
+//! Allows to verify a header against a trusted one without having the headers in between,
+//! e.g. when syncing from a trusted checkpoint or bridging the headers to other systems.
+
+use std::time::Duration;
+
+use celestia_tendermint::Time;
+
+use crate::trust_level::{TrustLevelRatio, DEFAULT_TRUST_LEVEL};
+use crate::{bail_verification, ClockCheck, ExtendedHeader, Result, DEFAULT_MAX_CLOCK_DRIFT};
+
+/// Verify the `target` header by skipping from the `trusted` one.
+///
+/// The `target` is validated on its own, including its commit, and then accepted only
+/// if the validators of the `trusted` header holding more than 1/3 of its voting power
+/// signed it, as per [`DEFAULT_TRUST_LEVEL`]. For adjacent headers, it is checked that
+/// the `target` links to the `trusted` one instead.
+///
+/// The `trusted` header can be relied on only within the `trusting_period` since its
+/// time, after which its validators may have unbonded and could sign any header without
+/// being slashed. The `target` can't be ahead of `now` by more than
+/// the [`DEFAULT_MAX_CLOCK_DRIFT`], see [`verify_skipping_with_trust_level`] to tolerate
+/// a different drift of the local clock.
+///
+/// # Errors
+///
+/// If verification fails, this function will return an error with a reason of failure.
+/// It also fails if the `trusted` header expired, i.e. its time plus the `trusting_period`
+/// isn't after `now`.
+///
+/// Please note that the verification will fail if the validator set changed by more
+/// than 2/3 of the voting power between the headers. In that case, a header in between
+/// needs to be verified first and used as the new trusted one.
+///
+/// # Example
+///
+/// ```
+/// # use celestia_types::ExtendedHeader;
+/// use celestia_types::light_client::verify_skipping;
+/// # let s = include_str!("../test_data/chain3/extended_header_block_1_to_256.json");
+/// # let headers: Vec<ExtendedHeader> = serde_json::from_str(s).unwrap();
+/// # let trusted = &headers[0];
+/// # let target = &headers[100];
+/// # let now = target.time();
+/// use std::time::Duration;
+///
+/// let trusting_period = Duration::from_secs(14 * 24 * 60 * 60);
+/// verify_skipping(trusted, target, trusting_period, now).unwrap();
+/// ```
+pub fn verify_skipping(
+    trusted: &ExtendedHeader,
+    target: &ExtendedHeader,
+    trusting_period: Duration,
+    now: Time,
+) -> Result<()> {
+    verify_skipping_with_trust_level(
+        trusted,
+        target,
+        trusting_period,
+        now,
+        DEFAULT_MAX_CLOCK_DRIFT,
+        DEFAULT_TRUST_LEVEL,
+    )
+}
+
+/// Same as [`verify_skipping`], but tolerates the `target` being ahead of `now` by up to
+/// `max_clock_drift`, and requires the given `trust_level` of the voting power of
+/// the `trusted` header validators to sign the `target`.
+pub fn verify_skipping_with_trust_level(
+    trusted: &ExtendedHeader,
+    target: &ExtendedHeader,
+    trusting_period: Duration,
+    now: Time,
+    max_clock_drift: Duration,
+    trust_level: TrustLevelRatio,
+) -> Result<()> {
+    let expired = trusted
+        .time()
+        .checked_add(trusting_period)
+        .map_or(true, |expires| expires <= now);
+
+    if expired {
+        bail_verification!(
+            "trusted header at height {} expired, its time {} plus the trusting period {:?} isn't after {}",
+            trusted.height(),
+            trusted.time(),
+            trusting_period,
+            now,
+        );
+    }
+
+    target.validate()?;
+    trusted.verify_with_trust_level_and_clock(
+        target,
+        trust_level,
+        ClockCheck::At {
+            now,
+            max_clock_drift,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use celestia_tendermint::Hash;
+
+    const TRUSTING_PERIOD: Duration = Duration::from_secs(14 * 24 * 60 * 60);
+
+    fn verify_skipping_at(trusted: &ExtendedHeader, target: &ExtendedHeader) -> Result<()> {
+        let now = target.time().checked_add(Duration::from_secs(1)).unwrap();
+        verify_skipping(trusted, target, TRUSTING_PERIOD, now)
+    }
+
+    fn sample_eh_chain_2_block_1() -> ExtendedHeader {
+        let s = include_str!("../test_data/chain2/extended_header_block_1.json");
+        serde_json::from_str(s).unwrap()
+    }
+
+    fn sample_eh_chain_3_block_1_to_256() -> Vec<ExtendedHeader> {
+        let s = include_str!("../test_data/chain3/extended_header_block_1_to_256.json");
+        serde_json::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn verify_skipping_headers() {
+        let headers = sample_eh_chain_3_block_1_to_256();
+
+        verify_skipping_at(&headers[0], &headers[100]).unwrap();
+        verify_skipping_at(&headers[100], &headers[101]).unwrap();
+        verify_skipping_at(&headers[0], &headers[255]).unwrap();
+    }
+
+    #[test]
+    fn verify_skipping_backwards() {
+        let headers = sample_eh_chain_3_block_1_to_256();
+
+        verify_skipping_at(&headers[100], &headers[0]).unwrap_err();
+        verify_skipping_at(&headers[100], &headers[100]).unwrap_err();
+    }
+
+    #[test]
+    fn verify_skipping_different_chain() {
+        let headers = sample_eh_chain_3_block_1_to_256();
+        let other_chain = sample_eh_chain_2_block_1();
+
+        verify_skipping_at(&other_chain, &headers[100]).unwrap_err();
+    }
+
+    #[test]
+    fn verify_skipping_tampered_target() {
+        let headers = sample_eh_chain_3_block_1_to_256();
+
+        let mut target = headers[100].clone();
+        target.commit.block_id.hash = Hash::None;
+        verify_skipping_at(&headers[0], &target).unwrap_err();
+
+        let mut target = headers[100].clone();
+        target.header.validators_hash = Hash::None;
+        verify_skipping_at(&headers[0], &target).unwrap_err();
+    }
+
+    #[test]
+    fn verify_skipping_from_expired_header() {
+        let headers = sample_eh_chain_3_block_1_to_256();
+        let trusted = &headers[0];
+        let target = &headers[100];
+
+        let expires = trusted.time().checked_add(TRUSTING_PERIOD).unwrap();
+        let just_before = expires.checked_sub(Duration::from_secs(1)).unwrap();
+        verify_skipping(trusted, target, TRUSTING_PERIOD, just_before).unwrap();
+
+        verify_skipping(trusted, target, TRUSTING_PERIOD, expires).unwrap_err();
+        let later = expires.checked_add(Duration::from_secs(1)).unwrap();
+        verify_skipping(trusted, target, TRUSTING_PERIOD, later).unwrap_err();
+    }
+
+    #[test]
+    fn verify_skipping_to_header_from_the_future() {
+        let headers = sample_eh_chain_3_block_1_to_256();
+        let trusted = &headers[0];
+        let target = &headers[100];
+
+        let now = target
+            .time()
+            .checked_sub(DEFAULT_MAX_CLOCK_DRIFT + Duration::from_secs(1))
+            .unwrap();
+        verify_skipping(trusted, target, TRUSTING_PERIOD, now).unwrap_err();
+
+        // skewed clock is tolerated with a larger drift
+        verify_skipping_with_trust_level(
+            trusted,
+            target,
+            TRUSTING_PERIOD,
+            now,
+            DEFAULT_MAX_CLOCK_DRIFT + Duration::from_secs(2),
+            DEFAULT_TRUST_LEVEL,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn verify_skipping_with_unreachable_trust_level() {
+        let headers = sample_eh_chain_3_block_1_to_256();
+
+        // more than the whole voting power
+        let trust_level = TrustLevelRatio::new(3, 2);
+        verify_skipping_with_trust_level(
+            &headers[0],
+            &headers[100],
+            TRUSTING_PERIOD,
+            headers[100].time(),
+            DEFAULT_MAX_CLOCK_DRIFT,
+            trust_level,
+        )
+        .unwrap_err();
+    }
+}
@@ -7,15 +7,15 @@
 //! [`ExtendedDataSquare`]: crate::rsmt2d::ExtendedDataSquare
 
 use blockstore::block::CidError;
-use bytes::{BufMut, BytesMut};
+use bytes::{BufMut, Bytes, BytesMut};
 use celestia_proto::share::p2p::shwap::Data as RawNamespacedData;
 use celestia_tendermint_proto::Protobuf;
 use cid::CidGeneric;
 use multihash::Multihash;
 use serde::{Deserialize, Serialize};
 
-use crate::nmt::{Namespace, NamespaceProof, NS_SIZE};
-use crate::row::RowId;
+use crate::nmt::{Namespace, NamespaceProof, EMPTY_LEAVES, NS_SIZE};
+use crate::row::{IdLayout, RowId};
 use crate::{DataAvailabilityHeader, Error, Result};
 
 /// The size of the [`NamespacedDataId`] hash in `multihash`.
@@ -54,14 +54,32 @@ pub struct NamespacedData {
     /// Proof of data inclusion
     pub proof: NamespaceProof,
     /// Shares with data
-    pub shares: Vec<Vec<u8>>,
+    pub shares: Vec<Bytes>,
+}
+
+/// Outcome of the successful validation of the [`NamespacedData`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Presence<'a> {
+    /// The row contains the shares of the namespace and their inclusion was proven.
+    Present(&'a [Bytes]),
+    /// The row has no shares of the namespace, proven by the [`NamespaceProof`] of absence.
+    Absent(&'a NamespaceProof),
 }
 
 impl NamespacedData {
     /// Verifies proof inside `NamespacedData` using a row root from [`DataAvailabilityHeader`]
     ///
+    /// Empty shares are accepted together with a proof of absence, which shows that the
+    /// namespace has no shares in the row.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::WrongProofType`] if the proof is of absence while there are shares
+    /// of the namespace, or the other way around.
+    ///
     /// #Example
     /// ```no_run
+    /// use celestia_types::namespaced_data::Presence;
     /// use celestia_types::nmt::Namespace;
     /// # use celestia_types::{ExtendedDataSquare, ExtendedHeader};
     /// # fn get_extended_data_square(height: usize) -> ExtendedDataSquare {
@@ -79,13 +97,16 @@ impl NamespacedData {
     ///
     /// let rows = eds.get_namespaced_data(namespace, &header.dah, block_height as u64).unwrap();
     /// for namespaced_data in rows {
-    ///     namespaced_data.validate(&header.dah).unwrap()
+    ///     match namespaced_data.validate(&header.dah).unwrap() {
+    ///         Presence::Present(shares) => println!("{} shares", shares.len()),
+    ///         Presence::Absent(_) => println!("nothing was posted"),
+    ///     }
     /// }
     /// ```
     ///
     /// [`DataAvailabilityHeader`]: crate::DataAvailabilityHeader
-    pub fn validate(&self, dah: &DataAvailabilityHeader) -> Result<()> {
-        if self.shares.is_empty() {
+    pub fn validate(&self, dah: &DataAvailabilityHeader) -> Result<Presence<'_>> {
+        if self.shares.is_empty() != self.proof.is_of_absence() {
             return Err(Error::WrongProofType);
         }
 
@@ -96,23 +117,35 @@ impl NamespacedData {
             .row_root(row.into())
             .ok_or(Error::EdsIndexOutOfRange(row.into()))?;
 
-        self.proof
-            .verify_complete_namespace(&root, &self.shares, *namespace)
-            .map_err(Error::RangeProofError)
+        if self.proof.is_of_absence() {
+            self.proof
+                .verify_complete_namespace(&root, EMPTY_LEAVES, *namespace)
+                .map_err(Error::RangeProofError)?;
+
+            Ok(Presence::Absent(&self.proof))
+        } else {
+            self.proof
+                .verify_complete_namespace(&root, &self.shares, *namespace)
+                .map_err(Error::RangeProofError)?;
+
+            Ok(Presence::Present(&self.shares))
+        }
     }
 }
 
 impl Protobuf<RawNamespacedData> for NamespacedData {}
 
-impl TryFrom<RawNamespacedData> for NamespacedData {
-    type Error = Error;
-
-    fn try_from(namespaced_data: RawNamespacedData) -> Result<NamespacedData, Self::Error> {
+impl NamespacedData {
+    /// Decode the [`NamespacedData`] from its protobuf representation with the given [`IdLayout`].
+    pub fn from_raw(
+        namespaced_data: RawNamespacedData,
+        layout: IdLayout,
+    ) -> Result<NamespacedData> {
         let Some(proof) = namespaced_data.data_proof else {
             return Err(Error::MissingProof);
         };
 
-        let namespaced_data_id = NamespacedDataId::decode(&namespaced_data.data_id)?;
+        let namespaced_data_id = NamespacedDataId::decode(&namespaced_data.data_id, layout)?;
 
         Ok(NamespacedData {
             namespaced_data_id,
@@ -120,23 +153,34 @@ impl TryFrom<RawNamespacedData> for NamespacedData {
             proof: proof.try_into()?,
         })
     }
-}
 
-impl From<NamespacedData> for RawNamespacedData {
-    fn from(namespaced_data: NamespacedData) -> RawNamespacedData {
+    /// Convert the [`NamespacedData`] to its protobuf representation with the given [`IdLayout`].
+    pub fn into_raw(self, layout: IdLayout) -> RawNamespacedData {
         let mut data_id_bytes = BytesMut::new();
-        namespaced_data
-            .namespaced_data_id
-            .encode(&mut data_id_bytes);
+        self.namespaced_data_id.encode(&mut data_id_bytes, layout);
 
         RawNamespacedData {
             data_id: data_id_bytes.to_vec(),
-            data_shares: namespaced_data.shares.iter().map(|s| s.to_vec()).collect(),
-            data_proof: Some(namespaced_data.proof.into()),
+            data_shares: self.shares,
+            data_proof: Some(self.proof.into()),
         }
     }
 }
 
+impl TryFrom<RawNamespacedData> for NamespacedData {
+    type Error = Error;
+
+    fn try_from(namespaced_data: RawNamespacedData) -> Result<NamespacedData, Self::Error> {
+        NamespacedData::from_raw(namespaced_data, IdLayout::default())
+    }
+}
+
+impl From<NamespacedData> for RawNamespacedData {
+    fn from(namespaced_data: NamespacedData) -> RawNamespacedData {
+        namespaced_data.into_raw(IdLayout::default())
+    }
+}
+
 impl NamespacedDataId {
     /// Create a new [`NamespacedDataId`] for given block, row and the [`Namespace`].
     ///
@@ -163,30 +207,23 @@ impl NamespacedDataId {
         RowId::size() + NS_SIZE
     }
 
-    fn encode(&self, bytes: &mut BytesMut) {
-        self.row.encode(bytes);
-        bytes.put(self.namespace.as_bytes());
-    }
-
-    fn decode(buffer: &[u8]) -> Result<Self, CidError> {
-        if buffer.len() != NAMESPACED_DATA_ID_SIZE {
-            return Err(CidError::InvalidMultihashLength(buffer.len()));
-        }
-
-        let (row_id, namespace) = buffer.split_at(RowId::size());
+    /// Convert the [`NamespacedDataId`] to the `Cid` of the given [`IdLayout`].
+    ///
+    /// The codecs of the [`NamespacedDataId`] are the same in all the layouts.
+    pub fn to_cid(&self, layout: IdLayout) -> CidGeneric<NAMESPACED_DATA_ID_SIZE> {
+        let mut bytes = BytesMut::with_capacity(NAMESPACED_DATA_ID_SIZE);
+        self.encode(&mut bytes, layout);
+        // length is correct, so the unwrap is safe
+        let mh = Multihash::wrap(NAMESPACED_DATA_ID_MULTIHASH_CODE, &bytes[..]).unwrap();
 
-        Ok(Self {
-            row: RowId::decode(row_id)?,
-            namespace: Namespace::from_raw(namespace)
-                .map_err(|e| CidError::InvalidCid(e.to_string()))?,
-        })
+        CidGeneric::new_v1(NAMESPACED_DATA_ID_CODEC, mh)
     }
-}
 
-impl<const S: usize> TryFrom<CidGeneric<S>> for NamespacedDataId {
-    type Error = CidError;
-
-    fn try_from(cid: CidGeneric<S>) -> Result<Self, Self::Error> {
+    /// Decode the [`NamespacedDataId`] from the `Cid` of the given [`IdLayout`].
+    pub fn from_cid<const S: usize>(
+        cid: &CidGeneric<S>,
+        layout: IdLayout,
+    ) -> Result<Self, CidError> {
         let codec = cid.codec();
         if codec != NAMESPACED_DATA_ID_CODEC {
             return Err(CidError::InvalidCidCodec(codec));
@@ -207,7 +244,34 @@ impl<const S: usize> TryFrom<CidGeneric<S>> for NamespacedDataId {
             ));
         }
 
-        NamespacedDataId::decode(hash.digest())
+        NamespacedDataId::decode(hash.digest(), layout)
+    }
+
+    fn encode(&self, bytes: &mut BytesMut, layout: IdLayout) {
+        self.row.encode(bytes, layout);
+        bytes.put(self.namespace.as_bytes());
+    }
+
+    fn decode(buffer: &[u8], layout: IdLayout) -> Result<Self, CidError> {
+        if buffer.len() != NAMESPACED_DATA_ID_SIZE {
+            return Err(CidError::InvalidMultihashLength(buffer.len()));
+        }
+
+        let (row_id, namespace) = buffer.split_at(RowId::size());
+
+        Ok(Self {
+            row: RowId::decode(row_id, layout)?,
+            namespace: Namespace::from_raw(namespace)
+                .map_err(|e| CidError::InvalidCid(e.to_string()))?,
+        })
+    }
+}
+
+impl<const S: usize> TryFrom<CidGeneric<S>> for NamespacedDataId {
+    type Error = CidError;
+
+    fn try_from(cid: CidGeneric<S>) -> Result<Self, Self::Error> {
+        NamespacedDataId::from_cid(&cid, IdLayout::default())
     }
 }
 
@@ -215,31 +279,37 @@ impl TryFrom<NamespacedDataId> for CidGeneric<NAMESPACED_DATA_ID_SIZE> {
     type Error = CidError;
 
     fn try_from(namespaced_data_id: NamespacedDataId) -> Result<Self, Self::Error> {
-        let mut bytes = BytesMut::with_capacity(NAMESPACED_DATA_ID_SIZE);
-        namespaced_data_id.encode(&mut bytes);
-        // length is correct, so the unwrap is safe
-        let mh = Multihash::wrap(NAMESPACED_DATA_ID_MULTIHASH_CODE, &bytes[..]).unwrap();
-
-        Ok(CidGeneric::new_v1(NAMESPACED_DATA_ID_CODEC, mh))
+        Ok(namespaced_data_id.to_cid(IdLayout::default()))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::Share;
+    use crate::{ExtendedDataSquare, Share};
+    use prost::Message;
+
+    fn test_eds_and_dah() -> (ExtendedDataSquare, DataAvailabilityHeader) {
+        let eds_json = include_str!("../test_data/shwap_samples/eds.json");
+        let dah_json = include_str!("../test_data/shwap_samples/dah.json");
+
+        (
+            serde_json::from_str(eds_json).unwrap(),
+            serde_json::from_str(dah_json).unwrap(),
+        )
+    }
 
     #[test]
     fn round_trip() {
         let ns = Namespace::new_v0(&[0, 1]).unwrap();
         let data_id = NamespacedDataId::new(ns, 5, 100).unwrap();
-        let cid = CidGeneric::try_from(data_id).unwrap();
+        let cid = data_id.to_cid(IdLayout::V1);
 
         let multihash = cid.hash();
         assert_eq!(multihash.code(), NAMESPACED_DATA_ID_MULTIHASH_CODE);
         assert_eq!(multihash.size(), NAMESPACED_DATA_ID_SIZE as u8);
 
-        let deserialized_data_id = NamespacedDataId::try_from(cid).unwrap();
+        let deserialized_data_id = NamespacedDataId::from_cid(&cid, IdLayout::V1).unwrap();
         assert_eq!(data_id, deserialized_data_id);
     }
 
@@ -250,8 +320,8 @@ mod tests {
             0xA0, 0xF0, 0x01, // CID codec = 7820
             0xA1, 0xF0, 0x01, // multihash code = 7821
             0x27, // len = NAMESPACED_DATA_ID_SIZE = 39
-            64, 0, 0, 0, 0, 0, 0, 0, // block height = 64
-            7, 0, // row = 7
+            0, 0, 0, 0, 0, 0, 0, 64, // block height = 64
+            0, 7, // row = 7
             0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
             1, // NS = 1
         ];
@@ -261,10 +331,31 @@ mod tests {
         let mh = cid.hash();
         assert_eq!(mh.code(), NAMESPACED_DATA_ID_MULTIHASH_CODE);
         assert_eq!(mh.size(), NAMESPACED_DATA_ID_SIZE as u8);
-        let data_id = NamespacedDataId::try_from(cid).unwrap();
+        let data_id = NamespacedDataId::from_cid(&cid, IdLayout::V1).unwrap();
+        assert_eq!(data_id.namespace, Namespace::new_v0(&[1]).unwrap());
+        assert_eq!(data_id.row.block_height, 64);
+        assert_eq!(data_id.row.index, 7);
+    }
+
+    #[test]
+    fn from_buffer_draft_layout() {
+        let bytes = [
+            0x01, // CIDv1
+            0xA0, 0xF0, 0x01, // CID codec = 7820
+            0xA1, 0xF0, 0x01, // multihash code = 7821
+            0x27, // len = NAMESPACED_DATA_ID_SIZE = 39
+            64, 0, 0, 0, 0, 0, 0, 0, // block height = 64
+            7, 0, // row = 7
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            1, // NS = 1
+        ];
+
+        let cid = CidGeneric::<NAMESPACED_DATA_ID_SIZE>::read_bytes(bytes.as_ref()).unwrap();
+        let data_id = NamespacedDataId::from_cid(&cid, IdLayout::Draft).unwrap();
         assert_eq!(data_id.namespace, Namespace::new_v0(&[1]).unwrap());
         assert_eq!(data_id.row.block_height, 64);
         assert_eq!(data_id.row.index, 7);
+        assert_eq!(data_id.to_cid(IdLayout::Draft), cid);
     }
 
     #[test]
@@ -273,7 +364,7 @@ mod tests {
             Multihash::<NAMESPACED_DATA_ID_SIZE>::wrap(888, &[0; NAMESPACED_DATA_ID_SIZE]).unwrap();
         let cid =
             CidGeneric::<NAMESPACED_DATA_ID_SIZE>::new_v1(NAMESPACED_DATA_ID_CODEC, multihash);
-        let axis_err = NamespacedDataId::try_from(cid).unwrap_err();
+        let axis_err = NamespacedDataId::from_cid(&cid, IdLayout::V1).unwrap_err();
         assert_eq!(
             axis_err,
             CidError::InvalidMultihashCode(888, NAMESPACED_DATA_ID_MULTIHASH_CODE)
@@ -288,14 +379,15 @@ mod tests {
         )
         .unwrap();
         let cid = CidGeneric::<NAMESPACED_DATA_ID_SIZE>::new_v1(4321, multihash);
-        let axis_err = NamespacedDataId::try_from(cid).unwrap_err();
+        let axis_err = NamespacedDataId::from_cid(&cid, IdLayout::V1).unwrap_err();
         assert_eq!(axis_err, CidError::InvalidCidCodec(4321));
     }
 
     #[test]
     fn decode_data_bytes() {
         let bytes = include_bytes!("../test_data/shwap_samples/namespaced_data.data");
-        let msg = NamespacedData::decode(&bytes[..]).unwrap();
+        let raw = RawNamespacedData::decode(&bytes[..]).unwrap();
+        let msg = NamespacedData::from_raw(raw, IdLayout::Draft).unwrap();
 
         let ns = Namespace::new_v0(&[135, 30, 47, 81, 60, 66, 177, 20, 57, 85]).unwrap();
         assert_eq!(msg.namespaced_data_id.namespace, ns);
@@ -307,4 +399,53 @@ mod tests {
             assert_eq!(s.namespace(), ns);
         }
     }
+
+    #[test]
+    fn validate_presence() {
+        let (eds, dah) = test_eds_and_dah();
+        let namespace = Namespace::new_v0(&[1, 187]).unwrap();
+
+        for data in eds.get_namespaced_data(namespace, &dah, 45577).unwrap() {
+            assert_eq!(
+                data.validate(&dah).unwrap(),
+                Presence::Present(&data.shares)
+            );
+        }
+    }
+
+    #[test]
+    fn validate_absence() {
+        let (eds, dah) = test_eds_and_dah();
+        // namespace in the middle of the range of the row
+        let namespace = Namespace::new_v0(&[1, 171]).unwrap();
+
+        let rows = eds.get_namespaced_data(namespace, &dah, 45577).unwrap();
+        assert!(!rows.is_empty());
+
+        for data in rows {
+            assert!(data.shares.is_empty());
+            assert_eq!(data.validate(&dah).unwrap(), Presence::Absent(&data.proof));
+        }
+    }
+
+    #[test]
+    fn validate_wrong_proof_type() {
+        let (eds, dah) = test_eds_and_dah();
+        let present = Namespace::new_v0(&[1, 187]).unwrap();
+        let absent = Namespace::new_v0(&[1, 171]).unwrap();
+
+        let mut data = eds.get_namespaced_data(absent, &dah, 45577).unwrap()[0].clone();
+        data.shares.push(Bytes::from(vec![0; 512]));
+        assert!(matches!(
+            data.validate(&dah).unwrap_err(),
+            Error::WrongProofType
+        ));
+
+        let mut data = eds.get_namespaced_data(present, &dah, 45577).unwrap()[0].clone();
+        data.shares.clear();
+        assert!(matches!(
+            data.validate(&dah).unwrap_err(),
+            Error::WrongProofType
+        ));
+    }
 }
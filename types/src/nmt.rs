@@ -17,24 +17,35 @@
 //!
 //! [`nmt-rs`]: https://github.com/sovereign-labs/nmt-rs
 
-use base64::prelude::*;
+use core::fmt;
+use core::str::FromStr;
+
+#[cfg(feature = "std")]
 use blockstore::block::CidError;
 use celestia_tendermint::hash::SHA256_HASH_SIZE;
-use celestia_tendermint_proto::serializers::cow_str::CowStr;
+#[cfg(feature = "std")]
 use cid::CidGeneric;
+#[cfg(feature = "std")]
 use multihash::Multihash;
 use nmt_rs::simple_merkle::db::MemDb;
 use nmt_rs::simple_merkle::tree::MerkleHash;
 use nmt_rs::NamespaceMerkleHasher;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+
+use crate::serializers::decode_hex;
 
+mod multiproof;
 mod namespace_proof;
 mod namespaced_hash;
+mod root_hasher;
 
+pub use self::multiproof::NamespaceMultiProof;
 pub use self::namespace_proof::{NamespaceProof, EMPTY_LEAVES};
 pub use self::namespaced_hash::{
     NamespacedHashExt, RawNamespacedHash, HASH_SIZE, NAMESPACED_HASH_SIZE,
 };
+pub use self::root_hasher::NmtRootHasher;
 use crate::{Error, Result};
 
 /// Namespace version size in bytes.
@@ -104,7 +115,7 @@ impl Namespace {
     ///
     /// [`Share`]: crate::share::Share
     /// [`MsgPayForBlobs`]: celestia_proto::celestia::blob::v1::MsgPayForBlobs
-    pub const PAY_FOR_BLOB: Namespace = Namespace::const_v0([0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+    pub const PAY_FOR_BLOB: Namespace = Namespace::const_v0([0, 0, 0, 0, 0, 0, 0, 0, 0, 4]);
 
     /// Primary reserved [`Namespace`] for the [`Share`]s used for padding.
     ///
@@ -248,6 +259,60 @@ impl Namespace {
         Ok(Namespace(nmt_rs::NamespaceId(bytes)))
     }
 
+    /// Create a new random [`Namespace`] version `0`.
+    ///
+    /// The id is drawn again until it falls outside of the reserved ranges, so the
+    /// returned namespace can always be used for the blobs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use celestia_types::nmt::Namespace;
+    ///
+    /// let namespace = Namespace::random_v0();
+    /// namespace.validate_for_blob().unwrap();
+    /// ```
+    #[cfg(feature = "rand")]
+    #[cfg_attr(docs_rs, doc(cfg(feature = "rand")))]
+    pub fn random_v0() -> Self {
+        loop {
+            let namespace = Namespace::const_v0(rand::random());
+
+            if !namespace.is_reserved() {
+                return namespace;
+            }
+        }
+    }
+
+    /// Derive a [`Namespace`] version `0` from a human readable identifier.
+    ///
+    /// The user-defined suffix is made of the first 10 bytes of the `Sha256` hash of
+    /// the `id`, so the same identifier always maps to the same namespace.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the derived namespace falls into the
+    /// primary reserved range, which is practically impossible for a hash.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use celestia_types::nmt::Namespace;
+    ///
+    /// let namespace = Namespace::from_string_id("my-rollup").unwrap();
+    ///
+    /// assert_eq!(namespace, Namespace::from_string_id("my-rollup").unwrap());
+    /// namespace.validate_for_blob().unwrap();
+    /// ```
+    pub fn from_string_id(id: &str) -> Result<Self> {
+        let hash = Sha256::digest(id.as_bytes());
+        let namespace = Namespace::new_v0(&hash[..NS_ID_V0_SIZE])?;
+
+        namespace.validate_for_blob()?;
+
+        Ok(namespace)
+    }
+
     pub(crate) const fn new_unchecked(bytes: [u8; NS_SIZE]) -> Self {
         Namespace(nmt_rs::NamespaceId(bytes))
     }
@@ -354,6 +419,107 @@ impl Namespace {
             None
         }
     }
+
+    /// Returns `true` if the [`Namespace`] is in the primary reserved range.
+    ///
+    /// Those are the version `0` namespaces up to the [`MAX_PRIMARY_RESERVED`],
+    /// used for the transactions and the padding placed before the blobs.
+    ///
+    /// [`MAX_PRIMARY_RESERVED`]: Namespace::MAX_PRIMARY_RESERVED
+    pub fn is_primary_reserved(&self) -> bool {
+        *self <= Namespace::MAX_PRIMARY_RESERVED
+    }
+
+    /// Returns `true` if the [`Namespace`] is in the secondary reserved range.
+    ///
+    /// Those are the version `255` namespaces starting from the [`MIN_SECONDARY_RESERVED`],
+    /// including the [`TAIL_PADDING`] and the [`PARITY_SHARE`].
+    ///
+    /// [`MIN_SECONDARY_RESERVED`]: Namespace::MIN_SECONDARY_RESERVED
+    /// [`TAIL_PADDING`]: Namespace::TAIL_PADDING
+    /// [`PARITY_SHARE`]: Namespace::PARITY_SHARE
+    pub fn is_secondary_reserved(&self) -> bool {
+        *self >= Namespace::MIN_SECONDARY_RESERVED
+    }
+
+    /// Returns `true` if the [`Namespace`] is reserved by the protocol.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use celestia_types::nmt::Namespace;
+    ///
+    /// assert!(Namespace::TRANSACTION.is_reserved());
+    /// assert!(Namespace::PARITY_SHARE.is_reserved());
+    /// assert!(!Namespace::new_v0(b"my-ns").unwrap().is_reserved());
+    /// ```
+    pub fn is_reserved(&self) -> bool {
+        self.is_primary_reserved() || self.is_secondary_reserved()
+    }
+
+    /// Returns `true` if it is the [`TRANSACTION`] namespace.
+    ///
+    /// [`TRANSACTION`]: Namespace::TRANSACTION
+    pub fn is_tx(&self) -> bool {
+        *self == Namespace::TRANSACTION
+    }
+
+    /// Returns `true` if it is the [`PAY_FOR_BLOB`] namespace.
+    ///
+    /// [`PAY_FOR_BLOB`]: Namespace::PAY_FOR_BLOB
+    pub fn is_pay_for_blob(&self) -> bool {
+        *self == Namespace::PAY_FOR_BLOB
+    }
+
+    /// Returns `true` if it is the [`PRIMARY_RESERVED_PADDING`] namespace.
+    ///
+    /// [`PRIMARY_RESERVED_PADDING`]: Namespace::PRIMARY_RESERVED_PADDING
+    pub fn is_primary_reserved_padding(&self) -> bool {
+        *self == Namespace::PRIMARY_RESERVED_PADDING
+    }
+
+    /// Returns `true` if it is the [`TAIL_PADDING`] namespace.
+    ///
+    /// [`TAIL_PADDING`]: Namespace::TAIL_PADDING
+    pub fn is_tail_padding(&self) -> bool {
+        *self == Namespace::TAIL_PADDING
+    }
+
+    /// Returns `true` if it is the [`PARITY_SHARE`] namespace.
+    ///
+    /// [`PARITY_SHARE`]: Namespace::PARITY_SHARE
+    pub fn is_parity(&self) -> bool {
+        *self == Namespace::PARITY_SHARE
+    }
+
+    /// Check if the [`Namespace`] can be used for the user submitted blobs.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the namespace isn't a version `0`
+    /// namespace or if it falls in any of the reserved ranges, as such blobs
+    /// are rejected by the celestia-app.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use celestia_types::nmt::Namespace;
+    ///
+    /// assert!(Namespace::new_v0(b"my-ns").unwrap().validate_for_blob().is_ok());
+    /// assert!(Namespace::PAY_FOR_BLOB.validate_for_blob().is_err());
+    /// assert!(Namespace::TAIL_PADDING.validate_for_blob().is_err());
+    /// ```
+    pub fn validate_for_blob(&self) -> Result<()> {
+        if self.is_reserved() {
+            return Err(Error::ReservedNamespace(*self));
+        }
+
+        if self.version() != 0 {
+            return Err(Error::UnsupportedNamespaceVersion(self.version()));
+        }
+
+        Ok(())
+    }
 }
 
 impl From<Namespace> for nmt_rs::NamespaceId<NS_SIZE> {
@@ -368,7 +534,7 @@ impl From<nmt_rs::NamespaceId<NS_SIZE>> for Namespace {
     }
 }
 
-impl std::ops::Deref for Namespace {
+impl core::ops::Deref for Namespace {
     type Target = nmt_rs::NamespaceId<NS_SIZE>;
 
     fn deref(&self) -> &Self::Target {
@@ -376,13 +542,28 @@ impl std::ops::Deref for Namespace {
     }
 }
 
+impl fmt::Display for Namespace {
+    /// Formats the [`Namespace`] as a `0x` prefixed hex string.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{}", hex::encode(self.as_bytes()))
+    }
+}
+
+impl FromStr for Namespace {
+    type Err = Error;
+
+    /// Parses the [`Namespace`] from a hex string, optionally prefixed with `0x`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Namespace::from_raw(&decode_hex(s)?)
+    }
+}
+
 impl Serialize for Namespace {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        let s = BASE64_STANDARD.encode(self.0);
-        serializer.serialize_str(&s)
+        namespace_serde::base64::serialize(self, serializer)
     }
 }
 
@@ -391,22 +572,95 @@ impl<'de> Deserialize<'de> for Namespace {
     where
         D: Deserializer<'de>,
     {
-        // base64 needs more buffer size than the final output
-        let mut buf = [0u8; NS_SIZE * 2];
+        namespace_serde::base64::deserialize(deserializer)
+    }
+}
 
-        let s = CowStr::deserialize(deserializer)?;
+/// Serde representations of the [`Namespace`] for use with `#[serde(with = "...")]`.
+///
+/// ```
+/// use celestia_types::nmt::{namespace_serde, Namespace};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Explorer {
+///     #[serde(with = "namespace_serde::hex")]
+///     namespace: Namespace,
+/// }
+///
+/// let explorer = Explorer { namespace: Namespace::new_v0(&[1, 2]).unwrap() };
+/// let json = serde_json::to_string(&explorer).unwrap();
+///
+/// assert_eq!(json, r#"{"namespace":"0x0000000000000000000000000000000000000000000000000000000102"}"#);
+/// ```
+pub mod namespace_serde {
+    /// Base64 representation, used by celestia-node's JSON RPC. This is the default one.
+    pub mod base64 {
+        use alloc::string::ToString;
+
+        use base64::prelude::*;
+        use celestia_tendermint_proto::serializers::cow_str::CowStr;
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        use crate::nmt::{Namespace, NS_SIZE};
+
+        /// Serialize the [`Namespace`] as a base64 string.
+        pub fn serialize<S>(namespace: &Namespace, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            let s = BASE64_STANDARD.encode(namespace.as_bytes());
+            serializer.serialize_str(&s)
+        }
 
-        let len = BASE64_STANDARD
-            .decode_slice(s, &mut buf)
-            .map_err(|e| serde::de::Error::custom(e.to_string()))?;
+        /// Deserialize the [`Namespace`] from a base64 string.
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<Namespace, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            // base64 needs more buffer size than the final output
+            let mut buf = [0u8; NS_SIZE * 2];
 
-        Namespace::from_raw(&buf[..len]).map_err(|e| serde::de::Error::custom(e.to_string()))
+            let s = CowStr::deserialize(deserializer)?;
+
+            let len = BASE64_STANDARD
+                .decode_slice(s, &mut buf)
+                .map_err(|e| serde::de::Error::custom(e.to_string()))?;
+
+            Namespace::from_raw(&buf[..len]).map_err(|e| serde::de::Error::custom(e.to_string()))
+        }
+    }
+
+    /// Hex representation with the `0x` prefix, as used by the explorers.
+    pub mod hex {
+        use celestia_tendermint_proto::serializers::cow_str::CowStr;
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        use crate::nmt::Namespace;
+
+        /// Serialize the [`Namespace`] as a `0x` prefixed hex string.
+        pub fn serialize<S>(namespace: &Namespace, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            serializer.collect_str(namespace)
+        }
+
+        /// Deserialize the [`Namespace`] from a hex string, optionally prefixed with `0x`.
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<Namespace, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let s = CowStr::deserialize(deserializer)?;
+            s.parse().map_err(serde::de::Error::custom)
+        }
     }
 }
 
 /// A pair of two nodes in the [`Nmt`], usually the siblings.
 pub struct NodePair(NamespacedHash, NamespacedHash);
 
+#[cfg(feature = "std")]
 impl NodePair {
     fn validate_namespace_order(&self) -> Result<()> {
         let NodePair(left, right) = self;
@@ -422,6 +676,7 @@ impl NodePair {
     }
 }
 
+#[cfg(feature = "std")]
 impl TryFrom<NodePair> for CidGeneric<NMT_ID_SIZE> {
     type Error = CidError;
 
@@ -446,6 +701,57 @@ mod tests {
     #[cfg(target_arch = "wasm32")]
     use wasm_bindgen_test::wasm_bindgen_test as test;
 
+    #[test]
+    fn namespace_hex_round_trip() {
+        let ns = Namespace::new_v0(&[0xab, 0xcd]).unwrap();
+        let s = ns.to_string();
+
+        assert_eq!(
+            s,
+            "0x000000000000000000000000000000000000000000000000000000abcd"
+        );
+        assert_eq!(s.parse::<Namespace>().unwrap(), ns);
+        assert_eq!(s[2..].parse::<Namespace>().unwrap(), ns);
+        assert_eq!(
+            "0x000000000000000000000000000000000000000000000000000000ABCD"
+                .parse::<Namespace>()
+                .unwrap(),
+            ns
+        );
+
+        assert!(matches!(
+            "0xzz".parse::<Namespace>(),
+            Err(Error::InvalidHex(_))
+        ));
+        assert!(matches!(
+            "0x00ab".parse::<Namespace>(),
+            Err(Error::InvalidNamespaceSize)
+        ));
+    }
+
+    #[test]
+    fn namespace_serde_representations() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Both {
+            base64: Namespace,
+            #[serde(with = "namespace_serde::hex")]
+            hex: Namespace,
+        }
+
+        let ns = Namespace::new_v0(&[1]).unwrap();
+        let both = Both {
+            base64: ns,
+            hex: ns,
+        };
+
+        let json = serde_json::to_string(&both).unwrap();
+        assert_eq!(
+            json,
+            r#"{"base64":"AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAE=","hex":"0x0000000000000000000000000000000000000000000000000000000001"}"#
+        );
+        assert_eq!(serde_json::from_str::<Both>(&json).unwrap(), both);
+    }
+
     #[test]
     fn namespace_id_8_bytes() {
         let nid = Namespace::new_v0(&[1, 2, 3, 4, 5, 6, 7, 8]).unwrap();
@@ -557,6 +863,80 @@ mod tests {
         assert!(matches!(e, Error::InvalidNamespaceSize));
     }
 
+    #[test]
+    fn reserved_namespaces() {
+        for ns in [
+            Namespace::TRANSACTION,
+            Namespace::PAY_FOR_BLOB,
+            Namespace::PRIMARY_RESERVED_PADDING,
+            Namespace::new_v0(&[0]).unwrap(),
+            Namespace::new_v0(&[0x80]).unwrap(),
+        ] {
+            assert!(ns.is_primary_reserved());
+            assert!(!ns.is_secondary_reserved());
+            assert!(ns.is_reserved());
+            assert!(matches!(
+                ns.validate_for_blob().unwrap_err(),
+                Error::ReservedNamespace(n) if n == ns
+            ));
+        }
+
+        for ns in [
+            Namespace::MIN_SECONDARY_RESERVED,
+            Namespace::TAIL_PADDING,
+            Namespace::PARITY_SHARE,
+        ] {
+            assert!(!ns.is_primary_reserved());
+            assert!(ns.is_secondary_reserved());
+            assert!(ns.is_reserved());
+            assert!(ns.validate_for_blob().is_err());
+        }
+
+        for ns in [
+            Namespace::new_v0(&[1, 0]).unwrap(),
+            Namespace::new_v0(&[0xff; NS_ID_V0_SIZE]).unwrap(),
+        ] {
+            assert!(!ns.is_reserved());
+            ns.validate_for_blob().unwrap();
+        }
+    }
+
+    #[test]
+    fn namespace_from_string_id() {
+        let namespace = Namespace::from_string_id("my-rollup").unwrap();
+        let hash = Sha256::digest(b"my-rollup");
+
+        assert_eq!(namespace.version(), 0);
+        assert_eq!(namespace.id_v0().unwrap(), &hash[..NS_ID_V0_SIZE]);
+        assert_eq!(namespace, Namespace::from_string_id("my-rollup").unwrap());
+        assert_ne!(namespace, Namespace::from_string_id("my-rollup-2").unwrap());
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn random_namespaces() {
+        let namespaces: Vec<_> = (0..100).map(|_| Namespace::random_v0()).collect();
+
+        for ns in &namespaces {
+            assert_eq!(ns.version(), 0);
+            ns.validate_for_blob().unwrap();
+        }
+        assert_ne!(namespaces[0], namespaces[1]);
+    }
+
+    #[test]
+    fn special_namespaces_predicates() {
+        assert!(Namespace::TRANSACTION.is_tx());
+        assert!(Namespace::PAY_FOR_BLOB.is_pay_for_blob());
+        assert!(Namespace::PRIMARY_RESERVED_PADDING.is_primary_reserved_padding());
+        assert!(Namespace::TAIL_PADDING.is_tail_padding());
+        assert!(Namespace::PARITY_SHARE.is_parity());
+
+        assert!(!Namespace::TRANSACTION.is_pay_for_blob());
+        assert!(!Namespace::TAIL_PADDING.is_parity());
+        assert!(!Namespace::PARITY_SHARE.is_tail_padding());
+    }
+
     #[test]
     fn namespace_id_v255_too_long() {
         let e = Namespace::new_v255(&[0xff; 29]).unwrap_err();
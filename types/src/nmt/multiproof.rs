@@ -0,0 +1,408 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use nmt_rs::simple_merkle::error::RangeProofError;
+use nmt_rs::simple_merkle::tree::MerkleHash;
+use nmt_rs::NamespaceMerkleHasher;
+use serde::{Deserialize, Serialize};
+
+use crate::nmt::{Namespace, NamespacedHash, NamespacedHashExt, NamespacedSha2Hasher};
+use crate::{Error, Result};
+
+/// Merkle proof of inclusion of an arbitrary set of leaves in the [`Nmt`].
+///
+/// Unlike the [`NamespaceProof`], which covers a contiguous range of leaves,
+/// the multiproof covers any set of them. Nodes shared between the paths of
+/// the proven leaves are included only once, so proving many leaves of the same
+/// tree takes much less space than a separate proof for each of them.
+///
+/// # Example
+///
+/// ```
+/// use nmt_rs::NamespaceMerkleHasher;
+/// use celestia_types::nmt::{
+///     Namespace, NamespaceMultiProof, NamespaceProof, NamespacedSha2Hasher, Nmt,
+/// };
+///
+/// let ns = Namespace::new_v0(&[1]).unwrap();
+/// let leaves: Vec<_> = (0..8u8).map(|i| vec![i; 8]).collect();
+///
+/// let hasher = NamespacedSha2Hasher::with_ignore_max_ns(true);
+/// let mut nmt = Nmt::with_hasher(hasher.clone());
+/// let mut leaf_hashes = Vec::new();
+///
+/// for leaf in &leaves {
+///     nmt.push_leaf(leaf, *ns).unwrap();
+///     leaf_hashes.push(hasher.hash_leaf_with_namespace(leaf, *ns));
+/// }
+///
+/// let proof = NamespaceMultiProof::from_leaf_hashes(&leaf_hashes, &[1, 4, 5], true).unwrap();
+/// let proven = [(ns, &leaves[1][..]), (ns, &leaves[4]), (ns, &leaves[5])];
+///
+/// NamespaceProof::verify_multi(&proof, &nmt.root(), &proven).unwrap();
+/// ```
+///
+/// [`Nmt`]: crate::nmt::Nmt
+/// [`NamespaceProof`]: crate::nmt::NamespaceProof
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RawNamespaceMultiProof", into = "RawNamespaceMultiProof")]
+pub struct NamespaceMultiProof {
+    tree_size: u32,
+    indices: Vec<u32>,
+    siblings: Vec<NamespacedHash>,
+    ignore_max_ns: bool,
+}
+
+impl NamespaceMultiProof {
+    /// Create the proof of inclusion of the leaves with given indices, from the hashes
+    /// of all the leaves of the tree.
+    ///
+    /// The indices don't need to be sorted and duplicates are ignored.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if no indices are given, any of them is
+    /// out of the tree or the leaves aren't ordered by their namespaces.
+    pub fn from_leaf_hashes(
+        leaf_hashes: &[NamespacedHash],
+        indices: &[u32],
+        ignore_max_ns: bool,
+    ) -> Result<Self> {
+        let tree_size = u32::try_from(leaf_hashes.len())
+            .map_err(|_| Error::RangeProofError(RangeProofError::TreeTooLarge))?;
+
+        let mut indices = indices.to_vec();
+        indices.sort_unstable();
+        indices.dedup();
+
+        match indices.last() {
+            None => return Err(Error::RangeProofError(RangeProofError::NoLeavesProvided)),
+            Some(&last) if last >= tree_size => {
+                return Err(Error::RangeProofError(
+                    RangeProofError::TreeDoesNotContainLeaf,
+                ))
+            }
+            _ => {}
+        }
+
+        if leaf_hashes
+            .windows(2)
+            .any(|pair| pair[0].max_namespace() > pair[1].min_namespace())
+        {
+            return Err(Error::RangeProofError(RangeProofError::MalformedTree));
+        }
+
+        let hasher = NamespacedSha2Hasher::with_ignore_max_ns(ignore_max_ns);
+        let mut siblings = Vec::new();
+        collect_siblings(&hasher, leaf_hashes, 0, &indices, &mut siblings);
+
+        Ok(NamespaceMultiProof {
+            tree_size,
+            indices,
+            siblings,
+            ignore_max_ns,
+        })
+    }
+
+    /// Number of leaves in the tree.
+    pub fn tree_size(&self) -> u32 {
+        self.tree_size
+    }
+
+    /// Sorted indices of the proven leaves.
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    /// Hashes of the subtrees not containing any of the proven leaves, in the order
+    /// of the depth-first traversal of the tree.
+    pub fn siblings(&self) -> &[NamespacedHash] {
+        &self.siblings
+    }
+
+    /// Returns true if the proof ignores all the leaves inserted with
+    /// [`Namespace::PARITY_SHARE`].
+    pub fn max_ns_ignored(&self) -> bool {
+        self.ignore_max_ns
+    }
+
+    /// Compute the root of the tree from the proof and the proven leaves.
+    pub(crate) fn compute_root<L>(&self, leaves: &[(Namespace, L)]) -> Result<NamespacedHash>
+    where
+        L: AsRef<[u8]>,
+    {
+        if leaves.len() != self.indices.len() {
+            return Err(Error::RangeProofError(
+                RangeProofError::WrongAmountOfLeavesProvided,
+            ));
+        }
+
+        let hasher = NamespacedSha2Hasher::with_ignore_max_ns(self.ignore_max_ns);
+        let indices: Vec<_> = self.indices.iter().map(|&idx| idx as usize).collect();
+        let mut leaf_hashes = leaves
+            .iter()
+            .map(|(ns, data)| hasher.hash_leaf_with_namespace(data.as_ref(), **ns));
+        let mut siblings = self.siblings.iter();
+
+        let root = reconstruct(
+            &hasher,
+            0..self.tree_size as usize,
+            &indices,
+            &mut leaf_hashes,
+            &mut siblings,
+        )
+        .map_err(Error::RangeProofError)?;
+
+        if siblings.next().is_some() {
+            return Err(Error::RangeProofError(RangeProofError::MalformedProof(
+                "unused nodes in the multiproof",
+            )));
+        }
+
+        Ok(root)
+    }
+}
+
+/// Index at which the tree of the given size is split into its subtrees.
+///
+/// The left subtree holds the largest power of two leaves strictly lower than the size.
+fn split_point(size: usize) -> usize {
+    size.next_power_of_two() >> 1
+}
+
+fn subtree_root(hasher: &NamespacedSha2Hasher, leaf_hashes: &[NamespacedHash]) -> NamespacedHash {
+    match leaf_hashes.len() {
+        0 => NamespacedHash::empty_root(),
+        1 => leaf_hashes[0].clone(),
+        size => {
+            let (left, right) = leaf_hashes.split_at(split_point(size));
+            hasher.hash_nodes(&subtree_root(hasher, left), &subtree_root(hasher, right))
+        }
+    }
+}
+
+fn collect_siblings(
+    hasher: &NamespacedSha2Hasher,
+    leaf_hashes: &[NamespacedHash],
+    offset: u32,
+    indices: &[u32],
+    siblings: &mut Vec<NamespacedHash>,
+) {
+    if indices.is_empty() {
+        siblings.push(subtree_root(hasher, leaf_hashes));
+        return;
+    }
+
+    if leaf_hashes.len() == 1 {
+        return;
+    }
+
+    let split = split_point(leaf_hashes.len());
+    let mid = offset + split as u32;
+    let (left_indices, right_indices) = indices.split_at(indices.partition_point(|&i| i < mid));
+    let (left, right) = leaf_hashes.split_at(split);
+
+    collect_siblings(hasher, left, offset, left_indices, siblings);
+    collect_siblings(hasher, right, mid, right_indices, siblings);
+}
+
+fn reconstruct<'a>(
+    hasher: &NamespacedSha2Hasher,
+    range: Range<usize>,
+    indices: &[usize],
+    leaf_hashes: &mut impl Iterator<Item = NamespacedHash>,
+    siblings: &mut impl Iterator<Item = &'a NamespacedHash>,
+) -> Result<NamespacedHash, RangeProofError> {
+    if indices.is_empty() {
+        return siblings
+            .next()
+            .cloned()
+            .ok_or(RangeProofError::MissingProofNode);
+    }
+
+    if range.len() == 1 {
+        return leaf_hashes
+            .next()
+            .ok_or(RangeProofError::WrongAmountOfLeavesProvided);
+    }
+
+    let mid = range.start + split_point(range.len());
+    let (left_indices, right_indices) = indices.split_at(indices.partition_point(|&i| i < mid));
+
+    let left = reconstruct(
+        hasher,
+        range.start..mid,
+        left_indices,
+        leaf_hashes,
+        siblings,
+    )?;
+    let right = reconstruct(hasher, mid..range.end, right_indices, leaf_hashes, siblings)?;
+
+    if left.max_namespace() > right.min_namespace() {
+        return Err(RangeProofError::MalformedTree);
+    }
+
+    Ok(hasher.hash_nodes(&left, &right))
+}
+
+/// Serialized form of the [`NamespaceMultiProof`].
+#[derive(Serialize, Deserialize)]
+struct RawNamespaceMultiProof {
+    tree_size: u32,
+    indices: Vec<u32>,
+    #[serde(with = "celestia_tendermint_proto::serializers::bytes::vec_base64string")]
+    nodes: Vec<Vec<u8>>,
+    is_max_namespace_ignored: bool,
+}
+
+impl TryFrom<RawNamespaceMultiProof> for NamespaceMultiProof {
+    type Error = Error;
+
+    fn try_from(value: RawNamespaceMultiProof) -> Result<Self, Self::Error> {
+        let siblings = value
+            .nodes
+            .iter()
+            .map(|bytes| NamespacedHash::from_raw(bytes))
+            .collect::<Result<Vec<_>>>()?;
+
+        if value.indices.is_empty() {
+            return Err(Error::RangeProofError(RangeProofError::NoLeavesProvided));
+        }
+
+        let sorted = value.indices.windows(2).all(|pair| pair[0] < pair[1]);
+        let in_tree = value.indices.last().is_some_and(|&i| i < value.tree_size);
+
+        if !sorted || !in_tree {
+            return Err(Error::RangeProofError(RangeProofError::MalformedProof(
+                "multiproof indices must be sorted, unique and within the tree",
+            )));
+        }
+
+        Ok(NamespaceMultiProof {
+            tree_size: value.tree_size,
+            indices: value.indices,
+            siblings,
+            ignore_max_ns: value.is_max_namespace_ignored,
+        })
+    }
+}
+
+impl From<NamespaceMultiProof> for RawNamespaceMultiProof {
+    fn from(value: NamespaceMultiProof) -> Self {
+        RawNamespaceMultiProof {
+            tree_size: value.tree_size,
+            indices: value.indices,
+            nodes: value.siblings.iter().map(|hash| hash.to_vec()).collect(),
+            is_max_namespace_ignored: value.ignore_max_ns,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nmt::{NamespaceProof, Nmt};
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    type Leaves = Vec<(Namespace, Vec<u8>)>;
+
+    fn tree(size: u8) -> (Nmt, Leaves, Vec<NamespacedHash>) {
+        let hasher = NamespacedSha2Hasher::with_ignore_max_ns(true);
+        let mut nmt = Nmt::with_hasher(hasher.clone());
+        let mut leaves = Vec::new();
+        let mut leaf_hashes = Vec::new();
+
+        for i in 0..size {
+            let ns = Namespace::new_v0(&[i / 2 + 1]).unwrap();
+            let data = vec![i; 16];
+
+            nmt.push_leaf(&data, *ns).unwrap();
+            leaf_hashes.push(hasher.hash_leaf_with_namespace(&data, *ns));
+            leaves.push((ns, data));
+        }
+
+        (nmt, leaves, leaf_hashes)
+    }
+
+    fn proven(leaves: &[(Namespace, Vec<u8>)], indices: &[u32]) -> Leaves {
+        indices
+            .iter()
+            .map(|&i| leaves[i as usize].clone())
+            .collect()
+    }
+
+    #[test]
+    fn verify_any_set_of_leaves() {
+        for size in [1, 2, 5, 8, 13] {
+            let (mut nmt, leaves, leaf_hashes) = tree(size);
+            let root = nmt.root();
+
+            for indices in [vec![0], vec![size as u32 - 1], (0..size as u32).collect()] {
+                let proof =
+                    NamespaceMultiProof::from_leaf_hashes(&leaf_hashes, &indices, true).unwrap();
+                NamespaceProof::verify_multi(&proof, &root, &proven(&leaves, &indices)).unwrap();
+            }
+        }
+
+        let (mut nmt, leaves, leaf_hashes) = tree(16);
+        let indices = [14, 2, 3, 9, 3];
+        let proof = NamespaceMultiProof::from_leaf_hashes(&leaf_hashes, &indices, true).unwrap();
+
+        assert_eq!(proof.indices(), &[2, 3, 9, 14]);
+        NamespaceProof::verify_multi(&proof, &nmt.root(), &proven(&leaves, proof.indices()))
+            .unwrap();
+    }
+
+    #[test]
+    fn shared_nodes_are_not_duplicated() {
+        let (_, _, leaf_hashes) = tree(16);
+
+        // separate proofs of these leaves would need 4 nodes each
+        let proof =
+            NamespaceMultiProof::from_leaf_hashes(&leaf_hashes, &[0, 1, 2, 3], true).unwrap();
+        assert_eq!(proof.siblings().len(), 2);
+
+        let proof = NamespaceMultiProof::from_leaf_hashes(&leaf_hashes, &[0, 15], true).unwrap();
+        assert_eq!(proof.siblings().len(), 6);
+    }
+
+    #[test]
+    fn invalid_leaves() {
+        let (mut nmt, leaves, leaf_hashes) = tree(8);
+        let root = nmt.root();
+        let proof = NamespaceMultiProof::from_leaf_hashes(&leaf_hashes, &[1, 6], true).unwrap();
+
+        let mut modified = proven(&leaves, &[1, 6]);
+        modified[1].1[0] ^= 1;
+        NamespaceProof::verify_multi(&proof, &root, &modified).unwrap_err();
+
+        // swapped leaves
+        NamespaceProof::verify_multi(&proof, &root, &proven(&leaves, &[6, 1])).unwrap_err();
+
+        // wrong amount of leaves
+        NamespaceProof::verify_multi(&proof, &root, &proven(&leaves, &[1])).unwrap_err();
+    }
+
+    #[test]
+    fn invalid_indices() {
+        let (_, _, leaf_hashes) = tree(8);
+
+        NamespaceMultiProof::from_leaf_hashes(&leaf_hashes, &[], true).unwrap_err();
+        NamespaceMultiProof::from_leaf_hashes(&leaf_hashes, &[8], true).unwrap_err();
+    }
+
+    #[test]
+    fn serde_roundtrip() {
+        let (_, _, leaf_hashes) = tree(8);
+        let proof = NamespaceMultiProof::from_leaf_hashes(&leaf_hashes, &[1, 6], true).unwrap();
+
+        let json = serde_json::to_string(&proof).unwrap();
+        let decoded: NamespaceMultiProof = serde_json::from_str(&json).unwrap();
+        assert_eq!(proof, decoded);
+
+        let json = json.replace("[1,6]", "[6,1]");
+        serde_json::from_str::<NamespaceMultiProof>(&json).unwrap_err();
+    }
+}
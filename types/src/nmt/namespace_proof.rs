@@ -1,11 +1,16 @@
-use std::ops::{Deref, DerefMut};
+use alloc::vec::Vec;
+use core::ops::{Deref, DerefMut};
 
 use celestia_proto::proof::pb::Proof as RawProof;
 use celestia_tendermint_proto::Protobuf;
+use nmt_rs::simple_merkle::error::RangeProofError;
 use nmt_rs::simple_merkle::proof::Proof as NmtProof;
 use serde::{Deserialize, Serialize};
 
-use crate::nmt::{NamespacedHash, NamespacedHashExt, NamespacedSha2Hasher, NS_SIZE};
+use crate::nmt::{
+    Namespace, NamespaceMultiProof, NamespacedHash, NamespacedHashExt, NamespacedSha2Hasher,
+    NS_SIZE,
+};
 use crate::{Error, Result};
 
 type NmtNamespaceProof = nmt_rs::nmt_proof::NamespaceProof<NamespacedSha2Hasher, NS_SIZE>;
@@ -95,6 +100,33 @@ impl NamespaceProof {
             | NmtNamespaceProof::PresenceProof { ignore_max_ns, .. } => *ignore_max_ns,
         }
     }
+
+    /// Verify the [`NamespaceMultiProof`] of inclusion of the given leaves in the tree
+    /// with the given root.
+    ///
+    /// The leaves, together with their [`Namespace`]s, must be given in the order
+    /// of [`NamespaceMultiProof::indices`].
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the amount of leaves doesn't match the proof
+    /// or the root computed from them and the proof is different than the given one.
+    ///
+    /// [`Namespace`]: crate::nmt::Namespace
+    pub fn verify_multi<L>(
+        proof: &NamespaceMultiProof,
+        root: &NamespacedHash,
+        leaves: &[(Namespace, L)],
+    ) -> Result<()>
+    where
+        L: AsRef<[u8]>,
+    {
+        if proof.compute_root(leaves)? != *root {
+            return Err(Error::RangeProofError(RangeProofError::InvalidRoot));
+        }
+
+        Ok(())
+    }
 }
 
 impl Deref for NamespaceProof {
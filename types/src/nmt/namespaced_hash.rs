@@ -1,3 +1,5 @@
+use alloc::vec::Vec;
+
 use crate::nmt::{NamespacedHash, NamespacedSha2Hasher, NS_SIZE};
 use crate::{Error, Result};
 
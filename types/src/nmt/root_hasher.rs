@@ -0,0 +1,144 @@
+use alloc::vec::Vec;
+
+use nmt_rs::simple_merkle::tree::MerkleHash;
+use nmt_rs::NamespaceMerkleHasher;
+
+use crate::nmt::{Namespace, NamespacedHash, NamespacedSha2Hasher};
+use crate::{Error, Result};
+
+/// Incremental hasher computing the root of the [`Nmt`].
+///
+/// Unlike the [`Nmt`], it doesn't keep the pushed leaves, only the roots of
+/// the complete subtrees built so far, so computing a root of `n` leaves requires
+/// `O(log n)` memory. The resulting root is the same as the one of the [`Nmt`]
+/// with the same leaves.
+///
+/// # Example
+///
+/// ```
+/// use celestia_types::nmt::{Namespace, Nmt, NmtRootHasher, NamespacedSha2Hasher};
+/// use nmt_rs::NamespaceMerkleHasher;
+///
+/// let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+/// let mut nmt = Nmt::with_hasher(NamespacedSha2Hasher::with_ignore_max_ns(true));
+/// let mut hasher = NmtRootHasher::new();
+///
+/// for leaf in [b"foo", b"bar"] {
+///     nmt.push_leaf(leaf, *namespace).unwrap();
+///     hasher.push_leaf(leaf, namespace).unwrap();
+/// }
+///
+/// assert_eq!(hasher.root(), nmt.root());
+/// ```
+///
+/// [`Nmt`]: crate::nmt::Nmt
+#[derive(Debug, Clone)]
+pub struct NmtRootHasher {
+    hasher: NamespacedSha2Hasher,
+    /// Roots of the complete subtrees with their heights, strictly decreasing.
+    subtrees: Vec<(u32, NamespacedHash)>,
+    highest_ns: Option<Namespace>,
+}
+
+impl NmtRootHasher {
+    /// Create a new hasher ignoring the [`Namespace::PARITY_SHARE`] in the namespace ranges.
+    pub fn new() -> Self {
+        NmtRootHasher {
+            hasher: NamespacedSha2Hasher::with_ignore_max_ns(true),
+            subtrees: Vec::new(),
+            highest_ns: None,
+        }
+    }
+
+    /// Push the next leaf of the tree.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if leaves are not pushed in the ascending
+    /// order of their namespaces.
+    pub fn push_leaf(&mut self, data: &[u8], namespace: Namespace) -> Result<()> {
+        if self.highest_ns.is_some_and(|highest| namespace < highest) {
+            return Err(Error::InvalidNmtNodeOrder);
+        }
+        self.highest_ns = Some(namespace);
+
+        let mut node = (0, self.hasher.hash_leaf_with_namespace(data, *namespace));
+
+        while let Some((height, left)) = self.subtrees.last() {
+            if *height != node.0 {
+                break;
+            }
+
+            node = (height + 1, self.hasher.hash_nodes(left, &node.1));
+            self.subtrees.pop();
+        }
+
+        self.subtrees.push(node);
+
+        Ok(())
+    }
+
+    /// Compute the root of the leaves pushed so far.
+    pub fn root(&self) -> NamespacedHash {
+        self.subtrees
+            .iter()
+            .rev()
+            .map(|(_, hash)| hash.clone())
+            .reduce(|right, left| self.hasher.hash_nodes(&left, &right))
+            .unwrap_or(NamespacedSha2Hasher::EMPTY_ROOT)
+    }
+}
+
+impl Default for NmtRootHasher {
+    fn default() -> Self {
+        NmtRootHasher::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nmt::Nmt;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    #[test]
+    fn empty_root() {
+        let mut nmt = Nmt::with_hasher(NamespacedSha2Hasher::with_ignore_max_ns(true));
+        assert_eq!(NmtRootHasher::new().root(), nmt.root());
+    }
+
+    #[test]
+    fn same_root_as_nmt() {
+        for leaves in 1..=17u8 {
+            let mut nmt = Nmt::with_hasher(NamespacedSha2Hasher::with_ignore_max_ns(true));
+            let mut hasher = NmtRootHasher::new();
+
+            for i in 0..leaves {
+                let namespace = if i < leaves / 2 {
+                    Namespace::new_v0(&[i]).unwrap()
+                } else {
+                    Namespace::PARITY_SHARE
+                };
+
+                nmt.push_leaf(&[i; 8], *namespace).unwrap();
+                hasher.push_leaf(&[i; 8], namespace).unwrap();
+            }
+
+            assert_eq!(hasher.root(), nmt.root(), "{leaves} leaves");
+        }
+    }
+
+    #[test]
+    fn unordered_namespaces() {
+        let mut hasher = NmtRootHasher::new();
+
+        hasher
+            .push_leaf(b"foo", Namespace::new_v0(&[2]).unwrap())
+            .unwrap();
+        hasher
+            .push_leaf(b"bar", Namespace::new_v0(&[1]).unwrap())
+            .unwrap_err();
+    }
+}
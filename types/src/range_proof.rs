@@ -0,0 +1,730 @@
+//! Proofs of inclusion of a range of shares in the block's data root.
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use celestia_tendermint::merkle::proof::Proof as MerkleProof;
+use celestia_tendermint::merkle::simple_hash_from_byte_vectors;
+use celestia_tendermint::Hash;
+use nmt_rs::simple_merkle::proof::Proof as NmtProof;
+use nmt_rs::NamespaceProof as NmtNamespaceProof;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::nmt::{Namespace, NamespaceProof, NamespacedHash, NamespacedHashExt, NS_ID_SIZE};
+use crate::{bail_verification, Error, Result, Share};
+
+/// Response of the `share.GetRange` with the shares and the proof of their inclusion.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct GetRangeResponse {
+    /// Shares from the requested range.
+    pub shares: Vec<Share>,
+    /// Proof of the shares inclusion in the data root.
+    pub proof: RangeProof,
+}
+
+impl GetRangeResponse {
+    /// Verify that the shares are the ones covered by the proof and that the proof
+    /// is valid for the requested range `start..end` and the data root.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the shares don't match the proven data
+    /// or the proof is invalid or of another range.
+    pub fn verify(&self, start: usize, end: usize, data_root: &Hash) -> Result<()> {
+        if self.shares.len() != self.proof.data.len()
+            || self
+                .shares
+                .iter()
+                .zip(&self.proof.data)
+                .any(|(share, data)| share.as_ref() != &data[..])
+        {
+            bail_verification!("shares don't match the proven data");
+        }
+
+        self.proof.verify(start, end, data_root)
+    }
+}
+
+/// Proof of inclusion of a range of shares in the data root of a block.
+///
+/// It consists of the [`NamespaceProof`] of the shares in each row the range spans,
+/// and the [`RowProof`] of those rows in the data root. All the shares must belong
+/// to the same namespace.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RawRangeProof", into = "RawRangeProof")]
+pub struct RangeProof {
+    /// Raw shares covered by the proof.
+    pub data: Vec<Vec<u8>>,
+    /// Proofs of the shares in each of the rows.
+    pub share_proofs: Vec<NamespaceProof>,
+    /// Namespace of the shares.
+    pub namespace: Namespace,
+    /// Proof of the rows in the data root.
+    pub row_proof: RowProof,
+}
+
+impl RangeProof {
+    /// Verify that the proof covers exactly the shares `start..end` of the original data
+    /// square and that they are included in the data root of the block.
+    ///
+    /// The range counts the shares row by row in the original data square, as in the
+    /// `share.GetRange` request. Binding the proof to it ensures that a proof of other
+    /// shares of the same namespace isn't accepted instead.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the shares or row roots are not
+    /// included in the data root, or the proof is of a different range.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use celestia_types::GetRangeResponse;
+    /// # use celestia_types::ExtendedHeader;
+    /// # fn get_extended_header(_: u64) -> ExtendedHeader {
+    /// #     unimplemented!()
+    /// # }
+    /// # fn get_range(_: u64, _: u64, _: u64) -> GetRangeResponse {
+    /// #     unimplemented!()
+    /// # }
+    /// let header = get_extended_header(100);
+    /// let range = get_range(100, 0, 4);
+    ///
+    /// range.proof.verify(0, 4, &header.header.data_hash).unwrap();
+    /// ```
+    pub fn verify(&self, start: usize, end: usize, data_root: &Hash) -> Result<()> {
+        self.verify_inclusion(data_root)?;
+        self.verify_range(start, end)
+    }
+
+    /// Verify that the proven shares are included in the data root, wherever they are.
+    fn verify_inclusion(&self, data_root: &Hash) -> Result<()> {
+        self.row_proof.verify(data_root)?;
+
+        if self.share_proofs.len() != self.row_proof.row_roots.len() {
+            bail_verification!(
+                "share proofs len ({}) != row roots len ({})",
+                self.share_proofs.len(),
+                self.row_proof.row_roots.len()
+            );
+        }
+
+        let mut shares = &self.data[..];
+
+        for (proof, root) in self.share_proofs.iter().zip(&self.row_proof.row_roots) {
+            if proof.is_of_absence() {
+                bail_verification!("unexpected proof of absence");
+            }
+
+            if proof.start_idx() >= proof.end_idx() {
+                bail_verification!("empty share proof");
+            }
+
+            let amount = (proof.end_idx() - proof.start_idx()) as usize;
+            if shares.len() < amount {
+                bail_verification!("share proofs cover more shares than provided");
+            }
+
+            let (row_shares, rest) = shares.split_at(amount);
+            proof
+                .verify_range(root, row_shares, *self.namespace)
+                .map_err(Error::RangeProofError)?;
+
+            shares = rest;
+        }
+
+        if !shares.is_empty() {
+            bail_verification!("{} shares not covered by the share proofs", shares.len());
+        }
+
+        Ok(())
+    }
+
+    /// Verify that the proven shares are located at `start..end` of the original data square.
+    fn verify_range(&self, start: usize, end: usize) -> Result<()> {
+        let ods_width = self.ods_width();
+
+        if ods_width == 0 || start >= end || end > ods_width * ods_width {
+            bail_verification!("range {}..{} out of the original data square", start, end);
+        }
+
+        let first_row = start / ods_width;
+        let last_row = (end - 1) / ods_width;
+
+        if self.row_proof.start_row as usize != first_row
+            || self.row_proof.end_row as usize != last_row
+        {
+            bail_verification!(
+                "row proof of rows {}..={} doesn't cover the range {}..{}",
+                self.row_proof.start_row,
+                self.row_proof.end_row,
+                start,
+                end
+            );
+        }
+
+        for (row, share_proof) in (first_row..=last_row).zip(&self.share_proofs) {
+            let row_start = row * ods_width;
+            let row_range =
+                start.max(row_start) - row_start..end.min(row_start + ods_width) - row_start;
+
+            if share_proof.start_idx() as usize != row_range.start
+                || share_proof.end_idx() as usize != row_range.end
+            {
+                bail_verification!("share proof of row {} doesn't match the range", row);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Width of the original data square the proof was built for.
+    fn ods_width(&self) -> usize {
+        // row proofs are of the leaves of both the row and column roots
+        let total = self.row_proof.proofs.first().map_or(0, |proof| proof.total) as usize;
+
+        if total % 4 != 0 {
+            return 0;
+        }
+
+        total / 4
+    }
+
+    /// Range of the shares of the original data square claimed by the proof, to be
+    /// checked with [`verify_share_range_proof`].
+    pub(crate) fn claimed_range(&self) -> Option<Range<usize>> {
+        let first = self.share_proofs.first()?;
+        let last = self.share_proofs.last()?;
+        let ods_width = self.ods_width();
+
+        let start = self.row_proof.start_row as usize * ods_width + first.start_idx() as usize;
+        let end = self.row_proof.end_row as usize * ods_width + last.end_idx() as usize;
+
+        Some(start..end)
+    }
+}
+
+/// Verify that the [`RangeProof`] proves exactly the shares `start..end` of the original
+/// data square to the data root.
+///
+/// It's the same as [`RangeProof::verify`], so that the proof built by
+/// [`ExtendedDataSquare::prove_share_range`] can't be presented for other shares of
+/// the same namespace.
+///
+/// # Errors
+///
+/// This function returns an error if the proof is invalid or it covers a different
+/// range of shares.
+///
+/// [`ExtendedDataSquare::prove_share_range`]: crate::ExtendedDataSquare::prove_share_range
+pub fn verify_share_range_proof(
+    proof: &RangeProof,
+    start: usize,
+    end: usize,
+    data_root: &Hash,
+) -> Result<()> {
+    proof.verify(start, end, data_root)
+}
+
+/// Proof of inclusion of a range of rows in the data root of a block.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RawRowProof", into = "RawRowProof")]
+pub struct RowProof {
+    /// Roots of the proven rows.
+    pub row_roots: Vec<NamespacedHash>,
+    /// Merkle proofs of each of the row roots in the data root.
+    pub proofs: Vec<MerkleProof>,
+    /// Index of the first proven row.
+    pub start_row: u32,
+    /// Index of the last proven row.
+    pub end_row: u32,
+}
+
+impl RowProof {
+    /// Verify the proof against the data root of the block.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any of the row roots is not
+    /// included in the data root.
+    pub fn verify(&self, data_root: &Hash) -> Result<()> {
+        let Hash::Sha256(data_root) = data_root else {
+            bail_verification!("empty data root");
+        };
+
+        let rows = self
+            .end_row
+            .checked_sub(self.start_row)
+            .map(|n| n as usize + 1);
+
+        if rows != Some(self.row_roots.len()) || self.proofs.len() != self.row_roots.len() {
+            bail_verification!(
+                "invalid row proof of rows {}..={} with {} roots and {} proofs",
+                self.start_row,
+                self.end_row,
+                self.row_roots.len(),
+                self.proofs.len()
+            );
+        }
+
+        for (row, (root, proof)) in self.row_roots.iter().zip(&self.proofs).enumerate() {
+            let leaf_hash = leaf_hash(&root.to_array());
+
+            if proof.index != u64::from(self.start_row) + row as u64
+                || proof.leaf_hash != Hash::Sha256(leaf_hash)
+            {
+                bail_verification!("row proof doesn't match row {}", proof.index);
+            }
+
+            let aunts: Vec<_> = proof
+                .aunts
+                .iter()
+                .map(|aunt| match aunt {
+                    Hash::Sha256(aunt) => Ok(*aunt),
+                    Hash::None => Err(Error::MissingProof),
+                })
+                .collect::<Result<_>>()?;
+
+            if compute_root(proof.index, proof.total, leaf_hash, &aunts).as_ref() != Some(data_root)
+            {
+                bail_verification!("row {} is not included in the data root", proof.index);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Build the RFC-6962 merkle proof of the leaf by recomputing the subtrees.
+pub(crate) fn merkle_proof(leaves: &[Vec<u8>], index: usize) -> MerkleProof {
+    fn aunts(leaves: &[Vec<u8>], index: usize) -> Vec<Hash> {
+        if leaves.len() <= 1 {
+            return vec![];
+        }
+
+        let split = leaves.len().next_power_of_two() / 2;
+        let (left, right) = leaves.split_at(split);
+
+        if index < split {
+            let mut aunts = aunts(left, index);
+            aunts.push(Hash::Sha256(simple_hash_from_byte_vectors::<Sha256>(right)));
+            aunts
+        } else {
+            let mut aunts = aunts(right, index - split);
+            aunts.push(Hash::Sha256(simple_hash_from_byte_vectors::<Sha256>(left)));
+            aunts
+        }
+    }
+
+    MerkleProof {
+        total: leaves.len() as u64,
+        index: index as u64,
+        leaf_hash: Hash::Sha256(leaf_hash(&leaves[index])),
+        aunts: aunts(leaves, index),
+    }
+}
+
+fn leaf_hash(leaf: &[u8]) -> [u8; 32] {
+    Sha256::new()
+        .chain_update([0])
+        .chain_update(leaf)
+        .finalize()
+        .into()
+}
+
+fn inner_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    Sha256::new()
+        .chain_update([1])
+        .chain_update(left)
+        .chain_update(right)
+        .finalize()
+        .into()
+}
+
+/// Compute the root of the RFC-6962 merkle tree from the hash of the leaf and its aunts.
+fn compute_root(
+    index: u64,
+    total: u64,
+    leaf_hash: [u8; 32],
+    aunts: &[[u8; 32]],
+) -> Option<[u8; 32]> {
+    if index >= total {
+        return None;
+    }
+
+    if total == 1 {
+        return aunts.is_empty().then_some(leaf_hash);
+    }
+
+    let (last, aunts) = aunts.split_last()?;
+    // the largest power of two smaller than the total
+    let split = 1 << (63 - (total - 1).leading_zeros());
+
+    if index < split {
+        let left = compute_root(index, split, leaf_hash, aunts)?;
+        Some(inner_hash(&left, last))
+    } else {
+        let right = compute_root(index - split, total - split, leaf_hash, aunts)?;
+        Some(inner_hash(last, &right))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct RawRangeProof {
+    #[serde(with = "celestia_tendermint_proto::serializers::bytes::vec_base64string")]
+    data: Vec<Vec<u8>>,
+    share_proofs: Vec<RawNmtProof>,
+    #[serde(with = "celestia_tendermint_proto::serializers::bytes::base64string")]
+    namespace_id: Vec<u8>,
+    row_proof: RowProof,
+    #[serde(default)]
+    namespace_version: u8,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RawNmtProof {
+    #[serde(default)]
+    start: u32,
+    #[serde(default)]
+    end: u32,
+    #[serde(
+        default,
+        with = "celestia_tendermint_proto::serializers::bytes::vec_base64string"
+    )]
+    nodes: Vec<Vec<u8>>,
+    #[serde(
+        default,
+        with = "celestia_tendermint_proto::serializers::bytes::base64string"
+    )]
+    leaf_hash: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RawRowProof {
+    row_roots: Vec<String>,
+    proofs: Vec<RawMerkleProof>,
+    #[serde(default)]
+    start_row: u32,
+    #[serde(default)]
+    end_row: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RawMerkleProof {
+    total: u64,
+    index: u64,
+    #[serde(with = "celestia_tendermint_proto::serializers::bytes::base64string")]
+    leaf_hash: Vec<u8>,
+    #[serde(
+        default,
+        with = "celestia_tendermint_proto::serializers::bytes::vec_base64string"
+    )]
+    aunts: Vec<Vec<u8>>,
+}
+
+impl TryFrom<RawRangeProof> for RangeProof {
+    type Error = Error;
+
+    fn try_from(value: RawRangeProof) -> Result<Self, Self::Error> {
+        // The namespace id might be given with the leading zeroes trimmed
+        if value.namespace_id.len() > NS_ID_SIZE {
+            return Err(Error::InvalidNamespaceSize);
+        }
+        let mut id = [0; NS_ID_SIZE];
+        id[NS_ID_SIZE - value.namespace_id.len()..].copy_from_slice(&value.namespace_id);
+
+        let share_proofs = value
+            .share_proofs
+            .into_iter()
+            .map(|proof| {
+                let siblings = proof
+                    .nodes
+                    .iter()
+                    .map(|node| NamespacedHash::from_raw(node))
+                    .collect::<Result<_>>()?;
+
+                let proof = NmtNamespaceProof::PresenceProof {
+                    proof: NmtProof {
+                        siblings,
+                        range: proof.start..proof.end,
+                    },
+                    ignore_max_ns: true,
+                };
+
+                Ok(NamespaceProof::from(proof))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(RangeProof {
+            data: value.data,
+            share_proofs,
+            namespace: Namespace::new(value.namespace_version, &id)?,
+            row_proof: value.row_proof,
+        })
+    }
+}
+
+impl From<RangeProof> for RawRangeProof {
+    fn from(value: RangeProof) -> Self {
+        RawRangeProof {
+            data: value.data,
+            share_proofs: value
+                .share_proofs
+                .iter()
+                .map(|proof| RawNmtProof {
+                    start: proof.start_idx(),
+                    end: proof.end_idx(),
+                    nodes: proof.siblings().iter().map(|node| node.to_vec()).collect(),
+                    leaf_hash: proof.leaf().map(|leaf| leaf.to_vec()).unwrap_or_default(),
+                })
+                .collect(),
+            namespace_id: value.namespace.id().to_vec(),
+            row_proof: value.row_proof,
+            namespace_version: value.namespace.version(),
+        }
+    }
+}
+
+impl TryFrom<RawRowProof> for RowProof {
+    type Error = Error;
+
+    fn try_from(value: RawRowProof) -> Result<Self, Self::Error> {
+        let row_roots = value
+            .row_roots
+            .iter()
+            .map(|root| NamespacedHash::from_raw(&crate::serializers::decode_hex(root)?))
+            .collect::<Result<_>>()?;
+
+        let proofs = value
+            .proofs
+            .into_iter()
+            .map(|proof| {
+                Ok(MerkleProof {
+                    total: proof.total,
+                    index: proof.index,
+                    leaf_hash: Hash::from_bytes(
+                        celestia_tendermint::hash::Algorithm::Sha256,
+                        &proof.leaf_hash,
+                    )?,
+                    aunts: proof
+                        .aunts
+                        .iter()
+                        .map(|aunt| {
+                            Hash::from_bytes(celestia_tendermint::hash::Algorithm::Sha256, aunt)
+                        })
+                        .collect::<Result<_, _>>()?,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(RowProof {
+            row_roots,
+            proofs,
+            start_row: value.start_row,
+            end_row: value.end_row,
+        })
+    }
+}
+
+impl From<RowProof> for RawRowProof {
+    fn from(value: RowProof) -> Self {
+        RawRowProof {
+            row_roots: value
+                .row_roots
+                .iter()
+                .map(|root| hex::encode_upper(root.to_vec()))
+                .collect(),
+            proofs: value
+                .proofs
+                .into_iter()
+                .map(|proof| RawMerkleProof {
+                    total: proof.total,
+                    index: proof.index,
+                    leaf_hash: proof.leaf_hash.as_bytes().to_vec(),
+                    aunts: proof
+                        .aunts
+                        .iter()
+                        .map(|aunt| aunt.as_bytes().to_vec())
+                        .collect(),
+                })
+                .collect(),
+            start_row: value.start_row,
+            end_row: value.end_row,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::appconsts::SHARE_SIZE;
+    use crate::nmt::{NamespacedSha2Hasher, Nmt};
+    use crate::{DataAvailabilityHeader, ExtendedDataSquare};
+    use nmt_rs::NamespaceMerkleHasher;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    /// Build an EDS with the namespace filling the whole original data square.
+    fn eds_of_namespace(namespace: Namespace) -> ExtendedDataSquare {
+        let ods = (0..16u8)
+            .map(|i| {
+                let mut share = vec![i; SHARE_SIZE];
+                share[..namespace.as_bytes().len()].copy_from_slice(namespace.as_bytes());
+                share
+            })
+            .collect();
+
+        ExtendedDataSquare::from_ods(ods).unwrap()
+    }
+
+    /// Prove the range of shares in the first `rows` rows, from the given column of the first row.
+    fn range_proof(
+        eds: &ExtendedDataSquare,
+        dah: &DataAvailabilityHeader,
+        namespace: Namespace,
+        start_col: usize,
+        rows: usize,
+    ) -> RangeProof {
+        let ods_width = eds.square_len() / 2;
+        let leaves: Vec<_> = dah
+            .row_roots
+            .iter()
+            .chain(&dah.column_roots)
+            .map(|root| root.to_array().to_vec())
+            .collect();
+
+        let mut data = Vec::new();
+        let mut share_proofs = Vec::new();
+        let mut proofs = Vec::new();
+
+        for row in 0..rows {
+            let start = if row == 0 { start_col } else { 0 };
+            let shares = eds.row(row).unwrap();
+            let mut nmt = Nmt::with_hasher(NamespacedSha2Hasher::with_ignore_max_ns(true));
+
+            for (col, share) in shares.iter().enumerate() {
+                let ns = if col < ods_width {
+                    namespace
+                } else {
+                    Namespace::PARITY_SHARE
+                };
+                nmt.push_leaf(share, *ns).unwrap();
+            }
+
+            let proof = nmt.build_range_proof(start..ods_width);
+            share_proofs.push(NamespaceProof::from(NmtNamespaceProof::PresenceProof {
+                proof,
+                ignore_max_ns: true,
+            }));
+            data.extend(shares[start..ods_width].iter().map(|share| share.to_vec()));
+            proofs.push(merkle_proof(&leaves, row));
+        }
+
+        RangeProof {
+            data,
+            share_proofs,
+            namespace,
+            row_proof: RowProof {
+                row_roots: dah.row_roots[..rows].to_vec(),
+                proofs,
+                start_row: 0,
+                end_row: rows as u32 - 1,
+            },
+        }
+    }
+
+    #[test]
+    fn verify_range_proof() {
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+        let eds = eds_of_namespace(namespace);
+        let dah = DataAvailabilityHeader::from_eds(&eds).unwrap();
+
+        let proof = range_proof(&eds, &dah, namespace, 1, 3);
+        assert_eq!(proof.data.len(), 3 + 4 + 4);
+
+        proof.verify(1, 12, &dah.hash()).unwrap();
+    }
+
+    #[test]
+    fn verify_range_proof_of_other_range() {
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+        let eds = eds_of_namespace(namespace);
+        let dah = DataAvailabilityHeader::from_eds(&eds).unwrap();
+        let proof = range_proof(&eds, &dah, namespace, 1, 3);
+
+        // valid proof of the same namespace, but not of the requested shares
+        proof.verify(0, 12, &dah.hash()).unwrap_err();
+        proof.verify(1, 11, &dah.hash()).unwrap_err();
+        proof.verify(5, 16, &dah.hash()).unwrap_err();
+        proof.verify(1, 17, &dah.hash()).unwrap_err();
+    }
+
+    #[test]
+    fn verify_range_proof_wrong_root() {
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+        let eds = eds_of_namespace(namespace);
+        let dah = DataAvailabilityHeader::from_eds(&eds).unwrap();
+        let proof = range_proof(&eds, &dah, namespace, 0, 2);
+
+        let other_eds = eds_of_namespace(Namespace::new_v0(&[1, 2, 4]).unwrap());
+        let other_dah = DataAvailabilityHeader::from_eds(&other_eds).unwrap();
+
+        proof.verify(0, 8, &other_dah.hash()).unwrap_err();
+    }
+
+    #[test]
+    fn verify_range_proof_modified_share() {
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+        let eds = eds_of_namespace(namespace);
+        let dah = DataAvailabilityHeader::from_eds(&eds).unwrap();
+
+        let mut proof = range_proof(&eds, &dah, namespace, 0, 2);
+        proof.data[5][100] ^= 1;
+        proof.verify(0, 8, &dah.hash()).unwrap_err();
+
+        let mut proof = range_proof(&eds, &dah, namespace, 0, 2);
+        proof.data.pop();
+        proof.verify(0, 8, &dah.hash()).unwrap_err();
+    }
+
+    #[test]
+    fn verify_get_range_response() {
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+        let eds = eds_of_namespace(namespace);
+        let dah = DataAvailabilityHeader::from_eds(&eds).unwrap();
+        let proof = range_proof(&eds, &dah, namespace, 2, 1);
+
+        let mut response = GetRangeResponse {
+            shares: proof
+                .data
+                .iter()
+                .map(|share| Share::from_raw(share).unwrap())
+                .collect(),
+            proof,
+        };
+        response.verify(2, 4, &dah.hash()).unwrap();
+        response.verify(0, 2, &dah.hash()).unwrap_err();
+
+        response.shares.swap(0, 1);
+        response.verify(2, 4, &dah.hash()).unwrap_err();
+    }
+
+    #[test]
+    fn range_proof_serde_round_trip() {
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+        let eds = eds_of_namespace(namespace);
+        let dah = DataAvailabilityHeader::from_eds(&eds).unwrap();
+        let proof = range_proof(&eds, &dah, namespace, 1, 2);
+
+        let json = serde_json::to_string(&proof).unwrap();
+        let decoded: RangeProof = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(decoded, proof);
+        decoded.verify(1, 8, &dah.hash()).unwrap();
+    }
+}
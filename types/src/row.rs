@@ -9,7 +9,7 @@
 use std::io::Cursor;
 
 use blockstore::block::CidError;
-use bytes::{Buf, BufMut, BytesMut};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
 use celestia_proto::share::p2p::shwap::Row as RawRow;
 use celestia_tendermint_proto::Protobuf;
 use cid::CidGeneric;
@@ -17,6 +17,8 @@ use multihash::Multihash;
 use nmt_rs::NamespaceMerkleHasher;
 use serde::{Deserialize, Serialize};
 
+use crate::consts::appconsts::SHARE_SIZE;
+use crate::namespaced_data::{NamespacedData, NamespacedDataId};
 use crate::nmt::NS_SIZE;
 use crate::nmt::{Namespace, NamespacedSha2Hasher, Nmt};
 use crate::rsmt2d::ExtendedDataSquare;
@@ -25,9 +27,114 @@ use crate::{DataAvailabilityHeader, Error, Result};
 /// The size of the [`RowId`] hash in `multihash`.
 const ROW_ID_SIZE: usize = RowId::size();
 /// The code of the [`RowId`] hashing algorithm in `multihash`.
-pub const ROW_ID_MULTIHASH_CODE: u64 = 0x7811;
+pub const ROW_ID_MULTIHASH_CODE: u64 = 0x7801;
 /// The id of codec used for the [`RowId`] in `Cid`s.
-pub const ROW_ID_CODEC: u64 = 0x7810;
+pub const ROW_ID_CODEC: u64 = 0x7800;
+/// The code of the [`RowId`] hashing algorithm in `multihash` in the [`IdLayout::Draft`].
+pub const DRAFT_ROW_ID_MULTIHASH_CODE: u64 = 0x7811;
+/// The id of codec used for the [`RowId`] in `Cid`s in the [`IdLayout::Draft`].
+pub const DRAFT_ROW_ID_CODEC: u64 = 0x7810;
+
+/// Binary layout of the shwap identifiers.
+///
+/// The finalized shwap specification encodes the identifiers as the big-endian
+/// [`EdsId`] followed by the indexes of the row and sample, and swaps the codecs of
+/// the [`RowId`] and [`SampleId`] compared to its draft.
+///
+/// Only the identifiers are migrated so far, the containers are still encoded with
+/// the messages of the draft. The draft layout therefore stays the default, as it's
+/// the only one understood by the other celestia-node peers, until the containers
+/// are migrated to the finalized specification too.
+///
+/// [`SampleId`]: crate::sample::SampleId
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum IdLayout {
+    /// Identifiers of the finalized shwap specification, with the containers of the draft.
+    V1,
+    /// Layout of the draft shwap specification, with little-endian integers.
+    #[default]
+    Draft,
+}
+
+impl IdLayout {
+    pub(crate) fn put_u64(self, bytes: &mut BytesMut, n: u64) {
+        match self {
+            IdLayout::V1 => bytes.put_u64(n),
+            IdLayout::Draft => bytes.put_u64_le(n),
+        }
+    }
+
+    pub(crate) fn put_u16(self, bytes: &mut BytesMut, n: u16) {
+        match self {
+            IdLayout::V1 => bytes.put_u16(n),
+            IdLayout::Draft => bytes.put_u16_le(n),
+        }
+    }
+
+    pub(crate) fn get_u64(self, buffer: &mut impl Buf) -> u64 {
+        match self {
+            IdLayout::V1 => buffer.get_u64(),
+            IdLayout::Draft => buffer.get_u64_le(),
+        }
+    }
+
+    pub(crate) fn get_u16(self, buffer: &mut impl Buf) -> u16 {
+        match self {
+            IdLayout::V1 => buffer.get_u16(),
+            IdLayout::Draft => buffer.get_u16_le(),
+        }
+    }
+}
+
+/// Identifies the [`ExtendedDataSquare`] of a particular block.
+///
+/// [`ExtendedDataSquare`]: crate::rsmt2d::ExtendedDataSquare
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct EdsId {
+    /// A height of the block which contains the data.
+    pub height: u64,
+}
+
+impl EdsId {
+    /// Create a new [`EdsId`] of the block at the given height.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the block height is invalid.
+    pub fn new(height: u64) -> Result<Self> {
+        if height == 0 {
+            return Err(Error::ZeroBlockHeight);
+        }
+
+        Ok(EdsId { height })
+    }
+
+    /// Number of bytes needed to represent [`EdsId`].
+    pub const fn size() -> usize {
+        // size of:
+        // u64
+        8
+    }
+
+    pub(crate) fn encode(&self, bytes: &mut BytesMut, layout: IdLayout) {
+        bytes.reserve(EdsId::size());
+        layout.put_u64(bytes, self.height);
+    }
+
+    pub(crate) fn decode(buffer: &mut impl Buf, layout: IdLayout) -> Result<Self, CidError> {
+        if buffer.remaining() < EdsId::size() {
+            return Err(CidError::InvalidMultihashLength(buffer.remaining()));
+        }
+
+        let height = layout.get_u64(buffer);
+
+        if height == 0 {
+            return Err(CidError::InvalidCid("Zero block height".to_string()));
+        }
+
+        Ok(EdsId { height })
+    }
+}
 
 /// Represents particular row in a specific Data Square,
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -47,7 +154,7 @@ pub struct Row {
     /// Location of the row in the EDS and associated block height
     pub row_id: RowId,
     /// Shares contained in the row
-    pub shares: Vec<Vec<u8>>,
+    pub shares: Vec<Bytes>,
 }
 
 impl Row {
@@ -61,9 +168,65 @@ impl Row {
 
     /// Validate the row against roots from DAH
     pub fn validate(&self, dah: &DataAvailabilityHeader) -> Result<()> {
+        let index = usize::from(self.row_id.index);
+        let mut tree = self.tree()?;
+
+        let Some(root) = dah.row_root(index) else {
+            return Err(Error::EdsIndexOutOfRange(index));
+        };
+
+        if tree.root().hash() != root.hash() {
+            return Err(Error::RootMismatch);
+        }
+
+        Ok(())
+    }
+
+    /// Collect the [`Share`]s of the namespace in the row, together with the proof
+    /// of their inclusion in it.
+    ///
+    /// The proof is built from all the shares of the row, so it should be validated
+    /// first. Shares of a namespace can only be in the original data half of the row,
+    /// the parity half is used only to build the proof.
+    ///
+    /// [`Share`]: crate::Share
+    pub fn namespaced_data(&self, namespace: Namespace) -> Result<NamespacedData> {
+        let mut tree = self.tree()?;
+        let mut shares = Vec::new();
+
+        for share in &self.shares[..self.data_len()] {
+            if Namespace::from_raw(&share[..NS_SIZE])? == namespace {
+                shares.push(share.clone());
+            }
+        }
+
+        let proof = tree.get_namespace_proof(*namespace);
+
+        Ok(NamespacedData {
+            namespaced_data_id: NamespacedDataId {
+                row: self.row_id,
+                namespace,
+            },
+            shares,
+            proof: proof.into(),
+        })
+    }
+
+    /// Number of the original data shares in the row.
+    fn data_len(&self) -> usize {
         let square_len = self.shares.len();
 
-        let (data_shares, parity_shares) = self.shares.split_at(square_len / 2);
+        // rows in the lower half of the EDS consist of parity shares only
+        if usize::from(self.row_id.index) < square_len / 2 {
+            square_len / 2
+        } else {
+            0
+        }
+    }
+
+    /// Build the [`Nmt`] of the row.
+    fn tree(&self) -> Result<Nmt> {
+        let (data_shares, parity_shares) = self.shares.split_at(self.data_len());
 
         let mut tree = Nmt::with_hasher(NamespacedSha2Hasher::with_ignore_max_ns(true));
         for s in data_shares {
@@ -75,47 +238,41 @@ impl Row {
                 .map_err(Error::Nmt)?;
         }
 
-        let index = self.row_id.index.into();
-        let Some(root) = dah.row_root(index) else {
-            return Err(Error::EdsIndexOutOfRange(index));
-        };
-
-        if tree.root().hash() != root.hash() {
-            return Err(Error::RootMismatch);
-        }
-
-        Ok(())
+        Ok(tree)
     }
 }
 
 impl Protobuf<RawRow> for Row {}
 
-impl TryFrom<RawRow> for Row {
-    type Error = Error;
-
-    fn try_from(row: RawRow) -> Result<Row, Self::Error> {
-        let row_id = RowId::decode(&row.row_id)?;
-        let shares = row.row_half;
+impl Row {
+    /// Decode the [`Row`] from its protobuf representation with the given [`IdLayout`].
+    pub fn from_raw(row: RawRow, layout: IdLayout) -> Result<Row> {
+        let row_id = RowId::decode(&row.row_id, layout)?;
+        let mut shares = row.row_half;
 
-        // TODO: only original data shares are sent over the wire, we need leopard codec to
-        // re-compute parity shares
-        //
-        // somehow_generate_parity_shares(&mut shares);
+        if let Some(share) = shares.iter().find(|share| share.len() != SHARE_SIZE) {
+            return Err(Error::InvalidShareSize(share.len()));
+        }
 
-        let _ = Row { row_id, shares };
+        // only original data shares are sent over the wire, parity shares are re-computed.
+        // leopard encodes in place, so the data shares are only copied into its scratch buffer
+        let data_shares = shares.len();
+        let mut shards: Vec<_> = shares.iter().map(|share| share.to_vec()).collect();
+        shards.resize(data_shares * 2, vec![0; SHARE_SIZE]);
+        leopard_codec::encode(&mut shards, data_shares)?;
+        shares.extend(shards.into_iter().skip(data_shares).map(Bytes::from));
 
-        unimplemented!()
+        Ok(Row { row_id, shares })
     }
-}
 
-impl From<Row> for RawRow {
-    fn from(row: Row) -> RawRow {
+    /// Convert the [`Row`] to its protobuf representation with the given [`IdLayout`].
+    pub fn into_raw(self, layout: IdLayout) -> RawRow {
         let mut row_id_bytes = BytesMut::new();
-        row.row_id.encode(&mut row_id_bytes);
+        self.row_id.encode(&mut row_id_bytes, layout);
 
         // parity shares aren't transmitted over shwap, just data shares
-        let square_len = row.shares.len();
-        let mut row_half = row.shares;
+        let square_len = self.shares.len();
+        let mut row_half = self.shares;
         row_half.truncate(square_len / 2);
 
         RawRow {
@@ -125,6 +282,20 @@ impl From<Row> for RawRow {
     }
 }
 
+impl TryFrom<RawRow> for Row {
+    type Error = Error;
+
+    fn try_from(row: RawRow) -> Result<Row, Self::Error> {
+        Row::from_raw(row, IdLayout::default())
+    }
+}
+
+impl From<Row> for RawRow {
+    fn from(row: Row) -> RawRow {
+        row.into_raw(IdLayout::default())
+    }
+}
+
 impl RowId {
     /// Create a new [`RowId`] for the particular block.
     ///
@@ -145,44 +316,51 @@ impl RowId {
     /// Number of bytes needed to represent [`RowId`]
     pub const fn size() -> usize {
         // size of:
-        // u16 + u64
-        // 2  + 8
-        10
+        // EdsId + u16
+        //     8 + 2
+        EdsId::size() + 2
     }
 
-    pub(crate) fn encode(&self, bytes: &mut BytesMut) {
-        bytes.reserve(ROW_ID_SIZE);
-
-        bytes.put_u64_le(self.block_height);
-        bytes.put_u16_le(self.index);
+    /// The [`EdsId`] of the square containing the row.
+    pub fn eds_id(&self) -> EdsId {
+        EdsId {
+            height: self.block_height,
+        }
     }
 
-    pub(crate) fn decode(buffer: &[u8]) -> Result<Self, CidError> {
-        if buffer.len() != ROW_ID_SIZE {
-            return Err(CidError::InvalidMultihashLength(buffer.len()));
+    /// The id of codec used for the [`RowId`] in `Cid`s of the given [`IdLayout`].
+    pub const fn codec(layout: IdLayout) -> u64 {
+        match layout {
+            IdLayout::V1 => ROW_ID_CODEC,
+            IdLayout::Draft => DRAFT_ROW_ID_CODEC,
         }
+    }
 
-        let mut cursor = Cursor::new(buffer);
-        let block_height = cursor.get_u64_le();
-        let index = cursor.get_u16_le();
-
-        if block_height == 0 {
-            return Err(CidError::InvalidCid("Zero block height".to_string()));
+    /// The code of the [`RowId`] hashing algorithm in `multihash` of the given [`IdLayout`].
+    pub const fn multihash_code(layout: IdLayout) -> u64 {
+        match layout {
+            IdLayout::V1 => ROW_ID_MULTIHASH_CODE,
+            IdLayout::Draft => DRAFT_ROW_ID_MULTIHASH_CODE,
         }
-
-        Ok(Self {
-            block_height,
-            index,
-        })
     }
-}
 
-impl<const S: usize> TryFrom<CidGeneric<S>> for RowId {
-    type Error = CidError;
+    /// Convert the [`RowId`] to the `Cid` of the given [`IdLayout`].
+    pub fn to_cid(&self, layout: IdLayout) -> CidGeneric<ROW_ID_SIZE> {
+        let mut bytes = BytesMut::with_capacity(ROW_ID_SIZE);
+        self.encode(&mut bytes, layout);
+        // length is correct, so unwrap is safe
+        let mh = Multihash::wrap(RowId::multihash_code(layout), &bytes[..]).unwrap();
 
-    fn try_from(cid: CidGeneric<S>) -> Result<Self, Self::Error> {
+        CidGeneric::new_v1(RowId::codec(layout), mh)
+    }
+
+    /// Decode the [`RowId`] from the `Cid` of the given [`IdLayout`].
+    pub fn from_cid<const S: usize>(
+        cid: &CidGeneric<S>,
+        layout: IdLayout,
+    ) -> Result<Self, CidError> {
         let codec = cid.codec();
-        if codec != ROW_ID_CODEC {
+        if codec != RowId::codec(layout) {
             return Err(CidError::InvalidCidCodec(codec));
         }
 
@@ -194,11 +372,44 @@ impl<const S: usize> TryFrom<CidGeneric<S>> for RowId {
         }
 
         let code = hash.code();
-        if code != ROW_ID_MULTIHASH_CODE {
-            return Err(CidError::InvalidMultihashCode(code, ROW_ID_MULTIHASH_CODE));
+        if code != RowId::multihash_code(layout) {
+            return Err(CidError::InvalidMultihashCode(
+                code,
+                RowId::multihash_code(layout),
+            ));
         }
 
-        RowId::decode(hash.digest())
+        RowId::decode(hash.digest(), layout)
+    }
+
+    pub(crate) fn encode(&self, bytes: &mut BytesMut, layout: IdLayout) {
+        bytes.reserve(ROW_ID_SIZE);
+
+        self.eds_id().encode(bytes, layout);
+        layout.put_u16(bytes, self.index);
+    }
+
+    pub(crate) fn decode(buffer: &[u8], layout: IdLayout) -> Result<Self, CidError> {
+        if buffer.len() != ROW_ID_SIZE {
+            return Err(CidError::InvalidMultihashLength(buffer.len()));
+        }
+
+        let mut cursor = Cursor::new(buffer);
+        let eds_id = EdsId::decode(&mut cursor, layout)?;
+        let index = layout.get_u16(&mut cursor);
+
+        Ok(Self {
+            block_height: eds_id.height,
+            index,
+        })
+    }
+}
+
+impl<const S: usize> TryFrom<CidGeneric<S>> for RowId {
+    type Error = CidError;
+
+    fn try_from(cid: CidGeneric<S>) -> Result<Self, Self::Error> {
+        RowId::from_cid(&cid, IdLayout::default())
     }
 }
 
@@ -206,38 +417,34 @@ impl TryFrom<RowId> for CidGeneric<ROW_ID_SIZE> {
     type Error = CidError;
 
     fn try_from(row: RowId) -> Result<Self, Self::Error> {
-        let mut bytes = BytesMut::with_capacity(ROW_ID_SIZE);
-        row.encode(&mut bytes);
-        // length is correct, so unwrap is safe
-        let mh = Multihash::wrap(ROW_ID_MULTIHASH_CODE, &bytes[..]).unwrap();
-
-        Ok(CidGeneric::new_v1(ROW_ID_CODEC, mh))
+        Ok(row.to_cid(IdLayout::default()))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::consts::appconsts::SHARE_SIZE;
     use crate::nmt::{Namespace, NS_SIZE};
+    use crate::test_utils::ExtendedHeaderGenerator;
+    use prost::Message;
 
     #[test]
     fn round_trip_test() {
         let row_id = RowId::new(5, 100).unwrap();
-        let cid = CidGeneric::try_from(row_id).unwrap();
+        let cid = row_id.to_cid(IdLayout::V1);
 
         let multihash = cid.hash();
         assert_eq!(multihash.code(), ROW_ID_MULTIHASH_CODE);
         assert_eq!(multihash.size(), ROW_ID_SIZE as u8);
 
-        let deserialized_row_id = RowId::try_from(cid).unwrap();
+        let deserialized_row_id = RowId::from_cid(&cid, IdLayout::V1).unwrap();
         assert_eq!(row_id, deserialized_row_id);
     }
 
     #[test]
     fn index_calculation() {
         let height = 100;
-        let shares = vec![vec![0; SHARE_SIZE]; 8 * 8];
+        let shares = vec![Bytes::from(vec![0; SHARE_SIZE]); 8 * 8];
         let eds = ExtendedDataSquare::new(shares, "codec".to_string()).unwrap();
 
         Row::new(1, &eds, height).unwrap();
@@ -248,15 +455,46 @@ mod tests {
         assert!(matches!(row_err, Error::EdsIndexOutOfRange(100)));
     }
 
+    #[test]
+    fn namespaced_data_from_row() {
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+        let other = Namespace::new_v0(&[4, 5, 6]).unwrap();
+        let (header, eds) = ExtendedHeaderGenerator::new().next_with_eds(4, &[namespace, other]);
+        let height = header.height().value();
+        let expected = eds
+            .get_namespaced_data(namespace, &header.dah, height)
+            .unwrap();
+        assert!(!expected.is_empty());
+
+        for expected in expected {
+            let row = Row::new(expected.namespaced_data_id.row.index, &eds, height).unwrap();
+            row.validate(&header.dah).unwrap();
+
+            let namespaced_data = row.namespaced_data(namespace).unwrap();
+            namespaced_data.validate(&header.dah).unwrap();
+            assert_eq!(
+                namespaced_data.namespaced_data_id,
+                expected.namespaced_data_id
+            );
+            assert_eq!(namespaced_data.shares, expected.shares);
+        }
+
+        // absence is proven in the rows without the namespace
+        let row = Row::new(0, &eds, height).unwrap();
+        let missing = Namespace::new_v0(&[0xff]).unwrap();
+        let namespaced_data = row.namespaced_data(missing).unwrap();
+        assert!(namespaced_data.shares.is_empty());
+    }
+
     #[test]
     fn from_buffer() {
         let bytes = [
             0x01, // CIDv1
-            0x90, 0xF0, 0x01, // CID codec = 7810
-            0x91, 0xF0, 0x01, // multihash code = 7811
+            0x80, 0xF0, 0x01, // CID codec = 7800
+            0x81, 0xF0, 0x01, // multihash code = 7801
             0x0A, // len = ROW_ID_SIZE = 10
-            64, 0, 0, 0, 0, 0, 0, 0, // block height = 64
-            7, 0, // row index = 7
+            0, 0, 0, 0, 0, 0, 0, 64, // block height = 64
+            0, 7, // row index = 7
         ];
 
         let cid = CidGeneric::<ROW_ID_SIZE>::read_bytes(bytes.as_ref()).unwrap();
@@ -264,28 +502,50 @@ mod tests {
         let mh = cid.hash();
         assert_eq!(mh.code(), ROW_ID_MULTIHASH_CODE);
         assert_eq!(mh.size(), ROW_ID_SIZE as u8);
-        let row_id = RowId::try_from(cid).unwrap();
+        let row_id = RowId::from_cid(&cid, IdLayout::V1).unwrap();
         assert_eq!(row_id.index, 7);
         assert_eq!(row_id.block_height, 64);
+        assert_eq!(row_id.eds_id(), EdsId::new(64).unwrap());
     }
 
     #[test]
-    fn zero_block_height() {
+    fn from_buffer_draft_layout() {
         let bytes = [
             0x01, // CIDv1
             0x90, 0xF0, 0x01, // CID codec = 7810
-            0x91, 0xF0, 0x01, // code = 7811
+            0x91, 0xF0, 0x01, // multihash code = 7811
             0x0A, // len = ROW_ID_SIZE = 10
-            0, 0, 0, 0, 0, 0, 0, 0, // invalid block height = 0 !
+            64, 0, 0, 0, 0, 0, 0, 0, // block height = 64
             7, 0, // row index = 7
         ];
 
+        let cid = CidGeneric::<ROW_ID_SIZE>::read_bytes(bytes.as_ref()).unwrap();
+        let row_id = RowId::from_cid(&cid, IdLayout::Draft).unwrap();
+        assert_eq!(row_id.index, 7);
+        assert_eq!(row_id.block_height, 64);
+        assert_eq!(row_id.to_cid(IdLayout::Draft), cid);
+
+        // draft codec of the row is the codec of the sample in v1
+        RowId::from_cid(&cid, IdLayout::V1).unwrap_err();
+    }
+
+    #[test]
+    fn zero_block_height() {
+        let bytes = [
+            0x01, // CIDv1
+            0x80, 0xF0, 0x01, // CID codec = 7800
+            0x81, 0xF0, 0x01, // code = 7801
+            0x0A, // len = ROW_ID_SIZE = 10
+            0, 0, 0, 0, 0, 0, 0, 0, // invalid block height = 0 !
+            0, 7, // row index = 7
+        ];
+
         let cid = CidGeneric::<ROW_ID_SIZE>::read_bytes(bytes.as_ref()).unwrap();
         assert_eq!(cid.codec(), ROW_ID_CODEC);
         let mh = cid.hash();
         assert_eq!(mh.code(), ROW_ID_MULTIHASH_CODE);
         assert_eq!(mh.size(), ROW_ID_SIZE as u8);
-        let row_err = RowId::try_from(cid).unwrap_err();
+        let row_err = RowId::from_cid(&cid, IdLayout::V1).unwrap_err();
         assert_eq!(
             row_err,
             CidError::InvalidCid("Zero block height".to_string())
@@ -296,7 +556,7 @@ mod tests {
     fn multihash_invalid_code() {
         let multihash = Multihash::<ROW_ID_SIZE>::wrap(999, &[0; ROW_ID_SIZE]).unwrap();
         let cid = CidGeneric::<ROW_ID_SIZE>::new_v1(ROW_ID_CODEC, multihash);
-        let row_err = RowId::try_from(cid).unwrap_err();
+        let row_err = RowId::from_cid(&cid, IdLayout::V1).unwrap_err();
         assert_eq!(
             row_err,
             CidError::InvalidMultihashCode(999, ROW_ID_MULTIHASH_CODE)
@@ -308,21 +568,15 @@ mod tests {
         let multihash =
             Multihash::<ROW_ID_SIZE>::wrap(ROW_ID_MULTIHASH_CODE, &[0; ROW_ID_SIZE]).unwrap();
         let cid = CidGeneric::<ROW_ID_SIZE>::new_v1(1234, multihash);
-        let row_err = RowId::try_from(cid).unwrap_err();
+        let row_err = RowId::from_cid(&cid, IdLayout::V1).unwrap_err();
         assert_eq!(row_err, CidError::InvalidCidCodec(1234));
     }
 
     #[test]
-    // TODO:
-    // fully testing protobuf deserialisation requires leopard codec, to generate parity shares.
-    // By asserting that we've reached `unimplemented!`, we rely on implementation detail to
-    // check whether protobuf, RowId and Share deserialisations were successful (but can't check
-    // the actual data)
-    // Once we have leopard codec, remove `should_panic` to enable full test functionality
-    #[should_panic(expected = "not implemented")]
     fn decode_row_bytes() {
         let bytes = include_bytes!("../test_data/shwap_samples/row.data");
-        let mut row = Row::decode(&bytes[..]).unwrap();
+        let raw = RawRow::decode(&bytes[..]).unwrap();
+        let mut row = Row::from_raw(raw, IdLayout::Draft).unwrap();
 
         row.row_id.index = 64;
         row.row_id.block_height = 255;
@@ -330,7 +584,9 @@ mod tests {
         assert_eq!(row.row_id.index, 64);
         assert_eq!(row.row_id.block_height, 255);
 
-        for (idx, share) in row.shares.iter().enumerate() {
+        let (data_shares, _parity_shares) = row.shares.split_at(row.shares.len() / 2);
+
+        for (idx, share) in data_shares.iter().enumerate() {
             let expected_ns = Namespace::new_v0(&[idx as u8]).unwrap();
             let ns = Namespace::from_raw(&share[..NS_SIZE]).unwrap();
             assert_eq!(ns, expected_ns);
@@ -357,4 +613,33 @@ mod tests {
 
         row.validate(&dah).unwrap();
     }
+
+    #[test]
+    fn validate_parity_row() {
+        let eds_json = include_str!("../test_data/shwap_samples/eds.json");
+        let eds: ExtendedDataSquare = serde_json::from_str(eds_json).unwrap();
+        let dah_json = include_str!("../test_data/shwap_samples/dah.json");
+        let dah: DataAvailabilityHeader = serde_json::from_str(dah_json).unwrap();
+
+        let index = eds.square_len() as u16 - 1;
+        let row = Row::new(index, &eds, 1).unwrap();
+
+        row.validate(&dah).unwrap();
+    }
+
+    #[test]
+    fn protobuf_round_trip_recomputes_parity() {
+        let eds_json = include_str!("../test_data/shwap_samples/eds.json");
+        let eds: ExtendedDataSquare = serde_json::from_str(eds_json).unwrap();
+        let dah_json = include_str!("../test_data/shwap_samples/dah.json");
+        let dah: DataAvailabilityHeader = serde_json::from_str(dah_json).unwrap();
+
+        let row = Row::new(3, &eds, 1).unwrap();
+        let bytes = row.clone().encode_vec().unwrap();
+        let decoded = Row::decode(&bytes[..]).unwrap();
+
+        assert_eq!(decoded.row_id, row.row_id);
+        assert_eq!(decoded.shares, row.shares);
+        decoded.validate(&dah).unwrap();
+    }
 }
@@ -1,12 +1,30 @@
-use std::result::Result as StdResult;
+use core::result::Result as StdResult;
 
+#[cfg(feature = "std")]
+use bytes::Bytes;
+#[cfg(feature = "std")]
 use nmt_rs::NamespaceMerkleHasher;
+#[cfg(feature = "std")]
 use serde::{Deserialize, Deserializer, Serialize};
 
+#[cfg(feature = "std")]
+use crate::consts::appconsts::{AppVersion, SHARE_SIZE};
+#[cfg(feature = "std")]
 use crate::namespaced_data::{NamespacedData, NamespacedDataId};
-use crate::nmt::{Namespace, NamespacedSha2Hasher, Nmt, NS_SIZE};
+#[cfg(feature = "std")]
+use crate::nmt::{
+    Namespace, NamespaceMultiProof, NamespaceProof, NamespacedHash, NamespacedSha2Hasher, Nmt,
+    NmtRootHasher, NS_SIZE,
+};
+#[cfg(feature = "std")]
 use crate::row::RowId;
-use crate::{DataAvailabilityHeader, Error, Result};
+use crate::Error;
+#[cfg(feature = "std")]
+use crate::{square, Blob, DataAvailabilityHeader, RangeProof, Result, Share};
+
+/// The Reed-Solomon codec used by Celestia to compute the parity data.
+#[cfg(feature = "std")]
+pub const LEOPARD_CODEC: &str = "Leopard";
 
 /// Represents either column or row of the [`ExtendedDataSquare`].
 ///
@@ -131,11 +149,15 @@ impl TryFrom<u8> for AxisType {
 /// [`Nmt`]: crate::nmt::Nmt
 /// [`Share`]: crate::share::Share
 /// [`DataAvailabilityHeader`]: crate::DataAvailabilityHeader
+#[cfg(feature = "std")]
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct ExtendedDataSquare {
     /// The raw data of the EDS.
+    ///
+    /// Shares are reference counted, so rows, columns and the containers built out of
+    /// them share the memory with the square instead of copying it.
     #[serde(with = "celestia_tendermint_proto::serializers::bytes::vec_base64string")]
-    pub data_square: Vec<Vec<u8>>,
+    pub data_square: Vec<Bytes>,
     /// The codec used to encode parity shares.
     pub codec: String,
     /// pre-calculated square length
@@ -143,10 +165,11 @@ pub struct ExtendedDataSquare {
     square_len: usize,
 }
 
+#[cfg(feature = "std")]
 impl ExtendedDataSquare {
     /// Create a new EDS out of the provided shares. Returns error if number of shares isn't
     /// a square number
-    pub fn new(shares: Vec<Vec<u8>>, codec: String) -> Result<Self> {
+    pub fn new(shares: Vec<Bytes>, codec: String) -> Result<Self> {
         let square_len = f64::sqrt(shares.len() as f64) as usize;
         if square_len * square_len != shares.len() {
             return Err(Error::EdsInvalidDimentions);
@@ -159,8 +182,178 @@ impl ExtendedDataSquare {
         })
     }
 
+    /// Create a new EDS by erasure coding the original data square.
+    ///
+    /// The parity data is computed with the [`LEOPARD_CODEC`], first for each row of the
+    /// original data and then for each of the resulting columns.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the number of shares isn't a square of
+    /// a power of two, or any of the shares has invalid size.
+    pub fn from_ods(ods: Vec<Vec<u8>>) -> Result<Self> {
+        let ods_len = f64::sqrt(ods.len() as f64) as usize;
+        if ods_len * ods_len != ods.len() || !ods_len.is_power_of_two() {
+            return Err(Error::EdsInvalidDimentions);
+        }
+        if let Some(share) = ods.iter().find(|share| share.len() != SHARE_SIZE) {
+            return Err(Error::InvalidShareSize(share.len()));
+        }
+
+        let square_len = ods_len * 2;
+        let mut data_square = vec![Vec::new(); square_len * square_len];
+
+        // extend the rows of the original data
+        for (row, shares) in ods.chunks(ods_len).enumerate() {
+            let mut shards = shares.to_vec();
+            shards.resize(square_len, vec![0; SHARE_SIZE]);
+            leopard_codec::encode(&mut shards, ods_len)?;
+
+            for (col, shard) in shards.into_iter().enumerate() {
+                data_square[row * square_len + col] = shard;
+            }
+        }
+
+        // extend all the columns
+        for col in 0..square_len {
+            let mut shards: Vec<_> = (0..ods_len)
+                .map(|row| data_square[row * square_len + col].clone())
+                .collect();
+            shards.resize(square_len, vec![0; SHARE_SIZE]);
+            leopard_codec::encode(&mut shards, ods_len)?;
+
+            for (row, shard) in shards.into_iter().enumerate().skip(ods_len) {
+                data_square[row * square_len + col] = shard;
+            }
+        }
+
+        let data_square = data_square.into_iter().map(Bytes::from).collect();
+        ExtendedDataSquare::new(data_square, LEOPARD_CODEC.to_owned())
+    }
+
+    /// Build the EDS out of the transactions and blobs, following the celestia-app square layout.
+    ///
+    /// Ordinary `txs` are split into the compact shares of the [`Namespace::TRANSACTION`].
+    /// Transactions encoded as `BlobTx` have their blobs placed in the square, while
+    /// the transaction itself is wrapped with the start indexes of its blobs and stored in the
+    /// [`Namespace::PAY_FOR_BLOB`]. The `blobs` are placed in the square without any transaction
+    /// paying for them, which is useful for the test fixtures. All the blobs are sorted
+    /// by their namespace and aligned according to the [`non-interactive default rules`],
+    /// then the rest of the square is filled with padding and it is erasure coded.
+    /// The size limit of the square and the alignment of the blobs follow the rules of
+    /// the given [`AppVersion`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the `square_size`, being the width of the
+    /// original data square, is not a power of two or exceeds the
+    /// [`square_size_upper_bound`] of the app version, or if the data doesn't fit in it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use celestia_types::consts::appconsts::AppVersion;
+    /// use celestia_types::nmt::Namespace;
+    /// use celestia_types::{Blob, ExtendedDataSquare, Share};
+    ///
+    /// let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+    /// let blob = Blob::new(namespace, b"foo".to_vec(), AppVersion::V2).unwrap();
+    ///
+    /// let eds = ExtendedDataSquare::build(&[blob], &[b"tx".to_vec()], 2, AppVersion::V2).unwrap();
+    /// assert_eq!(eds.square_len(), 4);
+    ///
+    /// let first_row = eds.row(0).unwrap();
+    /// assert_eq!(Share::from_raw(&first_row[0]).unwrap().namespace(), Namespace::TRANSACTION);
+    /// assert_eq!(Share::from_raw(&first_row[1]).unwrap().namespace(), namespace);
+    /// ```
+    ///
+    /// [`non-interactive default rules`]: https://github.com/celestiaorg/celestia-app/blob/main/specs/src/specs/data_square_layout.md#blob-share-commitment-rules
+    /// [`square_size_upper_bound`]: crate::consts::appconsts::square_size_upper_bound
+    pub fn build(
+        blobs: &[Blob],
+        txs: &[Vec<u8>],
+        square_size: usize,
+        app_version: AppVersion,
+    ) -> Result<Self> {
+        let ods = square::build_ods(blobs, txs, square_size, app_version)?;
+        ExtendedDataSquare::from_ods(ods.into_iter().map(|share| share.to_vec()).collect())
+    }
+
+    /// Reconstruct the [`ExtendedDataSquare`] from a subset of its shares.
+    ///
+    /// `shares` are the known shares together with their `(row, column)` coordinates.
+    /// Missing shares are recovered with the Leopard Reed-Solomon decoding of the rows
+    /// and columns which have at least half of their shares available, repeated until
+    /// the whole square is filled. This is always possible with any quadrant of the
+    /// square present. Each row and column root is then recomputed and compared with
+    /// the [`DataAvailabilityHeader`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if coordinates are outside of the square,
+    /// shares are not enough to recover the square, or the recovered square doesn't
+    /// match the roots of the [`DataAvailabilityHeader`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use celestia_types::ExtendedDataSquare;
+    /// # use celestia_types::ExtendedHeader;
+    /// # fn get_extended_header(height: usize) -> ExtendedHeader {
+    /// #    unimplemented!()
+    /// # }
+    /// # fn get_sampled_shares(height: usize) -> Vec<(usize, usize, Vec<u8>)> {
+    /// #    unimplemented!()
+    /// # }
+    /// let header = get_extended_header(100);
+    /// let shares = get_sampled_shares(100);
+    ///
+    /// let eds = ExtendedDataSquare::reconstruct(shares, &header.dah).unwrap();
+    /// ```
+    pub fn reconstruct<I>(shares: I, dah: &DataAvailabilityHeader) -> Result<Self>
+    where
+        I: IntoIterator<Item = (usize, usize, Vec<u8>)>,
+    {
+        let square_len = dah.square_len();
+        let mut square: Vec<Option<Vec<u8>>> = vec![None; square_len * square_len];
+
+        for (row, col, share) in shares {
+            if row >= square_len {
+                return Err(Error::EdsIndexOutOfRange(row));
+            }
+            if col >= square_len {
+                return Err(Error::EdsIndexOutOfRange(col));
+            }
+            if share.len() != SHARE_SIZE {
+                return Err(Error::InvalidShareSize(share.len()));
+            }
+
+            square[row * square_len + col] = Some(share);
+        }
+
+        while square.iter().any(Option::is_none) {
+            let mut recovered_any = false;
+
+            for axis in [AxisType::Row, AxisType::Col] {
+                for index in 0..square_len {
+                    recovered_any |= recover_axis(&mut square, square_len, axis, index)?;
+                }
+            }
+
+            if !recovered_any {
+                return Err(Error::EdsNotRecoverable);
+            }
+        }
+
+        let data_square = square.into_iter().flatten().map(Bytes::from).collect();
+        let eds = ExtendedDataSquare::new(data_square, LEOPARD_CODEC.to_owned())?;
+        dah.verify_eds(&eds)?;
+
+        Ok(eds)
+    }
+
     /// Return row with index
-    pub fn row(&self, index: usize) -> Result<Vec<Vec<u8>>> {
+    pub fn row(&self, index: usize) -> Result<Vec<Bytes>> {
         Ok(self
             .data_square
             .get(index * self.square_len..(index + 1) * self.square_len)
@@ -169,28 +362,90 @@ impl ExtendedDataSquare {
     }
 
     /// Return colum with index
-    pub fn column(&self, mut index: usize) -> Result<Vec<Vec<u8>>> {
-        let mut r = Vec::with_capacity(self.square_len);
-        while index < self.data_square.len() {
-            r.push(
-                self.data_square
-                    .get(index)
-                    .ok_or(Error::EdsIndexOutOfRange(index))?
-                    .to_vec(),
-            );
-            index += self.square_len;
+    pub fn column(&self, index: usize) -> Result<Vec<Bytes>> {
+        if index >= self.square_len {
+            return Err(Error::EdsIndexOutOfRange(index));
         }
-        Ok(r)
+
+        Ok(self
+            .data_square
+            .iter()
+            .skip(index)
+            .step_by(self.square_len)
+            .cloned()
+            .collect())
     }
 
     /// Return column or row with the provided index
-    pub fn axis(&self, axis: AxisType, index: usize) -> Result<Vec<Vec<u8>>> {
+    pub fn axis(&self, axis: AxisType, index: usize) -> Result<Vec<Bytes>> {
         match axis {
             AxisType::Col => self.column(index),
             AxisType::Row => self.row(index),
         }
     }
 
+    /// Return the share at the given `row` and `column` of the square.
+    ///
+    /// Shares outside of the original data square are the parity ones, see [`Share::parity`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the coordinates are out of the square,
+    /// or the share has invalid size.
+    pub fn share(&self, row: usize, column: usize) -> Result<&Share> {
+        if row >= self.square_len {
+            return Err(Error::EdsIndexOutOfRange(row));
+        }
+        if column >= self.square_len {
+            return Err(Error::EdsIndexOutOfRange(column));
+        }
+
+        Share::from_raw_ref(&self.data_square[row * self.square_len + column])
+    }
+
+    /// Iterate over the shares of the original data square, with their `(row, column)`
+    /// coordinates, row by row.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use celestia_types::consts::appconsts::AppVersion;
+    /// use celestia_types::nmt::Namespace;
+    /// use celestia_types::{Blob, ExtendedDataSquare, Share};
+    ///
+    /// let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+    /// let blob = Blob::new(namespace, b"foo".to_vec(), AppVersion::V2).unwrap();
+    /// let eds = ExtendedDataSquare::build(&[blob], &[], 2, AppVersion::V2).unwrap();
+    ///
+    /// for (row, column, share) in eds.iter_ods() {
+    ///     let share = Share::from_raw(share).unwrap();
+    ///
+    ///     if (row, column) == (0, 0) {
+    ///         assert_eq!(share.namespace(), namespace);
+    ///     }
+    /// }
+    /// ```
+    pub fn iter_ods(&self) -> impl Iterator<Item = (usize, usize, &[u8])> {
+        let ods_len = self.square_len / 2;
+        self.iter_all()
+            .filter(move |&(row, column, _)| row < ods_len && column < ods_len)
+    }
+
+    /// Iterate over all the shares of the square, with their `(row, column)` coordinates,
+    /// row by row.
+    ///
+    /// Unlike the shares of the original data square, the parity shares don't have
+    /// a valid [`Namespace`] and can't be parsed as a [`Share`].
+    ///
+    /// [`Share`]: crate::Share
+    pub fn iter_all(&self) -> impl Iterator<Item = (usize, usize, &[u8])> {
+        let square_len = self.square_len;
+        self.data_square
+            .iter()
+            .enumerate()
+            .map(move |(i, share)| (i / square_len, i % square_len, &share[..]))
+    }
+
     /// Get EDS square length
     pub fn square_len(&self) -> usize {
         self.square_len
@@ -204,51 +459,498 @@ impl ExtendedDataSquare {
         dah: &DataAvailabilityHeader,
         height: u64,
     ) -> Result<Vec<NamespacedData>> {
-        let mut data = Vec::new();
+        self.get_namespaced_data_stream(namespace, dah, height)
+            .collect()
+    }
 
-        for i in 0u16..self.square_len as u16 {
-            let row_root = dah.row_root(i.into()).unwrap();
-            if !row_root.contains::<NamespacedSha2Hasher>(*namespace) {
+    /// Lazily yield the shares that belong to the provided namespace in the EDS.
+    ///
+    /// This is a streaming counterpart of [`get_namespaced_data`]. Rows which don't
+    /// contain the namespace are skipped based on the roots from [`DataAvailabilityHeader`],
+    /// and the inclusion proof for a row is computed only when that row is requested,
+    /// so consumers can start verifying the first rows before the rest is processed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use celestia_types::nmt::Namespace;
+    /// # use celestia_types::{ExtendedDataSquare, ExtendedHeader};
+    /// # fn get_extended_data_square(height: usize) -> ExtendedDataSquare {
+    /// #    unimplemented!()
+    /// # }
+    /// # fn get_extended_header(height: usize) -> ExtendedHeader {
+    /// #    unimplemented!()
+    /// # }
+    /// #
+    /// let block_height = 100;
+    /// let eds = get_extended_data_square(block_height);
+    /// let header = get_extended_header(block_height);
+    ///
+    /// let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+    ///
+    /// for namespaced_data in eds.get_namespaced_data_stream(namespace, &header.dah, 100) {
+    ///     namespaced_data.unwrap().validate(&header.dah).unwrap();
+    /// }
+    /// ```
+    ///
+    /// [`get_namespaced_data`]: ExtendedDataSquare::get_namespaced_data
+    pub fn get_namespaced_data_stream<'a>(
+        &'a self,
+        namespace: Namespace,
+        dah: &'a DataAvailabilityHeader,
+        height: u64,
+    ) -> impl Iterator<Item = Result<NamespacedData>> + 'a {
+        (0u16..self.square_len as u16)
+            .filter(move |&i| {
+                dah.row_root(i.into())
+                    .map(|root| root.contains::<NamespacedSha2Hasher>(*namespace))
+                    .unwrap_or(true)
+            })
+            .map(move |i| self.namespaced_data_in_row(namespace, dah, i, height))
+    }
+
+    /// Prove that the provided namespace has no shares in the EDS.
+    ///
+    /// Returns the index and the absence [`NamespaceProof`] of each row whose root
+    /// range covers the namespace. Rows which don't cover the namespace need no proof,
+    /// as their roots in the [`DataAvailabilityHeader`] already show the namespace
+    /// isn't there, so the result is empty if the namespace falls between the ranges
+    /// of two consecutive rows.
+    ///
+    /// Each proof can be verified against the corresponding row root with
+    /// [`EMPTY_LEAVES`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NamespacePresent`] if any row contains shares of the namespace.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use celestia_types::nmt::{Namespace, EMPTY_LEAVES};
+    /// # use celestia_types::{ExtendedDataSquare, ExtendedHeader};
+    /// # fn get_extended_data_square(height: usize) -> ExtendedDataSquare {
+    /// #    unimplemented!()
+    /// # }
+    /// # fn get_extended_header(height: usize) -> ExtendedHeader {
+    /// #    unimplemented!()
+    /// # }
+    /// #
+    /// let eds = get_extended_data_square(100);
+    /// let header = get_extended_header(100);
+    ///
+    /// let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+    ///
+    /// for (row, proof) in eds.get_namespace_proof_of_absence(namespace, &header.dah).unwrap() {
+    ///     let root = header.dah.row_root(row.into()).unwrap();
+    ///     proof
+    ///         .verify_complete_namespace(&root, EMPTY_LEAVES, *namespace)
+    ///         .unwrap();
+    /// }
+    /// ```
+    ///
+    /// [`EMPTY_LEAVES`]: crate::nmt::EMPTY_LEAVES
+    pub fn get_namespace_proof_of_absence(
+        &self,
+        namespace: Namespace,
+        dah: &DataAvailabilityHeader,
+    ) -> Result<Vec<(u16, NamespaceProof)>> {
+        let mut proofs = Vec::new();
+
+        for index in 0u16..self.square_len as u16 {
+            let root = dah
+                .row_root(index.into())
+                .ok_or(Error::EdsIndexOutOfRange(index.into()))?;
+
+            if !root.contains::<NamespacedSha2Hasher>(*namespace) {
                 continue;
             }
 
-            let mut shares = Vec::with_capacity(self.square_len);
-            let mut tree = Nmt::with_hasher(NamespacedSha2Hasher::with_ignore_max_ns(true));
-            for (col, s) in self.row(i.into())?.iter().enumerate() {
-                let ns = if col < self.square_len / 2 {
+            let (mut tree, shares) = self.row_tree(namespace, index)?;
+
+            if !shares.is_empty() {
+                return Err(Error::NamespacePresent(index.into()));
+            }
+
+            proofs.push((index, tree.get_namespace_proof(*namespace).into()));
+        }
+
+        Ok(proofs)
+    }
+
+    /// Reconstruct all the [`Blob`]s of the namespace stored in the EDS.
+    ///
+    /// Unlike [`Blob::reconstruct_all`], the returned blobs know their
+    /// [`index`] in the original data square. Their [`Commitment`]s are computed
+    /// following the rules of the given [`AppVersion`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the shares of the namespace don't form
+    /// valid blobs.
+    ///
+    /// [`index`]: Blob::index
+    /// [`Commitment`]: crate::Commitment
+    pub fn get_blobs(&self, namespace: Namespace, app_version: AppVersion) -> Result<Vec<Blob>> {
+        let ods_width = self.square_len / 2;
+        let mut shares = Vec::new();
+
+        for row in 0..ods_width {
+            let start = row * self.square_len;
+
+            for (col, share) in self.data_square[start..start + ods_width]
+                .iter()
+                .enumerate()
+            {
+                if share[..NS_SIZE] == *namespace.as_bytes() {
+                    shares.push(((row * ods_width + col) as u64, &share[..]));
+                }
+            }
+        }
+
+        Blob::reconstruct_from_shares(
+            shares
+                .into_iter()
+                .map(|(index, share)| (Some(index), share)),
+            app_version,
+        )
+    }
+
+    /// Prove the inclusion of the [`Blob`] in the EDS.
+    ///
+    /// Returns the [`NamespaceProof`] of the blob's shares in each row it spans, in
+    /// the same form as the `blob.GetProof` of the celestia node.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the [`index`] of the blob is not known,
+    /// or the shares at that index don't belong to the blob.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use celestia_types::consts::appconsts::AppVersion;
+    /// use celestia_types::nmt::Namespace;
+    /// use celestia_types::{Blob, ExtendedDataSquare};
+    ///
+    /// let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+    /// let blob = Blob::new(namespace, vec![1; 1000], AppVersion::V2).unwrap();
+    ///
+    /// let eds = ExtendedDataSquare::build(&[blob], &[], 2, AppVersion::V2).unwrap();
+    /// let blob = eds.get_blobs(namespace, AppVersion::V2).unwrap().remove(0);
+    ///
+    /// // 3 shares of the blob span both rows of the original data square
+    /// let proofs = eds.get_blob_proof(&blob).unwrap();
+    /// assert_eq!(proofs.len(), 2);
+    /// ```
+    ///
+    /// [`index`]: Blob::index
+    pub fn get_blob_proof(&self, blob: &Blob) -> Result<Vec<NamespaceProof>> {
+        let ods_width = self.square_len / 2;
+        let range = blob.share_range(ods_width)?;
+        let shares = blob.to_shares()?;
+
+        let first_row = range.start / ods_width;
+        let last_row = (range.end - 1) / ods_width;
+        let mut proofs = Vec::with_capacity(last_row - first_row + 1);
+
+        for row in first_row..=last_row {
+            let start = range.start.max(row * ods_width);
+            let end = range.end.min((row + 1) * ods_width);
+
+            let (mut tree, _) = self.row_tree(blob.namespace, row as u16)?;
+            let row_start = row * ods_width;
+            let (leaves, proof) = tree.get_range_with_proof(start - row_start..end - row_start);
+
+            let expected = &shares[start - range.start..end - range.start];
+            if leaves
+                .iter()
+                .zip(expected)
+                .any(|(leaf, share)| leaf != share.as_ref())
+            {
+                return Err(Error::BlobNotInSquare(range.start));
+            }
+
+            proofs.push(proof.into());
+        }
+
+        Ok(proofs)
+    }
+
+    /// Prove the inclusion of the range of shares of the original data square in the
+    /// data root of the block.
+    ///
+    /// Shares are indexed row by row in the original data square, with `start` inclusive
+    /// and `end` exclusive, as in the `share.ProveShares` of celestia-node. The resulting
+    /// [`RangeProof`] holds the [`NamespaceProof`] of the shares in each of the rows and
+    /// the merkle proofs of the row roots in the data root, so it can be checked with
+    /// [`verify_share_range_proof`] or passed to the Blobstream verifiers.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the range is empty, doesn't fit in the original
+    /// data square or the shares belong to different namespaces.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use celestia_types::consts::appconsts::AppVersion;
+    /// use celestia_types::nmt::Namespace;
+    /// use celestia_types::{verify_share_range_proof, Blob, DataAvailabilityHeader, ExtendedDataSquare};
+    ///
+    /// let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+    /// let blob = Blob::new(namespace, vec![1; 1000], AppVersion::V2).unwrap();
+    ///
+    /// let eds = ExtendedDataSquare::build(&[blob], &[], 2, AppVersion::V2).unwrap();
+    /// let dah = DataAvailabilityHeader::from_eds(&eds).unwrap();
+    ///
+    /// // blob takes the 3 last shares of the original data square
+    /// let proof = eds.prove_share_range(1, 4, &dah).unwrap();
+    /// verify_share_range_proof(&proof, 1, 4, &dah.hash()).unwrap();
+    /// ```
+    ///
+    /// [`verify_share_range_proof`]: crate::verify_share_range_proof
+    pub fn prove_share_range(
+        &self,
+        start: usize,
+        end: usize,
+        dah: &DataAvailabilityHeader,
+    ) -> Result<RangeProof> {
+        let ods_width = self.square_len / 2;
+
+        if end > ods_width * ods_width {
+            return Err(Error::EdsIndexOutOfRange(end));
+        }
+        if start >= end {
+            return Err(Error::EdsIndexOutOfRange(start));
+        }
+
+        let first_row = start / ods_width;
+        let last_row = (end - 1) / ods_width;
+        let namespace = Namespace::from_raw(&self.share(first_row, start % ods_width)?[..NS_SIZE])?;
+
+        let mut data = Vec::with_capacity(end - start);
+        let mut share_proofs = Vec::with_capacity(last_row - first_row + 1);
+
+        for row in first_row..=last_row {
+            let row_start = row * ods_width;
+            let range =
+                start.max(row_start) - row_start..end.min(row_start + ods_width) - row_start;
+
+            let (mut tree, _) = self.row_tree(namespace, row as u16)?;
+            let (leaves, proof) = tree.get_range_with_proof(range);
+
+            if leaves
+                .iter()
+                .any(|leaf| leaf[..NS_SIZE] != *namespace.as_bytes())
+            {
+                return Err(Error::MixedNamespaceRange(start, end));
+            }
+
+            share_proofs.push(proof.into());
+            data.extend(leaves);
+        }
+
+        Ok(RangeProof {
+            data,
+            share_proofs,
+            namespace,
+            row_proof: dah.row_proof(first_row..=last_row)?,
+        })
+    }
+
+    /// Get the [`NamespaceMultiProof`] of inclusion of the shares at the given columns
+    /// of the row.
+    ///
+    /// The proof can be verified against the row root from the [`DataAvailabilityHeader`]
+    /// with [`NamespaceProof::verify_multi`]. Shares of the original data square are
+    /// proven under their own namespace, while any share in the extended part uses
+    /// [`Namespace::PARITY_SHARE`].
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the row or any of the columns is out of the
+    /// square, or no columns are given.
+    pub fn get_row_multiproof(&self, row: u16, columns: &[u16]) -> Result<NamespaceMultiProof> {
+        let row = usize::from(row);
+
+        if let Some(&col) = columns
+            .iter()
+            .find(|&&col| usize::from(col) >= self.square_len)
+        {
+            return Err(Error::EdsIndexOutOfRange(
+                row * self.square_len + usize::from(col),
+            ));
+        }
+
+        let leaf_hashes = self.row_leaf_hashes(row)?;
+        let indices: Vec<_> = columns.iter().map(|&col| u32::from(col)).collect();
+
+        NamespaceMultiProof::from_leaf_hashes(&leaf_hashes, &indices, true)
+    }
+
+    /// Compute the hashes of the leaves of the row's [`Nmt`].
+    ///
+    /// Shares of the original data square are hashed with their own namespace, while
+    /// any share in the extended part uses [`Namespace::PARITY_SHARE`].
+    ///
+    /// [`Nmt`]: crate::nmt::Nmt
+    pub(crate) fn row_leaf_hashes(&self, row: usize) -> Result<Vec<NamespacedHash>> {
+        let half = self.square_len / 2;
+        let hasher = NamespacedSha2Hasher::with_ignore_max_ns(true);
+
+        self.row(row)?
+            .iter()
+            .enumerate()
+            .map(|(col, s)| {
+                let ns = if col < half && row < half {
                     Namespace::from_raw(&s[..NS_SIZE])?
                 } else {
                     Namespace::PARITY_SHARE
                 };
+                Ok(hasher.hash_leaf_with_namespace(s, *ns))
+            })
+            .collect()
+    }
 
-                tree.push_leaf(s, *ns).map_err(Error::Nmt)?;
-                if ns == namespace {
-                    shares.push(s.clone());
-                }
+    /// Compute the root of the row or column, without copying its shares.
+    pub(crate) fn axis_root(&self, axis: AxisType, index: usize) -> Result<NamespacedHash> {
+        if index >= self.square_len {
+            return Err(Error::EdsIndexOutOfRange(index));
+        }
+
+        match axis {
+            AxisType::Row => {
+                let start = index * self.square_len;
+                compute_axis_root(&self.data_square[start..start + self.square_len], index)
             }
-            let row = RowId::new(i, height)?;
+            AxisType::Col => compute_axis_root(
+                self.data_square.iter().skip(index).step_by(self.square_len),
+                index,
+            ),
+        }
+    }
 
-            let proof = tree.get_namespace_proof(*namespace);
-            let namespaced_data_id = NamespacedDataId { row, namespace };
+    fn namespaced_data_in_row(
+        &self,
+        namespace: Namespace,
+        dah: &DataAvailabilityHeader,
+        index: u16,
+        height: u64,
+    ) -> Result<NamespacedData> {
+        if dah.row_root(index.into()).is_none() {
+            return Err(Error::EdsIndexOutOfRange(index.into()));
+        }
 
-            data.push(NamespacedData {
-                namespaced_data_id,
-                proof: proof.into(),
-                shares,
-            })
+        let (mut tree, shares) = self.row_tree(namespace, index)?;
+        let row = RowId::new(index, height)?;
+
+        let proof = tree.get_namespace_proof(*namespace);
+        let namespaced_data_id = NamespacedDataId { row, namespace };
+
+        Ok(NamespacedData {
+            namespaced_data_id,
+            proof: proof.into(),
+            shares,
+        })
+    }
+
+    /// Build the [`Nmt`] of the row, collecting the shares of the namespace.
+    fn row_tree(&self, namespace: Namespace, index: u16) -> Result<(Nmt, Vec<Bytes>)> {
+        let mut shares = Vec::with_capacity(self.square_len);
+        let mut tree = Nmt::with_hasher(NamespacedSha2Hasher::with_ignore_max_ns(true));
+
+        for (col, s) in self.row(index.into())?.iter().enumerate() {
+            let ns = if col < self.square_len / 2 {
+                Namespace::from_raw(&s[..NS_SIZE])?
+            } else {
+                Namespace::PARITY_SHARE
+            };
+
+            tree.push_leaf(s, *ns).map_err(Error::Nmt)?;
+            if ns == namespace {
+                shares.push(s.clone());
+            }
         }
 
-        Ok(data)
+        Ok((tree, shares))
+    }
+}
+
+/// Compute the root of the row or column with the given index from all of its shares.
+///
+/// Shares of the original data square are pushed under their own namespace,
+/// while any share in the extended part uses [`Namespace::PARITY_SHARE`].
+#[cfg(feature = "std")]
+pub(crate) fn compute_axis_root<I>(shares: I, index: usize) -> Result<NamespacedHash>
+where
+    I: IntoIterator,
+    I::IntoIter: ExactSizeIterator,
+    I::Item: AsRef<[u8]>,
+{
+    let shares = shares.into_iter();
+    let mut hasher = NmtRootHasher::new();
+    let half = shares.len() / 2;
+
+    for (i, s) in shares.enumerate() {
+        let s = s.as_ref();
+        let ns = if i < half && index < half {
+            Namespace::from_raw(&s[..NS_SIZE])?
+        } else {
+            Namespace::PARITY_SHARE
+        };
+
+        hasher.push_leaf(s, ns)?;
     }
+
+    Ok(hasher.root())
+}
+
+/// Recover the missing shares of the row or column, if enough shares are present.
+///
+/// Returns whether any share was recovered.
+#[cfg(feature = "std")]
+fn recover_axis(
+    square: &mut [Option<Vec<u8>>],
+    square_len: usize,
+    axis: AxisType,
+    index: usize,
+) -> Result<bool> {
+    let positions: Vec<_> = match axis {
+        AxisType::Row => (0..square_len).map(|i| index * square_len + i).collect(),
+        AxisType::Col => (0..square_len).map(|i| i * square_len + index).collect(),
+    };
+
+    let present = positions.iter().filter(|&&p| square[p].is_some()).count();
+
+    if present == square_len || present < square_len / 2 {
+        return Ok(false);
+    }
+
+    // missing shards are represented by empty vectors
+    let mut shards: Vec<Vec<u8>> = positions
+        .iter()
+        .map(|&p| square[p].clone().unwrap_or_default())
+        .collect();
+
+    leopard_codec::reconstruct(&mut shards, square_len / 2)?;
+
+    for (p, shard) in positions.into_iter().zip(shards) {
+        square[p] = Some(shard);
+    }
+
+    Ok(true)
 }
 
+#[cfg(feature = "std")]
 #[derive(Deserialize)]
 struct RawExtendedDataSquare {
     #[serde(with = "celestia_tendermint_proto::serializers::bytes::vec_base64string")]
-    pub data_square: Vec<Vec<u8>>,
+    pub data_square: Vec<Bytes>,
     pub codec: String,
 }
 
+#[cfg(feature = "std")]
 impl<'de> Deserialize<'de> for ExtendedDataSquare {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -268,6 +970,9 @@ impl<'de> Deserialize<'de> for ExtendedDataSquare {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::nmt::EMPTY_LEAVES;
+    use celestia_tendermint_proto::v0_34::types::{BlobTx, IndexWrapper};
+    use prost::Message;
 
     #[test]
     fn axis_type_serialization() {
@@ -313,4 +1018,374 @@ mod tests {
             row.validate(&dah).unwrap();
         }
     }
+
+    #[test]
+    fn get_namespaced_data_stream() {
+        let eds_json = include_str!("../test_data/shwap_samples/eds.json");
+        let eds: ExtendedDataSquare = serde_json::from_str(eds_json).unwrap();
+        let dah_json = include_str!("../test_data/shwap_samples/dah.json");
+        let dah: DataAvailabilityHeader = serde_json::from_str(dah_json).unwrap();
+
+        let height = 45577;
+        let namespace = Namespace::new_v0(&[1, 187]).unwrap();
+
+        let mut stream = eds.get_namespaced_data_stream(namespace, &dah, height);
+
+        let first = stream.next().unwrap().unwrap();
+        first.validate(&dah).unwrap();
+        assert_eq!(first.shares.len(), 1);
+
+        let second = stream.next().unwrap().unwrap();
+        second.validate(&dah).unwrap();
+        assert_eq!(second.shares.len(), 4);
+
+        assert!(stream.next().is_none());
+
+        let collected = eds.get_namespaced_data(namespace, &dah, height).unwrap();
+        assert_eq!(collected.len(), 2);
+        assert_eq!(collected[0].shares, first.shares);
+        assert_eq!(collected[1].shares, second.shares);
+    }
+
+    #[test]
+    fn get_namespace_proof_of_absence() {
+        let (eds, dah) = test_eds_and_dah();
+
+        // Namespace in the middle of the range of the row
+        let namespace = Namespace::new_v0(&[1, 171]).unwrap();
+        let proofs = eds.get_namespace_proof_of_absence(namespace, &dah).unwrap();
+        assert!(!proofs.is_empty());
+
+        for (row, proof) in proofs {
+            let root = dah.row_root(row.into()).unwrap();
+            assert!(root.contains::<NamespacedSha2Hasher>(*namespace));
+            assert!(proof.is_of_absence());
+            proof
+                .verify_complete_namespace(&root, EMPTY_LEAVES, *namespace)
+                .unwrap();
+        }
+
+        // Namespace present in the square
+        let namespace = Namespace::new_v0(&[1, 187]).unwrap();
+        let err = eds
+            .get_namespace_proof_of_absence(namespace, &dah)
+            .unwrap_err();
+        assert!(matches!(err, Error::NamespacePresent(_)));
+
+        // Namespace not covered by any row
+        let namespace = Namespace::new_v0(&[0xff; 10]).unwrap();
+        let proofs = eds.get_namespace_proof_of_absence(namespace, &dah).unwrap();
+        assert!(proofs.is_empty());
+    }
+
+    #[test]
+    fn get_row_multiproof() {
+        let (eds, dah) = test_eds_and_dah();
+        let half = eds.square_len() / 2;
+
+        for row in [0, eds.square_len() - 1] {
+            let columns = [0, half as u16 - 1, half as u16, eds.square_len() as u16 - 1];
+            let proof = eds.get_row_multiproof(row as u16, &columns).unwrap();
+            let shares = eds.row(row).unwrap();
+
+            let leaves: Vec<_> = columns
+                .iter()
+                .map(|&col| {
+                    let share = &shares[usize::from(col)];
+                    let ns = if usize::from(col) < half && row < half {
+                        Namespace::from_raw(&share[..NS_SIZE]).unwrap()
+                    } else {
+                        Namespace::PARITY_SHARE
+                    };
+                    (ns, &share[..])
+                })
+                .collect();
+
+            let root = dah.row_root(row).unwrap();
+            NamespaceProof::verify_multi(&proof, &root, &leaves).unwrap();
+
+            let other_root = dah.row_root(row ^ 1).unwrap();
+            NamespaceProof::verify_multi(&proof, &other_root, &leaves).unwrap_err();
+        }
+
+        let out_of_square = eds.square_len() as u16;
+        eds.get_row_multiproof(0, &[out_of_square]).unwrap_err();
+        eds.get_row_multiproof(out_of_square, &[0]).unwrap_err();
+    }
+
+    fn test_eds_and_dah() -> (ExtendedDataSquare, DataAvailabilityHeader) {
+        let eds_json = include_str!("../test_data/shwap_samples/eds.json");
+        let eds: ExtendedDataSquare = serde_json::from_str(eds_json).unwrap();
+        let dah_json = include_str!("../test_data/shwap_samples/dah.json");
+        let dah: DataAvailabilityHeader = serde_json::from_str(dah_json).unwrap();
+        (eds, dah)
+    }
+
+    fn shares_where(
+        eds: &ExtendedDataSquare,
+        keep: impl Fn(usize, usize) -> bool,
+    ) -> Vec<(usize, usize, Vec<u8>)> {
+        let len = eds.square_len();
+        (0..len * len)
+            .map(|i| (i / len, i % len))
+            .filter(|&(row, col)| keep(row, col))
+            .map(|(row, col)| (row, col, eds.data_square[row * len + col].to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn reconstruct_from_original_data_square() {
+        let (eds, dah) = test_eds_and_dah();
+        let half = eds.square_len() / 2;
+
+        let shares = shares_where(&eds, |row, col| row < half && col < half);
+        let reconstructed = ExtendedDataSquare::reconstruct(shares, &dah).unwrap();
+
+        assert_eq!(reconstructed, eds);
+    }
+
+    #[test]
+    fn reconstruct_from_parity_quadrant() {
+        let (eds, dah) = test_eds_and_dah();
+        let half = eds.square_len() / 2;
+
+        let shares = shares_where(&eds, |row, col| row >= half && col >= half);
+        let reconstructed = ExtendedDataSquare::reconstruct(shares, &dah).unwrap();
+
+        assert_eq!(reconstructed, eds);
+    }
+
+    #[test]
+    fn reconstruct_from_scattered_shares() {
+        let (eds, dah) = test_eds_and_dah();
+        let len = eds.square_len();
+
+        // a diagonal band of half of each row
+        let shares = shares_where(&eds, |row, col| (col + len - row) % len < len / 2);
+        let reconstructed = ExtendedDataSquare::reconstruct(shares, &dah).unwrap();
+
+        assert_eq!(reconstructed, eds);
+    }
+
+    #[test]
+    fn reconstruct_not_enough_shares() {
+        let (eds, dah) = test_eds_and_dah();
+        let half = eds.square_len() / 2;
+
+        // one share missing from the original data square
+        let shares = shares_where(&eds, |row, col| row < half && col < half && row + col > 0);
+        let err = ExtendedDataSquare::reconstruct(shares, &dah).unwrap_err();
+
+        assert!(matches!(err, Error::EdsNotRecoverable));
+    }
+
+    #[test]
+    fn reconstruct_root_mismatch() {
+        let (eds, dah) = test_eds_and_dah();
+        let half = eds.square_len() / 2;
+
+        let mut shares = shares_where(&eds, |row, col| row < half && col < half);
+        // corrupt data of the last share, leaving its namespace intact
+        let share = &mut shares.last_mut().unwrap().2;
+        share[SHARE_SIZE - 1] ^= 0xff;
+
+        let err = ExtendedDataSquare::reconstruct(shares, &dah).unwrap_err();
+
+        assert!(matches!(err, Error::RootMismatch));
+    }
+
+    #[test]
+    fn reconstruct_out_of_range() {
+        let (eds, dah) = test_eds_and_dah();
+        let len = eds.square_len();
+
+        let shares = vec![(len, 0, eds.data_square[0].to_vec())];
+        let err = ExtendedDataSquare::reconstruct(shares, &dah).unwrap_err();
+
+        assert!(matches!(err, Error::EdsIndexOutOfRange(_)));
+    }
+
+    fn original_data_square(eds: &ExtendedDataSquare) -> Vec<Vec<u8>> {
+        let half = eds.square_len() / 2;
+        shares_where(eds, |row, col| row < half && col < half)
+            .into_iter()
+            .map(|(_, _, share)| share)
+            .collect()
+    }
+
+    #[test]
+    fn share_coordinates() {
+        let (eds, _) = test_eds_and_dah();
+        let len = eds.square_len();
+
+        for (row, column, share) in eds.iter_all() {
+            assert_eq!(share, eds.share(row, column).unwrap().as_ref());
+            assert_eq!(eds.row(row).unwrap()[column], share);
+            assert_eq!(eds.column(column).unwrap()[row], share);
+        }
+        assert_eq!(eds.iter_all().count(), len * len);
+
+        let ods: Vec<_> = eds.iter_ods().map(|(_, _, share)| share.to_vec()).collect();
+        assert_eq!(ods, original_data_square(&eds));
+
+        assert!(matches!(
+            eds.share(len, 0).unwrap_err(),
+            Error::EdsIndexOutOfRange(_)
+        ));
+        assert!(matches!(
+            eds.column(len).unwrap_err(),
+            Error::EdsIndexOutOfRange(_)
+        ));
+    }
+
+    #[test]
+    fn axes_share_memory_with_square() {
+        let (eds, _) = test_eds_and_dah();
+        let len = eds.square_len();
+
+        let row = eds.row(1).unwrap();
+        let column = eds.column(2).unwrap();
+        assert_eq!(row[2].as_ptr(), eds.data_square[len + 2].as_ptr());
+        assert_eq!(column[1].as_ptr(), eds.data_square[len + 2].as_ptr());
+    }
+
+    #[test]
+    fn from_ods() {
+        let (eds, _) = test_eds_and_dah();
+
+        let extended = ExtendedDataSquare::from_ods(original_data_square(&eds)).unwrap();
+        assert_eq!(extended, eds);
+
+        let err = ExtendedDataSquare::from_ods(vec![vec![0; SHARE_SIZE]; 3]).unwrap_err();
+        assert!(matches!(err, Error::EdsInvalidDimentions));
+    }
+
+    #[test]
+    fn build_matches_go_square() {
+        let (eds, _) = test_eds_and_dah();
+        let ods: Vec<_> = original_data_square(&eds)
+            .iter()
+            .map(|share| Share::from_raw(share).unwrap())
+            .collect();
+
+        // the sample has a single pay for blobs transaction in the first share
+        assert_eq!(ods[0].namespace(), Namespace::PAY_FOR_BLOB);
+        let mut units = ods[0].payload();
+        let len = prost::encoding::decode_varint(&mut units).unwrap() as usize;
+        let wrapper = IndexWrapper::decode(&units[..len]).unwrap();
+
+        let blobs = wrapper
+            .share_indexes
+            .iter()
+            .map(|&index| {
+                let shares: Vec<_> = ods[index as usize..]
+                    .iter()
+                    .take_while(|share| share.namespace() == ods[index as usize].namespace())
+                    .cloned()
+                    .collect();
+                let len = shares[0].sequence_length().unwrap() as usize;
+                let data = shares
+                    .iter()
+                    .flat_map(|share| share.payload())
+                    .take(len)
+                    .copied()
+                    .collect();
+                Blob::new(shares[0].namespace(), data, AppVersion::V2)
+                    .unwrap()
+                    .into()
+            })
+            .collect();
+
+        let blob_tx = BlobTx {
+            tx: wrapper.tx,
+            blobs,
+            type_id: "BLOB".to_owned(),
+        };
+
+        let built =
+            ExtendedDataSquare::build(&[], &[blob_tx.encode_to_vec()], 4, AppVersion::V2).unwrap();
+        assert_eq!(built, eds);
+    }
+
+    #[test]
+    fn blob_proofs() {
+        let (eds, dah) = test_eds_and_dah();
+        let namespace = Namespace::new_v0(&[1, 187]).unwrap();
+
+        let blobs = eds.get_blobs(namespace, AppVersion::V2).unwrap();
+        assert!(!blobs.is_empty());
+
+        for blob in &blobs {
+            blob.validate().unwrap();
+
+            let range = blob.share_range(eds.square_len() / 2).unwrap();
+            let first_row = range.start / (eds.square_len() / 2);
+            let shares = blob.to_shares().unwrap();
+            let mut shares = &shares[..];
+
+            for (i, proof) in eds.get_blob_proof(blob).unwrap().into_iter().enumerate() {
+                let root = dah.row_root(first_row + i).unwrap();
+                let len = proof.end_idx() as usize - proof.start_idx() as usize;
+                let (row_shares, rest) = shares.split_at(len);
+
+                proof.verify_range(&root, row_shares, *namespace).unwrap();
+                shares = rest;
+            }
+            assert!(shares.is_empty());
+        }
+
+        // blob at a wrong index can't be proven
+        let mut json = serde_json::to_value(&blobs[0]).unwrap();
+        json["index"] = 0.into();
+        let misplaced: Blob = serde_json::from_value(json).unwrap();
+        assert!(matches!(
+            eds.get_blob_proof(&misplaced),
+            Err(Error::BlobNotInSquare(0))
+        ));
+    }
+
+    #[test]
+    fn prove_share_range() {
+        let (eds, dah) = test_eds_and_dah();
+        let namespace = Namespace::new_v0(&[1, 187]).unwrap();
+        let ods_width = eds.square_len() / 2;
+
+        let blob = eds.get_blobs(namespace, AppVersion::V2).unwrap().remove(0);
+        let range = blob.share_range(ods_width).unwrap();
+
+        let proof = eds.prove_share_range(range.start, range.end, &dah).unwrap();
+        assert_eq!(proof.namespace, namespace);
+        assert_eq!(
+            proof.data,
+            blob.to_shares()
+                .unwrap()
+                .iter()
+                .map(|s| s.to_vec())
+                .collect::<Vec<_>>()
+        );
+        crate::verify_share_range_proof(&proof, range.start, range.end, &dah.hash()).unwrap();
+
+        // the proof doesn't hold for other positions
+        crate::verify_share_range_proof(&proof, range.start + 1, range.end + 1, &dah.hash())
+            .unwrap_err();
+
+        // a subrange of the blob is proven on its own
+        let proof = eds
+            .prove_share_range(range.start + 1, range.end, &dah)
+            .unwrap();
+        crate::verify_share_range_proof(&proof, range.start + 1, range.end, &dah.hash()).unwrap();
+
+        assert!(matches!(
+            eds.prove_share_range(range.start - 1, range.end, &dah),
+            Err(Error::MixedNamespaceRange(..))
+        ));
+        assert!(matches!(
+            eds.prove_share_range(0, ods_width * ods_width + 1, &dah),
+            Err(Error::EdsIndexOutOfRange(..))
+        ));
+        assert!(matches!(
+            eds.prove_share_range(3, 3, &dah),
+            Err(Error::EdsIndexOutOfRange(3))
+        ));
+    }
 }
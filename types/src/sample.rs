@@ -10,7 +10,7 @@
 use std::mem::size_of;
 
 use blockstore::block::CidError;
-use bytes::{BufMut, BytesMut};
+use bytes::{Bytes, BytesMut};
 use celestia_proto::share::p2p::shwap::Sample as RawSample;
 use celestia_tendermint_proto::Protobuf;
 use cid::CidGeneric;
@@ -20,16 +20,20 @@ use nmt_rs::NamespaceMerkleHasher;
 use serde::{Deserialize, Serialize};
 
 use crate::nmt::{Namespace, NamespaceProof, NamespacedSha2Hasher, Nmt, NS_SIZE};
-use crate::row::RowId;
+use crate::row::{IdLayout, RowId};
 use crate::rsmt2d::{AxisType, ExtendedDataSquare};
 use crate::{DataAvailabilityHeader, Error, Result};
 
 /// The size of the [`SampleId`] hash in `multihash`.
 const SAMPLE_ID_SIZE: usize = SampleId::size();
 /// The code of the [`SampleId`] hashing algorithm in `multihash`.
-pub const SAMPLE_ID_MULTIHASH_CODE: u64 = 0x7801;
+pub const SAMPLE_ID_MULTIHASH_CODE: u64 = 0x7811;
 /// The id of codec used for the [`SampleId`] in `Cid`s.
-pub const SAMPLE_ID_CODEC: u64 = 0x7800;
+pub const SAMPLE_ID_CODEC: u64 = 0x7810;
+/// The code of the [`SampleId`] hashing algorithm in `multihash` in the [`IdLayout::Draft`].
+pub const DRAFT_SAMPLE_ID_MULTIHASH_CODE: u64 = 0x7801;
+/// The id of codec used for the [`SampleId`] in `Cid`s in the [`IdLayout::Draft`].
+pub const DRAFT_SAMPLE_ID_CODEC: u64 = 0x7800;
 
 /// Identifies a particular [`Share`] located in the [`row`] of the [`ExtendedDataSquare`].
 ///
@@ -53,7 +57,7 @@ pub struct Sample {
     /// Indication whether sampling was done row or column-wise
     pub sample_proof_type: AxisType,
     /// Share that is being sampled
-    pub share: Vec<u8>,
+    pub share: Bytes,
     /// Proof of the inclusion of the share
     pub proof: NamespaceProof,
 }
@@ -178,15 +182,14 @@ impl Sample {
 
 impl Protobuf<RawSample> for Sample {}
 
-impl TryFrom<RawSample> for Sample {
-    type Error = Error;
-
-    fn try_from(sample: RawSample) -> Result<Sample, Self::Error> {
+impl Sample {
+    /// Decode the [`Sample`] from its protobuf representation with the given [`IdLayout`].
+    pub fn from_raw(sample: RawSample, layout: IdLayout) -> Result<Sample> {
         let Some(proof) = sample.sample_proof else {
             return Err(Error::MissingProof);
         };
 
-        let sample_id = SampleId::decode(&sample.sample_id)?;
+        let sample_id = SampleId::decode(&sample.sample_id, layout)?;
         let sample_proof_type = u8::try_from(sample.sample_type)
             .map_err(|_| Error::InvalidAxis(sample.sample_type))?
             .try_into()?;
@@ -198,22 +201,35 @@ impl TryFrom<RawSample> for Sample {
             proof: proof.try_into()?,
         })
     }
-}
 
-impl From<Sample> for RawSample {
-    fn from(sample: Sample) -> RawSample {
+    /// Convert the [`Sample`] to its protobuf representation with the given [`IdLayout`].
+    pub fn into_raw(self, layout: IdLayout) -> RawSample {
         let mut sample_id_bytes = BytesMut::with_capacity(SAMPLE_ID_SIZE);
-        sample.sample_id.encode(&mut sample_id_bytes);
+        self.sample_id.encode(&mut sample_id_bytes, layout);
 
         RawSample {
             sample_id: sample_id_bytes.to_vec(),
-            sample_share: sample.share.to_vec(),
-            sample_type: sample.sample_proof_type as u8 as i32,
-            sample_proof: Some(sample.proof.into()),
+            sample_share: self.share,
+            sample_type: self.sample_proof_type as u8 as i32,
+            sample_proof: Some(self.proof.into()),
         }
     }
 }
 
+impl TryFrom<RawSample> for Sample {
+    type Error = Error;
+
+    fn try_from(sample: RawSample) -> Result<Sample, Self::Error> {
+        Sample::from_raw(sample, IdLayout::default())
+    }
+}
+
+impl From<Sample> for RawSample {
+    fn from(sample: Sample) -> RawSample {
+        sample.into_raw(IdLayout::default())
+    }
+}
+
 impl SampleId {
     /// Create a new [`SampleId`] for the given index of the [`ExtendedDataSquare`] in a block.
     ///
@@ -278,31 +294,39 @@ impl SampleId {
         RowId::size() + size_of::<u16>()
     }
 
-    fn encode(&self, bytes: &mut BytesMut) {
-        self.row.encode(bytes);
-        bytes.put_u16_le(self.index);
+    /// The id of codec used for the [`SampleId`] in `Cid`s of the given [`IdLayout`].
+    pub const fn codec(layout: IdLayout) -> u64 {
+        match layout {
+            IdLayout::V1 => SAMPLE_ID_CODEC,
+            IdLayout::Draft => DRAFT_SAMPLE_ID_CODEC,
+        }
     }
 
-    fn decode(buffer: &[u8]) -> Result<Self, CidError> {
-        if buffer.len() != SAMPLE_ID_SIZE {
-            return Err(CidError::InvalidMultihashLength(buffer.len()));
+    /// The code of the [`SampleId`] hashing algorithm in `multihash` of the given [`IdLayout`].
+    pub const fn multihash_code(layout: IdLayout) -> u64 {
+        match layout {
+            IdLayout::V1 => SAMPLE_ID_MULTIHASH_CODE,
+            IdLayout::Draft => DRAFT_SAMPLE_ID_MULTIHASH_CODE,
         }
-
-        let (row_id, index) = buffer.split_at(RowId::size());
-        // RawSampleId len is defined as RowId::size + u16::size, these are safe
-        Ok(Self {
-            row: RowId::decode(row_id)?,
-            index: u16::from_le_bytes(index.try_into().unwrap()),
-        })
     }
-}
 
-impl<const S: usize> TryFrom<CidGeneric<S>> for SampleId {
-    type Error = CidError;
+    /// Convert the [`SampleId`] to the `Cid` of the given [`IdLayout`].
+    pub fn to_cid(&self, layout: IdLayout) -> CidGeneric<SAMPLE_ID_SIZE> {
+        let mut bytes = BytesMut::with_capacity(SAMPLE_ID_SIZE);
+        self.encode(&mut bytes, layout);
+        // length is correct, so unwrap is safe
+        let mh = Multihash::wrap(SampleId::multihash_code(layout), &bytes[..]).unwrap();
 
-    fn try_from(cid: CidGeneric<S>) -> Result<Self, Self::Error> {
+        CidGeneric::new_v1(SampleId::codec(layout), mh)
+    }
+
+    /// Decode the [`SampleId`] from the `Cid` of the given [`IdLayout`].
+    pub fn from_cid<const S: usize>(
+        cid: &CidGeneric<S>,
+        layout: IdLayout,
+    ) -> Result<Self, CidError> {
         let codec = cid.codec();
-        if codec != SAMPLE_ID_CODEC {
+        if codec != SampleId::codec(layout) {
             return Err(CidError::InvalidCidCodec(codec));
         }
 
@@ -314,28 +338,48 @@ impl<const S: usize> TryFrom<CidGeneric<S>> for SampleId {
         }
 
         let code = hash.code();
-        if code != SAMPLE_ID_MULTIHASH_CODE {
+        if code != SampleId::multihash_code(layout) {
             return Err(CidError::InvalidMultihashCode(
                 code,
-                SAMPLE_ID_MULTIHASH_CODE,
+                SampleId::multihash_code(layout),
             ));
         }
 
-        SampleId::decode(hash.digest())
+        SampleId::decode(hash.digest(), layout)
+    }
+
+    fn encode(&self, bytes: &mut BytesMut, layout: IdLayout) {
+        self.row.encode(bytes, layout);
+        layout.put_u16(bytes, self.index);
+    }
+
+    fn decode(buffer: &[u8], layout: IdLayout) -> Result<Self, CidError> {
+        if buffer.len() != SAMPLE_ID_SIZE {
+            return Err(CidError::InvalidMultihashLength(buffer.len()));
+        }
+
+        let (row_id, mut index) = buffer.split_at(RowId::size());
+        // RawSampleId len is defined as RowId::size + u16::size, these are safe
+        Ok(Self {
+            row: RowId::decode(row_id, layout)?,
+            index: layout.get_u16(&mut index),
+        })
     }
 }
 
-impl TryFrom<SampleId> for CidGeneric<SAMPLE_ID_SIZE> {
+impl<const S: usize> TryFrom<CidGeneric<S>> for SampleId {
     type Error = CidError;
 
-    fn try_from(sample_id: SampleId) -> Result<Self, Self::Error> {
-        let mut bytes = BytesMut::with_capacity(SAMPLE_ID_SIZE);
-        // length is correct, so unwrap is safe
-        sample_id.encode(&mut bytes);
+    fn try_from(cid: CidGeneric<S>) -> Result<Self, Self::Error> {
+        SampleId::from_cid(&cid, IdLayout::default())
+    }
+}
 
-        let mh = Multihash::wrap(SAMPLE_ID_MULTIHASH_CODE, &bytes[..]).unwrap();
+impl TryFrom<SampleId> for CidGeneric<SAMPLE_ID_SIZE> {
+    type Error = CidError;
 
-        Ok(CidGeneric::new_v1(SAMPLE_ID_CODEC, mh))
+    fn try_from(sample_id: SampleId) -> Result<Self, Self::Error> {
+        Ok(sample_id.to_cid(IdLayout::default()))
     }
 }
 
@@ -343,17 +387,18 @@ impl TryFrom<SampleId> for CidGeneric<SAMPLE_ID_SIZE> {
 mod tests {
     use super::*;
     use crate::nmt::Namespace;
+    use prost::Message;
 
     #[test]
     fn round_trip() {
         let sample_id = SampleId::new(5, 10, 100).unwrap();
-        let cid = CidGeneric::try_from(sample_id).unwrap();
+        let cid = sample_id.to_cid(IdLayout::V1);
 
         let multihash = cid.hash();
         assert_eq!(multihash.code(), SAMPLE_ID_MULTIHASH_CODE);
         assert_eq!(multihash.size(), SAMPLE_ID_SIZE as u8);
 
-        let deserialized_sample_id = SampleId::try_from(cid).unwrap();
+        let deserialized_sample_id = SampleId::from_cid(&cid, IdLayout::V1).unwrap();
         assert_eq!(sample_id, deserialized_sample_id);
     }
 
@@ -371,6 +416,29 @@ mod tests {
 
     #[test]
     fn from_buffer() {
+        let bytes = [
+            0x01, // CIDv1
+            0x90, 0xF0, 0x01, // CID codec = 7810
+            0x91, 0xF0, 0x01, // multihash code = 7811
+            0x0C, // len = SAMPLE_ID_SIZE = 12
+            0, 0, 0, 0, 0, 0, 0, 64, // block height = 64
+            0, 7, // row index = 7
+            0, 5, // sample index = 5
+        ];
+
+        let cid = CidGeneric::<SAMPLE_ID_SIZE>::read_bytes(bytes.as_ref()).unwrap();
+        assert_eq!(cid.codec(), SAMPLE_ID_CODEC);
+        let mh = cid.hash();
+        assert_eq!(mh.code(), SAMPLE_ID_MULTIHASH_CODE);
+        assert_eq!(mh.size(), SAMPLE_ID_SIZE as u8);
+        let sample_id = SampleId::from_cid(&cid, IdLayout::V1).unwrap();
+        assert_eq!(sample_id.row.index, 7);
+        assert_eq!(sample_id.row.block_height, 64);
+        assert_eq!(sample_id.index, 5);
+    }
+
+    #[test]
+    fn from_buffer_draft_layout() {
         let bytes = [
             0x01, // CIDv1
             0x80, 0xF0, 0x01, // CID codec = 7800
@@ -382,21 +450,21 @@ mod tests {
         ];
 
         let cid = CidGeneric::<SAMPLE_ID_SIZE>::read_bytes(bytes.as_ref()).unwrap();
-        assert_eq!(cid.codec(), SAMPLE_ID_CODEC);
-        let mh = cid.hash();
-        assert_eq!(mh.code(), SAMPLE_ID_MULTIHASH_CODE);
-        assert_eq!(mh.size(), SAMPLE_ID_SIZE as u8);
-        let sample_id = SampleId::try_from(cid).unwrap();
+        let sample_id = SampleId::from_cid(&cid, IdLayout::Draft).unwrap();
         assert_eq!(sample_id.row.index, 7);
         assert_eq!(sample_id.row.block_height, 64);
         assert_eq!(sample_id.index, 5);
+        assert_eq!(sample_id.to_cid(IdLayout::Draft), cid);
+
+        // draft codec of the sample is the codec of the row in v1
+        SampleId::from_cid(&cid, IdLayout::V1).unwrap_err();
     }
 
     #[test]
     fn multihash_invalid_code() {
         let multihash = Multihash::<SAMPLE_ID_SIZE>::wrap(888, &[0; SAMPLE_ID_SIZE]).unwrap();
         let cid = CidGeneric::<SAMPLE_ID_SIZE>::new_v1(SAMPLE_ID_CODEC, multihash);
-        let code_err = SampleId::try_from(cid).unwrap_err();
+        let code_err = SampleId::from_cid(&cid, IdLayout::V1).unwrap_err();
         assert_eq!(
             code_err,
             CidError::InvalidMultihashCode(888, SAMPLE_ID_MULTIHASH_CODE)
@@ -409,14 +477,15 @@ mod tests {
             Multihash::<SAMPLE_ID_SIZE>::wrap(SAMPLE_ID_MULTIHASH_CODE, &[0; SAMPLE_ID_SIZE])
                 .unwrap();
         let cid = CidGeneric::<SAMPLE_ID_SIZE>::new_v1(4321, multihash);
-        let codec_err = SampleId::try_from(cid).unwrap_err();
+        let codec_err = SampleId::from_cid(&cid, IdLayout::V1).unwrap_err();
         assert!(matches!(codec_err, CidError::InvalidCidCodec(4321)));
     }
 
     #[test]
     fn decode_sample_bytes() {
         let bytes = include_bytes!("../test_data/shwap_samples/sample.data");
-        let msg = Sample::decode(&bytes[..]).unwrap();
+        let raw = RawSample::decode(&bytes[..]).unwrap();
+        let msg = Sample::from_raw(raw, IdLayout::Draft).unwrap();
 
         assert_eq!(msg.sample_id.index, 1);
         assert_eq!(msg.sample_id.row.index, 0);
@@ -1,3 +1,23 @@
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+/// Decode a hex string, optionally prefixed with `0x`.
+pub(crate) fn decode_hex(s: &str) -> crate::Result<Vec<u8>> {
+    let digits = s.strip_prefix("0x").unwrap_or(s);
+    hex::decode(digits).map_err(|e| crate::Error::InvalidHex(e.to_string()))
+}
+
+/// Deserialize `null` as the [`Default`] value of the type.
+pub(crate) fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: serde::Deserialize<'de> + Default,
+{
+    let value = <Option<T> as serde::Deserialize>::deserialize(deserializer)?;
+    Ok(value.unwrap_or_default())
+}
+
+#[cfg(feature = "std")]
 pub(crate) mod none_as_negative_one {
     use serde::{Deserialize, Deserializer, Serializer};
 
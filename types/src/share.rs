@@ -1,21 +1,31 @@
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
 use blockstore::block::{Block, CidError};
 use celestia_proto::share::p2p::shrex::nd::NamespaceRowResponse as RawNamespacedRow;
 use celestia_tendermint_proto::Protobuf;
+#[cfg(feature = "std")]
 use cid::CidGeneric;
+#[cfg(feature = "std")]
 use multihash::Multihash;
+#[cfg(feature = "std")]
 use nmt_rs::simple_merkle::tree::MerkleHash;
+#[cfg(feature = "std")]
 use nmt_rs::NamespaceMerkleHasher;
 use serde::{Deserialize, Serialize};
 
 use crate::consts::appconsts;
-use crate::nmt::{
-    Namespace, NamespaceProof, NamespacedSha2Hasher, NMT_CODEC, NMT_ID_SIZE, NMT_MULTIHASH_CODE,
-    NS_SIZE,
-};
+use crate::nmt::{Namespace, NamespaceProof, NS_SIZE};
+#[cfg(feature = "std")]
+use crate::nmt::{NamespacedSha2Hasher, NMT_CODEC, NMT_ID_SIZE, NMT_MULTIHASH_CODE};
+use crate::serializers::null_as_default;
 use crate::{Error, Result};
 
+mod compact;
 mod info_byte;
 
+pub use compact::parse_compact_shares;
+pub(crate) use compact::{parse_compact_units, parse_compact_units_in_range};
 pub use info_byte::InfoByte;
 
 const SHARE_SEQUENCE_LENGTH_OFFSET: usize = NS_SIZE + appconsts::SHARE_INFO_BYTES;
@@ -38,6 +48,34 @@ pub struct NamespacedRow {
     pub proof: NamespaceProof,
 }
 
+/// [`Share`]s of a single [`Namespace`] included in the block at the given height.
+///
+/// It is the item of the `share.SubscribeToShares` subscription of the Celestia nodes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SharesAtHeight {
+    /// [`Share`]s included in the block, empty if there were none in the [`Namespace`].
+    #[serde(deserialize_with = "null_as_default")]
+    pub shares: Vec<Share>,
+    /// Height of the block.
+    pub height: u64,
+}
+
+/// The kind of a [`Share`], determining the layout of its payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShareType {
+    /// A share of the transactions, using one of the reserved namespaces.
+    ///
+    /// Compact shares have the reserved bytes, pointing to the start of the first unit
+    /// in the share, placed right before the payload.
+    Compact,
+    /// A share of a [`Blob`] in the user-defined namespace.
+    ///
+    /// [`Blob`]: crate::Blob
+    Sparse,
+    /// A share that doesn't carry any data and is used only to fill the data square.
+    Padding,
+}
+
 /// A single fixed-size chunk of data which is used to form an [`ExtendedDataSquare`].
 ///
 /// All data in Celestia is split into [`Share`]s before being put into a
@@ -55,6 +93,7 @@ pub struct NamespacedRow {
 /// [`Blob::to_shares`]: crate::Blob::to_shares
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(try_from = "RawShare", into = "RawShare")]
+#[repr(transparent)]
 pub struct Share {
     /// A raw data of the share.
     pub data: [u8; appconsts::SHARE_SIZE],
@@ -92,6 +131,40 @@ impl Share {
         })
     }
 
+    /// Create a new parity [`Share`] from raw bytes.
+    ///
+    /// Parity shares are the Reed-Solomon extension of the original data square, so
+    /// unlike in [`Share::from_raw`] only their size is checked. Their namespace and
+    /// [`InfoByte`] don't carry any meaning.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the slice length isn't [`SHARE_SIZE`].
+    ///
+    /// [`SHARE_SIZE`]: crate::consts::appconsts::SHARE_SIZE
+    pub fn parity(data: &[u8]) -> Result<Self> {
+        let data = data
+            .try_into()
+            .map_err(|_| Error::InvalidShareSize(data.len()))?;
+
+        Ok(Share { data })
+    }
+
+    /// Borrow the raw bytes as a [`Share`], without copying them.
+    ///
+    /// Like in [`Share::parity`], only the size is checked, so it can be used for
+    /// any share of the [`ExtendedDataSquare`].
+    ///
+    /// [`ExtendedDataSquare`]: crate::rsmt2d::ExtendedDataSquare
+    pub(crate) fn from_raw_ref(data: &[u8]) -> Result<&Self> {
+        let data: &[u8; appconsts::SHARE_SIZE] = data
+            .try_into()
+            .map_err(|_| Error::InvalidShareSize(data.len()))?;
+
+        // SAFETY: `Share` is `repr(transparent)` over the array of the share size
+        Ok(unsafe { &*(data as *const [u8; appconsts::SHARE_SIZE] as *const Share) })
+    }
+
     /// Get the [`Namespace`] the [`Share`] belongs to.
     pub fn namespace(&self) -> Namespace {
         Namespace::new_unchecked(self.data[..NS_SIZE].try_into().unwrap())
@@ -116,9 +189,19 @@ impl Share {
         InfoByte::from_raw_unchecked(self.data[NS_SIZE])
     }
 
+    /// Get the version of the [`Share`] format, encoded in the [`InfoByte`].
+    pub fn version(&self) -> u8 {
+        self.info_byte().version()
+    }
+
+    /// Check if the [`Share`] is the first one in a sequence.
+    pub fn is_sequence_start(&self) -> bool {
+        self.info_byte().is_sequence_start()
+    }
+
     /// For first share in a sequence, return sequence length, None for continuation shares
     pub fn sequence_length(&self) -> Option<u32> {
-        if self.info_byte().is_sequence_start() {
+        if self.is_sequence_start() {
             let sequence_length_bytes = &self.data[SHARE_SEQUENCE_LENGTH_OFFSET
                 ..SHARE_SEQUENCE_LENGTH_OFFSET + appconsts::SEQUENCE_LEN_BYTES];
             Some(u32::from_be_bytes(
@@ -128,6 +211,69 @@ impl Share {
             None
         }
     }
+
+    /// Get the [`ShareType`] of the [`Share`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use celestia_types::consts::appconsts::AppVersion;
+    /// use celestia_types::nmt::Namespace;
+    /// use celestia_types::{Blob, ShareType};
+    ///
+    /// let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+    /// let blob = Blob::new(namespace, b"foo".to_vec(), AppVersion::V2).unwrap();
+    /// let shares = blob.to_shares().unwrap();
+    ///
+    /// assert_eq!(shares[0].share_type(), ShareType::Sparse);
+    /// ```
+    pub fn share_type(&self) -> ShareType {
+        let namespace = self.namespace();
+
+        if namespace == Namespace::TAIL_PADDING
+            || namespace == Namespace::PRIMARY_RESERVED_PADDING
+            || self.sequence_length() == Some(0)
+        {
+            ShareType::Padding
+        } else if namespace == Namespace::TRANSACTION || namespace == Namespace::PAY_FOR_BLOB {
+            ShareType::Compact
+        } else {
+            ShareType::Sparse
+        }
+    }
+
+    /// Get the payload of the [`Share`].
+    ///
+    /// The payload is the data that follows the [`InfoByte`], the `sequence length`
+    /// if the share starts a sequence, and the reserved bytes of the compact shares.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use celestia_types::consts::appconsts::AppVersion;
+    /// use celestia_types::nmt::Namespace;
+    /// use celestia_types::Blob;
+    ///
+    /// let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+    /// let blob = Blob::new(namespace, b"foo".to_vec(), AppVersion::V2).unwrap();
+    /// let shares = blob.to_shares().unwrap();
+    ///
+    /// assert_eq!(shares[0].sequence_length(), Some(3));
+    /// assert_eq!(&shares[0].payload()[..3], b"foo");
+    /// ```
+    pub fn payload(&self) -> &[u8] {
+        let mut start = NS_SIZE + appconsts::SHARE_INFO_BYTES;
+
+        if self.is_sequence_start() {
+            start += appconsts::SEQUENCE_LEN_BYTES;
+        }
+
+        if self.share_type() == ShareType::Compact {
+            start += appconsts::COMPACT_SHARE_RESERVED_BYTES;
+        }
+
+        &self.data[start..]
+    }
 }
 
 impl AsRef<[u8]> for Share {
@@ -136,6 +282,7 @@ impl AsRef<[u8]> for Share {
     }
 }
 
+#[cfg(feature = "std")]
 impl Block<NMT_ID_SIZE> for Share {
     fn cid(&self) -> Result<CidGeneric<NMT_ID_SIZE>, CidError> {
         let hasher = NamespacedSha2Hasher::with_ignore_max_ns(true);
@@ -239,6 +386,7 @@ impl From<NamespacedRow> for RawNamespacedRow {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::consts::appconsts::AppVersion;
     use crate::nmt::{NamespacedHash, NAMESPACED_HASH_SIZE};
     use base64::prelude::*;
 
@@ -256,6 +404,106 @@ mod tests {
         Share::from_raw(&vec![0; appconsts::SHARE_SIZE]).unwrap();
     }
 
+    #[test]
+    fn parity_share_skips_namespace_check() {
+        let raw = [0xff; appconsts::SHARE_SIZE];
+        Share::from_raw(&raw).unwrap_err();
+
+        let share = Share::parity(&raw).unwrap();
+        assert_eq!(share.as_ref(), &raw[..]);
+        Share::parity(&raw[1..]).unwrap_err();
+    }
+
+    fn share_with(namespace: Namespace, info_byte: InfoByte, rest: &[u8]) -> Share {
+        let mut raw = namespace.as_bytes().to_vec();
+        raw.push(info_byte.as_u8());
+        raw.extend_from_slice(rest);
+        raw.resize(appconsts::SHARE_SIZE, 0);
+        Share::from_raw(&raw).unwrap()
+    }
+
+    #[test]
+    fn sparse_share_accessors() {
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+        let data = vec![7; appconsts::FIRST_SPARSE_SHARE_CONTENT_SIZE + 10];
+        let shares = crate::Blob::new(namespace, data, AppVersion::V2)
+            .unwrap()
+            .to_shares()
+            .unwrap();
+
+        assert_eq!(shares[0].share_type(), ShareType::Sparse);
+        assert_eq!(shares[0].version(), appconsts::SHARE_VERSION_ZERO);
+        assert!(shares[0].is_sequence_start());
+        assert_eq!(
+            shares[0].sequence_length(),
+            Some(appconsts::FIRST_SPARSE_SHARE_CONTENT_SIZE as u32 + 10)
+        );
+        assert_eq!(
+            shares[0].payload(),
+            &[7; appconsts::FIRST_SPARSE_SHARE_CONTENT_SIZE][..]
+        );
+
+        assert_eq!(shares[1].share_type(), ShareType::Sparse);
+        assert!(!shares[1].is_sequence_start());
+        assert_eq!(shares[1].sequence_length(), None);
+        assert_eq!(
+            shares[1].payload().len(),
+            appconsts::CONTINUATION_SPARSE_SHARE_CONTENT_SIZE
+        );
+        assert_eq!(&shares[1].payload()[..10], &[7; 10]);
+    }
+
+    #[test]
+    fn compact_share_accessors() {
+        let first = share_with(
+            Namespace::TRANSACTION,
+            InfoByte::new(0, true).unwrap(),
+            &[0, 0, 0, 3, 0, 0, 0, 10, 1, 2, 3],
+        );
+
+        assert_eq!(first.share_type(), ShareType::Compact);
+        assert_eq!(first.sequence_length(), Some(3));
+        assert_eq!(
+            first.payload().len(),
+            appconsts::FIRST_COMPACT_SHARE_CONTENT_SIZE
+        );
+        assert_eq!(&first.payload()[..3], &[1, 2, 3]);
+
+        let continuation = share_with(
+            Namespace::PAY_FOR_BLOB,
+            InfoByte::new(0, false).unwrap(),
+            &[0, 0, 0, 0, 4, 5],
+        );
+
+        assert_eq!(continuation.share_type(), ShareType::Compact);
+        assert_eq!(
+            continuation.payload().len(),
+            appconsts::CONTINUATION_COMPACT_SHARE_CONTENT_SIZE
+        );
+        assert_eq!(&continuation.payload()[..2], &[4, 5]);
+    }
+
+    #[test]
+    fn padding_share_type() {
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+        let namespace_padding = share_with(namespace, InfoByte::new(0, true).unwrap(), &[]);
+        assert_eq!(namespace_padding.share_type(), ShareType::Padding);
+
+        let tail_padding = share_with(
+            Namespace::TAIL_PADDING,
+            InfoByte::new(0, true).unwrap(),
+            &[],
+        );
+        assert_eq!(tail_padding.share_type(), ShareType::Padding);
+
+        let reserved_padding = share_with(
+            Namespace::PRIMARY_RESERVED_PADDING,
+            InfoByte::new(0, true).unwrap(),
+            &[],
+        );
+        assert_eq!(reserved_padding.share_type(), ShareType::Padding);
+    }
+
     #[test]
     fn decode_presence_proof() {
         let blob_get_proof_response = r#"{
@@ -0,0 +1,256 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::consts::appconsts;
+use crate::nmt::Namespace;
+use crate::{Error, Result, Share};
+
+/// Parse the units stored in the sequence of compact shares of the given [`Namespace`].
+///
+/// Shares of the other namespaces are skipped, so it can be given the whole
+/// original data square. Each unit in the sequence is prefixed with its varint
+/// encoded length.
+///
+/// # Errors
+///
+/// Returns an error if the shares don't hold a single, complete sequence of the
+/// compact shares of version zero.
+pub fn parse_compact_shares<'a, I>(namespace: Namespace, shares: I) -> Result<Vec<Vec<u8>>>
+where
+    I: IntoIterator<Item = &'a Share>,
+{
+    Ok(parse_compact_units(namespace, shares)?
+        .into_iter()
+        .map(|(unit, _)| unit)
+        .collect())
+}
+
+/// Parse the units stored in the sequence of compact shares of the given [`Namespace`],
+/// together with the range of the shares holding each of them, length prefix included.
+///
+/// Ranges are the positions of the shares in the given iterator, counting also the
+/// skipped shares of the other namespaces.
+pub(crate) fn parse_compact_units<'a, I>(
+    namespace: Namespace,
+    shares: I,
+) -> Result<Vec<(Vec<u8>, Range<usize>)>>
+where
+    I: IntoIterator<Item = &'a Share>,
+{
+    let mut sequence: Option<(usize, Vec<u8>)> = None;
+    // position of each share of the sequence and the offset of its payload in the data
+    let mut share_offsets = Vec::new();
+
+    for (position, share) in shares.into_iter().enumerate() {
+        if share.namespace() != namespace {
+            continue;
+        }
+
+        if share.version() != appconsts::SHARE_VERSION_ZERO {
+            return Err(Error::UnsupportedShareVersion(share.version()));
+        }
+
+        match (&sequence, share.sequence_length()) {
+            (None, Some(len)) => {
+                sequence = Some((len as usize, Vec::with_capacity(len as usize)));
+            }
+            (None, None) => return Err(Error::UnexpectedContinuationShare),
+            // there is a single sequence in each of the compact namespaces
+            (Some((len, data)), Some(_)) => {
+                return Err(Error::IncompleteShareSequence(*len, data.len()))
+            }
+            (Some(_), None) => (),
+        }
+
+        let (len, data) = sequence.as_mut().expect("sequence is started");
+        share_offsets.push((position, data.len()));
+
+        let payload = share.payload();
+        let read_amount = payload.len().min(*len - data.len());
+        data.extend_from_slice(&payload[..read_amount]);
+    }
+
+    let Some((len, data)) = sequence else {
+        return Ok(Vec::new());
+    };
+
+    if data.len() != len {
+        return Err(Error::IncompleteShareSequence(len, data.len()));
+    }
+
+    split_units(&data, 0, &share_offsets, true)
+}
+
+/// Parse the units starting in the consecutive compact shares of a sequence, which don't
+/// have to include its start, together with the range of the shares holding each of them.
+///
+/// Parsing starts at the first unit pointed to by the reserved bytes of the first share.
+/// Units not completed in the given shares are skipped.
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+pub(crate) fn parse_compact_units_in_range(
+    shares: &[Share],
+) -> Result<Vec<(Vec<u8>, Range<usize>)>> {
+    let Some(first) = shares.first() else {
+        return Ok(Vec::new());
+    };
+
+    let mut data = Vec::new();
+    let mut share_offsets = Vec::with_capacity(shares.len());
+
+    for (position, share) in shares.iter().enumerate() {
+        if share.version() != appconsts::SHARE_VERSION_ZERO {
+            return Err(Error::UnsupportedShareVersion(share.version()));
+        }
+
+        share_offsets.push((position, data.len()));
+        data.extend_from_slice(share.payload());
+    }
+
+    // cut off the padding if the whole sequence is given
+    if let Some(len) = first.sequence_length() {
+        data.truncate(len as usize);
+    }
+
+    match first_unit_offset(first)? {
+        Some(start) => split_units(&data, start, &share_offsets, false),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Offset in the payload of the compact share of the first unit starting in it.
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+fn first_unit_offset(share: &Share) -> Result<Option<usize>> {
+    let payload_start = appconsts::SHARE_SIZE - share.payload().len();
+    let reserved_start = payload_start - appconsts::COMPACT_SHARE_RESERVED_BYTES;
+    let reserved = &share.as_ref()[reserved_start..payload_start];
+    // unwrap is safe, the slice has the size of the reserved bytes
+    let reserved = u32::from_be_bytes(reserved.try_into().unwrap());
+
+    match reserved as usize {
+        0 => Ok(None),
+        offset if (payload_start..appconsts::SHARE_SIZE).contains(&offset) => {
+            Ok(Some(offset - payload_start))
+        }
+        _ => Err(Error::InvalidReservedBytes(reserved)),
+    }
+}
+
+/// Split the data of a sequence, starting at the given offset, into the units prefixed
+/// with their varint encoded length. `share_offsets` holds the position of each share
+/// and the offset of its payload in the data.
+///
+/// If the data isn't `complete`, the last unit not fitting in it is skipped.
+fn split_units(
+    data: &[u8],
+    start: usize,
+    share_offsets: &[(usize, usize)],
+    complete: bool,
+) -> Result<Vec<(Vec<u8>, Range<usize>)>> {
+    // position of the share holding the byte at the given offset of the data
+    let position_of = |offset: usize| {
+        let idx = share_offsets.partition_point(|&(_, start)| start <= offset);
+        share_offsets[idx - 1].0
+    };
+
+    let mut units = Vec::new();
+    let mut buf = &data[start..];
+
+    while !buf.is_empty() {
+        let unit_start = data.len() - buf.len();
+        let unit_len = match prost::encoding::decode_varint(&mut buf) {
+            Ok(len) => len as usize,
+            Err(_) if !complete => break,
+            Err(e) => return Err(e.into()),
+        };
+
+        if unit_len > buf.len() || (unit_len == 0 && !complete) {
+            if !complete {
+                break;
+            }
+            return Err(Error::MalformedCompactShareUnit(unit_len, buf.len()));
+        }
+
+        let (unit, rest) = buf.split_at(unit_len);
+        let unit_end = data.len() - rest.len();
+
+        units.push((
+            unit.to_vec(),
+            position_of(unit_start)..position_of(unit_end - 1) + 1,
+        ));
+        buf = rest;
+    }
+
+    Ok(units)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::square::split_compact_shares;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    #[test]
+    fn parse_split_units() {
+        let units = vec![vec![1; 10], vec![2; 600], vec![3; 10], vec![4; 1500]];
+        let shares = split_compact_shares(Namespace::TRANSACTION, &units).unwrap();
+
+        let parsed = parse_compact_shares(Namespace::TRANSACTION, &shares).unwrap();
+        assert_eq!(parsed, units);
+
+        // other namespaces are skipped
+        let parsed = parse_compact_shares(Namespace::PAY_FOR_BLOB, &shares).unwrap();
+        assert!(parsed.is_empty());
+    }
+
+    #[test]
+    fn parse_units_share_ranges() {
+        let units = vec![vec![1; 10], vec![2; 600], vec![3; 10], vec![4; 1500]];
+        let mut shares = split_compact_shares(Namespace::TRANSACTION, &units).unwrap();
+        // shares of the other namespaces count into the positions
+        let other = split_compact_shares(Namespace::PAY_FOR_BLOB, &[vec![5; 10]]).unwrap();
+        shares.insert(0, other[0].clone());
+
+        let parsed = parse_compact_units(Namespace::TRANSACTION, &shares).unwrap();
+        let ranges: Vec<_> = parsed.iter().map(|(_, range)| range.clone()).collect();
+        assert_eq!(ranges, vec![1..2, 1..3, 2..3, 2..6]);
+        assert_eq!(parsed[3].0, units[3]);
+    }
+
+    #[test]
+    fn parse_units_in_range() {
+        let units = vec![vec![1; 10], vec![2; 600], vec![3; 10], vec![4; 1500]];
+        let shares = split_compact_shares(Namespace::TRANSACTION, &units).unwrap();
+
+        // parsing starts at the first unit of the first share
+        let parsed = parse_compact_units_in_range(&shares[1..3]).unwrap();
+        assert_eq!(parsed, vec![(units[2].clone(), 0..1)]);
+
+        // whole sequence is parsed as with its start
+        let parsed = parse_compact_units_in_range(&shares).unwrap();
+        assert_eq!(
+            parsed,
+            parse_compact_units(Namespace::TRANSACTION, &shares).unwrap()
+        );
+
+        // none of the units starts in the share
+        let parsed = parse_compact_units_in_range(&shares[3..4]).unwrap();
+        assert!(parsed.is_empty());
+    }
+
+    #[test]
+    fn parse_incomplete_sequence() {
+        let units = vec![vec![1; 1000]];
+        let shares = split_compact_shares(Namespace::TRANSACTION, &units).unwrap();
+
+        assert!(matches!(
+            parse_compact_shares(Namespace::TRANSACTION, &shares[..2]),
+            Err(Error::IncompleteShareSequence(..))
+        ));
+        assert!(matches!(
+            parse_compact_shares(Namespace::TRANSACTION, &shares[1..]),
+            Err(Error::UnexpectedContinuationShare)
+        ));
+    }
+}
@@ -0,0 +1,213 @@
+//! Identifiers of the shwap containers and their [`Cid`]s.
+//!
+//! Shwap containers, the [`Row`]s, [`Sample`]s and [`NamespacedData`], are addressed
+//! in bitswap with the [`Cid`]s embedding their ids as the multihash digest. This module
+//! gathers the ids and allows classifying an arbitrary [`Cid`] with [`ShwapCid`].
+//!
+//! [`Row`]: crate::row::Row
+//! [`Sample`]: crate::sample::Sample
+//! [`NamespacedData`]: crate::namespaced_data::NamespacedData
+
+use blockstore::block::CidError;
+use cid::{Cid, CidGeneric};
+use multihash::Multihash;
+
+pub use crate::namespaced_data::{NamespacedDataId, NAMESPACED_DATA_ID_CODEC};
+pub use crate::row::{EdsId, IdLayout, RowId};
+pub use crate::sample::SampleId;
+
+/// Id of a shwap container, decoded from its [`Cid`].
+///
+/// # Example
+///
+/// ```
+/// use celestia_types::shwap::{IdLayout, RowId, ShwapCid};
+///
+/// let row_id = RowId::new(3, 100).unwrap();
+/// let cid = ShwapCid::from(row_id).to_cid(IdLayout::V1);
+///
+/// let shwap_cid = ShwapCid::from_cid(&cid, IdLayout::V1).unwrap();
+/// assert_eq!(shwap_cid, ShwapCid::Row(row_id));
+/// assert_eq!(shwap_cid.block_height(), 100);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShwapCid {
+    /// [`Cid`] of a [`Row`].
+    ///
+    /// [`Row`]: crate::row::Row
+    Row(RowId),
+    /// [`Cid`] of a [`Sample`].
+    ///
+    /// [`Sample`]: crate::sample::Sample
+    Sample(SampleId),
+    /// [`Cid`] of a [`NamespacedData`].
+    ///
+    /// [`NamespacedData`]: crate::namespaced_data::NamespacedData
+    NamespacedData(NamespacedDataId),
+}
+
+impl ShwapCid {
+    /// Classify the `Cid` encoded with the given [`IdLayout`] and decode its id.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CidError::InvalidCidCodec`] if the `Cid` is not one of the shwap
+    /// containers, or the error of decoding the id otherwise.
+    pub fn from_cid<const S: usize>(
+        cid: &CidGeneric<S>,
+        layout: IdLayout,
+    ) -> Result<Self, CidError> {
+        // codecs of the rows and samples are swapped between the layouts, so they
+        // can't be used in the match patterns
+        match cid.codec() {
+            codec if codec == RowId::codec(layout) => RowId::from_cid(cid, layout).map(Self::Row),
+            codec if codec == SampleId::codec(layout) => {
+                SampleId::from_cid(cid, layout).map(Self::Sample)
+            }
+            NAMESPACED_DATA_ID_CODEC => {
+                NamespacedDataId::from_cid(cid, layout).map(Self::NamespacedData)
+            }
+            codec => Err(CidError::InvalidCidCodec(codec)),
+        }
+    }
+
+    /// Encode the id as a [`Cid`] with the given [`IdLayout`].
+    pub fn to_cid(&self, layout: IdLayout) -> Cid {
+        match self {
+            ShwapCid::Row(id) => resize_cid(&id.to_cid(layout)),
+            ShwapCid::Sample(id) => resize_cid(&id.to_cid(layout)),
+            ShwapCid::NamespacedData(id) => resize_cid(&id.to_cid(layout)),
+        }
+    }
+
+    /// [`RowId`] of the row the container is located on.
+    pub fn row_id(&self) -> RowId {
+        match self {
+            ShwapCid::Row(id) => *id,
+            ShwapCid::Sample(id) => id.row,
+            ShwapCid::NamespacedData(id) => id.row,
+        }
+    }
+
+    /// Height of the block the container belongs to.
+    pub fn block_height(&self) -> u64 {
+        self.row_id().block_height
+    }
+}
+
+impl From<RowId> for ShwapCid {
+    fn from(id: RowId) -> Self {
+        ShwapCid::Row(id)
+    }
+}
+
+impl From<SampleId> for ShwapCid {
+    fn from(id: SampleId) -> Self {
+        ShwapCid::Sample(id)
+    }
+}
+
+impl From<NamespacedDataId> for ShwapCid {
+    fn from(id: NamespacedDataId) -> Self {
+        ShwapCid::NamespacedData(id)
+    }
+}
+
+/// Detect the [`IdLayout`] of the shwap id from the codec of its `Cid` and the size
+/// of the id.
+///
+/// Codecs of the rows and samples are swapped between the layouts, so they are told
+/// apart by the size of the id. Returns `None` for the [`NamespacedDataId`]s, which
+/// have the same codec and size in all the layouts, and for the other `Cid`s.
+///
+/// # Example
+///
+/// ```
+/// use celestia_types::shwap::{detect_id_layout, IdLayout, RowId, ShwapCid};
+///
+/// let cid = ShwapCid::from(RowId::new(3, 100).unwrap()).to_cid(IdLayout::Draft);
+/// let layout = detect_id_layout(cid.codec(), cid.hash().digest().len());
+/// assert_eq!(layout, Some(IdLayout::Draft));
+/// ```
+pub fn detect_id_layout(codec: u64, id_size: usize) -> Option<IdLayout> {
+    [IdLayout::V1, IdLayout::Draft].into_iter().find(|&layout| {
+        (codec == RowId::codec(layout) && id_size == RowId::size())
+            || (codec == SampleId::codec(layout) && id_size == SampleId::size())
+    })
+}
+
+fn resize_cid<const S: usize>(cid: &CidGeneric<S>) -> Cid {
+    // shwap ids are much shorter than the default multihash size, so the unwrap is safe
+    let hash = Multihash::wrap(cid.hash().code(), cid.hash().digest()).unwrap();
+    Cid::new_v1(cid.codec(), hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nmt::Namespace;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    #[test]
+    fn round_trip_all_layouts() {
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+        let ids = [
+            ShwapCid::from(RowId::new(3, 100).unwrap()),
+            ShwapCid::from(SampleId::new(5, 4, 101).unwrap()),
+            ShwapCid::from(NamespacedDataId::new(namespace, 1, 102).unwrap()),
+        ];
+
+        for layout in [IdLayout::V1, IdLayout::Draft] {
+            for id in ids {
+                let cid = id.to_cid(layout);
+                assert_eq!(ShwapCid::from_cid(&cid, layout).unwrap(), id);
+            }
+        }
+
+        assert_eq!(ids[1].row_id(), RowId::new(1, 101).unwrap());
+        assert_eq!(ids[2].block_height(), 102);
+    }
+
+    #[test]
+    fn classify_by_layout() {
+        // codec of the rows in the draft is the one of the samples in the V1
+        let cid = ShwapCid::from(RowId::new(3, 100).unwrap()).to_cid(IdLayout::Draft);
+
+        ShwapCid::from_cid(&cid, IdLayout::V1).unwrap_err();
+        assert!(matches!(
+            ShwapCid::from_cid(&cid, IdLayout::Draft).unwrap(),
+            ShwapCid::Row(_)
+        ));
+    }
+
+    #[test]
+    fn detect_layout_from_codec() {
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+        let detect = |cid: Cid| detect_id_layout(cid.codec(), cid.hash().digest().len());
+
+        for layout in [IdLayout::V1, IdLayout::Draft] {
+            let row = ShwapCid::from(RowId::new(3, 100).unwrap()).to_cid(layout);
+            let sample = ShwapCid::from(SampleId::new(5, 4, 101).unwrap()).to_cid(layout);
+            let data = ShwapCid::from(NamespacedDataId::new(namespace, 1, 102).unwrap());
+
+            assert_eq!(detect(row), Some(layout));
+            assert_eq!(detect(sample), Some(layout));
+            assert_eq!(detect(data.to_cid(layout)), None);
+        }
+
+        let cid = Cid::new_v1(0x55, Multihash::wrap(0x12, &[1; 32]).unwrap());
+        assert_eq!(detect(cid), None);
+    }
+
+    #[test]
+    fn not_shwap_cid() {
+        let cid = Cid::new_v1(0x55, Multihash::wrap(0x12, &[1; 32]).unwrap());
+
+        assert!(matches!(
+            ShwapCid::from_cid(&cid, IdLayout::V1).unwrap_err(),
+            CidError::InvalidCidCodec(0x55)
+        ));
+    }
+}
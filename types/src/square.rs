@@ -0,0 +1,314 @@
+//! Layout of the original data square following the celestia-app square builder.
+//!
+//! The square is filled in the following order:
+//!
+//! 1. compact shares of the ordinary transactions in the [`Namespace::TRANSACTION`],
+//! 2. compact shares of the transactions paying for the blobs, wrapped with the indexes of
+//!    the blobs' first shares, in the [`Namespace::PAY_FOR_BLOB`],
+//! 3. reserved padding up to the first blob,
+//! 4. blobs sorted by the namespace, each starting at the index required by the
+//!    non-interactive default rules and separated by the namespace padding,
+//! 5. tail padding filling up the rest of the square.
+
+use celestia_tendermint_proto::v0_34::types::{BlobTx, IndexWrapper};
+use prost::Message;
+
+use crate::blob::commitment::subtree_width;
+use crate::consts::appconsts::{self, AppVersion};
+use crate::nmt::Namespace;
+use crate::{Blob, Error, InfoByte, Result, Share};
+
+/// Type id of the [`BlobTx`].
+const BLOB_TX_TYPE_ID: &str = "BLOB";
+/// Type id of the [`IndexWrapper`].
+pub(crate) const INDEX_WRAPPER_TYPE_ID: &str = "INDX";
+/// Share index used to estimate the size of the [`IndexWrapper`]s before the blobs are placed.
+const fn worst_case_share_index(app_version: AppVersion) -> u32 {
+    let square_size = appconsts::square_size_upper_bound(app_version);
+    (square_size * square_size) as u32
+}
+
+/// A transaction paying for the blobs, together with the indexes where the blobs start.
+struct PayForBlobs {
+    tx: Vec<u8>,
+    share_indexes: Vec<u32>,
+}
+
+/// A blob to be placed in the square.
+struct BlobElement {
+    shares: Vec<Share>,
+    /// Index of the [`PayForBlobs`] and of the blob in it, `None` for blobs without one.
+    pfb: Option<(usize, usize)>,
+}
+
+/// Lay out the transactions and blobs in the original data square of the given width,
+/// following the rules of the given [`AppVersion`].
+///
+/// Transactions decoding as the [`BlobTx`] have their blobs placed in the square and
+/// are themselves stored as the [`IndexWrapper`]s, all the other transactions are
+/// stored as they are.
+pub(crate) fn build_ods(
+    blobs: &[Blob],
+    txs: &[Vec<u8>],
+    square_size: usize,
+    app_version: AppVersion,
+) -> Result<Vec<Share>> {
+    if !square_size.is_power_of_two()
+        || square_size > appconsts::square_size_upper_bound(app_version)
+    {
+        return Err(Error::InvalidSquareSize(square_size));
+    }
+
+    let mut normal_txs = Vec::new();
+    let mut pfbs = Vec::new();
+    let mut elements = Vec::new();
+
+    for tx in txs {
+        match BlobTx::decode(&tx[..]) {
+            Ok(blob_tx) if blob_tx.type_id == BLOB_TX_TYPE_ID => {
+                let pfb_index = pfbs.len();
+
+                for (blob_index, raw_blob) in blob_tx.blobs.into_iter().enumerate() {
+                    let blob = Blob::from_raw(raw_blob, app_version)?;
+                    elements.push(BlobElement {
+                        shares: blob.to_shares()?,
+                        pfb: Some((pfb_index, blob_index)),
+                    });
+                }
+
+                pfbs.push(PayForBlobs {
+                    tx: blob_tx.tx,
+                    share_indexes: vec![
+                        worst_case_share_index(app_version);
+                        elements.len() - pfb_index
+                    ],
+                });
+            }
+            _ => normal_txs.push(tx.clone()),
+        }
+    }
+
+    for blob in blobs {
+        elements.push(BlobElement {
+            shares: blob.to_shares()?,
+            pfb: None,
+        });
+    }
+
+    // stable sort keeps the order of the blobs within the same namespace
+    elements.sort_by_key(|element| element.shares[0].namespace());
+
+    let tx_shares = split_compact_shares(Namespace::TRANSACTION, &normal_txs)?;
+    let pfb_shares_estimate =
+        split_compact_shares(Namespace::PAY_FOR_BLOB, &index_wrappers(&pfbs))?.len();
+
+    // place the blobs after the space reserved for the compact shares
+    let non_reserved_start = tx_shares.len() + pfb_shares_estimate;
+    let mut cursor = non_reserved_start;
+    let mut blob_starts = Vec::with_capacity(elements.len());
+
+    let threshold = appconsts::subtree_root_threshold(app_version);
+
+    for element in &elements {
+        let len = element.shares.len();
+        let width = subtree_width(len as u64, threshold) as usize;
+        let start = cursor.div_ceil(width) * width;
+
+        if let Some((pfb_index, blob_index)) = element.pfb {
+            pfbs[pfb_index].share_indexes[blob_index] = start as u32;
+        }
+
+        blob_starts.push(start);
+        cursor = start + len;
+    }
+
+    let total_shares = square_size * square_size;
+    if cursor > total_shares {
+        return Err(Error::SquareTooSmall(square_size));
+    }
+
+    let pfb_shares = split_compact_shares(Namespace::PAY_FOR_BLOB, &index_wrappers(&pfbs))?;
+    let first_blob_start = blob_starts.first().copied().unwrap_or(non_reserved_start);
+
+    let mut ods = Vec::with_capacity(total_shares);
+    ods.extend(tx_shares);
+    ods.extend(pfb_shares);
+    fill_padding(
+        &mut ods,
+        Namespace::PRIMARY_RESERVED_PADDING,
+        first_blob_start,
+    )?;
+
+    for (element, start) in elements.into_iter().zip(blob_starts) {
+        // padding between the blobs uses the namespace of the preceding one
+        if let Some(namespace) = ods.last().map(Share::namespace) {
+            fill_padding(&mut ods, namespace, start)?;
+        }
+        ods.extend(element.shares);
+    }
+
+    fill_padding(&mut ods, Namespace::TAIL_PADDING, total_shares)?;
+
+    Ok(ods)
+}
+
+fn index_wrappers(pfbs: &[PayForBlobs]) -> Vec<Vec<u8>> {
+    pfbs.iter()
+        .map(|pfb| {
+            IndexWrapper {
+                tx: pfb.tx.clone(),
+                share_indexes: pfb.share_indexes.clone(),
+                type_id: INDEX_WRAPPER_TYPE_ID.to_owned(),
+            }
+            .encode_to_vec()
+        })
+        .collect()
+}
+
+/// Split the units into a sequence of compact shares.
+///
+/// Each unit is prefixed with its varint encoded length. The reserved bytes of each share
+/// hold the offset of the first unit starting in it, or zero if there is none.
+pub(crate) fn split_compact_shares(namespace: Namespace, units: &[Vec<u8>]) -> Result<Vec<Share>> {
+    let mut data = Vec::new();
+    let mut unit_starts = Vec::with_capacity(units.len());
+
+    for unit in units {
+        unit_starts.push(data.len());
+        prost::encoding::encode_varint(unit.len() as u64, &mut data);
+        data.extend_from_slice(unit);
+    }
+
+    let sequence_len =
+        u32::try_from(data.len()).map_err(|_| Error::ShareSequenceLenExceeded(data.len()))?;
+
+    let mut shares = Vec::new();
+    let mut offset = 0;
+    let mut unit_starts = unit_starts.into_iter().peekable();
+
+    while offset < data.len() {
+        let is_first_share = offset == 0;
+        let mut share = Vec::with_capacity(appconsts::SHARE_SIZE);
+
+        share.extend_from_slice(namespace.as_bytes());
+        share.push(InfoByte::new(appconsts::SHARE_VERSION_ZERO, is_first_share)?.as_u8());
+        if is_first_share {
+            share.extend_from_slice(&sequence_len.to_be_bytes());
+        }
+
+        let header_len = share.len() + appconsts::COMPACT_SHARE_RESERVED_BYTES;
+        let end = data.len().min(offset + appconsts::SHARE_SIZE - header_len);
+
+        let mut reserved = 0u32;
+        while let Some(start) = unit_starts.next_if(|start| *start < end) {
+            if reserved == 0 {
+                reserved = (header_len + start - offset) as u32;
+            }
+        }
+
+        share.extend_from_slice(&reserved.to_be_bytes());
+        share.extend_from_slice(&data[offset..end]);
+        share.resize(appconsts::SHARE_SIZE, 0);
+
+        shares.push(Share::from_raw(&share)?);
+        offset = end;
+    }
+
+    Ok(shares)
+}
+
+/// Append the padding shares of the namespace until the square has `until` shares.
+fn fill_padding(ods: &mut Vec<Share>, namespace: Namespace, until: usize) -> Result<()> {
+    if ods.len() >= until {
+        return Ok(());
+    }
+
+    let mut share = Vec::with_capacity(appconsts::SHARE_SIZE);
+    share.extend_from_slice(namespace.as_bytes());
+    share.push(InfoByte::new(appconsts::SHARE_VERSION_ZERO, true)?.as_u8());
+    // padding is an empty sequence
+    share.resize(appconsts::SHARE_SIZE, 0);
+
+    let padding = Share::from_raw(&share)?;
+    ods.resize(until, padding);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ShareType;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    #[test]
+    fn compact_shares_reserved_bytes() {
+        let units = vec![vec![1; 10], vec![2; 600], vec![3; 10]];
+        let shares = split_compact_shares(Namespace::TRANSACTION, &units).unwrap();
+
+        assert_eq!(shares.len(), 2);
+        // 1 byte of length + 10 bytes, 2 bytes of length + 600 bytes, 1 + 10
+        assert_eq!(shares[0].sequence_length(), Some(624));
+        assert_eq!(
+            shares.iter().map(Share::share_type).collect::<Vec<_>>(),
+            [ShareType::Compact; 2]
+        );
+
+        let reserved = |share: &Share, offset: usize| {
+            u32::from_be_bytes(share.data[offset..offset + 4].try_into().unwrap())
+        };
+        // first unit starts right after the reserved bytes
+        assert_eq!(reserved(&shares[0], 34), 38);
+        // the third unit starts at byte 613 of the data, that is 139 bytes into the second share
+        assert_eq!(reserved(&shares[1], 30), 34 + 139);
+    }
+
+    #[test]
+    fn blobs_follow_non_interactive_default_rules() {
+        let ns1 = Namespace::new_v0(&[1, 1]).unwrap();
+        let ns2 = Namespace::new_v0(&[1, 2]).unwrap();
+        // 70 shares need a subtree width of 2
+        let big = Blob::new(ns2, vec![7; 478 * 70], AppVersion::V2).unwrap();
+        let small = Blob::new(ns1, vec![1; 100], AppVersion::V2).unwrap();
+
+        // transaction taking 2 shares
+        let ods = build_ods(
+            &[big.clone(), small.clone()],
+            &[vec![5; 600]],
+            16,
+            AppVersion::V2,
+        )
+        .unwrap();
+        assert_eq!(ods.len(), 256);
+
+        assert_eq!(ods[0].namespace(), Namespace::TRANSACTION);
+        assert_eq!(ods[1].namespace(), Namespace::TRANSACTION);
+        // blobs are sorted by namespace
+        assert_eq!(ods[2], small.to_shares().unwrap()[0]);
+        // namespace padding of the preceding blob aligns the big one to the subtree width
+        assert_eq!(ods[3].namespace(), ns1);
+        assert_eq!(ods[3].share_type(), ShareType::Padding);
+        let big_shares = big.to_shares().unwrap();
+        assert_eq!(&ods[4..4 + big_shares.len()], &big_shares[..]);
+        assert!(ods[4 + big_shares.len()..]
+            .iter()
+            .all(|share| share.namespace() == Namespace::TAIL_PADDING));
+    }
+
+    #[test]
+    fn square_too_small() {
+        let ns = Namespace::new_v0(&[1, 1]).unwrap();
+        let blob = Blob::new(ns, vec![1; 478 * 5], AppVersion::V2).unwrap();
+
+        assert!(matches!(
+            build_ods(&[blob], &[], 2, AppVersion::V2),
+            Err(Error::SquareTooSmall(2))
+        ));
+        assert!(matches!(
+            build_ods(&[], &[], 3, AppVersion::V2),
+            Err(Error::InvalidSquareSize(3))
+        ));
+    }
+}
@@ -1,16 +1,24 @@
 //! Types and interfaces for accessing Celestia's state-relevant information.
 
+mod abci_query;
 mod address;
 mod balance;
+mod proof;
 mod query_delegation;
 mod tx;
+mod tx_status;
 
+pub use self::abci_query::{AbciQuery, AbciQueryResponse};
 pub use self::address::{AccAddress, Address, AddressKind, AddressTrait, ConsAddress, ValAddress};
 pub use self::balance::Balance;
+pub use self::proof::{balance_key, BalanceProof, BANK_STORE_KEY};
 pub use self::query_delegation::{
     QueryDelegationResponse, QueryRedelegationsResponse, QueryUnbondingDelegationResponse,
 };
-pub use self::tx::{RawTx, TxResponse};
+pub use self::tx::{
+    decode_txs, tx_inclusion_proof, verify_tx_inclusion_proof, RawTx, Tx, TxResponse,
+};
+pub use self::tx_status::{TxStatus, TxStatusResponse};
 
 /// A 256-bit unsigned integer.
 pub type Uint = ruint::aliases::U256;
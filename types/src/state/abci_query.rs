@@ -0,0 +1,175 @@
+use celestia_tendermint::merkle::proof::ProofOps;
+use serde::{Deserialize, Serialize};
+
+use crate::state::{Balance, BalanceProof, Uint};
+use crate::{Error, Result};
+
+/// Response of the consensus node's `abci_query` JSON-RPC method.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AbciQueryResponse {
+    /// Result of the query.
+    pub response: AbciQuery,
+}
+
+/// Result of the query to the application state.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AbciQuery {
+    /// Response code, `0` on success.
+    #[serde(default)]
+    pub code: u32,
+    /// Output of the application's logger.
+    #[serde(default)]
+    pub log: String,
+    /// Queried key.
+    #[serde(
+        default,
+        with = "celestia_tendermint_proto::serializers::bytes::base64string"
+    )]
+    pub key: Vec<u8>,
+    /// Value stored under the key, empty if the key is absent.
+    #[serde(
+        default,
+        with = "celestia_tendermint_proto::serializers::bytes::base64string"
+    )]
+    pub value: Vec<u8>,
+    /// Merkle proof of the value, if requested.
+    #[serde(default)]
+    pub proof_ops: Option<ProofOps>,
+    /// Height at which the state was queried.
+    #[serde(with = "celestia_tendermint_proto::serializers::from_str")]
+    pub height: u64,
+    /// Namespace of the response code.
+    #[serde(default)]
+    pub codespace: String,
+}
+
+impl AbciQuery {
+    /// Decode the balance of the given denomination and its proof from the response.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the query failed, the proof is missing
+    /// or either balance or proof are malformed.
+    pub fn into_balance_with_proof(self, denom: &str) -> Result<(Balance, BalanceProof)> {
+        if self.code != 0 {
+            return Err(Error::AbciQuery(self.code, self.log));
+        }
+
+        let proof = self.proof_ops.ok_or(Error::MissingProof)?.try_into()?;
+
+        let balance = if self.value.is_empty() {
+            Balance {
+                denom: denom.to_owned(),
+                amount: Uint::ZERO,
+            }
+        } else {
+            // bank module stores only the amount, as the string of the `math.Int`
+            let amount = String::from_utf8_lossy(&self.value);
+
+            Balance {
+                denom: denom.to_owned(),
+                amount: amount
+                    .parse()
+                    .map_err(|_| Error::InvalidBalanceAmount(amount.into_owned()))?,
+            }
+        };
+
+        Ok((balance, proof))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use celestia_tendermint::merkle::proof::ProofOp;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    #[test]
+    fn deserialize_failed_query() {
+        let s = r#"{
+          "response": {
+            "code": 1,
+            "log": "invalid height",
+            "info": "",
+            "index": "0",
+            "key": null,
+            "value": null,
+            "proofOps": null,
+            "height": "0",
+            "codespace": "sdk"
+          }
+        }"#;
+        let query: AbciQueryResponse = serde_json::from_str(s).unwrap();
+
+        assert_eq!(query.response.code, 1);
+        assert!(matches!(
+            query.response.into_balance_with_proof("utia"),
+            Err(Error::AbciQuery(1, _))
+        ));
+    }
+
+    #[test]
+    fn decode_balance_amount() {
+        let ops = [
+            ("ics23:iavl", b"key".to_vec()),
+            ("ics23:simple", b"bank".to_vec()),
+        ]
+        .into_iter()
+        .map(|(field_type, key)| ProofOp {
+            field_type: field_type.to_owned(),
+            key,
+            data: vec![],
+        })
+        .collect();
+        let query = AbciQuery {
+            code: 0,
+            log: String::new(),
+            key: vec![],
+            // base64 `MTIzNA==` in the response of the node
+            value: b"1234".to_vec(),
+            proof_ops: Some(ProofOps { ops }),
+            height: 10,
+            codespace: String::new(),
+        };
+
+        let (balance, _) = query.clone().into_balance_with_proof("utia").unwrap();
+        assert_eq!(balance.denom, "utia");
+        assert_eq!(balance.amount, Uint::from(1234));
+
+        let malformed = AbciQuery {
+            value: b"12a4".to_vec(),
+            ..query
+        };
+        assert!(matches!(
+            malformed.into_balance_with_proof("utia"),
+            Err(Error::InvalidBalanceAmount(_))
+        ));
+    }
+
+    #[test]
+    fn deserialize_zero_balance_without_proof() {
+        let s = r#"{
+          "response": {
+            "code": 0,
+            "log": "",
+            "key": "AhQBAgMEBQYHCAkKCwwNDg8QERITFHV0aWE=",
+            "value": null,
+            "proofOps": null,
+            "height": "1234",
+            "codespace": ""
+          }
+        }"#;
+        let query: AbciQueryResponse = serde_json::from_str(s).unwrap();
+
+        assert_eq!(query.response.height, 1234);
+        assert_eq!(query.response.key[0], 0x02);
+        assert!(query.response.value.is_empty());
+        assert!(matches!(
+            query.response.into_balance_with_proof("utia"),
+            Err(Error::MissingProof)
+        ));
+    }
+}
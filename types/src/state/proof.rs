@@ -0,0 +1,306 @@
+use celestia_tendermint::merkle::proof::{ProofOp, ProofOps};
+use ics23::commitment_proof::Proof;
+use ics23::{CommitmentProof, ExistenceProof, HostFunctionsManager};
+use prost::Message;
+
+use crate::state::{AccAddress, AddressTrait, Balance, Uint};
+use crate::{bail_verification, verification_error, Error, ExtendedHeader, Result};
+
+/// Name of the bank module's store in the application's multistore.
+pub const BANK_STORE_KEY: &str = "bank";
+
+/// Prefix of the balances in the bank module's store.
+const BALANCES_PREFIX: u8 = 0x02;
+
+/// Type of the proof operation proving a key in the IAVL tree of a module's store.
+const IAVL_PROOF_OP: &str = "ics23:iavl";
+/// Type of the proof operation proving a module's store root in the multistore.
+const MULTISTORE_PROOF_OP: &str = "ics23:simple";
+
+/// Returns the key under which the bank module stores the account's balance of the denomination.
+///
+/// Value stored under the key is the amount, encoded as the decimal string of the `math.Int`.
+pub fn balance_key(address: &AccAddress, denom: &str) -> Vec<u8> {
+    let address = address.as_bytes();
+    let mut key = Vec::with_capacity(2 + address.len() + denom.len());
+
+    key.push(BALANCES_PREFIX);
+    key.push(address.len() as u8);
+    key.extend_from_slice(address);
+    key.extend_from_slice(denom.as_bytes());
+
+    key
+}
+
+/// A proof of the account's balance in the application state.
+///
+/// It consists of an [ICS23] proof of the balance in the IAVL tree of the bank module's
+/// store and a proof of that store's root in the application's multistore. Root of the
+/// multistore is the `AppHash`, committed to in the header of the block following the
+/// one which state was queried.
+///
+/// [ICS23]: https://github.com/cosmos/ics23
+#[derive(Debug, Clone, PartialEq)]
+pub struct BalanceProof {
+    /// Proof of the balance in the bank module's store.
+    pub store_proof: CommitmentProof,
+    /// Proof of the bank module's store root in the multistore.
+    pub multistore_proof: CommitmentProof,
+}
+
+impl BalanceProof {
+    /// Verify the account's balance against the `AppHash` of the header.
+    ///
+    /// The state after executing block `N` is committed to in the header `N + 1`, so the
+    /// header must be the one following the height at which the balance was queried.
+    /// Zero balances are not stored, thus they are verified with a proof of absence.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the proof doesn't match the balance or
+    /// the `AppHash` of the header.
+    pub fn verify(
+        &self,
+        address: &AccAddress,
+        balance: &Balance,
+        header: &ExtendedHeader,
+    ) -> Result<()> {
+        let key = balance_key(address, &balance.denom);
+        let store_spec = ics23::iavl_spec();
+
+        let store_root = if balance.amount == Uint::ZERO {
+            let root = nonexistence_root(&self.store_proof)?;
+
+            if !ics23::verify_non_membership::<HostFunctionsManager>(
+                &self.store_proof,
+                &store_spec,
+                &root,
+                &key,
+            ) {
+                bail_verification!("balance absence proof is invalid");
+            }
+
+            root
+        } else {
+            let value = balance.amount.to_string().into_bytes();
+            let root = existence_root(&self.store_proof)?;
+
+            if !ics23::verify_membership::<HostFunctionsManager>(
+                &self.store_proof,
+                &store_spec,
+                &root,
+                &key,
+                &value,
+            ) {
+                bail_verification!("balance proof is invalid");
+            }
+
+            root
+        };
+
+        if !ics23::verify_membership::<HostFunctionsManager>(
+            &self.multistore_proof,
+            &ics23::tendermint_spec(),
+            &header.header.app_hash.as_bytes().to_vec(),
+            BANK_STORE_KEY.as_bytes(),
+            &store_root,
+        ) {
+            bail_verification!("bank store proof doesn't match app hash");
+        }
+
+        Ok(())
+    }
+}
+
+impl TryFrom<ProofOps> for BalanceProof {
+    type Error = Error;
+
+    fn try_from(value: ProofOps) -> Result<Self, Self::Error> {
+        let [store_op, multistore_op]: [ProofOp; 2] = value
+            .ops
+            .try_into()
+            .map_err(|ops: Vec<_>| Error::UnexpectedProofOp(format!("{} operations", ops.len())))?;
+
+        if store_op.field_type != IAVL_PROOF_OP {
+            return Err(Error::UnexpectedProofOp(store_op.field_type));
+        }
+
+        if multistore_op.field_type != MULTISTORE_PROOF_OP {
+            return Err(Error::UnexpectedProofOp(multistore_op.field_type));
+        }
+
+        if multistore_op.key != BANK_STORE_KEY.as_bytes() {
+            return Err(Error::UnexpectedProofOp(format!(
+                "proof of store {}",
+                String::from_utf8_lossy(&multistore_op.key)
+            )));
+        }
+
+        Ok(BalanceProof {
+            store_proof: CommitmentProof::decode(&store_op.data[..])?,
+            multistore_proof: CommitmentProof::decode(&multistore_op.data[..])?,
+        })
+    }
+}
+
+fn existence_root(proof: &CommitmentProof) -> Result<Vec<u8>> {
+    match &proof.proof {
+        Some(Proof::Exist(exist)) => calculate_root(exist),
+        _ => Err(Error::WrongProofType),
+    }
+}
+
+fn nonexistence_root(proof: &CommitmentProof) -> Result<Vec<u8>> {
+    match &proof.proof {
+        Some(Proof::Nonexist(nonexist)) => nonexist
+            .left
+            .as_ref()
+            .or(nonexist.right.as_ref())
+            .ok_or(Error::MissingProof)
+            .and_then(calculate_root),
+        _ => Err(Error::WrongProofType),
+    }
+}
+
+fn calculate_root(proof: &ExistenceProof) -> Result<Vec<u8>> {
+    ics23::calculate_existence_root::<HostFunctionsManager>(proof)
+        .map_err(|e| verification_error!("{e}").into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::ExtendedHeaderGenerator;
+    use celestia_tendermint::hash::AppHash;
+    use ics23::{HashOp, LeafOp, LengthOp};
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    fn address() -> AccAddress {
+        "celestia1qypqxpq9qcrsszg2pvxq6rs0zqg3yyc5wgawu3"
+            .parse()
+            .unwrap()
+    }
+
+    fn balance(amount: u64) -> Balance {
+        Balance {
+            denom: "utia".to_string(),
+            amount: Uint::from(amount),
+        }
+    }
+
+    fn leaf_proof(leaf: LeafOp, key: Vec<u8>, value: Vec<u8>) -> CommitmentProof {
+        CommitmentProof {
+            proof: Some(Proof::Exist(ExistenceProof {
+                key,
+                value,
+                leaf: Some(leaf),
+                path: vec![],
+            })),
+        }
+    }
+
+    /// Creates proofs of the single element IAVL tree in a multistore with only the bank
+    /// module's store, returning them along with the `AppHash`.
+    fn single_leaf_proof(address: &AccAddress, balance: &Balance) -> (BalanceProof, AppHash) {
+        let value = balance.amount.to_string().into_bytes();
+        let iavl_leaf = LeafOp {
+            hash: HashOp::Sha256.into(),
+            prehash_key: HashOp::NoHash.into(),
+            prehash_value: HashOp::Sha256.into(),
+            length: LengthOp::VarProto.into(),
+            // height 0, size 1, version 1 as zigzag varints
+            prefix: vec![0, 2, 2],
+        };
+        let store_proof = leaf_proof(iavl_leaf, balance_key(address, &balance.denom), value);
+        let store_root = existence_root(&store_proof).unwrap();
+
+        let multistore_leaf = ics23::tendermint_spec().leaf_spec.unwrap();
+        let multistore_proof = leaf_proof(multistore_leaf, BANK_STORE_KEY.into(), store_root);
+        let app_hash = existence_root(&multistore_proof).unwrap();
+
+        let proof = BalanceProof {
+            store_proof,
+            multistore_proof,
+        };
+
+        (proof, AppHash::try_from(app_hash).unwrap())
+    }
+
+    #[test]
+    fn balance_key_encoding() {
+        let address = address();
+        let key = balance_key(&address, "utia");
+
+        assert_eq!(key[0], BALANCES_PREFIX);
+        assert_eq!(key[1] as usize, address.as_bytes().len());
+        assert_eq!(&key[2..22], address.as_bytes());
+        assert_eq!(&key[22..], b"utia");
+    }
+
+    #[test]
+    fn verify_balance() {
+        let address = address();
+        let balance = balance(1234);
+        let (proof, app_hash) = single_leaf_proof(&address, &balance);
+
+        let mut header = ExtendedHeaderGenerator::new().next();
+        header.header.app_hash = app_hash;
+
+        proof.verify(&address, &balance, &header).unwrap();
+    }
+
+    #[test]
+    fn verify_wrong_balance() {
+        let address = address();
+        let (proof, app_hash) = single_leaf_proof(&address, &balance(1234));
+
+        let mut header = ExtendedHeaderGenerator::new().next();
+        header.header.app_hash = app_hash;
+
+        proof.verify(&address, &balance(4321), &header).unwrap_err();
+    }
+
+    #[test]
+    fn verify_wrong_app_hash() {
+        let address = address();
+        let balance = balance(1234);
+        let (proof, _) = single_leaf_proof(&address, &balance);
+
+        let header = ExtendedHeaderGenerator::new().next();
+
+        proof.verify(&address, &balance, &header).unwrap_err();
+    }
+
+    #[test]
+    fn proof_from_proof_ops() {
+        let address = address();
+        let (proof, _) = single_leaf_proof(&address, &balance(1234));
+
+        let ops = ProofOps {
+            ops: vec![
+                ProofOp {
+                    field_type: IAVL_PROOF_OP.to_string(),
+                    key: balance_key(&address, "utia"),
+                    data: proof.store_proof.encode_to_vec(),
+                },
+                ProofOp {
+                    field_type: MULTISTORE_PROOF_OP.to_string(),
+                    key: BANK_STORE_KEY.into(),
+                    data: proof.multistore_proof.encode_to_vec(),
+                },
+            ],
+        };
+
+        assert_eq!(BalanceProof::try_from(ops.clone()).unwrap(), proof);
+
+        let mut wrong_store = ops.clone();
+        wrong_store.ops[1].key = b"staking".to_vec();
+        BalanceProof::try_from(wrong_store).unwrap_err();
+
+        let mut missing_op = ops;
+        missing_op.ops.pop();
+        BalanceProof::try_from(missing_op).unwrap_err();
+    }
+}
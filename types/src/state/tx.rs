@@ -1,6 +1,18 @@
 use celestia_proto::cosmos::base::abci::v1beta1::TxResponse as RawTxResponse;
+use celestia_tendermint::Hash;
+use celestia_tendermint_proto::v0_34::types::IndexWrapper;
+use prost::Message;
 use serde::{Deserialize, Serialize};
 
+use crate::nmt::Namespace;
+use crate::{
+    bail_verification, parse_compact_shares, parse_compact_units, parse_compact_units_in_range,
+    verify_share_range_proof, DataAvailabilityHeader, Error, ExtendedDataSquare, RangeProof,
+    Result, Share,
+};
+
+pub use celestia_proto::cosmos::tx::v1beta1::Tx;
+
 /// Raw transaction data.
 ///
 /// # Note
@@ -13,5 +25,212 @@ pub struct RawTx {
     data: Vec<u8>,
 }
 
+impl RawTx {
+    /// Get the bytes of the transaction.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl From<Vec<u8>> for RawTx {
+    fn from(data: Vec<u8>) -> Self {
+        RawTx { data }
+    }
+}
+
 /// Raw transaction response.
 pub type TxResponse = RawTxResponse;
+
+/// Decode the ordinary transactions stored in the [`Namespace::TRANSACTION`].
+///
+/// Shares of the other namespaces are skipped, so it can be given either the
+/// whole original data square or only the shares of the transactions namespace.
+/// The transactions paying for blobs are stored separately, see
+/// [`decode_pay_for_blobs`].
+///
+/// [`decode_pay_for_blobs`]: crate::blob::decode_pay_for_blobs
+pub fn decode_txs<'a, I>(shares: I) -> Result<Vec<Tx>>
+where
+    I: IntoIterator<Item = &'a Share>,
+{
+    parse_compact_shares(Namespace::TRANSACTION, shares)?
+        .into_iter()
+        .map(|unit| Ok(Tx::decode(&unit[..])?))
+        .collect()
+}
+
+/// Prove the inclusion of the transaction with the given index in the data root of the block.
+///
+/// Transactions are indexed in the order they are stored in the square, the ordinary ones
+/// from the [`Namespace::TRANSACTION`] first, followed by the ones paying for blobs from
+/// the [`Namespace::PAY_FOR_BLOB`]. The [`RangeProof`] covers all the compact shares holding
+/// the transaction, which may also hold parts of the neighbouring ones, and can be checked
+/// with [`verify_tx_inclusion_proof`].
+///
+/// # Errors
+///
+/// This function will return an error if the compact shares of the square can't be parsed
+/// or there is no transaction with the given index.
+///
+/// # Example
+///
+/// ```
+/// use celestia_types::consts::appconsts::AppVersion;
+/// use celestia_types::state::{tx_inclusion_proof, verify_tx_inclusion_proof};
+/// use celestia_types::{DataAvailabilityHeader, ExtendedDataSquare};
+///
+/// let txs = [b"first tx".to_vec(), b"second tx".to_vec()];
+/// let eds = ExtendedDataSquare::build(&[], &txs, 2, AppVersion::V2).unwrap();
+/// let dah = DataAvailabilityHeader::from_eds(&eds).unwrap();
+///
+/// let proof = tx_inclusion_proof(&eds, &dah, 1).unwrap();
+/// verify_tx_inclusion_proof(&proof, b"second tx", &dah.hash()).unwrap();
+/// ```
+pub fn tx_inclusion_proof(
+    eds: &ExtendedDataSquare,
+    dah: &DataAvailabilityHeader,
+    tx_index: usize,
+) -> Result<RangeProof> {
+    let ods = eds
+        .iter_ods()
+        .map(|(_, _, share)| Share::from_raw(share))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut index = tx_index;
+
+    for namespace in [Namespace::TRANSACTION, Namespace::PAY_FOR_BLOB] {
+        let mut units = parse_compact_units(namespace, &ods)?;
+
+        if index < units.len() {
+            let (_, range) = units.swap_remove(index);
+            return eds.prove_share_range(range.start, range.end, dah);
+        }
+
+        index -= units.len();
+    }
+
+    Err(Error::TxNotFound(tx_index))
+}
+
+/// Verify the proof of the transaction inclusion, built by [`tx_inclusion_proof`].
+///
+/// Checks that the proof is valid for the data root and that the proven shares hold
+/// exactly the given transaction, as one of the units of the compact shares. For the transactions paying for blobs, `tx` is the signed
+/// transaction wrapped in the [`BlobTx`], without the blobs.
+///
+/// # Errors
+///
+/// This function will return an error if the proof is invalid or it doesn't cover
+/// the transaction.
+///
+/// [`BlobTx`]: celestia_tendermint_proto::v0_34::types::BlobTx
+pub fn verify_tx_inclusion_proof(proof: &RangeProof, tx: &[u8], data_root: &Hash) -> Result<()> {
+    if proof.namespace != Namespace::TRANSACTION && proof.namespace != Namespace::PAY_FOR_BLOB {
+        bail_verification!(
+            "proof of namespace {:?} doesn't prove transactions",
+            proof.namespace
+        );
+    }
+
+    if tx.is_empty() {
+        bail_verification!("empty transaction");
+    }
+
+    let Some(range) = proof.claimed_range() else {
+        bail_verification!("empty proof");
+    };
+    verify_share_range_proof(proof, range.start, range.end, data_root)?;
+
+    let shares = proof
+        .data
+        .iter()
+        .map(|share| Share::from_raw(share))
+        .collect::<Result<Vec<_>>>()?;
+
+    // transactions paying for blobs are stored wrapped with the indexes of the blobs
+    let holds_tx = |unit: &[u8]| {
+        if proof.namespace == Namespace::PAY_FOR_BLOB {
+            IndexWrapper::decode(unit).is_ok_and(|wrapper| wrapper.tx == tx)
+        } else {
+            unit == tx
+        }
+    };
+
+    // transaction must be exactly one of the units, spanning all the proven shares
+    let is_proven = parse_compact_units_in_range(&shares)?
+        .into_iter()
+        .any(|(unit, range)| holds_tx(&unit) && range == (0..shares.len()));
+
+    if !is_proven {
+        bail_verification!("transaction is not in the proven shares");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::appconsts::AppVersion;
+    use crate::Blob;
+    use celestia_tendermint_proto::v0_34::types::BlobTx;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    #[test]
+    fn prove_txs_inclusion() {
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+        let blob = Blob::new(namespace, vec![1; 1000], AppVersion::V2).unwrap();
+        let pfb_tx = Tx {
+            signatures: vec![vec![8; 100]],
+            ..Default::default()
+        }
+        .encode_to_vec();
+        let blob_tx = BlobTx {
+            tx: pfb_tx.clone(),
+            blobs: vec![blob.into()],
+            type_id: "BLOB".to_owned(),
+        };
+
+        let ordinary_txs: Vec<_> = (1..=3u8)
+            .map(|i| {
+                Tx {
+                    signatures: vec![vec![i; 300]],
+                    ..Default::default()
+                }
+                .encode_to_vec()
+            })
+            .collect();
+
+        let mut txs = vec![blob_tx.encode_to_vec()];
+        txs.extend(ordinary_txs.iter().cloned());
+
+        let eds = ExtendedDataSquare::build(&[], &txs, 4, AppVersion::V2).unwrap();
+        let dah = DataAvailabilityHeader::from_eds(&eds).unwrap();
+
+        // ordinary transactions come first
+        for (i, tx) in ordinary_txs.iter().enumerate() {
+            let proof = tx_inclusion_proof(&eds, &dah, i).unwrap();
+            assert_eq!(proof.namespace, Namespace::TRANSACTION);
+            verify_tx_inclusion_proof(&proof, tx, &dah.hash()).unwrap();
+        }
+
+        let proof = tx_inclusion_proof(&eds, &dah, 3).unwrap();
+        assert_eq!(proof.namespace, Namespace::PAY_FOR_BLOB);
+        verify_tx_inclusion_proof(&proof, &pfb_tx, &dah.hash()).unwrap();
+        verify_tx_inclusion_proof(&proof, &ordinary_txs[0], &dah.hash()).unwrap_err();
+
+        // only the whole transaction is proven, not a part of it
+        let proof = tx_inclusion_proof(&eds, &dah, 1).unwrap();
+        verify_tx_inclusion_proof(&proof, &ordinary_txs[1][..100], &dah.hash()).unwrap_err();
+        verify_tx_inclusion_proof(&proof, &ordinary_txs[1][1..], &dah.hash()).unwrap_err();
+        // neither a transaction outside of the proven range
+        verify_tx_inclusion_proof(&proof, &ordinary_txs[2], &dah.hash()).unwrap_err();
+
+        assert!(matches!(
+            tx_inclusion_proof(&eds, &dah, 4),
+            Err(Error::TxNotFound(4))
+        ));
+    }
+}
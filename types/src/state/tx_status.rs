@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+
+/// Response of the consensus node's `tx_status` JSON-RPC method.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TxStatusResponse {
+    /// Height of the block the transaction was included in, `0` if it wasn't.
+    #[serde(default, with = "celestia_tendermint_proto::serializers::from_str")]
+    pub height: u64,
+    /// Index of the transaction in the block.
+    #[serde(default)]
+    pub index: u32,
+    /// Response code of the executed transaction, `0` on success.
+    #[serde(default)]
+    pub execution_code: u32,
+    /// Error of the executed transaction, if it failed.
+    #[serde(default)]
+    pub error: String,
+    /// Status of the transaction.
+    pub status: TxStatus,
+}
+
+/// Status of the transaction known to the consensus node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TxStatus {
+    /// Transaction is neither in the mempool nor in a block.
+    Unknown,
+    /// Transaction is waiting in the mempool.
+    Pending,
+    /// Transaction was evicted from the mempool and won't be included.
+    Evicted,
+    /// Transaction was included in a block.
+    Committed,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    #[test]
+    fn deserialize_committed_tx() {
+        let s = r#"{
+          "height": "1234",
+          "index": 2,
+          "execution_code": 0,
+          "error": "",
+          "status": "COMMITTED"
+        }"#;
+        let response: TxStatusResponse = serde_json::from_str(s).unwrap();
+
+        assert_eq!(response.height, 1234);
+        assert_eq!(response.index, 2);
+        assert_eq!(response.status, TxStatus::Committed);
+    }
+
+    #[test]
+    fn deserialize_unknown_tx() {
+        let s = r#"{
+          "height": "0",
+          "index": 0,
+          "execution_code": 0,
+          "error": "",
+          "status": "UNKNOWN"
+        }"#;
+        let response: TxStatusResponse = serde_json::from_str(s).unwrap();
+
+        assert_eq!(response.height, 0);
+        assert_eq!(response.status, TxStatus::Unknown);
+    }
+}
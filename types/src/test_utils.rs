@@ -1,5 +1,8 @@
 //! Utilities for writing tests.
 
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
 use celestia_tendermint::{
     block::{
         header::{Header, Version},
@@ -10,34 +13,59 @@ use celestia_tendermint::{
     Signature, Time,
 };
 use ed25519_consensus::SigningKey;
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
 
 use crate::block::{CommitExt, GENESIS_HEIGHT};
+use crate::consts::appconsts::{NAMESPACE_SIZE, SHARE_SIZE};
 use crate::consts::version;
 use crate::hash::{Hash, HashExt};
-use crate::nmt::{NamespacedHash, NamespacedHashExt};
-use crate::{DataAvailabilityHeader, ExtendedHeader, ValidatorSet};
+use crate::nmt::{Namespace, NamespacedHash, NamespacedHashExt};
+use crate::{DataAvailabilityHeader, ExtendedDataSquare, ExtendedHeader, InfoByte, ValidatorSet};
+
+/// Unix timestamp of the genesis header created by seeded generators.
+const SEEDED_GENESIS_TIMESTAMP: i64 = 1_700_000_000;
 
 /// [`ExtendedHeader`] generator for testing purposes.
 ///
+/// Generators created with [`new_with_seed`] are fully deterministic: the
+/// signing key, hashes, timestamps and generated squares depend only on the
+/// seed and the sequence of calls.
+///
 /// **WARNING: ALL METHODS PANIC! DO NOT USE IT IN PRODUCTION!**
+///
+/// [`new_with_seed`]: ExtendedHeaderGenerator::new_with_seed
 #[derive(Debug, Clone)]
 pub struct ExtendedHeaderGenerator {
     chain_id: chain::Id,
     key: SigningKey,
     current_header: Option<ExtendedHeader>,
+    rng: GeneratorRng,
+    genesis_time: Option<Time>,
 }
 
 impl ExtendedHeaderGenerator {
     /// Creates new `ExtendedHeaderGenerator`.
     pub fn new() -> ExtendedHeaderGenerator {
-        let chain_id: chain::Id = "private".try_into().unwrap();
-        let key = SigningKey::new(rand::thread_rng());
+        ExtendedHeaderGenerator::from_rng(StdRng::from_entropy(), None)
+    }
 
-        ExtendedHeaderGenerator {
-            chain_id,
-            key,
-            current_header: None,
-        }
+    /// Creates new deterministic `ExtendedHeaderGenerator`.
+    ///
+    /// Two generators created with the same seed produce identical headers
+    /// when called in the same order.
+    ///
+    /// ```
+    /// use celestia_types::test_utils::ExtendedHeaderGenerator;
+    ///
+    /// let mut gen1 = ExtendedHeaderGenerator::new_with_seed(42);
+    /// let mut gen2 = ExtendedHeaderGenerator::new_with_seed(42);
+    ///
+    /// assert_eq!(gen1.next_many(3), gen2.next_many(3));
+    /// ```
+    pub fn new_with_seed(seed: u64) -> ExtendedHeaderGenerator {
+        let genesis_time = Time::from_unix_timestamp(SEEDED_GENESIS_TIMESTAMP, 0).unwrap();
+        ExtendedHeaderGenerator::from_rng(StdRng::seed_from_u64(seed), Some(genesis_time))
     }
 
     /// Creates new `ExtendedHeaderGenerator` starting from specified height.
@@ -49,24 +77,41 @@ impl ExtendedHeaderGenerator {
     /// let header5 = gen.next();
     /// ```
     pub fn new_from_height(height: u64) -> ExtendedHeaderGenerator {
-        let prev_height = height.saturating_sub(1);
         let mut gen = ExtendedHeaderGenerator::new();
+        gen.skip(height.saturating_sub(1));
+        gen
+    }
 
-        gen.current_header = if prev_height == 0 {
-            None
-        } else {
-            Some(generate_new(prev_height, &gen.chain_id, &gen.key))
-        };
-
+    /// Creates new deterministic `ExtendedHeaderGenerator` starting from specified height.
+    ///
+    /// See [`new_with_seed`] for details.
+    ///
+    /// [`new_with_seed`]: ExtendedHeaderGenerator::new_with_seed
+    pub fn new_from_height_with_seed(height: u64, seed: u64) -> ExtendedHeaderGenerator {
+        let mut gen = ExtendedHeaderGenerator::new_with_seed(seed);
+        gen.skip(height.saturating_sub(1));
         gen
     }
 
+    fn from_rng(mut rng: StdRng, genesis_time: Option<Time>) -> ExtendedHeaderGenerator {
+        let chain_id: chain::Id = "private".try_into().unwrap();
+        let key = SigningKey::new(&mut rng);
+
+        ExtendedHeaderGenerator {
+            chain_id,
+            key,
+            current_header: None,
+            rng: GeneratorRng(Mutex::new(rng)),
+            genesis_time,
+        }
+    }
+
     /// Generates the next header.
     #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> ExtendedHeader {
         let header = match self.current_header {
-            Some(ref header) => generate_next(1, header, &self.key),
-            None => generate_new(GENESIS_HEIGHT, &self.chain_id, &self.key),
+            Some(ref header) => self.generate_next(1, header),
+            None => self.generate_new(GENESIS_HEIGHT),
         };
 
         self.current_header = Some(header.clone());
@@ -84,6 +129,119 @@ impl ExtendedHeaderGenerator {
         headers
     }
 
+    /// Generates the next header with the provided [`DataAvailabilityHeader`].
+    ///
+    /// ```
+    /// use celestia_types::nmt::Namespace;
+    /// use celestia_types::test_utils::ExtendedHeaderGenerator;
+    /// use celestia_types::DataAvailabilityHeader;
+    ///
+    /// let mut gen = ExtendedHeaderGenerator::new_with_seed(1);
+    /// let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+    /// let eds = gen.generate_eds(4, &[namespace]);
+    /// let dah = DataAvailabilityHeader::from_eds(&eds).unwrap();
+    ///
+    /// let header = gen.next_with_dah(dah.clone());
+    /// assert_eq!(header.dah, dah);
+    /// ```
+    pub fn next_with_dah(&mut self, dah: DataAvailabilityHeader) -> ExtendedHeader {
+        let mut header = self.next();
+
+        header.dah = dah;
+        hash_and_sign(&mut header, &self.key);
+        header.validate().expect("invalid header generated");
+
+        self.current_header = Some(header.clone());
+        header
+    }
+
+    /// Generates the next header together with an [`ExtendedDataSquare`] it commits to.
+    ///
+    /// See [`generate_eds`] for the meaning of the arguments.
+    ///
+    /// ```
+    /// use celestia_types::nmt::Namespace;
+    /// use celestia_types::test_utils::ExtendedHeaderGenerator;
+    ///
+    /// let mut gen = ExtendedHeaderGenerator::new_with_seed(1);
+    /// let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+    ///
+    /// let (header, eds) = gen.next_with_eds(4, &[namespace]);
+    /// header.dah.verify_eds(&eds).unwrap();
+    /// ```
+    ///
+    /// [`generate_eds`]: ExtendedHeaderGenerator::generate_eds
+    pub fn next_with_eds(
+        &mut self,
+        square_size: usize,
+        namespaces: &[Namespace],
+    ) -> (ExtendedHeader, ExtendedDataSquare) {
+        let eds = self.generate_eds(square_size, namespaces);
+        let dah = DataAvailabilityHeader::from_eds(&eds).expect("invalid EDS generated");
+
+        (self.next_with_dah(dah), eds)
+    }
+
+    /// Generates an [`ExtendedDataSquare`] filled with random data.
+    ///
+    /// The `square_size` is the width of the original data square and must be a power
+    /// of two. Shares of the original data square are split between the provided
+    /// namespaces in ascending order, so each namespace gets a contiguous range of shares.
+    /// If there are more namespaces than shares, only the lowest ones are used.
+    ///
+    /// ```
+    /// use celestia_types::nmt::Namespace;
+    /// use celestia_types::test_utils::ExtendedHeaderGenerator;
+    /// use celestia_types::Share;
+    ///
+    /// let gen = ExtendedHeaderGenerator::new_with_seed(1);
+    /// let ns1 = Namespace::new_v0(&[1]).unwrap();
+    /// let ns2 = Namespace::new_v0(&[2]).unwrap();
+    ///
+    /// let eds = gen.generate_eds(2, &[ns2, ns1]);
+    /// assert_eq!(eds.square_len(), 4);
+    ///
+    /// let first_row = eds.row(0).unwrap();
+    /// assert_eq!(Share::from_raw(&first_row[0]).unwrap().namespace(), ns1);
+    /// assert_eq!(Share::from_raw(&first_row[1]).unwrap().namespace(), ns1);
+    /// let second_row = eds.row(1).unwrap();
+    /// assert_eq!(Share::from_raw(&second_row[0]).unwrap().namespace(), ns2);
+    /// ```
+    ///
+    /// # Note
+    ///
+    /// This method does not change the headers generated by `ExtendedHeaderGenerator`,
+    /// but it advances its random number generator.
+    pub fn generate_eds(&self, square_size: usize, namespaces: &[Namespace]) -> ExtendedDataSquare {
+        let mut namespaces = namespaces.to_vec();
+        namespaces.sort();
+        namespaces.dedup();
+
+        assert!(!namespaces.is_empty(), "no namespaces provided");
+        assert!(
+            namespaces.iter().all(|ns| *ns != Namespace::PARITY_SHARE),
+            "parity share namespace provided"
+        );
+
+        let ods_shares = square_size * square_size;
+        let info_byte = InfoByte::new(0, false).unwrap();
+        let mut rng = self.rng.lock();
+
+        let ods = (0..ods_shares)
+            .map(|idx| {
+                let namespace = namespaces[idx * namespaces.len().min(ods_shares) / ods_shares];
+
+                let mut share = vec![0; SHARE_SIZE];
+                share[..NAMESPACE_SIZE].copy_from_slice(namespace.as_bytes());
+                share[NAMESPACE_SIZE] = info_byte.as_u8();
+                rng.fill_bytes(&mut share[NAMESPACE_SIZE + 1..]);
+                share
+            })
+            .collect();
+
+        ExtendedDataSquare::from_ods(ods).expect("invalid EDS generated")
+    }
+
     /// Generates the next header of the provided header.
     ///
     /// This can be used to create two headers of same height but different hash.
@@ -101,7 +259,7 @@ impl ExtendedHeaderGenerator {
     ///
     /// This method does not change the state of `ExtendedHeaderGenerator`.
     pub fn next_of(&self, header: &ExtendedHeader) -> ExtendedHeader {
-        generate_next(1, header, &self.key)
+        self.generate_next(1, header)
     }
 
     /// Generates the next amount of headers of the provided header.
@@ -150,9 +308,11 @@ impl ExtendedHeaderGenerator {
     pub fn another_of(&self, header: &ExtendedHeader) -> ExtendedHeader {
         let mut header = header.to_owned();
 
-        header.header.consensus_hash = Hash::Sha256(rand::random());
+        let mut rng = self.rng.lock();
+
+        header.header.consensus_hash = Hash::Sha256(rng.gen());
         header.commit.block_id.part_set_header =
-            parts::Header::new(1, Hash::Sha256(rand::random())).expect("invalid PartSetHeader");
+            parts::Header::new(1, Hash::Sha256(rng.gen())).expect("invalid PartSetHeader");
 
         hash_and_sign(&mut header, &self.key);
         header.validate().expect("invalid header generated");
@@ -167,13 +327,46 @@ impl ExtendedHeaderGenerator {
         }
 
         let header = match self.current_header {
-            Some(ref header) => generate_next(amount, header, &self.key),
-            None => generate_new(amount, &self.chain_id, &self.key),
+            Some(ref header) => self.generate_next(amount, header),
+            None => self.generate_new(amount),
         };
 
         self.current_header = Some(header.clone());
     }
 
+    /// Sets the time of the next generated header.
+    ///
+    /// The headers following it are a second apart, as with the seeded generators,
+    /// until the time is set again. The time must be after the one of the current
+    /// header, for the next header to be verifiable against it.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use celestia_types::test_utils::ExtendedHeaderGenerator;
+    ///
+    /// let mut gen = ExtendedHeaderGenerator::new_with_seed(1);
+    /// let header1 = gen.next();
+    ///
+    /// let time = header1.time().checked_add(Duration::from_secs(60)).unwrap();
+    /// gen.set_next_time(time);
+    ///
+    /// let header2 = gen.next();
+    /// assert_eq!(header2.time(), time);
+    /// header1.verify(&header2).unwrap();
+    /// ```
+    pub fn set_next_time(&mut self, time: Time) {
+        let next_height = self
+            .current_header
+            .as_ref()
+            .map_or(GENESIS_HEIGHT, |header| header.height().value() + 1);
+        let genesis_time = time
+            .checked_sub(Duration::from_secs(next_height - GENESIS_HEIGHT))
+            .expect("time underflow");
+
+        self.genesis_time = Some(genesis_time);
+    }
+
     /// Create a "forked" generator for "forking" the chain.
     ///
     /// ```
@@ -192,9 +385,46 @@ impl ExtendedHeaderGenerator {
     ///
     /// # Note
     ///
-    /// This is the same as clone, but the name describes the intention.
+    /// Unlike clone, the forked generator gets its own random number generator
+    /// derived from this one, so both chains diverge from the current header.
     pub fn fork(&self) -> ExtendedHeaderGenerator {
-        self.clone()
+        let rng = StdRng::from_rng(&mut *self.rng.lock()).expect("rng failed");
+
+        ExtendedHeaderGenerator {
+            rng: GeneratorRng(Mutex::new(rng)),
+            ..self.clone()
+        }
+    }
+
+    fn generate_new(&self, height: u64) -> ExtendedHeader {
+        generate_new(
+            height,
+            &self.chain_id,
+            &self.key,
+            &mut *self.rng.lock(),
+            self.time_of(height),
+        )
+    }
+
+    fn generate_next(&self, increment: u64, current: &ExtendedHeader) -> ExtendedHeader {
+        let height = current.height().value() + increment;
+
+        generate_next(
+            increment,
+            current,
+            &self.key,
+            &mut *self.rng.lock(),
+            self.time_of(height),
+        )
+    }
+
+    fn time_of(&self, height: u64) -> Time {
+        match self.genesis_time {
+            Some(genesis_time) => genesis_time
+                .checked_add(Duration::from_secs(height - GENESIS_HEIGHT))
+                .expect("time overflow"),
+            None => Time::now(),
+        }
     }
 }
 
@@ -204,6 +434,22 @@ impl Default for ExtendedHeaderGenerator {
     }
 }
 
+/// Random number generator which can be used from the `&self` methods of the generator.
+#[derive(Debug)]
+struct GeneratorRng(Mutex<StdRng>);
+
+impl GeneratorRng {
+    fn lock(&self) -> MutexGuard<'_, StdRng> {
+        self.0.lock().expect("rng mutex poisoned")
+    }
+}
+
+impl Clone for GeneratorRng {
+    fn clone(&self) -> Self {
+        GeneratorRng(Mutex::new(self.lock().clone()))
+    }
+}
+
 /// Invalidate the provided header.
 ///
 /// This can be combined with [`unverify`]
@@ -257,19 +503,25 @@ pub fn unverify(header: &mut ExtendedHeader) {
     }
 }
 
-fn generate_new(height: u64, chain_id: &chain::Id, signing_key: &SigningKey) -> ExtendedHeader {
+fn generate_new(
+    height: u64,
+    chain_id: &chain::Id,
+    signing_key: &SigningKey,
+    rng: &mut impl Rng,
+    time: Time,
+) -> ExtendedHeader {
     assert!(height >= GENESIS_HEIGHT);
 
     let pub_key_bytes = signing_key.verification_key().to_bytes();
     let pub_key = PublicKey::from_raw_ed25519(&pub_key_bytes).unwrap();
-    let validator_address = celestia_tendermint::account::Id::new(rand::random());
+    let validator_address = celestia_tendermint::account::Id::new(rng.gen());
 
     let last_block_id = if height == GENESIS_HEIGHT {
         None
     } else {
         Some(celestia_tendermint::block::Id {
-            hash: Hash::Sha256(rand::random()),
-            part_set_header: parts::Header::new(1, Hash::Sha256(rand::random()))
+            hash: Hash::Sha256(rng.gen()),
+            part_set_header: parts::Header::new(1, Hash::Sha256(rng.gen()))
                 .expect("invalid PartSetHeader"),
         })
     };
@@ -282,13 +534,13 @@ fn generate_new(height: u64, chain_id: &chain::Id, signing_key: &SigningKey) ->
             },
             chain_id: chain_id.clone(),
             height: height.try_into().unwrap(),
-            time: Time::now(),
+            time,
             last_block_id,
             last_commit_hash: Hash::default_sha256(),
             data_hash: Hash::None,
             validators_hash: Hash::None,
             next_validators_hash: Hash::None,
-            consensus_hash: Hash::Sha256(rand::random()),
+            consensus_hash: Hash::Sha256(rng.gen()),
             app_hash: Hash::default_sha256()
                 .as_bytes()
                 .to_vec()
@@ -303,12 +555,12 @@ fn generate_new(height: u64, chain_id: &chain::Id, signing_key: &SigningKey) ->
             round: 0_u16.into(),
             block_id: celestia_tendermint::block::Id {
                 hash: Hash::None,
-                part_set_header: parts::Header::new(1, Hash::Sha256(rand::random()))
+                part_set_header: parts::Header::new(1, Hash::Sha256(rng.gen()))
                     .expect("invalid PartSetHeader"),
             },
             signatures: vec![CommitSig::BlockIdFlagCommit {
                 validator_address,
-                timestamp: Time::now(),
+                timestamp: time,
                 signature: None,
             }],
         },
@@ -344,6 +596,8 @@ fn generate_next(
     increment: u64,
     current: &ExtendedHeader,
     signing_key: &SigningKey,
+    rng: &mut impl Rng,
+    time: Time,
 ) -> ExtendedHeader {
     assert!(increment > 0);
 
@@ -357,8 +611,8 @@ fn generate_next(
         Some(current.commit.block_id)
     } else {
         Some(celestia_tendermint::block::Id {
-            hash: Hash::Sha256(rand::random()),
-            part_set_header: parts::Header::new(1, Hash::Sha256(rand::random()))
+            hash: Hash::Sha256(rng.gen()),
+            part_set_header: parts::Header::new(1, Hash::Sha256(rng.gen()))
                 .expect("invalid PartSetHeader"),
         })
     };
@@ -368,13 +622,13 @@ fn generate_next(
             version: current.header.version,
             chain_id: current.header.chain_id.clone(),
             height,
-            time: Time::now(),
+            time,
             last_block_id,
             last_commit_hash: Hash::default_sha256(),
             data_hash: Hash::None,
             validators_hash: Hash::None,
             next_validators_hash: Hash::None,
-            consensus_hash: Hash::Sha256(rand::random()),
+            consensus_hash: Hash::Sha256(rng.gen()),
             app_hash: Hash::default_sha256()
                 .as_bytes()
                 .to_vec()
@@ -389,12 +643,12 @@ fn generate_next(
             round: 0_u16.into(),
             block_id: celestia_tendermint::block::Id {
                 hash: Hash::None,
-                part_set_header: parts::Header::new(1, Hash::Sha256(rand::random()))
+                part_set_header: parts::Header::new(1, Hash::Sha256(rng.gen()))
                     .expect("invalid PartSetHeader"),
             },
             signatures: vec![CommitSig::BlockIdFlagCommit {
                 validator_address,
-                timestamp: Time::now(),
+                timestamp: time,
                 signature: None,
             }],
         },
@@ -440,6 +694,7 @@ fn hash_and_sign(header: &mut ExtendedHeader, signing_key: &SigningKey) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Share;
 
     #[cfg(target_arch = "wasm32")]
     use wasm_bindgen_test::wasm_bindgen_test as test;
@@ -615,6 +870,82 @@ mod tests {
         header5.verify(&another_header6).unwrap();
     }
 
+    #[test]
+    fn seeded_generators_are_deterministic() {
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+
+        let mut gen1 = ExtendedHeaderGenerator::new_from_height_with_seed(5, 7);
+        let mut gen2 = ExtendedHeaderGenerator::new_from_height_with_seed(5, 7);
+
+        assert_eq!(gen1.next_many(5), gen2.next_many(5));
+        assert_eq!(
+            gen1.next_with_eds(4, &[namespace]),
+            gen2.next_with_eds(4, &[namespace])
+        );
+        assert_eq!(gen1.fork().next(), gen2.fork().next());
+
+        let mut gen3 = ExtendedHeaderGenerator::new_from_height_with_seed(5, 8);
+        assert_ne!(gen1.next().hash(), gen3.next().hash());
+    }
+
+    #[test]
+    fn forked_seeded_generators_diverge() {
+        let mut gen1 = ExtendedHeaderGenerator::new_with_seed(7);
+        let header1 = gen1.next();
+        let mut gen2 = gen1.fork();
+
+        let header2_chain1 = gen1.next();
+        let header2_chain2 = gen2.next();
+
+        header1.verify(&header2_chain1).unwrap();
+        header1.verify(&header2_chain2).unwrap();
+        assert_ne!(header2_chain1.hash(), header2_chain2.hash());
+    }
+
+    #[test]
+    fn generate_headers_with_eds() {
+        let namespaces = [
+            Namespace::new_v0(&[3]).unwrap(),
+            Namespace::new_v0(&[1]).unwrap(),
+            Namespace::new_v0(&[2]).unwrap(),
+        ];
+        let mut gen = ExtendedHeaderGenerator::new_with_seed(1);
+
+        let header1 = gen.next();
+        let (header2, eds) = gen.next_with_eds(8, &namespaces);
+        let header3 = gen.next();
+
+        assert_eq!(eds.square_len(), 16);
+        header2.validate().unwrap();
+        header2.dah.verify_eds(&eds).unwrap();
+        header1.verify(&header2).unwrap();
+        header2.verify(&header3).unwrap();
+
+        let ods_namespaces: Vec<_> = (0..8)
+            .flat_map(|row| eds.row(row).unwrap()[..8].to_vec())
+            .map(|share| Share::from_raw(&share).unwrap().namespace())
+            .collect();
+
+        assert!(ods_namespaces.windows(2).all(|w| w[0] <= w[1]));
+        for ns in namespaces {
+            assert!(ods_namespaces.contains(&ns));
+        }
+    }
+
+    #[test]
+    fn generate_eds_more_namespaces_than_shares() {
+        let namespaces: Vec<_> = (0..10u8)
+            .map(|n| Namespace::new_v0(&[n]).unwrap())
+            .collect();
+        let gen = ExtendedHeaderGenerator::new();
+
+        let eds = gen.generate_eds(1, &namespaces);
+        let share = Share::from_raw(&eds.row(0).unwrap()[0]).unwrap();
+
+        assert_eq!(eds.square_len(), 2);
+        assert_eq!(share.namespace(), namespaces[0]);
+    }
+
     #[test]
     fn invalidate_header() {
         let mut gen = ExtendedHeaderGenerator::new_from_height(5);
@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use celestia_tendermint::block::CommitSig;
 use celestia_tendermint::crypto::default::signature::Verifier;
 use celestia_tendermint::validator::{Info, Set};
-use celestia_tendermint::{account, block, chain};
+use celestia_tendermint::{account, block, chain, Signature};
 
 use crate::trust_level::TrustLevelRatio;
 use crate::{
@@ -51,52 +51,15 @@ impl ValidatorSetExt for Set {
         height: &block::Height,
         commit: &block::Commit,
     ) -> Result<()> {
-        if self.validators().len() != commit.signatures.len() {
-            bail_verification!(
-                "validators signature len ({}) != commit signatures len ({})",
-                self.validators().len(),
-                commit.signatures.len(),
-            )
-        }
-
-        if height != &commit.height {
-            bail_verification!("height ({}) != commit height ({})", height, commit.height,)
-        }
-
-        let mut tallied_voting_power = 0;
-        let voting_power_needed =
-            TrustLevelRatio::new(2, 3).voting_power_needed(self.total_voting_power())?;
-
-        for (idx, (validator, commit_sig)) in self
-            .validators()
-            .iter()
-            .zip(commit.signatures.iter())
-            .enumerate()
-        {
-            let signature = match commit_sig {
-                CommitSig::BlockIdFlagCommit {
-                    signature: Some(ref sig),
-                    ..
-                } => sig,
-                CommitSig::BlockIdFlagCommit { .. } => {
-                    bail_verification!("No signature in CommitSig");
-                }
-                // not commiting for the block
-                _ => continue,
-            };
-            let vote_sign = commit.vote_sign_bytes(chain_id, idx)?;
-            validator.verify_signature::<Verifier>(&vote_sign, signature)?;
-
-            tallied_voting_power += validator.power();
-            if tallied_voting_power > voting_power_needed {
-                return Ok(());
-            }
-        }
-
-        Err(VerificationError::NotEnoughVotingPower(
-            tallied_voting_power,
-            voting_power_needed,
-        ))?
+        tally_commit_light(
+            self,
+            chain_id,
+            height,
+            commit,
+            |validator, vote_sign, signature| {
+                Ok(validator.verify_signature::<Verifier>(vote_sign, signature)?)
+            },
+        )
     }
 
     fn verify_commit_light_trusting(
@@ -151,6 +114,103 @@ impl ValidatorSetExt for Set {
     }
 }
 
+/// Queue the commit signatures needed for the light verification of the commit in the
+/// `batch`, verifying them later all at once.
+///
+/// Apart from the signatures, the commit is checked the same way as in
+/// [`ValidatorSetExt::verify_commit_light`]. Signatures of the keys other than ed25519
+/// are verified right away.
+#[cfg(feature = "batch-verify")]
+pub(crate) fn queue_commit_light(
+    vals: &Set,
+    chain_id: &chain::Id,
+    height: &block::Height,
+    commit: &block::Commit,
+    batch: &mut ed25519_consensus::batch::Verifier,
+) -> Result<()> {
+    tally_commit_light(
+        vals,
+        chain_id,
+        height,
+        commit,
+        |validator, vote_sign, signature| {
+            let Some(pub_key) = validator.pub_key.ed25519() else {
+                return Ok(validator.verify_signature::<Verifier>(vote_sign, signature)?);
+            };
+
+            let (Ok(key_bytes), Ok(signature)) = (
+                ed25519_consensus::VerificationKeyBytes::try_from(pub_key.as_bytes()),
+                ed25519_consensus::Signature::try_from(signature.as_bytes()),
+            ) else {
+                bail_verification!("invalid ed25519 key or signature of {}", validator.address);
+            };
+
+            batch.queue((key_bytes, signature, vote_sign));
+            Ok(())
+        },
+    )
+}
+
+/// Tally the voting power of the commit signatures until more than 2/3 of the
+/// validator set power is reached, passing each of the counted signatures to `verify`.
+fn tally_commit_light<F>(
+    vals: &Set,
+    chain_id: &chain::Id,
+    height: &block::Height,
+    commit: &block::Commit,
+    mut verify: F,
+) -> Result<()>
+where
+    F: FnMut(&Info, &[u8], &Signature) -> Result<()>,
+{
+    if vals.validators().len() != commit.signatures.len() {
+        bail_verification!(
+            "validators signature len ({}) != commit signatures len ({})",
+            vals.validators().len(),
+            commit.signatures.len(),
+        )
+    }
+
+    if height != &commit.height {
+        bail_verification!("height ({}) != commit height ({})", height, commit.height,)
+    }
+
+    let mut tallied_voting_power = 0;
+    let voting_power_needed =
+        TrustLevelRatio::new(2, 3).voting_power_needed(vals.total_voting_power())?;
+
+    for (idx, (validator, commit_sig)) in vals
+        .validators()
+        .iter()
+        .zip(commit.signatures.iter())
+        .enumerate()
+    {
+        let signature = match commit_sig {
+            CommitSig::BlockIdFlagCommit {
+                signature: Some(ref sig),
+                ..
+            } => sig,
+            CommitSig::BlockIdFlagCommit { .. } => {
+                bail_verification!("No signature in CommitSig");
+            }
+            // not commiting for the block
+            _ => continue,
+        };
+        let vote_sign = commit.vote_sign_bytes(chain_id, idx)?;
+        verify(validator, &vote_sign, signature)?;
+
+        tallied_voting_power += validator.power();
+        if tallied_voting_power > voting_power_needed {
+            return Ok(());
+        }
+    }
+
+    Err(VerificationError::NotEnoughVotingPower(
+        tallied_voting_power,
+        voting_power_needed,
+    ))?
+}
+
 fn find_validator<'a>(vals: &'a Set, val_id: &account::Id) -> Option<(usize, &'a Info)> {
     vals.validators()
         .iter()